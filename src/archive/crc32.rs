@@ -0,0 +1,38 @@
+//! The CRC-32 checksum used by zip archives.
+
+/// The reversed IEEE polynomial.
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+const TABLE: [u32; 256] = table();
+
+
+const fn table() -> [u32; 256] {
+	let mut table = [0; 256];
+	let mut ix = 0;
+
+	while ix < 256 {
+		let mut crc = ix as u32;
+		let mut bit = 0;
+
+		while bit < 8 {
+			crc = if crc & 1 == 1 { (crc >> 1) ^ POLYNOMIAL } else { crc >> 1 };
+			bit += 1;
+		}
+
+		table[ix] = crc;
+		ix += 1;
+	}
+
+	table
+}
+
+
+/// Compute the checksum of the given data.
+pub fn checksum(data: &[u8]) -> u32 {
+	!data
+		.iter()
+		.fold(
+			!0,
+			|crc, &byte| TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+		)
+}
@@ -0,0 +1,309 @@
+//! Decompression of raw deflate streams (RFC 1951), as used by zip archives.
+
+use std::io;
+
+use super::invalid_data;
+
+
+const LENGTH_BASE: [u16; 29] = [
+	3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+	35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+
+const LENGTH_EXTRA: [u8; 29] = [
+	0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+	3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+const DISTANCE_BASE: [u16; 30] = [
+	1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+	257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+const DISTANCE_EXTRA: [u8; 30] = [
+	0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+	7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+/// The order in which code length code lengths are stored in dynamic blocks.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+	16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const MAX_BITS: usize = 15;
+
+
+/// Decompress a raw deflate stream, failing if the output exceeds the given size.
+pub fn inflate(data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+	let mut bits = Bits { data, pos: 0, buffer: 0, count: 0 };
+	let mut output = Vec::new();
+
+	loop {
+		let last = bits.read(1)? == 1;
+
+		match bits.read(2)? {
+			0 => {
+				bits.align();
+
+				let length = bits.read(16)? as usize;
+				let complement = bits.read(16)? as usize;
+
+				if length != !complement & 0xFFFF {
+					return Err(invalid_data("invalid stored block length"));
+				}
+
+				let block = data
+					.get(bits.pos .. bits.pos + length)
+					.ok_or_else(|| invalid_data("truncated deflate stream"))?;
+
+				if output.len() + length > limit {
+					return Err(invalid_data("deflate stream exceeds the expected size"));
+				}
+
+				output.extend_from_slice(block);
+				bits.pos += length;
+			}
+
+			1 => {
+				let (literals, distances) = fixed_codes()?;
+				inflate_block(&mut bits, &literals, &distances, &mut output, limit)?;
+			}
+
+			2 => {
+				let (literals, distances) = dynamic_codes(&mut bits)?;
+				inflate_block(&mut bits, &literals, &distances, &mut output, limit)?;
+			}
+
+			_ => return Err(invalid_data("invalid deflate block type")),
+		}
+
+		if last {
+			return Ok(output);
+		}
+	}
+}
+
+
+/// Decompress the codes of a huffman compressed block.
+fn inflate_block(
+	bits: &mut Bits,
+	literals: &Huffman,
+	distances: &Huffman,
+	output: &mut Vec<u8>,
+	limit: usize,
+) -> io::Result<()> {
+	loop {
+		let symbol = literals.decode(bits)? as usize;
+
+		let length = match symbol {
+			0 ..= 255 => {
+				if output.len() >= limit {
+					return Err(invalid_data("deflate stream exceeds the expected size"));
+				}
+
+				output.push(symbol as u8);
+				continue;
+			}
+
+			256 => return Ok(()),
+
+			257 ..= 285 => {
+				let ix = symbol - 257;
+				LENGTH_BASE[ix] as usize + bits.read(LENGTH_EXTRA[ix] as u32)? as usize
+			}
+
+			_ => return Err(invalid_data("invalid deflate length code")),
+		};
+
+		let symbol = distances.decode(bits)? as usize;
+
+		if symbol >= DISTANCE_BASE.len() {
+			return Err(invalid_data("invalid deflate distance code"));
+		}
+
+		let distance = DISTANCE_BASE[symbol] as usize + bits.read(DISTANCE_EXTRA[symbol] as u32)? as usize;
+
+		if distance > output.len() {
+			return Err(invalid_data("invalid deflate distance"));
+		}
+
+		if output.len() + length > limit {
+			return Err(invalid_data("deflate stream exceeds the expected size"));
+		}
+
+		// The copy may overlap the bytes being produced, so it must be done bytewise.
+		let start = output.len() - distance;
+		for ix in start .. start + length {
+			output.push(output[ix]);
+		}
+	}
+}
+
+
+fn fixed_codes() -> io::Result<(Huffman, Huffman)> {
+	let mut lengths = [0; 288];
+	lengths[.. 144].fill(8);
+	lengths[144 .. 256].fill(9);
+	lengths[256 .. 280].fill(7);
+	lengths[280 ..].fill(8);
+
+	Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+
+fn dynamic_codes(bits: &mut Bits) -> io::Result<(Huffman, Huffman)> {
+	let literal_count = bits.read(5)? as usize + 257;
+	let distance_count = bits.read(5)? as usize + 1;
+	let code_length_count = bits.read(4)? as usize + 4;
+
+	let mut code_lengths = [0; 19];
+	for &ix in &CODE_LENGTH_ORDER[.. code_length_count] {
+		code_lengths[ix] = bits.read(3)? as u8;
+	}
+	let code_lengths = Huffman::new(&code_lengths)?;
+
+	let mut lengths = Vec::with_capacity(literal_count + distance_count);
+
+	while lengths.len() < literal_count + distance_count {
+		let (length, repeat) = match code_lengths.decode(bits)? {
+			length @ 0 ..= 15 => (length as u8, 1),
+
+			16 => {
+				let previous = *lengths
+					.last()
+					.ok_or_else(|| invalid_data("invalid deflate code lengths"))?;
+				(previous, 3 + bits.read(2)?)
+			}
+
+			17 => (0, 3 + bits.read(3)?),
+			18 => (0, 11 + bits.read(7)?),
+
+			_ => return Err(invalid_data("invalid deflate code lengths")),
+		};
+
+		if lengths.len() + repeat as usize > literal_count + distance_count {
+			return Err(invalid_data("invalid deflate code lengths"));
+		}
+
+		lengths.extend(std::iter::repeat_n(length, repeat as usize));
+	}
+
+	if lengths[256] == 0 {
+		return Err(invalid_data("missing deflate end of block code"));
+	}
+
+	Ok(
+		(
+			Huffman::new(&lengths[.. literal_count])?,
+			Huffman::new(&lengths[literal_count ..])?,
+		)
+	)
+}
+
+
+/// Reader of the bits of a deflate stream, least significant first.
+struct Bits<'a> {
+	data: &'a [u8],
+	/// The position of the next byte to be buffered.
+	pos: usize,
+	buffer: u32,
+	/// The amount of buffered bits.
+	count: u32,
+}
+
+
+impl<'a> Bits<'a> {
+	/// Read the given amount of bits, at most 16.
+	fn read(&mut self, amount: u32) -> io::Result<u32> {
+		while self.count < amount {
+			let byte = *self.data
+				.get(self.pos)
+				.ok_or_else(|| invalid_data("truncated deflate stream"))?;
+
+			self.buffer |= (byte as u32) << self.count;
+			self.pos += 1;
+			self.count += 8;
+		}
+
+		let value = self.buffer & ((1 << amount) - 1);
+		self.buffer >>= amount;
+		self.count -= amount;
+
+		Ok(value)
+	}
+
+
+	/// Discard the remaining bits of the current byte.
+	fn align(&mut self) {
+		self.buffer = 0;
+		self.count = 0;
+	}
+}
+
+
+/// A canonical huffman code.
+struct Huffman {
+	/// The amount of codes of each length.
+	counts: [u16; MAX_BITS + 1],
+	/// The symbols, ordered by code.
+	symbols: Vec<u16>,
+}
+
+
+impl Huffman {
+	/// Build a code from the code lengths of each symbol. Incomplete codes are accepted, as
+	/// they may occur in valid streams.
+	fn new(lengths: &[u8]) -> io::Result<Self> {
+		let mut counts = [0; MAX_BITS + 1];
+		for &length in lengths {
+			counts[length as usize] += 1;
+		}
+		counts[0] = 0;
+
+		let mut left: i32 = 1;
+		for &count in &counts[1 ..] {
+			left = (left << 1) - count as i32;
+			if left < 0 {
+				return Err(invalid_data("invalid deflate huffman code"));
+			}
+		}
+
+		let mut offsets = [0; MAX_BITS + 2];
+		for length in 1 ..= MAX_BITS {
+			offsets[length + 1] = offsets[length] + counts[length];
+		}
+
+		let mut symbols = vec![0; offsets[MAX_BITS + 1] as usize];
+		for (symbol, &length) in lengths.iter().enumerate() {
+			if length != 0 {
+				symbols[offsets[length as usize] as usize] = symbol as u16;
+				offsets[length as usize] += 1;
+			}
+		}
+
+		Ok(Self { counts, symbols })
+	}
+
+
+	/// Decode a symbol.
+	fn decode(&self, bits: &mut Bits) -> io::Result<u16> {
+		let mut code: i32 = 0;
+		let mut first: i32 = 0;
+		let mut index: i32 = 0;
+
+		for &count in &self.counts[1 ..] {
+			code |= bits.read(1)? as i32;
+			let count = count as i32;
+
+			if code - count < first {
+				return Ok(self.symbols[(index + code - first) as usize]);
+			}
+
+			index += count;
+			first = (first + count) << 1;
+			code <<= 1;
+		}
+
+		Err(invalid_data("invalid deflate code"))
+	}
+}
@@ -0,0 +1,254 @@
+//! Creation and extraction of tar and zip archives, used by std.archive.
+//!
+//! Only the common subset of the formats is supported: regular files, directories and
+//! symlinks. Zip archives are created without compression, but both stored and deflated
+//! entries can be extracted.
+
+#[cfg(test)]
+mod tests;
+
+mod crc32;
+mod inflate;
+pub mod tar;
+pub mod zip;
+
+use std::{
+	ffi::OsStr,
+	fs,
+	io,
+	os::unix::{ffi::{OsStrExt, OsStringExt}, fs::{MetadataExt, PermissionsExt}},
+	path::{Component, Path, PathBuf},
+};
+
+
+/// The kind of an archive entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kind {
+	File,
+	Dir,
+	/// A symlink, with the given target.
+	Symlink(Vec<u8>),
+}
+
+
+/// An entry to be archived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+	/// The name in the archive, with slash separated components.
+	pub name: Vec<u8>,
+	/// The path in the file system.
+	pub path: PathBuf,
+	pub kind: Kind,
+	/// Permission bits.
+	pub mode: u32,
+	/// Modification time, in seconds since the unix epoch.
+	pub mtime: u64,
+	/// The size of regular files.
+	pub size: u64,
+}
+
+
+/// Collect the entries for the given paths, descending into directories. Entries are named
+/// after the given paths, without leading slashes and parent components.
+pub fn collect<P: AsRef<Path>>(paths: &[P]) -> io::Result<Vec<Entry>> {
+	let mut entries = Vec::new();
+
+	for path in paths {
+		let path = path.as_ref();
+		let name = archive_name(path)?;
+		collect_entry(path.to_owned(), name, &mut entries)?;
+	}
+
+	Ok(entries)
+}
+
+
+fn collect_entry(path: PathBuf, name: Vec<u8>, entries: &mut Vec<Entry>) -> io::Result<()> {
+	let metadata = fs::symlink_metadata(&path)?;
+	let file_type = metadata.file_type();
+
+	let kind = if file_type.is_dir() {
+		Kind::Dir
+	} else if file_type.is_symlink() {
+		Kind::Symlink(fs::read_link(&path)?.into_os_string().into_vec())
+	} else if file_type.is_file() {
+		Kind::File
+	} else {
+		return Err(
+			io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("unsupported file type: {}", path.display())
+			)
+		);
+	};
+
+	let is_dir = kind == Kind::Dir;
+
+	// The current directory has no name, so only its contents are archived.
+	if !name.is_empty() {
+		entries.push(
+			Entry {
+				name: name.clone(),
+				path: path.clone(),
+				kind,
+				mode: metadata.mode() & 0o7777,
+				mtime: metadata.mtime().max(0) as u64,
+				size: if file_type.is_file() { metadata.len() } else { 0 },
+			}
+		);
+	}
+
+	if is_dir {
+		let mut children = fs::read_dir(&path)?
+			.map(|entry| entry.map(|entry| entry.file_name()))
+			.collect::<io::Result<Vec<_>>>()?;
+
+		children.sort();
+
+		for child in children {
+			let mut child_name = name.clone();
+			if !child_name.is_empty() {
+				child_name.push(b'/');
+			}
+			child_name.extend_from_slice(child.as_bytes());
+
+			collect_entry(path.join(child), child_name, entries)?;
+		}
+	}
+
+	Ok(())
+}
+
+
+/// The name of a path in an archive.
+fn archive_name(path: &Path) -> io::Result<Vec<u8>> {
+	let components: Vec<&[u8]> = path
+		.components()
+		.filter_map(
+			|component| match component {
+				Component::Normal(name) => Some(name.as_bytes()),
+				_ => None,
+			}
+		)
+		.collect();
+
+	if components.is_empty() && !path.is_dir() {
+		return Err(
+			io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("invalid path: {}", path.display())
+			)
+		);
+	}
+
+	Ok(components.join(&b'/'))
+}
+
+
+/// Resolve the destination of an archive entry, refusing names which would escape the
+/// destination directory, either through absolute or parent components, or through
+/// previously extracted symlinks.
+fn destination(dest: &Path, name: &[u8]) -> io::Result<PathBuf> {
+	let invalid = || invalid_data(
+		format!("unsafe path in archive: {}", String::from_utf8_lossy(name))
+	);
+
+	let mut path = dest.to_owned();
+	let mut components = 0;
+
+	for component in Path::new(OsStr::from_bytes(name)).components() {
+		match component {
+			Component::Normal(component) => {
+				if components > 0 && is_symlink(&path) {
+					return Err(invalid());
+				}
+
+				path.push(component);
+				components += 1;
+			}
+
+			Component::CurDir => (),
+
+			_ => return Err(invalid()),
+		}
+	}
+
+	if components == 0 {
+		return Err(invalid());
+	}
+
+	Ok(path)
+}
+
+
+fn is_symlink(path: &Path) -> bool {
+	fs::symlink_metadata(path)
+		.map(|metadata| metadata.file_type().is_symlink())
+		.unwrap_or(false)
+}
+
+
+/// Extract an entry to the destination directory.
+fn extract_entry(
+	dest: &Path,
+	name: &[u8],
+	kind: &Kind,
+	mode: u32,
+	data: &[u8],
+) -> io::Result<()> {
+	let path = destination(dest, name)?;
+
+	if *kind == Kind::Dir && is_symlink(&path) {
+		return Err(
+			invalid_data(format!("unsafe path in archive: {}", String::from_utf8_lossy(name)))
+		);
+	}
+
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	match kind {
+		Kind::Dir => {
+			fs::create_dir_all(&path)?;
+		}
+
+		Kind::File => {
+			if is_symlink(&path) {
+				fs::remove_file(&path)?;
+			}
+
+			fs::write(&path, data)?;
+		}
+
+		Kind::Symlink(target) => {
+			if is_symlink(&path) {
+				fs::remove_file(&path)?;
+			}
+
+			std::os::unix::fs::symlink(OsStr::from_bytes(target), &path)?;
+		}
+	}
+
+	let mode = match kind {
+		// Directories must remain writable for their entries to be extracted.
+		Kind::Dir => mode | 0o700,
+		Kind::File => mode,
+		Kind::Symlink(_) => return Ok(()),
+	};
+
+	if mode & 0o7777 != 0 {
+		fs::set_permissions(&path, fs::Permissions::from_mode(mode & 0o7777))?;
+	}
+
+	Ok(())
+}
+
+
+fn invalid_data<E>(message: E) -> io::Error
+where
+	E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
@@ -0,0 +1,412 @@
+//! The ustar format, with the GNU and pax extensions for long names.
+
+use std::{
+	fs::{self, File},
+	io::{self, Read, Write},
+	path::Path,
+};
+
+use super::{destination, extract_entry, invalid_data, Entry, Kind};
+
+
+const BLOCK: usize = 512;
+/// The name used for GNU long name entries.
+const LONG_LINK: &[u8] = b"././@LongLink";
+
+
+/// Write an archive with the given entries.
+pub fn write<W: Write>(out: &mut W, entries: &[Entry]) -> io::Result<()> {
+	for entry in entries {
+		let mut name = entry.name.clone();
+		if entry.kind == Kind::Dir {
+			name.push(b'/');
+		}
+
+		let (type_flag, link) = match &entry.kind {
+			Kind::File => (b'0', &[][..]),
+			Kind::Dir => (b'5', &[][..]),
+			Kind::Symlink(target) => (b'2', target.as_slice()),
+		};
+
+		if link.len() > 100 {
+			write_long_link(out, b'K', link)?;
+		}
+
+		let (prefix, short_name) = match split_name(&name) {
+			Some(split) => split,
+			None => {
+				write_long_link(out, b'L', &name)?;
+				(&[][..], &name[.. 100])
+			}
+		};
+
+		let header = Header {
+			name: short_name,
+			prefix,
+			mode: entry.mode,
+			size: entry.size,
+			mtime: entry.mtime,
+			type_flag,
+			link: &link[.. link.len().min(100)],
+		};
+
+		out.write_all(&header.encode()?)?;
+
+		if entry.kind == Kind::File {
+			let file = File::open(&entry.path)?;
+			let copied = io::copy(&mut file.take(entry.size), out)?;
+
+			if copied != entry.size {
+				return Err(
+					io::Error::new(
+						io::ErrorKind::UnexpectedEof,
+						format!("file changed while archiving: {}", entry.path.display())
+					)
+				);
+			}
+
+			pad(out, entry.size)?;
+		}
+	}
+
+	out.write_all(&[0; 2 * BLOCK])
+}
+
+
+/// Extract an archive to the given directory, returning the names of the extracted entries.
+pub fn extract<R: Read>(input: &mut R, dest: &Path) -> io::Result<Vec<Vec<u8>>> {
+	let mut names = Vec::new();
+
+	// Names set by GNU long name or pax entries, applying to the next entry.
+	let mut long_name: Option<Vec<u8>> = None;
+	let mut long_link: Option<Vec<u8>> = None;
+
+	loop {
+		let mut block = [0; BLOCK];
+
+		if !read_block(input, &mut block)? || block.iter().all(|&byte| byte == 0) {
+			return Ok(names);
+		}
+
+		let stored_checksum = octal(&block[148 .. 156])?;
+		let checksum: u64 = block
+			.iter()
+			.enumerate()
+			.map(|(ix, &byte)| if (148 .. 156).contains(&ix) { b' ' as u64 } else { byte as u64 })
+			.sum();
+
+		if checksum != stored_checksum {
+			return Err(invalid_data("invalid tar header checksum"));
+		}
+
+		let size = number(&block[124 .. 136])?;
+		let mode = octal(&block[100 .. 108])? as u32;
+		let type_flag = block[156];
+
+		let data = read_data(input, size)?;
+
+		// Extension entries carry data for the next entry.
+		match type_flag {
+			b'L' => {
+				long_name = Some(field(&data).to_vec());
+				continue;
+			}
+
+			b'K' => {
+				long_link = Some(field(&data).to_vec());
+				continue;
+			}
+
+			b'x' => {
+				for (key, value) in pax_records(&data)? {
+					match key {
+						b"path" => long_name = Some(value.to_vec()),
+						b"linkpath" => long_link = Some(value.to_vec()),
+						_ => (),
+					}
+				}
+				continue;
+			}
+
+			// Global pax headers carry no entry.
+			b'g' => continue,
+
+			_ => (),
+		}
+
+		let name = match long_name.take() {
+			Some(name) => name,
+			None if &block[257 .. 262] == b"ustar" && block[345] != 0 => {
+				let mut name = field(&block[345 .. 500]).to_vec();
+				name.push(b'/');
+				name.extend_from_slice(field(&block[.. 100]));
+				name
+			}
+			None => field(&block[.. 100]).to_vec(),
+		};
+
+		let link = long_link
+			.take()
+			.unwrap_or_else(|| field(&block[157 .. 257]).to_vec());
+
+		let kind = match type_flag {
+			b'0' | b'\0' | b'7' => Kind::File,
+			b'5' => Kind::Dir,
+			b'2' => Kind::Symlink(link),
+
+			b'1' => {
+				let target = destination(dest, &link)?;
+				let path = destination(dest, &name)?;
+
+				if let Some(parent) = path.parent() {
+					fs::create_dir_all(parent)?;
+				}
+
+				if fs::symlink_metadata(&path).is_ok() {
+					fs::remove_file(&path)?;
+				}
+
+				fs::hard_link(target, path)?;
+				names.push(name);
+				continue;
+			}
+
+			other => return Err(
+				invalid_data(format!("unsupported tar entry type ({})", other as char))
+			),
+		};
+
+		let name = name
+			.strip_suffix(b"/")
+			.map(<[u8]>::to_vec)
+			.unwrap_or(name);
+
+		extract_entry(dest, &name, &kind, mode, &data)?;
+		names.push(name);
+	}
+}
+
+
+/// The fields of an entry header.
+struct Header<'a> {
+	name: &'a [u8],
+	prefix: &'a [u8],
+	mode: u32,
+	size: u64,
+	mtime: u64,
+	type_flag: u8,
+	link: &'a [u8],
+}
+
+
+impl<'a> Header<'a> {
+	fn encode(&self) -> io::Result<[u8; BLOCK]> {
+		let mut block = [0; BLOCK];
+
+		block[.. self.name.len()].copy_from_slice(self.name);
+		write_octal(&mut block[100 .. 108], self.mode as u64)?;
+		write_octal(&mut block[108 .. 116], 0)?; // uid
+		write_octal(&mut block[116 .. 124], 0)?; // gid
+		write_octal(&mut block[124 .. 136], self.size)?;
+		write_octal(&mut block[136 .. 148], self.mtime)?;
+		block[156] = self.type_flag;
+		block[157 .. 157 + self.link.len()].copy_from_slice(self.link);
+		block[257 .. 263].copy_from_slice(b"ustar\0");
+		block[263 .. 265].copy_from_slice(b"00");
+		block[345 .. 345 + self.prefix.len()].copy_from_slice(self.prefix);
+
+		// The checksum is computed with the checksum field filled with spaces.
+		block[148 .. 156].fill(b' ');
+		let checksum: u64 = block.iter().map(|&byte| byte as u64).sum();
+		write_octal(&mut block[148 .. 155], checksum)?;
+
+		Ok(block)
+	}
+}
+
+
+/// Split a name in the ustar prefix and name fields, if it fits.
+fn split_name(name: &[u8]) -> Option<(&[u8], &[u8])> {
+	if name.len() <= 100 {
+		return Some((&[], name));
+	}
+
+	// The name may end with a slash, for directories, which must not be split.
+	let search = &name[.. name.len() - 1];
+
+	search
+		.iter()
+		.enumerate()
+		.rev()
+		.filter(|&(_, &byte)| byte == b'/')
+		.map(|(ix, _)| (&name[.. ix], &name[ix + 1 ..]))
+		.find(|(prefix, name)| prefix.len() <= 155 && name.len() <= 100 && !name.is_empty())
+}
+
+
+/// Write a GNU long name entry, carrying the name of the next entry.
+fn write_long_link<W: Write>(out: &mut W, type_flag: u8, name: &[u8]) -> io::Result<()> {
+	let header = Header {
+		name: LONG_LINK,
+		prefix: &[],
+		mode: 0,
+		size: name.len() as u64 + 1,
+		mtime: 0,
+		type_flag,
+		link: &[],
+	};
+
+	out.write_all(&header.encode()?)?;
+	out.write_all(name)?;
+	out.write_all(&[0])?;
+	pad(out, name.len() as u64 + 1)
+}
+
+
+/// Pad data of the given size to a whole block.
+fn pad<W: Write>(out: &mut W, size: u64) -> io::Result<()> {
+	let remainder = (size % BLOCK as u64) as usize;
+
+	if remainder != 0 {
+		out.write_all(&[0; BLOCK][remainder ..])?;
+	}
+
+	Ok(())
+}
+
+
+/// Write a NUL terminated octal number filling the field.
+fn write_octal(field: &mut [u8], value: u64) -> io::Result<()> {
+	let digits = field.len() - 1;
+	let octal = format!("{:0width$o}", value, width = digits);
+
+	if octal.len() > digits {
+		return Err(invalid_data(format!("value too large for tar header: {}", value)));
+	}
+
+	field[.. digits].copy_from_slice(octal.as_bytes());
+	field[digits] = 0;
+
+	Ok(())
+}
+
+
+/// Read a number field, which is either octal or base-256.
+fn number(field: &[u8]) -> io::Result<u64> {
+	if field[0] & 0x80 != 0 {
+		let value = field[1 ..]
+			.iter()
+			.fold(
+				(field[0] & 0x7F) as u64,
+				|value, &byte| (value << 8) | byte as u64
+			);
+
+		Ok(value)
+	} else {
+		octal(field)
+	}
+}
+
+
+fn octal(field: &[u8]) -> io::Result<u64> {
+	let digits = field
+		.iter()
+		.copied()
+		.skip_while(|&byte| byte == b' ')
+		.take_while(|&byte| byte != b' ' && byte != 0);
+
+	let mut value: u64 = 0;
+
+	for digit in digits {
+		if !(b'0' ..= b'7').contains(&digit) {
+			return Err(invalid_data("invalid number in tar header"));
+		}
+
+		value = value
+			.checked_mul(8)
+			.map(|value| value + (digit - b'0') as u64)
+			.ok_or_else(|| invalid_data("invalid number in tar header"))?;
+	}
+
+	Ok(value)
+}
+
+
+/// A NUL terminated field.
+fn field(field: &[u8]) -> &[u8] {
+	let end = field
+		.iter()
+		.position(|&byte| byte == 0)
+		.unwrap_or(field.len());
+
+	&field[.. end]
+}
+
+
+/// Parse the records of a pax extended header, in the form `<length> <key>=<value>\n`.
+fn pax_records(mut data: &[u8]) -> io::Result<Vec<(&[u8], &[u8])>> {
+	let invalid = || invalid_data("invalid pax header");
+	let mut records = Vec::new();
+
+	while !data.is_empty() && data[0] != 0 {
+		let space = data
+			.iter()
+			.position(|&byte| byte == b' ')
+			.ok_or_else(invalid)?;
+
+		let length: usize = std::str
+			::from_utf8(&data[.. space])
+			.ok()
+			.and_then(|length| length.parse().ok())
+			.filter(|&length| length > space + 1 && length <= data.len())
+			.ok_or_else(invalid)?;
+
+		let record = &data[space + 1 .. length - 1];
+		let equals = record
+			.iter()
+			.position(|&byte| byte == b'=')
+			.ok_or_else(invalid)?;
+
+		records.push((&record[.. equals], &record[equals + 1 ..]));
+		data = &data[length ..];
+	}
+
+	Ok(records)
+}
+
+
+/// Read a whole block, returning false at the end of the input.
+fn read_block<R: Read>(input: &mut R, block: &mut [u8; BLOCK]) -> io::Result<bool> {
+	let mut read = 0;
+
+	while read < BLOCK {
+		match input.read(&mut block[read ..]) {
+			Ok(0) if read == 0 => return Ok(false),
+			Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated tar archive")),
+			Ok(count) => read += count,
+			Err(error) if error.kind() == io::ErrorKind::Interrupted => (),
+			Err(error) => return Err(error),
+		}
+	}
+
+	Ok(true)
+}
+
+
+/// Read the data of an entry, including the padding.
+fn read_data<R: Read>(input: &mut R, size: u64) -> io::Result<Vec<u8>> {
+	let padded = size.div_ceil(BLOCK as u64) * BLOCK as u64;
+
+	let mut data = Vec::new();
+	input
+		.take(padded)
+		.read_to_end(&mut data)?;
+
+	if (data.len() as u64) < padded {
+		return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated tar archive"));
+	}
+
+	data.truncate(size as usize);
+
+	Ok(data)
+}
@@ -0,0 +1,146 @@
+use std::{
+	fs,
+	os::unix::fs::PermissionsExt,
+	path::PathBuf,
+};
+
+use super::*;
+
+
+/// Create an empty temporary directory for the given test.
+fn temp_dir(test: &str) -> PathBuf {
+	let dir = std::env::temp_dir().join(format!("hush-archive-{}-{}", test, std::process::id()));
+	let _ = fs::remove_dir_all(&dir);
+	fs::create_dir_all(&dir).expect("failed to create temp dir");
+	dir
+}
+
+
+/// Create a tree with a file, an executable, a symlink and a deeply nested file.
+fn populate(root: &Path) -> PathBuf {
+	let src = root.join("src");
+	let long = "x".repeat(60);
+	let nested = src.join(&long).join(&long);
+
+	fs::create_dir_all(&nested).unwrap();
+	fs::write(src.join("file"), "contents\n").unwrap();
+	fs::write(src.join("script"), "#!/bin/sh\n").unwrap();
+	fs::set_permissions(src.join("script"), fs::Permissions::from_mode(0o755)).unwrap();
+	fs::write(nested.join("deep"), vec![7; 1500]).unwrap();
+	std::os::unix::fs::symlink("file", src.join("link")).unwrap();
+
+	src
+}
+
+
+fn check_extracted(dest: &Path) {
+	let src = dest.join("src");
+	let long = "x".repeat(60);
+
+	assert_eq!(fs::read(src.join("file")).unwrap(), b"contents\n");
+	assert_eq!(fs::read_link(src.join("link")).unwrap(), PathBuf::from("file"));
+	assert_eq!(fs::read(src.join(&long).join(&long).join("deep")).unwrap(), vec![7; 1500]);
+
+	let mode = fs::metadata(src.join("script")).unwrap().permissions().mode();
+	assert_eq!(mode & 0o777, 0o755);
+}
+
+
+#[test]
+fn test_tar_roundtrip() {
+	let root = temp_dir("tar");
+	let src = populate(&root);
+	let entries = collect(&[&src]).unwrap();
+
+	let mut archive = Vec::new();
+	tar::write(&mut archive, &entries).unwrap();
+	assert_eq!(archive.len() % 512, 0);
+
+	let dest = root.join("dest");
+	let names = tar::extract(&mut archive.as_slice(), &dest).unwrap();
+
+	assert_eq!(names.len(), entries.len());
+	check_extracted(dest.join(src.strip_prefix("/").unwrap()).parent().unwrap());
+
+	fs::remove_dir_all(root).unwrap();
+}
+
+
+#[test]
+fn test_zip_roundtrip() {
+	let root = temp_dir("zip");
+	let src = populate(&root);
+	let entries = collect(&[&src]).unwrap();
+
+	let mut archive = Vec::new();
+	zip::write(&mut archive, &entries).unwrap();
+
+	let dest = root.join("dest");
+	let names = zip::extract(&archive, &dest).unwrap();
+
+	assert_eq!(names.len(), entries.len());
+	check_extracted(dest.join(src.strip_prefix("/").unwrap()).parent().unwrap());
+
+	fs::remove_dir_all(root).unwrap();
+}
+
+
+#[test]
+fn test_unsafe_paths() {
+	let root = temp_dir("unsafe");
+
+	for name in [&b"../escape"[..], b"/absolute", b"a/../../escape", b""] {
+		assert!(destination(&root, name).is_err());
+	}
+
+	std::os::unix::fs::symlink("/tmp", root.join("link")).unwrap();
+	assert!(destination(&root, b"link/file").is_err());
+	assert!(destination(&root, b"./dir/file").is_ok());
+
+	fs::remove_dir_all(root).unwrap();
+}
+
+
+#[test]
+fn test_inflate() {
+	// Fixed huffman codes.
+	let fixed = [
+		203, 72, 205, 201, 201, 87, 200, 64, 39, 117, 20, 82, 82, 211, 114, 18, 75, 82, 21, 49,
+		229, 104, 162, 130, 129, 145, 137, 153, 133, 149, 141, 157, 131, 147, 139, 155, 135, 151,
+		143, 95, 64, 80, 72, 88, 68, 84, 76, 92, 66, 82, 74, 90, 70, 86, 78, 94, 65, 81, 73, 89,
+		69, 85, 77, 93, 67, 83, 75, 91, 71, 87, 79, 223, 192, 208, 200, 216, 196, 212, 204, 220,
+		194, 210, 202, 218, 198, 214, 206, 30, 0,
+	];
+	let mut expected = b"hello hello hello hello, deflate! ".repeat(4);
+	expected.extend(0 .. 64);
+	assert_eq!(inflate::inflate(&fixed, 200).unwrap(), expected);
+	assert!(inflate::inflate(&fixed, 199).is_err());
+
+	// Dynamic huffman codes.
+	let dynamic = [
+		125, 211, 205, 13, 130, 0, 16, 68, 225, 187, 85, 108, 9, 206, 140, 226, 79, 57, 24, 12,
+		68, 2, 209, 64, 164, 124, 99, 1, 188, 243, 59, 237, 151, 217, 113, 152, 186, 58, 222, 107,
+		233, 187, 122, 175, 195, 227, 85, 237, 103, 254, 78, 245, 156, 183, 195, 248, 111, 130,
+		102, 104, 129, 118, 130, 118, 134, 214, 64, 187, 64, 187, 66, 187, 209, 237, 8, 67, 50,
+		34, 26, 145, 141, 8, 71, 164, 35, 226, 17, 249, 136, 128, 68, 66, 38, 33, 227, 118, 72,
+		200, 36, 100, 18, 50, 9, 153, 132, 76, 66, 38, 33, 147, 80, 72, 40, 36, 20, 124, 47, 18,
+		10, 9, 133, 132, 66, 66, 33, 161, 144, 80, 118, 132, 126,
+	];
+	let expected: String = (0 .. 40)
+		.map(|ix| format!("line {}: the quick brown fox\n", ix))
+		.collect();
+	assert_eq!(inflate::inflate(&dynamic, 1150).unwrap(), expected.as_bytes());
+
+	// Stored block.
+	let stored = [1, 6, 0, 249, 255, 115, 116, 111, 114, 101, 100];
+	assert_eq!(inflate::inflate(&stored, 6).unwrap(), b"stored");
+
+	assert!(inflate::inflate(&dynamic[.. 64], 1150).is_err());
+}
+
+
+#[test]
+fn test_crc32() {
+	assert_eq!(crc32::checksum(b""), 0);
+	assert_eq!(crc32::checksum(b"123456789"), 0xCBF4_3926);
+}
@@ -0,0 +1,272 @@
+//! The zip format. Entries are written uncompressed, and may be read either uncompressed
+//! or deflated. Zip64 and encryption are unsupported.
+
+use std::{
+	convert::TryFrom,
+	fs,
+	io::{self, Write},
+	path::Path,
+};
+
+use super::{crc32, extract_entry, inflate::inflate, invalid_data, Entry, Kind};
+
+
+const LOCAL_HEADER: u32 = 0x0403_4B50;
+const CENTRAL_HEADER: u32 = 0x0201_4B50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4B50;
+
+/// The size of the end of central directory record, without the comment.
+const END_SIZE: usize = 22;
+
+/// Version 2.0, which supports directories and deflate.
+const VERSION: u16 = 20;
+/// The host system in the "version made by" field, for unix file attributes.
+const UNIX_HOST: u16 = 3;
+/// Flag for names encoded in UTF-8.
+const UTF8_FLAG: u16 = 0x0800;
+const ENCRYPTED_FLAG: u16 = 0x0001;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+const TYPE_MASK: u32 = 0o170000;
+const TYPE_FILE: u32 = 0o100000;
+const TYPE_DIR: u32 = 0o040000;
+const TYPE_SYMLINK: u32 = 0o120000;
+
+
+/// Write an archive with the given entries.
+pub fn write<W: Write>(out: &mut W, entries: &[Entry]) -> io::Result<()> {
+	let too_large = || invalid_data("archive too large for the zip format");
+
+	let mut central = Vec::new();
+	let mut offset: u64 = 0;
+
+	for entry in entries {
+		let mut name = entry.name.clone();
+
+		let (data, file_type) = match &entry.kind {
+			Kind::File => (fs::read(&entry.path)?, TYPE_FILE),
+			Kind::Dir => {
+				name.push(b'/');
+				(Vec::new(), TYPE_DIR)
+			}
+			Kind::Symlink(target) => (target.clone(), TYPE_SYMLINK),
+		};
+
+		let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+		let local_offset = u32::try_from(offset).map_err(|_| too_large())?;
+		let name_length = u16::try_from(name.len()).map_err(|_| too_large())?;
+		let checksum = crc32::checksum(&data);
+		let (time, date) = dos_time(entry.mtime);
+		let flags = if std::str::from_utf8(&name).is_ok() { UTF8_FLAG } else { 0 };
+
+		let mut local = Vec::with_capacity(30 + name.len());
+		local.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+		local.extend_from_slice(&VERSION.to_le_bytes());
+		local.extend_from_slice(&flags.to_le_bytes());
+		local.extend_from_slice(&STORED.to_le_bytes());
+		local.extend_from_slice(&time.to_le_bytes());
+		local.extend_from_slice(&date.to_le_bytes());
+		local.extend_from_slice(&checksum.to_le_bytes());
+		local.extend_from_slice(&size.to_le_bytes()); // Compressed size.
+		local.extend_from_slice(&size.to_le_bytes());
+		local.extend_from_slice(&name_length.to_le_bytes());
+		local.extend_from_slice(&0u16.to_le_bytes()); // Extra field length.
+		local.extend_from_slice(&name);
+
+		out.write_all(&local)?;
+		out.write_all(&data)?;
+		offset += (local.len() + data.len()) as u64;
+
+		// The low byte holds the MS-DOS attributes, where 0x10 marks directories.
+		let dos_attributes = if file_type == TYPE_DIR { 0x10 } else { 0 };
+		let attributes = ((file_type | entry.mode) << 16) | dos_attributes;
+
+		central.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+		central.extend_from_slice(&(UNIX_HOST << 8 | VERSION).to_le_bytes());
+		central.extend_from_slice(&VERSION.to_le_bytes());
+		central.extend_from_slice(&flags.to_le_bytes());
+		central.extend_from_slice(&STORED.to_le_bytes());
+		central.extend_from_slice(&time.to_le_bytes());
+		central.extend_from_slice(&date.to_le_bytes());
+		central.extend_from_slice(&checksum.to_le_bytes());
+		central.extend_from_slice(&size.to_le_bytes());
+		central.extend_from_slice(&size.to_le_bytes());
+		central.extend_from_slice(&name_length.to_le_bytes());
+		central.extend_from_slice(&0u16.to_le_bytes()); // Extra field length.
+		central.extend_from_slice(&0u16.to_le_bytes()); // Comment length.
+		central.extend_from_slice(&0u16.to_le_bytes()); // Disk number.
+		central.extend_from_slice(&0u16.to_le_bytes()); // Internal attributes.
+		central.extend_from_slice(&attributes.to_le_bytes());
+		central.extend_from_slice(&local_offset.to_le_bytes());
+		central.extend_from_slice(&name);
+	}
+
+	let count = u16::try_from(entries.len()).map_err(|_| too_large())?;
+	let central_size = u32::try_from(central.len()).map_err(|_| too_large())?;
+	let central_offset = u32::try_from(offset).map_err(|_| too_large())?;
+
+	out.write_all(&central)?;
+
+	let mut end = Vec::with_capacity(END_SIZE);
+	end.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+	end.extend_from_slice(&0u16.to_le_bytes()); // Disk number.
+	end.extend_from_slice(&0u16.to_le_bytes()); // Disk of the central directory.
+	end.extend_from_slice(&count.to_le_bytes());
+	end.extend_from_slice(&count.to_le_bytes());
+	end.extend_from_slice(&central_size.to_le_bytes());
+	end.extend_from_slice(&central_offset.to_le_bytes());
+	end.extend_from_slice(&0u16.to_le_bytes()); // Comment length.
+
+	out.write_all(&end)
+}
+
+
+/// Extract an archive to the given directory, returning the names of the extracted entries.
+pub fn extract(data: &[u8], dest: &Path) -> io::Result<Vec<Vec<u8>>> {
+	let end = find_end(data)?;
+
+	let count = u16_at(data, end + 10)? as usize;
+	let central_offset = u32_at(data, end + 16)?;
+
+	if central_offset == u32::MAX {
+		return Err(invalid_data("zip64 archives are unsupported"));
+	}
+
+	let mut names = Vec::with_capacity(count);
+	let mut pos = central_offset as usize;
+
+	for _ in 0 .. count {
+		if u32_at(data, pos)? != CENTRAL_HEADER {
+			return Err(invalid_data("invalid zip central directory"));
+		}
+
+		let host = u16_at(data, pos + 4)? >> 8;
+		let flags = u16_at(data, pos + 8)?;
+		let method = u16_at(data, pos + 10)?;
+		let checksum = u32_at(data, pos + 16)?;
+		let compressed_size = u32_at(data, pos + 20)? as usize;
+		let size = u32_at(data, pos + 24)? as usize;
+		let name_length = u16_at(data, pos + 28)? as usize;
+		let extra_length = u16_at(data, pos + 30)? as usize;
+		let comment_length = u16_at(data, pos + 32)? as usize;
+		let attributes = u32_at(data, pos + 38)?;
+		let local_offset = u32_at(data, pos + 42)? as usize;
+		let name = slice(data, pos + 46, name_length)?.to_vec();
+
+		pos += 46 + name_length + extra_length + comment_length;
+
+		if flags & ENCRYPTED_FLAG != 0 {
+			return Err(invalid_data("encrypted zip entries are unsupported"));
+		}
+
+		if u32_at(data, local_offset)? != LOCAL_HEADER {
+			return Err(invalid_data("invalid zip local header"));
+		}
+
+		let data_start = local_offset
+			+ 30
+			+ u16_at(data, local_offset + 26)? as usize
+			+ u16_at(data, local_offset + 28)? as usize;
+
+		let compressed = slice(data, data_start, compressed_size)?;
+
+		let content = match method {
+			STORED if compressed_size == size => compressed.to_vec(),
+			STORED => return Err(invalid_data("invalid zip entry size")),
+			DEFLATED => inflate(compressed, size)?,
+			other => return Err(
+				invalid_data(format!("unsupported zip compression method ({})", other))
+			),
+		};
+
+		if content.len() != size || crc32::checksum(&content) != checksum {
+			return Err(invalid_data("zip entry checksum mismatch"));
+		}
+
+		let mode = if host == UNIX_HOST { attributes >> 16 } else { 0 };
+		let is_dir = name.ends_with(b"/");
+
+		let kind = match mode & TYPE_MASK {
+			TYPE_SYMLINK => Kind::Symlink(content.clone()),
+			TYPE_DIR => Kind::Dir,
+			_ if is_dir => Kind::Dir,
+			_ => Kind::File,
+		};
+
+		let name = name
+			.strip_suffix(b"/")
+			.map(<[u8]>::to_vec)
+			.unwrap_or(name);
+
+		extract_entry(dest, &name, &kind, mode & 0o7777, &content)?;
+		names.push(name);
+	}
+
+	Ok(names)
+}
+
+
+/// Find the end of central directory record, which is followed by a variable length
+/// comment.
+fn find_end(data: &[u8]) -> io::Result<usize> {
+	let last = data
+		.len()
+		.checked_sub(END_SIZE)
+		.ok_or_else(|| invalid_data("invalid zip archive"))?;
+
+	let first = last.saturating_sub(u16::MAX as usize);
+
+	(first ..= last)
+		.rev()
+		.find(|&pos| u32_at(data, pos).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+		.ok_or_else(|| invalid_data("invalid zip archive"))
+}
+
+
+/// Convert a unix timestamp to the MS-DOS time and date format, in UTC. Dates before 1980
+/// are clamped, as they can't be represented.
+fn dos_time(timestamp: u64) -> (u16, u16) {
+	let days = (timestamp / 86400) as i64;
+	let seconds = timestamp % 86400;
+
+	// Convert days since the epoch to a civil date.
+	let days = days + 719468;
+	let era = days / 146097;
+	let day_of_era = days - era * 146097;
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let month_index = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+	let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+	let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+	if year < 1980 {
+		return (0, (1 << 5) | 1);
+	}
+
+	let time = ((seconds / 3600) << 11) | ((seconds % 3600 / 60) << 5) | ((seconds % 60) / 2);
+	let date = (((year - 1980).min(127) as u64) << 9) | ((month as u64) << 5) | day as u64;
+
+	(time as u16, date as u16)
+}
+
+
+fn slice(data: &[u8], start: usize, length: usize) -> io::Result<&[u8]> {
+	data
+		.get(start .. start.saturating_add(length))
+		.ok_or_else(|| invalid_data("truncated zip archive"))
+}
+
+
+fn u16_at(data: &[u8], pos: usize) -> io::Result<u16> {
+	let bytes = slice(data, pos, 2)?;
+	Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+
+fn u32_at(data: &[u8], pos: usize) -> io::Result<u32> {
+	let bytes = slice(data, pos, 4)?;
+	Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
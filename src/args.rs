@@ -1,27 +1,58 @@
-use std::{ffi::{OsStr, OsString}, os::unix::ffi::OsStrExt, path::{Path, PathBuf}};
+use std::{
+	ffi::{OsStr, OsString},
+	os::unix::ffi::OsStrExt,
+	path::{Path, PathBuf},
+	time::Duration,
+};
 
 use clap::{AppSettings, clap_app, crate_authors, crate_description, crate_version};
 
+use crate::{fmt::ErrorFormat, runtime::watchdog::Limits, term::color};
+
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Command {
 	Help(Box<str>),
 	Version(Box<str>),
-	Run(Args)
+	Run(Args),
+	/// Serve the Debug Adapter Protocol (`dap` subcommand).
+	Dap,
 }
 
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct Args {
 	pub script_path: Option<PathBuf>,
 	/// Check program with static analysis, but don't run.
 	pub check: bool,
+	/// Type check annotated code, reporting warnings.
+	pub typecheck: bool,
 	/// Print the lexemes.
 	pub print_lexemes: bool,
 	/// Print the AST.
 	pub print_ast: bool,
 	/// Print the program.
 	pub print_program: bool,
+	/// Load and store the compiled program cache.
+	pub cache: bool,
+	/// Output format of errors and panics.
+	pub error_format: ErrorFormat,
+	/// When to color the interpreter's output.
+	pub color: color::Mode,
+	/// Resource limits for the interpreter.
+	pub limits: Limits,
+	/// Maximum amount of simultaneously running child processes.
+	pub max_children: Option<usize>,
+	/// Report file descriptors leaked by command blocks.
+	pub debug_fds: bool,
+	/// Panic when reading variables declared without a value.
+	pub strict_nil: bool,
+	/// Run the script to print completions for its arguments (hidden `__complete` entry).
+	pub complete: bool,
+	/// Run the test cases registered by the script (`test` subcommand).
+	pub test: bool,
+	/// Project directory to check recursively (`check` subcommand, or `--check <dir>`).
+	pub project: Option<PathBuf>,
 	/// Arguments for the script.
 	pub script_args: Box<[Box<[u8]>]>
 }
@@ -39,9 +70,23 @@ where
 				(author: crate_authors!())
 				(about: crate_description!())
 				(@arg check: --check "Perform only static analysis instead of executing.")
+				(@arg typecheck: --typecheck "Type check annotated code, reporting warnings.")
 				(@arg lex: --lex "Print the lexemes")
 				(@arg ast: --ast "Print the AST")
 				(@arg program: --program "Print the PROGAM")
+				(@arg cache: --cache "Cache the compiled program, reusing it while the script is unchanged.")
+				(@arg debug_fds: --("debug-fds") "Report file descriptors leaked by command blocks.")
+				(@arg strict_nil: --("strict-nil") "Panic when reading variables declared without a value.")
+				(@arg error_format: --("error-format") +takes_value possible_values(&["human", "json"])
+					"Output format of errors and panics.")
+				(@arg color: --color +takes_value possible_values(&["auto", "always", "never"])
+					"When to color the output. Auto disables colors when NO_COLOR is set.")
+				(@arg max_runtime: --("max-runtime") +takes_value {validate_runtime}
+					"Abort after the given number of seconds.")
+				(@arg max_memory: --("max-memory") +takes_value {validate_memory}
+					"Abort when memory usage exceeds the given size, in bytes. Accepts K, M and G suffixes.")
+				(@arg max_children: --("max-children") +takes_value {validate_children}
+					"Limit the number of simultaneously running child processes. Spawning waits for a free slot.")
 				// The script path must not be a separate parameter because we must prevent clap
 				// from parsing flags to the right of the script path.
 				(@arg arguments: ... +allow_hyphen_values "Script and/or arguments")
@@ -56,14 +101,53 @@ where
 				.flatten()
 				.map(OsStrExt::as_bytes);
 
+			// The completion entry point is hidden, as it is only meant to be called by the
+			// shell glue generated by std.complete.generate.
+			let mut first = arguments.next();
+			let complete = first == Some(b"__complete");
+			if complete {
+				first = arguments.next();
+			}
+
+			// The dap subcommand serves the Debug Adapter Protocol, where the script is given by
+			// the launch request. A script named `dap` in the current directory takes precedence.
+			if !complete && first == Some(b"dap") && !Path::new("dap").is_file() {
+				return Ok(Command::Dap);
+			}
+
+			// The test subcommand runs the cases registered with std.test after the script.
+			// A script named `test` in the current directory takes precedence.
+			let test = !complete
+				&& first == Some(b"test")
+				&& !Path::new("test").is_file();
+			if test {
+				first = arguments.next();
+			}
+
+			// The check subcommand is equivalent to --check. Likewise, a script named `check`
+			// in the current directory takes precedence.
+			let check_command = !complete
+				&& !test
+				&& first == Some(b"check")
+				&& !Path::new("check").is_file();
+			if check_command {
+				first = arguments.next();
+			}
+
+			let check = check_command || matches.is_present("check");
+
 			let mut script_args = Vec::new();
-			let script_path = match arguments.next() {
+			let mut project = None;
+			let script_path = match first {
 				None => None,
 				Some(b"-") => None,
 				Some(arg) => {
 					let path = Path::new(OsStr::from_bytes(arg));
 					if path.is_file() {
 						Some(path.to_owned())
+					} else if check && path.is_dir() {
+						project = Some(path.to_owned());
+						None
 					} else {
 						script_args.push(arg.into());
 						None
@@ -77,10 +161,31 @@ where
 				Command::Run(
 					Args {
 						script_path,
-						check: matches.is_present("check"),
+						check,
+						typecheck: matches.is_present("typecheck"),
 						print_lexemes: matches.is_present("lex"),
 						print_ast: matches.is_present("ast"),
 						print_program: matches.is_present("program"),
+						cache: matches.is_present("cache"),
+						error_format: match matches.value_of("error_format") {
+							Some("json") => ErrorFormat::Json,
+							_ => ErrorFormat::Human,
+						},
+						color: match matches.value_of("color") {
+							Some("always") => color::Mode::Always,
+							Some("never") => color::Mode::Never,
+							_ => color::Mode::Auto,
+						},
+						limits: Limits {
+							max_runtime: matches.value_of("max_runtime").and_then(parse_runtime),
+							max_memory: matches.value_of("max_memory").and_then(parse_memory),
+						},
+						max_children: matches.value_of("max_children").and_then(parse_children),
+						debug_fds: matches.is_present("debug_fds"),
+						strict_nil: matches.is_present("strict_nil"),
+						complete,
+						test,
+						project,
 						script_args: script_args.into_boxed_slice(),
 					}
 				)
@@ -98,3 +203,61 @@ where
 		}
 	}
 }
+
+
+/// Parse a duration in seconds, which may be fractional.
+fn parse_runtime(value: &str) -> Option<Duration> {
+	let seconds: f64 = value.parse().ok()?;
+
+	if seconds.is_finite() && seconds > 0.0 {
+		Some(Duration::from_secs_f64(seconds))
+	} else {
+		None
+	}
+}
+
+
+fn validate_runtime(value: String) -> Result<(), String> {
+	parse_runtime(&value)
+		.map(|_| ())
+		.ok_or_else(|| format!("invalid runtime '{}', expected positive seconds", value))
+}
+
+
+fn parse_children(value: &str) -> Option<usize> {
+	value
+		.parse()
+		.ok()
+		.filter(|&max| max > 0)
+}
+
+
+fn validate_children(value: String) -> Result<(), String> {
+	parse_children(&value)
+		.map(|_| ())
+		.ok_or_else(|| format!("invalid number of children '{}', expected a positive integer", value))
+}
+
+
+/// Parse a size in bytes, with an optional binary K, M or G suffix.
+fn parse_memory(value: &str) -> Option<u64> {
+	let (number, multiplier) = match value.char_indices().last()? {
+		(ix, 'k' | 'K') => (&value[..ix], 1 << 10),
+		(ix, 'm' | 'M') => (&value[..ix], 1 << 20),
+		(ix, 'g' | 'G') => (&value[..ix], 1 << 30),
+		_ => (value, 1),
+	};
+
+	let number: u64 = number.parse().ok()?;
+
+	number
+		.checked_mul(multiplier)
+		.filter(|&bytes| bytes > 0)
+}
+
+
+fn validate_memory(value: String) -> Result<(), String> {
+	parse_memory(&value)
+		.map(|_| ())
+		.ok_or_else(|| format!("invalid memory size '{}', expected positive bytes", value))
+}
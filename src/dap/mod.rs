@@ -0,0 +1,889 @@
+//! Debug Adapter Protocol server, with `hush dap`.
+//!
+//! The adapter talks to the editor over the standard input and output, and runs the single
+//! script given by the launch request, with the interpreter's standard input redirected
+//! from `/dev/null`. The standard output and error of the script and of its commands are
+//! forwarded to the editor as output events.
+//!
+//! Supported requests are initialize, launch, setBreakpoints, configurationDone, threads,
+//! stackTrace, scopes, variables, continue, next, stepIn, stepOut, pause and disconnect.
+//! Scripts have a single thread, and each frame has a single scope with its variables.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::{HashMap, HashSet},
+	ffi::OsStr,
+	fs::{self, File, OpenOptions},
+	io::{self, BufRead, BufReader, Read, Write},
+	os::unix::{
+		ffi::OsStrExt,
+		io::{AsRawFd, FromRawFd, RawFd},
+	},
+	path::PathBuf,
+	rc::Rc,
+	sync::{
+		atomic::{AtomicI64, Ordering},
+		mpsc,
+		Arc,
+		Mutex,
+	},
+	thread,
+	time::Duration,
+};
+
+use serde_json::{json, Value as Json};
+
+use crate::{
+	fmt,
+	runtime::{
+		debug::{self, Code, Debugger},
+		value::Value,
+		SourcePos,
+	},
+	symbol::{self, Symbol},
+};
+
+
+/// The id of the script's only thread.
+const THREAD_ID: i64 = 1;
+
+/// How long to wait for the output of the script's background processes when it finishes.
+const OUTPUT_TIMEOUT: Duration = Duration::from_secs(1);
+
+
+/// The launch configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Launch {
+	pub program: PathBuf,
+	pub args: Box<[Box<[u8]>]>,
+	/// Pause before the first statement.
+	pub stop_on_entry: bool,
+}
+
+
+/// Read a message, framed by a Content-Length header. Returns None at the end of input.
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Json>> {
+	let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+	let mut length = None;
+	let mut line = String::new();
+
+	loop {
+		line.clear();
+
+		if input.read_line(&mut line)? == 0 {
+			return if length.is_none() {
+				Ok(None)
+			} else {
+				Err(io::ErrorKind::UnexpectedEof.into())
+			};
+		}
+
+		let header = line.trim_end_matches(&['\r', '\n'][..]);
+
+		if header.is_empty() {
+			break;
+		}
+
+		if let Some(value) = header.strip_prefix("Content-Length:") {
+			let value = value
+				.trim()
+				.parse()
+				.map_err(|_| invalid("invalid content length"))?;
+
+			length = Some(value);
+		}
+	}
+
+	let length = length.ok_or_else(|| invalid("missing content length"))?;
+	let mut content = vec![0; length];
+	input.read_exact(&mut content)?;
+
+	serde_json::from_slice(&content)
+		.map(Some)
+		.map_err(|error| invalid(&error.to_string()))
+}
+
+
+/// Write a message, framed by a Content-Length header.
+fn write_message<W: Write>(output: &mut W, message: &Json) -> io::Result<()> {
+	let content = message.to_string();
+	write!(output, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+	output.flush()
+}
+
+
+/// The sending half of the connection, shared with the threads that forward the output.
+struct Sender {
+	output: Mutex<Box<dyn Write + Send>>,
+	seq: AtomicI64,
+}
+
+
+impl std::fmt::Debug for Sender {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("Sender")
+			.field("seq", &self.seq)
+			.finish_non_exhaustive()
+	}
+}
+
+
+impl Sender {
+	fn new(output: Box<dyn Write + Send>) -> Self {
+		Self { output: Mutex::new(output), seq: AtomicI64::new(1) }
+	}
+
+
+	/// Send a message, numbering it. Errors are ignored, as the editor is gone if writing
+	/// fails, which is noticed when reading.
+	fn send(&self, mut message: Json) {
+		let mut output = self.output
+			.lock()
+			.expect("poisoned connection");
+
+		message["seq"] = self.seq.fetch_add(1, Ordering::SeqCst).into();
+
+		let _ = write_message(&mut *output, &message);
+	}
+
+
+	fn event(&self, event: &str, body: Json) {
+		self.send(json!({ "type": "event", "event": event, "body": body }));
+	}
+
+
+	fn respond(&self, request: &Json, body: Json) {
+		self.send(
+			json!({
+				"type": "response",
+				"request_seq": request["seq"],
+				"command": request["command"],
+				"success": true,
+				"body": body,
+			})
+		);
+	}
+
+
+	fn fail(&self, request: &Json, message: &str) {
+		self.send(
+			json!({
+				"type": "response",
+				"request_seq": request["seq"],
+				"command": request["command"],
+				"success": false,
+				"message": message,
+			})
+		);
+	}
+}
+
+
+/// The connection to the editor, which outlives the adapter attached to the runtime.
+#[derive(Debug)]
+pub struct Session {
+	sender: Arc<Sender>,
+	requests: Rc<mpsc::Receiver<Json>>,
+	/// Receives a message as each output forwarding thread finishes.
+	forwarded: mpsc::Receiver<()>,
+	forwarders: usize,
+}
+
+
+/// Take over the standard streams, and start reading requests.
+pub fn start() -> io::Result<Session> {
+	let input = dup(0)?;
+	let output = dup(1)?;
+
+	let null = OpenOptions::new().read(true).write(true).open("/dev/null")?;
+	dup2(null.as_raw_fd(), 0)?;
+
+	let (forwarded_sender, forwarded) = mpsc::channel();
+	let sender = Arc::new(Sender::new(Box::new(output)));
+
+	for &(fd, category) in &[(1, "stdout"), (2, "stderr")] {
+		let (reader, writer) = os_pipe::pipe()?;
+		dup2(writer.as_raw_fd(), fd)?;
+
+		let sender = sender.clone();
+		let forwarded = forwarded_sender.clone();
+		thread::spawn(
+			move || {
+				forward(reader, category, &sender);
+				let _ = forwarded.send(());
+			}
+		);
+	}
+
+	let (requests_sender, requests) = mpsc::channel();
+	thread::spawn(
+		move || {
+			let mut input = BufReader::new(input);
+
+			// The channel is closed when the editor closes the connection.
+			while let Ok(Some(request)) = read_message(&mut input) {
+				if requests_sender.send(request).is_err() {
+					break;
+				}
+			}
+		}
+	);
+
+	Ok(
+		Session {
+			sender,
+			requests: Rc::new(requests),
+			forwarded,
+			forwarders: 2,
+		}
+	)
+}
+
+
+/// Forward the output of a pipe as output events.
+fn forward<R: Read>(mut reader: R, category: &str, sender: &Sender) {
+	let mut buffer = [0; 4096];
+
+	loop {
+		match reader.read(&mut buffer) {
+			Ok(0) => break,
+			Ok(size) => sender.event(
+				"output",
+				json!({
+					"category": category,
+					"output": String::from_utf8_lossy(&buffer[..size]),
+				})
+			),
+			Err(error) if error.kind() == io::ErrorKind::Interrupted => (),
+			Err(_) => break,
+		}
+	}
+}
+
+
+/// Duplicate a file descriptor, closing the copy on exec.
+fn dup(fd: RawFd) -> io::Result<File> {
+	// SAFETY: fcntl is safe to call with any arguments.
+	let copy = unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 3) };
+
+	if copy < 0 {
+		Err(io::Error::last_os_error())
+	} else {
+		// SAFETY: the descriptor was just created, and is owned by the file.
+		Ok(unsafe { File::from_raw_fd(copy) })
+	}
+}
+
+
+fn dup2(fd: RawFd, target: RawFd) -> io::Result<()> {
+	// SAFETY: dup2 is safe to call with any arguments.
+	if unsafe { libc::dup2(fd, target) } < 0 {
+		Err(io::Error::last_os_error())
+	} else {
+		Ok(())
+	}
+}
+
+
+impl Session {
+	/// Handle the requests until the script is launched and configured, returning the
+	/// launch configuration and the adapter to attach to the runtime. Returns None if the
+	/// editor disconnects before.
+	pub fn launch(&self) -> Option<(Launch, Adapter)> {
+		let mut adapter = Adapter::new(self.sender.clone(), self.requests.clone());
+		let mut launch = None;
+		let mut configured = false;
+		// No script has been loaded yet.
+		let interner = symbol::Interner::new();
+
+		while launch.is_none() || !configured {
+			let request = self.requests.recv().ok()?;
+
+			match adapter.handle(&request, None, &interner) {
+				Handled::Launch(config) => launch = Some(config),
+				Handled::Configured => configured = true,
+				Handled::Disconnect => return None,
+				Handled::Done | Handled::Resume => (),
+			}
+		}
+
+		let launch = launch?;
+
+		if launch.stop_on_entry {
+			adapter.step = Step::Pause("entry");
+		}
+
+		Some((launch, adapter))
+	}
+
+
+	/// Report that the script has finished with the given exit status, and wait for the
+	/// editor to disconnect.
+	pub fn finish(self, status: i32) {
+		let _ = io::stdout().flush();
+		let _ = io::stderr().flush();
+
+		// Close the pipes, so that the forwarding threads finish once the script's
+		// background processes close them too.
+		if let Ok(null) = OpenOptions::new().write(true).open("/dev/null") {
+			let _ = dup2(null.as_raw_fd(), 1);
+			let _ = dup2(null.as_raw_fd(), 2);
+		}
+
+		for _ in 0 .. self.forwarders {
+			if self.forwarded.recv_timeout(OUTPUT_TIMEOUT).is_err() {
+				break;
+			}
+		}
+
+		self.sender.event("exited", json!({ "exitCode": status }));
+		self.sender.event("terminated", json!({}));
+
+		while let Ok(request) = self.requests.recv() {
+			match request["command"].as_str() {
+				Some("disconnect") | Some("terminate") => {
+					self.sender.respond(&request, json!({}));
+					break;
+				}
+
+				Some("threads") => self.sender.respond(&request, json!({ "threads": [] })),
+
+				_ => self.sender.fail(&request, "the script has finished"),
+			}
+		}
+	}
+}
+
+
+/// How to proceed after a pause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+	/// Run until a breakpoint.
+	Continue,
+	/// Pause before the next statement, for the given reason.
+	Pause(&'static str),
+	/// Pause on another line, or in another call.
+	In(Location),
+	/// Pause on another line of the same call, or after it returns.
+	Over(Location),
+	/// Pause after the call returns.
+	Out(Location),
+}
+
+
+/// The location of a statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Location {
+	path: Symbol,
+	line: u32,
+	/// The number of active function calls.
+	depth: usize,
+}
+
+
+/// The result of handling a request.
+#[derive(Debug)]
+enum Handled {
+	Done,
+	/// Resume the paused script.
+	Resume,
+	Launch(Launch),
+	Configured,
+	Disconnect,
+}
+
+
+/// The state of the paused script.
+#[derive(Debug)]
+struct Paused {
+	frames: Vec<debug::Frame>,
+	/// Values whose children may be requested. Variable references up to the amount of
+	/// frames refer to the variables of the frames, and the following ones to these values.
+	values: Vec<Value>,
+}
+
+
+impl Paused {
+	fn new(frames: Vec<debug::Frame>) -> Self {
+		Self { frames, values: Vec::new() }
+	}
+
+
+	/// A variable of the protocol, with a reference for the children of collections.
+	fn variable(&mut self, name: String, value: Value, interner: &symbol::Interner) -> Json {
+		let (display, children) = match &value {
+			Value::String(string) => (format!("{:?}", String::from_utf8_lossy(string.as_bytes())), false),
+			Value::Array(array) => (format!("array[{}]", array.len()), !array.is_empty()),
+			Value::Dict(dict) if dict.secret().is_some() => (fmt::Show(&value, interner).to_string(), false),
+			Value::Dict(dict) => (format!("dict[{}]", dict.len()), !dict.is_empty()),
+			Value::Error(error) => (format!("error: {}", error.description), true),
+			_ => (fmt::Show(&value, interner).to_string(), false),
+		};
+
+		let reference = if children {
+			self.values.push(value.copy());
+			self.frames.len() + self.values.len()
+		} else {
+			0
+		};
+
+		json!({
+			"name": name,
+			"value": display,
+			"type": value.get_type().to_string(),
+			"variablesReference": reference,
+		})
+	}
+
+
+	/// The variables of a frame or the children of a value, given by reference.
+	fn variables(&mut self, reference: usize, interner: &symbol::Interner) -> Option<Json> {
+		let children: Vec<(String, Value)> = match reference.checked_sub(1) {
+			None => return None,
+
+			Some(ix) if ix < self.frames.len() => self.frames[ix].variables
+				.iter()
+				.map(
+					|(name, value)| {
+						let name = match name {
+							// Captured variables are named after the original ones.
+							Some(name) => String::from_utf8_lossy(interner.resolve(*name)?)
+								.trim_end_matches("@closed")
+								.to_owned(),
+							None => "self".to_owned(),
+						};
+
+						Some((name, value.copy()))
+					}
+				)
+				.collect::<Option<_>>()?,
+
+			Some(ix) => match self.values.get(ix - self.frames.len())? {
+				Value::Array(array) => array
+					.borrow()
+					.iter()
+					.enumerate()
+					.map(|(ix, value)| (ix.to_string(), value.copy()))
+					.collect(),
+
+				Value::Dict(dict) => dict
+					.borrow()
+					.iter()
+					.map(|(key, value)| (fmt::Show(key, interner).to_string(), value.copy()))
+					.collect(),
+
+				Value::Error(error) => vec![
+					("description".to_owned(), error.description.copy().into()),
+					("context".to_owned(), error.context.borrow().copy()),
+				],
+
+				_ => return None,
+			},
+		};
+
+		let variables: Vec<Json> = children
+			.into_iter()
+			.map(|(name, value)| self.variable(name, value, interner))
+			.collect();
+
+		Some(json!({ "variables": variables }))
+	}
+
+
+	/// The frames of the protocol.
+	fn stack_trace(&self, arguments: &Json, interner: &symbol::Interner) -> Json {
+		let start = arguments["startFrame"].as_u64().unwrap_or(0) as usize;
+		let levels = match arguments["levels"].as_u64() {
+			Some(levels) if levels > 0 => levels as usize,
+			_ => self.frames.len(),
+		};
+
+		let resolve = |symbol| interner
+			.resolve(symbol)
+			.map(|name| String::from_utf8_lossy(name).into_owned())
+			.unwrap_or_default();
+
+		let frames: Vec<Json> = self.frames
+			.iter()
+			.enumerate()
+			.skip(start)
+			.take(levels)
+			.map(
+				|(ix, frame)| {
+					let name = match frame.code {
+						Code::Script(path) => resolve(path),
+						Code::Function(Some(name)) => resolve(name),
+						Code::Function(None) => "<anonymous>".to_owned(),
+						Code::Builtin(name) => name.to_owned(),
+					};
+
+					let mut json = json!({ "id": ix + 1, "name": name, "line": 0, "column": 0 });
+
+					match &frame.pos {
+						Some(pos) => {
+							json["line"] = pos.line.into();
+							// Columns are counted from zero in the interpreter, and from one in the protocol.
+							json["column"] = (pos.column + 1).into();
+							json["source"] = source(pos.path, interner);
+						}
+
+						None => json["presentationHint"] = "subtle".into(),
+					}
+
+					json
+				}
+			)
+			.collect();
+
+		json!({ "stackFrames": frames, "totalFrames": self.frames.len() })
+	}
+}
+
+
+/// The source of the protocol for a script path.
+fn source(path: Symbol, interner: &symbol::Interner) -> Json {
+	let path = interner
+		.resolve(path)
+		.map(|path| PathBuf::from(OsStr::from_bytes(path)))
+		.unwrap_or_default();
+
+	let path = fs::canonicalize(&path).unwrap_or(path);
+
+	json!({
+		"name": path.file_name().map(|name| name.to_string_lossy()),
+		"path": path.to_string_lossy(),
+	})
+}
+
+
+/// The debugger attached to the runtime.
+#[derive(Debug)]
+pub struct Adapter {
+	sender: Arc<Sender>,
+	requests: Rc<mpsc::Receiver<Json>>,
+	/// Lines with breakpoints, by canonical path.
+	breakpoints: HashMap<PathBuf, HashSet<u32>>,
+	/// Canonical paths of the scripts, if they exist.
+	paths: HashMap<Symbol, Option<PathBuf>>,
+	step: Step,
+	/// The location of the statement checked last.
+	location: Option<Location>,
+	/// Why the script is about to pause.
+	reason: &'static str,
+	/// Whether the editor has closed the connection, in which case the script runs freely.
+	detached: bool,
+}
+
+
+impl Adapter {
+	fn new(sender: Arc<Sender>, requests: Rc<mpsc::Receiver<Json>>) -> Self {
+		Self {
+			sender,
+			requests,
+			breakpoints: HashMap::new(),
+			paths: HashMap::new(),
+			step: Step::Continue,
+			location: None,
+			reason: "step",
+			detached: false,
+		}
+	}
+
+
+	/// Handle a request. Requests which inspect the script fail unless it is paused.
+	fn handle(
+		&mut self,
+		request: &Json,
+		paused: Option<&mut Paused>,
+		interner: &symbol::Interner,
+	) -> Handled {
+		let arguments = &request["arguments"];
+		let command = request["command"].as_str().unwrap_or_default();
+
+		match (command, paused) {
+			("initialize", _) => {
+				self.sender.respond(request, json!({ "supportsConfigurationDoneRequest": true }));
+				self.sender.event("initialized", json!({}));
+			}
+
+			("launch", _) => match launch(arguments) {
+				Ok(launch) => {
+					self.sender.respond(request, json!({}));
+					return Handled::Launch(launch);
+				}
+
+				Err(message) => self.sender.fail(request, &message),
+			},
+
+			("configurationDone", _) => {
+				self.sender.respond(request, json!({}));
+				return Handled::Configured;
+			}
+
+			("setBreakpoints", _) => {
+				let lines: HashSet<u32> = arguments["breakpoints"]
+					.as_array()
+					.into_iter()
+					.flatten()
+					.filter_map(|breakpoint| breakpoint["line"].as_u64())
+					.map(|line| line as u32)
+					.collect();
+
+				let breakpoints: Vec<Json> = lines
+					.iter()
+					.map(|line| json!({ "verified": true, "line": line }))
+					.collect();
+
+				let path = PathBuf::from(arguments["source"]["path"].as_str().unwrap_or_default());
+				let path = fs::canonicalize(&path).unwrap_or(path);
+
+				if lines.is_empty() {
+					self.breakpoints.remove(&path);
+				} else {
+					self.breakpoints.insert(path, lines);
+				}
+
+				self.sender.respond(request, json!({ "breakpoints": breakpoints }));
+			}
+
+			("setExceptionBreakpoints", _) => self.sender.respond(request, json!({})),
+
+			("threads", _) => self.sender.respond(
+				request,
+				json!({ "threads": [ { "id": THREAD_ID, "name": "main" } ] })
+			),
+
+			("disconnect", _) | ("terminate", _) => {
+				self.sender.respond(request, json!({}));
+				return Handled::Disconnect;
+			}
+
+			("pause", Some(_)) => self.sender.respond(request, json!({})),
+
+			("pause", None) => {
+				self.step = Step::Pause("pause");
+				self.sender.respond(request, json!({}));
+			}
+
+			("continue", Some(_)) => {
+				self.step = Step::Continue;
+				self.sender.respond(request, json!({ "allThreadsContinued": true }));
+				return Handled::Resume;
+			}
+
+			(command @ ("next" | "stepIn" | "stepOut"), Some(_)) => {
+				let location = self.location.expect("paused without a location");
+
+				self.step = match command {
+					"next" => Step::Over(location),
+					"stepIn" => Step::In(location),
+					_ => Step::Out(location),
+				};
+
+				self.sender.respond(request, json!({}));
+				return Handled::Resume;
+			}
+
+			("stackTrace", Some(paused)) => self.sender.respond(
+				request,
+				paused.stack_trace(arguments, interner)
+			),
+
+			("scopes", Some(paused)) => {
+				let frame = arguments["frameId"]
+					.as_u64()
+					.and_then(|id| paused.frames.get((id as usize).checked_sub(1)?));
+
+				match frame {
+					Some(frame) => {
+						let scopes = match frame.code {
+							Code::Builtin(_) => json!([]),
+							_ => json!([
+								{
+									"name": "Locals",
+									"variablesReference": arguments["frameId"],
+									"expensive": false,
+								}
+							]),
+						};
+
+						self.sender.respond(request, json!({ "scopes": scopes }));
+					}
+
+					None => self.sender.fail(request, "invalid frame"),
+				}
+			}
+
+			("variables", Some(paused)) => {
+				let reference = arguments["variablesReference"].as_u64().unwrap_or(0);
+
+				match paused.variables(reference as usize, interner) {
+					Some(variables) => self.sender.respond(request, variables),
+					None => self.sender.fail(request, "invalid variables reference"),
+				}
+			}
+
+			(
+				"continue" | "next" | "stepIn" | "stepOut" | "stackTrace" | "scopes" | "variables",
+				None
+			) => self.sender.fail(request, "the script is not paused"),
+
+			(command, _) => self.sender.fail(request, &format!("unsupported request: {}", command)),
+		}
+
+		Handled::Done
+	}
+
+
+	/// Whether there is a breakpoint at the given position.
+	fn is_breakpoint(&mut self, pos: &SourcePos, interner: &symbol::Interner) -> bool {
+		if self.breakpoints.is_empty() {
+			return false;
+		}
+
+		let breakpoints = &self.breakpoints;
+		let path = self.paths
+			.entry(pos.path)
+			.or_insert_with(
+				|| interner
+					.resolve(pos.path)
+					.and_then(|path| fs::canonicalize(OsStr::from_bytes(path)).ok())
+			);
+
+		path
+			.as_ref()
+			.and_then(|path| breakpoints.get(path))
+			.is_some_and(|lines| lines.contains(&pos.line))
+	}
+
+
+	/// Receive a request while paused, detaching if the editor has closed the connection.
+	fn receive(&mut self) -> Option<Json> {
+		match self.requests.recv() {
+			Ok(request) => Some(request),
+			Err(_) => {
+				self.detached = true;
+				None
+			}
+		}
+	}
+}
+
+
+impl Debugger for Adapter {
+	fn check(&mut self, pos: &SourcePos, depth: usize, interner: &symbol::Interner) -> bool {
+		if self.detached {
+			return false;
+		}
+
+		// Handle the requests received while running.
+		loop {
+			match self.requests.try_recv() {
+				Ok(request) => {
+					if let Handled::Disconnect = self.handle(&request, None, interner) {
+						std::process::exit(0);
+					}
+				}
+
+				Err(mpsc::TryRecvError::Empty) => break,
+
+				Err(mpsc::TryRecvError::Disconnected) => {
+					self.detached = true;
+					return false;
+				}
+			}
+		}
+
+		let location = Location { path: pos.path, line: pos.line, depth };
+		let previous = self.location.replace(location);
+
+		let step = self.step;
+		let reason = match step {
+			Step::Pause(reason) => Some(reason),
+
+			Step::In(from) if location != from => Some("step"),
+
+			Step::Over(from) if depth < from.depth
+				|| (depth == from.depth && (location.path, location.line) != (from.path, from.line))
+				=> Some("step"),
+
+			Step::Out(from) if depth < from.depth => Some("step"),
+
+			// Pause once for each line with a breakpoint.
+			_ if previous != Some(location) && self.is_breakpoint(pos, interner) => Some("breakpoint"),
+
+			_ => None,
+		};
+
+		match reason {
+			Some(reason) => {
+				self.reason = reason;
+				true
+			}
+
+			None => false,
+		}
+	}
+
+
+	fn pause(&mut self, frames: Vec<debug::Frame>, interner: &symbol::Interner) {
+		self.step = Step::Continue;
+
+		self.sender.event(
+			"stopped",
+			json!({ "reason": self.reason, "threadId": THREAD_ID, "allThreadsStopped": true })
+		);
+
+		let mut paused = Paused::new(frames);
+
+		while let Some(request) = self.receive() {
+			match self.handle(&request, Some(&mut paused), interner) {
+				Handled::Resume => break,
+				Handled::Disconnect => std::process::exit(0),
+				Handled::Done | Handled::Launch(_) | Handled::Configured => (),
+			}
+		}
+	}
+}
+
+
+/// Parse the arguments of the launch request.
+fn launch(arguments: &Json) -> Result<Launch, String> {
+	let program = arguments["program"]
+		.as_str()
+		.ok_or("missing program path")?;
+
+	if let Some(cwd) = arguments["cwd"].as_str() {
+		std::env::set_current_dir(cwd)
+			.map_err(|error| format!("invalid working directory: {}", error))?;
+	}
+
+	let program = PathBuf::from(program);
+
+	if !program.is_file() {
+		return Err(format!("program not found: {}", program.display()));
+	}
+
+	let args = arguments["args"]
+		.as_array()
+		.into_iter()
+		.flatten()
+		.map(
+			|arg| arg
+				.as_str()
+				.map(|arg| arg.as_bytes().into())
+				.ok_or("invalid program argument")
+		)
+		.collect::<Result<_, _>>()?;
+
+	Ok(
+		Launch {
+			program,
+			args,
+			stop_on_entry: arguments["stopOnEntry"].as_bool().unwrap_or(false),
+		}
+	)
+}
@@ -0,0 +1,209 @@
+use std::{
+	env,
+	io::Cursor,
+	os::unix::ffi::OsStrExt,
+	process,
+};
+
+use serial_test::serial;
+
+use crate::{fmt, runtime::Runtime, semantic, syntax};
+use super::*;
+
+
+#[test]
+fn test_messages() -> io::Result<()> {
+	let message = json!({ "seq": 1, "type": "request", "command": "threads" });
+
+	let mut data = Vec::new();
+	write_message(&mut data, &message)?;
+	write_message(&mut data, &message)?;
+	assert!(data.starts_with(b"Content-Length: 46\r\n\r\n{"));
+
+	let mut input = Cursor::new(data);
+	assert_eq!(read_message(&mut input)?, Some(message.clone()));
+	assert_eq!(read_message(&mut input)?, Some(message));
+	assert_eq!(read_message(&mut input)?, None);
+
+	let mut input = Cursor::new(b"Content-Type: json\r\n\r\n{}".to_vec());
+	assert_eq!(read_message(&mut input).map_err(|error| error.kind()), Err(io::ErrorKind::InvalidData));
+
+	let mut input = Cursor::new(b"Content-Length: 10\r\n\r\n{}".to_vec());
+	assert_eq!(read_message(&mut input).map_err(|error| error.kind()), Err(io::ErrorKind::UnexpectedEof));
+
+	Ok(())
+}
+
+
+/// The editor side of a session.
+struct Editor {
+	input: BufReader<os_pipe::PipeReader>,
+	requests: mpsc::Sender<Json>,
+	seq: i64,
+}
+
+
+impl Editor {
+	/// Send a request, returning the body of its successful response.
+	fn request(&mut self, command: &str, arguments: Json) -> Json {
+		self.seq += 1;
+		let seq = self.seq;
+
+		self.requests
+			.send(json!({ "seq": seq, "type": "request", "command": command, "arguments": arguments }))
+			.expect("adapter is gone");
+
+		let response = self.receive(|message| message["request_seq"] == seq);
+		assert_eq!(response["success"], true, "{}", response);
+
+		response["body"].clone()
+	}
+
+
+	/// Wait for the stopped event, returning the reason.
+	fn stopped(&mut self) -> Json {
+		self.receive(|message| message["event"] == "stopped")["body"]["reason"].clone()
+	}
+
+
+	fn receive<F: Fn(&Json) -> bool>(&mut self, predicate: F) -> Json {
+		loop {
+			let message = read_message(&mut self.input)
+				.expect("invalid message")
+				.expect("connection closed");
+
+			if predicate(&message) {
+				return message;
+			}
+		}
+	}
+
+
+	/// The names and values of the variables with the given reference.
+	fn variables(&mut self, reference: &Json) -> Vec<(String, String, Json)> {
+		self.request("variables", json!({ "variablesReference": reference }))["variables"]
+			.as_array()
+			.expect("missing variables")
+			.iter()
+			.map(
+				|variable| (
+					variable["name"].as_str().unwrap_or_default().to_owned(),
+					variable["value"].as_str().unwrap_or_default().to_owned(),
+					variable["variablesReference"].clone(),
+				)
+			)
+			.collect()
+	}
+}
+
+
+/// The names and values of variables, without their references.
+fn values(variables: &[(String, String, Json)]) -> Vec<(&str, &str)> {
+	variables
+		.iter()
+		.map(|(name, value, _)| (name.as_str(), value.as_str()))
+		.collect()
+}
+
+
+#[test]
+#[serial]
+fn test_session() -> io::Result<()> {
+	let dir = env::temp_dir().join(format!("hush-dap-{}", process::id()));
+	fs::create_dir_all(&dir)?;
+	let path = fs::canonicalize(&dir)?.join("script.hsh");
+
+	fs::write(
+		&path,
+		"let x = 1\n\
+		function f(a)\n\
+			let b = a * 2\n\
+			b\n\
+		end\n\
+		let y = f(x)\n\
+		let z = [ y, @[ key: \"value\" ] ]\n\
+		std.assert(std.len(z) == 2)\n"
+	)?;
+
+	let (reader, writer) = os_pipe::pipe()?;
+	let (requests_sender, requests) = mpsc::channel();
+	let mut adapter = Adapter::new(Arc::new(Sender::new(Box::new(writer))), Rc::new(requests));
+
+	let mut editor = Editor { input: BufReader::new(reader), requests: requests_sender, seq: 0 };
+
+	let breakpoints = json!({
+		"source": { "path": path.to_string_lossy() },
+		"breakpoints": [ { "line": 3 } ],
+	});
+	let request = json!({ "seq": 0, "command": "setBreakpoints", "arguments": breakpoints });
+	adapter.handle(&request, None, &symbol::Interner::new());
+
+	let script = path.to_string_lossy().into_owned();
+	let driver = thread::spawn(
+		move || {
+			assert_eq!(editor.stopped(), "breakpoint");
+
+			let trace = editor.request("stackTrace", json!({ "threadId": THREAD_ID }));
+			assert_eq!(trace["totalFrames"], 2);
+			assert_eq!(trace["stackFrames"][0]["name"], "f");
+			assert_eq!(trace["stackFrames"][0]["line"], 3);
+			assert_eq!(trace["stackFrames"][0]["source"]["path"], script.as_str());
+			assert_eq!(trace["stackFrames"][1]["name"], script.as_str());
+			assert_eq!(trace["stackFrames"][1]["line"], 6);
+
+			let scopes = editor.request("scopes", json!({ "frameId": 1 }));
+			let locals = scopes["scopes"][0]["variablesReference"].clone();
+			assert_eq!(values(&editor.variables(&locals)), [("a", "1"), ("b", "nil")]);
+
+			editor.request("next", json!({ "threadId": THREAD_ID }));
+			assert_eq!(editor.stopped(), "step");
+			assert_eq!(values(&editor.variables(&locals)), [("a", "1"), ("b", "2")]);
+
+			editor.request("stepOut", json!({ "threadId": THREAD_ID }));
+			assert_eq!(editor.stopped(), "step");
+			let trace = editor.request("stackTrace", json!({ "threadId": THREAD_ID }));
+			assert_eq!(trace["totalFrames"], 1);
+			assert_eq!(trace["stackFrames"][0]["line"], 7);
+
+			editor.request("next", json!({ "threadId": THREAD_ID }));
+			assert_eq!(editor.stopped(), "step");
+
+			let variables = editor.variables(&json!(1));
+			let names: Vec<&str> = variables.iter().map(|(name, _, _)| name.as_str()).collect();
+			assert_eq!(names, ["std", "x", "f", "y", "z"]);
+			assert_eq!(values(&variables)[3 ..], [("y", "2"), ("z", "array[2]")]);
+
+			let items = editor.variables(&variables[4].2);
+			assert_eq!(values(&items), [("0", "2"), ("1", "dict[1]")]);
+			assert_eq!(values(&editor.variables(&items[1].2)), [("\"key\"", "\"value\"")]);
+
+			editor.request("continue", json!({ "threadId": THREAD_ID }));
+		}
+	);
+
+	let mut runtime = Runtime::new(std::iter::empty::<&str>(), symbol::Interner::new());
+	let path_symbol = runtime.interner_mut().get_or_intern(path.as_os_str().as_bytes());
+	let source = syntax::Source::from_path(path_symbol, runtime.interner_mut())?;
+
+	let analysis = syntax::Analysis::analyze(&source, runtime.interner_mut());
+	assert!(analysis.errors.is_empty());
+
+	let program = semantic::Analyzer::analyze(analysis.ast, runtime.interner_mut())
+		.unwrap_or_else(|_| panic!("semantic errors"));
+	let program = Box::leak(Box::new(program));
+
+	runtime.set_debugger(Box::new(adapter));
+	let result = runtime.eval(program);
+
+	if let Err(panic) = result {
+		panic!("{}", fmt::Show(panic, runtime.interner()));
+	}
+
+	// Close the connection, in case the driver is still waiting.
+	drop(runtime);
+	fs::remove_dir_all(&dir)?;
+
+	driver
+		.join()
+		.map_err(|_| io::Error::other("driver failed"))
+}
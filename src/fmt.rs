@@ -1,5 +1,8 @@
 use std::fmt::Write;
 
+use crate::symbol::{self, Symbol};
+
+
 /// A Display-like trait that takes an additional context when formatting.
 /// This is needed to have access to the string interner when formating the AST or error
 /// messages.
@@ -125,3 +128,84 @@ where
 
 	Ok(())
 }
+
+
+/// The output format of error messages.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorFormat {
+	/// Colored messages, meant for humans.
+	#[default]
+	Human,
+	/// One JSON object per error, meant for machine consumption.
+	Json,
+}
+
+
+/// An adapter to format an error as a single line JSON object.
+#[derive(Debug)]
+pub struct Json<T>(pub T);
+
+
+/// Write a single line JSON object describing an error, with the given position (path,
+/// line and column), kind and message. The message is formatted without colors.
+pub fn json_error<M>(
+	f: &mut std::fmt::Formatter,
+	interner: &symbol::Interner,
+	pos: Option<(Symbol, u32, u32)>,
+	kind: &str,
+	message: M,
+) -> std::fmt::Result
+where
+	M: std::fmt::Display,
+{
+	write!(f, "{}", json_object(interner, pos, kind, message))
+}
+
+
+/// Write a single line JSON object describing a warning, like `json_error`, with an
+/// additional severity field so that warnings may be told apart from errors.
+pub fn json_warning<M>(
+	f: &mut std::fmt::Formatter,
+	interner: &symbol::Interner,
+	pos: Option<(Symbol, u32, u32)>,
+	kind: &str,
+	message: M,
+) -> std::fmt::Result
+where
+	M: std::fmt::Display,
+{
+	let mut object = json_object(interner, pos, kind, message);
+	object["severity"] = "warning".into();
+
+	write!(f, "{}", object)
+}
+
+
+fn json_object<M>(
+	interner: &symbol::Interner,
+	pos: Option<(Symbol, u32, u32)>,
+	kind: &str,
+	message: M,
+) -> serde_json::Value
+where
+	M: std::fmt::Display,
+{
+	let message = crate::term::color::disabled(|| message.to_string());
+
+	let (file, line, column) = match pos {
+		Some((path, line, column)) => (
+			Some(String::from_utf8_lossy(interner.resolve(path).unwrap_or_default())),
+			Some(line),
+			Some(column),
+		),
+		None => (None, None, None),
+	};
+
+	serde_json::json!({
+		"file": file,
+		"line": line,
+		"column": column,
+		"kind": kind,
+		"message": message,
+	})
+}
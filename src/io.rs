@@ -4,6 +4,12 @@ use std::os::unix::prelude::{AsRawFd, RawFd};
 pub type FileDescriptor = RawFd;
 
 
+/// Get the file descriptor for stdin.
+pub fn stdin_fd() -> FileDescriptor {
+	std::io::stdin().as_raw_fd()
+}
+
+
 /// Get the file descriptor for stdout.
 pub fn stdout_fd() -> FileDescriptor {
 	std::io::stdout().as_raw_fd()
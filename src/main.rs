@@ -1,17 +1,25 @@
 #![allow(dead_code)] // This is temporarily used for the inital development.
 
+mod archive;
 mod args;
+mod dap;
 mod fmt;
 mod io;
+mod project;
 mod runtime;
 mod semantic;
 mod symbol;
 mod syntax;
 mod term;
+mod typecheck;
 #[cfg(test)]
 mod tests;
 
-use std::os::unix::ffi::OsStrExt;
+use std::{
+	io::Write,
+	os::unix::ffi::OsStrExt,
+	path::Path,
+};
 
 use term::color;
 
@@ -24,6 +32,7 @@ enum ExitStatus {
 	Success,
 	InvalidArgs,
 	StaticError,
+	TestsFailed,
 	Panic,
 }
 
@@ -34,6 +43,7 @@ impl From<ExitStatus> for i32 {
 			ExitStatus::Success => 0,
 			ExitStatus::InvalidArgs => 1,
 			ExitStatus::StaticError => 2,
+			ExitStatus::TestsFailed => 3,
 			ExitStatus::Panic => 127,
 		}
 	}
@@ -50,7 +60,8 @@ fn main() -> ! {
 	};
 
 	let exit_status = match command {
-		Command::Run(args) => run(args),
+		Command::Run(args) => run(args, None),
+		Command::Dap => serve_dap(),
 		Command::Help(msg) | Command::Version(msg) => {
 			println!("{}", msg);
 			ExitStatus::Success
@@ -61,10 +72,16 @@ fn main() -> ! {
 }
 
 
-fn run(args: Args) -> ExitStatus {
+fn run(args: Args, debugger: Option<Box<dyn runtime::debug::Debugger>>) -> ExitStatus {
+	color::set_mode(args.color);
+
+	if let Some(dir) = &args.project {
+		return check_project(dir, &args);
+	}
+
 	let mut interner = symbol::Interner::new();
 
-	let (source, path) = match args.script_path {
+	let (source, path) = match &args.script_path {
 		Some(path) => {
 			let path = interner.get_or_intern(path.as_os_str().as_bytes());
 			let source = syntax::Source::from_path(path, &mut interner);
@@ -81,27 +98,236 @@ fn run(args: Args) -> ExitStatus {
 	let source = match source {
     Ok(source) => source,
     Err(error) => {
-			eprintln!(
-				"{}",
-				fmt::Show(
-					Panic::io(error, SourcePos::file(path)),
-					&interner
-				)
-			);
+			print_panic(&Panic::io(error, SourcePos::file(path)), args.error_format, &interner);
+			return ExitStatus::Panic;
+		}
+	};
+
+	// ----------------------------------------------------------------------------------------
+	// The cache is opt-in, and only used when running scripts, as the other flags require
+	// the full static analysis.
+	let use_cache = args.cache
+		&& args.script_path.is_some()
+		&& !args.check
+		&& !args.typecheck
+		&& !args.print_lexemes
+		&& !args.print_ast;
+
+	let cached = if use_cache {
+		semantic::program::cache::load(&source, &mut interner)
+	} else {
+		None
+	};
+
+	let program = match cached {
+		Some((program, warnings)) => {
+			// Warnings are reported on every run, not only when the cache is created.
+			print_warnings(&mut std::io::stderr(), &warnings, &args, &interner);
+
+			if args.print_program {
+				print_program(&program, &interner);
+			}
+
+			program
+		}
+
+		None => {
+			let analysis = analyze(&source, &args, &mut interner, &mut std::io::stderr());
+
+			let (program, warnings) = match analysis {
+				Ok(analysis) => analysis,
+				Err(status) => return status,
+			};
+
+			if use_cache {
+				// Failing to store the cache must not prevent the script from running.
+				let _ = semantic::program::cache::store(&source, &program, &warnings, &interner);
+			}
+
+			program
+		}
+	};
+
+	if args.check {
+		return ExitStatus::Success;
+	}
+
+	if let Err(error) = runtime::signal::install() {
+		print_panic(&Panic::io(error, SourcePos::file(path)), args.error_format, &interner);
+		return ExitStatus::Panic;
+	}
+
+	if let Err(error) = runtime::watchdog::start(args.limits) {
+		print_panic(&Panic::io(error, SourcePos::file(path)), args.error_format, &interner);
+		return ExitStatus::Panic;
+	}
+
+	runtime::children::set_limit(
+		args.max_children.map(|max| runtime::children::Limit { max, wait: true })
+	);
+	runtime::fds::set_debug(args.debug_fds);
+
+	let program = Box::leak(Box::new(program));
+	let mut runtime = Runtime::new(
+		args.script_args.into_vec(), // Use vec's owned iterator.
+		interner
+	);
+	runtime.set_error_format(args.error_format);
+	runtime.set_completing(args.complete);
+	runtime.set_strict_nil(args.strict_nil);
+
+	if let Some(debugger) = debugger {
+		runtime.set_debugger(debugger);
+	}
+
+	let test = args.test;
+	let result = runtime
+		.eval(program)
+		.and_then(|_| if test { runtime.run_tests(SourcePos::file(path)) } else { Ok(true) });
+
+	let (status, mut panic) = match result {
+    Ok(true) => (ExitStatus::Success, None),
+    Ok(false) => (ExitStatus::TestsFailed, None),
+    Err(panic) => {
+			print_panic(&panic, args.error_format, runtime.interner());
+			(ExitStatus::Panic, Some(panic))
+		}
+	};
+
+	// Exit handlers run even if the script panicked, so that it may clean up.
+	let status = match runtime.run_exit_handlers(SourcePos::file(path)) {
+		Ok(()) => status,
+		Err(handler_panic) => {
+			print_panic(&handler_panic, args.error_format, runtime.interner());
+			panic.get_or_insert(handler_panic);
+			ExitStatus::Panic
+		}
+	};
+
+	// The panic hook reports the first panic, after the script has cleaned up.
+	if let Some(panic) = panic {
+		if let Err(hook_panic) = runtime.run_panic_hook(&panic) {
+			print_panic(&hook_panic, args.error_format, runtime.interner());
+		}
+	}
+
+	status
+}
+
+
+/// Serve the Debug Adapter Protocol, running the launched script with the adapter attached.
+fn serve_dap() -> ExitStatus {
+	let session = match dap::start() {
+		Ok(session) => session,
+		Err(error) => {
+			eprintln!("{}: {}", color::Fg(color::Red, "Error"), error);
+			return ExitStatus::Panic;
+		}
+	};
+
+	let (launch, adapter) = match session.launch() {
+		Some(launch) => launch,
+		None => return ExitStatus::Success,
+	};
+
+	let args = Args {
+		script_path: Some(launch.program),
+		script_args: launch.args,
+		..Args::default()
+	};
+
+	let status = run(args, Some(Box::new(adapter)));
+	session.finish(status.into());
+
+	ExitStatus::Success
+}
+
+
+/// Check all scripts in a project directory, reporting errors grouped by file.
+fn check_project(dir: &Path, args: &Args) -> ExitStatus {
+	let manifest = match project::Manifest::load(dir) {
+		Ok(manifest) => manifest,
+		Err(error) => {
+			eprintln!("{}: {}", color::Fg(color::Red, "Error"), error);
+			return ExitStatus::InvalidArgs;
+		}
+	};
+
+	let files = match manifest.files(dir) {
+		Ok(files) => files,
+		Err(error) => {
+			eprintln!("{}: {}", color::Fg(color::Red, "Error"), error);
 			return ExitStatus::Panic;
 		}
 	};
 
+	let args = Args {
+		typecheck: args.typecheck || manifest.typecheck,
+		print_lexemes: false,
+		print_ast: false,
+		print_program: false,
+		..args.clone()
+	};
+
+	let mut interner = symbol::Interner::new();
+	let mut failed = 0;
+
+	for file in &files {
+		let path = interner.get_or_intern(file.as_os_str().as_bytes());
+		let mut output = Vec::new();
+
+		let ok = match syntax::Source::from_path(path, &mut interner) {
+			Ok(source) => analyze(&source, &args, &mut interner, &mut output).is_ok(),
+			Err(error) => {
+				print_panic(&Panic::io(error, SourcePos::file(path)), args.error_format, &interner);
+				false
+			}
+		};
+
+		if !ok {
+			failed += 1;
+		}
+
+		if !output.is_empty() {
+			if args.error_format == fmt::ErrorFormat::Human {
+				eprintln!("{}", color::Fg(color::Yellow, format!("==> {}", file.display())));
+			}
+
+			let _ = std::io::stderr().write_all(&output);
+		}
+	}
+
+	if args.error_format == fmt::ErrorFormat::Human {
+		eprintln!("checked {} files: {} with errors", files.len(), failed);
+	}
+
+	if failed == 0 {
+		ExitStatus::Success
+	} else {
+		ExitStatus::StaticError
+	}
+}
+
+
+/// Perform the static analysis of the source, reporting errors and warnings to the given
+/// output. The semantic warnings are returned along with the program, for caching.
+fn analyze<W: Write>(
+	source: &syntax::Source,
+	args: &Args,
+	interner: &mut symbol::Interner,
+	errors: &mut W,
+) -> Result<(semantic::program::Program, semantic::Warnings), ExitStatus> {
 	// ----------------------------------------------------------------------------------------
-	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+	let syntactic_analysis = syntax::Analysis::analyze(source, interner);
 	let has_syntax_errors = !syntactic_analysis.is_ok();
 
 	if has_syntax_errors {
-		eprint!("{}", fmt::Show(
+		let _ = write!(errors, "{}", fmt::Show(
 			syntactic_analysis.errors,
 			syntax::AnalysisDisplayContext {
 				max_errors: Some(20),
-				interner: &interner,
+				interner,
+				format: args.error_format,
 			}
 		));
 	}
@@ -109,12 +335,12 @@ fn run(args: Args) -> ExitStatus {
 	if args.print_lexemes {
 		println!("{}", color::Fg(color::Yellow, "--------------------------------------------------"));
 
-		let cursor = syntax::lexer::Cursor::from(&source);
-		let results: Vec<_> = syntax::lexer::Lexer::new(cursor, &mut interner).collect();
+		let cursor = syntax::lexer::Cursor::from(source);
+		let results: Vec<_> = syntax::lexer::Lexer::new(cursor, interner).collect();
 
 		for result in results {
 			match result {
-				Ok(token) => println!("{}", fmt::Show(&token, &interner)),
+				Ok(token) => println!("{}", fmt::Show(&token, &*interner)),
 				Err(error) => println!("{}: {}", color::Fg(color::Red, "Error"), error)
 			}
 		}
@@ -128,59 +354,91 @@ fn run(args: Args) -> ExitStatus {
 			"{}",
 			fmt::Show(
 				&syntactic_analysis.ast,
-				syntax::ast::fmt::Context::from(&interner)
+				syntax::ast::fmt::Context::from(&*interner)
 			)
 		);
 		println!("{}", color::Fg(color::Yellow, "--------------------------------------------------"));
 	}
 
 	// ----------------------------------------------------------------------------------------
-	let program = match semantic::Analyzer::analyze(syntactic_analysis.ast, &mut interner) {
+	if args.typecheck && !has_syntax_errors {
+		let warnings = typecheck::Checker::check(&syntactic_analysis.ast, &*interner);
+
+		let _ = write!(errors, "{}", fmt::Show(
+			warnings,
+			typecheck::WarningsDisplayContext {
+				max_warnings: Some(20),
+				interner,
+			}
+		));
+	}
+
+	// ----------------------------------------------------------------------------------------
+	let (result, warnings) = semantic::Analyzer::analyze_with_warnings(syntactic_analysis.ast, interner);
+
+	print_warnings(errors, &warnings, args, interner);
+
+	let program = match result {
 		Ok(program) => program,
-		Err(errors) => {
-			eprint!("{}", fmt::Show(
-				errors,
+		Err(analysis_errors) => {
+			let _ = write!(errors, "{}", fmt::Show(
+				analysis_errors,
 				semantic::ErrorsDisplayContext {
 					max_errors: Some(20),
-					interner: &interner,
+					interner,
+					format: args.error_format,
 				}
 			));
-			return ExitStatus::StaticError;
+			return Err(ExitStatus::StaticError);
 		}
 	};
 
 	if args.print_program {
-		println!("{}", color::Fg(color::Yellow, "--------------------------------------------------"));
-		println!(
-			"{}",
-			fmt::Show(
-				&program,
-				semantic::program::fmt::Context::from(&interner)
-			)
-		);
-		println!("{}", color::Fg(color::Yellow, "--------------------------------------------------"));
+		print_program(&program, &*interner);
 	}
 
 	// ----------------------------------------------------------------------------------------
 	if has_syntax_errors {
-		return ExitStatus::StaticError;
+		return Err(ExitStatus::StaticError);
 	}
 
-	if args.check {
-		return ExitStatus::Success;
-	}
+	Ok((program, warnings))
+}
 
-	let program = Box::leak(Box::new(program));
-	let mut runtime = Runtime::new(
-		args.script_args.into_vec(), // Use vec's owned iterator.
-		interner
-	);
 
-	match runtime.eval(program) {
-    Ok(_) => ExitStatus::Success,
-    Err(panic) => {
-			eprintln!("{}", fmt::Show(panic, runtime.interner()));
-			ExitStatus::Panic
+fn print_warnings<W: Write>(
+	output: &mut W,
+	warnings: &semantic::Warnings,
+	args: &Args,
+	interner: &symbol::Interner,
+) {
+	let _ = write!(output, "{}", fmt::Show(
+		warnings,
+		semantic::WarningsDisplayContext {
+			max_warnings: Some(20),
+			interner,
+			format: args.error_format,
 		}
+	));
+}
+
+
+fn print_panic(panic: &Panic, format: fmt::ErrorFormat, interner: &symbol::Interner) {
+	match format {
+		fmt::ErrorFormat::Human => eprintln!("{}", fmt::Show(panic, interner)),
+		fmt::ErrorFormat::Json => eprintln!("{}", fmt::Show(fmt::Json(panic), interner)),
 	}
 }
+
+
+fn print_program(program: &semantic::program::Program, interner: &symbol::Interner) {
+	println!("{}", color::Fg(color::Yellow, "--------------------------------------------------"));
+	println!(
+		"{}",
+		fmt::Show(
+			program,
+			semantic::program::fmt::Context::from(interner)
+		)
+	);
+	println!("{}", color::Fg(color::Yellow, "--------------------------------------------------"));
+}
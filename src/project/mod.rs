@@ -0,0 +1,207 @@
+//! Project level checking, with `hush check <dir>`.
+//!
+//! A project is a directory, optionally containing a `hush.toml` manifest. The manifest
+//! supports a small subset of toml:
+//!
+//! ```toml
+//! roots = ["scripts", "lib"]   # Directories to check, relative to the project.
+//! exclude = ["lib/vendor"]     # Paths to skip, relative to the project.
+//!
+//! [lint]
+//! typecheck = true             # Type check annotated code, reporting warnings.
+//! ```
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+	fs,
+	io,
+	path::{Path, PathBuf},
+};
+
+
+/// The name of the manifest file.
+pub const MANIFEST: &str = "hush.toml";
+
+
+/// The project manifest.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Manifest {
+	/// Directories to be checked, relative to the project.
+	pub roots: Vec<PathBuf>,
+	/// Paths to be skipped, relative to the project.
+	pub exclude: Vec<PathBuf>,
+	/// Type check annotated code.
+	pub typecheck: bool,
+}
+
+
+impl Default for Manifest {
+	fn default() -> Self {
+		Self {
+			roots: vec![PathBuf::from(".")],
+			exclude: Vec::new(),
+			typecheck: false,
+		}
+	}
+}
+
+
+impl Manifest {
+	/// Load the manifest of the given project directory, if any.
+	pub fn load(dir: &Path) -> Result<Self, String> {
+		let path = dir.join(MANIFEST);
+
+		match fs::read_to_string(&path) {
+			Ok(text) => Self::parse(&text)
+				.map_err(|error| format!("{}: {}", path.display(), error)),
+			Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+			Err(error) => Err(format!("{}: {}", path.display(), error)),
+		}
+	}
+
+
+	/// Parse the manifest contents.
+	pub fn parse(text: &str) -> Result<Self, String> {
+		let mut manifest = Self::default();
+		let mut section = String::new();
+
+		for (ix, line) in text.lines().enumerate() {
+			let error = |message: &str| format!("line {}: {}", ix + 1, message);
+
+			let line = strip_comment(line).trim();
+			if line.is_empty() {
+				continue;
+			}
+
+			if let Some(name) = line.strip_prefix('[') {
+				section = name
+					.strip_suffix(']')
+					.ok_or_else(|| error("unterminated section"))?
+					.trim()
+					.to_owned();
+
+				if section != "lint" {
+					return Err(error(&format!("unknown section '{}'", section)));
+				}
+
+				continue;
+			}
+
+			let (key, value) = line
+				.split_once('=')
+				.ok_or_else(|| error("expected key = value"))?;
+			let (key, value) = (key.trim(), value.trim());
+
+			match (section.as_str(), key) {
+				("", "roots") => manifest.roots = paths(value).map_err(|e| error(&e))?,
+				("", "exclude") => manifest.exclude = paths(value).map_err(|e| error(&e))?,
+				("lint", "typecheck") => manifest.typecheck = boolean(value).map_err(|e| error(&e))?,
+				_ => return Err(error(&format!("unknown key '{}'", key))),
+			}
+		}
+
+		Ok(manifest)
+	}
+
+
+	/// Collect the script files of the project, sorted by path.
+	pub fn files(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+		let exclude: Vec<PathBuf> = self.exclude
+			.iter()
+			.map(|path| normalize(&dir.join(path)))
+			.collect();
+
+		let mut files = Vec::new();
+
+		for root in &self.roots {
+			collect(&normalize(&dir.join(root)), &exclude, &mut files)?;
+		}
+
+		files.sort();
+		files.dedup();
+
+		Ok(files)
+	}
+}
+
+
+/// Recursively collect the `.hsh` files in the given directory. Hidden entries are skipped.
+fn collect(dir: &Path, exclude: &[PathBuf], files: &mut Vec<PathBuf>) -> io::Result<()> {
+	for entry in fs::read_dir(dir)? {
+		let path = normalize(&entry?.path());
+
+		let hidden = path
+			.file_name()
+			.is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+		if hidden || exclude.iter().any(|excluded| path.starts_with(excluded)) {
+			continue;
+		}
+
+		if path.is_dir() {
+			collect(&path, exclude, files)?;
+		} else if path.extension().is_some_and(|ext| ext == "hsh") {
+			files.push(path);
+		}
+	}
+
+	Ok(())
+}
+
+
+/// Remove `.` components, so that paths may be compared.
+fn normalize(path: &Path) -> PathBuf {
+	path
+		.components()
+		.filter(|component| !matches!(component, std::path::Component::CurDir))
+		.collect()
+}
+
+
+/// Strip a comment, which may not be inside a string.
+fn strip_comment(line: &str) -> &str {
+	let mut in_string = false;
+
+	for (ix, c) in line.char_indices() {
+		match c {
+			'"' => in_string = !in_string,
+			'#' if !in_string => return &line[..ix],
+			_ => (),
+		}
+	}
+
+	line
+}
+
+
+fn boolean(value: &str) -> Result<bool, String> {
+	match value {
+		"true" => Ok(true),
+		"false" => Ok(false),
+		_ => Err(format!("expected boolean, found '{}'", value)),
+	}
+}
+
+
+/// Parse a single line array of strings.
+fn paths(value: &str) -> Result<Vec<PathBuf>, String> {
+	let items = value
+		.strip_prefix('[')
+		.and_then(|value| value.strip_suffix(']'))
+		.ok_or_else(|| format!("expected array, found '{}'", value))?;
+
+	items
+		.split(',')
+		.map(str::trim)
+		.filter(|item| !item.is_empty())
+		.map(
+			|item| item
+				.strip_prefix('"')
+				.and_then(|item| item.strip_suffix('"'))
+				.map(PathBuf::from)
+				.ok_or_else(|| format!("expected string, found '{}'", item))
+		)
+		.collect()
+}
@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+
+use super::*;
+
+
+fn data_dir() -> PathBuf {
+	let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+	dir.push("src/project/tests/data/project");
+	dir
+}
+
+
+#[test]
+fn test_parse() {
+	let manifest = Manifest::parse(
+		"roots = [\"a\", \"b#c\"] # comment\n\
+		\n\
+		[lint]\n\
+		typecheck = true\n"
+	);
+
+	assert_eq!(
+		manifest,
+		Ok(
+			Manifest {
+				roots: vec!["a".into(), "b#c".into()],
+				exclude: Vec::new(),
+				typecheck: true,
+			}
+		)
+	);
+
+	assert_eq!(Manifest::parse(""), Ok(Manifest::default()));
+}
+
+
+#[test]
+fn test_parse_errors() {
+	assert!(Manifest::parse("roots = \"a\"").is_err());
+	assert!(Manifest::parse("typecheck = true").is_err());
+	assert!(Manifest::parse("[lint]\ntypecheck = yes").is_err());
+	assert!(Manifest::parse("[other]").is_err());
+	assert!(Manifest::parse("roots").is_err());
+}
+
+
+#[test]
+fn test_files() {
+	let dir = data_dir();
+	let manifest = Manifest::load(&dir).expect("failed to load manifest");
+
+	assert!(manifest.typecheck);
+
+	let files = manifest.files(&dir).expect("failed to collect files");
+
+	assert_eq!(
+		files,
+		vec![
+			dir.join("scripts/a.hsh"),
+			dir.join("scripts/nested/b.hsh"),
+		]
+	);
+}
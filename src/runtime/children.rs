@@ -0,0 +1,109 @@
+//! Limit on the amount of simultaneously running child processes.
+//!
+//! Every process spawned by the interpreter, including the ones spawned by asynchronous
+//! command blocks, must reserve a slot before spawning. Slots are released once the
+//! process has been waited. When no slots are available, spawning either waits until one
+//! is released, or fails.
+
+use std::{
+	io,
+	sync::{Condvar, Mutex, MutexGuard},
+	time::Duration,
+};
+
+use super::signal;
+
+
+/// Interval between signal checks while waiting for a slot.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+static STATE: Mutex<State> = Mutex::new(State { limit: None, running: 0 });
+/// Notified when slots are released.
+static RELEASED: Condvar = Condvar::new();
+
+
+/// A limit on running child processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limit {
+	/// Maximum amount of running processes.
+	pub max: usize,
+	/// Whether to wait for a slot when the limit is reached, instead of failing.
+	pub wait: bool,
+}
+
+
+#[derive(Debug)]
+struct State {
+	limit: Option<Limit>,
+	running: usize,
+}
+
+
+/// Set the limit, or remove it if None.
+pub fn set_limit(limit: Option<Limit>) {
+	lock().limit = limit;
+	RELEASED.notify_all();
+}
+
+
+/// Reserve slots for the given amount of processes, which are spawned together and
+/// therefore must be reserved atomically, as in pipelines.
+pub fn acquire(count: usize) -> io::Result<Slots> {
+	let mut state = lock();
+
+	while let Some(limit) = state.limit {
+		if count > limit.max {
+			return Err(
+				io::Error::other(
+					format!("command needs {} processes, exceeding the limit of {}", count, limit.max)
+				)
+			);
+		}
+
+		if state.running + count <= limit.max {
+			break;
+		}
+
+		if !limit.wait {
+			return Err(io::Error::other(format!("too many running processes (limit is {})", limit.max)));
+		}
+
+		if signal::pending().is_some() {
+			return Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"));
+		}
+
+		if signal::abandoned() {
+			return Err(io::Error::new(io::ErrorKind::TimedOut, "wait for process slot abandoned"));
+		}
+
+		state = RELEASED
+			.wait_timeout(state, POLL_INTERVAL)
+			.unwrap_or_else(|error| error.into_inner())
+			.0;
+	}
+
+	state.running += count;
+
+	Ok(Slots(count))
+}
+
+
+/// Reserved process slots, released on drop.
+#[derive(Debug)]
+pub struct Slots(usize);
+
+
+impl Drop for Slots {
+	fn drop(&mut self) {
+		lock().running -= self.0;
+		RELEASED.notify_all();
+	}
+}
+
+
+/// Lock the state, ignoring poisoning, as the state is always consistent.
+fn lock() -> MutexGuard<'static, State> {
+	STATE
+		.lock()
+		.unwrap_or_else(|error| error.into_inner())
+}
@@ -0,0 +1,123 @@
+use std::{
+	io::{self, Read},
+	os::unix::prelude::AsRawFd,
+	time::{Duration, Instant},
+};
+
+use crate::runtime::signal;
+
+
+/// Read timeout for capture blocks, see std.capture_timeout.
+#[derive(Debug, Clone, Copy)]
+pub struct CaptureTimeout {
+	/// Maximum time without any output.
+	pub inactivity: Duration,
+	/// Whether to leave the commands running when the timeout expires.
+	pub keep_running: bool,
+}
+
+
+/// The captured output of a block.
+#[derive(Debug, Default)]
+pub struct Captured {
+	pub stdout: Vec<u8>,
+	pub stderr: Vec<u8>,
+	/// Whether the read timeout expired, in which case the output is partial.
+	pub timed_out: bool,
+}
+
+
+/// Read both pipes until they are closed, or until there is no output for longer than the
+/// timeout. In the latter case, the waits for the block's commands are abandoned.
+pub fn read(
+	mut stdout: os_pipe::PipeReader,
+	mut stderr: os_pipe::PipeReader,
+	timeout: CaptureTimeout,
+	abandon: signal::Abandon,
+) -> io::Result<Captured> {
+	let mut captured = Captured::default();
+	let mut open = [true, true];
+	let mut deadline = Instant::now() + timeout.inactivity;
+	let mut buffer = [0; 8192];
+
+	while open[0] || open[1] {
+		let now = Instant::now();
+		if now >= deadline {
+			captured.timed_out = true;
+			abandon.set(!timeout.keep_running);
+			break;
+		}
+
+		// Negative file descriptors are ignored by poll.
+		let mut fds = [
+			libc::pollfd {
+				fd: if open[0] { stdout.as_raw_fd() } else { -1 },
+				events: libc::POLLIN,
+				revents: 0,
+			},
+			libc::pollfd {
+				fd: if open[1] { stderr.as_raw_fd() } else { -1 },
+				events: libc::POLLIN,
+				revents: 0,
+			},
+		];
+
+		let wait = (deadline - now).as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+
+		// SAFETY: the pollfd array is valid for the given length.
+		let ready = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, wait) };
+
+		if ready < 0 {
+			let error = io::Error::last_os_error();
+			if error.kind() == io::ErrorKind::Interrupted {
+				continue;
+			}
+			return Err(error);
+		}
+
+		for (ix, fd) in fds.iter().enumerate() {
+			if fd.fd < 0 || fd.revents == 0 {
+				continue;
+			}
+
+			let (reader, data): (&mut dyn Read, _) = match ix {
+				0 => (&mut stdout, &mut captured.stdout),
+				_ => (&mut stderr, &mut captured.stderr),
+			};
+
+			match reader.read(&mut buffer)? {
+				0 => open[ix] = false,
+				count => {
+					data.extend_from_slice(&buffer[..count]);
+					deadline = Instant::now() + timeout.inactivity;
+				}
+			}
+		}
+	}
+
+	Ok(captured)
+}
+
+
+/// Read both pipes until they are closed.
+pub fn read_all(
+	mut stdout: os_pipe::PipeReader,
+	mut stderr: os_pipe::PipeReader,
+) -> io::Result<Captured> {
+	let stderr_reader = std::thread::spawn(move || -> io::Result<Vec<u8>> {
+		let mut data = Vec::with_capacity(512);
+		stderr.read_to_end(&mut data)?;
+		Ok(data)
+	});
+
+	let mut captured = Captured::default();
+	captured.stdout.reserve(512);
+	stdout.read_to_end(&mut captured.stdout)?;
+
+	captured.stderr = match stderr_reader.join() {
+		Err(error) => std::panic::resume_unwind(error),
+		Ok(result) => result?,
+	};
+
+	Ok(captured)
+}
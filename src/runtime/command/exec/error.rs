@@ -1,6 +1,6 @@
 use std::{
 	io,
-	collections::HashMap, ffi::OsString,
+	ffi::OsString,
 };
 
 use crate::{
@@ -19,7 +19,7 @@ pub enum Panic {
 		items: u32,
 		pos: SourcePos,
 	},
-	/// Redirection of the given file descriptor is currently unsupported.
+	/// Redirection to a file descriptor which is not open.
 	UnsupportedFileDescriptor {
 		fd: FileDescriptor,
 		pos: SourcePos,
@@ -28,7 +28,12 @@ pub enum Panic {
 	InvalidPattern {
 		pattern: OsString,
 		pos: SourcePos,
-	}
+	},
+	/// Pattern matched no files, see std.glob_options.
+	NoMatches {
+		pattern: OsString,
+		pos: SourcePos,
+	},
 }
 
 
@@ -38,7 +43,7 @@ impl Panic {
 		Self::InvalidArgs { object, items, pos }
 	}
 
-	/// Redirection of the given file descriptor is currently unsupported.
+	/// Redirection to a file descriptor which is not open.
 	pub fn unsupported_fd(fd: FileDescriptor, pos: SourcePos) -> Self {
 		Self::UnsupportedFileDescriptor { fd, pos }
 	}
@@ -47,6 +52,11 @@ impl Panic {
 	pub fn invalid_pattern(pattern: OsString, pos: SourcePos) -> Self {
 		Self::InvalidPattern { pattern, pos }
 	}
+
+	/// Pattern matched no files, see std.glob_options.
+	pub fn no_matches(pattern: OsString, pos: SourcePos) -> Self {
+		Self::NoMatches { pattern, pos }
+	}
 }
 
 
@@ -68,7 +78,7 @@ impl std::fmt::Display for Panic {
 			Self::UnsupportedFileDescriptor { fd, .. } =>
 				write!(
 					f,
-					"{}: unsupported file descriptor ({})",
+					"{}: bad file descriptor ({})",
 					panic,
 					color::Fg(color::Yellow, fd)
 				),
@@ -80,6 +90,14 @@ impl std::fmt::Display for Panic {
 					panic,
 					color::Fg(color::Yellow, pattern)
 				),
+
+			Self::NoMatches { pattern, .. } =>
+				write!(
+					f,
+					"{}: pattern ({:?}) matched no files",
+					panic,
+					color::Fg(color::Yellow, pattern)
+				),
 		}
 	}
 }
@@ -96,6 +114,7 @@ impl From<Panic> for crate::runtime::Panic {
 			Panic::InvalidArgs { object, items, pos } => P::invalid_command_args(object, items, pos),
 			Panic::UnsupportedFileDescriptor { fd, pos } => P::unsupported_fd(fd, pos),
 			Panic::InvalidPattern { pattern, pos } => P::invalid_pattern(pattern, pos),
+			Panic::NoMatches { pattern, pos } => P::no_matches(pattern, pos),
 		}
 	}
 }
@@ -113,7 +132,7 @@ impl IntoValue for ErrorStatus {
 
 		let description = std::mem::take(&mut self.description).into();
 
-		let mut context = HashMap::new();
+		let mut context = value::OrderedMap::new();
 		STATUS.with(
 			|status| context.insert(status.copy(), Value::Int(self.status as i64))
 		);
@@ -133,6 +152,8 @@ pub enum Error {
 		pos: SourcePos,
 	},
 	Panic(Panic),
+	/// Execution was aborted by an exec hook.
+	Aborted,
 }
 
 
@@ -159,6 +180,12 @@ impl PipelineErrors {
 	pub fn is_empty(&self) -> bool {
 		self.0.is_empty()
 	}
+
+
+	/// The exit status of the last failed command, if any.
+	pub fn status(&self) -> Option<i32> {
+		self.0.last().map(|error| error.status)
+	}
 }
 
 impl IntoValue for PipelineErrors {
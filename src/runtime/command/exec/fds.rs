@@ -0,0 +1,109 @@
+//! Tracking of the file descriptors opened by command blocks.
+//!
+//! Pipes and redirected files are closed in the interpreter as soon as the processes
+//! using them have been spawned. When debugging is enabled, the open file descriptors are
+//! listed before and after each command block, and the ones left open are reported. The
+//! listing is process wide, so descriptors opened concurrently by asynchronous blocks may
+//! be reported as well.
+
+use std::{
+	collections::BTreeSet,
+	fs,
+	os::unix::io::RawFd,
+	path::PathBuf,
+	sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{fmt, symbol, term::color};
+use super::SourcePos;
+
+
+static DEBUG: AtomicBool = AtomicBool::new(false);
+
+
+/// Enable or disable reporting of file descriptors leaked by command blocks.
+pub fn set_debug(debug: bool) {
+	DEBUG.store(debug, Ordering::Relaxed);
+}
+
+
+/// The file descriptors open at some point.
+#[derive(Debug)]
+pub struct Snapshot(BTreeSet<RawFd>);
+
+
+impl Snapshot {
+	/// List the open file descriptors, if debugging is enabled.
+	pub fn take() -> Option<Self> {
+		if DEBUG.load(Ordering::Relaxed) {
+			Some(Self(open_fds()))
+		} else {
+			None
+		}
+	}
+
+
+	/// Report the file descriptors which have been opened since the snapshot, and are
+	/// still open.
+	pub fn report(self, pos: &SourcePos, interner: &symbol::Interner) {
+		let leaked: Vec<String> = open_fds()
+			.difference(&self.0)
+			.map(
+				|fd| match target(*fd) {
+					Some(target) => format!("{} ({})", fd, target.display()),
+					None => fd.to_string(),
+				}
+			)
+			.collect();
+
+		if !leaked.is_empty() {
+			eprintln!(
+				"{}: {} - command block leaked file descriptors: {}",
+				color::Fg(color::Yellow, "Warning"),
+				fmt::Show(pos, interner),
+				leaked.join(", "),
+			);
+		}
+	}
+}
+
+
+/// The directory listing the process' file descriptors.
+fn fd_dir() -> &'static str {
+	if cfg!(target_os = "linux") {
+		"/proc/self/fd"
+	} else {
+		"/dev/fd"
+	}
+}
+
+
+/// List the open file descriptors, excluding the one used for the listing itself.
+fn open_fds() -> BTreeSet<RawFd> {
+	let entries = match fs::read_dir(fd_dir()) {
+		Ok(entries) => entries,
+		Err(_) => return BTreeSet::new(),
+	};
+
+	entries
+		.filter_map(
+			|entry| entry
+				.ok()?
+				.file_name()
+				.to_str()?
+				.parse()
+				.ok()
+		)
+		.collect::<Vec<RawFd>>()
+		.into_iter()
+		// The listing's descriptor has been closed by now.
+		// SAFETY: F_GETFD only queries the descriptor's flags.
+		.filter(|&fd| unsafe { libc::fcntl(fd, libc::F_GETFD) } != -1)
+		.collect()
+}
+
+
+/// The file or pipe the descriptor refers to, if available.
+fn target(fd: RawFd) -> Option<PathBuf> {
+	fs::read_link(format!("{}/{}", fd_dir(), fd)).ok()
+}
@@ -3,9 +3,12 @@ use std::{
 	os::unix::ffi::OsStrExt,
 };
 
-use super::{Argument, RedirectionTarget, Redirection, Builtin, BasicCommand, Command, Block};
+use super::{
+	Argument, RedirectionTarget, Redirection, Builtin, BasicCommand, FunctionCommand, Stage, Command, Block
+};
 
 use crate::{
+	io,
 	syntax::lexer::CommandOperator,
 	fmt::{self, Indentation},
 	term::color,
@@ -53,12 +56,18 @@ impl Display for Redirection {
 				target.fmt(f)
 			}
 
-			Self::Input { literal: false, source } => {
+			Self::Input { literal: false, fd, source } => {
+				if *fd != io::stdin_fd() {
+					fd.fmt(f)?;
+				}
 				"<".fmt(f)?;
 				source.fmt(f)
 			}
 
-			Self::Input { literal: true, source } => {
+			Self::Input { literal: true, fd, source } => {
+				if *fd != io::stdin_fd() {
+					fd.fmt(f)?;
+				}
 				"<<".fmt(f)?;
 				source.fmt(f)
 			}
@@ -98,7 +107,7 @@ impl Display for BasicCommand {
 
 		if self.abort_on_error {
 			" ".fmt(f)?;
-			CommandOperator::Try.fmt(f)?;
+			CommandOperator::Try { attached: false }.fmt(f)?;
 		}
 
 		Ok(())
@@ -106,6 +115,30 @@ impl Display for BasicCommand {
 }
 
 
+impl Display for FunctionCommand {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		color::Fg(color::Green, "<function>").fmt(f)?;
+
+		if self.abort_on_error {
+			" ".fmt(f)?;
+			CommandOperator::Try { attached: false }.fmt(f)?;
+		}
+
+		Ok(())
+	}
+}
+
+
+impl Display for Stage {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Process(command) => command.fmt(f),
+			Self::Function(command) => command.fmt(f),
+		}
+	}
+}
+
+
 impl Display for Command {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		match self {
@@ -119,7 +152,7 @@ impl Display for Command {
 
 				if *abort_on_error {
 					" ".fmt(f)?;
-					CommandOperator::Try.fmt(f)?;
+					CommandOperator::Try { attached: false }.fmt(f)?;
 				}
 			}
 
@@ -0,0 +1,213 @@
+//! Process groups of pipelines.
+//!
+//! Each pipeline runs in its own process group, so that all of its processes may be
+//! signaled at once. Pipelines of blocks which are not asynchronous are placed in the
+//! terminal's foreground while running, if the interpreter is in it, so that they may read
+//! from the terminal and receive its signals. As the interpreter no longer receives them,
+//! a pipeline interrupted by SIGINT interrupts the interpreter as well, and a stopped
+//! pipeline stops the interpreter until it's continued.
+
+use std::{
+	io,
+	os::unix::{
+		io::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+		process::{CommandExt, ExitStatusExt},
+	},
+	process,
+	sync::atomic::{AtomicI32, AtomicU64, Ordering},
+};
+
+use crate::runtime::signal;
+
+
+/// The process group of a pipeline.
+#[derive(Debug)]
+pub struct Group {
+	/// The group id, which is the pid of the first spawned process, or zero if none has
+	/// been spawned yet.
+	pgid: AtomicI32,
+	/// Bit set of the signals which have been sent to the group.
+	signaled: AtomicU64,
+	/// The terminal whose foreground the group is placed in, if any. This is a duplicate of
+	/// the standard descriptor, which remains open in children after their standard
+	/// descriptors have been redirected.
+	terminal: Option<OwnedFd>,
+}
+
+
+impl Group {
+	pub fn new(foreground: bool) -> Self {
+		Self {
+			pgid: AtomicI32::new(0),
+			signaled: AtomicU64::new(0),
+			terminal: if foreground { foreground_terminal() } else { None },
+		}
+	}
+
+
+	/// Configure a command to be spawned in the group. The first process creates it, and
+	/// places it in the terminal's foreground if needed, before calling exec. Otherwise,
+	/// the program could read from the terminal before the parent hands it over.
+	pub fn configure(&self, command: &mut process::Command) {
+		let pgid = self.pgid.load(Ordering::SeqCst);
+
+		command.process_group(pgid);
+
+		if let (0, Some(terminal)) = (pgid, &self.terminal) {
+			let terminal = terminal.as_raw_fd();
+
+			// SAFETY: only async-signal-safe functions are called after forking. The process
+			// group has already been set when the closure runs.
+			unsafe {
+				command.pre_exec(
+					move || {
+						set_foreground(terminal, libc::getpgrp());
+						Ok(())
+					}
+				);
+			}
+		}
+	}
+
+
+	/// Record a spawned process, placing the group in the terminal's foreground if needed.
+	pub fn spawned(&self, pid: u32) {
+		let pid = pid as libc::pid_t;
+
+		let pgid = match self.pgid.compare_exchange(0, pid, Ordering::SeqCst, Ordering::SeqCst) {
+			Ok(_) => pid,
+			Err(pgid) => pgid,
+		};
+
+		// The group is set from both sides, so that it's in place regardless of which
+		// process runs first. This fails harmlessly if the child has already called exec.
+		// SAFETY: setpgid is safe to call with any arguments.
+		unsafe { libc::setpgid(pid, pgid) };
+
+		// The child places the group in the foreground itself. This is only a backup in case
+		// the parent runs first, and is redundant otherwise.
+		if pgid == pid {
+			if let Some(terminal) = &self.terminal {
+				set_foreground(terminal.as_raw_fd(), pgid);
+			}
+		}
+	}
+
+
+	/// Send a signal to all processes of the group. Each signal is sent only once, as the
+	/// processes are waited one at a time.
+	pub fn kill(&self, signal: libc::c_int) -> io::Result<()> {
+		let pgid = self.pgid.load(Ordering::SeqCst);
+
+		if pgid == 0 || self.signaled.fetch_or(1 << signal, Ordering::SeqCst) & (1 << signal) != 0 {
+			return Ok(());
+		}
+
+		// SAFETY: kill is safe to call with any arguments. The group has a process which
+		// has not been reaped yet, so its id can't have been reused.
+		if unsafe { libc::kill(-pgid, signal) } == 0 {
+			return Ok(());
+		}
+
+		match io::Error::last_os_error() {
+			error if error.raw_os_error() == Some(libc::ESRCH) => Ok(()),
+			error => Err(error),
+		}
+	}
+
+
+	/// Check whether the given process of the group has been stopped, such as by SIGTSTP from
+	/// the terminal. If so, stop the interpreter as well, and continue the group once the
+	/// interpreter is continued.
+	pub fn poll(&self, pid: u32) {
+		let terminal = match &self.terminal {
+			Some(terminal) => terminal.as_raw_fd(),
+			None => return,
+		};
+
+		// SAFETY: siginfo_t is a plain struct, and waitid only reports stopped processes,
+		// without reaping them.
+		let stopped = unsafe {
+			let mut info: libc::siginfo_t = std::mem::zeroed();
+			let result = libc::waitid(
+				libc::P_PID,
+				pid as libc::id_t,
+				&mut info,
+				libc::WSTOPPED | libc::WNOHANG,
+			);
+
+			result == 0 && info.si_pid() != 0
+		};
+
+		if !stopped {
+			return;
+		}
+
+		let pgid = self.pgid.load(Ordering::SeqCst);
+
+		// SAFETY: getpgrp and raise are always safe to call.
+		unsafe {
+			set_foreground(terminal, libc::getpgrp());
+			libc::raise(libc::SIGTSTP);
+		}
+
+		set_foreground(terminal, pgid);
+		// SAFETY: kill is safe to call with any arguments.
+		unsafe { libc::kill(-pgid, libc::SIGCONT) };
+	}
+
+
+	/// Handle the termination of a process of the group. A foreground process interrupted
+	/// from the terminal interrupts the interpreter, as if it had received the signal.
+	pub fn exited(&self, status: process::ExitStatus) {
+		if self.terminal.is_some() && status.signal() == Some(libc::SIGINT) {
+			signal::raise(libc::SIGINT);
+		}
+	}
+}
+
+
+impl Drop for Group {
+	/// Take the terminal's foreground back.
+	fn drop(&mut self) {
+		if let Some(terminal) = &self.terminal {
+			if self.pgid.load(Ordering::SeqCst) != 0 {
+				// SAFETY: getpgrp is always safe to call.
+				set_foreground(terminal.as_raw_fd(), unsafe { libc::getpgrp() });
+			}
+		}
+	}
+}
+
+
+/// A duplicate of the standard file descriptor of the terminal, if the interpreter is in
+/// its foreground.
+fn foreground_terminal() -> Option<OwnedFd> {
+	// SAFETY: these functions are safe to call with any file descriptor.
+	let fd = (0 ..= 2).find(
+		|&fd| unsafe { libc::isatty(fd) == 1 && libc::tcgetpgrp(fd) == libc::getpgrp() }
+	)?;
+
+	// SAFETY: fcntl is safe to call with any descriptor, and the duplicate is owned.
+	match unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) } {
+		-1 => None,
+		dup => Some(unsafe { OwnedFd::from_raw_fd(dup) }),
+	}
+}
+
+
+/// Place the given process group in the terminal's foreground. This is async-signal-safe.
+fn set_foreground(terminal: RawFd, pgid: libc::pid_t) {
+	// Changing the foreground from a background process raises SIGTTOU, unless blocked.
+	// SAFETY: the signal sets are initialized before use, and the previous mask is restored.
+	unsafe {
+		let mut block: libc::sigset_t = std::mem::zeroed();
+		let mut previous: libc::sigset_t = std::mem::zeroed();
+		libc::sigemptyset(&mut block);
+		libc::sigaddset(&mut block, libc::SIGTTOU);
+
+		libc::pthread_sigmask(libc::SIG_BLOCK, &block, &mut previous);
+		libc::tcsetpgrp(terminal, pgid);
+		libc::pthread_sigmask(libc::SIG_SETMASK, &previous, std::ptr::null_mut());
+	}
+}
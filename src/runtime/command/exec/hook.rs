@@ -0,0 +1,77 @@
+use std::{
+	ffi::OsStr,
+	path::PathBuf,
+	time::{Duration, SystemTime},
+};
+
+use super::Error;
+
+
+/// Description of a spawned process, reported to exec hooks.
+#[derive(Debug, Clone)]
+pub struct ExecInfo {
+	/// The program and its arguments.
+	pub argv: Box<[Box<OsStr>]>,
+	/// The working directory, if available.
+	pub cwd: Option<PathBuf>,
+	/// Environment variables set for the command, in addition to the inherited ones.
+	pub env: Box<[(Box<OsStr>, Box<OsStr>)]>,
+	/// When the process was spawned.
+	pub start: SystemTime,
+}
+
+
+impl ExecInfo {
+	pub fn new<A>(argv: A, env: Box<[(Box<OsStr>, Box<OsStr>)]>) -> Self
+	where
+		A: IntoIterator<Item = Box<OsStr>>,
+	{
+		Self {
+			argv: argv.into_iter().collect(),
+			cwd: std::env::current_dir().ok(),
+			env,
+			start: SystemTime::now(),
+		}
+	}
+}
+
+
+/// An event around the execution of a process.
+#[derive(Debug)]
+pub enum ExecEvent {
+	/// The process is about to be spawned.
+	Pre(ExecInfo),
+	/// The process has finished.
+	Post {
+		info: ExecInfo,
+		status: i32,
+		duration: Duration,
+	},
+}
+
+
+/// Callbacks from the execution of a command block into the interpreter.
+pub trait Hook {
+	/// Report an exec event. Returning false aborts the execution of the command block.
+	fn event(&mut self, event: ExecEvent) -> bool;
+
+	/// Call the function of a pipeline stage with its input, producing either its output or
+	/// the description of its failure.
+	fn call(&mut self, function: usize, input: Box<[u8]>) -> Result<Result<Box<[u8]>, String>, Error>;
+}
+
+
+/// Plain callbacks only handle exec events.
+impl<F> Hook for F
+where
+	F: FnMut(ExecEvent) -> bool,
+{
+	fn event(&mut self, event: ExecEvent) -> bool {
+		self(event)
+	}
+
+
+	fn call(&mut self, _: usize, _: Box<[u8]>) -> Result<Result<Box<[u8]>, String>, Error> {
+		unreachable!("pipeline functions must be called by the interpreter")
+	}
+}
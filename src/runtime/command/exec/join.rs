@@ -1,55 +1,390 @@
-use gc::{Finalize, GcCell, Trace};
+use std::{
+	cell::RefCell,
+	collections::BTreeSet,
+	rc::Rc,
+	sync::{Arc, Mutex},
+	thread,
+	time::{Duration, Instant},
+};
 
-use crate::runtime::value::{CallContext, NativeFun, Value};
+use gc::{Finalize, Trace};
 
-use super::{Panic, PipelineErrors, IntoValue};
+use crate::runtime::{
+	self,
+	signal,
+	SourcePos,
+	value::{self, CallContext, Dict, Error, NativeFun, OrderedMap, Value},
+};
 
+use super::{ExecEvent, Panic, PipelineErrors, IntoValue};
 
-#[derive(Finalize)]
-struct JoinHandle(
-	std::thread::JoinHandle<Result<Box<[PipelineErrors]>, Panic>>
-);
 
+/// Interval between polls when waiting for an asynchronous block.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
 
-unsafe impl Trace for JoinHandle {
-	gc::unsafe_empty_trace!();
+
+/// The result of an asynchronous block, along with the recorded exec events.
+type JoinResult = (Result<Box<[PipelineErrors]>, Panic>, Vec<ExecEvent>);
+
+
+thread_local! {
+	/// The processes of the asynchronous block running in the current thread, if any.
+	static PROCESSES: RefCell<Option<Arc<Processes>>> = const { RefCell::new(None) };
 }
 
 
-#[derive(Trace, Finalize)]
-pub struct Join(GcCell<Option<JoinHandle>>);
+/// The processes spawned by an asynchronous block.
+#[derive(Debug, Default)]
+pub struct Processes(Mutex<ProcessesState>);
 
 
-impl Join {
-	pub fn new(handle: std::thread::JoinHandle<Result<Box<[PipelineErrors]>, Panic>>) -> Self {
-		Self(
-			GcCell::new(
-				Some(JoinHandle(handle))
-			)
+#[derive(Debug, Default)]
+struct ProcessesState {
+	/// The pids of all spawned processes, in order.
+	spawned: Vec<u32>,
+	/// The pids of the processes which have not been reaped yet.
+	running: BTreeSet<u32>,
+}
+
+
+impl Processes {
+	/// Run the given function with processes spawned in the current thread tracked.
+	pub fn track<F, R>(self: Arc<Self>, f: F) -> R
+	where
+		F: FnOnce() -> R,
+	{
+		let previous = PROCESSES.with(|processes| processes.replace(Some(self)));
+		let result = f();
+		PROCESSES.with(|processes| processes.replace(previous));
+		result
+	}
+
+
+	/// Record a process spawned in the current thread.
+	pub(super) fn spawned(pid: u32) {
+		Self::with_current(
+			|state| {
+				state.spawned.push(pid);
+				state.running.insert(pid);
+			}
 		)
 	}
+
+
+	/// Record a process of the current thread which has been reaped.
+	pub(super) fn reaped(pid: u32) {
+		Self::with_current(|state| { state.running.remove(&pid); })
+	}
+
+
+	fn with_current<F>(f: F)
+	where
+		F: FnOnce(&mut ProcessesState),
+	{
+		PROCESSES.with(
+			|processes| {
+				if let Some(processes) = processes.borrow().as_ref() {
+					f(&mut processes.lock())
+				}
+			}
+		)
+	}
+
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, ProcessesState> {
+		self.0
+			.lock()
+			.unwrap_or_else(|error| error.into_inner())
+	}
+}
+
+
+/// An asynchronous block, shared by the methods of its dict.
+#[derive(Debug)]
+pub struct Job {
+	handle: RefCell<Option<thread::JoinHandle<JoinResult>>>,
+	processes: Arc<Processes>,
+	/// The abandon flag for the waits of the block's thread.
+	abandon: signal::Abandon,
+}
+
+
+impl Job {
+	pub fn new(
+		handle: thread::JoinHandle<JoinResult>,
+		processes: Arc<Processes>,
+		abandon: signal::Abandon,
+	) -> Self {
+		Self {
+			handle: RefCell::new(Some(handle)),
+			processes,
+			abandon,
+		}
+	}
+
+
+	/// Whether the block's thread is still running.
+	fn is_running(&self) -> bool {
+		self.handle
+			.borrow()
+			.as_ref()
+			.is_some_and(|handle| !handle.is_finished())
+	}
+
+
+	/// Wait up to the given timeout for the block to finish, returning whether it did.
+	fn wait(&self, timeout: Duration, pos: &SourcePos) -> Result<bool, runtime::Panic> {
+		let deadline = Instant::now() + timeout;
+
+		loop {
+			if !self.is_running() {
+				return Ok(true);
+			}
+
+			let now = Instant::now();
+			if now >= deadline {
+				return Ok(false);
+			}
+
+			signal::sleep(POLL_INTERVAL.min(deadline - now))
+				.map_err(|signal| runtime::Panic::interrupted(signal, pos.copy()))?;
+		}
+	}
 }
 
 
+impl From<Job> for Value {
+	/// The dict of an asynchronous block, with the `join`, `pids`, `kill`, `wait` and
+	/// `is_running` methods.
+	fn from(job: Job) -> Self {
+		thread_local! {
+			pub static JOIN: Value = "join".into();
+		}
+
+		let job = Rc::new(job);
+
+		let mut dict = OrderedMap::new();
+
+		JOIN.with(
+			|join| dict.insert(join.copy(), Join(job.clone()).into())
+		);
+
+		dict.insert("pids".into(), Pids(job.clone()).into());
+		dict.insert("kill".into(), Kill(job.clone()).into());
+		dict.insert("wait".into(), Wait(job.clone()).into());
+		dict.insert("is_running".into(), IsRunning(job).into());
+
+		Dict::new(dict).into()
+	}
+}
+
+
+/// Wait for the block to finish, returning its result. If a timeout, as a duration or in
+/// milliseconds, is given and the block doesn't finish in time, its commands are killed,
+/// and an error is returned whose context is the block's result.
+#[derive(Finalize)]
+struct Join(Rc<Job>);
+
+/// Join has no garbage-collected fields.
+unsafe impl Trace for Join {
+	gc::unsafe_empty_trace!();
+}
+
 impl NativeFun for Join {
 	fn name(&self) -> &'static str { "<command>.join" }
 
-	fn call(&self, context: CallContext) -> Result<Value, crate::runtime::Panic> {
-		match self.0.borrow_mut().take() {
-			Some(JoinHandle(join_handle)) => {
-				let result = match join_handle.join() {
+	fn call(&self, context: CallContext) -> Result<Value, runtime::Panic> {
+		let timeout = match context.args() {
+			[] => None,
+			[ timeout ] => Some(parse_timeout(timeout, &context.pos)?),
+			args => return Err(runtime::Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let timed_out = match timeout {
+			Some(timeout) if self.0.handle.borrow().is_some() => {
+				let finished = self.0.wait(timeout, &context.pos)?;
+
+				// The block stops once its waits are abandoned, as in capture timeouts.
+				if !finished {
+					self.0.abandon.set(true);
+				}
+
+				!finished
+			}
+
+			_ => false,
+		};
+
+		let handle = self.0.handle.borrow_mut().take();
+
+		match handle {
+			Some(join_handle) => {
+				let (result, events) = match join_handle.join() {
 					Ok(result) => result,
 					Err(error) => std::panic::resume_unwind(error),
 				};
 
-				result
-					.map(|errors| errors.into_value(context.interner()))
-					.map_err(Into::into)
+				for event in events {
+					context.runtime.exec_hook(event, context.pos.copy())?;
+				}
+
+				let result = result.map(|errors| errors.into_value(context.interner()))?;
+
+				if timed_out {
+					Ok(Error::new("command timed out".into(), result).into())
+				} else {
+					Ok(result)
+				}
 			},
 
 			None => Err(
-				crate::runtime::Panic::invalid_join(context.pos),
+				runtime::Panic::invalid_join(context.pos),
 			)
 		}
 	}
 }
+
+
+/// The pids of the processes spawned by the block so far, in order.
+#[derive(Finalize)]
+struct Pids(Rc<Job>);
+
+/// Pids has no garbage-collected fields.
+unsafe impl Trace for Pids {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for Pids {
+	fn name(&self) -> &'static str { "<command>.pids" }
+
+	fn call(&self, context: CallContext) -> Result<Value, runtime::Panic> {
+		match context.args() {
+			[] => Ok(
+				self.0.processes
+					.lock()
+					.spawned
+					.iter()
+					.map(|&pid| Value::Int(pid.into()))
+					.collect::<Vec<_>>()
+					.into()
+			),
+
+			args => Err(runtime::Panic::invalid_args(args.len() as u32, 0, context.pos)),
+		}
+	}
+}
+
+
+/// Send the given signal, or SIGTERM if omitted, to the running processes of the block.
+/// The signal may be given by number or by name, such as "SIGINT". Returns the number of processes signaled.
+#[derive(Finalize)]
+struct Kill(Rc<Job>);
+
+/// Kill has no garbage-collected fields.
+unsafe impl Trace for Kill {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for Kill {
+	fn name(&self) -> &'static str { "<command>.kill" }
+
+	fn call(&self, context: CallContext) -> Result<Value, runtime::Panic> {
+		let signal = match context.args() {
+			[] => libc::SIGTERM,
+			&[ Value::Int(signal) ] if (1 .. 64).contains(&signal) => signal as libc::c_int,
+			[ value @ Value::Int(_) ] => return Err(
+				runtime::Panic::value_error(value.copy(), "signal number", context.pos)
+			),
+			[ value @ Value::String(name) ] => match signal::parse(name.as_bytes()) {
+				Some(signal) => signal,
+				None => {
+					let names: Vec<&str> = signal::names().collect();
+					return Err(
+						runtime::Panic::value_error(
+							value.copy(),
+							format!("signal number or name ({})", names.join(", ")),
+							context.pos,
+						)
+					);
+				}
+			},
+			[ other ] => return Err(
+				runtime::Panic::type_error(other.copy(), "int or string", context.pos)
+			),
+			args => return Err(runtime::Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		// Processes are removed as soon as they are reaped, which makes signaling a reused
+		// pid unlikely.
+		let state = self.0.processes.lock();
+
+		let count = state.running
+			.iter()
+			// SAFETY: kill is safe to call with any arguments.
+			.filter(|&&pid| unsafe { libc::kill(pid as libc::pid_t, signal) } == 0)
+			.count();
+
+		Ok(Value::Int(count as i64))
+	}
+}
+
+
+/// Wait up to the given duration or number of milliseconds for the block to finish,
+/// returning whether it did. The block's result must still be obtained with `join`.
+#[derive(Finalize)]
+struct Wait(Rc<Job>);
+
+/// Wait has no garbage-collected fields.
+unsafe impl Trace for Wait {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for Wait {
+	fn name(&self) -> &'static str { "<command>.wait" }
+
+	fn call(&self, context: CallContext) -> Result<Value, runtime::Panic> {
+		let timeout = match context.args() {
+			[ timeout ] => parse_timeout(timeout, &context.pos)?,
+			args => return Err(runtime::Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		self.0
+			.wait(timeout, &context.pos)
+			.map(Into::into)
+	}
+}
+
+
+/// Whether the block is still running.
+#[derive(Finalize)]
+struct IsRunning(Rc<Job>);
+
+/// IsRunning has no garbage-collected fields.
+unsafe impl Trace for IsRunning {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for IsRunning {
+	fn name(&self) -> &'static str { "<command>.is_running" }
+
+	fn call(&self, context: CallContext) -> Result<Value, runtime::Panic> {
+		match context.args() {
+			[] => Ok(self.0.is_running().into()),
+			args => Err(runtime::Panic::invalid_args(args.len() as u32, 0, context.pos)),
+		}
+	}
+}
+
+
+/// Parse a timeout, as a duration or in milliseconds.
+fn parse_timeout(timeout: &Value, pos: &SourcePos) -> Result<Duration, runtime::Panic> {
+	match timeout {
+		&Value::Int(timeout) | &Value::Duration(value::Duration(timeout)) if timeout >= 0 => {
+			Ok(Duration::from_millis(timeout as u64))
+		}
+		value @ (Value::Int(_) | Value::Duration(_)) => Err(
+			runtime::Panic::value_error(value.copy(), "positive integer", pos.copy())
+		),
+		other => Err(runtime::Panic::type_error(other.copy(), "int or duration", pos.copy())),
+	}
+}
@@ -1,18 +1,30 @@
 mod error;
+pub mod fds;
 mod fmt;
+mod group;
+mod hook;
 mod join;
+mod redirect;
 
 use std::{
+	cmp::Reverse,
+	collections::HashSet,
 	ffi::{OsStr, OsString},
-	fs::{File, OpenOptions},
-	io::{self, Write},
-	os::unix::{prelude::{FromRawFd, OsStrExt, ExitStatusExt, IntoRawFd}, process::CommandExt},
+	fs::{self, File, OpenOptions},
+	io::{self, Read, Write},
+	os::unix::{prelude::{FromRawFd, OsStrExt, ExitStatusExt, IntoRawFd, OwnedFd}, process::CommandExt},
+	path::PathBuf,
 	process,
+	sync::Arc,
+	thread,
 };
 
-use crate::io::FileDescriptor;
+use crate::{io::FileDescriptor, runtime::{children, signal}};
 use super::{program, SourcePos};
-pub use join::Join;
+pub use group::Group;
+pub use hook::{ExecEvent, ExecInfo, Hook};
+pub use join::{Job, Processes};
+use redirect::FdTable;
 pub use error::{Panic, Error, PipelineErrors, IntoValue};
 
 
@@ -20,6 +32,8 @@ pub use error::{Panic, Error, PipelineErrors, IntoValue};
 const IO_ERROR_STATUS: i32 = 0x7F;
 /// Offset of a signal status, according to Bash and Dash.
 const SIGNAL_STATUS_OFFSET: i32 = 0xFF;
+/// Status to be produced when a function in a pipeline returns an error.
+const FUNCTION_ERROR_STATUS: i32 = 1;
 
 
 /// Execution status of a single command.
@@ -33,44 +47,128 @@ pub struct ErrorStatus {
 
 impl ErrorStatus {
 	/// Wait a child process, and return the status.
-	fn wait_child(mut child: Child) -> Option<Self> {
-		let status = match child.process.wait() {
-			Ok(status) => status,
-			Err(error) => return Some(
+	fn wait_child(mut child: Child, hook: &mut dyn Hook) -> Option<Self> {
+		let pid = child.process.id();
+
+		let result = match &child.group {
+			Some(group) => signal::wait_with(
+				&mut child.process,
+				|signal| group.kill(signal),
+				|| group.poll(pid),
+			),
+			None => signal::wait(&mut child.process),
+		};
+
+		Processes::reaped(pid);
+
+		if let (Some(group), Ok(status)) = (&child.group, &result) {
+			group.exited(*status);
+		}
+
+		let duration = child.info.start
+			.elapsed()
+			.unwrap_or_default();
+
+		let error = match result {
+			Ok(status) => {
+				let code = status
+					.code()
+					.or_else(
+						|| status
+							.signal()
+							.map(
+								|status| status + SIGNAL_STATUS_OFFSET
+							)
+					)
+					.unwrap_or(255);
+
+				if code == 0 {
+					None
+				} else {
+					Some(
+						Self {
+							description: "command returned non-zero".into(),
+							status: code,
+							pos: child.pos,
+						}
+					)
+				}
+			}
+
+			Err(error) => Some(
 				Self {
 					description: error.to_string(),
 					status: IO_ERROR_STATUS,
 					pos: child.pos,
 				}
-			)
+			),
 		};
 
-		let code = status
-			.code()
-			.or_else(
-				|| status
-					.signal()
-					.map(
-						|status| status + SIGNAL_STATUS_OFFSET
-					)
-			)
-			.unwrap_or(255);
+		let status = error
+			.as_ref()
+			.map(|error| error.status)
+			.unwrap_or(0);
 
-		if code == 0 {
-			None
-		} else {
-			Some(
-				Self {
-					description: "command returned non-zero".into(),
-					status: code,
-					pos: child.pos,
+		// The process has already finished, so there is nothing to abort.
+		hook.event(ExecEvent::Post { info: child.info, status, duration });
+
+		error
+	}
+}
+
+
+/// How to order the matches of a glob pattern.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GlobSort {
+	/// Lexicographically, by path.
+	#[default]
+	Name,
+	/// By modification time, newest first.
+	Mtime,
+}
+
+
+impl GlobSort {
+	/// Sort the given paths.
+	pub fn sort(self, paths: &mut [PathBuf]) {
+		match self {
+			Self::Name => paths.sort(),
+			Self::Mtime => paths.sort_by_cached_key(
+				|path| {
+					let mtime = fs::symlink_metadata(path)
+						.and_then(|metadata| metadata.modified())
+						.ok();
+					(Reverse(mtime), path.clone())
 				}
-			)
+			),
 		}
 	}
 }
 
 
+/// What to do when a pattern matches no files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GlobNoMatch {
+	/// Expand to no arguments.
+	#[default]
+	Empty,
+	/// Keep the pattern as a literal argument, like other shells.
+	Literal,
+	/// Panic.
+	Error,
+}
+
+
+/// Options for glob expansion of command arguments.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GlobOptions {
+	pub sort: GlobSort,
+	/// Whether to omit files already matched by a previous pattern in the same command.
+	pub dedup: bool,
+	pub no_match: GlobNoMatch,
+}
+
+
 /// An argument may expand to zero or more literals.
 #[derive(Debug)]
 pub enum Argument {
@@ -82,8 +180,14 @@ pub enum Argument {
 
 
 impl Argument {
-	/// Resolve the argument in the current directory.
+	/// Resolve the argument in the current directory, with the default glob options.
 	pub fn resolve(self, pos: SourcePos) -> Result<Box<[Box<OsStr>]>, Panic> {
+		self.resolve_with(GlobOptions::default(), pos)
+	}
+
+
+	/// Resolve the argument in the current directory.
+	pub fn resolve_with(self, options: GlobOptions, pos: SourcePos) -> Result<Box<[Box<OsStr>]>, Panic> {
 		match self {
 			Self::Literal(lit) => Ok(Box::new([lit])),
 			Self::Pattern(pattern) => {
@@ -95,9 +199,25 @@ impl Argument {
 
 				let is_absolute = pattern_str.starts_with('/');
 
-				let entries = glob::glob(&pattern_str)
-					.map_err(|_| Panic::invalid_pattern(pattern_str.into(), pos))?
+				let mut paths: Vec<PathBuf> = glob::glob(&pattern_str)
+					.map_err(|_| Panic::invalid_pattern(pattern_str.clone().into(), pos.copy()))?
 					.filter_map(Result::ok)
+					.collect();
+
+				if paths.is_empty() {
+					match options.no_match {
+						GlobNoMatch::Empty => (),
+						GlobNoMatch::Literal => return Ok(
+							Box::new([OsString::from(pattern_unescape(&pattern_str)).into_boxed_os_str()])
+						),
+						GlobNoMatch::Error => return Err(Panic::no_matches(pattern_str.into(), pos)),
+					}
+				}
+
+				options.sort.sort(&mut paths);
+
+				let entries = paths
+					.into_iter()
 					.map(
 						|path| if is_absolute {
 							OsString::from(path).into_boxed_os_str()
@@ -117,6 +237,29 @@ impl Argument {
 }
 
 
+/// Undo the escaping of pattern metacharacters in literal parts, as in `[*]`.
+fn pattern_unescape(pattern: &str) -> String {
+	let mut result = String::with_capacity(pattern.len());
+	let mut rest = pattern;
+
+	while let Some(c) = rest.chars().next() {
+		match rest.as_bytes() {
+			[ b'[', meta @ (b'?' | b'*' | b'[' | b']'), b']', .. ] => {
+				result.push(*meta as char);
+				rest = &rest[3 ..];
+			}
+
+			_ => {
+				result.push(c);
+				rest = &rest[c.len_utf8() ..];
+			}
+		}
+	}
+
+	result
+}
+
+
 /// The target of a redirection operation.
 #[derive(Debug)]
 pub enum RedirectionTarget {
@@ -141,6 +284,8 @@ pub enum Redirection {
 	Input {
 		/// Whether the source is the input or the file path.
 		literal: bool,
+		/// The file descriptor to be redirected, which is stdin by default.
+		fd: FileDescriptor,
 		/// The source argument. Panics if the argument does not expand to a single literal.
 		source: Argument,
 	},
@@ -162,10 +307,12 @@ impl Builtin {
 	pub fn exec(
 		self,
 		arguments: Box<[Argument]>,
+		glob: GlobOptions,
 		pos: SourcePos,
+		hook: &mut dyn Hook,
 	) -> Result<Option<ErrorStatus>, Error> {
 		let io_error = |error| Error::io(error, pos.copy());
-		let mut args = Self::resolve_args(arguments, pos.copy())?;
+		let mut args = Self::resolve_args(arguments, glob, pos.copy())?;
 
 		match self {
 			Self::Alias => todo!(),
@@ -192,25 +339,39 @@ impl Builtin {
 					.next()
 					.ok_or_else(|| Panic::invalid_args("argument", 0, pos.copy()))?;
 
-				let mut command = process::Command::new(cmd);
+				let mut command = process::Command::new(&cmd);
+
+				let mut argv = vec![cmd];
 
 				if matches!(self, Self::Exec0 | Self::Spawn0) {
 					let arg0 = args
 						.next()
 						.ok_or_else(|| Panic::invalid_args("arg0", 0, pos.copy()))?;
 
-					command.arg0(arg0);
+					command.arg0(&arg0);
+					argv.push(arg0);
 				}
 
 				for arg in args {
-					command.arg(arg);
+					command.arg(&arg);
+					argv.push(arg);
+				}
+
+				let info = ExecInfo::new(argv, Box::default());
+
+				if !hook.event(ExecEvent::Pre(info.clone())) {
+					return Err(Error::Aborted);
 				}
 
 				if matches!(self, Self::Spawn0) {
+					let _slots = children::acquire(1).map_err(io_error)?;
+
 					let process = command.spawn()
 						.map_err(io_error)?;
 
-					Ok(ErrorStatus::wait_child(Child { process, pos }))
+					Processes::spawned(process.id());
+
+					Ok(ErrorStatus::wait_child(Child { process, pos, info, group: None }, hook))
 				} else {
 					let error = command.exec();
 					Err(io_error(error))
@@ -221,12 +382,13 @@ impl Builtin {
 
 	fn resolve_args(
 		arguments: Box<[Argument]>,
+		glob: GlobOptions,
 		pos: SourcePos,
 	) -> Result<impl Iterator<Item = Box<OsStr>>, Error> {
 		let args = arguments
 			.into_vec()
 			.into_iter()
-			.map(|arg| arg.resolve(pos.copy()))
+			.map(|arg| arg.resolve_with(glob, pos.copy()))
 			.collect::<Result<Vec<_>, _>>()?
 			.into_iter()
 			.map(<[_]>::into_vec)
@@ -269,6 +431,8 @@ pub struct BasicCommand {
 	pub arguments: Box<[Argument]>,
 	/// Redirections to be placed in order.
 	pub redirections: Box<[Redirection]>,
+	/// Options for glob expansion of the arguments.
+	pub glob: GlobOptions,
 	/// Whether to abort the command block execution if the command fails.
 	pub abort_on_error: bool,
 	/// Source position of the command.
@@ -277,62 +441,86 @@ pub struct BasicCommand {
 
 
 impl BasicCommand {
-	pub fn exec(self, stdio: Stdio) -> Result<Child, Error> {
+	/// Spawn the command in the given process group.
+	pub fn exec(self, stdio: Stdio, group: &Arc<Group>, hook: &mut dyn Hook) -> Result<Child, Error> {
 		let pos = self.pos.copy();
 
 		let program_args = self.program.resolve(pos.copy())?;
 
-		let mut command = match program_args.as_ref() {
-			[ program ] => process::Command::new(program),
+		let (mut command, mut argv) = match program_args.into_vec().as_mut_slice() {
+			[ program ] => (
+				process::Command::new(&program),
+				vec![std::mem::take(program)],
+			),
 			other => return Err(
 				Panic::invalid_args("program", other.len() as u32, pos.copy()).into()
 			),
 		};
 
+		let mut env = Vec::new();
+
 		for (key, value) in self.env.into_vec() { // Use vec's owned iterator.
 			let value = value.resolve(pos.copy())?;
 
-			match value.as_ref() {
-				[ value ] => command.env(key, value),
+			match value.into_vec().as_mut_slice() {
+				[ value ] => {
+					command.env(&key, &value);
+					env.push((key, std::mem::take(value)));
+				}
 				other => return Err(
 					Panic::invalid_args("env variable", other.len() as u32, pos.copy()).into()
 				),
 			};
 		}
 
+		// Files matched by previous patterns, for deduplication.
+		let mut matched = HashSet::new();
+
 		for argument in self.arguments.into_vec() {
-			let args = argument.resolve(pos.copy())?;
+			let is_pattern = matches!(argument, Argument::Pattern(_));
+			let args = argument.resolve_with(self.glob, pos.copy())?;
+
+			for arg in args.into_vec() {
+				if is_pattern && self.glob.dedup && !matched.insert(arg.clone()) {
+					continue;
+				}
 
-			for arg in args.iter() {
-				command.arg(arg);
+				command.arg(&arg);
+				argv.push(arg);
 			}
 		}
 
-		Self::spawn(&mut command, stdio, self.redirections, self.pos)
+		let info = ExecInfo::new(argv, env.into());
+
+		if !hook.event(ExecEvent::Pre(info.clone())) {
+			return Err(Error::Aborted);
+		}
+
+		group.configure(&mut command);
+
+		Self::spawn(command, stdio, self.redirections, self.pos, info, group)
 	}
 
 
 	fn spawn(
-		command: &mut process::Command,
-		mut stdio: Stdio,
+		mut command: process::Command,
+		stdio: Stdio,
 		redirections: Box<[Redirection]>,
 		pos: SourcePos,
+		info: ExecInfo,
+		group: &Arc<Group>,
 	) -> Result<Child, Error> {
+		let mut fds = FdTable::new(stdio);
+
 		for redirection in redirections.into_vec() { // Use vec's owned iterator.
 			match redirection {
 				Redirection::Output { source, target } => {
-					let target = Self::resolve_target(target, &stdio, pos.copy())?;
+					let target = Self::resolve_target(target, &fds, pos.copy())?;
 
-					match source {
-						1 => stdio.stdout = target,
-						2 => stdio.stderr = target,
-						other => return Err(
-							Panic::unsupported_fd(other, pos.copy()).into()
-						),
-					};
+					fds.set(source, target);
 				}
 
-				Redirection::Input { literal, source } => {
+				Redirection::Input { literal, fd, source } => {
 					let args = source.resolve(pos.copy())?;
 
 					let source = match args.as_ref() {
@@ -342,7 +530,7 @@ impl BasicCommand {
 						),
 					};
 
-					let stdin: os_pipe::PipeReader =
+					let input: os_pipe::PipeReader =
 						if literal {
 							let (reader, mut writer) = os_pipe::pipe()
 								.map_err(|error| Error::io(error, pos.copy()))?;
@@ -363,23 +551,31 @@ impl BasicCommand {
 							unsafe { os_pipe::PipeReader::from_raw_fd(file) }
 						};
 
-					stdio.stdin = stdin;
+					fds.set(fd, input);
 				}
 			}
 		}
 
-		command.stdin(stdio.stdin);
-		command.stdout(stdio.stdout);
-		command.stderr(stdio.stderr);
+		let redirected = fds.configure(&mut command)
+			.map_err(|error| Error::io(error, pos.copy()))?;
 
 		let process = command.spawn()
-			.map_err(|error| Error::io(error, pos.copy()))?;
+			.map_err(|error| Error::io(error, pos.copy()));
+
+		// Close our copies of the child's descriptors right away, so that the pipe ends are
+		// not held open after spawning, even if spawning failed.
+		drop(command);
+		drop(redirected);
 
-		Ok(Child { process, pos })
+		let process = process?;
+		Processes::spawned(process.id());
+		group.spawned(process.id());
+
+		Ok(Child { process, pos, info, group: Some(group.clone()) })
 	}
 
 
-	fn resolve_target(target: RedirectionTarget, stdio: &Stdio, pos: SourcePos) -> Result<os_pipe::PipeWriter, Error> {
+	fn resolve_target(target: RedirectionTarget, fds: &FdTable, pos: SourcePos) -> Result<OwnedFd, Error> {
 		let open = |arg: Argument, append| {
 			let args = arg.resolve(pos.copy())?;
 
@@ -400,27 +596,135 @@ impl BasicCommand {
 
 			Ok(
 				// SAFETY: converting from a FD originated from a File is fine.
-				unsafe { os_pipe::PipeWriter::from_raw_fd(file) }
+				unsafe { OwnedFd::from_raw_fd(file) }
 			)
 		};
 
 		match target {
 			RedirectionTarget::Overwrite(arg) => open(arg, false),
 			RedirectionTarget::Append(arg) => open(arg, true),
-			RedirectionTarget::Fd(fd) => {
-				let writer = match fd {
-					1 => &stdio.stdout,
-					2 => &stdio.stderr,
-					other => return Err(
-						Panic::unsupported_fd(other, pos.copy()).into()
-					),
-				};
+			RedirectionTarget::Fd(fd) => fds
+				.duplicate(fd)
+				.map_err(
+					|error| match error.raw_os_error() {
+						Some(libc::EBADF) => Panic::unsupported_fd(fd, pos).into(),
+						_ => Error::io(error, pos),
+					}
+				),
+		}
+	}
+}
+
+
+/// A Hush function in a pipeline. It is called with the output of the previous command,
+/// and its result is fed to the next command.
+#[derive(Debug)]
+pub struct FunctionCommand {
+	/// The function to be called through the hook.
+	pub function: usize,
+	/// Whether to abort the command block execution if the function fails.
+	pub abort_on_error: bool,
+	/// Source position of the command.
+	pub pos: SourcePos,
+}
+
+
+impl FunctionCommand {
+	/// Call the function with the given input. The output is written from a separate
+	/// thread, as it may not fit in the pipe buffer.
+	fn call(
+		self,
+		stdin: Option<os_pipe::PipeReader>,
+		mut stdout: os_pipe::PipeWriter,
+		hook: &mut dyn Hook,
+	) -> Result<(Started, thread::JoinHandle<()>), Error> {
+		let mut input = Vec::new();
+
+		if let Some(mut stdin) = stdin {
+			stdin.read_to_end(&mut input)
+				.map_err(|error| Error::io(error, self.pos.copy()))?;
+		}
+
+		let (output, error) = match hook.call(self.function, input.into())? {
+			Ok(output) => (output, None),
+			Err(description) => (
+				Box::default(),
+				Some(
+					ErrorStatus {
+						description,
+						status: FUNCTION_ERROR_STATUS,
+						pos: self.pos,
+					}
+				),
+			),
+		};
+
+		// If the next command doesn't consume the whole output, the write will fail once
+		// the reader is closed, terminating the thread.
+		let writer = thread::spawn(
+			move || {
+				let _ = stdout.write_all(&output);
+			}
+		);
 
-				writer.try_clone()
-					.map_err(|error| Error::io(error, pos))
+		Ok((Started::Called { error, abort_on_error: self.abort_on_error }, writer))
+	}
+}
+
+
+/// A stage of a pipeline.
+#[derive(Debug)]
+pub enum Stage {
+	Process(BasicCommand),
+	Function(FunctionCommand),
+}
+
+
+impl Stage {
+	/// Spawn the stage's process. Functions are only called once all processes in the
+	/// pipeline have been spawned.
+	fn start(self, stdio: Stdio, group: &Arc<Group>, hook: &mut dyn Hook) -> Result<Started, Error> {
+		match self {
+			Self::Process(command) => {
+				let abort_on_error = command.abort_on_error;
+				let child = command.exec(stdio, group, hook)?;
+
+				Ok(Started::Process { child, abort_on_error })
 			}
+
+			Self::Function(command) => Ok(
+				Started::Function { command, stdin: stdio.stdin, stdout: stdio.stdout }
+			),
 		}
 	}
+
+
+	pub fn pos(&self) -> SourcePos {
+		match self {
+			Self::Process(command) => command.pos.copy(),
+			Self::Function(command) => command.pos.copy(),
+		}
+	}
+}
+
+
+/// A pipeline stage which has been started.
+#[derive(Debug)]
+enum Started {
+	Process {
+		child: Child,
+		abort_on_error: bool,
+	},
+	Function {
+		command: FunctionCommand,
+		stdin: os_pipe::PipeReader,
+		stdout: os_pipe::PipeWriter,
+	},
+	/// A function which has already been called.
+	Called {
+		error: Option<ErrorStatus>,
+		abort_on_error: bool,
+	},
 }
 
 
@@ -428,6 +732,9 @@ impl BasicCommand {
 pub struct Child {
 	process: process::Child,
 	pos: SourcePos,
+	info: ExecInfo,
+	/// The process group of the pipeline, if any.
+	group: Option<Arc<Group>>,
 }
 
 
@@ -446,6 +753,8 @@ pub enum Command {
 		program: Builtin,
 		/// Arguments to the program. The arguments may expand to an arbitrary number of literals.
 		arguments: Box<[Argument]>,
+		/// Options for glob expansion of the arguments.
+		glob: GlobOptions,
 		/// Whether to abort the command block execution if the command fails.
 		abort_on_error: bool,
 		/// Source position of the command.
@@ -453,23 +762,27 @@ pub enum Command {
 	},
 	External {
 		/// The first command.
-		head: BasicCommand,
+		head: Stage,
 		/// The following commands, if any.
-		tail: Box<[BasicCommand]>
+		tail: Box<[Stage]>
 	}
 }
 
 
 impl Command {
-	/// Returns a pair of result value and whether to abort.
+	/// Returns a pair of result value and whether to abort. Pipelines run in their own
+	/// process group, which is placed in the terminal's foreground if requested.
 	pub fn exec(
 		self,
+		stdin: os_pipe::PipeReader,
 		stdout: os_pipe::PipeWriter,
 		stderr: os_pipe::PipeWriter,
+		foreground: bool,
+		hook: &mut dyn Hook,
 	) -> Result<CommandExec, Error> {
 		match self {
-			Command::Builtin { program, arguments, abort_on_error, pos } => {
-				let error = program.exec(arguments, pos)?;
+			Command::Builtin { program, arguments, glob, abort_on_error, pos } => {
+				let error = program.exec(arguments, glob, pos, hook)?;
 				let abort = abort_on_error && error.is_some();
 				Ok(
 					CommandExec {
@@ -480,75 +793,232 @@ impl Command {
 			}
 
 			Command::External { head, tail } => {
-				let mut last_stdout = stdout;
-				let mut last_stderr = stderr;
+				let processes = std::iter::once(&head)
+					.chain(tail.iter())
+					.filter(|stage| matches!(stage, Stage::Process(_)))
+					.count();
 
-				let mut tail_children = Vec::new();
-				for cmd in tail.into_vec().into_iter().rev() {
-					let child_abort_on_error = cmd.abort_on_error;
+				// Released once all children have been waited.
+				let _slots = children::acquire(processes)
+					.map_err(|error| Error::io(error, head.pos()))?;
 
-					let (pipe_reader, pipe_writer) = os_pipe::pipe()
-						.map_err(|error| Error::io(error, cmd.pos.copy()))?;
+				let group = Arc::new(Group::new(foreground));
 
-					let child = cmd.exec(
-						Stdio {
-							stdin: pipe_reader,
-							stdout: last_stdout,
-							stderr: last_stderr,
+				let mut last_stdout = stdout;
+				let mut last_stderr = stderr;
+
+				// The started stages, in reverse order.
+				let mut started = Vec::new();
+
+				for stage in tail.into_vec().into_iter().rev() {
+					let pos = stage.pos();
+
+					let spawned = os_pipe::pipe()
+						.map_err(|error| Error::io(error, pos.copy()))
+						.and_then(
+							|(pipe_reader, pipe_writer)| {
+								// The stderr of the previous command in the pipeline. Every stage
+								// writes to the block's stderr, which may be captured, unless
+								// redirected by its own redirections.
+								let stderr = last_stderr
+									.try_clone()
+									.map_err(|error| Error::io(error, pos.copy()))?;
+
+								let stage = stage.start(
+									Stdio {
+										stdin: pipe_reader,
+										stdout: last_stdout,
+										stderr: last_stderr,
+									},
+									&group,
+									hook,
+								)?;
+
+								Ok((stage, pipe_writer, stderr))
+							}
+						);
+
+					// On failure, all pipe ends have been closed by now.
+					let (stage, pipe_writer, stderr) = match spawned {
+						Ok(spawned) => spawned,
+						Err(error) => {
+							Self::reap(started, &group, hook);
+							return Err(error);
 						}
-					)?;
+					};
 
-					last_stdout = pipe_writer;
-					last_stderr = os_pipe::dup_stderr()
-						.map_err(|error| Error::io(error, child.pos.copy()))?;
+					started.push(stage);
 
-					tail_children.push((child, child_abort_on_error));
+					last_stdout = pipe_writer;
+					last_stderr = stderr;
 				}
 
-				let head_abort_on_error = head.abort_on_error;
-
-				let stdin = os_pipe::dup_stdin()
-					.map_err(|error| Error::io(error, head.pos.copy()))?;
-
-				let head_child = head.exec(
+				let head = head.start(
 					Stdio {
 						stdin,
 						stdout: last_stdout,
 						stderr: last_stderr,
+					},
+					&group,
+					hook,
+				);
+
+				match head {
+					Ok(head) => started.push(head),
+					Err(error) => {
+						Self::reap(started, &group, hook);
+						return Err(error);
 					}
-				)?;
+				}
 
-				let mut abort = false;
-				let mut errors = Vec::new();
+				started.reverse();
 
-				// Wait on head command.
-				if let Some(error) = ErrorStatus::wait_child(head_child) {
-					abort |= head_abort_on_error;
-					errors.push(error);
-				}
+				Self::finish(started, hook)
+			}
+		}
+	}
+
+
+	/// Call the functions of a started pipeline in order, and wait all of its processes.
+	fn finish(stages: Vec<Started>, hook: &mut dyn Hook) -> Result<CommandExec, Error> {
+		let mut called = Vec::with_capacity(stages.len());
+		let mut writers = Vec::new();
+		let mut failure = None;
+
+		for (ix, stage) in stages.into_iter().enumerate() {
+			let stage = match stage {
+				Started::Function { command, stdin, stdout } if failure.is_none() => {
+					// A function at the head of the pipeline receives no input.
+					let stdin = if ix == 0 { None } else { Some(stdin) };
+
+					match command.call(stdin, stdout, hook) {
+						Ok((stage, writer)) => {
+							writers.push(writer);
+							stage
+						}
 
-				// Wait on tail commands.
-				for (child, abort_on_error) in tail_children.into_iter().rev() {
-					if let Some(error) = ErrorStatus::wait_child(child) {
-						abort |= abort_on_error;
-						errors.push(error);
+						Err(error) => {
+							failure = Some(error);
+							continue;
+						}
 					}
 				}
 
-				Ok(
-					CommandExec {
-						errors: errors.into(),
-						abort,
-					}
-				)
+				// Drop the pipe ends of the remaining functions, so that the processes finish.
+				Started::Function { .. } => continue,
+
+				other => other,
+			};
+
+			called.push(stage);
+		}
+
+		let mut abort = false;
+		let mut errors = Vec::new();
+
+		for stage in called {
+			let (error, abort_on_error) = match stage {
+				Started::Process { child, abort_on_error } => (
+					ErrorStatus::wait_child(child, hook),
+					abort_on_error,
+				),
+				Started::Called { error, abort_on_error } => (error, abort_on_error),
+				Started::Function { .. } => unreachable!("all functions should have been called"),
+			};
+
+			if let Some(error) = error {
+				abort |= abort_on_error;
+				errors.push(error);
 			}
 		}
+
+		for writer in writers {
+			let _ = writer.join();
+		}
+
+		if let Some(error) = failure {
+			return Err(error);
+		}
+
+		Ok(
+			CommandExec {
+				errors: errors.into(),
+				abort,
+			}
+		)
 	}
 
+
+	/// Terminate and wait the already spawned commands of a pipeline which failed to be
+	/// spawned. The pipe ends of the functions are closed first, so that the processes are
+	/// not left running detached from the interpreter.
+	fn reap(stages: Vec<Started>, group: &Group, hook: &mut dyn Hook) {
+		let children: Vec<Child> = stages
+			.into_iter()
+			.filter_map(
+				|stage| match stage {
+					Started::Process { child, .. } => Some(child),
+					_ => None,
+				}
+			)
+			.collect();
+
+		let _ = group.kill(libc::SIGTERM);
+
+		for child in children {
+			ErrorStatus::wait_child(child, hook);
+		}
+	}
+
+
 	pub fn pos(&self) -> SourcePos {
 		match self {
 			Command::Builtin { pos, .. } => pos.copy(),
-			Command::External { head, .. } => head.pos.copy(),
+			Command::External { head, .. } => head.pos(),
+		}
+	}
+}
+
+
+/// Block-level input redirection.
+#[derive(Debug)]
+pub enum BlockInput {
+	/// Read from the given file.
+	File(Box<OsStr>),
+	/// Read the given literal.
+	Literal(Box<[u8]>),
+}
+
+
+impl BlockInput {
+	/// Open the input, producing a reader which may be cloned for each command.
+	fn open(self, pos: SourcePos) -> Result<os_pipe::PipeReader, Error> {
+		match self {
+			Self::File(path) => {
+				let file = File::open(path.as_ref())
+					.map_err(|error| Error::io(error, pos))?
+					.into_raw_fd();
+
+				// SAFETY: converting from a FD originated from a File is fine.
+				Ok(unsafe { os_pipe::PipeReader::from_raw_fd(file) })
+			}
+
+			Self::Literal(literal) => {
+				let (reader, mut writer) = os_pipe::pipe()
+					.map_err(|error| Error::io(error, pos))?;
+
+				// Write from a separate thread, as the literal may not fit in the pipe buffer.
+				// If the commands don't consume the whole input, the write will fail once all
+				// readers are closed, terminating the thread.
+				std::thread::spawn(
+					move || {
+						let _ = writer.write_all(&literal);
+						let _ = writer.write_all(b"\n");
+					}
+				);
+
+				Ok(reader)
+			}
 		}
 	}
 }
@@ -559,18 +1029,31 @@ impl Command {
 pub struct Block {
 	pub head: Command,
 	pub tail: Box<[Command]>,
+	/// Standard input for all commands in the block, if redirected.
+	pub input: Option<BlockInput>,
+	/// Whether the block's pipelines may be placed in the terminal's foreground, which is
+	/// the case unless the block is asynchronous.
+	pub foreground: bool,
 }
 
 
 impl Block {
-	pub fn exec<F, G>(self, stdout: F, stderr: G) -> Result<Box<[PipelineErrors]>, Panic>
+	/// Execute the block, reporting every spawned process to the given hook.
+	pub fn exec<F, G>(
+		self,
+		stdout: F,
+		stderr: G,
+		hook: &mut dyn Hook,
+	) -> Result<Box<[PipelineErrors]>, Panic>
 	where
 		F: FnMut() -> io::Result<os_pipe::PipeWriter>,
 		G: FnMut() -> io::Result<os_pipe::PipeWriter>,
 	{
-		match self._exec(stdout, stderr) {
+		match self._exec(stdout, stderr, hook) {
 			Ok(status) => Ok(status),
 			Err(Error::Panic(panic)) => Err(panic),
+			// The hook is responsible for reporting why the execution was aborted.
+			Err(Error::Aborted) => Ok(Box::default()),
 			Err(Error::Io { error, pos }) => {
 				let error = ErrorStatus {
 					description: error.to_string(),
@@ -584,7 +1067,12 @@ impl Block {
 	}
 
 
-	fn _exec<F, G>(self, mut stdout: F, mut stderr: G,) -> Result<Box<[PipelineErrors]>, Error>
+	fn _exec<F, G>(
+		self,
+		mut stdout: F,
+		mut stderr: G,
+		hook: &mut dyn Hook,
+	) -> Result<Box<[PipelineErrors]>, Error>
 	where
 		F: FnMut() -> io::Result<os_pipe::PipeWriter>,
 		G: FnMut() -> io::Result<os_pipe::PipeWriter>,
@@ -592,11 +1080,26 @@ impl Block {
 		let mut errors = Vec::new();
 
 		let pos = self.head.pos();
+
+		let input = self.input
+			.map(|input| input.open(pos.copy()))
+			.transpose()?;
+
+		// All commands share the same input, like in `{ cmd1; cmd2; } < file` in Bash.
+		let stdin = || match &input {
+			Some(input) => input.try_clone(),
+			None => os_pipe::dup_stdin(),
+		};
+
 		let head = self.head.exec(
+			stdin()
+				.map_err(|error| Error::io(error, pos.copy()))?,
 			stdout()
 				.map_err(|error| Error::io(error, pos.copy()))?,
 			stderr()
 				.map_err(|error| Error::io(error, pos.copy()))?,
+			self.foreground,
+			hook,
 		)?;
 
 		if !head.errors.is_empty() {
@@ -608,12 +1111,21 @@ impl Block {
 		}
 
 		for command in self.tail.into_vec() { // Use vec's owned iterator.
+			// The block's capture has been abandoned, or the interpreter has been interrupted.
+			if signal::abandoned() || signal::pending().is_some() {
+				break;
+			}
+
 			let pos = command.pos();
 			let child = command.exec(
+				stdin()
+					.map_err(|error| Error::io(error, pos.copy()))?,
 				stdout()
 					.map_err(|error| Error::io(error, pos.copy()))?,
 				stderr()
 					.map_err(|error| Error::io(error, pos.copy()))?,
+				self.foreground,
+				hook,
 			)?;
 
 			if !child.errors.is_empty() {
@@ -0,0 +1,117 @@
+//! File descriptor tables of commands to be spawned.
+//!
+//! The standard descriptors are passed to the command as usual. Other descriptors are
+//! duplicated onto their numbers in the child process, after it has been forked.
+
+use std::{
+	collections::BTreeMap,
+	io,
+	os::unix::{
+		io::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd, RawFd},
+		process::CommandExt,
+	},
+	process,
+};
+
+use crate::io::FileDescriptor;
+use super::Stdio;
+
+
+/// The file descriptors of a command, with its redirections applied in order.
+#[derive(Debug)]
+pub struct FdTable(BTreeMap<FileDescriptor, OwnedFd>);
+
+
+impl FdTable {
+	/// A table with the given standard descriptors.
+	pub fn new(stdio: Stdio) -> Self {
+		// SAFETY: the descriptors are owned by the given pipes.
+		let owned = |fd: RawFd| unsafe { OwnedFd::from_raw_fd(fd) };
+
+		Self(
+			BTreeMap::from([
+				(0, owned(stdio.stdin.into_raw_fd())),
+				(1, owned(stdio.stdout.into_raw_fd())),
+				(2, owned(stdio.stderr.into_raw_fd())),
+			])
+		)
+	}
+
+
+	/// Redirect the given descriptor to a file.
+	pub fn set<F>(&mut self, fd: FileDescriptor, file: F)
+	where
+		F: IntoRawFd,
+	{
+		// SAFETY: the descriptor is owned by the given file.
+		self.0.insert(fd, unsafe { OwnedFd::from_raw_fd(file.into_raw_fd()) });
+	}
+
+
+	/// Duplicate the given descriptor of the command. Descriptors which have not been
+	/// redirected are inherited from the interpreter, in which case they may not be open.
+	pub fn duplicate(&self, fd: FileDescriptor) -> io::Result<OwnedFd> {
+		match self.0.get(&fd) {
+			Some(file) => file.try_clone(),
+			None => dup_above(fd, 0),
+		}
+	}
+
+
+	/// Pass the descriptors to the command. The returned descriptors must be kept open
+	/// until the command has been spawned.
+	pub fn configure(mut self, command: &mut process::Command) -> io::Result<Box<[OwnedFd]>> {
+		let mut take = |fd| self.0.remove(&fd).expect("missing standard descriptor");
+
+		command.stdin(take(0));
+		command.stdout(take(1));
+		command.stderr(take(2));
+
+		let min = match self.0.last_key_value() {
+			Some((&max, _)) => max + 1,
+			None => return Ok(Box::default()),
+		};
+
+		// The sources are duplicated above all targets, so that no source is overwritten
+		// by a target before being duplicated itself.
+		let mut sources = Vec::with_capacity(self.0.len());
+		let mut dups = Vec::with_capacity(self.0.len());
+
+		for (target, file) in self.0 {
+			let source = dup_above(file.as_raw_fd(), min)?;
+			dups.push((source.as_raw_fd(), target));
+			sources.push(source);
+		}
+
+		// SAFETY: only async-signal-safe functions are called after forking, and nothing
+		// is allocated.
+		unsafe {
+			command.pre_exec(
+				move || {
+					for &(source, target) in &dups {
+						if libc::dup2(source, target) == -1 {
+							return Err(io::Error::last_os_error());
+						}
+					}
+
+					Ok(())
+				}
+			);
+		}
+
+		Ok(sources.into_boxed_slice())
+	}
+}
+
+
+/// Duplicate a descriptor to the lowest available number not lower than the given one.
+/// The duplicate is closed on exec, like all descriptors opened by the interpreter.
+fn dup_above(fd: RawFd, min: RawFd) -> io::Result<OwnedFd> {
+	// SAFETY: fcntl is safe to call with any descriptor, and the duplicate is owned.
+	unsafe {
+		match libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, min) {
+			-1 => Err(io::Error::last_os_error()),
+			dup => Ok(OwnedFd::from_raw_fd(dup)),
+		}
+	}
+}
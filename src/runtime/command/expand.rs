@@ -0,0 +1,93 @@
+use std::os::unix::ffi::OsStrExt;
+
+use crate::{fmt, semantic::{self, program}, syntax};
+use super::{
+	arg::Args,
+	exec,
+	Error,
+	Panic,
+	Runtime,
+	SourcePos,
+	Value,
+};
+
+
+impl Runtime {
+	/// Expand an argument written in command syntax, as if it were part of a command block.
+	/// The argument is lexed and analyzed just like in command blocks, except that it must
+	/// not refer to variables. Returns an array with the expanded arguments, or an error if
+	/// the pattern is invalid.
+	pub(in crate::runtime) fn expand(&mut self, pattern: &[u8], pos: SourcePos) -> Result<Value, Panic> {
+		let source = syntax::Source { path: pos.path, contents: pattern.into() };
+
+		let argument = match syntax::parse_argument(&source, self.interner_mut()) {
+			Ok(argument) => argument,
+			Err(error) => return Ok(
+				Error::new(error.error.to_string().into(), pattern.into()).into()
+			),
+		};
+
+		let argument = match semantic::Analyzer::analyze_standalone_argument(argument, self.interner_mut()) {
+			Ok(argument) => program::Argument {
+				// Report panics, like a zero range step, in the call site.
+				pos: program::SourcePos { line: pos.line, column: pos.column, path: pos.path },
+				..argument
+			},
+			Err(errors) => {
+				let error = &errors.0[0];
+				let description = fmt::Show(&error.kind, self.interner()).to_string();
+				return Ok(Error::new(description.into(), pattern.into()).into())
+			}
+		};
+
+		let args = self.build_argument(&argument)?;
+
+		self.resolve_expansion(args, pos)
+	}
+
+
+	/// Expand the given parts into a single argument, as interpolation of variables does in
+	/// command blocks: arrays produce one argument for each of their items, and no glob
+	/// expansion is performed.
+	pub(in crate::runtime) fn expand_parts(&self, parts: &[Value], pos: SourcePos) -> Result<Value, Panic> {
+		let mut args = Args::default();
+
+		for part in parts {
+			match part {
+				Value::Array(ref array) => {
+					let literals: Vec<Box<[u8]>> = array
+						.borrow()
+						.iter()
+						.map(|value| Self::build_basic_value(value.copy(), pos.copy()))
+						.collect::<Result<_, Panic>>()?;
+
+					args.push_literals(literals.iter());
+				}
+
+				other => {
+					let literal = Self::build_basic_value(other.copy(), pos.copy())?;
+					args.push_literal(&literal);
+				}
+			}
+		}
+
+		self.resolve_expansion(args.into(), pos)
+	}
+
+
+	fn resolve_expansion(&self, args: Box<[exec::Argument]>, pos: SourcePos) -> Result<Value, Panic> {
+		let mut expanded = Vec::new();
+
+		for arg in args.into_vec() {
+			let items = arg.resolve_with(self.glob_options, pos.copy())?;
+
+			expanded.extend(
+				items
+					.iter()
+					.map(|item| Value::from(item.as_bytes()))
+			);
+		}
+
+		Ok(expanded.into())
+	}
+}
@@ -1,43 +1,189 @@
 mod arg;
+mod capture;
 mod exec;
+mod expand;
 
 use std::{
 	borrow::Cow,
-	collections::HashMap,
 	os::unix::{ffi::OsStrExt, prelude::OsStringExt},
-	path::PathBuf,
-	ops::DerefMut, io::Read, ffi::{OsStr, OsString}, thread
+	ops::DerefMut, io::Write, ffi::{OsStr, OsString}, sync::Arc, thread
 };
 
 use super::{
 	program,
+	signal,
 	Dict,
+	Error,
+	Function,
+	OrderedMap,
 	Panic,
 	Runtime,
 	SourcePos,
+	Str,
 	Value,
 };
+use crate::symbol::Symbol;
 use arg::Args;
 use exec::IntoValue;
+pub(super) use exec::{ExecEvent, ExecInfo};
+pub use capture::CaptureTimeout;
+pub use exec::{fds, GlobNoMatch, GlobOptions, GlobSort};
+
+
+/// Functions to be called around the execution of every spawned process.
+#[derive(Debug, Default)]
+pub struct ExecHooks {
+	/// Called before spawning a process.
+	pub pre: Vec<Function>,
+	/// Called after a process has finished.
+	pub post: Vec<Function>,
+}
+
+
+impl ExecHooks {
+	pub fn is_empty(&self) -> bool {
+		self.pre.is_empty() && self.post.is_empty()
+	}
+}
+
+
+/// Callbacks from the execution of a command block, which call the exec hooks for every
+/// event, and the functions in the block's pipelines. The first produced panic is stored,
+/// and once it has happened all further executions are aborted.
+struct ExecCallbacks<'a> {
+	runtime: &'a mut Runtime,
+	functions: &'a [(Function, SourcePos)],
+	panic: &'a mut Option<Panic>,
+	pos: SourcePos,
+}
+
+
+impl exec::Hook for ExecCallbacks<'_> {
+	fn event(&mut self, event: exec::ExecEvent) -> bool {
+		if self.panic.is_some() {
+			return false;
+		}
+
+		match self.runtime.exec_hook(event, self.pos.copy()) {
+			Ok(()) => true,
+			Err(error) => {
+				*self.panic = Some(error);
+				false
+			}
+		}
+	}
+
+
+	fn call(&mut self, function: usize, input: Box<[u8]>) -> Result<Result<Box<[u8]>, String>, exec::Error> {
+		if self.panic.is_some() {
+			return Err(exec::Error::Aborted);
+		}
+
+		let (function, pos) = &self.functions[function];
+
+		match self.runtime.call_pipeline_function(function, &input, pos.copy()) {
+			Ok(output) => Ok(output),
+			Err(error) => {
+				*self.panic = Some(error);
+				Err(exec::Error::Aborted)
+			}
+		}
+	}
+}
 
 
 impl Runtime {
+	/// Execute a command block.
+	/// The block input, if any, must have been previously evaluated.
 	pub(super) fn eval_command_block(
 		&mut self,
 		block: &'static program::CommandBlock,
+		input: Option<(Value, SourcePos)>,
+		pos: SourcePos,
+	) -> Result<Value, Panic> {
+		// Asynchronous blocks keep their file descriptors until joined.
+		let fds = match block.kind {
+			program::CommandBlockKind::Asynchronous => None,
+			_ => fds::Snapshot::take(),
+		};
+
+		self.interrupted = false;
+
+		let result = self.exec_command_block(block, input, pos.copy());
+
+		if let Some(fds) = fds {
+			fds.report(&pos, self.interner());
+		}
+
+		let value = result?;
+
+		// An interrupted block produces an error value instead of a panic, so that the script
+		// may clean up. The commands have received the signal as well.
+		let asynchronous = matches!(block.kind, program::CommandBlockKind::Asynchronous);
+		if !asynchronous && signal::take_signal(libc::SIGINT) {
+			self.interrupted = true;
+
+			let mut context = OrderedMap::new();
+			context.insert("signal".into(), Value::Int(libc::SIGINT.into()));
+			context.insert("result".into(), value);
+
+			return Ok(Error::new("interrupted".into(), Dict::new(context).into()).into());
+		}
+
+		Ok(value)
+	}
+
+
+	fn exec_command_block(
+		&mut self,
+		block: &'static program::CommandBlock,
+		input: Option<(Value, SourcePos)>,
 		pos: SourcePos,
 	) -> Result<Value, Panic> {
-		let command_block = self.build_command_block(&block.head, &block.tail)?;
+		let mut functions = Vec::new();
+		let mut command_block = self.build_command_block(&block.head, &block.tail, &mut functions)?;
+
+		// Flush pending output from std.print, so that it's not reordered with the output of
+		// the commands.
+		std::io::stdout()
+			.flush()
+			.map_err(|error| Panic::io(error, pos.copy()))?;
+
+		command_block.input = match (&block.input, input) {
+			(Some(block_input), Some((Value::String(ref string), _))) => Some(
+				if block_input.literal {
+					exec::BlockInput::Literal(string.as_bytes().into())
+				} else {
+					exec::BlockInput::File(OsStr::from_bytes(string.as_bytes()).into())
+				}
+			),
+
+			(_, Some((value, pos))) => return Err(Panic::type_error(value, "string", pos)),
+
+			_ => None,
+		};
+
+		command_block.foreground = !matches!(block.kind, program::CommandBlockKind::Asynchronous);
+
+		// Panic produced by an exec hook, which aborts the command block.
+		let mut hook_panic = None;
 
 		match block.kind {
 			program::CommandBlockKind::Synchronous => {
-				command_block
-					.exec(
-						os_pipe::dup_stdout,
-						os_pipe::dup_stderr,
-					)
-					.map(|errors| errors.into_value(self.interner()))
-					.map_err(Into::into)
+				let result = command_block.exec(
+					os_pipe::dup_stdout,
+					os_pipe::dup_stderr,
+					&mut self.exec_callbacks(&functions, &mut hook_panic, pos),
+				);
+
+				if let Some(panic) = hook_panic {
+					return Err(panic);
+				}
+
+				let errors = result.map_err(Panic::from)?;
+				self.set_status(&errors);
+
+				Ok(errors.into_value(self.interner()))
 			}
 
 			program::CommandBlockKind::Capture => {
@@ -45,62 +191,76 @@ impl Runtime {
 					pub static ERROR: Value = "error".into();
 					pub static STDOUT: Value = "stdout".into();
 					pub static STDERR: Value = "stderr".into();
+					pub static STATUS: Value = "status".into();
 				}
 
-				let (mut stdout_read, stdout_write) = os_pipe::pipe()
+				let (stdout_read, stdout_write) = os_pipe::pipe()
 					.map_err(|error| Panic::io(error, pos.copy()))?;
 
-				let (mut stderr_read, stderr_write) = os_pipe::pipe()
+				let (stderr_read, stderr_write) = os_pipe::pipe()
 					.map_err(|error| Panic::io(error, pos.copy()))?;
 
-				let stdout_reader = thread::spawn(move || {
-					let mut data = Vec::with_capacity(512);
-					stdout_read.read_to_end(&mut data)?;
-					Ok(data)
-				});
+				let abandon = signal::Abandon::default();
+
+				let reader = {
+					let timeout = self.capture_timeout;
+					let abandon = abandon.clone();
 
-				let stderr_reader = thread::spawn(move || {
-					let mut data = Vec::with_capacity(512);
-					stderr_read.read_to_end(&mut data)?;
-					Ok(data)
-				});
+					thread::spawn(
+						move || match timeout {
+							Some(timeout) => capture::read(stdout_read, stderr_read, timeout, abandon),
+							None => capture::read_all(stdout_read, stderr_read),
+						}
+					)
+				};
 
-				let errors = command_block
-					.exec(
+				let result = signal::with_abandon(
+					&abandon,
+					|| command_block.exec(
 						// We must drop all writers before attempting to read, otherwise we'll deadlock.
 						move || stdout_write.try_clone(),
 						move || stderr_write.try_clone(),
+						&mut self.exec_callbacks(&functions, &mut hook_panic, pos.copy()),
 					)
-					.map_err(Panic::from)?;
+				);
+
+				if let Some(panic) = hook_panic {
+					return Err(panic);
+				}
+
+				let errors = result.map_err(Panic::from)?;
+				let status = self.set_status(&errors);
 
 				let mut result = errors.into_value(self.interner());
-				let mut captures = {
-					let out = match stdout_reader.join() {
-						Err(error) => std::panic::resume_unwind(error),
-						Ok(result) => result
-							.map_err(|error| Panic::io(error, pos.copy()))?
-							.into_boxed_slice(),
-					};
-
-					let err = match stderr_reader.join() {
-						Err(error) => std::panic::resume_unwind(error),
-						Ok(result) => result
-							.map_err(|error| Panic::io(error, pos.copy()))?
-							.into_boxed_slice(),
-					};
-
-					let mut dict = HashMap::new();
-
-					STDOUT.with(
-						|stdout| dict.insert(stdout.copy(), out.into())
-					);
-					STDERR.with(
-						|stderr| dict.insert(stderr.copy(), err.into())
-					);
 
-					dict
+				let capture::Captured { stdout, stderr, timed_out } = match reader.join() {
+					Err(error) => std::panic::resume_unwind(error),
+					Ok(result) => result.map_err(|error| Panic::io(error, pos.copy()))?,
 				};
 
+				let mut captures = OrderedMap::new();
+
+				STDOUT.with(
+					|key| captures.insert(key.copy(), Str::from(stdout).into())
+				);
+				STDERR.with(
+					|key| captures.insert(key.copy(), Str::from(stderr).into())
+				);
+				STATUS.with(
+					|key| captures.insert(key.copy(), Value::Int(status as i64))
+				);
+
+				if timed_out {
+					// The partial output is kept, along with the errors of the commands.
+					ERROR.with(
+						|error| captures.insert(error.copy(), result)
+					);
+
+					return Ok(
+						Error::new("capture timed out".into(), Dict::new(captures).into()).into()
+					);
+				}
+
 				match &mut result {
 					Value::Nil => Ok(Dict::new(captures).into()),
 					Value::Error(error) => {
@@ -119,53 +279,218 @@ impl Runtime {
 			}
 
 			program::CommandBlockKind::Asynchronous => {
-				thread_local! {
-					pub static JOIN: Value = "join".into();
+				// Functions can't be called from the block's thread.
+				if let Some((function, pos)) = functions.first() {
+					return Err(
+						Panic::value_error(
+							function.copy().into(),
+							"program, as functions can't run in asynchronous blocks",
+							pos.copy(),
+						)
+					);
 				}
 
+				// Hooks can't be called from the block's thread, so events are recorded and
+				// reported when the block is joined.
+				let record_events = !self.exec_hooks.is_empty();
+
+				// The processes are tracked so that the block may be signaled, and the waits
+				// may be abandoned so that the block may be timed out.
+				let processes = Arc::new(exec::Processes::default());
+				let tracked = processes.clone();
+				let abandon = signal::Abandon::default();
+				let tracked_abandon = abandon.clone();
+
 				let join_handle = std::thread::spawn(
-					|| command_block.exec(
-						os_pipe::dup_stdout,
-						os_pipe::dup_stderr,
+					move || tracked.track(
+						|| signal::with_abandon(
+							&tracked_abandon,
+							|| {
+								let mut events = Vec::new();
+
+								let result = command_block.exec(
+									os_pipe::dup_stdout,
+									os_pipe::dup_stderr,
+									&mut |event| {
+										if record_events {
+											events.push(event);
+										}
+										true
+									},
+								);
+
+								(result, events)
+							}
+						)
 					)
 				);
 
-				let join_handle = exec::Join
-					::new(join_handle)
-					.into();
+				Ok(exec::Job::new(join_handle, processes, abandon).into())
+			}
+		}
+	}
 
-				let mut dict = HashMap::new();
 
-				JOIN.with(
-					|join| dict.insert(join.copy(), join_handle)
-				);
+	/// Record the status of a command block in the current scope, which is the status of
+	/// the last failed command, or zero if all commands succeeded. Returns the status.
+	fn set_status(&mut self, errors: &[exec::PipelineErrors]) -> i32 {
+		let status = errors
+			.iter()
+			.rev()
+			.find_map(exec::PipelineErrors::status)
+			.unwrap_or(0);
+
+		match self.frames.last_mut() {
+			Some(frame) => frame.status = Some(status),
+			None => self.status = Some(status),
+		}
+
+		status
+	}
+
+
+	fn exec_callbacks<'a>(
+		&'a mut self,
+		functions: &'a [(Function, SourcePos)],
+		panic: &'a mut Option<Panic>,
+		pos: SourcePos,
+	) -> ExecCallbacks<'a> {
+		ExecCallbacks { runtime: self, functions, panic, pos }
+	}
+
+
+	/// Call a function in a pipeline with the array of lines of its input. The function may
+	/// return a string, which is written as is, an array of lines, or nil. Returning an
+	/// error fails the pipeline stage, with the error's description.
+	fn call_pipeline_function(
+		&mut self,
+		function: &Function,
+		input: &[u8],
+		pos: SourcePos,
+	) -> Result<Result<Box<[u8]>, String>, Panic> {
+		let mut lines: Vec<Value> = input
+			.split(|&c| c == b'\n')
+			.map(Value::from)
+			.collect();
+
+		// The trailing newline doesn't start a new line.
+		if input.is_empty() || input.ends_with(b"\n") {
+			lines.pop();
+		}
+
+		let args_start = self.arguments.len();
+		self.arguments.push(lines.into());
 
-				Ok(Dict::new(dict).into())
+		match self.call(Value::Nil, function, args_start, pos.copy())? {
+			Value::Nil => Ok(Ok(Box::default())),
+
+			Value::String(ref string) => Ok(Ok(string.as_bytes().into())),
+
+			Value::Array(ref array) => {
+				let mut output = Vec::new();
+
+				for line in array.borrow().iter() {
+					output.extend_from_slice(&Self::build_basic_value(line.copy(), pos.copy())?);
+					output.push(b'\n');
+				}
+
+				Ok(Ok(output.into()))
 			}
+
+			Value::Error(ref error) => Ok(
+				Err(String::from_utf8_lossy(error.description.as_bytes()).into_owned())
+			),
+
+			other => Err(Panic::type_error(other, "nil, string, array or error", pos)),
 		}
 	}
 
 
+	/// Call the exec hooks for the given event.
+	/// Hooks receive a dict describing the process: `argv`, `cwd`, `env` and `start` (as
+	/// seconds since the unix epoch). Post exec hooks also receive the exit `status` and the
+	/// `duration` in seconds.
+	pub(super) fn exec_hook(&mut self, event: exec::ExecEvent, pos: SourcePos) -> Result<(), Panic> {
+		let (info, post) = match event {
+			exec::ExecEvent::Pre(info) => (info, None),
+			exec::ExecEvent::Post { info, status, duration } => (info, Some((status, duration))),
+		};
+
+		let hooks: Vec<Function> = match post {
+			None => self.exec_hooks.pre.iter().map(Function::copy).collect(),
+			Some(_) => self.exec_hooks.post.iter().map(Function::copy).collect(),
+		};
+
+		if hooks.is_empty() {
+			return Ok(());
+		}
+
+		let mut dict = OrderedMap::new();
+
+		let argv: Vec<Value> = info.argv
+			.iter()
+			.map(|arg| arg.as_bytes().into())
+			.collect();
+		dict.insert("argv".into(), argv.into());
+
+		dict.insert(
+			"cwd".into(),
+			info.cwd
+				.map(|cwd| cwd.into_os_string().into())
+				.unwrap_or_default(),
+		);
+
+		let env: OrderedMap = info.env
+			.iter()
+			.map(|(key, value)| (key.as_bytes().into(), value.as_bytes().into()))
+			.collect();
+		dict.insert("env".into(), Dict::new(env).into());
+
+		let start = info.start
+			.duration_since(std::time::UNIX_EPOCH)
+			.unwrap_or_default();
+		dict.insert("start".into(), Value::Float(start.as_secs_f64().into()));
+
+		if let Some((status, duration)) = post {
+			dict.insert("status".into(), Value::Int(status as i64));
+			dict.insert("duration".into(), Value::Float(duration.as_secs_f64().into()));
+		}
+
+		let dict: Value = Dict::new(dict).into();
+
+		for hook in hooks {
+			let args_start = self.arguments.len();
+			self.arguments.push(dict.copy());
+			self.call(Value::Nil, &hook, args_start, pos.copy())?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Build a command block. Functions in pipelines are collected in the given vector.
 	fn build_command_block(
 		&mut self,
 		head: &'static program::Command,
 		tail: &'static [program::Command],
+		functions: &mut Vec<(Function, SourcePos)>,
 	) -> Result<exec::Block, Panic> {
-		let head = self.build_command(head)?;
+		let head = self.build_command(head, functions)?;
 		let tail = tail
 			.iter()
 			.map(
-				|cmd| self.build_command(cmd)
+				|cmd| self.build_command(cmd, functions)
 			)
 			.collect::<Result<_, Panic>>()?;
 
-		Ok(exec::Block { head, tail })
+		Ok(exec::Block { head, tail, input: None, foreground: true })
 	}
 
 
 	fn build_command(
 		&mut self,
-		command: &'static program::Command
+		command: &'static program::Command,
+		functions: &mut Vec<(Function, SourcePos)>,
 	) -> Result<exec::Command, Panic> {
 		match command {
 			program::Command::Builtin { program, arguments, abort_on_error, pos } => {
@@ -181,6 +506,7 @@ impl Runtime {
 					exec::Command::Builtin {
 						program: program.into(),
 						arguments: args.into(),
+						glob: self.glob_options,
 						abort_on_error: *abort_on_error,
 						pos: pos.into(),
 					}
@@ -188,11 +514,11 @@ impl Runtime {
 			}
 
 			program::Command::External { head, tail } => {
-				let head = self.build_basic_command(head)?;
+				let head = self.build_stage(head, functions)?;
 				let tail = tail
 					.iter()
 					.map(
-						|cmd| self.build_basic_command(cmd)
+						|cmd| self.build_stage(cmd, functions)
 					)
 					.collect::<Result<_, Panic>>()?;
 
@@ -202,21 +528,71 @@ impl Runtime {
 	}
 
 
+	/// Build a pipeline stage. A sole dollar argument in program position may hold a
+	/// function, which is then called with the output of the previous command.
+	fn build_stage(
+		&mut self,
+		command: &'static program::BasicCommand,
+		functions: &mut Vec<(Function, SourcePos)>,
+	) -> Result<exec::Stage, Panic> {
+		let function = match self.fetch_sole_dollar(&command.program) {
+			Some(Value::Function(ref function)) => function.copy(),
+			_ => return Ok(exec::Stage::Process(self.build_basic_command(command)?)),
+		};
+
+		let pos: SourcePos = command.pos.into();
+
+		if !command.env.is_empty() || !command.arguments.is_empty() || !command.redirections.is_empty() {
+			return Err(
+				Panic::value_error(
+					function.into(),
+					"program, as functions in pipelines take no arguments or redirections",
+					pos,
+				)
+			);
+		}
+
+		functions.push((function, pos.copy()));
+
+		Ok(
+			exec::Stage::Function(
+				exec::FunctionCommand {
+					function: functions.len() - 1,
+					abort_on_error: command.abort_on_error,
+					pos,
+				}
+			)
+		)
+	}
+
+
 	fn build_basic_command(
 		&mut self,
 		command: &'static program::BasicCommand,
 	) -> Result<exec::BasicCommand, Panic> {
-		let program_pos = command.program.pos.into();
+		let program_pos: SourcePos = command.program.pos.into();
 
-		let program = self.build_single_argument(
-			&command.program,
-			|items| Panic::invalid_command_args("program", items, program_pos)
-		)?;
+		let mut env = self.build_env_vars(&command.env)?;
+		let mut arguments = command.arguments.iter();
+
+		// A sole dollar argument in program position may hold a dict, which is spread as
+		// environment variables. The program is then the following argument.
+		let program = match self.fetch_sole_dollar(&command.program) {
+			Some(Value::Dict(ref dict)) => {
+				Self::spread_env_vars(dict, &mut env, program_pos.copy())?;
+
+				arguments
+					.next()
+					.ok_or_else(|| Panic::invalid_command_args("program", 0, program_pos))?
+			}
 
-		let env = self.build_env_vars(&command.env)?;
+			_ => &command.program,
+		};
 
 		let mut args = Vec::new();
-		for argument in command.arguments.iter() {
+		let program = self.build_program(program, &mut args)?;
+
+		for argument in arguments {
 			let arguments = self
 				.build_argument(argument)?
 				.into_vec();
@@ -233,9 +609,10 @@ impl Runtime {
 		Ok(
 			exec::BasicCommand {
 				program,
-				env,
+				env: env.into(),
 				arguments: args.into(),
 				redirections,
+				glob: self.glob_options,
 				abort_on_error: command.abort_on_error,
 				pos: command.pos.into(),
 			}
@@ -243,21 +620,63 @@ impl Runtime {
 	}
 
 
+	/// Build the program of a command. A sole dollar argument may hold an array with the
+	/// whole argv, which is expanded into the program and its leading arguments.
+	fn build_program(
+		&mut self,
+		argument: &'static program::Argument,
+		args: &mut Vec<exec::Argument>,
+	) -> Result<exec::Argument, Panic> {
+		let pos = argument.pos.into();
+
+		if self.fetch_sole_dollar(argument).is_some() {
+			let mut argv = self
+				.build_argument(argument)?
+				.into_vec()
+				.into_iter();
+
+			let program = argv
+				.next()
+				.ok_or_else(|| Panic::invalid_command_args("program", 0, pos))?;
+
+			args.extend(argv);
+
+			Ok(program)
+		} else {
+			self.build_single_argument(
+				argument,
+				|items| Panic::invalid_command_args("program", items, pos)
+			)
+		}
+	}
+
+
+	/// Fetch the value of an argument consisting of a sole dollar, if so.
+	fn fetch_sole_dollar(&mut self, argument: &program::Argument) -> Option<Value> {
+		match argument.parts.as_ref() {
+			[ program::ArgPart::Unit(program::ArgUnit::Dollar { slot_ix, .. }) ] => Some(
+				self.stack.fetch(slot_ix.into())
+			),
+
+			_ => None,
+		}
+	}
+
+
 	#[allow(clippy::type_complexity)]
 	fn build_env_vars(
 		&mut self,
 		input_env: &'static [(program::ArgUnit, program::Argument)],
-	) -> Result<Box<[(Box<OsStr>, exec::Argument)]>, Panic> {
+	) -> Result<Vec<(Box<OsStr>, exec::Argument)>, Panic> {
 		let mut env = Vec::new();
 		for (key, value) in input_env.iter() {
 			let pos = value.pos;
 
 			let key = match key {
 				program::ArgUnit::Literal(lit) => lit.clone(),
-				program::ArgUnit::Dollar { slot_ix, pos } => {
-					let value = self.stack.fetch(slot_ix.into());
-					let lit = Self::build_basic_value(value, pos.into())?;
-					lit.clone()
+				program::ArgUnit::Dollar { slot_ix, symbol, pos } => {
+					let value = self.fetch_variable(slot_ix, pos.into())?;
+					Self::build_variable_value(value, *symbol, pos.into())?
 				}
 			};
 			let key = OsString::from_vec(key.into()).into_boxed_os_str();
@@ -270,7 +689,29 @@ impl Runtime {
 			env.push((key, value))
 		}
 
-		Ok(env.into())
+		Ok(env)
+	}
+
+
+	/// Spread the entries of a dict as environment variables.
+	fn spread_env_vars(
+		dict: &Dict,
+		env: &mut Vec<(Box<OsStr>, exec::Argument)>,
+		pos: SourcePos,
+	) -> Result<(), Panic> {
+		for (key, value) in dict.borrow().iter() {
+			let key = Self::build_basic_value(key.copy(), pos.copy())?;
+			let value = Self::build_basic_value(value.copy(), pos.copy())?;
+
+			env.push(
+				(
+					OsString::from_vec(key.into()).into_boxed_os_str(),
+					exec::Argument::Literal(OsString::from_vec(value.into()).into_boxed_os_str()),
+				)
+			);
+		}
+
+		Ok(())
 	}
 
 
@@ -285,7 +726,7 @@ impl Runtime {
 				Ok(exec::Redirection::Output { source: *source, target })
 			}
 
-			program::Redirection::Input { literal, source } => {
+			program::Redirection::Input { literal, fd, source } => {
 				let pos = source.pos.into();
 
 				let source = self.build_single_argument(
@@ -293,7 +734,7 @@ impl Runtime {
 					|items| Panic::invalid_command_args("redirection", items, pos)
 				)?;
 
-				Ok(exec::Redirection::Input { literal: *literal, source })
+				Ok(exec::Redirection::Input { literal: *literal, fd: *fd, source })
 			}
 		}
 	}
@@ -343,15 +784,8 @@ impl Runtime {
 			.build_argument(argument)?
 			.into_vec();
 
-		let arg =
-			if let Some(arg) = args.pop() {
-				arg
-			} else {
-				return Err(panic(0))
-			};
-
-		if args.is_empty() {
-			Ok(arg)
+		if args.len() == 1 {
+			Ok(args.remove(0))
 		} else {
 			Err(panic(args.len() as u32))
 		}
@@ -360,7 +794,7 @@ impl Runtime {
 
 	fn build_argument(
 		&mut self,
-		argument: &'static program::Argument,
+		argument: &program::Argument,
 	) -> Result<Box<[exec::Argument]>, Panic> {
 		let mut args = Args::default();
 
@@ -370,8 +804,8 @@ impl Runtime {
 					args.push_literal(lit);
 				}
 
-				program::ArgPart::Unit(program::ArgUnit::Dollar { slot_ix, pos }) => {
-					let value = self.stack.fetch(slot_ix.into());
+				program::ArgPart::Unit(program::ArgUnit::Dollar { slot_ix, symbol, pos }) => {
+					let value = self.fetch_variable(slot_ix, pos.into())?;
 
 					match value {
 						Value::Array(ref array) => {
@@ -380,7 +814,7 @@ impl Runtime {
 								.iter()
 								.map(
 									|val| {
-										let lit = Self::build_basic_value(val.copy(), pos.into())?;
+										let lit = Self::build_variable_value(val.copy(), *symbol, pos.into())?;
 										Ok(Cow::Owned(lit.into_vec()))
 									}
 								)
@@ -391,52 +825,32 @@ impl Runtime {
 						}
 
 						other => {
-							let lit = Self::build_basic_value(other, pos.into())?;
+							let lit = Self::build_variable_value(other, *symbol, pos.into())?;
 							args.push_literal(&lit);
 						}
 					}
 				}
 
-				program::ArgPart::Home => {
-					// TODO: should we emit an error value here?
-					let home = std::env::var_os("HOME")
-						.map(
-							|home| {
-								let mut path = PathBuf::from(home);
-								path.push("");
-								path.into_os_string()
-							}
-						)
-						.unwrap_or_default();
+				program::ArgPart::Home(user) => {
+					let mut tilde = Vec::with_capacity(user.len() + 1);
+					tilde.push(b'~');
+					tilde.extend_from_slice(user);
 
-					args.push_literal(home.as_bytes());
+					// Like other shells, keep the argument as is if the user is unknown.
+					match super::lib::expand_user(&tilde) {
+						Some(home) => args.push_literal(&home),
+						None => args.push_literal(&tilde),
+					}
 				}
 
-				program::ArgPart::Range(from, to) => {
-					let items = (*from ..= *to)
-						.map(
-							|i| i.to_string().into_bytes()
-						);
-
-					args.push_literals(items);
+				program::ArgPart::Range { from, to, step } => {
+					let items = self.expand_range(from, to, step.as_ref(), argument.pos.into())?;
+					args.push_literals(items.iter());
 				},
 
 				program::ArgPart::Collection(items) => {
-					let literals: Vec<Cow<[u8]>> = items
-						.iter()
-						.map(
-							|unit| match unit {
-								program::ArgUnit::Literal(lit) => Ok(Cow::Borrowed(lit.as_ref())),
-								program::ArgUnit::Dollar { slot_ix, pos } => {
-									let value = self.stack.fetch(slot_ix.into());
-									let lit = Self::build_basic_value(value, pos.into())?;
-									Ok(Cow::Owned(lit.into_vec()))
-								},
-							}
-						)
-						.collect::<Result<_, Panic>>()?;
-
-					args.push_literals(literals.iter());
+					let items = self.expand_collection(items, argument.pos.into())?;
+					args.push_literals(items.iter());
 				},
 
 				program::ArgPart::Star => args.push_pattern(b"*"),
@@ -453,6 +867,119 @@ impl Runtime {
 	}
 
 
+	/// Expand the items of a range, which may be descending. The step defaults to one, and
+	/// its sign is ignored.
+	fn expand_range(
+		&mut self,
+		from: &program::ArgUnit,
+		to: &program::ArgUnit,
+		step: Option<&program::ArgUnit>,
+		pos: SourcePos,
+	) -> Result<Vec<Vec<u8>>, Panic> {
+		let from = self.build_range_bound(from)?;
+		let to = self.build_range_bound(to)?;
+		let step = match step {
+			Some(step) => self.build_range_bound(step)?,
+			None => 1,
+		};
+
+		if step == 0 {
+			return Err(Panic::value_error(Value::Int(step), "nonzero range step", pos));
+		}
+
+		let step = step.unsigned_abs();
+		let count = from.abs_diff(to) / step + 1;
+
+		let items = (0 .. count)
+			.map(
+				|i| {
+					let offset = (i * step) as i64;
+					let item = if from <= to { from.wrapping_add(offset) } else { from.wrapping_sub(offset) };
+					item.to_string().into_bytes()
+				}
+			)
+			.collect();
+
+		Ok(items)
+	}
+
+
+	/// Resolve a bound or step of a range, which may be a variable holding an int or a
+	/// numeric string.
+	fn build_range_bound(&mut self, unit: &program::ArgUnit) -> Result<i64, Panic> {
+		match unit {
+			program::ArgUnit::Literal(lit) => Ok(
+				std::str::from_utf8(lit)
+					.ok()
+					.and_then(|lit| lit.parse().ok())
+					.expect("invalid range literal")
+			),
+
+			program::ArgUnit::Dollar { slot_ix, pos, .. } => {
+				let value = self.fetch_variable(slot_ix, pos.into())?;
+
+				let int = match &value {
+					Value::Int(int) => Some(*int),
+					Value::String(string) => std::str::from_utf8(string.as_bytes())
+						.ok()
+						.and_then(|string| string.parse().ok()),
+					_ => None,
+				};
+
+				int.ok_or_else(|| Panic::type_error(value, "int or numeric string", pos.into()))
+			}
+		}
+	}
+
+
+	/// Expand the items of a collection, each of which may expand to several literals.
+	fn expand_collection(
+		&mut self,
+		items: &[Box<[program::ArgPart]>],
+		pos: SourcePos,
+	) -> Result<Vec<Vec<u8>>, Panic> {
+		let mut literals = Vec::new();
+
+		for item in items {
+			let mut expanded = vec![Vec::new()];
+
+			for part in item.iter() {
+				let suffixes = match part {
+					program::ArgPart::Unit(program::ArgUnit::Literal(lit)) => vec![lit.to_vec()],
+
+					program::ArgPart::Unit(program::ArgUnit::Dollar { slot_ix, symbol, pos }) => {
+						let value = self.fetch_variable(slot_ix, pos.into())?;
+						vec![Self::build_variable_value(value, *symbol, pos.into())?.into_vec()]
+					}
+
+					program::ArgPart::Range { from, to, step } => {
+						self.expand_range(from, to, step.as_ref(), pos.copy())?
+					}
+
+					program::ArgPart::Collection(items) => self.expand_collection(items, pos.copy())?,
+
+					// The lexer only produces units and nested expansions in collections.
+					_ => unreachable!("invalid collection item"),
+				};
+
+				// Like in Args, the product varies the leftmost parts first.
+				expanded = suffixes
+					.iter()
+					.flat_map(
+						|suffix| expanded
+							.iter()
+							.map(move |prefix| [prefix.as_slice(), suffix].concat())
+					)
+					.collect();
+			}
+
+			literals.extend(expanded);
+		}
+
+		Ok(literals)
+	}
+
+
 	fn build_basic_value(value: Value, pos: SourcePos) -> Result<Box<[u8]>, Panic> {
 		let literal: Option<Vec<u8>> = match &value {
 			Value::Nil => Some(Vec::default()),
@@ -461,15 +988,31 @@ impl Runtime {
 			Value::Float(float) => Some(float.to_string().into()),
 			Value::Byte(byte) => Some(vec![*byte]),
 			Value::String(string) => Some(AsRef::<[u8]>::as_ref(string).to_owned()),
+			Value::Duration(duration) => Some(duration.to_string().into()),
+			Value::Size(size) => Some(size.to_string().into()),
+
+			// Secrets are revealed to commands.
+			Value::Dict(dict) => dict.secret().map(|secret| secret.as_bytes().to_owned()),
 
 			Value::Array(_) => None,
-			Value::Dict(_) => None,
 			Value::Function(_) => None,
 			Value::Error(_) => None,
 		};
 
 		literal
 			.map(Into::into)
-			.ok_or_else(|| Panic::type_error(value, "nil, bool, int, float, byte or string", pos))
+			.ok_or_else(|| Panic::type_error(value, "nil, bool, int, float, byte, string, duration, size or secret", pos))
+	}
+
+
+	/// Like build_basic_value, but reporting the variable's name on type errors.
+	fn build_variable_value(value: Value, symbol: Symbol, pos: SourcePos) -> Result<Box<[u8]>, Panic> {
+		Self::build_basic_value(value, pos)
+			.map_err(
+				|panic| match panic {
+					Panic::TypeError { value, pos, .. } => Panic::invalid_command_variable(value, symbol, pos),
+					panic => panic,
+				}
+			)
 	}
 }
@@ -0,0 +1,118 @@
+//! Support for debuggers, which may pause the execution before each statement to inspect
+//! the active calls and their variables.
+
+use crate::symbol::{self, Symbol};
+use super::{value::Function, Runtime, SourcePos, Value};
+
+
+/// A debugger attached to the runtime, see `Runtime::set_debugger`.
+pub trait Debugger: std::fmt::Debug {
+	/// Whether to pause before the statement at the given position. The depth is the number
+	/// of active function calls.
+	fn check(&mut self, pos: &SourcePos, depth: usize, interner: &symbol::Interner) -> bool;
+
+	/// Pause the execution, returning when it should resume. The frames are innermost first.
+	fn pause(&mut self, frames: Vec<Frame>, interner: &symbol::Interner);
+}
+
+
+/// The code executed in a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+	/// The top level of the script with the given path.
+	Script(Symbol),
+	/// A function, with the name of the variable it is declared as, if any.
+	Function(Option<Symbol>),
+	/// A builtin function.
+	Builtin(&'static str),
+}
+
+
+/// An active call in the paused runtime.
+#[derive(Debug)]
+pub struct Frame {
+	pub code: Code,
+	/// The position being executed, or None for builtins.
+	pub pos: Option<SourcePos>,
+	/// The local variables, in declaration order, named as in `FrameInfo::slot_names`.
+	/// Builtins have no variables, and neither do functions which made a tail call.
+	pub variables: Vec<(Option<Symbol>, Value)>,
+}
+
+
+/// The top level of a script being executed.
+#[derive(Debug)]
+pub(super) struct Root {
+	pub path: Symbol,
+	/// How many function calls were active when the script started.
+	pub depth: usize,
+	/// The index of the first stack slot of the script.
+	pub base: usize,
+	pub slot_names: &'static [Option<Symbol>],
+}
+
+
+/// Collect the active calls of the runtime, innermost first, which is executing the given
+/// position.
+pub(super) fn frames(runtime: &Runtime, pos: SourcePos) -> Vec<Frame> {
+	let mut frames = Vec::new();
+	let mut pos = Some(pos);
+	let mut roots = runtime.roots.iter().rev().peekable();
+
+	for depth in (0 ..= runtime.frames.len()).rev() {
+		// Imported scripts are executed by a builtin, on top of its frame.
+		if let Some(root) = roots.next_if(|root| root.depth == depth) {
+			frames.push(
+				Frame {
+					code: Code::Script(root.path),
+					pos: pos.take(),
+					variables: variables(runtime, root.base, root.slot_names),
+				}
+			);
+		}
+
+		let call = match depth.checked_sub(1) {
+			Some(ix) => &runtime.frames[ix],
+			None => break,
+		};
+
+		frames.push(
+			match &call.function {
+				Function::Hush(fun) => Frame {
+					code: Code::Function(fun.name),
+					pos: pos.take(),
+					variables: call.base
+						.map(|base| variables(runtime, base, &fun.frame_info.slot_names))
+						.unwrap_or_default(),
+				},
+
+				Function::Rust(fun) => Frame {
+					code: Code::Builtin(fun.name()),
+					pos: None,
+					variables: Vec::new(),
+				},
+			}
+		);
+
+		pos = Some(call.pos.copy());
+	}
+
+	frames
+}
+
+
+/// The variables of a frame whose first slot is at the given index.
+fn variables(
+	runtime: &Runtime,
+	base: usize,
+	slot_names: &[Option<Symbol>],
+) -> Vec<(Option<Symbol>, Value)> {
+	// Slot indexes are counted from the top of the frame.
+	let top = base + slot_names.len();
+
+	slot_names
+		.iter()
+		.enumerate()
+		.map(|(ix, &name)| (name, runtime.stack.fetch_at(top - 1 - ix)))
+		.collect()
+}
@@ -5,16 +5,27 @@ use super::{
 	Array,
 	CallContext,
 	Dict,
+	Duration,
 	Error,
 	Float,
 	Function,
 	NativeFun,
+	NativeIter,
+	OrderedMap,
 	RustFun,
 	Panic,
+	Size,
 	Str,
 	Value,
 	Type,
+	Weak,
+	signal,
 };
+use super::command::{CaptureTimeout, GlobNoMatch, GlobOptions, GlobSort};
+
+pub use self::gc_::Collector;
+pub use self::path::expand_user;
+pub use self::test::{run as run_tests, Tests};
 
 
 inventory::collect!(RustFun);
@@ -33,19 +44,32 @@ pub fn new() -> Value {
 		insert(path, fun.copy().into(), &mut dict);
 	}
 
+	sys::init(&mut dict);
+
 	dict.into()
 }
 
 
+/// Set the path of the main script, in std.sys.program_path.
+pub fn set_program_path(std: &Value, path: Value) {
+	if let Value::Dict(dict) = std {
+		insert("sys.program_path", path, &mut dict.copy());
+	}
+}
+
+
 fn insert(path: &str, value: Value, dict: &mut Dict) {
 	match path.split_once('.') {
 		None => dict.insert(path.into(), value),
 		Some((key, path)) => {
-			let mut dict = dict.borrow_mut();
-			let dict = dict.entry(key.into()).or_insert_with(|| Dict::default().into());
+			let key: Value = key.into();
+
+			if !dict.contains(&key) {
+				dict.insert(key.copy(), Dict::default().into());
+			}
 
-			match dict {
-				Value::Dict(dict) => insert(path, value, dict),
+			match dict.get(&key) {
+				Ok(Value::Dict(ref dict)) => insert(path, value, &mut dict.copy()),
 				_ => panic!("invalid value in std initialization"),
 			}
 		},
@@ -0,0 +1,199 @@
+//! Tar and zip archives, implemented natively to avoid the divergent flags of the system
+//! tools.
+
+use std::{
+	ffi::OsStr,
+	fs::{self, File},
+	io::{self, BufReader, BufWriter, Write},
+	os::unix::ffi::OsStrExt,
+	path::{Path, PathBuf},
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+use crate::{archive, runtime::SourcePos};
+
+
+inventory::submit! { RustFun::from(TarCreate) }
+inventory::submit! { RustFun::from(TarExtract) }
+inventory::submit! { RustFun::from(ZipCreate) }
+inventory::submit! { RustFun::from(ZipExtract) }
+
+
+/// Create a tar archive at the given path, containing the given files or array of files.
+/// Directories are archived recursively, and symlinks are archived as such. Entries are
+/// named after the given paths, without leading slashes. Returns an error if the archive
+/// can't be created.
+#[derive(Trace, Finalize)]
+struct TarCreate;
+
+impl NativeFun for TarCreate {
+	fn name(&self) -> &'static str { "std.archive.tar_create" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (path, files) = create_args(&context)?;
+
+		Ok(create(&path, &files, archive::tar::write).into())
+	}
+}
+
+
+/// Extract a tar archive to the given directory, which is created if needed. Returns the
+/// array of extracted entry names, or an error. Entries which would be extracted outside
+/// the directory are refused.
+#[derive(Trace, Finalize)]
+struct TarExtract;
+
+impl NativeFun for TarExtract {
+	fn name(&self) -> &'static str { "std.archive.tar_extract" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (path, dest) = extract_args(&context)?;
+
+		let result = File::open(&path)
+			.and_then(
+				|file| {
+					fs::create_dir_all(&dest)?;
+					archive::tar::extract(&mut BufReader::new(file), &dest)
+				}
+			);
+
+		Ok(extract_result(result, &path))
+	}
+}
+
+
+/// Create a zip archive at the given path, containing the given files or array of files.
+/// Entries are stored without compression. See std.archive.tar_create.
+#[derive(Trace, Finalize)]
+struct ZipCreate;
+
+impl NativeFun for ZipCreate {
+	fn name(&self) -> &'static str { "std.archive.zip_create" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (path, files) = create_args(&context)?;
+
+		Ok(create(&path, &files, archive::zip::write).into())
+	}
+}
+
+
+/// Extract a zip archive to the given directory, which is created if needed. Both stored
+/// and deflated entries are supported. See std.archive.tar_extract.
+#[derive(Trace, Finalize)]
+struct ZipExtract;
+
+impl NativeFun for ZipExtract {
+	fn name(&self) -> &'static str { "std.archive.zip_extract" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (path, dest) = extract_args(&context)?;
+
+		let result = fs::read(&path)
+			.and_then(
+				|data| {
+					fs::create_dir_all(&dest)?;
+					archive::zip::extract(&data, &dest)
+				}
+			);
+
+		Ok(extract_result(result, &path))
+	}
+}
+
+
+fn to_path(string: &[u8]) -> PathBuf {
+	PathBuf::from(OsStr::from_bytes(string))
+}
+
+
+/// Parse the archive path and the files to be archived.
+fn create_args(context: &CallContext) -> Result<(PathBuf, Vec<PathBuf>), Panic> {
+	let file = |value: &Value, pos: &SourcePos| match value {
+		Value::String(ref string) => Ok(to_path(string.as_bytes())),
+		other => Err(Panic::type_error(other.copy(), "string", pos.copy())),
+	};
+
+	match context.args() {
+		[ Value::String(ref path), Value::Array(ref files) ] => Ok(
+			(
+				to_path(path.as_bytes()),
+				files
+					.borrow()
+					.iter()
+					.map(|value| file(value, &context.pos))
+					.collect::<Result<_, _>>()?,
+			)
+		),
+
+		[ Value::String(ref path), value @ Value::String(_) ] => Ok(
+			(to_path(path.as_bytes()), vec![file(value, &context.pos)?])
+		),
+
+		[ Value::String(_), other ] => Err(
+			Panic::type_error(other.copy(), "string or array", context.pos.copy())
+		),
+		[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy())),
+	}
+}
+
+
+/// Parse the archive path and the destination directory.
+fn extract_args(context: &CallContext) -> Result<(PathBuf, PathBuf), Panic> {
+	match context.args() {
+		[ Value::String(ref path), Value::String(ref dest) ] => Ok(
+			(to_path(path.as_bytes()), to_path(dest.as_bytes()))
+		),
+
+		[ Value::String(_), other ] | [ other, _ ] => Err(
+			Panic::type_error(other.copy(), "string", context.pos.copy())
+		),
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy())),
+	}
+}
+
+
+/// Create an archive with the given writer, removing the partial archive on failure.
+fn create<F>(path: &Path, files: &[PathBuf], write: F) -> Result<(), Error>
+where
+	F: FnOnce(&mut BufWriter<File>, &[archive::Entry]) -> io::Result<()>,
+{
+	let result = archive::collect(files)
+		.and_then(
+			|entries| {
+				let mut out = BufWriter::new(File::create(path)?);
+				write(&mut out, &entries)?;
+				out.flush()
+			}
+		);
+
+	result.map_err(
+		|error| {
+			let _ = fs::remove_file(path);
+			Error::new(error.to_string().into(), path.as_os_str().as_bytes().into())
+		}
+	)
+}
+
+
+fn extract_result(result: io::Result<Vec<Vec<u8>>>, path: &Path) -> Value {
+	match result {
+		Ok(names) => names
+			.into_iter()
+			.map(|name| Value::from(name.into_boxed_slice()))
+			.collect::<Vec<_>>()
+			.into(),
+
+		Err(error) => Error::new(error.to_string().into(), path.as_os_str().as_bytes().into()).into(),
+	}
+}
@@ -0,0 +1,73 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	Function,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Value,
+};
+use crate::runtime::SourcePos;
+
+
+inventory::submit! { RustFun::from(Backtrace) }
+
+/// Get the active function calls, innermost first, as an array of dicts with the
+/// following keys:
+/// - `name`: the function's name, or nil for anonymous functions and the script's top level.
+/// - `native`: whether the function is a builtin.
+/// - `path`, `line` and `column`: the position currently being executed in the frame, or
+///   nil for builtins.
+#[derive(Trace, Finalize)]
+struct Backtrace;
+
+impl NativeFun for Backtrace {
+	fn name(&self) -> &'static str { "std.backtrace" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		if !context.args().is_empty() {
+			return Err(Panic::invalid_args(context.args().len() as u32, 0, context.pos));
+		}
+
+		let interner = context.interner();
+		let resolve = |symbol| interner
+			.resolve(symbol)
+			.map(Value::from)
+			.unwrap_or_default();
+
+		let frame = |name: Value, native: bool, pos: Option<&SourcePos>| -> Value {
+			let mut frame = OrderedMap::new();
+
+			frame.insert("name".into(), name);
+			frame.insert("native".into(), native.into());
+			frame.insert("path".into(), pos.map(|pos| resolve(pos.path)).unwrap_or_default());
+			frame.insert("line".into(), pos.map(|pos| Value::Int(pos.line as i64)).unwrap_or_default());
+			frame.insert("column".into(), pos.map(|pos| Value::Int(pos.column as i64)).unwrap_or_default());
+
+			Dict::new(frame).into()
+		};
+
+		let mut frames = Vec::new();
+
+		// The innermost frame is the call to std.backtrace itself.
+		let mut pos = &context.pos;
+
+		for call in context.runtime.frames.iter().rev().skip(1) {
+			frames.push(
+				match &call.function {
+					Function::Hush(fun) => frame(fun.name.map(resolve).unwrap_or_default(), false, Some(pos)),
+					Function::Rust(fun) => frame(fun.name().into(), true, None),
+				}
+			);
+
+			pos = &call.pos;
+		}
+
+		frames.push(frame(Value::default(), false, Some(pos)));
+
+		Ok(frames.into())
+	}
+}
@@ -0,0 +1,488 @@
+//! Arbitrary precision integers.
+//!
+//! Big integers are represented by decimal strings, so that they may be printed, stored
+//! and passed to commands as is. All functions accept either ints or decimal strings, and
+//! return decimal strings, except for std.math.bigint.to_string and to_int.
+
+use std::{cmp::Ordering, convert::TryFrom};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+use crate::runtime::SourcePos;
+
+
+inventory::submit! { RustFun::from(Add) }
+inventory::submit! { RustFun::from(Sub) }
+inventory::submit! { RustFun::from(Mul) }
+inventory::submit! { RustFun::from(Div) }
+inventory::submit! { RustFun::from(Rem) }
+inventory::submit! { RustFun::from(Cmp) }
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(ToString) }
+inventory::submit! { RustFun::from(ToInt) }
+
+
+/// Sum two integers.
+#[derive(Trace, Finalize)]
+struct Add;
+
+impl NativeFun for Add {
+	fn name(&self) -> &'static str { "std.math.bigint.add" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (left, right) = binary(&context)?;
+		Ok(left.add(&right).into())
+	}
+}
+
+
+/// Subtract the second integer from the first.
+#[derive(Trace, Finalize)]
+struct Sub;
+
+impl NativeFun for Sub {
+	fn name(&self) -> &'static str { "std.math.bigint.sub" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (left, right) = binary(&context)?;
+		Ok(left.add(&right.neg()).into())
+	}
+}
+
+
+/// Multiply two integers.
+#[derive(Trace, Finalize)]
+struct Mul;
+
+impl NativeFun for Mul {
+	fn name(&self) -> &'static str { "std.math.bigint.mul" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (left, right) = binary(&context)?;
+		Ok(left.mul(&right).into())
+	}
+}
+
+
+/// Divide the first integer by the second, truncating towards zero, like int division.
+#[derive(Trace, Finalize)]
+struct Div;
+
+impl NativeFun for Div {
+	fn name(&self) -> &'static str { "std.math.bigint.div" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (left, right) = binary(&context)?;
+
+		match left.div_rem(&right) {
+			Some((quotient, _)) => Ok(quotient.into()),
+			None => Err(Panic::division_by_zero(context.pos)),
+		}
+	}
+}
+
+
+/// The remainder of the division, which has the sign of the first integer, like the int
+/// remainder.
+#[derive(Trace, Finalize)]
+struct Rem;
+
+impl NativeFun for Rem {
+	fn name(&self) -> &'static str { "std.math.bigint.rem" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (left, right) = binary(&context)?;
+
+		match left.div_rem(&right) {
+			Some((_, remainder)) => Ok(remainder.into()),
+			None => Err(Panic::division_by_zero(context.pos)),
+		}
+	}
+}
+
+
+/// Compare two integers, returning -1, 0 or 1 if the first is less than, equal to or
+/// greater than the second, respectively.
+#[derive(Trace, Finalize)]
+struct Cmp;
+
+impl NativeFun for Cmp {
+	fn name(&self) -> &'static str { "std.math.bigint.cmp" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (left, right) = binary(&context)?;
+		Ok(Value::Int(left.cmp(&right) as i64))
+	}
+}
+
+
+/// Parse a string in the given base, or in decimal if omitted, with an optional sign.
+/// Returns an error value if the string is not a valid integer.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.math.bigint.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (string, base) = match context.args() {
+			[ Value::String(ref string) ] => (string.copy(), 10),
+			[ Value::String(ref string), base ] => (string.copy(), parse_base(base, &context.pos)?),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		match BigInt::parse(string.as_bytes(), base) {
+			Some(int) => Ok(int.into()),
+			None => Ok(
+				Error::new("cannot parse integer".into(), string.into()).into()
+			),
+		}
+	}
+}
+
+
+/// Convert an integer to a string in the given base, or in decimal if omitted. Digits
+/// above 9 are lowercase letters.
+#[derive(Trace, Finalize)]
+struct ToString;
+
+impl NativeFun for ToString {
+	fn name(&self) -> &'static str { "std.math.bigint.to_string" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (int, base) = match context.args() {
+			[ value ] => (operand(value, &context.pos)?, 10),
+			[ value, base ] => (operand(value, &context.pos)?, parse_base(base, &context.pos)?),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		Ok(int.to_string(base).into())
+	}
+}
+
+
+/// Convert an integer to an int. Returns an error value if it's out of range.
+#[derive(Trace, Finalize)]
+struct ToInt;
+
+impl NativeFun for ToInt {
+	fn name(&self) -> &'static str { "std.math.bigint.to_int" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let int = match context.args() {
+			[ value ] => operand(value, &context.pos)?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		match int.to_i64() {
+			Some(int) => Ok(Value::Int(int)),
+			None => Ok(
+				Error::new("integer overflow".into(), int.into()).into()
+			),
+		}
+	}
+}
+
+
+/// Get the two integer arguments of a binary operation.
+fn binary(context: &CallContext) -> Result<(BigInt, BigInt), Panic> {
+	match context.args() {
+		[ left, right ] => Ok((operand(left, &context.pos)?, operand(right, &context.pos)?)),
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy())),
+	}
+}
+
+
+/// Convert an int or decimal string to a big integer.
+fn operand(value: &Value, pos: &SourcePos) -> Result<BigInt, Panic> {
+	match value {
+		&Value::Int(int) => Ok(BigInt::from(int)),
+
+		Value::String(ref string) => BigInt::parse(string.as_bytes(), 10)
+			.ok_or_else(|| Panic::value_error(value.copy(), "decimal integer", pos.copy())),
+
+		other => Err(Panic::type_error(other.copy(), "int or string", pos.copy())),
+	}
+}
+
+
+fn parse_base(base: &Value, pos: &SourcePos) -> Result<u32, Panic> {
+	match base {
+		&Value::Int(int) if (2 ..= 36).contains(&int) => Ok(int as u32),
+		value @ Value::Int(_) => Err(Panic::value_error(value.copy(), "base from 2 to 36", pos.copy())),
+		other => Err(Panic::type_error(other.copy(), "int", pos.copy())),
+	}
+}
+
+
+/// An arbitrary precision integer, as a sign and its magnitude in little endian 32 bit
+/// limbs, without trailing zeros. Zero is never negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BigInt {
+	negative: bool,
+	limbs: Vec<u32>,
+}
+
+
+impl BigInt {
+	fn new(negative: bool, mut limbs: Vec<u32>) -> Self {
+		while limbs.last() == Some(&0) {
+			limbs.pop();
+		}
+
+		Self {
+			negative: negative && !limbs.is_empty(),
+			limbs,
+		}
+	}
+
+
+	/// Parse digits in the given base, with an optional sign.
+	fn parse(string: &[u8], base: u32) -> Option<Self> {
+		let (negative, digits) = match string {
+			[ b'-', digits @ .. ] => (true, digits),
+			[ b'+', digits @ .. ] => (false, digits),
+			digits => (false, digits),
+		};
+
+		if digits.is_empty() {
+			return None;
+		}
+
+		let mut limbs = Vec::new();
+
+		for &digit in digits {
+			let digit = (digit as char).to_digit(base)?;
+			mul_small(&mut limbs, base, digit);
+		}
+
+		Some(Self::new(negative, limbs))
+	}
+
+
+	/// Format the integer in the given base.
+	fn to_string(&self, base: u32) -> String {
+		let mut limbs = self.limbs.clone();
+		let mut digits = Vec::new();
+
+		loop {
+			let digit = div_small(&mut limbs, base);
+			digits.push(std::char::from_digit(digit, base).expect("invalid digit"));
+
+			if limbs.is_empty() {
+				break;
+			}
+		}
+
+		if self.negative {
+			digits.push('-');
+		}
+
+		digits.iter().rev().collect()
+	}
+
+
+	fn to_i64(&self) -> Option<i64> {
+		let magnitude = match self.limbs.as_slice() {
+			[] => 0,
+			[ low ] => *low as u64,
+			[ low, high ] => (*high as u64) << 32 | *low as u64,
+			_ => return None,
+		};
+
+		if self.negative {
+			0i64.checked_sub_unsigned(magnitude)
+		} else {
+			i64::try_from(magnitude).ok()
+		}
+	}
+
+
+	fn neg(self) -> Self {
+		Self::new(!self.negative, self.limbs)
+	}
+
+
+	fn add(&self, other: &Self) -> Self {
+		if self.negative == other.negative {
+			return Self::new(self.negative, add(&self.limbs, &other.limbs));
+		}
+
+		match compare(&self.limbs, &other.limbs) {
+			Ordering::Less => Self::new(other.negative, sub(&other.limbs, &self.limbs)),
+			_ => Self::new(self.negative, sub(&self.limbs, &other.limbs)),
+		}
+	}
+
+
+	fn mul(&self, other: &Self) -> Self {
+		let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+
+		for (i, &left) in self.limbs.iter().enumerate() {
+			let mut carry = 0u64;
+
+			for (j, &right) in other.limbs.iter().enumerate() {
+				let product = left as u64 * right as u64 + limbs[i + j] as u64 + carry;
+				limbs[i + j] = product as u32;
+				carry = product >> 32;
+			}
+
+			limbs[i + other.limbs.len()] = carry as u32;
+		}
+
+		Self::new(self.negative != other.negative, limbs)
+	}
+
+
+	/// Truncated division, or None if the divisor is zero.
+	fn div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+		if other.limbs.is_empty() {
+			return None;
+		}
+
+		// Binary long division, one bit of the dividend at a time.
+		let mut quotient = vec![0u32; self.limbs.len()];
+		let mut remainder = Vec::new();
+
+		for bit in (0 .. self.limbs.len() * 32).rev() {
+			mul_small(&mut remainder, 2, (self.limbs[bit / 32] >> (bit % 32)) & 1);
+
+			if compare(&remainder, &other.limbs) != Ordering::Less {
+				remainder = sub(&remainder, &other.limbs);
+				quotient[bit / 32] |= 1 << (bit % 32);
+			}
+		}
+
+		Some(
+			(
+				Self::new(self.negative != other.negative, quotient),
+				Self::new(self.negative, remainder),
+			)
+		)
+	}
+}
+
+
+impl From<i64> for BigInt {
+	fn from(int: i64) -> Self {
+		let magnitude = int.unsigned_abs();
+		Self::new(int < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+	}
+}
+
+
+impl From<BigInt> for Value {
+	fn from(int: BigInt) -> Self {
+		int.to_string(10).into()
+	}
+}
+
+
+impl Ord for BigInt {
+	fn cmp(&self, other: &Self) -> Ordering {
+		match (self.negative, other.negative) {
+			(false, true) => Ordering::Greater,
+			(true, false) => Ordering::Less,
+			(false, false) => compare(&self.limbs, &other.limbs),
+			(true, true) => compare(&other.limbs, &self.limbs),
+		}
+	}
+}
+
+
+impl PartialOrd for BigInt {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+
+/// Compare two magnitudes.
+fn compare(left: &[u32], right: &[u32]) -> Ordering {
+	left.len()
+		.cmp(&right.len())
+		.then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+
+/// Add two magnitudes.
+fn add(left: &[u32], right: &[u32]) -> Vec<u32> {
+	let (long, short) = if left.len() >= right.len() { (left, right) } else { (right, left) };
+
+	let mut limbs = Vec::with_capacity(long.len() + 1);
+	let mut carry = 0u64;
+
+	for (ix, &limb) in long.iter().enumerate() {
+		let sum = limb as u64 + short.get(ix).copied().unwrap_or(0) as u64 + carry;
+		limbs.push(sum as u32);
+		carry = sum >> 32;
+	}
+
+	limbs.push(carry as u32);
+	limbs
+}
+
+
+/// Subtract the right magnitude from the left one, which must not be smaller.
+fn sub(left: &[u32], right: &[u32]) -> Vec<u32> {
+	let mut limbs = Vec::with_capacity(left.len());
+	let mut borrow = false;
+
+	for (ix, &limb) in left.iter().enumerate() {
+		let (difference, overflow1) = limb.overflowing_sub(right.get(ix).copied().unwrap_or(0));
+		let (difference, overflow2) = difference.overflowing_sub(borrow as u32);
+		limbs.push(difference);
+		borrow = overflow1 || overflow2;
+	}
+
+	debug_assert!(!borrow, "subtraction underflow");
+
+	while limbs.last() == Some(&0) {
+		limbs.pop();
+	}
+
+	limbs
+}
+
+
+/// Multiply a magnitude by a small factor and add a small term, in place.
+fn mul_small(limbs: &mut Vec<u32>, factor: u32, term: u32) {
+	let mut carry = term as u64;
+
+	for limb in limbs.iter_mut() {
+		let product = *limb as u64 * factor as u64 + carry;
+		*limb = product as u32;
+		carry = product >> 32;
+	}
+
+	if carry != 0 {
+		limbs.push(carry as u32);
+	}
+}
+
+
+/// Divide a magnitude by a small divisor in place, returning the remainder.
+fn div_small(limbs: &mut Vec<u32>, divisor: u32) -> u32 {
+	let mut remainder = 0u64;
+
+	for limb in limbs.iter_mut().rev() {
+		let dividend = remainder << 32 | *limb as u64;
+		*limb = (dividend / divisor as u64) as u32;
+		remainder = dividend % divisor as u64;
+	}
+
+	while limbs.last() == Some(&0) {
+		limbs.pop();
+	}
+
+	remainder as u32
+}
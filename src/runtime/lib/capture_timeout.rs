@@ -0,0 +1,63 @@
+use std::time;
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	CaptureTimeout as Timeout,
+	Duration,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(CaptureTimeout) }
+
+
+/// Set the read timeout for capture blocks, as a duration or in milliseconds. If a capture
+/// block produces no output for longer than the timeout, the capture is aborted with an
+/// error, whose context contains the partial `stdout` and `stderr`. The commands are
+/// killed, unless the second argument is true, in which case they are left running. Nil
+/// disables the timeout.
+#[derive(Trace, Finalize)]
+struct CaptureTimeout;
+
+impl NativeFun for CaptureTimeout {
+	fn name(&self) -> &'static str { "std.capture_timeout" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (millis, keep_running) = match context.args() {
+			[ Value::Nil ] => {
+				context.runtime.capture_timeout = None;
+				return Ok(Value::default());
+			}
+
+			[ Value::Int(millis) | Value::Duration(Duration(millis)) ] => (*millis, false),
+			[ Value::Int(millis) | Value::Duration(Duration(millis)), Value::Bool(keep_running) ] => {
+				(*millis, *keep_running)
+			}
+
+			[ Value::Int(_) | Value::Duration(_), other ] => {
+				return Err(Panic::type_error(other.copy(), "bool", context.pos))
+			}
+
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "int or duration", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		if millis <= 0 {
+			return Err(Panic::value_error(Value::Int(millis), "positive integer", context.pos));
+		}
+
+		context.runtime.capture_timeout = Some(
+			Timeout {
+				inactivity: time::Duration::from_millis(millis as u64),
+				keep_running,
+			}
+		);
+
+		Ok(Value::default())
+	}
+}
@@ -0,0 +1,48 @@
+use bstr::ByteSlice;
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Lower) }
+inventory::submit! { RustFun::from(Upper) }
+
+/// Convert a string to lowercase. Invalid UTF-8 bytes are kept unchanged.
+#[derive(Trace, Finalize)]
+struct Lower;
+
+impl NativeFun for Lower {
+	fn name(&self) -> &'static str { "std.lower" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(string.as_bytes().to_lowercase().into_boxed_slice().into()),
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Convert a string to uppercase. Invalid UTF-8 bytes are kept unchanged.
+#[derive(Trace, Finalize)]
+struct Upper;
+
+impl NativeFun for Upper {
+	fn name(&self) -> &'static str { "std.upper" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(string.as_bytes().to_uppercase().into_boxed_slice().into()),
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
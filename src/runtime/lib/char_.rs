@@ -0,0 +1,35 @@
+use std::convert::TryFrom;
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Offset) }
+
+/// Offset a char by the given int, which may be negative. The result must be a valid char.
+#[derive(Trace, Finalize)]
+struct Offset;
+
+impl NativeFun for Offset {
+	fn name(&self) -> &'static str { "std.char.offset" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			&[ Value::Byte(byte), Value::Int(offset) ] => match (byte as i64).checked_add(offset) {
+				Some(value) if u8::try_from(value).is_ok() => Ok(Value::Byte(value as u8)),
+				_ => Err(Panic::value_error(Value::Int(offset), "offset within char range", context.pos)),
+			},
+
+			[ Value::Byte(_), other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "char", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
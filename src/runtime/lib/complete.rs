@@ -0,0 +1,275 @@
+//! Programmable completion for scripts.
+//!
+//! A script registers a completion spec with `std.complete.register`. When the script is
+//! run through the hidden `hush __complete <script> <words...>` entry point, registering
+//! prints the completions for the given words and exits, so scripts should register their
+//! spec before doing any work. The shell glue which calls the entry point is produced by
+//! `std.complete.generate`.
+
+use std::{
+	ffi::OsStr,
+	io::{self, Write},
+	os::unix::ffi::OsStrExt,
+	path::PathBuf,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	NativeFun,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+	util,
+};
+use crate::runtime::SourcePos;
+
+
+inventory::submit! { RustFun::from(Register) }
+inventory::submit! { RustFun::from(Generate) }
+
+
+/// Register the completion spec for the script's arguments. The spec is a dict with the
+/// following optional keys:
+/// - `options`: array of options, completed for words starting with a dash.
+/// - `commands`: dict from subcommand names to their nested specs.
+/// - `args`: array of candidates, or function which receives the array of words and
+///   returns such array.
+///
+/// Outside of completion mode, this does nothing.
+#[derive(Trace, Finalize)]
+struct Register;
+
+impl NativeFun for Register {
+	fn name(&self) -> &'static str { "std.complete.register" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let spec = match context.args() {
+			[ Value::Dict(ref spec) ] => spec.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		if !context.runtime.completing {
+			return Ok(Value::default());
+		}
+
+		let words: Vec<Value> = match &context.runtime.args {
+			Value::Array(ref args) => args.borrow().iter().map(Value::copy).collect(),
+			_ => Vec::new(),
+		};
+
+		let candidates = complete(&mut context, spec, words)?;
+
+		let stdout = io::stdout();
+		let mut stdout = stdout.lock();
+
+		for candidate in candidates {
+			stdout
+				.write_all(candidate.as_bytes())
+				.and_then(|_| stdout.write_all(b"\n"))
+				.map_err(|error| Panic::io(error, context.pos.copy()))?;
+		}
+
+		stdout
+			.flush()
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		std::process::exit(0)
+	}
+}
+
+
+/// Produce the shell glue to complete the current script, for either "bash" or "zsh".
+/// The command name defaults to the script's file name.
+#[derive(Trace, Finalize)]
+struct Generate;
+
+impl NativeFun for Generate {
+	fn name(&self) -> &'static str { "std.complete.generate" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let script = context.runtime.scripts
+			.first()
+			.and_then(|&script| context.interner().resolve(script))
+			.map(|script| PathBuf::from(OsStr::from_bytes(script)))
+			.unwrap_or_default();
+
+		let (shell, name) = match context.args() {
+			[ Value::String(ref shell) ] => (
+				shell.copy(),
+				script
+					.file_name()
+					.map(|name| name.as_bytes().to_owned())
+					.unwrap_or_default(),
+			),
+
+			[ Value::String(ref shell), Value::String(ref name) ] => (
+				shell.copy(),
+				name.as_bytes().to_owned(),
+			),
+
+			[ Value::String(_), other ] => return Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+			[ other ] | [ other, _ ] => return Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let interp = std::env::current_exe()
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		let script = script.canonicalize().unwrap_or(script);
+
+		let glue = Glue {
+			interp: quote(interp.as_os_str().as_bytes()),
+			script: quote(script.as_os_str().as_bytes()),
+			name: quote(&name),
+			function: function_name(&name),
+		};
+
+		match shell.as_bytes() {
+			b"bash" => Ok(glue.bash().into()),
+			b"zsh" => Ok(glue.zsh().into()),
+			_ => Err(Panic::value_error(shell.into(), "\"bash\" or \"zsh\"", context.pos)),
+		}
+	}
+}
+
+
+/// Compute the completions for the last word, given the preceding words.
+fn complete(context: &mut CallContext, mut spec: Dict, words: Vec<Value>) -> Result<Vec<Str>, Panic> {
+	let pos = context.pos.copy();
+
+	let (current, previous) = match words.split_last() {
+		Some((Value::String(ref current), previous)) => (current.as_bytes(), previous),
+		_ => (&[][..], &words[..]),
+	};
+
+	for word in previous {
+		let command = match spec.get(&"commands".into()) {
+			Ok(Value::Dict(ref commands)) => commands.get(word).ok(),
+			_ => None,
+		};
+
+		if let Some(Value::Dict(ref command)) = command {
+			spec = command.copy();
+		}
+	}
+
+	let mut candidates = Vec::new();
+
+	if current.starts_with(b"-") {
+		candidates.extend(strings(spec.get(&"options".into()).unwrap_or_default(), &pos)?);
+	} else {
+		if let Ok(Value::Dict(ref commands)) = spec.get(&"commands".into()) {
+			for (command, _) in commands.borrow().iter() {
+				match command {
+					Value::String(ref command) => candidates.push(command.copy()),
+					other => return Err(Panic::type_error(other.copy(), "string", pos)),
+				}
+			}
+		}
+
+		let args = match spec.get(&"args".into()) {
+			Ok(Value::Function(ref fun)) => {
+				let args_start = context.runtime.arguments.len();
+				let words: Vec<Value> = words.iter().map(Value::copy).collect();
+				context.runtime.arguments.push(words.into());
+				context.call(Value::default(), fun, args_start)?
+			}
+
+			Ok(args) => args,
+			Err(_) => Value::default(),
+		};
+
+		candidates.extend(strings(args, &pos)?);
+	}
+
+	candidates.retain(|candidate| candidate.as_bytes().starts_with(current));
+
+	Ok(candidates)
+}
+
+
+/// Convert an array of candidates, which may be nil.
+fn strings(value: Value, pos: &SourcePos) -> Result<Vec<Str>, Panic> {
+	match value {
+		Value::Nil => Ok(Vec::new()),
+
+		Value::Array(ref array) => array
+			.borrow()
+			.iter()
+			.map(
+				|value| match value {
+					Value::String(ref string) => Ok(string.copy()),
+					other => Err(Panic::type_error(other.copy(), "string", pos.copy())),
+				}
+			)
+			.collect(),
+
+		other => Err(Panic::type_error(other, "array", pos.copy())),
+	}
+}
+
+
+/// Shell quote a string, to be used in the glue.
+fn quote(string: &[u8]) -> String {
+	String::from_utf8_lossy(&util::shell_quote(string)).into_owned()
+}
+
+
+/// A valid shell function name for the given command name.
+fn function_name(name: &[u8]) -> String {
+	name
+		.iter()
+		.map(|&c| if c.is_ascii_alphanumeric() { c as char } else { '_' })
+		.collect()
+}
+
+
+/// The shell glue, with all fields already quoted.
+struct Glue {
+	interp: String,
+	script: String,
+	name: String,
+	function: String,
+}
+
+
+impl Glue {
+	fn bash(&self) -> String {
+		format!(
+			"_hush_complete_{function}() {{\n\
+			\tlocal IFS=$'\\n'\n\
+			\tCOMPREPLY=($({interp} __complete {script} \"${{COMP_WORDS[@]:1:COMP_CWORD}}\"))\n\
+			}}\n\
+			complete -o default -F _hush_complete_{function} {name}\n",
+			function = self.function,
+			interp = self.interp,
+			script = self.script,
+			name = self.name,
+		)
+	}
+
+
+	fn zsh(&self) -> String {
+		format!(
+			"_hush_complete_{function}() {{\n\
+			\tlocal -a candidates\n\
+			\tcandidates=(${{(f)\"$({interp} __complete {script} \"${{(@)words[2,CURRENT]}}\")\"}})\n\
+			\tcompadd -a candidates\n\
+			}}\n\
+			compdef _hush_complete_{function} {name}\n",
+			function = self.function,
+			interp = self.interp,
+			script = self.script,
+			name = self.name,
+		)
+	}
+}
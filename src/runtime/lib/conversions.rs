@@ -0,0 +1,186 @@
+//! Conversions between primitive types.
+//!
+//! All conversions are strict: conversions that are not possible for the given value,
+//! such as parsing an invalid string or converting an out of range number, result in an
+//! error value. Non primitive values (nil, arrays, dicts, functions and errors) cause a
+//! type error panic.
+
+use std::convert::TryFrom;
+
+use gc::{Finalize, Trace};
+
+use crate::fmt::FmtString;
+use super::{
+	CallContext,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Bool) }
+inventory::submit! { RustFun::from(Int) }
+inventory::submit! { RustFun::from(Float) }
+inventory::submit! { RustFun::from(String) }
+inventory::submit! { RustFun::from(Byte) }
+
+
+const PRIMITIVE: &str = "bool, int, float, char or string";
+
+
+/// Build the error value for an impossible conversion.
+fn conversion_error(value: &Value, target: &str) -> Value {
+	Error::new(format!("cannot convert to {}", target).into(), value.copy()).into()
+}
+
+
+/// Parse a string as an UTF-8 number. No surrounding whitespace is allowed.
+fn parse<T: std::str::FromStr>(string: &[u8]) -> Option<T> {
+	std::str::from_utf8(string)
+		.ok()
+		.and_then(|string| string.parse().ok())
+}
+
+
+/// Only the strings "true" and "false" are convertible to bool.
+#[derive(Trace, Finalize)]
+struct Bool;
+
+impl NativeFun for Bool {
+	fn name(&self) -> &'static str { "std.bool" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Bool(b) ] => Ok(Value::Bool(*b)),
+
+			[ Value::String(ref string) ] if string.as_bytes() == b"true" => Ok(Value::Bool(true)),
+			[ Value::String(ref string) ] if string.as_bytes() == b"false" => Ok(Value::Bool(false)),
+
+			[ value @ (Value::Int(_) | Value::Float(_) | Value::Byte(_) | Value::String(_)) ] => Ok(
+				conversion_error(value, "bool")
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), PRIMITIVE, context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Floats are truncated towards zero.
+#[derive(Trace, Finalize)]
+struct Int;
+
+impl NativeFun for Int {
+	fn name(&self) -> &'static str { "std.int" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(i) ] => Ok(Value::Int(*i)),
+
+			[ Value::Byte(b) ] => Ok(Value::Int(*b as i64)),
+
+			// i64::MAX is not representable as a float, and gets rounded up to 2^63.
+			[ Value::Float(f) ] if f.0 >= i64::MIN as f64 && f.0 < i64::MAX as f64 => Ok(
+				Value::Int(f.into())
+			),
+
+			[ value @ Value::String(ref string) ] => Ok(
+				parse::<i64>(string.as_bytes())
+					.map(Value::Int)
+					.unwrap_or_else(|| conversion_error(value, "int"))
+			),
+
+			[ value @ (Value::Bool(_) | Value::Float(_)) ] => Ok(conversion_error(value, "int")),
+
+			[ other ] => Err(Panic::type_error(other.copy(), PRIMITIVE, context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Float;
+
+impl NativeFun for Float {
+	fn name(&self) -> &'static str { "std.float" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Float(f) ] => Ok(Value::Float(f.copy())),
+
+			[ Value::Int(i) ] => Ok(Value::Float(i.into())),
+
+			[ Value::Byte(b) ] => Ok(Value::from(*b as f64)),
+
+			[ value @ Value::String(ref string) ] => Ok(
+				parse::<f64>(string.as_bytes())
+					.map(Value::from)
+					.unwrap_or_else(|| conversion_error(value, "float"))
+			),
+
+			[ value @ Value::Bool(_) ] => Ok(conversion_error(value, "float")),
+
+			[ other ] => Err(Panic::type_error(other.copy(), PRIMITIVE, context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Unlike std.to_string, only primitive values are accepted.
+#[derive(Trace, Finalize)]
+struct String;
+
+impl NativeFun for String {
+	fn name(&self) -> &'static str { "std.string" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(string.copy().into()),
+
+			[ value @ (Value::Bool(_) | Value::Int(_) | Value::Float(_) | Value::Byte(_)) ] => Ok(
+				value.fmt_string(context.interner()).into()
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), PRIMITIVE, context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Ints must be in the range [0, 255], and strings must have a single byte.
+#[derive(Trace, Finalize)]
+struct Byte;
+
+impl NativeFun for Byte {
+	fn name(&self) -> &'static str { "std.byte" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Byte(b) ] => Ok(Value::Byte(*b)),
+
+			[ value @ Value::Int(i) ] => Ok(
+				u8::try_from(*i)
+					.map(Value::Byte)
+					.unwrap_or_else(|_| conversion_error(value, "char"))
+			),
+
+			[ value @ Value::String(ref string) ] => Ok(
+				match string.as_bytes() {
+					[ byte ] => Value::Byte(*byte),
+					_ => conversion_error(value, "char"),
+				}
+			),
+
+			[ value @ (Value::Bool(_) | Value::Float(_)) ] => Ok(conversion_error(value, "char")),
+
+			[ other ] => Err(Panic::type_error(other.copy(), PRIMITIVE, context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -0,0 +1,76 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Count) }
+
+/// Count the items of an array or an iterator function, consuming the latter. If a
+/// predicate is given, only items for which it returns true are counted.
+#[derive(Trace, Finalize)]
+struct Count;
+
+impl NativeFun for Count {
+	fn name(&self) -> &'static str { "std.count" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (iterable, predicate) = match context.args() {
+			[ iterable ] => (iterable.copy(), None),
+			[ iterable, Value::Function(ref predicate) ] => (iterable.copy(), Some(predicate.copy())),
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let mut count = 0;
+
+		let mut check = |context: &mut CallContext, item: Value| -> Result<(), Panic> {
+			let matches = match &predicate {
+				None => true,
+				Some(predicate) => {
+					let args_start = context.runtime.arguments.len();
+					context.runtime.arguments.push(item);
+					match context.call(Value::default(), predicate, args_start)? {
+						Value::Bool(matches) => matches,
+						other => return Err(Panic::type_error(other, "bool", context.pos.copy())),
+					}
+				}
+			};
+
+			if matches {
+				count += 1;
+			}
+
+			Ok(())
+		};
+
+		match iterable {
+			Value::Array(ref array) if predicate.is_none() => return Ok(Value::Int(array.len())),
+
+			Value::Array(ref array) => {
+				// The predicate may mutate the array, so items are fetched one at a time.
+				let mut ix = 0;
+				while let Ok(item) = array.index(ix) {
+					check(&mut context, item)?;
+					ix += 1;
+				}
+			}
+
+			Value::Function(ref iter) => {
+				while let Some(item) = context.runtime.iterate(iter, context.pos.copy())? {
+					check(&mut context, item)?;
+				}
+			}
+
+			other => return Err(Panic::type_error(other, "array or function", context.pos)),
+		}
+
+		Ok(Value::Int(count))
+	}
+}
+
@@ -0,0 +1,255 @@
+//! Cryptographic helpers for scripts which manage local credentials or validate signed
+//! payloads, without passing secrets to external programs through their arguments.
+//!
+//! Passwords are hashed with PBKDF2 using HMAC-SHA256 and a random salt, and encoded in
+//! the PHC string format, `$pbkdf2-sha256$<iterations>$<salt>$<hash>`, where the salt and
+//! hash are in base64 without padding. Comparisons of secrets take constant time.
+
+use std::convert::TryFrom;
+
+use gc::{Finalize, Trace};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use sha2::Sha256;
+
+use super::{
+	CallContext,
+	Error,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(ConstantTimeEq) }
+inventory::submit! { RustFun::from(HmacSha256) }
+inventory::submit! { RustFun::from(HmacSha256Verify) }
+inventory::submit! { RustFun::from(HashPassword) }
+inventory::submit! { RustFun::from(VerifyPassword) }
+
+
+/// The identifier of password hashes in the PHC string format.
+const PASSWORD_SCHEME: &str = "pbkdf2-sha256";
+/// The default amount of PBKDF2 iterations for password hashes.
+const PASSWORD_ITERATIONS: u32 = 600_000;
+/// The maximum amount of PBKDF2 iterations for password hashes. Hashes may come from
+/// untrusted sources, and verifying them must not take an unbounded amount of time.
+const PASSWORD_MAX_ITERATIONS: u32 = 10_000_000;
+/// The size in bytes of the random salt of password hashes.
+const PASSWORD_SALT_SIZE: usize = 16;
+
+
+/// Compare two strings in constant time, which doesn't reveal the position of the first
+/// difference. Only the length of the strings may be inferred from the timing.
+#[derive(Trace, Finalize)]
+struct ConstantTimeEq;
+
+impl NativeFun for ConstantTimeEq {
+	fn name(&self) -> &'static str { "std.crypto.constant_time_eq" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref a), Value::String(ref b) ] => Ok(
+				constant_time_eq(a.as_bytes(), b.as_bytes()).into()
+			),
+
+			[ Value::String(_), other ] | [ other, _ ] => Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		}
+	}
+}
+
+
+/// Sign a message with HMAC-SHA256, given the key and the message. Returns the signature
+/// in lowercase hexadecimal.
+#[derive(Trace, Finalize)]
+struct HmacSha256;
+
+impl NativeFun for HmacSha256 {
+	fn name(&self) -> &'static str { "std.crypto.hmac_sha256" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref key), Value::String(ref message) ] => Ok(
+				hex::encode(hmac_sha256(key.as_bytes(), message.as_bytes())).into()
+			),
+
+			[ Value::String(_), other ] | [ other, _ ] => Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		}
+	}
+}
+
+
+/// Verify the HMAC-SHA256 signature of a message, given the key, the message and the
+/// signature in hexadecimal. The signatures are compared in constant time.
+#[derive(Trace, Finalize)]
+struct HmacSha256Verify;
+
+impl NativeFun for HmacSha256Verify {
+	fn name(&self) -> &'static str { "std.crypto.hmac_sha256_verify" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref key), Value::String(ref message), Value::String(ref signature) ] => {
+				let expected = hmac_sha256(key.as_bytes(), message.as_bytes());
+
+				let valid = hex::decode(signature.as_bytes())
+					.map(|signature| constant_time_eq(&signature, &expected))
+					.unwrap_or(false);
+
+				Ok(valid.into())
+			}
+
+			[ Value::String(_), Value::String(_), other ]
+				| [ Value::String(_), other, _ ]
+				| [ other, _, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos)),
+		}
+	}
+}
+
+
+/// Hash a password with a random salt, returning the hash in the PHC string format. The
+/// amount of iterations may optionally be given, up to 10000000, and defaults to 600000.
+#[derive(Trace, Finalize)]
+struct HashPassword;
+
+impl NativeFun for HashPassword {
+	fn name(&self) -> &'static str { "std.crypto.hash_password" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (password, iterations) = match context.args() {
+			[ Value::String(ref password) ] => (password, PASSWORD_ITERATIONS),
+
+			[ Value::String(ref password), value @ Value::Int(iterations) ] => {
+				let iterations = u32::try_from(*iterations)
+					.ok()
+					.filter(|iterations| (1 ..= PASSWORD_MAX_ITERATIONS).contains(iterations))
+					.ok_or_else(
+						|| Panic::value_error(
+							value.copy(),
+							"positive amount of iterations, up to 10000000",
+							context.pos.copy()
+						)
+					)?;
+
+				(password, iterations)
+			}
+
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			[ other, .. ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[] => return Err(Panic::invalid_args(0, 1, context.pos)),
+		};
+
+		let mut salt = [0; PASSWORD_SALT_SIZE];
+		OsRng.fill_bytes(&mut salt);
+
+		let hash = pbkdf2_sha256(password.as_bytes(), &salt, iterations);
+
+		Ok(
+			format!(
+				"${}${}${}${}",
+				PASSWORD_SCHEME,
+				iterations,
+				base64::encode_config(salt, base64::STANDARD_NO_PAD),
+				base64::encode_config(hash, base64::STANDARD_NO_PAD),
+			)
+			.into()
+		)
+	}
+}
+
+
+/// Verify a password against a hash produced by std.crypto.hash_password. Returns an error
+/// if the hash is malformed, or if its amount of iterations exceeds 10000000.
+#[derive(Trace, Finalize)]
+struct VerifyPassword;
+
+impl NativeFun for VerifyPassword {
+	fn name(&self) -> &'static str { "std.crypto.verify_password" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref password), value @ Value::String(ref hash) ] => {
+				match parse_password_hash(hash.as_bytes()) {
+					Some((iterations, salt, expected)) => {
+						let hash = pbkdf2_sha256(password.as_bytes(), &salt, iterations);
+						Ok(constant_time_eq(&hash, &expected).into())
+					}
+
+					None => Ok(Error::new("invalid password hash".into(), value.copy()).into()),
+				}
+			}
+
+			[ Value::String(_), other ] | [ other, _ ] => Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		}
+	}
+}
+
+
+/// Parse a password hash in the PHC string format, returning the amount of iterations,
+/// the salt and the hash.
+fn parse_password_hash(hash: &[u8]) -> Option<(u32, Vec<u8>, Vec<u8>)> {
+	let hash = std::str::from_utf8(hash).ok()?;
+
+	match hash.split('$').collect::<Vec<_>>().as_slice() {
+		[ "", PASSWORD_SCHEME, iterations, salt, hash ] => {
+			let iterations = iterations
+				.parse()
+				.ok()
+				.filter(|iterations| (1 ..= PASSWORD_MAX_ITERATIONS).contains(iterations))?;
+			let salt = base64::decode_config(salt, base64::STANDARD_NO_PAD).ok()?;
+			let hash = base64::decode_config(hash, base64::STANDARD_NO_PAD).ok()?;
+
+			Some((iterations, salt, hash))
+		}
+
+		_ => None,
+	}
+}
+
+
+/// Compare two byte strings, taking time which depends only on their lengths.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	if a.len() != b.len() {
+		return false;
+	}
+
+	let difference = a
+		.iter()
+		.zip(b)
+		.fold(0, |difference, (x, y)| difference | (x ^ y));
+
+	// Prevent the compiler from short-circuiting the comparison.
+	std::hint::black_box(difference) == 0
+}
+
+
+/// PBKDF2 with HMAC-SHA256, producing a single block of output (RFC 8018).
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 32] {
+	pbkdf2::pbkdf2_hmac_array::<Sha256, 32>(password, salt, iterations)
+}
+
+
+/// HMAC with SHA-256 (RFC 2104).
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+	let mut mac = Hmac::<Sha256>::new_from_slice(key)
+		.expect("HMAC accepts keys of any length");
+
+	mac.update(message);
+
+	mac.finalize().into_bytes().into()
+}
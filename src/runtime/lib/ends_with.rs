@@ -0,0 +1,35 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(EndsWith) }
+
+#[derive(Trace, Finalize)]
+struct EndsWith;
+
+impl NativeFun for EndsWith {
+	fn name(&self) -> &'static str { "std.ends_with" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string), Value::String(ref pattern) ] => Ok(
+				string
+					.as_bytes()
+					.ends_with(pattern.as_bytes())
+					.into()
+			),
+
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
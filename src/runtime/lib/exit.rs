@@ -12,6 +12,7 @@ use super::{
 
 
 inventory::submit!{ RustFun::from(Exit) }
+inventory::submit!{ RustFun::from(AtExit) }
 
 #[derive(Trace, Finalize)]
 struct Exit;
@@ -25,6 +26,8 @@ impl NativeFun for Exit {
 				let code = u8::try_from(*i)
 					.map_err(|_| Panic::value_error(val.copy(), "valid exit code", context.pos.copy()))?;
 
+				context.runtime.run_exit_handlers(context.pos.copy())?;
+
 				std::process::exit(code.into())
 			}
 
@@ -33,3 +36,28 @@ impl NativeFun for Exit {
 		}
 	}
 }
+
+
+/// Register a function to be called without arguments when the script exits, either
+/// normally, by std.exit or by a panic, such as an interruption. Functions are called in
+/// reverse order of registration.
+#[derive(Trace, Finalize)]
+struct AtExit;
+
+impl NativeFun for AtExit {
+	fn name(&self) -> &'static str { "std.at_exit" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Function(ref function) ] => {
+				let function = function.copy();
+				context.runtime.exit_handlers.push(function);
+
+				Ok(Value::Nil)
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -0,0 +1,55 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	Panic,
+	RustFun,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Expand) }
+inventory::submit! { RustFun::from(ExpandArg) }
+
+/// Expand an argument written in command syntax, performing the same home, range,
+/// collection and glob expansions as command blocks, without executing anything. Returns
+/// the array of resulting arguments, or an error if the syntax is invalid.
+#[derive(Trace, Finalize)]
+struct Expand;
+
+impl NativeFun for Expand {
+	fn name(&self) -> &'static str { "std.expand" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => {
+				let pattern = string.copy();
+				context.runtime.expand(pattern.as_bytes(), context.pos.copy())
+			}
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Build the arguments resulting from the concatenation of the given array of parts, as
+/// variable interpolation does in command blocks. Array parts produce one argument for
+/// each of their items.
+#[derive(Trace, Finalize)]
+struct ExpandArg;
+
+impl NativeFun for ExpandArg {
+	fn name(&self) -> &'static str { "std.expand_arg" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Array(ref parts) ] => {
+				context.runtime.expand_parts(&parts.borrow(), context.pos.copy())
+			}
+			[ other ] => Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
@@ -0,0 +1,185 @@
+use bstr::ByteSlice;
+
+use gc::{Finalize, Trace};
+use regex::bytes::Regex;
+
+use super::{
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+use crate::runtime::SourcePos;
+
+
+inventory::submit! { RustFun::from(ExpectOutput) }
+
+
+/// Check the result of a capture block against expectations, returning its stdout. The
+/// result may also be the error of a failed capture. Expectations are a dict supporting the
+/// following keys:
+/// - `status`: the expected exit status, or nil to accept any. Defaults to 0.
+/// - `stdout_matches` and `stderr_matches`: regexes that must match the output.
+/// - `trim`: whether to trim whitespace from the output before matching and returning it.
+///   Defaults to true.
+///
+/// If an expectation fails, returns an error describing it, whose context is a dict with
+/// the failed `expectation` key, along with the `stdout`, `stderr` and `status` of the
+/// capture. Errors which are not from a capture are returned unchanged.
+#[derive(Trace, Finalize)]
+struct ExpectOutput;
+
+impl NativeFun for ExpectOutput {
+	fn name(&self) -> &'static str { "std.expect_output" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (result, expectations) = match context.args() {
+			[ result, Value::Dict(ref expectations) ] => (result.copy(), expectations.copy()),
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		let expectations = Expectations::parse(&expectations, &context.pos)?;
+
+		let captures = match &result {
+			Value::Dict(ref dict) => dict.copy(),
+
+			Value::Error(ref error) => match &*error.context.borrow() {
+				Value::Dict(ref dict) if dict.contains(&"stdout".into()) => dict.copy(),
+				_ => return Ok(result.copy()),
+			},
+
+			other => return Err(Panic::type_error(other.copy(), "dict or error", context.pos)),
+		};
+
+		let field = |key: &str| captures.get(&key.into());
+		let (stdout, stderr, status) = match (field("stdout"), field("stderr"), field("status")) {
+			(Ok(Value::String(ref stdout)), Ok(Value::String(ref stderr)), Ok(Value::Int(status))) => {
+				(stdout.copy(), stderr.copy(), status)
+			}
+			_ => return Err(Panic::value_error(result, "capture result", context.pos)),
+		};
+
+		if let Some(expected) = expectations.status {
+			if status != expected {
+				let description = format!("expected status {}, found {}", expected, status);
+				return Ok(failure("status", description, &captures));
+			}
+		}
+
+		let output = |string: &Str| if expectations.trim {
+			string.as_bytes().trim().to_owned()
+		} else {
+			string.as_bytes().to_owned()
+		};
+
+		let stdout = output(&stdout);
+
+		let checks = [
+			("stdout_matches", "stdout", &stdout, &expectations.stdout_matches),
+			("stderr_matches", "stderr", &output(&stderr), &expectations.stderr_matches),
+		];
+
+		for (expectation, name, output, regex) in checks {
+			if let Some(regex) = regex {
+				if !regex.is_match(output) {
+					let description = format!("{} doesn't match '{}'", name, regex.as_str());
+					return Ok(failure(expectation, description, &captures));
+				}
+			}
+		}
+
+		Ok(Str::from(stdout).into())
+	}
+}
+
+
+/// Parsed expectations of a capture result.
+struct Expectations {
+	status: Option<i64>,
+	stdout_matches: Option<Regex>,
+	stderr_matches: Option<Regex>,
+	trim: bool,
+}
+
+
+impl Default for Expectations {
+	fn default() -> Self {
+		Self {
+			status: Some(0),
+			stdout_matches: None,
+			stderr_matches: None,
+			trim: true,
+		}
+	}
+}
+
+
+impl Expectations {
+	fn parse(dict: &Dict, pos: &SourcePos) -> Result<Self, Panic> {
+		let mut expectations = Self::default();
+
+		let regex = |value: &Value| match value {
+			Value::String(ref pattern) => std::str
+				::from_utf8(pattern.as_bytes())
+				.ok()
+				.and_then(|pattern| Regex::new(pattern).ok())
+				.ok_or_else(|| Panic::value_error(value.copy(), "valid regex", pos.copy())),
+
+			other => Err(Panic::type_error(other.copy(), "string", pos.copy())),
+		};
+
+		for (key, value) in dict.borrow().iter() {
+			let key_name = match key {
+				Value::String(ref string) => string.as_bytes(),
+				other => return Err(Panic::type_error(other.copy(), "string", pos.copy())),
+			};
+
+			match key_name {
+				b"status" => expectations.status = match value {
+					Value::Nil => None,
+					Value::Int(status) => Some(*status),
+					other => return Err(Panic::type_error(other.copy(), "int or nil", pos.copy())),
+				},
+
+				b"stdout_matches" => expectations.stdout_matches = Some(regex(value)?),
+
+				b"stderr_matches" => expectations.stderr_matches = Some(regex(value)?),
+
+				b"trim" => expectations.trim = match value {
+					Value::Bool(trim) => *trim,
+					other => return Err(Panic::type_error(other.copy(), "bool", pos.copy())),
+				},
+
+				_ => return Err(
+					Panic::value_error(
+						key.copy(),
+						"expectation (status, stdout_matches, stderr_matches or trim)",
+						pos.copy(),
+					)
+				),
+			}
+		}
+
+		Ok(expectations)
+	}
+}
+
+
+/// An error for a failed expectation, with the capture in the context.
+fn failure(expectation: &str, description: String, captures: &Dict) -> Value {
+	let mut context: OrderedMap = captures
+		.borrow()
+		.iter()
+		.map(|(key, value)| (key.copy(), value.copy()))
+		.collect();
+
+	context.insert("expectation".into(), expectation.into());
+
+	Error::new(description.into(), Dict::new(context).into()).into()
+}
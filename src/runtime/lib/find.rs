@@ -0,0 +1,39 @@
+use bstr::ByteSlice;
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Find) }
+
+/// The index of the first occurrence of the pattern in the string, or nil if not found.
+#[derive(Trace, Finalize)]
+struct Find;
+
+impl NativeFun for Find {
+	fn name(&self) -> &'static str { "std.find" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string), Value::String(ref pattern) ] => Ok(
+				string
+					.as_bytes()
+					.find(pattern.as_bytes())
+					.map(|ix| Value::Int(ix as i64))
+					.into()
+			),
+
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
@@ -0,0 +1,68 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	Function,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ RustFun::from(FnInfo) }
+
+/// Describe the given function, returning a dict with the following keys:
+/// - `native`: whether the function is a builtin.
+/// - `name`: the builtin's name, or the name a hush function was declared as. Nil for
+///   anonymous functions.
+/// - `params`: the amount of parameters, or nil for builtins.
+/// - `path`, `line` and `column`: the definition position, or nil for builtins.
+/// - `doc`: the doc comment preceding the definition, if any.
+#[derive(Trace, Finalize)]
+struct FnInfo;
+
+impl NativeFun for FnInfo {
+	fn name(&self) -> &'static str { "std.fn_info" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let fun = match context.args() {
+			[ Value::Function(ref fun) ] => fun,
+			[ other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let mut info = OrderedMap::new();
+
+		match fun {
+			Function::Hush(fun) => {
+				let interner = context.interner();
+				let resolve = |symbol| interner
+					.resolve(symbol)
+					.map(Value::from)
+					.unwrap_or_default();
+
+				info.insert("native".into(), false.into());
+				info.insert("name".into(), fun.name.map(resolve).unwrap_or_default());
+				info.insert("params".into(), Value::Int(fun.params as i64));
+				info.insert("path".into(), resolve(fun.pos.path));
+				info.insert("line".into(), Value::Int(fun.pos.line as i64));
+				info.insert("column".into(), Value::Int(fun.pos.column as i64));
+				info.insert("doc".into(), fun.doc.map(resolve).unwrap_or_default());
+			}
+
+			Function::Rust(fun) => {
+				info.insert("native".into(), true.into());
+				info.insert("name".into(), fun.name().into());
+
+				for key in &["params", "path", "line", "column", "doc"] {
+					info.insert((*key).into(), Value::default());
+				}
+			}
+		}
+
+		Ok(Dict::new(info).into())
+	}
+}
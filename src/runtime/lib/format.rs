@@ -0,0 +1,314 @@
+use bstr::ByteSlice;
+
+use gc::{Finalize, Trace};
+
+use crate::fmt::FmtString;
+use super::{
+	CallContext,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Fmt) }
+
+/// Format the arguments according to a template. Placeholders are either `{}`, which take
+/// the next argument, or `{N}`, which take the argument at the given index. Literal braces
+/// are written as `{{` and `}}`. Placeholders may have a spec after a colon, as in
+/// `{:>8}`, of the form `[[fill]align][#][0][width][.precision][type]`:
+/// - align: `<`, `>` or `^`. Numbers are aligned to the right by default.
+/// - `#`: prefix hexadecimal, octal and binary numbers with `0x`, `0o` or `0b`.
+/// - `0`: pad numbers with zeros after the sign.
+/// - precision: digits after the point for floats, or maximum length of other values.
+/// - type: `x`, `X`, `o` or `b` for ints and bytes in hexadecimal, octal or binary.
+///
+/// Invalid templates result in an error value.
+#[derive(Trace, Finalize)]
+struct Fmt;
+
+impl NativeFun for Fmt {
+	fn name(&self) -> &'static str { "std.fmt" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (template, args) = match context.args() {
+			[ Value::String(ref template), args @ .. ] => (template, args),
+			[ other, .. ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[] => return Err(Panic::invalid_args(0, 1, context.pos)),
+		};
+
+		let mut formatter = Formatter {
+			args,
+			next: 0,
+			interner: context.interner(),
+			output: Vec::with_capacity(template.len()),
+		};
+
+		match formatter.format(template.as_bytes()) {
+			Ok(()) => Ok(Str::from(formatter.output).into()),
+			Err(description) => Ok(
+				Error::new(description.into(), Value::String(template.copy())).into()
+			),
+		}
+	}
+}
+
+
+/// The spec of a placeholder.
+#[derive(Debug, Default)]
+struct Spec {
+	fill: Option<char>,
+	align: Option<Align>,
+	alternate: bool,
+	zero: bool,
+	width: usize,
+	precision: Option<usize>,
+	radix: Option<Radix>,
+}
+
+
+#[derive(Debug, Clone, Copy)]
+enum Align {
+	Left,
+	Right,
+	Center,
+}
+
+
+#[derive(Debug, Clone, Copy)]
+enum Radix {
+	LowerHex,
+	UpperHex,
+	Octal,
+	Binary,
+}
+
+
+struct Formatter<'a> {
+	args: &'a [Value],
+	/// The index of the argument for the next `{}` placeholder.
+	next: usize,
+	interner: &'a crate::symbol::Interner,
+	output: Vec<u8>,
+}
+
+
+impl Formatter<'_> {
+	fn format(&mut self, template: &[u8]) -> Result<(), String> {
+		let mut rest = template;
+
+		while let Some(ix) = rest.find_byteset(b"{}") {
+			self.output.extend_from_slice(&rest[..ix]);
+
+			match (rest[ix], rest.get(ix + 1)) {
+				(b'{', Some(b'{')) | (b'}', Some(b'}')) => {
+					self.output.push(rest[ix]);
+					rest = &rest[ix + 2 ..];
+				}
+
+				(b'{', _) => {
+					let end = rest[ix ..]
+						.find_byte(b'}')
+						.ok_or("unterminated placeholder")?;
+
+					let placeholder = rest[ix + 1 .. ix + end]
+						.to_str()
+						.map_err(|_| "invalid placeholder")?;
+
+					self.placeholder(placeholder)?;
+					rest = &rest[ix + end + 1 ..];
+				}
+
+				_ => return Err("unmatched '}'".into()),
+			}
+		}
+
+		self.output.extend_from_slice(rest);
+
+		Ok(())
+	}
+
+
+	fn placeholder(&mut self, placeholder: &str) -> Result<(), String> {
+		let (index, spec) = match placeholder.split_once(':') {
+			Some((index, spec)) => (index, parse_spec(spec)?),
+			None => (placeholder, Spec::default()),
+		};
+
+		let index = if index.is_empty() {
+			self.next += 1;
+			self.next - 1
+		} else {
+			index
+				.parse()
+				.map_err(|_| format!("invalid placeholder '{{{}}}'", placeholder))?
+		};
+
+		let value = self.args
+			.get(index)
+			.ok_or_else(|| format!("missing argument {}", index))?;
+
+		self.write(value, &spec)
+	}
+
+
+	fn write(&mut self, value: &Value, spec: &Spec) -> Result<(), String> {
+		let int = match value {
+			Value::Int(int) => Some(*int),
+			Value::Byte(byte) => Some(*byte as i64),
+			_ => None,
+		};
+
+		let (sign, prefix, body): (&str, &str, Vec<u8>) = match (spec.radix, int, value) {
+			(Some(radix), Some(int), _) => {
+				let (prefix, body) = match radix {
+					Radix::LowerHex => ("0x", format!("{:x}", int)),
+					Radix::UpperHex => ("0x", format!("{:X}", int)),
+					Radix::Octal => ("0o", format!("{:o}", int)),
+					Radix::Binary => ("0b", format!("{:b}", int)),
+				};
+
+				("", if spec.alternate { prefix } else { "" }, body.into_bytes())
+			}
+
+			(Some(_), None, _) => return Err(format!("invalid radix for {}", value.get_type())),
+
+			(None, _, Value::Int(int)) => split_sign(int.to_string()),
+
+			(None, _, Value::Float(float)) => split_sign(
+				match spec.precision {
+					Some(precision) => format!("{:.*}", precision, float.0),
+					None => value.fmt_string(self.interner),
+				}
+			),
+
+			(None, _, _) => {
+				let mut body = match value {
+					Value::String(string) => string.as_bytes().to_owned(),
+					other => other.fmt_string(self.interner).into_bytes(),
+				};
+
+				if let Some(precision) = spec.precision {
+					if let Some((end, _, _)) = body.char_indices().nth(precision) {
+						body.truncate(end);
+					}
+				}
+
+				("", "", body)
+			}
+		};
+
+		let numeric = matches!(value, Value::Int(_) | Value::Float(_)) || spec.radix.is_some();
+		let length = sign.len() + prefix.len() + body.chars().count();
+		let padding = spec.width.saturating_sub(length);
+
+		if spec.zero && numeric {
+			self.output.extend_from_slice(sign.as_bytes());
+			self.output.extend_from_slice(prefix.as_bytes());
+			self.output.resize(self.output.len() + padding, b'0');
+			self.output.extend_from_slice(&body);
+			return Ok(());
+		}
+
+		let align = spec.align.unwrap_or(if numeric { Align::Right } else { Align::Left });
+		let (before, after) = match align {
+			Align::Left => (0, padding),
+			Align::Right => (padding, 0),
+			Align::Center => (padding / 2, padding - padding / 2),
+		};
+
+		let mut fill = [0; 4];
+		let fill = spec.fill.unwrap_or(' ').encode_utf8(&mut fill).as_bytes();
+
+		for _ in 0 .. before {
+			self.output.extend_from_slice(fill);
+		}
+
+		self.output.extend_from_slice(sign.as_bytes());
+		self.output.extend_from_slice(prefix.as_bytes());
+		self.output.extend_from_slice(&body);
+
+		for _ in 0 .. after {
+			self.output.extend_from_slice(fill);
+		}
+
+		Ok(())
+	}
+}
+
+
+/// Parse a spec of the form `[[fill]align][#][0][width][.precision][type]`.
+fn parse_spec(spec: &str) -> Result<Spec, String> {
+	let invalid = || format!("invalid format spec '{}'", spec);
+
+	let parse_align = |c| match c {
+		'<' => Some(Align::Left),
+		'>' => Some(Align::Right),
+		'^' => Some(Align::Center),
+		_ => None,
+	};
+
+	let mut result = Spec::default();
+	let mut chars = spec.chars();
+	let mut rest = spec;
+
+	match (chars.next(), chars.next()) {
+		(Some(fill), Some(align)) if parse_align(align).is_some() => {
+			result.fill = Some(fill);
+			result.align = parse_align(align);
+			rest = &rest[fill.len_utf8() + 1 ..];
+		}
+
+		(Some(align), _) if parse_align(align).is_some() => {
+			result.align = parse_align(align);
+			rest = &rest[1 ..];
+		}
+
+		_ => (),
+	}
+
+	if let Some(stripped) = rest.strip_prefix('#') {
+		result.alternate = true;
+		rest = stripped;
+	}
+
+	if let Some(stripped) = rest.strip_prefix('0') {
+		result.zero = true;
+		rest = stripped;
+	}
+
+	let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+	if digits > 0 {
+		result.width = rest[..digits].parse().map_err(|_| invalid())?;
+		rest = &rest[digits ..];
+	}
+
+	if let Some(stripped) = rest.strip_prefix('.') {
+		let digits = stripped.find(|c: char| !c.is_ascii_digit()).unwrap_or(stripped.len());
+		result.precision = Some(stripped[..digits].parse().map_err(|_| invalid())?);
+		rest = &stripped[digits ..];
+	}
+
+	result.radix = match rest {
+		"" => None,
+		"x" => Some(Radix::LowerHex),
+		"X" => Some(Radix::UpperHex),
+		"o" => Some(Radix::Octal),
+		"b" => Some(Radix::Binary),
+		_ => return Err(invalid()),
+	};
+
+	Ok(result)
+}
+
+
+/// Split the sign from a formatted number.
+fn split_sign(number: String) -> (&'static str, &'static str, Vec<u8>) {
+	match number.strip_prefix('-') {
+		Some(digits) => ("-", "", digits.as_bytes().to_owned()),
+		None => ("", "", number.into_bytes()),
+	}
+}
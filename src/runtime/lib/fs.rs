@@ -0,0 +1,321 @@
+use std::{
+	collections::HashSet,
+	ffi::OsStr,
+	fs::{self, Metadata},
+	os::unix::{ffi::OsStrExt, fs::MetadataExt},
+	path::{Path, PathBuf},
+	time::UNIX_EPOCH,
+};
+
+use gc::{Finalize, GcCell, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	NativeIter,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+use crate::runtime::SourcePos;
+
+
+inventory::submit! { RustFun::from(Walk) }
+
+
+/// Walk a directory tree, returning a lazy iterator of entries. Each entry is a dict with
+/// the `path`, `type` ("file", "dir", "symlink" or "other"), `size`, `mtime` (seconds
+/// since the unix epoch) and `depth` (the root has depth 0). Entries that can't be read
+/// are produced as errors.
+/// The optional options dict supports the following keys:
+/// - `min_depth` and `max_depth`: the range of depths of the produced entries.
+/// - `include`: glob pattern or array of patterns. Only matching entries are produced.
+/// - `exclude`: glob pattern or array of patterns. Matching entries are skipped, and
+///   matching directories are not descended.
+/// - `follow_symlinks`: whether to descend into symlinked directories. Defaults to false.
+///   Each directory is descended only once, which prevents symlink loops.
+///
+/// Patterns containing a slash are matched against the path relative to the root, others
+/// against the entry's file name.
+#[derive(Trace, Finalize)]
+struct Walk;
+
+impl NativeFun for Walk {
+	fn name(&self) -> &'static str { "std.fs.walk" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (root, options) = match context.args() {
+			[ Value::String(ref root) ] => (root.copy(), Options::default()),
+
+			[ Value::String(ref root), Value::Dict(ref options) ] => (
+				root.copy(),
+				Options::parse(options, &context.pos)?,
+			),
+
+			[ Value::String(_), other ] => return Err(
+				Panic::type_error(other.copy(), "dict", context.pos)
+			),
+			[ other ] | [ other, _ ] => return Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let root = PathBuf::from(OsStr::from_bytes(root.as_bytes()));
+
+		Ok(
+			WalkImpl(
+				GcCell::new(
+					WalkerCell(
+						Walker {
+							pending: vec![(root.clone(), 0)],
+							root,
+							options,
+							visited: HashSet::new(),
+						}
+					)
+				)
+			).into()
+		)
+	}
+}
+
+
+/// Options for walking a directory tree.
+struct Options {
+	min_depth: usize,
+	max_depth: usize,
+	include: Vec<glob::Pattern>,
+	exclude: Vec<glob::Pattern>,
+	follow_symlinks: bool,
+}
+
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			min_depth: 0,
+			max_depth: usize::MAX,
+			include: Vec::new(),
+			exclude: Vec::new(),
+			follow_symlinks: false,
+		}
+	}
+}
+
+
+impl Options {
+	fn parse(options: &Dict, pos: &SourcePos) -> Result<Self, Panic> {
+		let mut result = Self::default();
+
+		let depth = |key: &str| -> Result<Option<usize>, Panic> {
+			match options.get(&key.into()) {
+				Err(_) | Ok(Value::Nil) => Ok(None),
+				Ok(Value::Int(depth)) if depth >= 0 => Ok(Some(depth as usize)),
+				Ok(Value::Int(depth)) => Err(
+					Panic::value_error(Value::Int(depth), "non-negative int", pos.copy())
+				),
+				Ok(other) => Err(Panic::type_error(other, "int", pos.copy())),
+			}
+		};
+
+		let patterns = |key: &str| -> Result<Vec<glob::Pattern>, Panic> {
+			let pattern = |value: &Value| match value {
+				Value::String(ref string) => std::str
+					::from_utf8(string.as_bytes())
+					.ok()
+					.and_then(|pattern| glob::Pattern::new(pattern).ok())
+					.ok_or_else(|| Panic::value_error(value.copy(), "valid glob pattern", pos.copy())),
+
+				other => Err(Panic::type_error(other.copy(), "string", pos.copy())),
+			};
+
+			match options.get(&key.into()) {
+				Err(_) | Ok(Value::Nil) => Ok(Vec::new()),
+				Ok(Value::Array(ref array)) => array.borrow().iter().map(pattern).collect(),
+				Ok(value) => Ok(vec![pattern(&value)?]),
+			}
+		};
+
+		if let Some(min_depth) = depth("min_depth")? {
+			result.min_depth = min_depth;
+		}
+
+		if let Some(max_depth) = depth("max_depth")? {
+			result.max_depth = max_depth;
+		}
+
+		result.include = patterns("include")?;
+		result.exclude = patterns("exclude")?;
+
+		match options.get(&"follow_symlinks".into()) {
+			Err(_) | Ok(Value::Nil) => (),
+			Ok(Value::Bool(follow)) => result.follow_symlinks = follow,
+			Ok(other) => return Err(Panic::type_error(other, "bool", pos.copy())),
+		}
+
+		Ok(result)
+	}
+}
+
+
+/// The state of a directory walk.
+struct Walker {
+	root: PathBuf,
+	/// Entries yet to be visited, in reverse order, along with their depth.
+	pending: Vec<(PathBuf, usize)>,
+	options: Options,
+	/// Visited directories, by device and inode, to avoid symlink loops.
+	visited: HashSet<(u64, u64)>,
+}
+
+
+impl Walker {
+	/// Get the next entry, if any.
+	fn next(&mut self) -> Option<Value> {
+		while let Some((path, depth)) = self.pending.pop() {
+			let metadata = if self.options.follow_symlinks {
+				fs::metadata(&path).or_else(|_| fs::symlink_metadata(&path))
+			} else {
+				fs::symlink_metadata(&path)
+			};
+
+			let metadata = match metadata {
+				Ok(metadata) => metadata,
+				Err(error) => return Some(Self::error(error, &path)),
+			};
+
+			let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+
+			if depth > 0 && Self::matches(&self.options.exclude, relative) {
+				continue;
+			}
+
+			if metadata.is_dir() && depth < self.options.max_depth {
+				if let Err(error) = self.descend(&path, depth, &metadata) {
+					return Some(Self::error(error, &path));
+				}
+			}
+
+			let included = self.options.include.is_empty()
+				|| Self::matches(&self.options.include, relative);
+
+			if depth >= self.options.min_depth && depth <= self.options.max_depth && included {
+				return Some(Self::entry(path, depth, &metadata));
+			}
+		}
+
+		None
+	}
+
+
+	/// Push the children of the given directory to the pending entries.
+	fn descend(&mut self, path: &Path, depth: usize, metadata: &Metadata) -> std::io::Result<()> {
+		if !self.visited.insert((metadata.dev(), metadata.ino())) {
+			return Ok(()); // Already visited, through a symlink.
+		}
+
+		let mut children = fs::read_dir(path)?
+			.map(|entry| entry.map(|entry| entry.path()))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		// Sort in reverse, as pending entries are popped from the end.
+		children.sort_unstable_by(|a, b| b.cmp(a));
+
+		self.pending.extend(children.into_iter().map(|child| (child, depth + 1)));
+
+		Ok(())
+	}
+
+
+	fn matches(patterns: &[glob::Pattern], relative: &Path) -> bool {
+		let file_name = relative
+			.file_name()
+			.map(Path::new)
+			.unwrap_or(relative);
+
+		let options = glob::MatchOptions {
+			require_literal_separator: true,
+			..glob::MatchOptions::default()
+		};
+
+		patterns.iter().any(
+			|pattern| if pattern.as_str().contains('/') {
+				pattern.matches_path_with(relative, options)
+			} else {
+				pattern.matches_path_with(file_name, options)
+			}
+		)
+	}
+
+
+	fn entry(path: PathBuf, depth: usize, metadata: &Metadata) -> Value {
+		let file_type = metadata.file_type();
+		let file_type = if file_type.is_file() {
+			"file"
+		} else if file_type.is_dir() {
+			"dir"
+		} else if file_type.is_symlink() {
+			"symlink"
+		} else {
+			"other"
+		};
+
+		let mtime = metadata
+			.modified()
+			.ok()
+			.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+			.map(|duration| Value::from(duration.as_secs_f64()));
+
+		let mut entry = OrderedMap::new();
+		entry.insert("path".into(), Str::from(path).into());
+		entry.insert("type".into(), file_type.into());
+		entry.insert("size".into(), Value::Int(metadata.len() as i64));
+		entry.insert("mtime".into(), mtime.into());
+		entry.insert("depth".into(), Value::Int(depth as i64));
+
+		Dict::new(entry).into()
+	}
+
+
+	fn error(error: std::io::Error, path: &Path) -> Value {
+		Error::new(
+			error.to_string().into(),
+			Str::from(path.to_owned()).into()
+		).into()
+	}
+}
+
+
+/// The walker holds no garbage-collected values.
+#[derive(Finalize)]
+struct WalkerCell(Walker);
+
+
+unsafe impl Trace for WalkerCell {
+	gc::unsafe_empty_trace!();
+}
+
+
+#[derive(Trace, Finalize)]
+struct WalkImpl(GcCell<WalkerCell>);
+
+impl NativeFun for WalkImpl {
+	fn name(&self) -> &'static str { "std.fs.walk<impl>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		self.call_iter(context)
+	}
+
+	fn as_iter(&self) -> Option<&dyn NativeIter> { Some(self) }
+}
+
+impl NativeIter for WalkImpl {
+	fn next(&self, _: CallContext) -> Result<Option<Value>, Panic> {
+		Ok(self.0.borrow_mut().0.next())
+	}
+}
@@ -0,0 +1,205 @@
+//! Scheduling and statistics of garbage collection.
+//!
+//! The garbage collector is a stop-the-world mark and sweep collector, which collects
+//! automatically when the amount of allocated memory exceeds a threshold. As the threshold
+//! grows along with the heap, long loops which allocate a lot may see long pauses at
+//! unpredictable points. Scripts may schedule collections every given number of loop
+//! iterations instead. Every collection marks the whole live heap, so a scheduled
+//! collection is not cheaper than an automatic one. It reclaims garbage before the
+//! threshold is reached though, which avoids the automatic collections and keeps the
+//! threshold from growing.
+//!
+//! The pauses of scheduled and explicit collections are measured. Automatic collections
+//! happen inside the collector, so they are only counted, by allocating an unreachable
+//! sentinel which is finalized by the next collection.
+
+use std::{
+	cell::Cell,
+	time::{Duration, Instant},
+};
+
+use gc::{Finalize, Gc, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Collect) }
+inventory::submit! { RustFun::from(Schedule) }
+inventory::submit! { RustFun::from(Stats) }
+
+
+thread_local! {
+	/// Whether the sentinel has been finalized by a collection.
+	static SENTINEL_COLLECTED: Cell<bool> = const { Cell::new(false) };
+}
+
+
+/// An object which is never reachable, and therefore is finalized by the next collection.
+#[derive(Trace)]
+struct Sentinel;
+
+impl Finalize for Sentinel {
+	fn finalize(&self) {
+		// The collector may finalize remaining objects when the thread exits.
+		let _ = SENTINEL_COLLECTED.try_with(|collected| collected.set(true));
+	}
+}
+
+
+/// Collections triggered by the runtime, see std.gc.schedule.
+#[derive(Debug, Default)]
+pub struct Collector {
+	/// Collect every given number of loop iterations.
+	interval: Option<u64>,
+	/// Loop iterations since the last collection.
+	iterations: u64,
+	/// Whether a sentinel is waiting for the next collection.
+	armed: bool,
+	automatic_collections: u64,
+	collections: u64,
+	last_pause: Duration,
+	max_pause: Duration,
+	total_pause: Duration,
+}
+
+
+impl Collector {
+	/// Count a loop iteration, collecting if the scheduled interval has elapsed.
+	pub fn tick(&mut self) {
+		self.observe();
+
+		if let Some(interval) = self.interval {
+			self.iterations += 1;
+
+			if self.iterations >= interval {
+				self.collect();
+			}
+		}
+	}
+
+
+	/// Collect garbage, recording the pause.
+	pub fn collect(&mut self) {
+		self.observe();
+
+		let start = Instant::now();
+		gc::force_collect();
+		let pause = start.elapsed();
+
+		// The sentinel was finalized by this collection, which is not an automatic one.
+		SENTINEL_COLLECTED.with(|collected| collected.set(false));
+		self.armed = false;
+		self.observe();
+
+		self.iterations = 0;
+		self.collections += 1;
+		self.last_pause = pause;
+		self.max_pause = self.max_pause.max(pause);
+		self.total_pause += pause;
+	}
+
+
+	/// Count an automatic collection if the sentinel was finalized, and allocate a new
+	/// sentinel if needed. Objects can't be allocated during a collection, so a collection
+	/// is missed if another one happens before the next call.
+	fn observe(&mut self) {
+		if self.armed && SENTINEL_COLLECTED.with(|collected| collected.replace(false)) {
+			self.automatic_collections += 1;
+			self.armed = false;
+		}
+
+		if !self.armed {
+			drop(Gc::new(Sentinel));
+			self.armed = true;
+		}
+	}
+}
+
+
+/// Collect garbage immediately.
+#[derive(Trace, Finalize)]
+struct Collect;
+
+impl NativeFun for Collect {
+	fn name(&self) -> &'static str { "std.gc.collect" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => {
+				context.runtime.collector.collect();
+				Ok(Value::default())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos)),
+		}
+	}
+}
+
+
+/// Collect garbage every given number of loop iterations, counting iterations of all
+/// `while` and `for` loops. Nil disables scheduled collections, which is the default.
+#[derive(Trace, Finalize)]
+struct Schedule;
+
+impl NativeFun for Schedule {
+	fn name(&self) -> &'static str { "std.gc.schedule" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let interval = match context.args() {
+			[ Value::Nil ] => None,
+			[ Value::Int(interval) ] if *interval > 0 => Some(*interval as u64),
+			[ value @ Value::Int(_) ] => return Err(
+				Panic::value_error(value.copy(), "positive interval", context.pos)
+			),
+			[ other ] => return Err(Panic::type_error(other.copy(), "int or nil", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let collector = &mut context.runtime.collector;
+		collector.interval = interval;
+		collector.iterations = 0;
+
+		Ok(Value::default())
+	}
+}
+
+
+/// Statistics of garbage collection. Returns a dict with the amount of scheduled and
+/// explicit `collections`, their `last_pause`, `max_pause` and `total_pause` in
+/// milliseconds, and the amount of `automatic_collections`, which is counted on loop
+/// iterations and collections.
+#[derive(Trace, Finalize)]
+struct Stats;
+
+impl NativeFun for Stats {
+	fn name(&self) -> &'static str { "std.gc.stats" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let collector = &mut context.runtime.collector;
+		collector.observe();
+
+		let millis = |pause: Duration| Value::from(pause.as_secs_f64() * 1000.0);
+
+		let mut dict = OrderedMap::new();
+		dict.insert("collections".into(), Value::Int(collector.collections as i64));
+		dict.insert("last_pause".into(), millis(collector.last_pause));
+		dict.insert("max_pause".into(), millis(collector.max_pause));
+		dict.insert("total_pause".into(), millis(collector.total_pause));
+		dict.insert("automatic_collections".into(), Value::Int(collector.automatic_collections as i64));
+
+		Ok(Dict::new(dict).into())
+	}
+}
@@ -0,0 +1,71 @@
+use bstr::ByteSlice;
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Get) }
+
+/// Safe access of nested values, using a dotted path such as "a.b.0.c".
+/// Path segments are dict keys, array or string indices, or error fields. If any segment
+/// is missing, the default value (or nil, if omitted) is returned instead of panicking.
+/// Segments that look like integers are also tried as int keys in dicts.
+#[derive(Trace, Finalize)]
+struct Get;
+
+impl NativeFun for Get {
+	fn name(&self) -> &'static str { "std.get" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, path, default) = match context.args() {
+			[ value, Value::String(ref path) ] => (value, path, Value::default()),
+			[ value, Value::String(ref path), default ] => (value, path, default.copy()),
+
+			[ _, other ] | [ _, other, _ ] => return Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		if path.is_empty() {
+			return Ok(value.copy());
+		}
+
+		let result = path
+			.as_bytes()
+			.split_str(".")
+			.try_fold(value.copy(), get);
+
+		Ok(result.unwrap_or(default))
+	}
+}
+
+
+/// Get a single segment of the path.
+fn get(value: Value, segment: &[u8]) -> Option<Value> {
+	let index = std::str::from_utf8(segment)
+		.ok()
+		.and_then(|segment| segment.parse::<i64>().ok());
+
+	match (&value, index) {
+		(Value::Dict(dict), index) => dict
+			.get(&segment.into())
+			.ok()
+			.or_else(|| dict.get(&Value::Int(index?)).ok()),
+
+		(Value::Array(array), Some(index)) => array.index(index).ok(),
+
+		(Value::String(string), Some(index)) => string.index(index).ok(),
+
+		(Value::Error(error), _) => error.get(&segment.into()).ok(),
+
+		_ => None,
+	}
+}
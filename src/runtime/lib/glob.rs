@@ -1,7 +1,12 @@
+use std::path::PathBuf;
+
 use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
+	GlobNoMatch,
+	GlobOptions as Options,
+	GlobSort,
 	RustFun,
 	NativeFun,
 	Panic,
@@ -11,20 +16,27 @@ use super::{
 
 
 inventory::submit! { RustFun::from(Glob) }
+inventory::submit! { RustFun::from(GlobOptions) }
 
+/// Expand a glob pattern, ordering matches as configured with std.glob_options.
 #[derive(Trace, Finalize)]
 struct Glob;
 
 impl Glob {
-	fn glob(pattern: &[u8]) -> Result<Value, Error> {
+	fn glob(pattern: &[u8], sort: GlobSort) -> Result<Value, Error> {
 		let pattern = std::str::from_utf8(pattern).map_err(|_| Error::new("Invalid pattern".into(), Value::default()))?;
 		let paths = glob::glob(pattern).map_err(|error| Error::new("Invalid pattern".into(), error.msg.into()))?;
-		let paths: Vec<Value> = paths
-			.map(|result| result
-				 .map(|path| Value::String(path.into()))
-				 .map_err(|error| error.into_error().into())
-			)
+		let mut paths: Vec<PathBuf> = paths
+			.map(|result| result.map_err(|error| error.into_error().into()))
 			.collect::<Result<_, Error>>()?;
+
+		sort.sort(&mut paths);
+
+		let paths: Vec<Value> = paths
+			.into_iter()
+			.map(|path| Value::String(path.into()))
+			.collect();
+
 		Ok(paths.into())
 	}
 }
@@ -35,7 +47,7 @@ impl NativeFun for Glob {
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
 		    [ Value::String(ref string) ] => {
-				let result = Self::glob(string.as_ref());
+				let result = Self::glob(string.as_ref(), context.runtime.glob_options.sort);
 				Ok(result.unwrap_or_else(Into::into))
 			},
 		    [ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
@@ -43,3 +55,62 @@ impl NativeFun for Glob {
 		}
 	}
 }
+
+
+/// Configure glob expansion of command arguments, given a dict with the following
+/// optional keys:
+/// - `sort`: "name" to sort matches lexicographically, the default, or "mtime" to sort
+///   them by modification time, newest first.
+/// - `dedup`: whether to omit files already matched by a previous pattern in the same
+///   command, false by default.
+/// - `no_match`: what to do when a pattern matches no files: "empty" to expand to no
+///   arguments, the default, "literal" to keep the pattern as an argument, or "error" to
+///   panic.
+///
+/// Patterns support `**`, which matches any number of directories. The options apply to
+/// the following command blocks, and may be changed between them.
+#[derive(Trace, Finalize)]
+struct GlobOptions;
+
+impl NativeFun for GlobOptions {
+	fn name(&self) -> &'static str { "std.glob_options" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let options = match context.args() {
+			[ Value::Dict(ref options) ] => options.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let sort = match options.get(&"sort".into()) {
+			Err(_) | Ok(Value::Nil) => GlobSort::default(),
+			Ok(Value::String(ref sort)) if sort.as_bytes() == b"name" => GlobSort::Name,
+			Ok(Value::String(ref sort)) if sort.as_bytes() == b"mtime" => GlobSort::Mtime,
+			Ok(value @ Value::String(_)) => return Err(
+				Panic::value_error(value, "\"name\" or \"mtime\"", context.pos)
+			),
+			Ok(other) => return Err(Panic::type_error(other, "string", context.pos)),
+		};
+
+		let dedup = match options.get(&"dedup".into()) {
+			Err(_) | Ok(Value::Nil) => false,
+			Ok(Value::Bool(dedup)) => dedup,
+			Ok(other) => return Err(Panic::type_error(other, "bool", context.pos)),
+		};
+
+		let no_match = match options.get(&"no_match".into()) {
+			Err(_) | Ok(Value::Nil) => GlobNoMatch::default(),
+			Ok(Value::String(ref no_match)) if no_match.as_bytes() == b"empty" => GlobNoMatch::Empty,
+			Ok(Value::String(ref no_match)) if no_match.as_bytes() == b"literal" => GlobNoMatch::Literal,
+			Ok(Value::String(ref no_match)) if no_match.as_bytes() == b"error" => GlobNoMatch::Error,
+			Ok(value @ Value::String(_)) => return Err(
+				Panic::value_error(value, "\"empty\", \"literal\" or \"error\"", context.pos)
+			),
+			Ok(other) => return Err(Panic::type_error(other, "string", context.pos)),
+		};
+
+		context.runtime.glob_options = Options { sort, dedup, no_match };
+
+		Ok(Value::default())
+	}
+}
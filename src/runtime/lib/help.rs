@@ -0,0 +1,38 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Function,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit!{ RustFun::from(Help) }
+
+/// Get the doc comment (`##`) preceding the definition of the given function, or nil if
+/// there is none. Builtin functions have no doc comments.
+#[derive(Trace, Finalize)]
+struct Help;
+
+impl NativeFun for Help {
+	fn name(&self) -> &'static str { "std.help" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Function(Function::Hush(fun)) ] => Ok(
+				fun.doc
+					.and_then(|doc| context.interner().resolve(doc))
+					.map(Value::from)
+					.into()
+			),
+
+			[ Value::Function(Function::Rust(_)) ] => Ok(Value::default()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
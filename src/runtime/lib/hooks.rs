@@ -0,0 +1,83 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(PreExec) }
+inventory::submit! { RustFun::from(PostExec) }
+inventory::submit! { RustFun::from(Clear) }
+
+
+/// Register a function to be called before spawning every process.
+/// The function receives a dict with the `argv`, `cwd`, `env` and `start` of the process.
+/// Panicking in the hook aborts the command block.
+#[derive(Trace, Finalize)]
+struct PreExec;
+
+impl NativeFun for PreExec {
+	fn name(&self) -> &'static str { "std.hooks.pre_exec" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Function(ref fun) ] => {
+				let fun = fun.copy();
+				context.runtime.exec_hooks.pre.push(fun);
+				Ok(Value::default())
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Register a function to be called after every spawned process finishes.
+/// In addition to the fields given to pre exec hooks, the dict includes the exit `status`
+/// and the `duration` in seconds.
+#[derive(Trace, Finalize)]
+struct PostExec;
+
+impl NativeFun for PostExec {
+	fn name(&self) -> &'static str { "std.hooks.post_exec" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Function(ref fun) ] => {
+				let fun = fun.copy();
+				context.runtime.exec_hooks.post.push(fun);
+				Ok(Value::default())
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Remove all registered exec hooks.
+#[derive(Trace, Finalize)]
+struct Clear;
+
+impl NativeFun for Clear {
+	fn name(&self) -> &'static str { "std.hooks.clear" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => {
+				context.runtime.exec_hooks.pre.clear();
+				context.runtime.exec_hooks.post.clear();
+				Ok(Value::default())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
@@ -0,0 +1,209 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(TimeAgo) }
+inventory::submit! { RustFun::from(Ordinal) }
+inventory::submit! { RustFun::from(Pluralize) }
+inventory::submit! { RustFun::from(Commas) }
+
+
+/// Describe a unix timestamp relative to now, such as "3 minutes ago" or "in 2 days".
+/// An optional second timestamp may be given as the reference instead of now.
+#[derive(Trace, Finalize)]
+struct TimeAgo;
+
+impl NativeFun for TimeAgo {
+	fn name(&self) -> &'static str { "std.humanize.time_ago" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (time, now) = match context.args() {
+			[ time ] => (
+				seconds(time, &context)?,
+				SystemTime::now()
+					.duration_since(UNIX_EPOCH)
+					.map(|now| now.as_secs_f64())
+					.unwrap_or_default(),
+			),
+			[ time, now ] => (seconds(time, &context)?, seconds(now, &context)?),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		Ok(time_ago(now - time).into())
+	}
+}
+
+
+/// The ordinal of an integer, such as "1st" or "22nd".
+#[derive(Trace, Finalize)]
+struct Ordinal;
+
+impl NativeFun for Ordinal {
+	fn name(&self) -> &'static str { "std.humanize.ordinal" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(n) ] => {
+				let suffix = match (n.unsigned_abs() % 10, n.unsigned_abs() % 100) {
+					(_, 11 ..= 13) => "th",
+					(1, _) => "st",
+					(2, _) => "nd",
+					(3, _) => "rd",
+					_ => "th",
+				};
+
+				Ok(format!("{}{}", n, suffix).into())
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+	}
+}
+
+
+/// Prefix a word with a count, in plural form unless the count is one, such as "3 files".
+/// The plural form is derived with English rules, unless given as the third argument.
+#[derive(Trace, Finalize)]
+struct Pluralize;
+
+impl NativeFun for Pluralize {
+	fn name(&self) -> &'static str { "std.humanize.pluralize" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (count, word) = match context.args() {
+			[ Value::Int(count), Value::String(ref word) ] => (
+				*count,
+				if *count == 1 { word.as_bytes().to_owned() } else { plural(word.as_bytes()) },
+			),
+
+			[ Value::Int(count), Value::String(ref word), Value::String(ref plural) ] => (
+				*count,
+				if *count == 1 { word.as_bytes() } else { plural.as_bytes() }.to_owned(),
+			),
+
+			[ Value::Int(_), Value::String(_), other ] => return Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+			[ Value::Int(_), other, .. ] => return Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+			[ other, _, .. ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		let mut result = format!("{} ", count).into_bytes();
+		result.extend(word);
+
+		Ok(Str::from(result).into())
+	}
+}
+
+
+/// Format an integer with thousands separators, such as "1,234,567".
+#[derive(Trace, Finalize)]
+struct Commas;
+
+impl NativeFun for Commas {
+	fn name(&self) -> &'static str { "std.humanize.commas" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(n) ] => {
+				let digits = n.unsigned_abs().to_string();
+				let mut result = String::with_capacity(digits.len() * 4 / 3 + 1);
+
+				if *n < 0 {
+					result.push('-');
+				}
+
+				for (ix, digit) in digits.chars().enumerate() {
+					if ix > 0 && (digits.len() - ix) % 3 == 0 {
+						result.push(',');
+					}
+					result.push(digit);
+				}
+
+				Ok(result.into())
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+	}
+}
+
+
+/// Get a timestamp in seconds, which may be an int or a float.
+fn seconds(value: &Value, context: &CallContext) -> Result<f64, Panic> {
+	match value {
+		Value::Int(seconds) => Ok(*seconds as f64),
+		Value::Float(seconds) => Ok(seconds.0),
+		other => Err(Panic::type_error(other.copy(), "int or float", context.pos.copy())),
+	}
+}
+
+
+/// Describe an elapsed amount of seconds, which is negative for future times.
+fn time_ago(elapsed: f64) -> String {
+	const UNITS: [(&str, f64); 6] = [
+		("year", 365.0 * 86400.0),
+		("month", 30.0 * 86400.0),
+		("day", 86400.0),
+		("hour", 3600.0),
+		("minute", 60.0),
+		("second", 1.0),
+	];
+
+	let seconds = elapsed.abs();
+
+	if seconds < 1.0 {
+		return "just now".into();
+	}
+
+	let (unit, size) = UNITS
+		.iter()
+		.copied()
+		.find(|&(_, size)| seconds >= size)
+		.unwrap_or(("second", 1.0));
+
+	let count = (seconds / size).floor() as i64;
+	let amount = format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" });
+
+	if elapsed < 0.0 {
+		format!("in {}", amount)
+	} else {
+		format!("{} ago", amount)
+	}
+}
+
+
+/// The English plural form of a word.
+fn plural(word: &[u8]) -> Vec<u8> {
+	let mut plural = word.to_owned();
+
+	let is_vowel = |c: u8| b"aeiou".contains(&c.to_ascii_lowercase());
+
+	match word {
+		[ .., c, b'y' | b'Y' ] if !is_vowel(*c) => {
+			plural.pop();
+			plural.extend_from_slice(b"ies");
+		}
+
+		[ .., b's' | b'x' | b'z' ] | [ .., b'c' | b's', b'h' ] => plural.extend_from_slice(b"es"),
+
+		_ => plural.push(b's'),
+	}
+
+	plural
+}
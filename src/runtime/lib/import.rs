@@ -25,7 +25,7 @@ use super::{
 inventory::submit! { RustFun::from(Import) }
 
 #[derive(Trace, Finalize)]
-struct Import;
+pub(super) struct Import;
 
 impl Import {
 	fn import(module_path: &Path, mut context: CallContext) -> Result<Value, Panic> {
@@ -42,6 +42,11 @@ impl Import {
 		match context.runtime.modules.get(&path) {
 			Some(module) => Ok(module.copy()), // Don't reload module if cached.
 			None => {
+				// Modules are cached only once loaded, so a cyclic import would never end.
+				if let Some(cycle) = Self::cycle(path, &context) {
+					return Err(Panic::import_cycle(cycle, context.pos));
+				}
+
 				let module = Self::load(path, &mut context)?;
 				context.runtime.modules.insert(path, module.copy());
 				Ok(module)
@@ -50,7 +55,36 @@ impl Import {
 	}
 
 
-	fn resolve_path(
+	/// The import chain from the given module to the current script, if the module is
+	/// already being imported.
+	fn cycle(path: Symbol, context: &CallContext) -> Option<Box<[Symbol]>> {
+		let interner = context.runtime.interner();
+
+		// The main script's path may not be canonical.
+		let canonical = |script: Symbol| {
+			let script = interner.resolve(script).expect("failed to resolve symbol");
+			Path::new(OsStr::from_bytes(script)).canonicalize().ok()
+		};
+
+		let target = canonical(path);
+		let scripts = &context.runtime.scripts;
+
+		let start = scripts
+			.iter()
+			.position(|&script| script == path || (target.is_some() && canonical(script) == target))?;
+
+		Some(
+			scripts[start ..]
+				.iter()
+				.copied()
+				.chain(std::iter::once(path))
+				.collect()
+		)
+	}
+
+
+	/// Resolve a path relative to the directory of the current script.
+	pub(super) fn resolve_path(
 		target_path: &Path,
 		current_path: Symbol,
 		interner: &mut symbol::Interner,
@@ -100,6 +134,7 @@ impl Import {
 				syntax::AnalysisDisplayContext {
 					max_errors: Some(20),
 					interner: context.runtime.interner(),
+					format: context.runtime.error_format,
 				}
 			));
 			return Err(Panic::import_failed(path, context.pos.copy()));
@@ -117,6 +152,7 @@ impl Import {
 						semantic::ErrorsDisplayContext {
 							max_errors: Some(20),
 							interner: context.runtime.interner(),
+							format: context.runtime.error_format,
 						}
 					));
 
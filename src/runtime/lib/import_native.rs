@@ -0,0 +1,271 @@
+//! Native modules, which are shared libraries loaded at runtime.
+//!
+//! A native module exports the following C functions:
+//!
+//! ```c
+//! typedef void (*hush_write)(void *out, const uint8_t *data, size_t len);
+//! typedef int32_t (*hush_function)(const uint8_t *args, size_t args_len, hush_write write, void *out);
+//! typedef void (*hush_register)(void *registry, const char *name, hush_function function);
+//!
+//! uint32_t hush_plugin_abi_version(void);
+//! void hush_plugin_init(hush_register register, void *registry);
+//! ```
+//!
+//! `hush_plugin_abi_version` must return the version of this interface, which is
+//! currently 1. `hush_plugin_init` registers the module's functions by calling `register`
+//! with the given registry, a function name in UTF-8, and the function.
+//!
+//! Functions receive their arguments as a JSON array, and may only take and return values
+//! which are representable in JSON, as in std.json.encode. On success, a function returns
+//! zero and writes its result as JSON by calling `write` with the given `out` pointer any
+//! number of times. On failure, it returns nonzero and writes a description of the error,
+//! which is returned to the script as an error value.
+//!
+//! # Safety
+//!
+//! Loading a native module runs arbitrary code in the interpreter's process, with no
+//! further checks. Functions are called in the interpreter's thread, and must not unwind
+//! across the interface, nor keep the given pointers after returning. Modules are never
+//! unloaded.
+
+use std::{
+	ffi::{CStr, CString},
+	io,
+	os::{raw::{c_char, c_void}, unix::ffi::OsStrExt},
+	path::Path,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	import::Import,
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(ImportNative) }
+
+
+/// The version of the native module interface.
+const ABI_VERSION: u32 = 1;
+
+
+type Write = extern "C" fn(out: *mut c_void, data: *const u8, len: usize);
+type Function = extern "C" fn(args: *const u8, args_len: usize, write: Write, out: *mut c_void) -> i32;
+type Register = extern "C" fn(registry: *mut c_void, name: *const c_char, function: Function);
+type AbiVersion = extern "C" fn() -> u32;
+type Init = extern "C" fn(register: Register, registry: *mut c_void);
+
+
+/// Load a native module from a shared library, returning a dict of its functions.
+/// Paths containing a slash are relative to the current script, while plain file names
+/// are searched in the system's library paths. Modules are cached, like in std.import.
+#[derive(Trace, Finalize)]
+struct ImportNative;
+
+impl NativeFun for ImportNative {
+	fn name(&self) -> &'static str { "std.import_native" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let name = match context.args() {
+			[ Value::String(ref string) ] => string.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let path = if name.as_bytes().contains(&b'/') {
+			Import
+				::resolve_path(
+					Path::new(&name),
+					context.pos.path,
+					context.runtime.interner_mut(),
+				)
+				.map_err(|error| Panic::io(error, context.pos.copy()))?
+		} else {
+			context.runtime.interner_mut().get_or_intern(name.as_bytes())
+		};
+
+		if let Some(module) = context.runtime.modules.get(&path) {
+			return Ok(module.copy());
+		}
+
+		let file = context.runtime
+			.interner()
+			.resolve(path)
+			.expect("failed to resolve symbol");
+
+		let functions = load(Path::new(std::ffi::OsStr::from_bytes(file)))
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		let mut dict = OrderedMap::new();
+
+		for (name, function) in functions {
+			let full_name = format!("<native>.{}", name);
+
+			let function = NativeFunction {
+				name: Box::leak(full_name.into_boxed_str()),
+				function,
+			};
+
+			dict.insert(name.into(), function.into());
+		}
+
+		let module: Value = Dict::new(dict).into();
+		context.runtime.modules.insert(path, module.copy());
+
+		Ok(module)
+	}
+}
+
+
+/// A function of a native module.
+#[derive(Finalize)]
+struct NativeFunction {
+	name: &'static str,
+	function: Function,
+}
+
+/// NativeFunction has no garbage-collected fields.
+unsafe impl Trace for NativeFunction {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for NativeFunction {
+	fn name(&self) -> &'static str { self.name }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = serde_json::to_vec(context.args())
+			.map_err(
+				|_| Panic::value_error(
+					Value::from(context.args().iter().map(Value::copy).collect::<Vec<_>>()),
+					"arguments of nil, bool, byte, int, float, string, array or dict",
+					context.pos.copy(),
+				)
+			)?;
+
+		let mut output: Vec<u8> = Vec::new();
+
+		let status = (self.function)(
+			args.as_ptr(),
+			args.len(),
+			write,
+			&mut output as *mut Vec<u8> as *mut c_void,
+		);
+
+		if status != 0 {
+			let description = String::from_utf8_lossy(&output).into_owned();
+			return Ok(Error::new(description.into(), Value::Nil).into());
+		}
+
+		serde_json::from_slice(&output)
+			.map_err(
+				|_| Panic::value_error(
+					Value::from(output.as_slice()),
+					format!("JSON result from {}", self.name),
+					context.pos,
+				)
+			)
+	}
+}
+
+
+/// Open a shared library and collect the functions it registers.
+fn load(path: &Path) -> io::Result<Vec<(String, Function)>> {
+	let path = CString::new(path.as_os_str().as_bytes())
+		.map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+	// SAFETY: the path is a valid C string. Loading the library runs its initializers,
+	// which is inherent to native modules.
+	let handle = unsafe { libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+
+	if handle.is_null() {
+		return Err(dl_error());
+	}
+
+	// SAFETY: the symbols are declared with these signatures by the module interface.
+	let (abi_version, init) = unsafe {
+		let abi_version: AbiVersion = std::mem::transmute(symbol(handle, "hush_plugin_abi_version")?);
+		let init: Init = std::mem::transmute(symbol(handle, "hush_plugin_init")?);
+		(abi_version, init)
+	};
+
+	let version = abi_version();
+	if version != ABI_VERSION {
+		return Err(
+			io::Error::new(
+				io::ErrorKind::InvalidData,
+				format!("native module interface version {}, expected {}", version, ABI_VERSION),
+			)
+		);
+	}
+
+	let mut registry: Vec<(String, Function)> = Vec::new();
+
+	init(register, &mut registry as *mut _ as *mut c_void);
+
+	Ok(registry)
+}
+
+
+/// Look up a symbol in a shared library.
+///
+/// # Safety
+/// The handle must have been returned by dlopen.
+unsafe fn symbol(handle: *mut c_void, name: &str) -> io::Result<*mut c_void> {
+	let name = CString::new(name).expect("invalid symbol name");
+	let symbol = libc::dlsym(handle, name.as_ptr());
+
+	if symbol.is_null() {
+		Err(dl_error())
+	} else {
+		Ok(symbol)
+	}
+}
+
+
+/// The last error of the dynamic loader.
+fn dl_error() -> io::Error {
+	// SAFETY: dlerror returns either null or a valid C string.
+	let message = unsafe {
+		let error = libc::dlerror();
+
+		if error.is_null() {
+			"unknown dynamic loader error".into()
+		} else {
+			CStr::from_ptr(error).to_string_lossy().into_owned()
+		}
+	};
+
+	io::Error::other(message)
+}
+
+
+extern "C" fn register(registry: *mut c_void, name: *const c_char, function: Function) {
+	// SAFETY: the registry is the vector given to hush_plugin_init, and the name is a C
+	// string, as required by the interface.
+	let (registry, name) = unsafe {
+		(
+			&mut *(registry as *mut Vec<(String, Function)>),
+			CStr::from_ptr(name),
+		)
+	};
+
+	registry.push((name.to_string_lossy().into_owned(), function));
+}
+
+
+extern "C" fn write(out: *mut c_void, data: *const u8, len: usize) {
+	// SAFETY: the output is the vector given to the function, and the data is valid for
+	// the given length, as required by the interface.
+	unsafe {
+		let output = &mut *(out as *mut Vec<u8>);
+		output.extend_from_slice(std::slice::from_raw_parts(data, len));
+	}
+}
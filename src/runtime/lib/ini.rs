@@ -0,0 +1,349 @@
+//! INI parsing and writing, in the dialect used by git config and systemd units.
+
+use bstr::ByteSlice;
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+use crate::{fmt::FmtString, runtime::SourcePos};
+
+
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Dump) }
+
+
+/// Parse an INI string into a dict of sections, each of which is a dict of keys to string
+/// values. Keys before the first section header belong to the "" section. Lines starting
+/// with `;` or `#` are comments. The optional options dict supports the following keys:
+/// - `duplicates`: how to handle repeated keys in a section. One of "last" (the default),
+///   "first", "array" (collect all values in an array) or "error".
+/// - `comments`: if true, return a dict with `sections` and `comments`, the latter mapping
+///   each section to a dict from keys to the comment lines preceding them. Comments
+///   preceding a section header are stored under the "" key, and comments at the end of
+///   the input under the "" key of the "" section.
+///
+/// Returns an error for invalid lines and, if requested, duplicate keys.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.ini.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (text, options) = match context.args() {
+			[ Value::String(ref text) ] => (text.copy(), None),
+			[ Value::String(ref text), Value::Dict(ref options) ] => (text.copy(), Some(options.copy())),
+			[ Value::String(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other, .. ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let options = match options {
+			Some(options) => Options::from_dict(&options, &context.pos)?,
+			None => Options::default(),
+		};
+
+		Ok(
+			parse(text.as_bytes(), &options)
+				.unwrap_or_else(|error| error)
+		)
+	}
+}
+
+
+/// Write a dict of sections as an INI string. Non-dict values at the top level, and the
+/// "" section, are written before any section header. Values may be strings, ints, floats
+/// or bools, and arrays of those are written as repeated keys. An optional comments dict,
+/// as returned by `std.ini.parse`, is written back along the keys.
+#[derive(Trace, Finalize)]
+struct Dump;
+
+impl NativeFun for Dump {
+	fn name(&self) -> &'static str { "std.ini.dump" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (sections, comments) = match context.args() {
+			[ Value::Dict(ref sections) ] => (sections.copy(), Dict::default()),
+			[ Value::Dict(ref sections), Value::Dict(ref comments) ] => (sections.copy(), comments.copy()),
+			[ Value::Dict(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other, .. ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		Ok(Str::from(dump(&sections, &comments, &context)?).into())
+	}
+}
+
+
+/// How to handle repeated keys in a section.
+#[derive(Default)]
+enum Duplicates {
+	First,
+	#[default]
+	Last,
+	Array,
+	Error,
+}
+
+
+/// Parsing options.
+#[derive(Default)]
+struct Options {
+	duplicates: Duplicates,
+	comments: bool,
+}
+
+
+impl Options {
+	fn from_dict(options: &Dict, pos: &SourcePos) -> Result<Self, Panic> {
+		let duplicates = match options.get(&"duplicates".into()) {
+			Err(_) | Ok(Value::Nil) => Duplicates::default(),
+			Ok(Value::String(ref string)) if string.as_bytes() == b"first" => Duplicates::First,
+			Ok(Value::String(ref string)) if string.as_bytes() == b"last" => Duplicates::Last,
+			Ok(Value::String(ref string)) if string.as_bytes() == b"array" => Duplicates::Array,
+			Ok(Value::String(ref string)) if string.as_bytes() == b"error" => Duplicates::Error,
+			Ok(value @ Value::String(_)) => return Err(
+				Panic::value_error(value, "\"first\", \"last\", \"array\" or \"error\"", pos.copy())
+			),
+			Ok(other) => return Err(Panic::type_error(other, "string", pos.copy())),
+		};
+
+		let comments = match options.get(&"comments".into()) {
+			Err(_) | Ok(Value::Nil) => false,
+			Ok(Value::Bool(comments)) => comments,
+			Ok(other) => return Err(Panic::type_error(other, "bool", pos.copy())),
+		};
+
+		Ok(Self { duplicates, comments })
+	}
+}
+
+
+/// Parse an INI string, returning an error value for invalid input.
+fn parse(text: &[u8], options: &Options) -> Result<Value, Value> {
+	let sections = Dict::default();
+	let comments = Dict::default();
+
+	let mut section: Value = Str::from("").into();
+	let mut pending = Vec::new();
+
+	for (ix, line) in text.lines().enumerate() {
+		let line = line.trim();
+
+		let error = |description: &str| -> Value {
+			let context = Dict::default();
+			context.insert("line".into(), Value::Int(ix as i64 + 1));
+			context.insert("text".into(), Str::from(line).into());
+			Error::new(description.into(), context.into()).into()
+		};
+
+		match line {
+			[] => (),
+
+			[ b';' | b'#', .. ] => pending.push(Str::from(line).into()),
+
+			[ b'[', name @ .., b']' ] => {
+				section = Str::from(name.trim()).into();
+				entry(&sections, &section);
+				attach(&comments, &section, Str::from("").into(), &mut pending);
+			}
+
+			_ => {
+				let (key, value) = match line.find_byte(b'=') {
+					Some(ix) => (line[..ix].trim(), line[ix + 1 ..].trim()),
+					None => return Err(error("invalid line")),
+				};
+
+				if key.is_empty() {
+					return Err(error("invalid line"));
+				}
+
+				let dict = entry(&sections, &section);
+				let key: Value = Str::from(key).into();
+				let value: Value = Str::from(value).into();
+
+				match (dict.get(&key), &options.duplicates) {
+					(Err(_), _) | (Ok(_), Duplicates::Last) => dict.insert(key.copy(), value),
+					(Ok(_), Duplicates::First) => (),
+					(Ok(Value::Array(ref array)), Duplicates::Array) => array.copy().push(value),
+					(Ok(previous), Duplicates::Array) => dict.insert(key.copy(), vec![previous, value].into()),
+					(Ok(_), Duplicates::Error) => return Err(error("duplicate key")),
+				}
+
+				attach(&comments, &section, key, &mut pending);
+			}
+		}
+	}
+
+	let empty: Value = Str::from("").into();
+	attach(&comments, &empty, empty.copy(), &mut pending);
+
+	if options.comments {
+		let result = Dict::default();
+		result.insert("sections".into(), sections.into());
+		result.insert("comments".into(), comments.into());
+		Ok(result.into())
+	} else {
+		Ok(sections.into())
+	}
+}
+
+
+/// Get the dict for the given key, inserting an empty one if missing.
+fn entry(dict: &Dict, key: &Value) -> Dict {
+	match dict.get(key) {
+		Ok(Value::Dict(ref entry)) => entry.copy(),
+		_ => {
+			let entry = Dict::default();
+			dict.insert(key.copy(), entry.copy().into());
+			entry
+		}
+	}
+}
+
+
+/// Attach the pending comment lines to the given key of a section.
+fn attach(comments: &Dict, section: &Value, key: Value, pending: &mut Vec<Value>) {
+	if pending.is_empty() {
+		return;
+	}
+
+	let lines = std::mem::take(pending);
+
+	match entry(comments, section).get(&key) {
+		Ok(Value::Array(ref array)) => {
+			let mut array = array.copy();
+			for line in lines {
+				array.push(line);
+			}
+		}
+
+		_ => entry(comments, section).insert(key, lines.into()),
+	}
+}
+
+
+/// Write a dict of sections as an INI string.
+fn dump(sections: &Dict, comments: &Dict, context: &CallContext) -> Result<Vec<u8>, Panic> {
+	let mut output = Vec::new();
+	let empty: Value = Str::from("").into();
+
+	let section_comments = |section: &Value| match comments.get(section) {
+		Ok(Value::Dict(ref dict)) => dict.copy(),
+		_ => Dict::default(),
+	};
+
+	// Global keys.
+	let global_comments = section_comments(&empty);
+	for (key, value) in sections.borrow().iter() {
+		match value {
+			Value::Dict(ref dict) if key == &empty => {
+				for (key, value) in dict.borrow().iter() {
+					write_entry(&mut output, key, value, &global_comments, context)?;
+				}
+			}
+			Value::Dict(_) => (),
+			value => write_entry(&mut output, key, value, &global_comments, context)?,
+		}
+	}
+
+	for (name, value) in sections.borrow().iter() {
+		let dict = match value {
+			Value::Dict(ref dict) if name != &empty => dict,
+			_ => continue,
+		};
+
+		let name = match name {
+			Value::String(ref name) => name,
+			other => return Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+		};
+
+		if !output.is_empty() {
+			output.push(b'\n');
+		}
+
+		let comments = section_comments(&name.copy().into());
+		write_comments(&mut output, &comments, &empty);
+
+		output.push(b'[');
+		output.extend_from_slice(name.as_bytes());
+		output.extend_from_slice(b"]\n");
+
+		for (key, value) in dict.borrow().iter() {
+			write_entry(&mut output, key, value, &comments, context)?;
+		}
+	}
+
+	write_comments(&mut output, &global_comments, &empty);
+
+	Ok(output)
+}
+
+
+/// Write the comment lines for the given key, if any.
+fn write_comments(output: &mut Vec<u8>, comments: &Dict, key: &Value) {
+	if let Ok(Value::Array(ref lines)) = comments.get(key) {
+		for line in lines.borrow().iter() {
+			if let Value::String(ref line) = line {
+				output.extend_from_slice(line.as_bytes());
+				output.push(b'\n');
+			}
+		}
+	}
+}
+
+
+/// Write a key and its value, or one line per item if the value is an array.
+fn write_entry(
+	output: &mut Vec<u8>,
+	key: &Value,
+	value: &Value,
+	comments: &Dict,
+	context: &CallContext,
+) -> Result<(), Panic> {
+	let name = match key {
+		Value::String(ref name) => name,
+		other => return Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+	};
+
+	write_comments(output, comments, key);
+
+	let mut write = |value: &Value| -> Result<(), Panic> {
+		let value: Vec<u8> = match value {
+			Value::String(ref string) => string.as_bytes().into(),
+			value @ (Value::Int(_) | Value::Float(_) | Value::Bool(_)) => value
+				.fmt_string(context.interner())
+				.into_bytes(),
+			other => return Err(
+				Panic::type_error(other.copy(), "string, int, float or bool", context.pos.copy())
+			),
+		};
+
+		output.extend_from_slice(name.as_bytes());
+		output.extend_from_slice(b" = ");
+		output.extend_from_slice(&value);
+		output.push(b'\n');
+
+		Ok(())
+	};
+
+	match value {
+		Value::Array(ref array) => {
+			for value in array.borrow().iter() {
+				write(value)?;
+			}
+			Ok(())
+		}
+
+		value => write(value),
+	}
+}
@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use gc::{Finalize, GcCell, Trace};
 
 use super::{
@@ -9,6 +7,8 @@ use super::{
 	Dict,
 	RustFun,
 	NativeFun,
+	NativeIter,
+	OrderedMap,
 	Panic,
 	Str,
 	Value,
@@ -34,10 +34,12 @@ impl NativeFun for Iter {
 
 			[ Value::Dict(ref dict) ] => Ok(
 				IterImpl::Dict {
+					// Reversed, as entries are popped from the end.
 					entries: GcCell::new(
 						dict
 							.borrow()
 							.iter()
+							.rev()
 							.map(|(k, v)| (k.copy(), v.copy()))
 							.collect()
 					)
@@ -58,6 +60,41 @@ impl NativeFun for Iter {
 }
 
 
+inventory::submit! { RustFun::from(IterSorted) }
+
+/// Iterate a dict's entries ordered by key, regardless of insertion order.
+#[derive(Trace, Finalize)]
+struct IterSorted;
+
+impl NativeFun for IterSorted {
+	fn name(&self) -> &'static str { "std.iter_sorted" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Dict(ref dict) ] => {
+				let mut entries: Vec<(Value, Value)> = dict
+					.borrow()
+					.iter()
+					.map(|(k, v)| (k.copy(), v.copy()))
+					.collect();
+
+				// Sorted in reverse, as entries are popped from the end.
+				entries.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+
+				Ok(
+					IterImpl::Dict {
+						entries: GcCell::new(entries)
+					}.into()
+				)
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
 #[derive(Trace, Finalize)]
 enum IterImpl {
 	Array {
@@ -77,13 +114,14 @@ impl NativeFun for IterImpl {
 	fn name(&self) -> &'static str { "std.iter<impl>" }
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
-		let args = context.args();
-		if !args.is_empty() {
-			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
-		}
+		self.call_iter(context)
+	}
 
-		let mut iteration = HashMap::new();
+	fn as_iter(&self) -> Option<&dyn NativeIter> { Some(self) }
+}
 
+impl NativeIter for IterImpl {
+	fn next(&self, _: CallContext) -> Result<Option<Value>, Panic> {
 		let next = match self {
 			IterImpl::Array { array, ix } => {
 				let mut ix = ix.borrow_mut();
@@ -110,7 +148,7 @@ impl NativeFun for IterImpl {
 				.pop()
 				.map(
 					|(k, v)| {
-						let mut entry = HashMap::new();
+						let mut entry = OrderedMap::new();
 
 						keys::KEY.with(
 							|key| entry.insert(key.copy(), k)
@@ -125,16 +163,6 @@ impl NativeFun for IterImpl {
 				)
 		};
 
-		keys::FINISHED.with(
-			|finished| iteration.insert(finished.copy(), next.is_none().into())
-		);
-
-		if let Some(next) = next {
-			keys::VALUE.with(
-				|value| iteration.insert(value.copy(), next)
-			);
-		}
-
-		Ok(Dict::new(iteration).into())
+		Ok(next)
 	}
 }
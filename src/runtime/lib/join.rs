@@ -0,0 +1,82 @@
+use gc::{Finalize, Trace};
+
+use crate::runtime::SourcePos;
+use super::{
+	Array,
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Join) }
+inventory::submit! { RustFun::from(ConcatAll) }
+
+/// Join an array of strings with the given separator.
+#[derive(Trace, Finalize)]
+struct Join;
+
+impl NativeFun for Join {
+	fn name(&self) -> &'static str { "std.join" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Array(ref array), Value::String(ref separator) ] => {
+				concat(array, separator.as_bytes(), &context.pos).map(Into::into)
+			}
+
+			[ Value::Array(_), other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "array", context.pos)),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Concatenate an array of strings.
+#[derive(Trace, Finalize)]
+struct ConcatAll;
+
+impl NativeFun for ConcatAll {
+	fn name(&self) -> &'static str { "std.concat_all" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Array(ref array) ] => concat(array, b"", &context.pos).map(Into::into),
+			[ other ] => Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Concatenate an array of strings with the given separator, allocating the result once.
+fn concat(array: &Array, separator: &[u8], pos: &SourcePos) -> Result<Str, Panic> {
+	let array = array.borrow();
+
+	let mut length = separator.len() * array.len().saturating_sub(1);
+	for item in array.iter() {
+		match item {
+			Value::String(ref string) => length += string.len(),
+			other => return Err(Panic::type_error(other.copy(), "string", pos.copy())),
+		}
+	}
+
+	let mut result = Vec::with_capacity(length);
+
+	for (ix, item) in array.iter().enumerate() {
+		if ix > 0 {
+			result.extend_from_slice(separator);
+		}
+
+		if let Value::String(ref string) = item {
+			result.extend_from_slice(string.as_bytes());
+		}
+	}
+
+	Ok(result.into())
+}
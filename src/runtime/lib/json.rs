@@ -1,4 +1,4 @@
-use std::{fmt, collections::HashMap, convert::TryFrom};
+use std::{fmt, convert::TryFrom};
 
 use gc::{Finalize, Trace};
 use serde::{
@@ -15,15 +15,19 @@ use super::{
 	Error,
 	Float,
 	NativeFun,
+	OrderedMap,
 	Panic,
 	RustFun,
 	Value,
 	CallContext,
 };
+use crate::runtime::SourcePos;
 
 
 inventory::submit! { RustFun::from(Encode) }
 inventory::submit! { RustFun::from(Decode) }
+inventory::submit! { RustFun::from(Dump) }
+inventory::submit! { RustFun::from(Parse) }
 
 #[derive(Trace, Finalize)]
 struct Encode;
@@ -33,16 +37,7 @@ impl NativeFun for Encode {
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
-			[ value ] => serde_json::to_string_pretty(value)
-					.map(Into::into)
-					.map_err(
-						|_| Panic::value_error(
-							value.copy(),
-							"nil, bool, byte, int, float, string, array or dict",
-							context.pos.copy()
-						)
-					),
-
+			[ value ] => encode(value, true, context.pos.copy()),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
@@ -54,21 +49,76 @@ struct Decode;
 impl NativeFun for Decode {
 	fn name(&self) -> &'static str { "std.json.decode" }
 
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		decode(context)
+	}
+}
+
+/// Serialize a value to JSON, pretty printed if the optional second argument is true.
+/// Panics if the value contains functions, errors or non-string dict keys. Durations and
+/// sizes are serialized as ints, in milliseconds and bytes.
+#[derive(Trace, Finalize)]
+struct Dump;
+
+impl NativeFun for Dump {
+	fn name(&self) -> &'static str { "std.json.dump" }
+
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
-			[ value @ Value::String(ref string) ] => Ok(
-				serde_json::from_slice(string.as_bytes())
-					.unwrap_or_else(
-						|error| Error::new(error.to_string().into(), value.copy()).into()
-					)
-			),
-
-			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+			[ value ] => encode(value, false, context.pos.copy()),
+			[ value, Value::Bool(pretty) ] => encode(value, *pretty, context.pos.copy()),
+			[ _, other ] => Err(Panic::type_error(other.copy(), "bool", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
 		}
 	}
 }
 
+/// Parse a JSON string. Objects are mapped to dicts, preserving the key order, and
+/// numbers are mapped to ints when possible. Returns an error if the string is invalid.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.json.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		decode(context)
+	}
+}
+
+fn encode(value: &Value, pretty: bool, pos: SourcePos) -> Result<Value, Panic> {
+	let result =
+		if pretty {
+			serde_json::to_string_pretty(value)
+		} else {
+			serde_json::to_string(value)
+		};
+
+	result
+		.map(Into::into)
+		.map_err(
+			|_| Panic::value_error(
+				value.copy(),
+				"nil, bool, byte, int, float, string, array or dict",
+				pos
+			)
+		)
+}
+
+fn decode(context: CallContext) -> Result<Value, Panic> {
+	match context.args() {
+		[ value @ Value::String(ref string) ] => Ok(
+			serde_json::from_slice(string.as_bytes())
+				.unwrap_or_else(
+					|error| Error::new(error.to_string().into(), value.copy()).into()
+				)
+		),
+
+		[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+	}
+}
+
 impl<'de> Deserialize<'de> for Value {
 	fn deserialize<D>(deserializer: D) -> Result<Value, D::Error>
 	where
@@ -149,7 +199,7 @@ impl<'de> Deserialize<'de> for Value {
 			{
 				match visitor.next_key()? {
 					Some(key) => {
-						let mut values = HashMap::new();
+						let mut values = OrderedMap::new();
 
 						values.insert(key, visitor.next_value()?);
 						while let Some((key, value)) = visitor.next_entry()? {
@@ -192,6 +242,10 @@ impl Serialize for Value {
 				map.end()
 			}
 
+			// Units are serialized in milliseconds and bytes.
+			Value::Duration(duration) => duration.0.serialize(serializer),
+			Value::Size(size) => size.0.serialize(serializer),
+
 			Value::Function(_) => Err(ser::Error::custom("can't serialize function")),
 			Value::Error(_) => Err(ser::Error::custom("can't serialize error")),
 		}
@@ -22,6 +22,13 @@ impl NativeFun for Length {
 			[ Value::Array(ref array) ] => Ok(Value::Int(array.len())),
 			[ Value::Dict(ref dict) ] => Ok(Value::Int(dict.len())),
 			[ Value::String(ref string) ] => Ok(Value::Int(string.len() as i64)),
+			[ value @ Value::Function(_) ] => Err(
+				Panic::type_error(
+					value.copy(),
+					"string, array or dict (use std.count to consume an iterator)",
+					context.pos
+				)
+			),
 			[ other ] => Err(Panic::type_error(other.copy(), "string, array or dict", context.pos)),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
@@ -0,0 +1,263 @@
+//! Binary serialization of Hush values.
+//!
+//! The format consists of a header followed by the tagged value. Integers and lengths are
+//! encoded in little endian. Only data values are supported: nil, bool, char, int, float,
+//! string, array, dict, duration and size. Functions and errors can't be marshaled.
+
+use std::{convert::TryInto};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	Duration,
+	Error,
+	Float,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Size,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Marshal) }
+inventory::submit! { RustFun::from(Unmarshal) }
+
+
+/// Header of marshaled data, including the format version.
+const HEADER: &[u8] = b"HSHM\x01";
+
+/// Maximum nesting of arrays and dicts. This also prevents infinite recursion when
+/// marshaling cyclic values.
+const MAX_DEPTH: usize = 512;
+
+const NIL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const BYTE: u8 = 3;
+const INT: u8 = 4;
+const FLOAT: u8 = 5;
+const STRING: u8 = 6;
+const ARRAY: u8 = 7;
+const DICT: u8 = 8;
+const DURATION: u8 = 9;
+const SIZE: u8 = 10;
+
+
+#[derive(Trace, Finalize)]
+struct Marshal;
+
+impl NativeFun for Marshal {
+	fn name(&self) -> &'static str { "std.marshal" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value ] => {
+				let mut output = Vec::from(HEADER);
+
+				marshal(value, 0, &mut output)
+					.map_err(
+						|value| Panic::value_error(
+							value,
+							"nil, bool, char, int, float, string, array, dict, duration or size",
+							context.pos.copy()
+						)
+					)?;
+
+				Ok(Str::from(output).into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Unmarshal;
+
+impl NativeFun for Unmarshal {
+	fn name(&self) -> &'static str { "std.unmarshal" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value @ Value::String(ref string) ] => {
+				let result = string
+					.as_bytes()
+					.strip_prefix(HEADER)
+					.and_then(
+						|mut input| {
+							let value = unmarshal(&mut input, 0)?;
+							if input.is_empty() { Some(value) } else { None }
+						}
+					);
+
+				Ok(
+					result.unwrap_or_else(
+						|| Error::new("invalid marshaled data".into(), value.copy()).into()
+					)
+				)
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Marshal a value, returning the offending value if it can't be marshaled.
+fn marshal(value: &Value, depth: usize, output: &mut Vec<u8>) -> Result<(), Value> {
+	if depth > MAX_DEPTH {
+		return Err(value.copy());
+	}
+
+	match value {
+		Value::Nil => output.push(NIL),
+		Value::Bool(false) => output.push(FALSE),
+		Value::Bool(true) => output.push(TRUE),
+
+		Value::Byte(byte) => {
+			output.push(BYTE);
+			output.push(*byte);
+		}
+
+		Value::Int(int) => {
+			output.push(INT);
+			output.extend_from_slice(&int.to_le_bytes());
+		}
+
+		Value::Float(float) => {
+			output.push(FLOAT);
+			output.extend_from_slice(&float.0.to_bits().to_le_bytes());
+		}
+
+		Value::String(string) => {
+			output.push(STRING);
+			marshal_len(string.len(), output);
+			output.extend_from_slice(string.as_bytes());
+		}
+
+		Value::Array(array) => {
+			let array = array.borrow();
+
+			output.push(ARRAY);
+			marshal_len(array.len(), output);
+
+			for item in array.iter() {
+				marshal(item, depth + 1, output)?;
+			}
+		}
+
+		Value::Dict(dict) => {
+			let dict = dict.borrow();
+
+			output.push(DICT);
+			marshal_len(dict.len(), output);
+
+			for (key, value) in dict.iter() {
+				marshal(key, depth + 1, output)?;
+				marshal(value, depth + 1, output)?;
+			}
+		}
+
+		Value::Duration(duration) => {
+			output.push(DURATION);
+			output.extend_from_slice(&duration.0.to_le_bytes());
+		}
+
+		Value::Size(size) => {
+			output.push(SIZE);
+			output.extend_from_slice(&size.0.to_le_bytes());
+		}
+
+		Value::Function(_) | Value::Error(_) => return Err(value.copy()),
+	}
+
+	Ok(())
+}
+
+
+fn marshal_len(len: usize, output: &mut Vec<u8>) {
+	output.extend_from_slice(&(len as u64).to_le_bytes());
+}
+
+
+/// Unmarshal a value, advancing the input. Returns None if the input is invalid.
+fn unmarshal(input: &mut &[u8], depth: usize) -> Option<Value> {
+	if depth > MAX_DEPTH {
+		return None;
+	}
+
+	let value = match take(input, 1)?[0] {
+		NIL => Value::Nil,
+		FALSE => Value::Bool(false),
+		TRUE => Value::Bool(true),
+		BYTE => Value::Byte(take(input, 1)?[0]),
+		INT => Value::Int(i64::from_le_bytes(take(input, 8)?.try_into().ok()?)),
+
+		FLOAT => {
+			let bits = u64::from_le_bytes(take(input, 8)?.try_into().ok()?);
+			Value::Float(Float(f64::from_bits(bits)))
+		}
+
+		STRING => {
+			let len = unmarshal_len(input)?;
+			take(input, len)?.into()
+		}
+
+		ARRAY => {
+			let len = unmarshal_len(input)?;
+
+			// Don't trust the length for preallocation, as the input may be malformed.
+			let mut array = Vec::new();
+			for _ in 0 .. len {
+				array.push(unmarshal(input, depth + 1)?);
+			}
+
+			array.into()
+		}
+
+		DICT => {
+			let len = unmarshal_len(input)?;
+
+			let mut dict = OrderedMap::new();
+			for _ in 0 .. len {
+				let key = unmarshal(input, depth + 1)?;
+				let value = unmarshal(input, depth + 1)?;
+				dict.insert(key, value);
+			}
+
+			Dict::new(dict).into()
+		}
+
+		DURATION => Duration(i64::from_le_bytes(take(input, 8)?.try_into().ok()?)).into(),
+		SIZE => Size(i64::from_le_bytes(take(input, 8)?.try_into().ok()?)).into(),
+
+		_ => return None,
+	};
+
+	Some(value)
+}
+
+
+fn unmarshal_len(input: &mut &[u8]) -> Option<usize> {
+	let len = u64::from_le_bytes(take(input, 8)?.try_into().ok()?);
+	len.try_into().ok()
+}
+
+
+fn take<'a>(input: &mut &'a [u8], size: usize) -> Option<&'a [u8]> {
+	if input.len() < size {
+		return None;
+	}
+
+	let (data, rest) = input.split_at(size);
+	*input = rest;
+
+	Some(data)
+}
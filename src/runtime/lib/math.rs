@@ -0,0 +1,260 @@
+use std::convert::TryFrom;
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(IsNan) }
+inventory::submit! { RustFun::from(IsFinite) }
+inventory::submit! { RustFun::from(IsInf) }
+inventory::submit! { RustFun::from(CheckedDiv) }
+inventory::submit! { RustFun::from(Floor) }
+inventory::submit! { RustFun::from(Ceil) }
+inventory::submit! { RustFun::from(Round) }
+inventory::submit! { RustFun::from(Abs) }
+inventory::submit! { RustFun::from(Pow) }
+inventory::submit! { RustFun::from(Sqrt) }
+
+
+/// Ints are always numbers.
+#[derive(Trace, Finalize)]
+struct IsNan;
+
+impl NativeFun for IsNan {
+	fn name(&self) -> &'static str { "std.math.is_nan" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Float(ref float) ] => Ok(float.is_nan().into()),
+			[ Value::Int(_) ] => Ok(false.into()),
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Ints are always finite.
+#[derive(Trace, Finalize)]
+struct IsFinite;
+
+impl NativeFun for IsFinite {
+	fn name(&self) -> &'static str { "std.math.is_finite" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Float(ref float) ] => Ok(float.is_finite().into()),
+			[ Value::Int(_) ] => Ok(true.into()),
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Check for either positive or negative infinity.
+#[derive(Trace, Finalize)]
+struct IsInf;
+
+impl NativeFun for IsInf {
+	fn name(&self) -> &'static str { "std.math.is_inf" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Float(ref float) ] => Ok(float.is_infinite().into()),
+			[ Value::Int(_) ] => Ok(false.into()),
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Division which returns an error value instead of panicking or producing inf/NaN when
+/// the denominator is zero. Integer overflow also results in an error value.
+#[derive(Trace, Finalize)]
+struct CheckedDiv;
+
+impl NativeFun for CheckedDiv {
+	fn name(&self) -> &'static str { "std.math.checked_div" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(_), denominator @ Value::Int(0) ] => Ok(
+				Error::new("division by zero".into(), denominator.copy()).into()
+			),
+
+			[ Value::Int(numerator), denominator @ Value::Int(int) ] => Ok(
+				match numerator.checked_div(*int) {
+					Some(result) => result.into(),
+					None => Error::new("integer overflow".into(), denominator.copy()).into(),
+				}
+			),
+
+			[ Value::Float(_), denominator @ Value::Float(ref float) ] if float.0 == 0.0 => Ok(
+				Error::new("division by zero".into(), denominator.copy()).into()
+			),
+
+			[ Value::Float(ref numerator), Value::Float(ref denominator) ] => Ok(
+				Value::Float(numerator.copy() / denominator.copy())
+			),
+
+			[ Value::Int(_), other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			[ Value::Float(_), other ] => Err(Panic::type_error(other.copy(), "float", context.pos)),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Round towards negative infinity, returning an int. Floats which are not representable
+/// as an int result in an error value.
+#[derive(Trace, Finalize)]
+struct Floor;
+
+impl NativeFun for Floor {
+	fn name(&self) -> &'static str { "std.math.floor" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		round_with(f64::floor, context)
+	}
+}
+
+
+/// Round towards positive infinity, returning an int. Floats which are not representable
+/// as an int result in an error value.
+#[derive(Trace, Finalize)]
+struct Ceil;
+
+impl NativeFun for Ceil {
+	fn name(&self) -> &'static str { "std.math.ceil" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		round_with(f64::ceil, context)
+	}
+}
+
+
+/// Round half away from zero, returning an int. Floats which are not representable as an
+/// int result in an error value.
+#[derive(Trace, Finalize)]
+struct Round;
+
+impl NativeFun for Round {
+	fn name(&self) -> &'static str { "std.math.round" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		round_with(f64::round, context)
+	}
+}
+
+
+/// The absolute value of i64::MIN results in an error value.
+#[derive(Trace, Finalize)]
+struct Abs;
+
+impl NativeFun for Abs {
+	fn name(&self) -> &'static str { "std.math.abs" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value @ Value::Int(int) ] => Ok(
+				match int.checked_abs() {
+					Some(result) => result.into(),
+					None => Error::new("integer overflow".into(), value.copy()).into(),
+				}
+			),
+
+			[ Value::Float(ref float) ] => Ok(float.0.abs().into()),
+			[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Exponentiation of ints or floats. Negative int exponents and integer overflow result
+/// in an error value.
+#[derive(Trace, Finalize)]
+struct Pow;
+
+impl NativeFun for Pow {
+	fn name(&self) -> &'static str { "std.math.pow" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(_), exponent @ Value::Int(int) ] if *int < 0 => Ok(
+				Error::new("negative exponent".into(), exponent.copy()).into()
+			),
+
+			[ Value::Int(base), exponent @ Value::Int(int) ] => Ok(
+				match u32::try_from(*int).ok().and_then(|int| base.checked_pow(int)) {
+					Some(result) => result.into(),
+					None => Error::new("integer overflow".into(), exponent.copy()).into(),
+				}
+			),
+
+			[ Value::Float(ref base), Value::Float(ref exponent) ] => Ok(base.0.powf(exponent.0).into()),
+
+			[ Value::Int(_), other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			[ Value::Float(_), other ] => Err(Panic::type_error(other.copy(), "float", context.pos)),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// Square root, returning a float. Negative numbers result in an error value.
+#[derive(Trace, Finalize)]
+struct Sqrt;
+
+impl NativeFun for Sqrt {
+	fn name(&self) -> &'static str { "std.math.sqrt" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let float = match context.args() {
+			[ Value::Int(int) ] => *int as f64,
+			[ Value::Float(ref float) ] => float.0,
+			[ other ] => return Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		if float < 0.0 {
+			Ok(Error::new("negative operand".into(), context.args()[0].copy()).into())
+		} else {
+			Ok(float.sqrt().into())
+		}
+	}
+}
+
+
+/// Round a float to an int with the given function. Ints are returned unchanged.
+fn round_with(round: fn(f64) -> f64, context: CallContext) -> Result<Value, Panic> {
+	match context.args() {
+		[ Value::Int(int) ] => Ok(Value::Int(*int)),
+
+		[ value @ Value::Float(ref float) ] => {
+			let rounded = round(float.0);
+
+			// i64::MAX is not representable as a float, and gets rounded up to 2^63.
+			if rounded >= i64::MIN as f64 && rounded < i64::MAX as f64 {
+				Ok(Value::Int(rounded as i64))
+			} else {
+				Ok(Error::new("integer overflow".into(), value.copy()).into())
+			}
+		}
+
+		[ other ] => Err(Panic::type_error(other.copy(), "int or float", context.pos)),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+	}
+}
@@ -0,0 +1,50 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+use crate::runtime::children::{self, Limit};
+
+
+inventory::submit! { RustFun::from(MaxChildren) }
+
+
+/// Limit the number of simultaneously running child processes, including the ones spawned
+/// by asynchronous blocks. When the limit is reached, spawning waits until a process
+/// finishes, unless the second argument is false, in which case the command fails with
+/// an error. Pipelines with more commands than the limit always fail. Nil removes the
+/// limit.
+#[derive(Trace, Finalize)]
+struct MaxChildren;
+
+impl NativeFun for MaxChildren {
+	fn name(&self) -> &'static str { "std.max_children" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (max, wait) = match context.args() {
+			[ Value::Nil ] => {
+				children::set_limit(None);
+				return Ok(Value::default());
+			}
+
+			[ Value::Int(max) ] => (*max, true),
+			[ Value::Int(max), Value::Bool(wait) ] => (*max, *wait),
+
+			[ Value::Int(_), other ] => return Err(Panic::type_error(other.copy(), "bool", context.pos)),
+			[ other ] | [ other, _ ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		if max <= 0 {
+			return Err(Panic::value_error(Value::Int(max), "positive integer", context.pos));
+		}
+
+		children::set_limit(Some(Limit { max: max as usize, wait }));
+
+		Ok(Value::default())
+	}
+}
@@ -0,0 +1,534 @@
+//! Metrics for long-running scripts, exposed in the Prometheus text format.
+//!
+//! Metrics are kept in a global registry, which may be rendered to a string, written to a
+//! text file (for the node exporter's textfile collector) or served over HTTP.
+
+use std::{
+	ffi::OsStr,
+	fmt::Write as _,
+	fs,
+	io::{self, Read, Write},
+	net::{TcpListener, TcpStream},
+	os::unix::ffi::OsStrExt,
+	path::PathBuf,
+	sync::{Mutex, MutexGuard},
+	thread,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+use crate::runtime::SourcePos;
+
+
+inventory::submit! { RustFun::from(Counter) }
+inventory::submit! { RustFun::from(Gauge) }
+inventory::submit! { RustFun::from(Histogram) }
+inventory::submit! { RustFun::from(Render) }
+inventory::submit! { RustFun::from(WriteFile) }
+inventory::submit! { RustFun::from(Serve) }
+
+
+/// Default histogram buckets, the same as in the Prometheus client libraries.
+const DEFAULT_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+
+static REGISTRY: Mutex<Vec<Metric>> = Mutex::new(Vec::new());
+
+
+/// Register a counter, which may only increase. Returns a dict with the `inc([amount])`
+/// and `get()` methods. Registering an existing counter returns the same counter.
+#[derive(Trace, Finalize)]
+struct Counter;
+
+impl NativeFun for Counter {
+	fn name(&self) -> &'static str { "std.metrics.counter" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (name, help) = name_and_help(&context)?;
+		let ix = register(name, help, Kind::Counter(0.0), &context.pos)?;
+
+		let mut dict = OrderedMap::new();
+		dict.insert("inc".into(), RustFun::from(Inc(ix)).into());
+		dict.insert("get".into(), RustFun::from(Get(ix)).into());
+
+		Ok(Dict::new(dict).into())
+	}
+}
+
+
+/// Register a gauge, which may be set to any value. Returns a dict with the `set(value)`,
+/// `inc([amount])`, `dec([amount])` and `get()` methods.
+#[derive(Trace, Finalize)]
+struct Gauge;
+
+impl NativeFun for Gauge {
+	fn name(&self) -> &'static str { "std.metrics.gauge" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (name, help) = name_and_help(&context)?;
+		let ix = register(name, help, Kind::Gauge(0.0), &context.pos)?;
+
+		let mut dict = OrderedMap::new();
+		dict.insert("set".into(), RustFun::from(Set(ix)).into());
+		dict.insert("inc".into(), RustFun::from(Inc(ix)).into());
+		dict.insert("dec".into(), RustFun::from(Dec(ix)).into());
+		dict.insert("get".into(), RustFun::from(Get(ix)).into());
+
+		Ok(Dict::new(dict).into())
+	}
+}
+
+
+/// Register a histogram, optionally with an array of bucket upper bounds. Returns a dict
+/// with the `observe(value)` and `get()` methods. The latter returns a dict with the
+/// `count` and `sum` of the observations.
+#[derive(Trace, Finalize)]
+struct Histogram;
+
+impl NativeFun for Histogram {
+	fn name(&self) -> &'static str { "std.metrics.histogram" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (name, help, buckets) = match context.args() {
+			[ Value::String(ref name), Value::String(ref help) ] => (
+				name.copy(),
+				help.copy(),
+				DEFAULT_BUCKETS.to_vec(),
+			),
+
+			[ Value::String(ref name), Value::String(ref help), Value::Array(ref buckets) ] => {
+				let mut buckets = buckets
+					.borrow()
+					.iter()
+					.map(|bucket| number(bucket, &context.pos))
+					.collect::<Result<Vec<_>, _>>()?;
+
+				buckets.sort_by(|a, b| a.total_cmp(b));
+				buckets.dedup();
+
+				(name.copy(), help.copy(), buckets)
+			}
+
+			[ Value::String(_), Value::String(_), other ] => return Err(
+				Panic::type_error(other.copy(), "array", context.pos)
+			),
+			[ Value::String(_), other ] | [ Value::String(_), other, _ ] => return Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+			[ other, _ ] | [ other, _, _ ] => return Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		let counts = vec![0; buckets.len()];
+		let kind = Kind::Histogram { buckets, counts, sum: 0.0, count: 0 };
+		let ix = register(name, help, kind, &context.pos)?;
+
+		let mut dict = OrderedMap::new();
+		dict.insert("observe".into(), RustFun::from(Observe(ix)).into());
+		dict.insert("get".into(), RustFun::from(Get(ix)).into());
+
+		Ok(Dict::new(dict).into())
+	}
+}
+
+
+/// Render all metrics in the Prometheus text format.
+#[derive(Trace, Finalize)]
+struct Render;
+
+impl NativeFun for Render {
+	fn name(&self) -> &'static str { "std.metrics.render" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => Ok(Str::from(render()).into()),
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+/// Write all metrics to the given file, in the Prometheus text format. The file is
+/// replaced atomically, so that readers never observe partial contents.
+/// Returns an error if the file can't be written.
+#[derive(Trace, Finalize)]
+struct WriteFile;
+
+impl NativeFun for WriteFile {
+	fn name(&self) -> &'static str { "std.metrics.write" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let path = match context.args() {
+			[ Value::String(ref path) ] => PathBuf::from(OsStr::from_bytes(path.as_bytes())),
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let mut temp = path.clone().into_os_string();
+		temp.push(".tmp");
+
+		let result = fs::write(&temp, render())
+			.and_then(|()| fs::rename(&temp, &path));
+
+		Ok(
+			match result {
+				Ok(()) => Value::default(),
+				Err(error) => Error::new(error.to_string().into(), Str::from(path).into()).into(),
+			}
+		)
+	}
+}
+
+
+/// Serve the metrics over HTTP in the given address, such as "127.0.0.1:9100", from a
+/// background thread. Every request is answered with the rendered metrics.
+/// Returns an error if the address can't be bound.
+#[derive(Trace, Finalize)]
+struct Serve;
+
+impl NativeFun for Serve {
+	fn name(&self) -> &'static str { "std.metrics.serve" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let address = match context.args() {
+			[ Value::String(ref address) ] => address.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let listener = match TcpListener::bind(String::from_utf8_lossy(address.as_bytes()).as_ref()) {
+			Ok(listener) => listener,
+			Err(error) => return Ok(Error::new(error.to_string().into(), address.into()).into()),
+		};
+
+		thread::spawn(
+			move || {
+				for stream in listener.incoming().flatten() {
+					// A failing client must not stop the server.
+					let _ = respond(stream);
+				}
+			}
+		);
+
+		Ok(Value::default())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Inc(usize);
+
+impl NativeFun for Inc {
+	fn name(&self) -> &'static str { "std.metrics<inc>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let amount = optional_amount(&context)?;
+
+		match &mut registry()[self.0].kind {
+			Kind::Counter(_) if amount < 0.0 => Err(
+				Panic::value_error(amount.into(), "non-negative amount", context.pos)
+			),
+			Kind::Counter(value) | Kind::Gauge(value) => {
+				*value += amount;
+				Ok(Value::default())
+			}
+			Kind::Histogram { .. } => unreachable!("inc on histogram"),
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Dec(usize);
+
+impl NativeFun for Dec {
+	fn name(&self) -> &'static str { "std.metrics<dec>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let amount = optional_amount(&context)?;
+
+		if let Kind::Gauge(value) = &mut registry()[self.0].kind {
+			*value -= amount;
+		}
+
+		Ok(Value::default())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Set(usize);
+
+impl NativeFun for Set {
+	fn name(&self) -> &'static str { "std.metrics<set>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let amount = match context.args() {
+			[ value ] => number(value, &context.pos)?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		if let Kind::Gauge(value) = &mut registry()[self.0].kind {
+			*value = amount;
+		}
+
+		Ok(Value::default())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Observe(usize);
+
+impl NativeFun for Observe {
+	fn name(&self) -> &'static str { "std.metrics<observe>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let observation = match context.args() {
+			[ value ] => number(value, &context.pos)?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		if let Kind::Histogram { buckets, counts, sum, count } = &mut registry()[self.0].kind {
+			for (bucket, bucket_count) in buckets.iter().zip(counts.iter_mut()) {
+				if observation <= *bucket {
+					*bucket_count += 1;
+				}
+			}
+
+			*sum += observation;
+			*count += 1;
+		}
+
+		Ok(Value::default())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Get(usize);
+
+impl NativeFun for Get {
+	fn name(&self) -> &'static str { "std.metrics<get>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(
+			match &registry()[self.0].kind {
+				Kind::Counter(value) | Kind::Gauge(value) => (*value).into(),
+
+				Kind::Histogram { sum, count, .. } => {
+					let mut dict = OrderedMap::new();
+					dict.insert("count".into(), Value::Int(*count as i64));
+					dict.insert("sum".into(), (*sum).into());
+					Dict::new(dict).into()
+				}
+			}
+		)
+	}
+}
+
+
+/// A registered metric.
+struct Metric {
+	name: Box<[u8]>,
+	help: Box<[u8]>,
+	kind: Kind,
+}
+
+
+enum Kind {
+	Counter(f64),
+	Gauge(f64),
+	Histogram {
+		/// Upper bounds, sorted.
+		buckets: Vec<f64>,
+		/// Observations in each bucket, cumulative.
+		counts: Vec<u64>,
+		sum: f64,
+		count: u64,
+	},
+}
+
+
+impl Kind {
+	fn name(&self) -> &'static str {
+		match self {
+			Self::Counter(_) => "counter",
+			Self::Gauge(_) => "gauge",
+			Self::Histogram { .. } => "histogram",
+		}
+	}
+}
+
+
+fn registry() -> MutexGuard<'static, Vec<Metric>> {
+	// The registry is always left consistent, even if a thread panics.
+	REGISTRY.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+
+/// Register a metric, or get the index of an existing metric of the same kind.
+fn register(name: Str, help: Str, kind: Kind, pos: &SourcePos) -> Result<usize, Panic> {
+	let valid_name = name
+		.as_bytes()
+		.iter()
+		.enumerate()
+		.all(|(ix, &c)| c == b'_' || c == b':' || c.is_ascii_alphabetic() || (ix > 0 && c.is_ascii_digit()));
+
+	if name.is_empty() || !valid_name {
+		return Err(Panic::value_error(name.into(), "valid metric name", pos.copy()));
+	}
+
+	let mut registry = registry();
+
+	if let Some(ix) = registry.iter().position(|metric| *metric.name == *name.as_bytes()) {
+		return if registry[ix].kind.name() == kind.name() {
+			Ok(ix)
+		} else {
+			Err(Panic::value_error(name.into(), "metric name not registered with another type", pos.copy()))
+		};
+	}
+
+	registry.push(
+		Metric {
+			name: name.as_bytes().into(),
+			help: help.as_bytes().into(),
+			kind,
+		}
+	);
+
+	Ok(registry.len() - 1)
+}
+
+
+/// Render all metrics in the Prometheus text format.
+fn render() -> Vec<u8> {
+	let registry = registry();
+	let mut output = String::new();
+
+	for metric in registry.iter() {
+		let name = String::from_utf8_lossy(&metric.name);
+
+		// Help text must escape backslashes and line feeds.
+		let help = String::from_utf8_lossy(&metric.help)
+			.replace('\\', "\\\\")
+			.replace('\n', "\\n");
+
+		let _ = writeln!(output, "# HELP {} {}", name, help);
+		let _ = writeln!(output, "# TYPE {} {}", name, metric.kind.name());
+
+		match &metric.kind {
+			Kind::Counter(value) | Kind::Gauge(value) => {
+				let _ = writeln!(output, "{} {}", name, format_number(*value));
+			}
+
+			Kind::Histogram { buckets, counts, sum, count } => {
+				for (bucket, bucket_count) in buckets.iter().zip(counts) {
+					let _ = writeln!(
+						output,
+						"{}_bucket{{le=\"{}\"}} {}",
+						name,
+						format_number(*bucket),
+						bucket_count
+					);
+				}
+
+				let _ = writeln!(output, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+				let _ = writeln!(output, "{}_sum {}", name, format_number(*sum));
+				let _ = writeln!(output, "{}_count {}", name, count);
+			}
+		}
+	}
+
+	output.into_bytes()
+}
+
+
+fn format_number(value: f64) -> String {
+	if value.is_nan() {
+		"NaN".into()
+	} else if value.is_infinite() {
+		if value > 0.0 { "+Inf".into() } else { "-Inf".into() }
+	} else {
+		value.to_string()
+	}
+}
+
+
+/// Answer a HTTP request with the rendered metrics.
+fn respond(mut stream: TcpStream) -> io::Result<()> {
+	// Read the request head. Its contents are irrelevant, as every path gets the metrics.
+	let mut request = Vec::new();
+	let mut buffer = [0; 1024];
+
+	while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+		let read = stream.read(&mut buffer)?;
+		if read == 0 {
+			break;
+		}
+
+		request.extend_from_slice(&buffer[..read]);
+	}
+
+	let body = render();
+
+	write!(
+		stream,
+		"HTTP/1.1 200 OK\r\n\
+		Content-Type: text/plain; version=0.0.4\r\n\
+		Content-Length: {}\r\n\
+		Connection: close\r\n\r\n",
+		body.len()
+	)?;
+
+	stream.write_all(&body)?;
+	stream.flush()
+}
+
+
+fn name_and_help(context: &CallContext) -> Result<(Str, Str), Panic> {
+	match context.args() {
+		[ Value::String(ref name) ] => Ok((name.copy(), "".into())),
+		[ Value::String(ref name), Value::String(ref help) ] => Ok((name.copy(), help.copy())),
+		[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+		[ other ] | [ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy())),
+	}
+}
+
+
+/// The amount for inc and dec, which defaults to one.
+fn optional_amount(context: &CallContext) -> Result<f64, Panic> {
+	match context.args() {
+		[] => Ok(1.0),
+		[ value ] => number(value, &context.pos),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy())),
+	}
+}
+
+
+fn number(value: &Value, pos: &SourcePos) -> Result<f64, Panic> {
+	match value {
+		Value::Int(int) => Ok(*int as f64),
+		Value::Float(float) => Ok(float.0),
+		other => Err(Panic::type_error(other.copy(), "int or float", pos.copy())),
+	}
+}
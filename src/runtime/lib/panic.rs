@@ -10,6 +10,8 @@ use super::{
 
 
 inventory::submit! { RustFun::from(UserPanic) }
+inventory::submit! { RustFun::from(SetPanicHook) }
+
 
 #[derive(Trace, Finalize)]
 struct UserPanic;
@@ -24,3 +26,28 @@ impl NativeFun for UserPanic {
 		}
 	}
 }
+
+
+/// Register a function to be called when the script panics, before the interpreter
+/// exits and after the std.at_exit handlers run. The function receives a dict with the
+/// panic's `kind`, `message`, `path`, `line` and `column`, and the `context` given to
+/// std.panic, so that scripts may report failures elsewhere. Nil removes the hook.
+#[derive(Trace, Finalize)]
+struct SetPanicHook;
+
+impl NativeFun for SetPanicHook {
+	fn name(&self) -> &'static str { "std.set_panic_hook" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let hook = match context.args() {
+			[ Value::Function(ref fun) ] => Some(fun.copy()),
+			[ Value::Nil ] => None,
+			[ other ] => return Err(Panic::type_error(other.copy(), "function or nil", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		context.runtime.panic_hook = hook;
+
+		Ok(Value::default())
+	}
+}
@@ -0,0 +1,480 @@
+//! Parsers for the output of common tools, which return arrays of dicts.
+
+use std::str::FromStr;
+
+use bstr::ByteSlice;
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(LsL) }
+inventory::submit! { RustFun::from(PsAux) }
+inventory::submit! { RustFun::from(Df) }
+inventory::submit! { RustFun::from(Passwd) }
+inventory::submit! { RustFun::from(IpAddr) }
+
+
+/// The result of parsing a line, or the error value for an invalid line.
+type ParseResult = Result<Value, Value>;
+
+
+/// Parse the output of `ls -l`, returning an array of dicts with the `mode`, `type`,
+/// `links`, `owner`, `group`, `size`, `date`, `name` and `target` keys. The type is one of
+/// "file", "dir", "link", "char", "block", "fifo" or "socket". Device files have nil size,
+/// and the `major` and `minor` keys instead. Targets are nil except for symbolic links.
+#[derive(Trace, Finalize)]
+struct LsL;
+
+impl NativeFun for LsL {
+	fn name(&self) -> &'static str { "std.parsers.ls_l" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		parse_lines(
+			context,
+			// Listings of multiple directories have a heading for each.
+			|line| {
+				line.is_empty()
+					|| line.starts_with(b"total ")
+					|| (line.ends_with(b":") && !is_mode(line))
+			},
+			|line| {
+				let (fields, rest) = split_fields(line, 5).ok_or(())?;
+				let (mode, links, owner, group) = (fields[0], fields[1], fields[2], fields[3]);
+
+				let file_type = match mode.first() {
+					Some(b'-') => "file",
+					Some(b'd') => "dir",
+					Some(b'l') => "link",
+					Some(b'c') => "char",
+					Some(b'b') => "block",
+					Some(b'p') => "fifo",
+					Some(b's') => "socket",
+					_ => return Err(()),
+				};
+
+				let mut entry = OrderedMap::new();
+				entry.insert("mode".into(), mode.into());
+				entry.insert("type".into(), file_type.into());
+				entry.insert("links".into(), Value::Int(parse_number(links)?));
+				entry.insert("owner".into(), owner.into());
+				entry.insert("group".into(), group.into());
+
+				// Device files have the major and minor numbers in place of the size.
+				let rest = match fields[4].strip_suffix(b",") {
+					Some(major) => {
+						let (fields, rest) = split_fields(rest, 1).ok_or(())?;
+						entry.insert("size".into(), Value::Nil);
+						entry.insert("major".into(), Value::Int(parse_number(major)?));
+						entry.insert("minor".into(), Value::Int(parse_number(fields[0])?));
+						rest
+					}
+
+					None => {
+						entry.insert("size".into(), Value::Int(parse_number(fields[4])?));
+						rest
+					}
+				};
+
+				let (date, name) = split_fields(rest, 3).ok_or(())?;
+				entry.insert("date".into(), date.join(&b' ').as_slice().into());
+
+				let (name, target) = match name.find(b" -> ") {
+					Some(ix) if file_type == "link" => (&name[..ix], Value::from(&name[ix + 4 ..])),
+					_ => (name, Value::Nil),
+				};
+
+				if name.is_empty() {
+					return Err(());
+				}
+
+				entry.insert("name".into(), name.into());
+				entry.insert("target".into(), target);
+
+				Ok(Dict::new(entry).into())
+			},
+			"ls -l",
+		)
+	}
+}
+
+
+/// Parse the output of `ps aux`, returning an array of dicts with the `user`, `pid`,
+/// `cpu`, `mem`, `vsz`, `rss`, `tty`, `stat`, `start`, `time` and `command` keys. The
+/// cpu and mem percentages are floats.
+#[derive(Trace, Finalize)]
+struct PsAux;
+
+impl NativeFun for PsAux {
+	fn name(&self) -> &'static str { "std.parsers.ps_aux" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		parse_lines(
+			context,
+			|line| line.is_empty() || line.starts_with(b"USER "),
+			|line| {
+				let (fields, command) = split_fields(line, 10).ok_or(())?;
+
+				let mut entry = OrderedMap::new();
+				entry.insert("user".into(), fields[0].into());
+				entry.insert("pid".into(), Value::Int(parse_number(fields[1])?));
+				entry.insert("cpu".into(), Value::from(parse_number::<f64>(fields[2])?));
+				entry.insert("mem".into(), Value::from(parse_number::<f64>(fields[3])?));
+				entry.insert("vsz".into(), Value::Int(parse_number(fields[4])?));
+				entry.insert("rss".into(), Value::Int(parse_number(fields[5])?));
+				entry.insert("tty".into(), fields[6].into());
+				entry.insert("stat".into(), fields[7].into());
+				entry.insert("start".into(), fields[8].into());
+				entry.insert("time".into(), fields[9].into());
+				entry.insert("command".into(), command.into());
+
+				Ok(Dict::new(entry).into())
+			},
+			"ps aux",
+		)
+	}
+}
+
+
+/// Parse the output of `df -k` or `df -P`, returning an array of dicts with the
+/// `filesystem`, `blocks`, `used`, `available`, `capacity` and `mounted_on` keys. Sizes
+/// are in blocks, as printed. The capacity is a percentage, or nil if unknown.
+#[derive(Trace, Finalize)]
+struct Df;
+
+impl NativeFun for Df {
+	fn name(&self) -> &'static str { "std.parsers.df" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let text = match context.args() {
+			[ Value::String(ref text) ] => text.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let mut entries = Vec::new();
+		let mut lines = text
+			.as_bytes()
+			.lines()
+			.map(ByteSlice::trim_end)
+			.filter(|line| !line.is_empty() && !line.starts_with(b"Filesystem"));
+
+		while let Some(line) = lines.next() {
+			// Filesystem names which are too long are printed in a line of their own.
+			let joined;
+			let line = if line.fields().count() == 1 {
+				joined = [ line, lines.next().unwrap_or_default() ].join(&b' ');
+				joined.as_slice()
+			} else {
+				line
+			};
+
+			let entry = (|| {
+				let (fields, mounted_on) = split_fields(line, 5).ok_or(())?;
+
+				let capacity = match fields[4] {
+					b"-" => Value::Nil,
+					capacity => Value::Int(
+						parse_number(capacity.strip_suffix(b"%").ok_or(())?)?
+					),
+				};
+
+				let mut entry = OrderedMap::new();
+				entry.insert("filesystem".into(), fields[0].into());
+				entry.insert("blocks".into(), parse_size(fields[1])?);
+				entry.insert("used".into(), parse_size(fields[2])?);
+				entry.insert("available".into(), parse_size(fields[3])?);
+				entry.insert("capacity".into(), capacity);
+				entry.insert("mounted_on".into(), mounted_on.into());
+
+				Ok(Dict::new(entry).into())
+			})();
+
+			match entry {
+				Ok(entry) => entries.push(entry),
+				Err(()) => return Ok(invalid_line("df", line)),
+			}
+		}
+
+		Ok(entries.into())
+	}
+}
+
+
+/// Parse the user database, as in `/etc/passwd` or the output of `getent passwd`,
+/// returning an array of dicts with the `name`, `password`, `uid`, `gid`, `gecos`, `home`
+/// and `shell` keys.
+#[derive(Trace, Finalize)]
+struct Passwd;
+
+impl NativeFun for Passwd {
+	fn name(&self) -> &'static str { "std.parsers.passwd" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		parse_lines(
+			context,
+			|line| line.is_empty() || line.starts_with(b"#"),
+			|line| {
+				let fields: Vec<&[u8]> = line.split_str(":").collect();
+				if fields.len() != 7 {
+					return Err(());
+				}
+
+				let mut entry = OrderedMap::new();
+				entry.insert("name".into(), fields[0].into());
+				entry.insert("password".into(), fields[1].into());
+				entry.insert("uid".into(), Value::Int(parse_number(fields[2])?));
+				entry.insert("gid".into(), Value::Int(parse_number(fields[3])?));
+				entry.insert("gecos".into(), fields[4].into());
+				entry.insert("home".into(), fields[5].into());
+				entry.insert("shell".into(), fields[6].into());
+
+				Ok(Dict::new(entry).into())
+			},
+			"passwd",
+		)
+	}
+}
+
+
+/// Parse the output of `ip -json addr`, falling back to the text output of `ip addr`.
+/// Returns an array of dicts with the `ifindex`, `ifname`, `flags`, `mtu`, `operstate`,
+/// `link_type`, `address` and `addr_info` keys, as in the JSON output. Address infos have
+/// the `family`, `local`, `prefixlen` and `scope` keys. When parsing the text output, only
+/// these keys are collected.
+#[derive(Trace, Finalize)]
+struct IpAddr;
+
+impl NativeFun for IpAddr {
+	fn name(&self) -> &'static str { "std.parsers.ip_addr" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let text = match context.args() {
+			[ Value::String(ref text) ] => text.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		if text.as_bytes().trim_start().starts_with(b"[") {
+			return Ok(
+				serde_json::from_slice(text.as_bytes())
+					.unwrap_or_else(
+						|error| Error::new(error.to_string().into(), text.copy().into()).into()
+					)
+			);
+		}
+
+		let mut interfaces: Vec<(OrderedMap, Vec<Value>)> = Vec::new();
+
+		for line in text.as_bytes().lines().map(ByteSlice::trim_end) {
+			if line.is_empty() {
+				continue;
+			}
+
+			let result = if line.starts_with(b" ") {
+				match interfaces.last_mut() {
+					Some((interface, addresses)) => parse_ip_detail(line.trim_start(), interface, addresses),
+					None => Err(()),
+				}
+			} else {
+				parse_ip_header(line)
+					.map(|interface| interfaces.push((interface, Vec::new())))
+			};
+
+			if result.is_err() {
+				return Ok(invalid_line("ip addr", line));
+			}
+		}
+
+		Ok(
+			interfaces
+				.into_iter()
+				.map(
+					|(mut interface, addresses)| {
+						interface.insert("addr_info".into(), addresses.into());
+						Dict::new(interface).into()
+					}
+				)
+				.collect::<Vec<Value>>()
+				.into()
+		)
+	}
+}
+
+
+/// Parse an interface header of `ip addr`, such as
+/// `2: eth0@if5: <BROADCAST,UP> mtu 1500 qdisc noqueue state UP group default`.
+fn parse_ip_header(line: &[u8]) -> Result<OrderedMap, ()> {
+	let (fields, rest) = split_fields(line, 3).ok_or(())?;
+
+	let index = fields[0].strip_suffix(b":").ok_or(())?;
+	let name = fields[1].strip_suffix(b":").ok_or(())?;
+	let name = name.split_str("@").next().unwrap_or(name);
+	let flags = fields[2]
+		.strip_prefix(b"<")
+		.and_then(|flags| flags.strip_suffix(b">"))
+		.ok_or(())?;
+
+	let mut interface = OrderedMap::new();
+	interface.insert("ifindex".into(), Value::Int(parse_number(index)?));
+	interface.insert("ifname".into(), name.into());
+	interface.insert(
+		"flags".into(),
+		flags
+			.split_str(",")
+			.filter(|flag| !flag.is_empty())
+			.map(Value::from)
+			.collect::<Vec<Value>>()
+			.into(),
+	);
+
+	let mut attributes = rest.fields();
+	while let Some(key) = attributes.next() {
+		let value = attributes.next().ok_or(())?;
+
+		match key {
+			b"mtu" => { interface.insert("mtu".into(), Value::Int(parse_number(value)?)); }
+			b"state" => { interface.insert("operstate".into(), value.into()); }
+			_ => (),
+		}
+	}
+
+	Ok(interface)
+}
+
+
+/// Parse a detail line of an interface of `ip addr`, such as
+/// `link/ether 02:42:ac:11:00:02 brd ff:ff:ff:ff:ff:ff` or
+/// `inet 172.17.0.2/16 brd 172.17.255.255 scope global eth0`.
+fn parse_ip_detail(
+	line: &[u8],
+	interface: &mut OrderedMap,
+	addresses: &mut Vec<Value>,
+) -> Result<(), ()> {
+	let mut fields = line.fields();
+
+	match fields.next() {
+		Some(link) if link.starts_with(b"link/") => {
+			interface.insert("link_type".into(), link[5 ..].into());
+
+			if let Some(address) = fields.next() {
+				interface.insert("address".into(), address.into());
+			}
+		}
+
+		Some(family @ (b"inet" | b"inet6")) => {
+			let address = fields.next().ok_or(())?;
+			let (local, prefixlen) = match address.find_byte(b'/') {
+				Some(ix) => (&address[..ix], Value::Int(parse_number(&address[ix + 1 ..])?)),
+				None => (address, Value::Nil),
+			};
+
+			let mut scope = Value::Nil;
+			while let Some(key) = fields.next() {
+				if key == b"scope" {
+					scope = fields.next().ok_or(())?.into();
+				}
+			}
+
+			let mut info = OrderedMap::new();
+			info.insert("family".into(), family.into());
+			info.insert("local".into(), local.into());
+			info.insert("prefixlen".into(), prefixlen);
+			info.insert("scope".into(), scope);
+
+			addresses.push(Dict::new(info).into());
+		}
+
+		// Address lifetimes and other details.
+		_ => (),
+	}
+
+	Ok(())
+}
+
+
+/// Parse each line of the single string argument, skipping the lines for which `skip`
+/// returns true. Returns an error value for the first invalid line.
+fn parse_lines<S, P>(context: CallContext, skip: S, parse: P, tool: &str) -> Result<Value, Panic>
+where
+	S: Fn(&[u8]) -> bool,
+	P: Fn(&[u8]) -> Result<Value, ()>,
+{
+	let text = match context.args() {
+		[ Value::String(ref text) ] => text.copy(),
+		[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+		args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+	};
+
+	let result: ParseResult = text
+		.as_bytes()
+		.lines()
+		.map(ByteSlice::trim_end)
+		.filter(|line| !skip(line))
+		.map(|line| parse(line).map_err(|()| invalid_line(tool, line)))
+		.collect::<Result<Vec<Value>, Value>>()
+		.map(Into::into);
+
+	Ok(result.unwrap_or_else(|error| error))
+}
+
+
+/// Split the given number of whitespace separated fields from the start of a line,
+/// returning them along with the rest of the line.
+fn split_fields(line: &[u8], count: usize) -> Option<(Vec<&[u8]>, &[u8])> {
+	let mut fields = Vec::with_capacity(count);
+	let mut rest = line.trim_start();
+
+	for _ in 0 .. count {
+		let end = rest.find_byteset(b" \t").unwrap_or(rest.len());
+		if end == 0 {
+			return None;
+		}
+
+		fields.push(&rest[..end]);
+		rest = rest[end ..].trim_start();
+	}
+
+	Some((fields, rest))
+}
+
+
+fn parse_number<T: FromStr>(field: &[u8]) -> Result<T, ()> {
+	field
+		.to_str()
+		.map_err(|_| ())?
+		.parse()
+		.map_err(|_| ())
+}
+
+
+/// Whether a line starts with a file mode, as printed by `ls -l`.
+fn is_mode(line: &[u8]) -> bool {
+	line.len() > 10
+		&& line[1 .. 10].iter().all(|c| b"rwxsStT-".contains(c))
+		&& b"-dlcbps".contains(&line[0])
+}
+
+
+/// Parse a size, which is nil if unknown.
+fn parse_size(field: &[u8]) -> Result<Value, ()> {
+	match field {
+		b"-" => Ok(Value::Nil),
+		field => parse_number(field).map(Value::Int),
+	}
+}
+
+
+fn invalid_line(tool: &str, line: &[u8]) -> Value {
+	Error::new(format!("invalid {} line", tool).into(), line.into()).into()
+}
@@ -0,0 +1,82 @@
+use std::{
+	os::unix::ffi::OsStringExt,
+	path::PathBuf,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+	users,
+};
+
+
+inventory::submit! { RustFun::from(ExpandUser) }
+
+
+/// Expand a leading `~`, `~user`, `~+` or `~-` in a path string, like home expansion in
+/// command arguments. The string is returned unchanged if the user can't be found.
+#[derive(Trace, Finalize)]
+struct ExpandUser;
+
+impl NativeFun for ExpandUser {
+	fn name(&self) -> &'static str { "std.path.expand_user" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(
+				match expand_user(string.as_bytes()) {
+					Some(path) => Str::from(path).into(),
+					None => Value::String(string.copy()),
+				}
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+	}
+}
+
+
+/// Expand a leading tilde in the given path, if it refers to a known directory.
+pub fn expand_user(path: &[u8]) -> Option<Vec<u8>> {
+	let path = path.strip_prefix(b"~")?;
+
+	let (user, rest) = match path.iter().position(|&c| c == b'/') {
+		Some(ix) => path.split_at(ix),
+		None => (path, &[][..]),
+	};
+
+	let mut expanded = home_dir(user)?.into_os_string().into_vec();
+	expanded.extend_from_slice(rest);
+
+	Some(expanded)
+}
+
+
+/// The directory referred by the given home expansion:
+/// - empty: the current user's home, from HOME or the password database.
+/// - `+`: the current directory, from PWD.
+/// - `-`: the previous directory, from OLDPWD.
+/// - otherwise, the home of the given user, from the password database.
+pub fn home_dir(user: &[u8]) -> Option<PathBuf> {
+	match user {
+		b"" => std::env::var_os("HOME")
+			.map(PathBuf::from)
+			.or_else(|| users::by_uid(unsafe { libc::getuid() }).map(|user| user.home)),
+
+		b"+" => std::env::var_os("PWD")
+			.map(PathBuf::from)
+			.or_else(|| std::env::current_dir().ok()),
+
+		b"-" => std::env::var_os("OLDPWD").map(PathBuf::from),
+
+		user => users::by_name(user).map(|user| user.home),
+	}
+}
+
@@ -0,0 +1,216 @@
+use std::{
+	convert::TryFrom,
+	ffi::OsStr,
+	io,
+	os::unix::{ffi::OsStrExt, process::{CommandExt, ExitStatusExt}},
+	process,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	signal,
+	users,
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+use crate::runtime::{children, command::{ExecEvent, ExecInfo}};
+
+
+inventory::submit! { RustFun::from(Spawn) }
+
+
+/// Run a command, given as an array of arguments, and wait for it to finish. The optional
+/// options dict supports the following keys:
+/// - `user`: name or id of the user to run the command as. The user's groups are set,
+///   along with the HOME, USER and LOGNAME environment variables.
+/// - `group`: name or id of the group to run the command as. Defaults to the user's
+///   primary group.
+///
+/// Like command blocks, returns nil on success, or an error if the command returned
+/// non-zero or could not be executed.
+#[derive(Trace, Finalize)]
+struct Spawn;
+
+impl NativeFun for Spawn {
+	fn name(&self) -> &'static str { "std.process.spawn" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (argv, options) = match context.args() {
+			[ Value::Array(ref argv) ] => (argv.copy(), None),
+			[ Value::Array(ref argv), Value::Dict(ref options) ] => (argv.copy(), Some(options.copy())),
+			[ Value::Array(_), other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			[ other, .. ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let argv = argv
+			.borrow()
+			.iter()
+			.map(
+				|arg| match arg {
+					Value::String(ref arg) => Ok(OsStr::from_bytes(arg.as_bytes()).into()),
+					other => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+				}
+			)
+			.collect::<Result<Vec<Box<OsStr>>, Panic>>()?;
+
+		if argv.is_empty() {
+			return Err(Panic::value_error(Vec::<Value>::new().into(), "non-empty array", context.pos));
+		}
+
+		let credentials = match options {
+			Some(options) => match Credentials::from_options(&options, &context)? {
+				Ok(credentials) => credentials,
+				Err(error) => return Ok(error),
+			},
+			None => None,
+		};
+
+		let mut command = process::Command::new(&argv[0]);
+		command.args(argv[1..].iter());
+
+		let mut env = Vec::new();
+
+		if let Some(credentials) = credentials {
+			if let Some(user) = &credentials.user {
+				let name: Box<OsStr> = OsStr::from_bytes(user.name.as_bytes()).into();
+				env.push((OsStr::new("HOME").into(), user.home.as_os_str().into()));
+				env.push((OsStr::new("USER").into(), name.clone()));
+				env.push((OsStr::new("LOGNAME").into(), name));
+			}
+
+			command.envs(env.iter().map(|(key, value)| (key, value)));
+
+			// SAFETY: the closure only performs async-signal-safe syscalls on data which was
+			// prepared before forking.
+			unsafe { command.pre_exec(move || credentials.apply()) };
+		}
+
+		let info = ExecInfo::new(argv.iter().cloned(), env.into_boxed_slice());
+		context.runtime.exec_hook(ExecEvent::Pre(info.clone()), context.pos.copy())?;
+
+		let status = children::acquire(1)
+			.and_then(
+				|_slots| command
+					.spawn()
+					.and_then(|mut child| signal::wait(&mut child))
+			);
+
+		signal::check(context.pos.copy())?;
+
+		let (status, error) = match status {
+			Ok(status) => {
+				let code = status
+					.code()
+					.or_else(|| status.signal().map(|signal| signal + 0xFF))
+					.unwrap_or(255);
+				(code, None)
+			}
+
+			Err(error) => (0x7F, Some(error)),
+		};
+
+		context.runtime.exec_hook(
+			ExecEvent::Post {
+				duration: info.start.elapsed().unwrap_or_default(),
+				info,
+				status,
+			},
+			context.pos.copy(),
+		)?;
+
+		Ok(
+			match error {
+				Some(error) => Error::new(error.to_string().into(), Value::Int(status as i64)).into(),
+				None if status != 0 => Error::new(
+					"command returned non-zero".into(),
+					Value::Int(status as i64)
+				).into(),
+				None => Value::default(),
+			}
+		)
+	}
+}
+
+
+/// The credentials to run a command with.
+struct Credentials {
+	user: Option<users::User>,
+	gid: libc::gid_t,
+}
+
+
+impl Credentials {
+	/// Get the credentials from the options dict. Unknown users and groups produce an
+	/// error value.
+	fn from_options(options: &Dict, context: &CallContext) -> Result<Result<Option<Self>, Value>, Panic> {
+		let user = match options.get(&"user".into()) {
+			Err(_) | Ok(Value::Nil) => None,
+
+			Ok(ref value @ Value::String(ref name)) => match users::by_name(name.as_bytes()) {
+				Some(user) => Some(user),
+				None => return Ok(Err(Error::new("unknown user".into(), value.copy()).into())),
+			},
+
+			Ok(Value::Int(uid)) => match libc::uid_t::try_from(uid).ok().and_then(users::by_uid) {
+				Some(user) => Some(user),
+				None => return Ok(Err(Error::new("unknown user".into(), Value::Int(uid)).into())),
+			},
+
+			Ok(other) => return Err(Panic::type_error(other, "string or int", context.pos.copy())),
+		};
+
+		let gid = match options.get(&"group".into()) {
+			Err(_) | Ok(Value::Nil) => user.as_ref().map(|user| user.gid),
+
+			Ok(ref value @ Value::String(ref name)) => match users::group_by_name(name.as_bytes()) {
+				Some(gid) => Some(gid),
+				None => return Ok(Err(Error::new("unknown group".into(), value.copy()).into())),
+			},
+
+			Ok(Value::Int(gid)) => match libc::gid_t::try_from(gid) {
+				Ok(gid) => Some(gid),
+				Err(_) => return Ok(Err(Error::new("unknown group".into(), Value::Int(gid)).into())),
+			},
+
+			Ok(other) => return Err(Panic::type_error(other, "string or int", context.pos.copy())),
+		};
+
+		Ok(Ok(gid.map(|gid| Self { user, gid })))
+	}
+
+
+	/// Drop privileges in the child process. The group must be set before the user, as the
+	/// user may not be allowed to change groups.
+	fn apply(&self) -> io::Result<()> {
+		let check = |result: libc::c_int| {
+			if result == 0 {
+				Ok(())
+			} else {
+				Err(io::Error::last_os_error())
+			}
+		};
+
+		unsafe {
+			match &self.user {
+				Some(user) => check(libc::initgroups(user.name.as_ptr(), self.gid as _))?,
+				None => check(libc::setgroups(1, &self.gid))?,
+			}
+
+			check(libc::setgid(self.gid))?;
+
+			if let Some(user) = &self.user {
+				check(libc::setuid(user.uid))?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
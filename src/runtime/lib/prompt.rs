@@ -0,0 +1,106 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	Array,
+	CallContext,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+use crate::{fmt::FmtString, term::menu::{self, Outcome}};
+
+
+inventory::submit! { RustFun::from(Select) }
+inventory::submit! { RustFun::from(MultiSelect) }
+
+
+/// Show a menu with the given title and options on the terminal, returning the chosen
+/// option. Options are navigated with the arrow keys, or chosen by number. Returns nil if
+/// the menu is cancelled, or an error if there is no terminal.
+#[derive(Trace, Finalize)]
+struct Select;
+
+impl NativeFun for Select {
+	fn name(&self) -> &'static str { "std.prompt.select" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (title, options) = args(&context)?;
+
+		Ok(
+			match show(&context, &title, &options, false) {
+				Ok(Outcome::Selected(indices)) => options
+					.index(indices[0] as i64)
+					.unwrap_or_default(),
+				Ok(Outcome::Cancelled) => Value::default(),
+				Err(error) => error,
+			}
+		)
+	}
+}
+
+
+/// Like std.prompt.select, but allowing many options to be toggled with the space key.
+/// Returns the array of chosen options, nil if the menu is cancelled, or an error if
+/// there is no terminal.
+#[derive(Trace, Finalize)]
+struct MultiSelect;
+
+impl NativeFun for MultiSelect {
+	fn name(&self) -> &'static str { "std.prompt.multi_select" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (title, options) = args(&context)?;
+
+		Ok(
+			match show(&context, &title, &options, true) {
+				Ok(Outcome::Selected(indices)) => indices
+					.into_iter()
+					.filter_map(|ix| options.index(ix as i64).ok())
+					.collect::<Vec<Value>>()
+					.into(),
+				Ok(Outcome::Cancelled) => Value::default(),
+				Err(error) => error,
+			}
+		)
+	}
+}
+
+
+/// Get the title and the non-empty options array.
+fn args(context: &CallContext) -> Result<(Str, Array), Panic> {
+	match context.args() {
+		[ Value::String(ref title), Value::Array(ref options) ] if options.is_empty() => Err(
+			Panic::value_error(options.copy().into(), "non-empty array", context.pos.copy())
+		),
+		[ Value::String(ref title), Value::Array(ref options) ] => Ok((title.copy(), options.copy())),
+		[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "array", context.pos.copy())),
+		[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy())),
+	}
+}
+
+
+/// Show the menu, rendering options as strings.
+fn show(context: &CallContext, title: &Str, options: &Array, multi: bool) -> Result<Outcome, Value> {
+	let title = String::from_utf8_lossy(title.as_bytes());
+
+	let options: Vec<String> = options
+		.borrow()
+		.iter()
+		.map(
+			|option| match option {
+				Value::String(ref string) => String::from_utf8_lossy(string.as_bytes()).into_owned(),
+				other => other.fmt_string(context.interner()),
+			}
+		)
+		.collect();
+
+	// Flush pending output from std.print, so that it appears before the menu.
+	let _ = std::io::Write::flush(&mut std::io::stdout());
+
+	menu::run(&title, &options, multi)
+		.map_err(|error| Error::new(error.to_string().into(), Value::default()).into())
+}
@@ -1,14 +1,11 @@
-use std::collections::HashMap;
-
 use gc::{Finalize, GcCell, Trace};
 
 use super::{
 	util,
-	keys,
 	CallContext,
-	Dict,
 	RustFun,
 	NativeFun,
+	NativeIter,
 	Panic,
 	Value,
 };
@@ -68,13 +65,20 @@ where
 	fn name(&self) -> &'static str { "std.range<impl>" }
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
-		let args = context.args();
-		if !args.is_empty() {
-			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
-		}
+		self.call_iter(context)
+	}
 
+	fn as_iter(&self) -> Option<&dyn NativeIter> { Some(self) }
+}
+
+impl<T> NativeIter for RangeImpl<T>
+where
+	T: Trace + Finalize + 'static,
+	T: Clone + Default + Ord + std::ops::Add<Output = T>,
+	T: Into<Value>,
+{
+	fn next(&self, _: CallContext) -> Result<Option<Value>, Panic> {
 		let mut from = self.from.borrow_mut();
-		let mut iteration = HashMap::new();
 
 		let finished =
 			if self.step > T::default() { // Step is positive.
@@ -83,24 +87,12 @@ where
 				*from <= self.to
 			};
 
-		let next = if finished {
-			None
+		if finished {
+			Ok(None)
 		} else {
 			let value = from.clone();
 			*from = from.clone() + self.step.clone();
-			Some(value)
-		};
-
-		keys::FINISHED.with(
-			|finished| iteration.insert(finished.copy(), next.is_none().into())
-		);
-
-		if let Some(next) = next {
-			keys::VALUE.with(
-				|value| iteration.insert(value.copy(), next.into())
-			);
+			Ok(Some(value.into()))
 		}
-
-		Ok(Dict::new(iteration).into())
 	}
 }
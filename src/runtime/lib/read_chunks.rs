@@ -0,0 +1,220 @@
+use std::{
+	ffi::OsStr,
+	io::{self, Read},
+	os::unix::ffi::OsStrExt,
+	process::{self, Stdio},
+};
+
+use gc::{Finalize, GcCell, Trace};
+
+use super::{
+	signal,
+	CallContext,
+	Error,
+	NativeFun,
+	NativeIter,
+	RustFun,
+	Panic,
+	Size,
+	Str,
+	Value,
+};
+use crate::runtime::{children, command::{ExecEvent, ExecInfo}};
+
+
+inventory::submit! { RustFun::from(ReadChunks) }
+
+
+/// Run a command, given as an array of arguments, returning a lazy iterator over its
+/// standard output in chunks of the given size, as a size or in bytes. Only the last chunk
+/// may be shorter. The output is read only as the iterator advances, so that a command
+/// producing large amounts of data is throttled by the pipe instead of being buffered in
+/// memory.
+/// If the command returns non-zero, an error with the exit status as context is produced
+/// as the last item. Abandoning the iterator kills the command.
+#[derive(Trace, Finalize)]
+struct ReadChunks;
+
+impl NativeFun for ReadChunks {
+	fn name(&self) -> &'static str { "std.read_chunks" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (argv, size) = match context.args() {
+			[ Value::Array(ref argv), Value::Int(size) | Value::Size(Size(size)) ] if *size > 0 => {
+				let argv = argv
+					.borrow()
+					.iter()
+					.map(
+						|arg| match arg {
+							Value::String(ref arg) => Ok(OsStr::from_bytes(arg.as_bytes()).into()),
+							other => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+						}
+					)
+					.collect::<Result<Vec<Box<OsStr>>, Panic>>()?;
+
+				(argv, *size as usize)
+			}
+
+			[ Value::Array(_), size @ (Value::Int(_) | Value::Size(_)) ] => return Err(
+				Panic::value_error(size.copy(), "positive chunk size", context.pos)
+			),
+			[ Value::Array(_), other ] => return Err(
+				Panic::type_error(other.copy(), "int or size", context.pos)
+			),
+			[ other, _ ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		if argv.is_empty() {
+			return Err(Panic::value_error(Vec::<Value>::new().into(), "non-empty array", context.pos));
+		}
+
+		let info = ExecInfo::new(argv.iter().cloned(), Box::default());
+		context.runtime.exec_hook(ExecEvent::Pre(info.clone()), context.pos.copy())?;
+
+		let slots = children::acquire(1)
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		let mut child = process::Command::new(&argv[0])
+			.args(argv[1..].iter())
+			.stdout(Stdio::piped())
+			.spawn()
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		let stdout = child.stdout.take();
+
+		Ok(
+			ReadChunksImpl(
+				GcCell::new(
+					ChunksCell(
+						Chunks {
+							child,
+							stdout,
+							size,
+							info: Some(info),
+							slots: Some(slots),
+						}
+					)
+				)
+			).into()
+		)
+	}
+}
+
+
+/// The state of a chunked read.
+struct Chunks {
+	child: process::Child,
+	/// The command's output, until it is exhausted.
+	stdout: Option<process::ChildStdout>,
+	size: usize,
+	/// Info for the post exec hook, until the command finishes.
+	info: Option<ExecInfo>,
+	/// The command's process slot, until it finishes.
+	slots: Option<children::Slots>,
+}
+
+
+impl Chunks {
+	/// Read the next chunk, or None if the output is exhausted.
+	fn read(&mut self) -> io::Result<Option<Vec<u8>>> {
+		let stdout = match &mut self.stdout {
+			Some(stdout) => stdout,
+			None => return Ok(None),
+		};
+
+		let mut chunk = Vec::with_capacity(self.size);
+
+		stdout
+			.take(self.size as u64)
+			.read_to_end(&mut chunk)?;
+
+		if chunk.is_empty() {
+			self.stdout = None;
+			Ok(None)
+		} else {
+			Ok(Some(chunk))
+		}
+	}
+}
+
+
+impl Drop for Chunks {
+	fn drop(&mut self) {
+		if self.info.is_some() {
+			// The iterator was abandoned before the command finished.
+			let _ = self.child.kill();
+			let _ = self.child.wait();
+		}
+	}
+}
+
+
+/// The chunks hold no garbage-collected values.
+#[derive(Finalize)]
+struct ChunksCell(Chunks);
+
+
+unsafe impl Trace for ChunksCell {
+	gc::unsafe_empty_trace!();
+}
+
+
+#[derive(Trace, Finalize)]
+struct ReadChunksImpl(GcCell<ChunksCell>);
+
+impl NativeFun for ReadChunksImpl {
+	fn name(&self) -> &'static str { "std.read_chunks<impl>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		self.call_iter(context)
+	}
+
+	fn as_iter(&self) -> Option<&dyn NativeIter> { Some(self) }
+}
+
+impl NativeIter for ReadChunksImpl {
+	fn next(&self, context: CallContext) -> Result<Option<Value>, Panic> {
+		let pos = context.pos.copy();
+		let io_error = |error| Panic::io(error, pos.copy());
+
+		let mut chunks = self.0.borrow_mut();
+		let chunks = &mut chunks.0;
+
+		let chunk = chunks.read().map_err(io_error)?;
+		signal::check(pos.copy())?;
+
+		let next = match chunk {
+			Some(chunk) => Some(Str::from(chunk).into()),
+
+			None => match chunks.info.take() {
+				Some(info) => {
+					let status = signal::wait(&mut chunks.child).map_err(io_error)?;
+					let status = status.code().unwrap_or(255);
+					chunks.slots = None;
+
+					context.runtime.exec_hook(
+						ExecEvent::Post {
+							duration: info.start.elapsed().unwrap_or_default(),
+							info,
+							status,
+						},
+						pos.copy(),
+					)?;
+
+					signal::check(pos.copy())?;
+
+					if status == 0 {
+						None
+					} else {
+						Some(Error::new("command returned non-zero".into(), Value::Int(status as i64)).into())
+					}
+				}
+
+				None => None,
+			},
+		};
+
+		Ok(next)
+	}
+}
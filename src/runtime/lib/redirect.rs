@@ -0,0 +1,231 @@
+use std::{
+	fs::OpenOptions,
+	io::{self, Read, Write},
+	os::unix::{ffi::OsStrExt, io::{AsRawFd, RawFd}},
+	ffi::OsStr,
+	thread,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(RedirectStdout) }
+inventory::submit! { RustFun::from(RedirectStderr) }
+
+
+/// Redirect the interpreter's stdout while calling the given function.
+/// See `redirect` for details.
+#[derive(Trace, Finalize)]
+struct RedirectStdout;
+
+impl NativeFun for RedirectStdout {
+	fn name(&self) -> &'static str { "std.io.redirect_stdout" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		redirect(context, libc::STDOUT_FILENO)
+	}
+}
+
+
+/// Redirect the interpreter's stderr while calling the given function.
+/// See `redirect` for details.
+#[derive(Trace, Finalize)]
+struct RedirectStderr;
+
+impl NativeFun for RedirectStderr {
+	fn name(&self) -> &'static str { "std.io.redirect_stderr" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		redirect(context, libc::STDERR_FILENO)
+	}
+}
+
+
+/// Where to send the redirected output.
+enum Target {
+	/// Append to the file.
+	File(std::fs::File),
+	/// Collect the output, and pass it to the callback once the function returns.
+	Callback {
+		writer: os_pipe::PipeWriter,
+		/// Closed once the function returns, which stops the reader.
+		done: os_pipe::PipeWriter,
+		reader: thread::JoinHandle<io::Result<Vec<u8>>>,
+	},
+}
+
+
+impl Target {
+	/// Close the redirection target, returning the collected output, if any.
+	fn finish(self) -> io::Result<Option<Vec<u8>>> {
+		match self {
+			Target::File(_) => Ok(None),
+			Target::Callback { writer, done, reader } => {
+				drop(writer);
+				drop(done);
+
+				match reader.join() {
+					Ok(output) => output.map(Some),
+					Err(error) => std::panic::resume_unwind(error),
+				}
+			}
+		}
+	}
+}
+
+
+/// Redirect the given file descriptor to a file or callback while calling a function,
+/// restoring it afterwards. The redirection happens at the file descriptor level, and
+/// therefore is inherited by commands executed inside the function. The return value of
+/// the function is returned.
+/// When redirecting to a callback, only the output written until the function returns is
+/// collected. Commands which outlive the function, such as background commands, keep a
+/// copy of the descriptor, but their later output is discarded, instead of the callback
+/// waiting for them to terminate.
+fn redirect(mut context: CallContext, fd: RawFd) -> Result<Value, Panic> {
+	let (target, fun, callback) = match context.args() {
+		[ Value::String(ref path), Value::Function(ref fun) ] => {
+			let file = OpenOptions::new()
+				.create(true)
+				.append(true)
+				.open(OsStr::from_bytes(path.as_bytes()))
+				.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+			(Target::File(file), fun.copy(), None)
+		}
+
+		[ Value::Function(ref callback), Value::Function(ref fun) ] => {
+			let pipes = os_pipe::pipe().and_then(
+				|output| os_pipe::pipe().map(|done| (output, done))
+			);
+
+			let ((reader, writer), (done_reader, done)) = pipes
+				.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+			let reader = thread::spawn(move || collect(reader, done_reader));
+
+			(Target::Callback { writer, done, reader }, fun.copy(), Some(callback.copy()))
+		}
+
+		[ Value::String(_) | Value::Function(_), other ] => return Err(
+			Panic::type_error(other.copy(), "function", context.pos)
+		),
+		[ other, _ ] => return Err(Panic::type_error(other.copy(), "string or function", context.pos)),
+		args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+	};
+
+	let target_fd = match &target {
+		Target::File(file) => file.as_raw_fd(),
+		Target::Callback { writer, .. } => writer.as_raw_fd(),
+	};
+
+	let pos = context.pos.copy();
+	let io_error = |error| Panic::io(error, pos.copy());
+
+	flush().map_err(io_error)?;
+
+	// SAFETY: fcntl and dup2 have no memory safety requirements. The saved descriptor must
+	// not leak into commands executed inside the function.
+	let saved = unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) };
+	if saved < 0 {
+		return Err(io_error(io::Error::last_os_error()));
+	}
+
+	if unsafe { libc::dup2(target_fd, fd) } < 0 {
+		let error = io::Error::last_os_error();
+		unsafe { libc::close(saved) };
+		return Err(io_error(error));
+	}
+
+	let result = context.call(Value::default(), &fun, context.args_start + 2);
+
+	// Restore the file descriptor even if the function panicked.
+	let flushed = flush();
+	let restored = match unsafe { libc::dup2(saved, fd) } {
+		-1 => Err(io::Error::last_os_error()),
+		_ => Ok(()),
+	};
+	unsafe { libc::close(saved) };
+
+	// The target is closed regardless of errors, so that the reader always terminates.
+	let output = target.finish();
+
+	restored.map_err(io_error)?;
+	flushed.map_err(io_error)?;
+	let output = output.map_err(io_error)?;
+
+	let value = result?;
+
+	if let (Some(callback), Some(output)) = (callback, output) {
+		let args_start = context.runtime.arguments.len();
+		context.runtime.arguments.push(Str::from(output).into());
+		context.call(Value::default(), &callback, args_start)?;
+	}
+
+	Ok(value)
+}
+
+
+/// Read the output until it is closed, or until `done` is closed. In the latter case, only
+/// the output which is already available is read, as some commands may outlive the
+/// redirection.
+fn collect(mut reader: os_pipe::PipeReader, done: os_pipe::PipeReader) -> io::Result<Vec<u8>> {
+	let mut output = Vec::with_capacity(512);
+	let mut buffer = [0; 4096];
+
+	loop {
+		let mut fds = [
+			libc::pollfd { fd: reader.as_raw_fd(), events: libc::POLLIN, revents: 0 },
+			libc::pollfd { fd: done.as_raw_fd(), events: libc::POLLIN, revents: 0 },
+		];
+
+		// SAFETY: the array is valid for its length.
+		if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+			let error = io::Error::last_os_error();
+			if error.kind() == io::ErrorKind::Interrupted {
+				continue;
+			}
+			return Err(error);
+		}
+
+		if fds[0].revents != 0 {
+			match reader.read(&mut buffer)? {
+				0 => return Ok(output),
+				n => output.extend_from_slice(&buffer[..n]),
+			}
+		} else if fds[1].revents != 0 {
+			break;
+		}
+	}
+
+	// SAFETY: fcntl has no memory safety requirements.
+	unsafe {
+		let flags = libc::fcntl(reader.as_raw_fd(), libc::F_GETFL);
+		libc::fcntl(reader.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK);
+	}
+
+	loop {
+		match reader.read(&mut buffer) {
+			Ok(0) => return Ok(output),
+			Ok(n) => output.extend_from_slice(&buffer[..n]),
+			Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(output),
+			Err(error) if error.kind() == io::ErrorKind::Interrupted => (),
+			Err(error) => return Err(error),
+		}
+	}
+}
+
+
+fn flush() -> io::Result<()> {
+	io::stdout().flush()?;
+	io::stderr().flush()
+}
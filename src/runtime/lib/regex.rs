@@ -1,4 +1,4 @@
-use std::{rc::Rc, collections::HashMap, borrow::Cow};
+use std::{rc::Rc, borrow::Cow};
 
 use gc::{Finalize, Trace};
 use regex::bytes::Regex;
@@ -9,6 +9,7 @@ use super::{
 	Dict,
 	RustFun,
 	NativeFun,
+	OrderedMap,
 	Panic,
 	Str,
 	Value,
@@ -17,6 +18,19 @@ use super::{
 
 inventory::submit! { RustFun::from(StdRegex) }
 
+/// Compile a regex, returning an error if the pattern is invalid. The compiled regex is a
+/// dict with the following methods, where offsets are in bytes:
+/// - `match(string)` or `is_match(string)`: whether the regex matches the string.
+/// - `find(string)`: the first match, as a dict with the `start`, `end` and `text` keys, or
+///   nil if there is no match.
+/// - `find_all(string)`: the array of non-overlapping matches.
+/// - `captures(string)`: the capture groups of the first match, or nil if there is no
+///   match. Returns a dict with the `groups` array, where the first group is the whole match
+///   and groups that didn't participate in the match are nil, and the `named` dict of named
+///   groups.
+/// - `split(string)`: split the string by the matches.
+/// - `replace(string, replacement)`: replace all matches. The replacement may refer to
+///   capture groups, as in `$1` or `${name}`.
 #[derive(Trace, Finalize)]
 struct StdRegex;
 
@@ -38,12 +52,17 @@ impl StdRegex {
 			pub static REPLACE: Value = "replace".into();
 		}
 
-		let mut dict = HashMap::new();
+		let mut dict = OrderedMap::new();
 
 		MATCH.with(
 			|name| dict.insert(name.copy(), RegexMatchImpl { pattern: pattern.clone() }.into())
 		);
 
+		dict.insert("is_match".into(), RegexMatchImpl { pattern: pattern.clone() }.into());
+		dict.insert("find".into(), RegexFindImpl { pattern: pattern.clone() }.into());
+		dict.insert("find_all".into(), RegexFindAllImpl { pattern: pattern.clone() }.into());
+		dict.insert("captures".into(), RegexCapturesImpl { pattern: pattern.clone() }.into());
+
 		SPLIT.with(
 			|split| dict.insert(split.copy(), RegexSplitImpl { pattern: pattern.clone() }.into())
 		);
@@ -93,6 +112,106 @@ impl NativeFun for RegexMatchImpl {
 	}
 }
 
+#[derive(Finalize)]
+struct RegexFindImpl {
+	pattern: Rc<Regex>,
+}
+
+/// RegexFindImpl has no garbage-collected fields.
+unsafe impl Trace for RegexFindImpl {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for RegexFindImpl {
+	fn name(&self) -> &'static str { "std.regex<find>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(
+				self.pattern
+					.find(string.as_ref())
+					.map(match_value)
+					.into()
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+#[derive(Finalize)]
+struct RegexFindAllImpl {
+	pattern: Rc<Regex>,
+}
+
+/// RegexFindAllImpl has no garbage-collected fields.
+unsafe impl Trace for RegexFindAllImpl {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for RegexFindAllImpl {
+	fn name(&self) -> &'static str { "std.regex<find_all>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(
+				self.pattern
+					.find_iter(string.as_ref())
+					.map(match_value)
+					.collect::<Vec<_>>()
+					.into()
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+#[derive(Finalize)]
+struct RegexCapturesImpl {
+	pattern: Rc<Regex>,
+}
+
+/// RegexCapturesImpl has no garbage-collected fields.
+unsafe impl Trace for RegexCapturesImpl {
+	gc::unsafe_empty_trace!();
+}
+
+impl NativeFun for RegexCapturesImpl {
+	fn name(&self) -> &'static str { "std.regex<captures>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let string = match context.args() {
+			[ Value::String(ref string) ] => string.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		};
+
+		let captures = match self.pattern.captures(string.as_ref()) {
+			Some(captures) => captures,
+			None => return Ok(Value::default()),
+		};
+
+		let groups: Vec<Value> = captures
+			.iter()
+			.map(|group| group.map(match_value).into())
+			.collect();
+
+		let mut named = OrderedMap::new();
+		for name in self.pattern.capture_names().flatten() {
+			named.insert(name.into(), captures.name(name).map(match_value).into());
+		}
+
+		let mut dict = OrderedMap::new();
+		dict.insert("groups".into(), groups.into());
+		dict.insert("named".into(), Dict::new(named).into());
+
+		Ok(Dict::new(dict).into())
+	}
+}
+
 #[derive(Finalize)]
 struct RegexSplitImpl {
 	pattern: Rc<Regex>,
@@ -153,3 +272,15 @@ impl NativeFun for RegexReplaceImpl {
 		}
 	}
 }
+
+/// Convert a match to a dict with the `start` and `end` byte offsets, and the matched
+/// `text`.
+fn match_value(found: regex::bytes::Match) -> Value {
+	let mut dict = OrderedMap::new();
+
+	dict.insert("start".into(), Value::Int(found.start() as i64));
+	dict.insert("end".into(), Value::Int(found.end() as i64));
+	dict.insert("text".into(), found.as_bytes().into());
+
+	Dict::new(dict).into()
+}
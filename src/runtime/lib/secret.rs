@@ -0,0 +1,73 @@
+//! Secrets are strings which are hidden when displayed, so that they don't leak into
+//! output and logs.
+
+use gc::{Finalize, Trace};
+
+use super::{
+	keys,
+	CallContext,
+	Dict,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Secret) }
+
+
+/// Wrap a string in a secret, which is displayed as `<redacted>` when printed, converted
+/// with std.to_string, or shown in errors and panics. Commands receive the actual string
+/// when a secret is used as an argument, and the `reveal` method returns it.
+#[derive(Trace, Finalize)]
+struct Secret;
+
+impl NativeFun for Secret {
+	fn name(&self) -> &'static str { "std.secret" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => {
+				let mut dict = OrderedMap::new();
+
+				keys::REVEAL.with(
+					|reveal| dict.insert(reveal.copy(), Reveal { secret: string.copy() }.into())
+				);
+
+				Ok(Dict::new(dict).into())
+			}
+
+			[ value @ Value::Dict(ref dict) ] if dict.secret().is_some() => Ok(value.copy()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string or secret", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+	}
+}
+
+
+/// Get the string wrapped in a secret.
+#[derive(Trace, Finalize)]
+struct Reveal {
+	secret: Str,
+}
+
+impl NativeFun for Reveal {
+	fn name(&self) -> &'static str { "std.secret.reveal" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		Ok(self.secret.copy().into())
+	}
+
+	fn as_secret(&self) -> Option<&Str> {
+		Some(&self.secret)
+	}
+}
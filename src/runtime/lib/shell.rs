@@ -0,0 +1,155 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	util,
+	CallContext,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(ToShell) }
+inventory::submit! { RustFun::from(FromShell) }
+
+
+/// Produce a POSIX shell command line from an array of arguments. Arguments are quoted
+/// unless they consist only of characters which are never special to the shell.
+#[derive(Trace, Finalize)]
+struct ToShell;
+
+impl NativeFun for ToShell {
+	fn name(&self) -> &'static str { "std.to_shell" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let argv = match context.args() {
+			[ Value::Array(ref argv) ] => argv.copy(),
+			[ other ] => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let mut line = Vec::new();
+
+		for (ix, arg) in argv.borrow().iter().enumerate() {
+			let arg = match arg {
+				Value::String(ref arg) => arg,
+				other => return Err(Panic::type_error(other.copy(), "string", context.pos)),
+			};
+
+			if ix > 0 {
+				line.push(b' ');
+			}
+
+			let arg = arg.as_bytes();
+			if !arg.is_empty() && arg.iter().all(|&c| is_safe(c)) {
+				line.extend_from_slice(arg);
+			} else {
+				line.extend(util::shell_quote(arg));
+			}
+		}
+
+		Ok(Str::from(line).into())
+	}
+}
+
+
+/// Split a POSIX shell command line into an array of arguments, handling quotes and
+/// backslash escapes. Expansions and operators are not interpreted, and are kept as
+/// literal text. Returns an error if a quote is unterminated.
+#[derive(Trace, Finalize)]
+struct FromShell;
+
+impl NativeFun for FromShell {
+	fn name(&self) -> &'static str { "std.from_shell" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value @ Value::String(ref line) ] => Ok(
+				match split(line.as_bytes()) {
+					Some(args) => args
+						.into_iter()
+						.map(|arg| Str::from(arg).into())
+						.collect::<Vec<Value>>()
+						.into(),
+					None => Error::new("unterminated quote".into(), value.copy()).into(),
+				}
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+	}
+}
+
+
+/// Characters which don't need quoting.
+fn is_safe(c: u8) -> bool {
+	c.is_ascii_alphanumeric() || b"-_./=:,+@%".contains(&c)
+}
+
+
+/// Split a shell line, returning None if a quote is unterminated.
+fn split(line: &[u8]) -> Option<Vec<Vec<u8>>> {
+	enum Quote {
+		None,
+		Single,
+		Double,
+	}
+
+	let mut args = Vec::new();
+	let mut arg: Option<Vec<u8>> = None;
+	let mut quote = Quote::None;
+	let mut chars = line.iter().copied();
+
+	while let Some(c) = chars.next() {
+		match quote {
+			Quote::None => match c {
+				b' ' | b'\t' | b'\n' => args.extend(arg.take()),
+				b'\'' => {
+					arg.get_or_insert_with(Vec::new);
+					quote = Quote::Single;
+				}
+				b'"' => {
+					arg.get_or_insert_with(Vec::new);
+					quote = Quote::Double;
+				}
+				b'\\' => match chars.next() {
+					Some(b'\n') => (), // Line continuation.
+					Some(c) => arg.get_or_insert_with(Vec::new).push(c),
+					None => arg.get_or_insert_with(Vec::new).push(b'\\'),
+				},
+				c => arg.get_or_insert_with(Vec::new).push(c),
+			},
+
+			Quote::Single => match c {
+				b'\'' => quote = Quote::None,
+				c => arg.get_or_insert_with(Vec::new).push(c),
+			},
+
+			Quote::Double => match c {
+				b'"' => quote = Quote::None,
+				b'\\' => {
+					let arg = arg.get_or_insert_with(Vec::new);
+					match chars.next() {
+						Some(b'\n') => (),
+						Some(c @ (b'$' | b'`' | b'"' | b'\\')) => arg.push(c),
+						Some(c) => arg.extend_from_slice(&[b'\\', c]),
+						None => return None,
+					}
+				}
+				c => arg.get_or_insert_with(Vec::new).push(c),
+			},
+		}
+	}
+
+	match quote {
+		Quote::None => {
+			args.extend(arg);
+			Some(args)
+		}
+		_ => None,
+	}
+}
@@ -0,0 +1,108 @@
+//! Handling of signals by scripts.
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+	signal::{self, Disposition},
+};
+use crate::runtime::SourcePos;
+
+
+inventory::submit! { RustFun::from(Trap) }
+inventory::submit! { RustFun::from(Ignore) }
+inventory::submit! { RustFun::from(Restore) }
+
+
+/// Handle the signal with the given name, such as `SIGINT`, by calling the given function
+/// without arguments. Instead of interrupting the script, the function is called after the
+/// current command block or loop iteration, and command blocks are not stopped. Commands
+/// still receive the signals sent by the terminal. The supported signals are SIGHUP, SIGINT, SIGQUIT, SIGTERM,
+/// SIGUSR1 and SIGUSR2.
+#[derive(Trace, Finalize)]
+struct Trap;
+
+impl NativeFun for Trap {
+	fn name(&self) -> &'static str { "std.signal.trap" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (signal, handler) = match context.args() {
+			[ name, Value::Function(ref handler) ] => (parse(name, &context.pos)?, handler.copy()),
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		signal::set_disposition(signal, Disposition::Trap)
+			.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+		context.runtime.signal_traps.insert(signal, handler);
+
+		Ok(Value::Nil)
+	}
+}
+
+
+/// Ignore the signal with the given name. Like in other shells, the signal is ignored by
+/// the commands executed afterwards as well.
+#[derive(Trace, Finalize)]
+struct Ignore;
+
+impl NativeFun for Ignore {
+	fn name(&self) -> &'static str { "std.signal.ignore" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		set(context, Disposition::Ignore)
+	}
+}
+
+
+/// Restore the default handling of the signal with the given name. SIGINT and SIGTERM
+/// interrupt the script, and the other signals terminate the interpreter.
+#[derive(Trace, Finalize)]
+struct Restore;
+
+impl NativeFun for Restore {
+	fn name(&self) -> &'static str { "std.signal.default" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		set(context, Disposition::Default)
+	}
+}
+
+
+/// Set the disposition of the given signal, removing its handler.
+fn set(context: CallContext, disposition: Disposition) -> Result<Value, Panic> {
+	let signal = match context.args() {
+		[ name ] => parse(name, &context.pos)?,
+		args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+	};
+
+	signal::set_disposition(signal, disposition)
+		.map_err(|error| Panic::io(error, context.pos.copy()))?;
+
+	context.runtime.signal_traps.remove(&signal);
+
+	Ok(Value::Nil)
+}
+
+
+fn parse(name: &Value, pos: &SourcePos) -> Result<libc::c_int, Panic> {
+	match name {
+		Value::String(ref string) => signal::parse(string.as_bytes()).ok_or_else(
+			|| {
+				let names: Vec<&str> = signal::names().collect();
+				Panic::value_error(
+					name.copy(),
+					format!("signal name ({})", names.join(", ")),
+					pos.copy(),
+				)
+			}
+		),
+
+		other => Err(Panic::type_error(other.copy(), "string", pos.copy())),
+	}
+}
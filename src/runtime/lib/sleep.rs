@@ -1,18 +1,21 @@
-use std::time::Duration;
+use std::time;
 
 use gc::{Finalize, Trace};
 
 use super::{
 	CallContext,
+	Duration,
 	NativeFun,
 	RustFun,
 	Panic,
 	Value,
+	signal,
 };
 
 
 inventory::submit!{ RustFun::from(Sleep) }
 
+/// Sleep for the given duration, or amount of milliseconds.
 #[derive(Trace, Finalize)]
 struct Sleep;
 
@@ -21,14 +24,18 @@ impl NativeFun for Sleep {
 
 	fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		match context.args() {
-			[ Value::Int(i) ] if *i < 0 => Err(Panic::value_error(Value::Int(*i), "positive integer", context.pos)),
+			[ value @ (Value::Int(i) | Value::Duration(Duration(i))) ] if *i < 0 => Err(
+				Panic::value_error(value.copy(), "positive integer", context.pos)
+			),
+
+			[ Value::Int(i) | Value::Duration(Duration(i)) ] => {
+				signal::sleep(time::Duration::from_millis(*i as u64))
+					.map_err(|signal| Panic::interrupted(signal, context.pos))?;
 
-			[ Value::Int(i) ] => {
-				std::thread::sleep(Duration::from_millis(*i as u64));
 				Ok(Value::default())
 			},
 
-			[ other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			[ other ] => Err(Panic::type_error(other.copy(), "int or duration", context.pos)),
 			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
 		}
 	}
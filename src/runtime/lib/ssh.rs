@@ -0,0 +1,401 @@
+//! Remote execution over ssh.
+//!
+//! Commands are run with the system's ssh client. Connections are multiplexed by default
+//! (ControlMaster), so that consecutive calls to the same host reuse the connection.
+
+use std::{
+	ffi::{OsStr, OsString},
+	io::{self, Read, Write},
+	os::unix::{
+		ffi::{OsStrExt, OsStringExt},
+		process::ExitStatusExt,
+	},
+	path::PathBuf,
+	process::{self, Stdio},
+	thread,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{
+	signal,
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+	util,
+};
+use crate::runtime::{children, command::{ExecEvent, ExecInfo}, SourcePos};
+
+
+inventory::submit! { RustFun::from(Run) }
+inventory::submit! { RustFun::from(Script) }
+
+
+/// Exit status of the ssh client when the connection fails.
+const CONNECTION_FAILED: i32 = 255;
+
+/// Seconds to keep a multiplexed connection open after the last use.
+const CONTROL_PERSIST: i64 = 60;
+
+
+/// Run a command in a remote host. The command may be an array of arguments, which are
+/// quoted for the remote shell, or a string, which is passed to the remote shell as is.
+/// Returns a dict with the `stdout`, `stderr` and `status` of the command, or an error
+/// with such dict as context if the command or the connection failed.
+#[derive(Trace, Finalize)]
+struct Run;
+
+impl NativeFun for Run {
+	fn name(&self) -> &'static str { "std.ssh.run" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (host, command, options) = match context.args() {
+			[ Value::String(ref host), command ] => (host.copy(), command.copy(), Options::default()),
+
+			[ Value::String(ref host), command, Value::Dict(ref options) ] => (
+				host.copy(),
+				command.copy(),
+				Options::parse(options, &context.pos)?,
+			),
+
+			[ Value::String(_), _, other ] => return Err(
+				Panic::type_error(other.copy(), "dict", context.pos)
+			),
+			[ other, _ ] | [ other, _, _ ] => return Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		let command = match command {
+			Value::String(ref command) => command.as_bytes().to_owned(),
+			Value::Array(ref array) => quote_command(&array.borrow(), &context.pos)?,
+			other => return Err(Panic::type_error(other, "string or array", context.pos)),
+		};
+
+		run(context, &host, command, &options)
+	}
+}
+
+
+/// Run a shell script in a remote host. The script may be given as a string, or as a
+/// function which returns either the script or an array of commands, each of which is an
+/// array of arguments to be quoted. The script stops at the first failing command.
+/// Returns the same as `std.ssh.run`.
+#[derive(Trace, Finalize)]
+struct Script;
+
+impl NativeFun for Script {
+	fn name(&self) -> &'static str { "std.ssh.script" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (host, script, options) = match context.args() {
+			[ Value::String(ref host), script ] => (host.copy(), script.copy(), Options::default()),
+
+			[ Value::String(ref host), script, Value::Dict(ref options) ] => (
+				host.copy(),
+				script.copy(),
+				Options::parse(options, &context.pos)?,
+			),
+
+			[ Value::String(_), _, other ] => return Err(
+				Panic::type_error(other.copy(), "dict", context.pos)
+			),
+			[ other, _ ] | [ other, _, _ ] => return Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		let script = match script {
+			Value::Function(ref fun) => {
+				let args_start = context.runtime.arguments.len();
+				context.call(Value::default(), fun, args_start)?
+			}
+
+			script => script,
+		};
+
+		let mut source = b"set -e\n".to_vec();
+
+		match script {
+			Value::String(ref script) => source.extend_from_slice(script.as_bytes()),
+
+			Value::Array(ref commands) => {
+				for command in commands.borrow().iter() {
+					match command {
+						Value::Array(ref args) => {
+							source.extend(quote_command(&args.borrow(), &context.pos)?);
+							source.push(b'\n');
+						}
+
+						other => return Err(Panic::type_error(other.copy(), "array", context.pos)),
+					}
+				}
+			}
+
+			other => return Err(Panic::type_error(other, "string, array or function", context.pos)),
+		}
+
+		let mut command = b"sh -c ".to_vec();
+		command.extend(util::shell_quote(&source));
+
+		run(context, &host, command, &options)
+	}
+}
+
+
+/// Options for ssh connections.
+struct Options {
+	/// The ssh client program.
+	program: OsString,
+	/// Remote user.
+	user: Option<OsString>,
+	/// Remote port.
+	port: Option<i64>,
+	/// Identity file.
+	identity: Option<OsString>,
+	/// Whether to multiplex connections.
+	multiplex: bool,
+	/// Data for the standard input of the remote command.
+	input: Option<Vec<u8>>,
+}
+
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			program: "ssh".into(),
+			user: None,
+			port: None,
+			identity: None,
+			multiplex: true,
+			input: None,
+		}
+	}
+}
+
+
+impl Options {
+	/// Parse the options dict. Supported keys are `user`, `port`, `identity`, `multiplex`,
+	/// `input` and `ssh` (the client program).
+	fn parse(options: &Dict, pos: &SourcePos) -> Result<Self, Panic> {
+		let mut result = Self::default();
+
+		let string = |key: &str| -> Result<Option<Vec<u8>>, Panic> {
+			match options.get(&key.into()) {
+				Err(_) | Ok(Value::Nil) => Ok(None),
+				Ok(Value::String(ref string)) => Ok(Some(string.as_bytes().to_owned())),
+				Ok(other) => Err(Panic::type_error(other, "string", pos.copy())),
+			}
+		};
+
+		if let Some(program) = string("ssh")? {
+			result.program = OsString::from_vec(program);
+		}
+
+		result.user = string("user")?.map(OsString::from_vec);
+		result.identity = string("identity")?.map(OsString::from_vec);
+		result.input = string("input")?;
+
+		match options.get(&"port".into()) {
+			Err(_) | Ok(Value::Nil) => (),
+			Ok(Value::Int(port)) if (1 ..= 65535).contains(&port) => result.port = Some(port),
+			Ok(Value::Int(port)) => return Err(
+				Panic::value_error(Value::Int(port), "valid port", pos.copy())
+			),
+			Ok(other) => return Err(Panic::type_error(other, "int", pos.copy())),
+		}
+
+		match options.get(&"multiplex".into()) {
+			Err(_) | Ok(Value::Nil) => (),
+			Ok(Value::Bool(multiplex)) => result.multiplex = multiplex,
+			Ok(other) => return Err(Panic::type_error(other, "bool", pos.copy())),
+		}
+
+		Ok(result)
+	}
+
+
+	/// The ssh client arguments, including the host and the remote command.
+	fn argv(&self, host: &[u8], command: Vec<u8>) -> Vec<Box<OsStr>> {
+		let mut argv: Vec<Box<OsStr>> = vec![self.program.clone().into_boxed_os_str()];
+
+		let mut arg = |arg: &OsStr| argv.push(arg.into());
+
+		if let Some(user) = &self.user {
+			arg("-l".as_ref());
+			arg(user);
+		}
+
+		if let Some(port) = self.port {
+			arg("-p".as_ref());
+			arg(port.to_string().as_ref());
+		}
+
+		if let Some(identity) = &self.identity {
+			arg("-i".as_ref());
+			arg(identity);
+		}
+
+		if self.multiplex {
+			let mut control_path = OsString::from("ControlPath=");
+			control_path.push(control_dir().join("hush-ssh-%C"));
+
+			arg("-o".as_ref());
+			arg("ControlMaster=auto".as_ref());
+			arg("-o".as_ref());
+			arg(&control_path);
+			arg("-o".as_ref());
+			arg(format!("ControlPersist={}", CONTROL_PERSIST).as_ref());
+		}
+
+		arg("--".as_ref());
+		arg(OsStr::from_bytes(host));
+		arg(OsStr::from_bytes(&command));
+
+		argv
+	}
+}
+
+
+/// Directory for the multiplexing control sockets.
+fn control_dir() -> PathBuf {
+	match std::env::var_os("XDG_RUNTIME_DIR") {
+		Some(dir) if !dir.is_empty() => dir.into(),
+		_ => std::env::temp_dir(),
+	}
+}
+
+
+/// Quote an array of arguments, joining them with spaces.
+fn quote_command(args: &[Value], pos: &SourcePos) -> Result<Vec<u8>, Panic> {
+	if args.is_empty() {
+		return Err(Panic::value_error(Vec::<Value>::new().into(), "non-empty array", pos.copy()));
+	}
+
+	let mut command = Vec::new();
+
+	for (ix, arg) in args.iter().enumerate() {
+		if ix > 0 {
+			command.push(b' ');
+		}
+
+		match arg {
+			Value::String(ref string) => command.extend(util::shell_quote(string.as_bytes())),
+			other => return Err(Panic::type_error(other.copy(), "string", pos.copy())),
+		}
+	}
+
+	Ok(command)
+}
+
+
+/// Run the remote command, reporting it to the exec hooks.
+fn run(context: CallContext, host: &Str, command: Vec<u8>, options: &Options) -> Result<Value, Panic> {
+	let pos = context.pos.copy();
+	let io_error = |error| Panic::io(error, pos.copy());
+
+	let argv = options.argv(host.as_bytes(), command);
+	let info = ExecInfo::new(argv.iter().cloned(), Box::default());
+
+	context.runtime.exec_hook(ExecEvent::Pre(info.clone()), pos.copy())?;
+
+	let _slots = children::acquire(1).map_err(io_error)?;
+
+	let mut child = process::Command::new(&argv[0])
+		.args(argv[1..].iter())
+		.stdin(if options.input.is_some() { Stdio::piped() } else { Stdio::null() })
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.map_err(io_error)?;
+
+	let writer = match (child.stdin.take(), options.input.clone()) {
+		(Some(mut stdin), Some(input)) => Some(thread::spawn(move || stdin.write_all(&input))),
+		_ => None,
+	};
+
+	let stdout_reader = reader(child.stdout.take());
+	let stderr_reader = reader(child.stderr.take());
+
+	let status = signal::wait(&mut child).map_err(io_error)?;
+
+	let stdout = join(stdout_reader).map_err(io_error)?;
+	let stderr = join(stderr_reader).map_err(io_error)?;
+
+	if let Some(writer) = writer {
+		// The remote command may not consume all of its input.
+		match join(Some(writer)) {
+			Err(error) if error.kind() != io::ErrorKind::BrokenPipe => return Err(io_error(error)),
+			_ => (),
+		}
+	}
+
+	// Only an exit status of 255 means the connection failed. If the client was killed by a
+	// signal, the status is reported like in command blocks.
+	let (status, description) = match status.code() {
+		Some(0) => (0, None),
+		Some(CONNECTION_FAILED) => (CONNECTION_FAILED, Some("ssh connection failed")),
+		Some(code) => (code, Some("command returned non-zero")),
+		None => (
+			status.signal().map(|signal| signal + 0xFF).unwrap_or(255),
+			Some("command returned non-zero"),
+		),
+	};
+
+	context.runtime.exec_hook(
+		ExecEvent::Post {
+			duration: info.start.elapsed().unwrap_or_default(),
+			info,
+			status,
+		},
+		pos.copy(),
+	)?;
+
+	signal::check(pos)?;
+
+	let mut dict = OrderedMap::new();
+	dict.insert("stdout".into(), Str::from(stdout).into());
+	dict.insert("stderr".into(), Str::from(stderr).into());
+	dict.insert("status".into(), Value::Int(status as i64));
+	let result = Dict::new(dict).into();
+
+	Ok(
+		match description {
+			None => result,
+			Some(description) => Error::new(description.into(), result).into(),
+		}
+	)
+}
+
+
+fn reader<R>(stream: Option<R>) -> Option<thread::JoinHandle<io::Result<Vec<u8>>>>
+where
+	R: Read + Send + 'static,
+{
+	stream.map(
+		|mut stream| thread::spawn(
+			move || {
+				let mut data = Vec::with_capacity(512);
+				stream.read_to_end(&mut data)?;
+				Ok(data)
+			}
+		)
+	)
+}
+
+
+fn join<T: Default>(handle: Option<thread::JoinHandle<io::Result<T>>>) -> io::Result<T> {
+	match handle.map(thread::JoinHandle::join) {
+		None => Ok(T::default()),
+		Some(Ok(result)) => result,
+		Some(Err(error)) => std::panic::resume_unwind(error),
+	}
+}
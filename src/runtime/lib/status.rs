@@ -0,0 +1,80 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Status) }
+
+/// Get the exit status of the last synchronous or capture command block executed in the
+/// calling function, or in the top level if called from there. The status is zero if all
+/// commands succeeded, or the status of the last failed command otherwise. Returns nil if
+/// no command block has been executed yet.
+///
+/// If the result of a command block is given, returns its status instead: zero for nil,
+/// the `status` field of capture results, or the status of the last failed command for
+/// errors. Returns nil for errors which were not produced by commands.
+#[derive(Trace, Finalize)]
+struct Status;
+
+impl NativeFun for Status {
+	fn name(&self) -> &'static str { "std.status" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => (),
+
+			// Synchronous blocks produce nil when all commands succeed.
+			[ Value::Nil ] => return Ok(Value::Int(0)),
+
+			[ value @ (Value::Dict(_) | Value::Error(_)) ] => {
+				return Ok(status_of(value).map(Value::Int).into())
+			}
+
+			[ other ] => return Err(Panic::type_error(other.copy(), "nil, dict or error", context.pos)),
+
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+
+		let frames = &context.runtime.frames;
+
+		// The innermost frame is the call to std.status itself.
+		let status = match frames.len().checked_sub(2) {
+			Some(caller) => frames[caller].status,
+			None => context.runtime.status,
+		};
+
+		Ok(status.map(|status| Value::Int(status as i64)).into())
+	}
+}
+
+
+/// The status of a command block result.
+fn status_of(value: &Value) -> Option<i64> {
+	thread_local! {
+		pub static STATUS: Value = "status".into();
+	}
+
+	match value {
+		// Capture results and the context of command errors.
+		Value::Dict(dict) => match STATUS.with(|status| dict.get(status)) {
+			Ok(Value::Int(status)) => Some(status),
+			_ => None,
+		},
+
+		// Failures of several commands are reported as an array of errors.
+		Value::Array(array) => array
+			.borrow()
+			.last()
+			.and_then(status_of),
+
+		Value::Error(error) => status_of(&error.context.borrow()),
+
+		_ => None,
+	}
+}
@@ -0,0 +1,35 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	NativeFun,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Strict) }
+
+
+/// Enable or disable strict mode, in which reading a variable that was declared without a
+/// value and not assigned since panics, instead of resulting in nil. Variables captured by
+/// closures are not checked. Strict mode may also be enabled with `--strict-nil`.
+#[derive(Trace, Finalize)]
+struct Strict;
+
+impl NativeFun for Strict {
+	fn name(&self) -> &'static str { "std.strict" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Bool(strict) ] => {
+				context.runtime.set_strict_nil(*strict);
+				Ok(Value::default())
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "bool", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+	}
+}
@@ -1,44 +1,49 @@
+use std::convert::TryFrom;
+
 use gc::{Finalize, Trace};
 
 use super::{
-    CallContext,
-    RustFun,
-    NativeFun,
-    Panic,
-    Value,
-    Error,
+	CallContext,
+	RustFun,
+	NativeFun,
+	Panic,
+	Value,
 };
 
 
 inventory::submit! { RustFun::from(Substr) }
 
+/// The substring of at most the given length starting at the given index. As when
+/// indexing strings, negative or out of bounds indexes panic. The length is clamped to the
+/// end of the string.
 #[derive(Trace, Finalize)]
 struct Substr;
 
-impl Substr {
-    fn substr(string: &[u8], start: i64, len: i64) -> Result<Value, Error> {
-        let start = start as usize;
-        let end = start + (len as usize);
-        let substr = string.get(start..end);
-        Ok(substr.into())
-    }
-}
-
 impl NativeFun for Substr {
-    fn name(&self) -> &'static str { "std.substr" }
-
-    fn call(&self, context: CallContext) -> Result<Value, Panic> {
-        match context.args() {
-            [ Value::String(ref string), Value::Int(start), Value::Int(len) ] => {
-                // TODO Panic if indexes out of bounds
-                let result = Self::substr(string.as_ref(), *start, *len);
-                Ok(result.unwrap_or_else(Into::into))
-            },
-            [ other, Value::Int(_), Value::Int(_) ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
-            [ Value::String(_), other, Value::Int(_) ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
-            [ Value::String(_), Value::Int(_), other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
-            args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
-        }
-    }
-}
+	fn name(&self) -> &'static str { "std.substr" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string), Value::Int(start), Value::Int(len) ] => {
+				let string = string.as_bytes();
 
+				let start = usize::try_from(*start)
+					.ok()
+					.filter(|&start| start <= string.len())
+					.ok_or_else(|| Panic::index_out_of_bounds(Value::Int(*start), context.pos.copy()))?;
+
+				let len = usize::try_from(*len)
+					.map_err(|_| Panic::value_error(Value::Int(*len), "non-negative length", context.pos.copy()))?;
+
+				let end = start.saturating_add(len).min(string.len());
+
+				Ok(string[start .. end].into())
+			},
+
+			[ other, Value::Int(_), Value::Int(_) ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ Value::String(_), other, Value::Int(_) ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			[ Value::String(_), Value::Int(_), other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 3, context.pos))
+		}
+	}
+}
@@ -0,0 +1,229 @@
+//! Coordination primitives for worker functions.
+//!
+//! Values are deep copied when sent through a channel, so that the sender and the receiver
+//! never share mutable state. As the interpreter runs in a single thread, operations never
+//! block: sending to a full channel or receiving from an empty one returns an error.
+
+use std::collections::VecDeque;
+
+use gc::{Finalize, Gc, GcCell, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	Error,
+	Function,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Channel) }
+inventory::submit! { RustFun::from(Once) }
+
+
+/// Create a channel with the given capacity, which must be positive. Returns a dict with
+/// the following methods:
+/// - `send(value)`: enqueue a copy of the value. Returns an error if the channel is full or
+///   closed.
+/// - `recv()`: dequeue the oldest value. Returns an error if the channel is empty, or if it
+///   is closed and drained.
+/// - `close()`: close the channel. Values already sent may still be received.
+/// - `len()`: the amount of queued values.
+/// - `is_closed()`: whether the channel has been closed.
+#[derive(Trace, Finalize)]
+struct Channel;
+
+impl NativeFun for Channel {
+	fn name(&self) -> &'static str { "std.sync.channel" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let capacity = match context.args() {
+			[ Value::Int(capacity) ] if *capacity > 0 => *capacity as usize,
+			[ value @ Value::Int(_) ] => return Err(
+				Panic::value_error(value.copy(), "positive capacity", context.pos)
+			),
+			[ other ] => return Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let state = Gc::new(
+			ChannelState {
+				queue: GcCell::new(VecDeque::with_capacity(capacity)),
+				capacity,
+				closed: GcCell::new(false),
+			}
+		);
+
+		let mut dict = OrderedMap::new();
+		dict.insert("send".into(), Send(state.clone()).into());
+		dict.insert("recv".into(), Recv(state.clone()).into());
+		dict.insert("close".into(), Close(state.clone()).into());
+		dict.insert("len".into(), Len(state.clone()).into());
+		dict.insert("is_closed".into(), IsClosed(state).into());
+
+		Ok(Dict::new(dict).into())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct ChannelState {
+	queue: GcCell<VecDeque<Value>>,
+	capacity: usize,
+	closed: GcCell<bool>,
+}
+
+
+#[derive(Trace, Finalize)]
+struct Send(Gc<ChannelState>);
+
+impl NativeFun for Send {
+	fn name(&self) -> &'static str { "std.sync.channel<send>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let value = match context.args() {
+			[ value ] => value.deep_copy(),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		if *self.0.closed.borrow() {
+			return Ok(Error::new("channel is closed".into(), value).into());
+		}
+
+		let mut queue = self.0.queue.borrow_mut();
+
+		if queue.len() >= self.0.capacity {
+			return Ok(Error::new("channel is full".into(), value).into());
+		}
+
+		queue.push_back(value);
+
+		Ok(Value::default())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Recv(Gc<ChannelState>);
+
+impl NativeFun for Recv {
+	fn name(&self) -> &'static str { "std.sync.channel<recv>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		if !context.args().is_empty() {
+			return Err(Panic::invalid_args(context.args().len() as u32, 0, context.pos));
+		}
+
+		let value = self.0.queue.borrow_mut().pop_front();
+
+		Ok(
+			match value {
+				Some(value) => value,
+				None if *self.0.closed.borrow() => Error::new("channel is closed".into(), Value::default()).into(),
+				None => Error::new("channel is empty".into(), Value::default()).into(),
+			}
+		)
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Close(Gc<ChannelState>);
+
+impl NativeFun for Close {
+	fn name(&self) -> &'static str { "std.sync.channel<close>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		if !context.args().is_empty() {
+			return Err(Panic::invalid_args(context.args().len() as u32, 0, context.pos));
+		}
+
+		*self.0.closed.borrow_mut() = true;
+
+		Ok(Value::default())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Len(Gc<ChannelState>);
+
+impl NativeFun for Len {
+	fn name(&self) -> &'static str { "std.sync.channel<len>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		if !context.args().is_empty() {
+			return Err(Panic::invalid_args(context.args().len() as u32, 0, context.pos));
+		}
+
+		Ok(Value::Int(self.0.queue.borrow().len() as i64))
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct IsClosed(Gc<ChannelState>);
+
+impl NativeFun for IsClosed {
+	fn name(&self) -> &'static str { "std.sync.channel<is_closed>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		if !context.args().is_empty() {
+			return Err(Panic::invalid_args(context.args().len() as u32, 0, context.pos));
+		}
+
+		Ok((*self.0.closed.borrow()).into())
+	}
+}
+
+
+/// Wrap a function so that it is called at most once. The first call invokes the function
+/// with the given arguments, and every call returns the result of the first one.
+#[derive(Trace, Finalize)]
+struct Once;
+
+impl NativeFun for Once {
+	fn name(&self) -> &'static str { "std.sync.once" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Function(fun) ] => Ok(
+				OnceImpl {
+					function: fun.copy(),
+					result: GcCell::new(None),
+				}.into()
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "function", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct OnceImpl {
+	function: Function,
+	result: GcCell<Option<Value>>,
+}
+
+impl NativeFun for OnceImpl {
+	fn name(&self) -> &'static str { "std.sync.once<impl>" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		if let Some(result) = self.result.borrow().as_ref() {
+			return Ok(result.copy());
+		}
+
+		let result = context.call(Value::default(), &self.function, context.args_start)?;
+
+		// The function may have called itself recursively, in which case the innermost call
+		// takes precedence.
+		let mut cached = self.result.borrow_mut();
+		Ok(cached.get_or_insert(result).copy())
+	}
+}
@@ -0,0 +1,92 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	RustFun,
+	NativeFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(ScriptStack) }
+inventory::submit! { RustFun::from(Script) }
+
+
+/// Insert the static values of std.sys in the given stdlib dict.
+pub(super) fn init(std: &mut Dict) {
+	let interp_path = std::env::current_exe()
+		.map(|path| Str::from(path).into())
+		.unwrap_or_default();
+
+	let argv0 = std::env::args_os()
+		.next()
+		.map(Value::from)
+		.unwrap_or_default();
+
+	super::insert("sys.interp_path", interp_path, std);
+	super::insert("sys.argv0", argv0, std);
+	super::insert("sys.program_path", Value::default(), std);
+	super::insert("sys.version", env!("CARGO_PKG_VERSION").into(), std);
+}
+
+
+/// The paths of the scripts being executed, from the main script to the innermost import.
+#[derive(Trace, Finalize)]
+struct ScriptStack;
+
+impl NativeFun for ScriptStack {
+	fn name(&self) -> &'static str { "std.sys.script_stack" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => {
+				let interner = context.interner();
+
+				let scripts: Vec<Value> = context.runtime.scripts
+					.iter()
+					.map(
+						|&script| interner
+							.resolve(script)
+							.expect("failed to resolve symbol")
+							.into()
+					)
+					.collect();
+
+				Ok(scripts.into())
+			}
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
+
+
+/// The path of the script currently being executed, which is the innermost import.
+#[derive(Trace, Finalize)]
+struct Script;
+
+impl NativeFun for Script {
+	fn name(&self) -> &'static str { "std.sys.script" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => Ok(
+				context.runtime.scripts
+					.last()
+					.map(
+						|&script| context
+							.interner()
+							.resolve(script)
+							.expect("failed to resolve symbol")
+							.into()
+					)
+					.unwrap_or_default()
+			),
+
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos))
+		}
+	}
+}
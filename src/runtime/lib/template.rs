@@ -0,0 +1,296 @@
+use bstr::ByteSlice;
+
+use gc::{Finalize, Trace};
+
+use crate::{fmt::FmtString, symbol};
+use super::{
+	CallContext,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Render) }
+
+/// Mustache-style template rendering. Supports variables (`{{name}}`), unescaped
+/// variables (`{{{name}}}` or `{{& name}}`), sections (`{{#name}}...{{/name}}`), inverted
+/// sections (`{{^name}}...{{/name}}`) and comments (`{{! comment }}`).
+/// An optional options dict may specify the escaping with the `escape` key, which may be
+/// either "html" (the default) or "none".
+#[derive(Trace, Finalize)]
+struct Render;
+
+impl NativeFun for Render {
+	fn name(&self) -> &'static str { "std.template.render" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (template, data, escape) = match context.args() {
+			[ Value::String(ref template), data @ Value::Dict(_) ] => (template, data, Escape::Html),
+
+			[ Value::String(ref template), data @ Value::Dict(_), Value::Dict(ref options) ] => {
+				let escape = match options.get(&"escape".into()) {
+					Err(_) => Escape::Html,
+					Ok(Value::String(ref escape)) if escape.as_bytes() == b"html" => Escape::Html,
+					Ok(Value::String(ref escape)) if escape.as_bytes() == b"none" => Escape::None,
+					Ok(other) => return Err(
+						Panic::value_error(other, "\"html\" or \"none\"", context.pos)
+					),
+				};
+
+				(template, data, escape)
+			}
+
+			[ Value::String(_), Value::Dict(_), other ] => return Err(
+				Panic::type_error(other.copy(), "dict", context.pos)
+			),
+			[ Value::String(_), other ] | [ Value::String(_), other, _ ] => return Err(
+				Panic::type_error(other.copy(), "dict", context.pos)
+			),
+			[ other, _ ] | [ other, _, _ ] => return Err(
+				Panic::type_error(other.copy(), "string", context.pos)
+			),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		let nodes = match parse(template.as_bytes()) {
+			Ok(nodes) => nodes,
+			Err(description) => return Ok(
+				Error::new(description.into(), Value::String(template.copy())).into()
+			),
+		};
+
+		let mut output = Vec::new();
+		let mut renderer = Renderer {
+			stack: vec![data.copy()],
+			escape,
+			interner: context.interner(),
+		};
+
+		renderer.render(&nodes, &mut output);
+
+		Ok(Str::from(output).into())
+	}
+}
+
+
+/// How to escape variables.
+#[derive(Debug, Clone, Copy)]
+enum Escape {
+	Html,
+	None,
+}
+
+
+/// A node in the template tree.
+#[derive(Debug)]
+enum Node<'a> {
+	Text(&'a [u8]),
+	Variable {
+		name: &'a [u8],
+		escape: bool,
+	},
+	Section {
+		name: &'a [u8],
+		inverted: bool,
+		children: Vec<Node<'a>>,
+	},
+}
+
+
+/// Parse a template into a tree of nodes.
+fn parse(template: &[u8]) -> Result<Vec<Node<'_>>, String> {
+	// Stack of open sections, along with the nodes preceding each section.
+	let mut sections: Vec<(&[u8], bool, Vec<Node>)> = Vec::new();
+	let mut nodes = Vec::new();
+	let mut input = template;
+
+	while let Some(start) = input.find("{{") {
+		if start > 0 {
+			nodes.push(Node::Text(&input[..start]));
+		}
+
+		input = &input[start + 2..];
+
+		let (tag, rest) =
+			if let Some(tag) = input.strip_prefix(b"{") {
+				let end = tag.find("}}}").ok_or("unclosed tag")?;
+				(&input[..end + 1], &tag[end + 3..])
+			} else {
+				let end = input.find("}}").ok_or("unclosed tag")?;
+				(&input[..end], &input[end + 2..])
+			};
+
+		input = rest;
+
+		match tag.first() {
+			Some(b'!') => (),
+
+			Some(b'{') => nodes.push(
+				Node::Variable { name: tag[1..].trim(), escape: false }
+			),
+
+			Some(b'&') => nodes.push(
+				Node::Variable { name: tag[1..].trim(), escape: false }
+			),
+
+			Some(prefix @ (b'#' | b'^')) => {
+				let name = tag[1..].trim();
+				let outer = std::mem::take(&mut nodes);
+				sections.push((name, *prefix == b'^', outer));
+			}
+
+			Some(b'/') => {
+				let name = tag[1..].trim();
+
+				let (open, inverted, outer) = sections
+					.pop()
+					.ok_or_else(|| format!("unopened section '{}'", name.as_bstr()))?;
+
+				if open != name {
+					return Err(
+						format!("mismatched section '{}', expected '{}'", name.as_bstr(), open.as_bstr())
+					);
+				}
+
+				let children = std::mem::replace(&mut nodes, outer);
+				nodes.push(Node::Section { name, inverted, children });
+			}
+
+			_ => nodes.push(
+				Node::Variable { name: tag.trim(), escape: true }
+			),
+		}
+	}
+
+	if !input.is_empty() {
+		nodes.push(Node::Text(input));
+	}
+
+	match sections.pop() {
+		Some((name, _, _)) => Err(format!("unclosed section '{}'", name.as_bstr())),
+		None => Ok(nodes),
+	}
+}
+
+
+/// Template renderer, holding the stack of contexts.
+struct Renderer<'a> {
+	stack: Vec<Value>,
+	escape: Escape,
+	interner: &'a symbol::Interner,
+}
+
+
+impl<'a> Renderer<'a> {
+	fn render(&mut self, nodes: &[Node], output: &mut Vec<u8>) {
+		for node in nodes {
+			match node {
+				Node::Text(text) => output.extend_from_slice(text),
+
+				Node::Variable { name, escape } => {
+					let value = self.lookup(name);
+					let escape = if *escape { self.escape } else { Escape::None };
+					self.write(&value, escape, output);
+				}
+
+				Node::Section { name, inverted: true, children } => {
+					if !Self::is_truthy(&self.lookup(name)) {
+						self.render(children, output);
+					}
+				}
+
+				Node::Section { name, inverted: false, children } => {
+					let value = self.lookup(name);
+
+					match &value {
+						Value::Array(array) => {
+							let items: Vec<Value> = array.borrow().iter().map(Value::copy).collect();
+
+							for item in items {
+								self.stack.push(item);
+								self.render(children, output);
+								self.stack.pop();
+							}
+						}
+
+						value if Self::is_truthy(value) => {
+							self.stack.push(value.copy());
+							self.render(children, output);
+							self.stack.pop();
+						}
+
+						_ => (),
+					}
+				}
+			}
+		}
+	}
+
+
+	/// Lookup a possibly dotted name in the context stack.
+	/// Missing names resolve to nil.
+	fn lookup(&self, name: &[u8]) -> Value {
+		if name == b"." {
+			return self.stack.last().map(Value::copy).unwrap_or_default();
+		}
+
+		let mut keys = name.split_str(".");
+		let first: Value = keys.next().unwrap_or_default().into();
+
+		let context = self.stack
+			.iter()
+			.rev()
+			.find_map(
+				|context| match context {
+					Value::Dict(dict) => dict.get(&first).ok(),
+					_ => None,
+				}
+			);
+
+		keys.fold(
+			context.unwrap_or_default(),
+			|value, key| match value {
+				Value::Dict(ref dict) => dict.get(&key.into()).unwrap_or_default(),
+				_ => Value::default(),
+			}
+		)
+	}
+
+
+	fn write(&self, value: &Value, escape: Escape, output: &mut Vec<u8>) {
+		let string = match value {
+			Value::Nil => return,
+			Value::String(string) => string.as_bytes().to_owned(),
+			other => other.fmt_string(self.interner).into_bytes(),
+		};
+
+		match escape {
+			Escape::None => output.extend_from_slice(&string),
+
+			Escape::Html => for byte in string {
+				match byte {
+					b'&' => output.extend_from_slice(b"&amp;"),
+					b'<' => output.extend_from_slice(b"&lt;"),
+					b'>' => output.extend_from_slice(b"&gt;"),
+					b'"' => output.extend_from_slice(b"&quot;"),
+					b'\'' => output.extend_from_slice(b"&#39;"),
+					other => output.push(other),
+				}
+			}
+		}
+	}
+
+
+	/// Nil, false and empty arrays are falsy. All other values are truthy.
+	fn is_truthy(value: &Value) -> bool {
+		match value {
+			Value::Nil | Value::Bool(false) => false,
+			Value::Array(array) => !array.is_empty(),
+			_ => true,
+		}
+	}
+}
@@ -0,0 +1,361 @@
+use std::{convert::TryFrom, time::Duration};
+
+use bstr::ByteSlice;
+
+use gc::{Finalize, Trace};
+
+use crate::{runtime::{signal, value}, term::{color, keys}};
+use super::{
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(IsTty) }
+inventory::submit! { RustFun::from(Color) }
+inventory::submit! { RustFun::from(Bold) }
+inventory::submit! { RustFun::from(Underline) }
+inventory::submit! { RustFun::from(NewStyle) }
+inventory::submit! { RustFun::from(ReadKey) }
+
+
+/// The names of the basic colors, by ANSI code.
+const COLORS: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+const RESET: &str = "\x1b[0m";
+
+
+/// Check whether the given file descriptor refers to a terminal. Scripts may use this to
+/// decide whether to color their own output.
+#[derive(Trace, Finalize)]
+struct IsTty;
+
+impl NativeFun for IsTty {
+	fn name(&self) -> &'static str { "std.term.is_tty" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Int(fd) ] => {
+				let fd = libc::c_int::try_from(*fd)
+					.ok()
+					.filter(|fd| *fd >= 0)
+					.ok_or_else(|| Panic::value_error(Value::Int(*fd), "valid file descriptor", context.pos.copy()))?;
+
+				// Safety: isatty has no preconditions, invalid descriptors yield false.
+				let is_tty = unsafe { libc::isatty(fd) } == 1;
+
+				Ok(is_tty.into())
+			}
+
+			[ other ] => Err(Panic::type_error(other.copy(), "int", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Paint a string with the given foreground color. Colors are either the name of a basic
+/// color (black, red, green, yellow, blue, magenta, cyan or white), optionally prefixed with
+/// `bright_`, or an int in the range 0-255. The string is returned unchanged when stdout is
+/// not a terminal or NO_COLOR is set, unless overridden by `--color`.
+#[derive(Trace, Finalize)]
+struct Color;
+
+impl NativeFun for Color {
+	fn name(&self) -> &'static str { "std.term.color" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ color, Value::String(ref text) ] => {
+				let style = Style { fg: Some(parse_color(color, &context)?), ..Style::default() };
+				Ok(style.paint(text).into())
+			}
+
+			[ _, other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		}
+	}
+}
+
+
+/// Paint a string in bold, see std.term.color.
+#[derive(Trace, Finalize)]
+struct Bold;
+
+impl NativeFun for Bold {
+	fn name(&self) -> &'static str { "std.term.bold" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		paint_text(Style { bold: true, ..Style::default() }, context)
+	}
+}
+
+
+/// Paint a string underlined, see std.term.color.
+#[derive(Trace, Finalize)]
+struct Underline;
+
+impl NativeFun for Underline {
+	fn name(&self) -> &'static str { "std.term.underline" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		paint_text(Style { underline: true, ..Style::default() }, context)
+	}
+}
+
+
+/// Create a style builder. The builder has the `fg(color)`, `bg(color)`, `bold()`,
+/// `dim()`, `italic()` and `underline()` methods, which return a new builder with the
+/// given attribute added, and `paint(text)`, which applies the style to a string. Colors
+/// are as in std.term.color. Painted strings may be nested: the outer style is restored
+/// after the inner one ends.
+#[derive(Trace, Finalize)]
+struct NewStyle;
+
+impl NativeFun for NewStyle {
+	fn name(&self) -> &'static str { "std.term.style" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => Ok(Style::default().into()),
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos)),
+		}
+	}
+}
+
+
+/// Read a key press from the terminal, waiting at most the given duration or amount of
+/// milliseconds, or forever if nil or omitted. Returns the key as a string: the character itself for
+/// printable characters, or names such as `enter`, `tab`, `esc`, `backspace`, `up`,
+/// `page_down`, `f1`, `ctrl-c` and `alt-x`. Returns nil on timeout, or an error if there is
+/// no terminal. The terminal is restored to its previous mode before returning.
+#[derive(Trace, Finalize)]
+struct ReadKey;
+
+impl NativeFun for ReadKey {
+	fn name(&self) -> &'static str { "std.term.read_key" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let timeout = match context.args() {
+			[] | [ Value::Nil ] => None,
+			[ Value::Int(millis) | Value::Duration(value::Duration(millis)) ] if *millis >= 0 => {
+				Some(Duration::from_millis(*millis as u64))
+			}
+			[ value @ (Value::Int(_) | Value::Duration(_)) ] => return Err(
+				Panic::value_error(value.copy(), "non-negative timeout", context.pos)
+			),
+			[ other ] => return Err(Panic::type_error(other.copy(), "int, duration or nil", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		// Flush pending output from std.print, so that it appears before reading.
+		let _ = std::io::Write::flush(&mut std::io::stdout());
+
+		match keys::read(timeout, &|| signal::pending().is_some()) {
+			Ok(Some(key)) => Ok(Str::from(keys::name(key)).into()),
+			Ok(None) => {
+				signal::check(context.pos)?;
+				Ok(Value::default())
+			}
+			Err(error) => Ok(Error::new(error.to_string().into(), Value::default()).into()),
+		}
+	}
+}
+
+
+/// Text attributes, as ANSI codes.
+#[derive(Finalize, Clone, Copy, Default)]
+struct Style {
+	fg: Option<u8>,
+	bg: Option<u8>,
+	bold: bool,
+	dim: bool,
+	italic: bool,
+	underline: bool,
+}
+
+/// Style has no garbage-collected fields.
+unsafe impl Trace for Style {
+	gc::unsafe_empty_trace!();
+}
+
+
+impl Style {
+	/// The escape sequence which enables the style.
+	fn open(&self) -> String {
+		let mut codes = Vec::new();
+
+		if self.bold { codes.push("1".to_owned()); }
+		if self.dim { codes.push("2".to_owned()); }
+		if self.italic { codes.push("3".to_owned()); }
+		if self.underline { codes.push("4".to_owned()); }
+		if let Some(fg) = self.fg { codes.push(format!("38;5;{}", fg)); }
+		if let Some(bg) = self.bg { codes.push(format!("48;5;{}", bg)); }
+
+		if codes.is_empty() {
+			String::new()
+		} else {
+			format!("\x1b[{}m", codes.join(";"))
+		}
+	}
+
+
+	/// Apply the style to the given text. Resets in the text, which end nested styles, are
+	/// followed by this style, so that it continues after them.
+	fn paint(&self, text: &Str) -> Str {
+		let open = self.open();
+
+		if open.is_empty() || !color::stdout_enabled() {
+			return text.copy();
+		}
+
+		let mut result = Vec::with_capacity(open.len() + text.len() + RESET.len());
+		result.extend_from_slice(open.as_bytes());
+		result.extend_from_slice(&text.as_bytes().replace(RESET, [RESET, &open].concat()));
+		result.extend_from_slice(RESET.as_bytes());
+
+		result.into()
+	}
+}
+
+
+impl From<Style> for Value {
+	fn from(style: Style) -> Self {
+		let mut dict = OrderedMap::new();
+
+		for method in [
+			StyleMethod::Fg,
+			StyleMethod::Bg,
+			StyleMethod::Bold,
+			StyleMethod::Dim,
+			StyleMethod::Italic,
+			StyleMethod::Underline,
+			StyleMethod::Paint,
+		] {
+			dict.insert(method.name().into(), RustFun::from(Method { style, method }).into());
+		}
+
+		Dict::new(dict).into()
+	}
+}
+
+
+#[derive(Finalize, Clone, Copy)]
+enum StyleMethod {
+	Fg,
+	Bg,
+	Bold,
+	Dim,
+	Italic,
+	Underline,
+	Paint,
+}
+
+/// StyleMethod has no garbage-collected fields.
+unsafe impl Trace for StyleMethod {
+	gc::unsafe_empty_trace!();
+}
+
+
+impl StyleMethod {
+	fn name(self) -> &'static str {
+		match self {
+			Self::Fg => "fg",
+			Self::Bg => "bg",
+			Self::Bold => "bold",
+			Self::Dim => "dim",
+			Self::Italic => "italic",
+			Self::Underline => "underline",
+			Self::Paint => "paint",
+		}
+	}
+}
+
+
+/// A method of a style builder.
+#[derive(Trace, Finalize)]
+struct Method {
+	style: Style,
+	method: StyleMethod,
+}
+
+impl NativeFun for Method {
+	fn name(&self) -> &'static str {
+		match self.method {
+			StyleMethod::Fg => "std.term.style<fg>",
+			StyleMethod::Bg => "std.term.style<bg>",
+			StyleMethod::Bold => "std.term.style<bold>",
+			StyleMethod::Dim => "std.term.style<dim>",
+			StyleMethod::Italic => "std.term.style<italic>",
+			StyleMethod::Underline => "std.term.style<underline>",
+			StyleMethod::Paint => "std.term.style<paint>",
+		}
+	}
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let mut style = self.style;
+
+		match (self.method, context.args()) {
+			(StyleMethod::Paint, _) => return paint_text(style, context),
+
+			(StyleMethod::Fg, [ color ]) => style.fg = Some(parse_color(color, &context)?),
+			(StyleMethod::Bg, [ color ]) => style.bg = Some(parse_color(color, &context)?),
+			(StyleMethod::Fg, args) | (StyleMethod::Bg, args) => return Err(
+				Panic::invalid_args(args.len() as u32, 1, context.pos)
+			),
+
+			(_, [ _, .. ]) => return Err(
+				Panic::invalid_args(context.args().len() as u32, 0, context.pos)
+			),
+
+			(StyleMethod::Bold, []) => style.bold = true,
+			(StyleMethod::Dim, []) => style.dim = true,
+			(StyleMethod::Italic, []) => style.italic = true,
+			(StyleMethod::Underline, []) => style.underline = true,
+		}
+
+		Ok(style.into())
+	}
+}
+
+
+/// Paint the single string argument with the given style.
+fn paint_text(style: Style, context: CallContext) -> Result<Value, Panic> {
+	match context.args() {
+		[ Value::String(ref text) ] => Ok(style.paint(text).into()),
+		[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+	}
+}
+
+
+/// Parse a color name or ANSI code.
+fn parse_color(color: &Value, context: &CallContext) -> Result<u8, Panic> {
+	let invalid = || Panic::value_error(color.copy(), "color name or code", context.pos.copy());
+
+	match color {
+		Value::Int(code) => u8::try_from(*code).map_err(|_| invalid()),
+
+		Value::String(ref name) => {
+			let (name, offset) = match name.as_bytes().strip_prefix(b"bright_") {
+				Some(name) => (name, 8),
+				None => (name.as_bytes(), 0),
+			};
+
+			COLORS
+				.iter()
+				.position(|color| color.as_bytes() == name)
+				.map(|code| code as u8 + offset)
+				.ok_or_else(invalid)
+		}
+
+		other => Err(Panic::type_error(other.copy(), "string or int", context.pos.copy())),
+	}
+}
@@ -0,0 +1,340 @@
+//! Test organization for scripts.
+//!
+//! Cases are registered with `std.test.case`, optionally nested in groups with fixtures.
+//! Registered cases are run by `std.test.run`, or after the script finishes when it is
+//! executed with `hush test <script>`.
+
+use std::io::{self, Write};
+
+use gc::{Finalize, Trace};
+
+use crate::{fmt, runtime::{Runtime, SourcePos}};
+
+use super::{
+	CallContext,
+	Dict,
+	Function,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Case) }
+inventory::submit! { RustFun::from(Group) }
+inventory::submit! { RustFun::from(Setup) }
+inventory::submit! { RustFun::from(Teardown) }
+inventory::submit! { RustFun::from(Run) }
+
+
+/// Register a test case. The case fails if the function panics.
+#[derive(Trace, Finalize)]
+struct Case;
+
+impl NativeFun for Case {
+	fn name(&self) -> &'static str { "std.test.case" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (name, fun) = name_and_function(&context)?;
+
+		let tests = &mut context.runtime.tests;
+		let group = tests.current();
+		let name = tests.path(group, name.as_bytes());
+
+		tests.cases.push(TestCase { name, fun, group });
+
+		Ok(Value::default())
+	}
+}
+
+
+/// Register a group of test cases. The function is called immediately, and the cases,
+/// groups and fixtures it registers are nested in the group.
+#[derive(Trace, Finalize)]
+struct Group;
+
+impl NativeFun for Group {
+	fn name(&self) -> &'static str { "std.test.group" }
+
+	fn call(&self, mut context: CallContext) -> Result<Value, Panic> {
+		let (name, fun) = name_and_function(&context)?;
+
+		let tests = &mut context.runtime.tests;
+		let parent = tests.current();
+		let name = tests.path(parent, name.as_bytes());
+
+		tests.groups.push(TestGroup { name, parent: Some(parent), ..TestGroup::default() });
+		tests.stack.push(tests.groups.len() - 1);
+
+		let args_start = context.runtime.arguments.len();
+		let result = context.call(Value::default(), &fun, args_start);
+
+		context.runtime.tests.stack.pop();
+
+		result.map(|_| Value::default())
+	}
+}
+
+
+/// Register a function to be called before each case of the current group, including
+/// nested groups. Outer fixtures are called first.
+#[derive(Trace, Finalize)]
+struct Setup;
+
+impl NativeFun for Setup {
+	fn name(&self) -> &'static str { "std.test.setup" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let fun = function(&context)?;
+
+		let tests = &mut context.runtime.tests;
+		let group = tests.current();
+		tests.groups[group].setup.push(fun);
+
+		Ok(Value::default())
+	}
+}
+
+
+/// Register a function to be called after each case of the current group, including
+/// nested groups, even if the case failed. Inner fixtures are called first.
+#[derive(Trace, Finalize)]
+struct Teardown;
+
+impl NativeFun for Teardown {
+	fn name(&self) -> &'static str { "std.test.teardown" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let fun = function(&context)?;
+
+		let tests = &mut context.runtime.tests;
+		let group = tests.current();
+		tests.groups[group].teardown.push(fun);
+
+		Ok(Value::default())
+	}
+}
+
+
+/// Run the registered cases, printing a report. Returns a dict with the amount of
+/// `passed` and `failed` cases. The registered cases are cleared.
+#[derive(Trace, Finalize)]
+struct Run;
+
+impl NativeFun for Run {
+	fn name(&self) -> &'static str { "std.test.run" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let summary = run(context.runtime, context.pos)?;
+
+		let mut dict = OrderedMap::new();
+		dict.insert("passed".into(), Value::Int(summary.passed as i64));
+		dict.insert("failed".into(), Value::Int(summary.failed as i64));
+
+		Ok(Dict::new(dict).into())
+	}
+}
+
+
+/// Test cases registered with std.test.
+#[derive(Debug)]
+pub struct Tests {
+	cases: Vec<TestCase>,
+	/// All groups. The first one is the root group.
+	groups: Vec<TestGroup>,
+	/// The groups being registered.
+	stack: Vec<usize>,
+}
+
+
+impl Default for Tests {
+	fn default() -> Self {
+		Self {
+			cases: Vec::new(),
+			groups: vec![TestGroup::default()],
+			stack: Vec::new(),
+		}
+	}
+}
+
+
+impl Tests {
+	/// The group being registered.
+	fn current(&self) -> usize {
+		self.stack.last().copied().unwrap_or(0)
+	}
+
+
+	/// The full name of an item in the given group.
+	fn path(&self, group: usize, name: &[u8]) -> Vec<u8> {
+		let mut path = self.groups[group].name.clone();
+
+		if !path.is_empty() {
+			path.extend_from_slice(b" / ");
+		}
+
+		path.extend_from_slice(name);
+
+		path
+	}
+
+
+	/// The groups of the given one, from the root.
+	fn chain(&self, mut group: usize) -> Vec<usize> {
+		let mut chain = vec![group];
+
+		while let Some(parent) = self.groups[group].parent {
+			chain.push(parent);
+			group = parent;
+		}
+
+		chain.reverse();
+
+		chain
+	}
+}
+
+
+#[derive(Debug)]
+struct TestCase {
+	name: Vec<u8>,
+	fun: Function,
+	group: usize,
+}
+
+
+#[derive(Debug, Default)]
+struct TestGroup {
+	name: Vec<u8>,
+	parent: Option<usize>,
+	setup: Vec<Function>,
+	teardown: Vec<Function>,
+}
+
+
+/// The result of running the registered cases.
+pub struct Summary {
+	pub passed: usize,
+	pub failed: usize,
+}
+
+
+/// Run and clear the registered cases, printing a report to stdout.
+/// Interruptions and exceeded limits abort the run.
+pub fn run(runtime: &mut Runtime, pos: SourcePos) -> Result<Summary, Panic> {
+	let tests = std::mem::take(&mut runtime.tests);
+
+	let stdout = io::stdout();
+	let io_error = |error| Panic::io(error, pos.copy());
+
+	let mut summary = Summary { passed: 0, failed: 0 };
+	let mut failures = Vec::new();
+
+	for case in &tests.cases {
+		let chain = tests.chain(case.group);
+
+		let mut result = Ok(());
+
+		for &group in &chain {
+			for fun in &tests.groups[group].setup {
+				if result.is_ok() {
+					result = call(runtime, fun, &pos);
+				}
+			}
+		}
+
+		if result.is_ok() {
+			result = call(runtime, &case.fun, &pos);
+		}
+
+		for &group in chain.iter().rev() {
+			for fun in tests.groups[group].teardown.iter().rev() {
+				let teardown = call(runtime, fun, &pos);
+				if result.is_ok() {
+					result = teardown;
+				}
+			}
+		}
+
+		if let Err(ref panic) = result {
+			if matches!(panic, Panic::Interrupted { .. } | Panic::LimitExceeded { .. }) {
+				return result.map(|_| summary);
+			}
+		}
+
+		let status = match result {
+			Ok(()) => {
+				summary.passed += 1;
+				"ok"
+			}
+
+			Err(panic) => {
+				summary.failed += 1;
+				failures.push(
+					(case.name.as_slice(), fmt::Show(panic, runtime.interner()).to_string())
+				);
+				"FAILED"
+			}
+		};
+
+		let mut stdout = stdout.lock();
+		stdout.write_all(b"test ").map_err(io_error)?;
+		stdout.write_all(&case.name).map_err(io_error)?;
+		writeln!(stdout, " ... {}", status).map_err(io_error)?;
+	}
+
+	let mut stdout = stdout.lock();
+
+	for (name, panic) in failures {
+		stdout.write_all(b"\n---- ").map_err(io_error)?;
+		stdout.write_all(name).map_err(io_error)?;
+		writeln!(stdout, " ----\n{}", panic).map_err(io_error)?;
+	}
+
+	writeln!(
+		stdout,
+		"\ntest result: {}. {} passed; {} failed",
+		if summary.failed == 0 { "ok" } else { "FAILED" },
+		summary.passed,
+		summary.failed,
+	).map_err(io_error)?;
+
+	stdout.flush().map_err(io_error)?;
+
+	Ok(summary)
+}
+
+
+fn call(runtime: &mut Runtime, fun: &Function, pos: &SourcePos) -> Result<(), Panic> {
+	let args_start = runtime.arguments.len();
+	runtime
+		.call(Value::default(), fun, args_start, pos.copy())
+		.map(|_| ())
+}
+
+
+fn name_and_function(context: &CallContext) -> Result<(Str, Function), Panic> {
+	match context.args() {
+		[ Value::String(ref name), Value::Function(ref fun) ] => Ok((name.copy(), fun.copy())),
+		[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "function", context.pos.copy())),
+		[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos.copy())),
+	}
+}
+
+
+fn function(context: &CallContext) -> Result<Function, Panic> {
+	match context.args() {
+		[ Value::Function(ref fun) ] => Ok(fun.copy()),
+		[ other ] => Err(Panic::type_error(other.copy(), "function", context.pos.copy())),
+		args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos.copy())),
+	}
+}
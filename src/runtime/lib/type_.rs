@@ -30,6 +30,8 @@ impl StdType {
 			pub static DICT: Value = Type::Dict.display().into();
 			pub static FUNCTION: Value = Type::Function.display().into();
 			pub static ERROR: Value = Type::Error.display().into();
+			pub static DURATION: Value = Type::Duration.display().into();
+			pub static SIZE: Value = Type::Size.display().into();
 		}
 
 		let typename = match value {
@@ -43,6 +45,8 @@ impl StdType {
 			Value::Dict(_) => &DICT,
 			Value::Function(_) => &FUNCTION,
 			Value::Error(_) => &ERROR,
+			Value::Duration(_) => &DURATION,
+			Value::Size(_) => &SIZE,
 		};
 
 		typename.with(Value::copy)
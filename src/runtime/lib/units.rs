@@ -0,0 +1,113 @@
+//! Durations and sizes. These are ints tagged with their unit, which support arithmetic
+//! and comparison with quantities of the same unit, and are displayed with the largest
+//! fitting unit. Functions taking timeouts in milliseconds also accept durations.
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Duration,
+	Error,
+	NativeFun,
+	RustFun,
+	Panic,
+	Size,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(StdDuration) }
+inventory::submit! { RustFun::from(StdSize) }
+inventory::submit! { RustFun::from(Milliseconds) }
+inventory::submit! { RustFun::from(Bytes) }
+
+
+/// Make a duration from a string such as "5s", "200ms" or "1h30m", or from an int in
+/// milliseconds. The units are ms, s, m, h, d and w, and quantities may be fractional.
+/// Returns an error if the string is not a valid duration.
+#[derive(Trace, Finalize)]
+struct StdDuration;
+
+impl NativeFun for StdDuration {
+	fn name(&self) -> &'static str { "std.units.duration" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value @ Value::String(ref string) ] => Ok(
+				match Duration::parse(string.as_bytes()) {
+					Some(duration) => duration.into(),
+					None => Error::new("invalid duration".into(), value.copy()).into(),
+				}
+			),
+
+			[ Value::Int(millis) ] => Ok(Duration(*millis).into()),
+			[ duration @ Value::Duration(_) ] => Ok(duration.copy()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string or int", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+	}
+}
+
+
+/// Make a size from a string such as "512M", "2G" or "10KiB", or from an int in bytes.
+/// The units are K, M, G and T, which are binary multiples, optionally followed by B or
+/// iB. Quantities without a unit are bytes, and may be fractional. Returns an error if the
+/// string is not a valid size.
+#[derive(Trace, Finalize)]
+struct StdSize;
+
+impl NativeFun for StdSize {
+	fn name(&self) -> &'static str { "std.units.size" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ value @ Value::String(ref string) ] => Ok(
+				match Size::parse(string.as_bytes()) {
+					Some(size) => size.into(),
+					None => Error::new("invalid size".into(), value.copy()).into(),
+				}
+			),
+
+			[ Value::Int(bytes) ] => Ok(Size(*bytes).into()),
+			[ size @ Value::Size(_) ] => Ok(size.copy()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string or int", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+	}
+}
+
+
+/// Get the amount of milliseconds of a duration, as an int.
+#[derive(Trace, Finalize)]
+struct Milliseconds;
+
+impl NativeFun for Milliseconds {
+	fn name(&self) -> &'static str { "std.units.milliseconds" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Duration(Duration(millis)) ] => Ok(Value::Int(*millis)),
+			[ other ] => Err(Panic::type_error(other.copy(), "duration", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+	}
+}
+
+
+/// Get the amount of bytes of a size, as an int.
+#[derive(Trace, Finalize)]
+struct Bytes;
+
+impl NativeFun for Bytes {
+	fn name(&self) -> &'static str { "std.units.bytes" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Size(Size(bytes)) ] => Ok(Value::Int(*bytes)),
+			[ other ] => Err(Panic::type_error(other.copy(), "size", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		}
+	}
+}
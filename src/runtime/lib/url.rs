@@ -0,0 +1,541 @@
+//! URL parsing and building, following RFC 3986.
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+use crate::runtime::SourcePos;
+
+
+inventory::submit! { RustFun::from(Parse) }
+inventory::submit! { RustFun::from(Build) }
+inventory::submit! { RustFun::from(Join) }
+
+
+/// Parse a URL into a dict with the `scheme`, `user`, `password`, `host`, `port`, `path`,
+/// `query` and `fragment` components. Missing components are nil. Components are
+/// percent-decoded, and the query is decoded into a dict. Keys repeated in the query
+/// produce an array of values.
+/// Returns an error if the port is invalid.
+#[derive(Trace, Finalize)]
+struct Parse;
+
+impl NativeFun for Parse {
+	fn name(&self) -> &'static str { "std.url.parse" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref string) ] => Ok(
+				parse(string.as_bytes())
+					.unwrap_or_else(|| Error::new("invalid url".into(), string.copy().into()).into())
+			),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Build a URL from a dict with the same keys as produced by `std.url.parse`, all of which
+/// are optional. The query may be a dict whose values are strings or arrays of strings.
+/// Components are percent-encoded as needed.
+#[derive(Trace, Finalize)]
+struct Build;
+
+impl NativeFun for Build {
+	fn name(&self) -> &'static str { "std.url.build" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::Dict(ref dict) ] => Ok(Str::from(build(dict, &context.pos)?).into()),
+
+			[ other ] => Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 1, context.pos))
+		}
+	}
+}
+
+
+/// Resolve a relative URL against an absolute base URL.
+/// Returns an error if the base URL has no scheme.
+#[derive(Trace, Finalize)]
+struct Join;
+
+impl NativeFun for Join {
+	fn name(&self) -> &'static str { "std.url.join" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[ Value::String(ref base), Value::String(ref reference) ] => {
+				let base_url = Url::split(base.as_bytes());
+
+				if base_url.scheme.is_none() {
+					return Ok(Error::new("invalid base url".into(), base.copy().into()).into());
+				}
+
+				let url = base_url.resolve(&Url::split(reference.as_bytes()));
+
+				Ok(Str::from(url.compose()).into())
+			}
+
+			[ Value::String(_), other ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			[ other, _ ] => Err(Panic::type_error(other.copy(), "string", context.pos)),
+			args => Err(Panic::invalid_args(args.len() as u32, 2, context.pos))
+		}
+	}
+}
+
+
+/// The components of a URL reference, as in RFC 3986, section 3. Components are kept
+/// percent-encoded.
+#[derive(Debug, Default, Clone)]
+struct Url {
+	scheme: Option<Vec<u8>>,
+	authority: Option<Vec<u8>>,
+	path: Vec<u8>,
+	query: Option<Vec<u8>>,
+	fragment: Option<Vec<u8>>,
+}
+
+
+impl Url {
+	/// Split a URL reference into its components. Any string is a valid reference, at
+	/// worst consisting only of a path.
+	fn split(input: &[u8]) -> Self {
+		let (rest, fragment) = split_once(input, b'#');
+		let (rest, query) = split_once(rest, b'?');
+
+		let (scheme, rest) = match rest.iter().position(|&c| c == b':') {
+			Some(ix) if is_scheme(&rest[..ix]) => (Some(rest[..ix].to_vec()), &rest[ix + 1 ..]),
+			_ => (None, rest),
+		};
+
+		let (authority, path) = match rest.strip_prefix(b"//") {
+			Some(rest) => {
+				let end = rest.iter().position(|&c| c == b'/').unwrap_or(rest.len());
+				(Some(rest[..end].to_vec()), &rest[end..])
+			}
+
+			None => (None, rest),
+		};
+
+		Self {
+			scheme,
+			authority,
+			path: path.to_vec(),
+			query: query.map(<[u8]>::to_vec),
+			fragment: fragment.map(<[u8]>::to_vec),
+		}
+	}
+
+
+	/// Resolve a reference using self as the base URL, as in RFC 3986, section 5.2.
+	fn resolve(&self, reference: &Url) -> Url {
+		let fragment = reference.fragment.clone();
+
+		if reference.scheme.is_some() {
+			return Url {
+				path: remove_dot_segments(&reference.path),
+				fragment,
+				..reference.clone()
+			};
+		}
+
+		if reference.authority.is_some() {
+			return Url {
+				scheme: self.scheme.clone(),
+				path: remove_dot_segments(&reference.path),
+				fragment,
+				..reference.clone()
+			};
+		}
+
+		let (path, query) = if reference.path.is_empty() {
+			(self.path.clone(), reference.query.clone().or_else(|| self.query.clone()))
+		} else if reference.path.starts_with(b"/") {
+			(remove_dot_segments(&reference.path), reference.query.clone())
+		} else {
+			(remove_dot_segments(&self.merge(&reference.path)), reference.query.clone())
+		};
+
+		Url {
+			scheme: self.scheme.clone(),
+			authority: self.authority.clone(),
+			path,
+			query,
+			fragment,
+		}
+	}
+
+
+	/// Merge a relative path with the base path, as in RFC 3986, section 5.2.3.
+	fn merge(&self, path: &[u8]) -> Vec<u8> {
+		if self.authority.is_some() && self.path.is_empty() {
+			let mut merged = b"/".to_vec();
+			merged.extend_from_slice(path);
+			return merged;
+		}
+
+		let directory = self.path
+			.iter()
+			.rposition(|&c| c == b'/')
+			.map(|ix| &self.path[..= ix])
+			.unwrap_or_default();
+
+		[directory, path].concat()
+	}
+
+
+	/// Recompose the URL, as in RFC 3986, section 5.3.
+	fn compose(&self) -> Vec<u8> {
+		let mut result = Vec::new();
+
+		if let Some(scheme) = &self.scheme {
+			result.extend_from_slice(scheme);
+			result.push(b':');
+		}
+
+		if let Some(authority) = &self.authority {
+			result.extend_from_slice(b"//");
+			result.extend_from_slice(authority);
+		}
+
+		result.extend_from_slice(&self.path);
+
+		if let Some(query) = &self.query {
+			result.push(b'?');
+			result.extend_from_slice(query);
+		}
+
+		if let Some(fragment) = &self.fragment {
+			result.push(b'#');
+			result.extend_from_slice(fragment);
+		}
+
+		result
+	}
+}
+
+
+/// Parse a URL into a dict, returning None if the port is invalid.
+fn parse(input: &[u8]) -> Option<Value> {
+	let url = Url::split(input);
+
+	let mut user = None;
+	let mut password = None;
+	let mut host = None;
+	let mut port = None;
+
+	if let Some(authority) = &url.authority {
+		let (userinfo, hostport) = match authority.iter().rposition(|&c| c == b'@') {
+			Some(ix) => (Some(&authority[..ix]), &authority[ix + 1 ..]),
+			None => (None, &authority[..]),
+		};
+
+		if let Some(userinfo) = userinfo {
+			let (name, pass) = split_once(userinfo, b':');
+			user = Some(decode(name, false));
+			password = pass.map(|pass| decode(pass, false));
+		}
+
+		// IPv6 addresses are enclosed in brackets, and contain colons.
+		let (name, port_digits) = match hostport.strip_prefix(b"[") {
+			Some(rest) => {
+				let end = rest.iter().position(|&c| c == b']')?;
+				match &rest[end + 1 ..] {
+					[] => (&rest[..end], None),
+					[ b':', digits @ .. ] => (&rest[..end], Some(digits)),
+					_ => return None,
+				}
+			}
+
+			None => match hostport.iter().rposition(|&c| c == b':') {
+				Some(ix) => (&hostport[..ix], Some(&hostport[ix + 1 ..])),
+				None => (hostport, None),
+			}
+		};
+
+		host = Some(decode(name, false));
+
+		port = match port_digits {
+			None | Some([]) => None,
+			Some(digits) => Some(
+				std::str::from_utf8(digits)
+					.ok()?
+					.parse::<u16>()
+					.ok()?
+			),
+		};
+	}
+
+	let mut dict = OrderedMap::new();
+
+	dict.insert("scheme".into(), url.scheme.map(|scheme| Value::from(Str::from(scheme))).into());
+	dict.insert("user".into(), user.map(Value::from).into());
+	dict.insert("password".into(), password.map(Value::from).into());
+	dict.insert("host".into(), host.map(Value::from).into());
+	dict.insert("port".into(), port.map(|port| Value::Int(port.into())).into());
+	dict.insert("path".into(), decode(&url.path, false).into());
+	dict.insert("query".into(), url.query.map(|query| parse_query(&query)).into());
+	dict.insert("fragment".into(), url.fragment.map(|fragment| Value::from(decode(&fragment, false))).into());
+
+	Some(Dict::new(dict).into())
+}
+
+
+/// Decode a query string into a dict. Repeated keys produce an array of values.
+fn parse_query(query: &[u8]) -> Value {
+	let dict = Dict::default();
+
+	for pair in query.split(|&c| c == b'&').filter(|pair| !pair.is_empty()) {
+		let (key, value) = split_once(pair, b'=');
+		let key: Value = decode(key, true).into();
+		let value: Value = decode(value.unwrap_or_default(), true).into();
+
+		let previous = dict.get(&key).ok();
+
+		let value = match previous {
+			Some(Value::Array(ref array)) => {
+				array.copy().push(value);
+				continue;
+			}
+
+			Some(previous) => vec![previous, value].into(),
+			None => value,
+		};
+
+		dict.insert(key, value);
+	}
+
+	dict.into()
+}
+
+
+/// Build a URL from a dict.
+fn build(dict: &Dict, pos: &SourcePos) -> Result<Vec<u8>, Panic> {
+	let string = |key: &str| -> Result<Option<Str>, Panic> {
+		match dict.get(&key.into()) {
+			Err(_) | Ok(Value::Nil) => Ok(None),
+			Ok(Value::String(ref string)) => Ok(Some(string.copy())),
+			Ok(other) => Err(Panic::type_error(other, "string", pos.copy())),
+		}
+	};
+
+	let mut url = Url {
+		scheme: string("scheme")?.map(|scheme| scheme.as_bytes().to_vec()),
+		..Url::default()
+	};
+
+	if let Some(host) = string("host")? {
+		let mut authority = Vec::new();
+
+		if let Some(user) = string("user")? {
+			authority.extend(encode(user.as_bytes(), USERINFO));
+
+			if let Some(password) = string("password")? {
+				authority.push(b':');
+				authority.extend(encode(password.as_bytes(), USERINFO));
+			}
+
+			authority.push(b'@');
+		}
+
+		if host.as_bytes().contains(&b':') {
+			authority.push(b'[');
+			authority.extend_from_slice(host.as_bytes());
+			authority.push(b']');
+		} else {
+			authority.extend(encode(host.as_bytes(), HOST));
+		}
+
+		match dict.get(&"port".into()) {
+			Err(_) | Ok(Value::Nil) => (),
+			Ok(Value::Int(port)) if (0 ..= 65535).contains(&port) => {
+				authority.extend(format!(":{}", port).bytes());
+			}
+			Ok(Value::Int(port)) => return Err(
+				Panic::value_error(Value::Int(port), "valid port", pos.copy())
+			),
+			Ok(other) => return Err(Panic::type_error(other, "int", pos.copy())),
+		}
+
+		url.authority = Some(authority);
+	}
+
+	if let Some(path) = string("path")? {
+		// With an authority, the path must be either empty or absolute.
+		if url.authority.is_some() && !path.as_bytes().is_empty() && !path.as_bytes().starts_with(b"/") {
+			url.path.push(b'/');
+		}
+
+		url.path.extend(encode(path.as_bytes(), PATH));
+	}
+
+	url.query = match dict.get(&"query".into()) {
+		Err(_) | Ok(Value::Nil) => None,
+		Ok(Value::Dict(ref query)) => Some(build_query(query, pos)?),
+		Ok(other) => return Err(Panic::type_error(other, "dict", pos.copy())),
+	};
+
+	url.fragment = string("fragment")?.map(|fragment| encode(fragment.as_bytes(), FRAGMENT));
+
+	Ok(url.compose())
+}
+
+
+/// Encode a query dict.
+fn build_query(query: &Dict, pos: &SourcePos) -> Result<Vec<u8>, Panic> {
+	let mut result = Vec::new();
+
+	let mut pair = |key: &Str, value: &Value| -> Result<(), Panic> {
+		match value {
+			Value::String(ref value) => {
+				if !result.is_empty() {
+					result.push(b'&');
+				}
+
+				result.extend(encode(key.as_bytes(), QUERY));
+				result.push(b'=');
+				result.extend(encode(value.as_bytes(), QUERY));
+
+				Ok(())
+			}
+
+			other => Err(Panic::type_error(other.copy(), "string or array of strings", pos.copy())),
+		}
+	};
+
+	for (key, value) in query.borrow().iter() {
+		let key = match key {
+			Value::String(ref key) => key,
+			other => return Err(Panic::type_error(other.copy(), "string", pos.copy())),
+		};
+
+		match value {
+			Value::Array(ref values) => {
+				for value in values.borrow().iter() {
+					pair(key, value)?;
+				}
+			}
+
+			value => pair(key, value)?,
+		}
+	}
+
+	Ok(result)
+}
+
+
+/// Characters which need not be encoded, besides the unreserved ones.
+const USERINFO: &[u8] = b"!$&'()*+,;=";
+const HOST: &[u8] = b"!$&'()*+,;=";
+const PATH: &[u8] = b"!$&'()*+,;=:@/";
+const QUERY: &[u8] = b"!$'()*,;:@/?";
+const FRAGMENT: &[u8] = b"!$&'()*+,;=:@/?";
+
+
+/// Percent-encode all characters but the unreserved ones and the given allowed ones.
+fn encode(input: &[u8], allowed: &[u8]) -> Vec<u8> {
+	let mut result = Vec::with_capacity(input.len());
+
+	for &c in input {
+		if c.is_ascii_alphanumeric() || b"-._~".contains(&c) || allowed.contains(&c) {
+			result.push(c);
+		} else {
+			result.extend(format!("%{:02X}", c).bytes());
+		}
+	}
+
+	result
+}
+
+
+/// Percent-decode the input. Invalid escape sequences are kept as is. In form decoding,
+/// plus signs are decoded as spaces.
+fn decode(input: &[u8], form: bool) -> Str {
+	let mut result = Vec::with_capacity(input.len());
+	let mut ix = 0;
+
+	while ix < input.len() {
+		let hex = |offset: usize| input
+			.get(ix + offset)
+			.and_then(|&c| (c as char).to_digit(16));
+
+		match input[ix] {
+			b'%' => match (hex(1), hex(2)) {
+				(Some(high), Some(low)) => {
+					result.push((high * 16 + low) as u8);
+					ix += 2;
+				}
+
+				_ => result.push(b'%'),
+			},
+
+			b'+' if form => result.push(b' '),
+
+			c => result.push(c),
+		}
+
+		ix += 1;
+	}
+
+	result.into()
+}
+
+
+/// Remove the dot segments of a path, as in RFC 3986, section 5.2.4.
+fn remove_dot_segments(path: &[u8]) -> Vec<u8> {
+	let absolute = path.starts_with(b"/");
+	let segments: Vec<&[u8]> = path.split(|&c| c == b'/').collect();
+	let last = segments.len() - 1;
+
+	let mut output: Vec<&[u8]> = Vec::new();
+
+	for (ix, &segment) in segments.iter().enumerate().skip(absolute as usize) {
+		match segment {
+			b"." => (),
+			b".." => {
+				output.pop();
+			}
+			segment => output.push(segment),
+		}
+
+		// A trailing dot segment denotes a directory.
+		if ix == last && (segment == b"." || segment == b"..") {
+			output.push(b"");
+		}
+	}
+
+	let mut result = if absolute { b"/".to_vec() } else { Vec::new() };
+	result.extend(output.join(&b'/'));
+
+	result
+}
+
+
+/// Split the input at the first occurrence of the separator.
+fn split_once(input: &[u8], separator: u8) -> (&[u8], Option<&[u8]>) {
+	match input.iter().position(|&c| c == separator) {
+		Some(ix) => (&input[..ix], Some(&input[ix + 1 ..])),
+		None => (input, None),
+	}
+}
+
+
+fn is_scheme(input: &[u8]) -> bool {
+	match input {
+		[ first, rest @ .. ] => first.is_ascii_alphabetic()
+			&& rest.iter().all(|&c| c.is_ascii_alphanumeric() || b"+-.".contains(&c)),
+		[] => false,
+	}
+}
@@ -0,0 +1,99 @@
+//! Lookups in the user and group databases.
+
+use std::{
+	ffi::{CStr, CString, OsStr},
+	os::unix::ffi::OsStrExt,
+	path::PathBuf,
+};
+
+
+/// An entry of the user database.
+#[derive(Debug)]
+pub struct User {
+	pub name: CString,
+	pub uid: libc::uid_t,
+	pub gid: libc::gid_t,
+	pub home: PathBuf,
+}
+
+
+/// Lookup a user by name.
+pub fn by_name(name: &[u8]) -> Option<User> {
+	let name = CString::new(name).ok()?;
+
+	lookup(
+		|passwd, buffer, result| unsafe {
+			libc::getpwnam_r(name.as_ptr(), passwd, buffer.as_mut_ptr(), buffer.len(), result)
+		}
+	)
+}
+
+
+/// Lookup a user by id.
+pub fn by_uid(uid: libc::uid_t) -> Option<User> {
+	lookup(
+		|passwd, buffer, result| unsafe {
+			libc::getpwuid_r(uid, passwd, buffer.as_mut_ptr(), buffer.len(), result)
+		}
+	)
+}
+
+
+/// Lookup a group id by name.
+pub fn group_by_name(name: &[u8]) -> Option<libc::gid_t> {
+	let name = CString::new(name).ok()?;
+	let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+
+	loop {
+		// SAFETY: group is a plain C struct, which is filled by getgrnam_r.
+		let mut group: libc::group = unsafe { std::mem::zeroed() };
+		let mut result = std::ptr::null_mut();
+
+		let status = unsafe {
+			libc::getgrnam_r(name.as_ptr(), &mut group, buffer.as_mut_ptr(), buffer.len(), &mut result)
+		};
+
+		match status {
+			libc::ERANGE if buffer.len() < 1 << 20 => buffer.resize(buffer.len() * 2, 0),
+			0 if !result.is_null() => return Some(group.gr_gid),
+			_ => return None,
+		}
+	}
+}
+
+
+/// Perform a reentrant lookup in the user database, growing the buffer as needed.
+fn lookup<F>(mut getpw: F) -> Option<User>
+where
+	F: FnMut(&mut libc::passwd, &mut Vec<libc::c_char>, &mut *mut libc::passwd) -> libc::c_int,
+{
+	let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+
+	loop {
+		// SAFETY: passwd is a plain C struct, which is filled by the lookup.
+		let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+		let mut result = std::ptr::null_mut();
+
+		match getpw(&mut passwd, &mut buffer, &mut result) {
+			libc::ERANGE if buffer.len() < 1 << 20 => buffer.resize(buffer.len() * 2, 0),
+
+			0 if !result.is_null() && !passwd.pw_name.is_null() && !passwd.pw_dir.is_null() => {
+				// SAFETY: the strings point into the buffer, which is still alive.
+				let (name, home) = unsafe {
+					(CStr::from_ptr(passwd.pw_name), CStr::from_ptr(passwd.pw_dir))
+				};
+
+				return Some(
+					User {
+						name: name.to_owned(),
+						uid: passwd.pw_uid,
+						gid: passwd.pw_gid,
+						home: PathBuf::from(OsStr::from_bytes(home.to_bytes())),
+					}
+				);
+			}
+
+			_ => return None,
+		}
+	}
+}
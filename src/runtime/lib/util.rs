@@ -54,3 +54,23 @@ impl<const N: usize> Numbers<N> {
 		}
 	}
 }
+
+
+/// Quote a single argument for a POSIX shell.
+pub fn shell_quote(arg: &[u8]) -> Vec<u8> {
+	let mut quoted = Vec::with_capacity(arg.len() + 2);
+
+	quoted.push(b'\'');
+
+	for &byte in arg {
+		if byte == b'\'' {
+			quoted.extend_from_slice(b"'\\''");
+		} else {
+			quoted.push(byte);
+		}
+	}
+
+	quoted.push(b'\'');
+
+	quoted
+}
@@ -0,0 +1,277 @@
+//! Validation of data, such as parsed configuration files, against a schema.
+
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Type,
+	Value,
+};
+use crate::runtime::SourcePos;
+
+
+inventory::submit! { RustFun::from(Validate) }
+
+
+/// Validate a value against a schema, which is a dict supporting the following keys:
+/// - `type`: the name of the expected type, as returned by std.type, or an array of names.
+/// - `required`: an array of keys which must be present in a dict.
+/// - `keys`: a dict from keys to the schemas of the respective values in a dict. Absent
+///   keys are only reported if required.
+/// - `items`: the schema of every item in an array.
+/// - `min` and `max`: inclusive bounds for numbers, or for the length of strings, arrays
+///   and dicts.
+/// - `one_of`: an array of the allowed values.
+///
+/// Returns nil if the value is valid. Otherwise, returns an error whose context is an
+/// array of all violations, each a dict with the `path` to the offending value (such as
+/// `.servers[0].port`) and a `message`.
+#[derive(Trace, Finalize)]
+struct Validate;
+
+impl NativeFun for Validate {
+	fn name(&self) -> &'static str { "std.validate" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (value, schema) = match context.args() {
+			[ value, Value::Dict(ref schema) ] => (value.copy(), schema.copy()),
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 2, context.pos)),
+		};
+
+		let schema = Schema::parse(&schema, &context.pos)?;
+
+		let mut violations = Vec::new();
+		schema.validate(&value, &mut String::from("."), &mut violations);
+
+		if violations.is_empty() {
+			Ok(Value::Nil)
+		} else {
+			Ok(Error::new("validation failed".into(), violations.into()).into())
+		}
+	}
+}
+
+
+/// A parsed schema.
+#[derive(Default)]
+struct Schema {
+	types: Option<Vec<Type>>,
+	required: Vec<Value>,
+	keys: Vec<(Value, Schema)>,
+	items: Option<Box<Schema>>,
+	min: Option<f64>,
+	max: Option<f64>,
+	one_of: Option<Vec<Value>>,
+}
+
+
+impl Schema {
+	fn parse(dict: &Dict, pos: &SourcePos) -> Result<Self, Panic> {
+		let mut schema = Self::default();
+
+		for (key, value) in dict.borrow().iter() {
+			let key_name = match key {
+				Value::String(ref string) => string.as_bytes(),
+				other => return Err(Panic::type_error(other.copy(), "string", pos.copy())),
+			};
+
+			match key_name {
+				b"type" => schema.types = Some(
+					match value {
+						Value::Array(ref array) => array
+							.borrow()
+							.iter()
+							.map(|name| parse_type(name, pos))
+							.collect::<Result<_, _>>()?,
+						name => vec![parse_type(name, pos)?],
+					}
+				),
+
+				b"required" => schema.required = match value {
+					Value::Array(ref array) => array.borrow().iter().map(Value::copy).collect(),
+					other => return Err(Panic::type_error(other.copy(), "array", pos.copy())),
+				},
+
+				b"keys" => match value {
+					Value::Dict(ref keys) => {
+						for (key, value) in keys.borrow().iter() {
+							let value = match value {
+								Value::Dict(ref value) => Self::parse(value, pos)?,
+								other => return Err(Panic::type_error(other.copy(), "dict", pos.copy())),
+							};
+
+							schema.keys.push((key.copy(), value));
+						}
+					}
+
+					other => return Err(Panic::type_error(other.copy(), "dict", pos.copy())),
+				},
+
+				b"items" => schema.items = match value {
+					Value::Dict(ref items) => Some(Box::new(Self::parse(items, pos)?)),
+					other => return Err(Panic::type_error(other.copy(), "dict", pos.copy())),
+				},
+
+				b"min" => schema.min = Some(parse_bound(value, pos)?),
+
+				b"max" => schema.max = Some(parse_bound(value, pos)?),
+
+				b"one_of" => schema.one_of = match value {
+					Value::Array(ref array) => Some(array.borrow().iter().map(Value::copy).collect()),
+					other => return Err(Panic::type_error(other.copy(), "array", pos.copy())),
+				},
+
+				_ => return Err(
+					Panic::value_error(
+						key.copy(),
+						"schema key (type, required, keys, items, min, max or one_of)",
+						pos.copy(),
+					)
+				),
+			}
+		}
+
+		Ok(schema)
+	}
+
+
+	/// Validate the value at the given path, collecting the violations.
+	fn validate(&self, value: &Value, path: &mut String, violations: &mut Vec<Value>) {
+		let found = value.get_type();
+
+		if let Some(types) = &self.types {
+			if !types.contains(&found) {
+				let expected: Vec<&str> = types
+					.iter()
+					.map(Type::display)
+					.collect();
+
+				let message = format!("expected {}, found {}", expected.join(" or "), found);
+				violations.push(violation(path, message));
+
+				// Further checks would be meaningless for the unexpected type.
+				return;
+			}
+		}
+
+		if let Some(allowed) = &self.one_of {
+			if !allowed.contains(value) {
+				violations.push(violation(path, "expected one of the allowed values".into()));
+			}
+		}
+
+		let (size, what) = match value {
+			Value::Int(int) => (Some(*int as f64), "value"),
+			Value::Float(float) => (Some(float.0), "value"),
+			Value::String(ref string) => (Some(string.as_bytes().len() as f64), "length"),
+			Value::Array(ref array) => (Some(array.len() as f64), "length"),
+			Value::Dict(ref dict) => (Some(dict.len() as f64), "length"),
+			_ => (None, ""),
+		};
+
+		if let Some(size) = size {
+			if let Some(min) = self.min.filter(|&min| size < min) {
+				let message = format!("expected {} of at least {}, found {}", what, min, size);
+				violations.push(violation(path, message));
+			}
+
+			if let Some(max) = self.max.filter(|&max| size > max) {
+				let message = format!("expected {} of at most {}, found {}", what, max, size);
+				violations.push(violation(path, message));
+			}
+		}
+
+		match value {
+			Value::Dict(ref dict) => {
+				for key in &self.required {
+					if !dict.contains(key) {
+						with_key(path, key, |path| {
+							violations.push(violation(path, "missing required key".into()))
+						});
+					}
+				}
+
+				for (key, schema) in &self.keys {
+					if let Ok(item) = dict.get(key) {
+						with_key(path, key, |path| schema.validate(&item, path, violations));
+					}
+				}
+			}
+
+			Value::Array(ref array) => {
+				if let Some(schema) = &self.items {
+					for (ix, item) in array.borrow().iter().enumerate() {
+						let len = path.len();
+						path.push_str(&format!("[{}]", ix));
+						schema.validate(item, path, violations);
+						path.truncate(len);
+					}
+				}
+			}
+
+			_ => (),
+		}
+	}
+}
+
+
+fn parse_type(name: &Value, pos: &SourcePos) -> Result<Type, Panic> {
+	match name {
+		Value::String(ref string) => Type::parse(string.as_bytes())
+			.ok_or_else(|| Panic::value_error(name.copy(), "type name", pos.copy())),
+		other => Err(Panic::type_error(other.copy(), "string", pos.copy())),
+	}
+}
+
+
+fn parse_bound(value: &Value, pos: &SourcePos) -> Result<f64, Panic> {
+	match value {
+		Value::Int(int) => Ok(*int as f64),
+		Value::Float(float) => Ok(float.0),
+		other => Err(Panic::type_error(other.copy(), "int or float", pos.copy())),
+	}
+}
+
+
+/// Run the given function with the path extended by a dict key.
+fn with_key<F>(path: &mut String, key: &Value, f: F)
+where
+	F: FnOnce(&mut String),
+{
+	let len = path.len();
+
+	match key {
+		Value::String(ref string) => {
+			// The root path is a sole dot.
+			if path != "." {
+				path.push('.');
+			}
+
+			path.push_str(&String::from_utf8_lossy(string.as_bytes()));
+		}
+
+		Value::Int(int) => path.push_str(&format!("[{}]", int)),
+
+		other => path.push_str(&format!("[<{}>]", other.get_type())),
+	}
+
+	f(path);
+	path.truncate(len);
+}
+
+
+fn violation(path: &str, message: String) -> Value {
+	let mut dict = OrderedMap::new();
+
+	dict.insert("path".into(), path.into());
+	dict.insert("message".into(), message.into());
+
+	Dict::new(dict).into()
+}
@@ -0,0 +1,517 @@
+//! Watching of file changes, built on inotify.
+#![cfg(target_os = "linux")]
+
+use std::{
+	collections::{HashMap, VecDeque},
+	ffi::{CString, OsStr},
+	fs::{self, File},
+	io::{self, Read},
+	os::unix::{ffi::OsStrExt, io::{AsRawFd, FromRawFd}},
+	path::{Path, PathBuf},
+	time::{Duration, Instant},
+};
+
+use gc::{Finalize, GcCell, Trace};
+
+use crate::runtime::{signal, value, SourcePos};
+use super::{
+	CallContext,
+	Dict,
+	Error,
+	NativeFun,
+	NativeIter,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Str,
+	Value,
+};
+
+
+inventory::submit! { RustFun::from(Watch) }
+
+
+/// Maximum time to wait for events before checking for signals.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The inotify events of interest.
+const MASK: u32 = libc::IN_CREATE
+	| libc::IN_MOVED_TO
+	| libc::IN_MODIFY
+	| libc::IN_ATTRIB
+	| libc::IN_CLOSE_WRITE
+	| libc::IN_DELETE
+	| libc::IN_MOVED_FROM
+	| libc::IN_DELETE_SELF
+	| libc::IN_MOVE_SELF;
+
+
+/// Watch files and directories for changes, returning a lazy iterator of events. The paths
+/// may be a string or an array of strings, and are watched from the moment of the call.
+/// Each event is a dict with the `type` ("created", "modified" or "removed") and the
+/// `path` of the changed file. Errors while watching are produced as errors.
+/// The optional options dict supports the following keys:
+/// - `recursive`: whether to watch subdirectories, including ones created later. Defaults
+///   to false.
+/// - `debounce`: duration or milliseconds to wait for further events after the first one.
+///   Events for the same path within that window are merged into one. Defaults to 50.
+/// - `timeout`: duration or milliseconds to wait for an event before finishing the
+///   iteration, or nil to wait forever, which is the default.
+///
+/// Setting up the watches may fail, in which case an error is returned instead.
+#[derive(Trace, Finalize)]
+struct Watch;
+
+impl NativeFun for Watch {
+	fn name(&self) -> &'static str { "std.fs.watch" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let (paths, options) = match context.args() {
+			[ paths ] => (Self::paths(paths, &context.pos)?, Options::default()),
+
+			[ paths, Value::Dict(ref options) ] => (
+				Self::paths(paths, &context.pos)?,
+				Options::parse(options, &context.pos)?,
+			),
+
+			[ _, other ] => return Err(Panic::type_error(other.copy(), "dict", context.pos)),
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let mut watcher = match Watcher::new(options) {
+			Ok(watcher) => watcher,
+			Err(error) => return Ok(Error::new(error.to_string().into(), Value::default()).into()),
+		};
+
+		for path in paths {
+			if let Err(error) = watcher.add(&path, true) {
+				return Ok(Watcher::error(error, &path));
+			}
+		}
+
+		Ok(WatchImpl(GcCell::new(WatcherCell(watcher))).into())
+	}
+}
+
+
+impl Watch {
+	fn paths(paths: &Value, pos: &SourcePos) -> Result<Vec<PathBuf>, Panic> {
+		let path = |value: &Value| match value {
+			Value::String(ref path) => Ok(PathBuf::from(OsStr::from_bytes(path.as_bytes()))),
+			other => Err(Panic::type_error(other.copy(), "string", pos.copy())),
+		};
+
+		match paths {
+			Value::Array(ref array) => array.borrow().iter().map(path).collect(),
+			Value::String(_) => Ok(vec![path(paths)?]),
+			other => Err(Panic::type_error(other.copy(), "string or array", pos.copy())),
+		}
+	}
+}
+
+
+/// Options for watching files.
+struct Options {
+	recursive: bool,
+	debounce: Duration,
+	timeout: Option<Duration>,
+}
+
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			recursive: false,
+			debounce: Duration::from_millis(50),
+			timeout: None,
+		}
+	}
+}
+
+
+impl Options {
+	fn parse(options: &Dict, pos: &SourcePos) -> Result<Self, Panic> {
+		let mut result = Self::default();
+
+		let millis = |key: &str| -> Result<Option<Duration>, Panic> {
+			match options.get(&key.into()) {
+				Err(_) | Ok(Value::Nil) => Ok(None),
+				Ok(Value::Int(millis) | Value::Duration(value::Duration(millis))) if millis >= 0 => {
+					Ok(Some(Duration::from_millis(millis as u64)))
+				}
+				Ok(value @ (Value::Int(_) | Value::Duration(_))) => Err(
+					Panic::value_error(value, "non-negative int", pos.copy())
+				),
+				Ok(other) => Err(Panic::type_error(other, "int or duration", pos.copy())),
+			}
+		};
+
+		match options.get(&"recursive".into()) {
+			Err(_) | Ok(Value::Nil) => (),
+			Ok(Value::Bool(recursive)) => result.recursive = recursive,
+			Ok(other) => return Err(Panic::type_error(other, "bool", pos.copy())),
+		}
+
+		if let Some(debounce) = millis("debounce")? {
+			result.debounce = debounce;
+		}
+
+		result.timeout = millis("timeout")?;
+
+		Ok(result)
+	}
+}
+
+
+/// The type of a change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+	Created,
+	Modified,
+	Removed,
+}
+
+
+impl Change {
+	fn name(self) -> &'static str {
+		match self {
+			Self::Created => "created",
+			Self::Modified => "modified",
+			Self::Removed => "removed",
+		}
+	}
+
+
+	/// Merge a change with a following one on the same path. None if they cancel out.
+	fn merge(self, next: Self) -> Option<Self> {
+		match (self, next) {
+			(Self::Created, Self::Modified) => Some(Self::Created),
+			(Self::Created, Self::Removed) => None,
+			(Self::Removed, Self::Created) => Some(Self::Modified),
+			(_, next) => Some(next),
+		}
+	}
+}
+
+
+/// A watched path.
+struct Watched {
+	path: PathBuf,
+	/// Whether the path was given by the user, as opposed to a watched subdirectory.
+	root: bool,
+}
+
+
+/// The state of a watch.
+struct Watcher {
+	inotify: File,
+	/// Watched paths, by watch descriptor.
+	watches: HashMap<libc::c_int, Watched>,
+	options: Options,
+	/// Changes of the current batch, in order of arrival. Changes that cancelled out are
+	/// left as None.
+	batch: Vec<(PathBuf, Option<Change>)>,
+	/// The index of each path in the current batch.
+	indices: HashMap<PathBuf, usize>,
+	/// Merged changes yet to be produced.
+	ready: VecDeque<(PathBuf, Change)>,
+}
+
+
+impl Watcher {
+	fn new(options: Options) -> io::Result<Self> {
+		let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+		if fd < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		Ok(
+			Self {
+				inotify: unsafe { File::from_raw_fd(fd) },
+				watches: HashMap::new(),
+				options,
+				batch: Vec::new(),
+				indices: HashMap::new(),
+				ready: VecDeque::new(),
+			}
+		)
+	}
+
+
+	/// Watch the given path, and its subdirectories if recursive.
+	fn add(&mut self, path: &Path, root: bool) -> io::Result<()> {
+		let c_path = CString::new(path.as_os_str().as_bytes())
+			.map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+		let wd = unsafe { libc::inotify_add_watch(self.inotify.as_raw_fd(), c_path.as_ptr(), MASK) };
+		if wd < 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		self.watches.insert(wd, Watched { path: path.to_owned(), root });
+
+		if self.options.recursive && fs::symlink_metadata(path)?.is_dir() {
+			for entry in fs::read_dir(path)? {
+				let entry = entry?;
+				if entry.file_type()?.is_dir() {
+					self.add(&entry.path(), false)?;
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+
+	/// Get the next event, waiting for it if needed. None if the timeout elapsed.
+	fn next(&mut self, pos: &SourcePos) -> Result<Option<Value>, Panic> {
+		while self.ready.is_empty() {
+			match self.collect() {
+				Ok(true) => (),
+				Ok(false) => return Ok(None),
+				Err(error) => {
+					signal::check(pos.copy())?;
+					return Ok(Some(Error::new(error.to_string().into(), Value::default()).into()));
+				}
+			}
+		}
+
+		Ok(
+			self.ready
+				.pop_front()
+				.map(|(path, change)| Self::event(path, change))
+		)
+	}
+
+
+	/// Collect a batch of changes, waiting for further events within the debounce window.
+	/// Returns false if no event arrived before the timeout.
+	fn collect(&mut self) -> io::Result<bool> {
+		if !self.wait(self.options.timeout)? {
+			return Ok(false);
+		}
+
+		self.read()?;
+
+		while !self.options.debounce.is_zero() && self.wait(Some(self.options.debounce))? {
+			self.read()?;
+		}
+
+		self.indices.clear();
+		self.ready.extend(
+			self.batch
+				.drain(..)
+				.filter_map(|(path, change)| change.map(|change| (path, change)))
+		);
+
+		Ok(true)
+	}
+
+
+	/// Wait for events up to the given duration, or forever if None. Fails with
+	/// `Interrupted` if a signal is received.
+	fn wait(&self, timeout: Option<Duration>) -> io::Result<bool> {
+		let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+		loop {
+			if signal::pending().is_some() {
+				return Err(io::ErrorKind::Interrupted.into());
+			}
+
+			let interval = match deadline {
+				Some(deadline) => {
+					let now = Instant::now();
+					if now >= deadline {
+						return Ok(false);
+					}
+					POLL_INTERVAL.min(deadline - now)
+				}
+				None => POLL_INTERVAL,
+			};
+
+			let mut poll = libc::pollfd {
+				fd: self.inotify.as_raw_fd(),
+				events: libc::POLLIN,
+				revents: 0,
+			};
+
+			let millis = interval.as_millis().max(1) as libc::c_int;
+			match unsafe { libc::poll(&mut poll, 1, millis) } {
+				0 => (),
+				result if result > 0 => return Ok(true),
+				_ => {
+					let error = io::Error::last_os_error();
+					if error.kind() != io::ErrorKind::Interrupted {
+						return Err(error);
+					}
+				}
+			}
+		}
+	}
+
+
+	/// Read all available events into the current batch.
+	fn read(&mut self) -> io::Result<()> {
+		const HEADER: usize = std::mem::size_of::<libc::inotify_event>();
+
+		let mut buffer = [0; 4096];
+
+		loop {
+			let length = match self.inotify.read(&mut buffer) {
+				Ok(length) => length,
+				Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+				Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+				Err(error) => return Err(error),
+			};
+
+			let mut offset = 0;
+			while offset + HEADER <= length {
+				let event = unsafe {
+					std::ptr::read_unaligned(buffer[offset ..].as_ptr() as *const libc::inotify_event)
+				};
+
+				let name = &buffer[offset + HEADER .. offset + HEADER + event.len as usize];
+				let name = &name[.. name.iter().position(|&byte| byte == 0).unwrap_or(name.len())];
+
+				self.handle(event.wd, event.mask, name)?;
+
+				offset += HEADER + event.len as usize;
+			}
+		}
+	}
+
+
+	/// Record an event in the current batch.
+	fn handle(&mut self, wd: libc::c_int, mask: u32, name: &[u8]) -> io::Result<()> {
+		if mask & libc::IN_Q_OVERFLOW != 0 {
+			return Err(io::Error::other("too many events, some were lost"));
+		}
+
+		if mask & libc::IN_IGNORED != 0 {
+			self.watches.remove(&wd);
+			return Ok(());
+		}
+
+		let watched = match self.watches.get(&wd) {
+			Some(watched) => watched,
+			None => return Ok(()),
+		};
+
+		let path = if name.is_empty() {
+			watched.path.clone()
+		} else {
+			watched.path.join(OsStr::from_bytes(name))
+		};
+
+		let change = if mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 {
+			Change::Created
+		} else if mask & (libc::IN_DELETE | libc::IN_MOVED_FROM) != 0 {
+			Change::Removed
+		} else if mask & (libc::IN_DELETE_SELF | libc::IN_MOVE_SELF) != 0 {
+			if !watched.root {
+				return Ok(()); // Already reported by the parent directory.
+			}
+			Change::Removed
+		} else {
+			Change::Modified
+		};
+
+		self.record(path.clone(), change);
+
+		if change == Change::Created && self.options.recursive && mask & libc::IN_ISDIR != 0 {
+			self.add_created(&path)?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Watch a directory created after the watch started, recording its contents as created,
+	/// as they may have been created before the directory was watched.
+	fn add_created(&mut self, path: &Path) -> io::Result<()> {
+		match self.add(path, false) {
+			Ok(()) => (),
+			Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()), // Already gone.
+			Err(error) => return Err(error),
+		}
+
+		let mut pending = vec![path.to_owned()];
+		while let Some(dir) = pending.pop() {
+			for entry in fs::read_dir(&dir)? {
+				let entry = entry?;
+				if entry.file_type()?.is_dir() {
+					pending.push(entry.path());
+				}
+				self.record(entry.path(), Change::Created);
+			}
+		}
+
+		Ok(())
+	}
+
+
+	/// Record a change in the current batch, merging it with previous changes of the path.
+	fn record(&mut self, path: PathBuf, change: Change) {
+		match self.indices.get(&path) {
+			Some(&ix) => {
+				let merged = self.batch[ix].1.and_then(|previous| previous.merge(change));
+				self.batch[ix].1 = merged;
+				if merged.is_none() {
+					self.indices.remove(&path);
+				}
+			}
+
+			None => {
+				self.indices.insert(path.clone(), self.batch.len());
+				self.batch.push((path, Some(change)));
+			}
+		}
+	}
+
+
+	fn event(path: PathBuf, change: Change) -> Value {
+		let mut event = OrderedMap::new();
+		event.insert("type".into(), change.name().into());
+		event.insert("path".into(), Str::from(path).into());
+
+		Dict::new(event).into()
+	}
+
+
+	fn error(error: io::Error, path: &Path) -> Value {
+		Error::new(
+			error.to_string().into(),
+			Str::from(path.to_owned()).into()
+		).into()
+	}
+}
+
+
+/// The watcher holds no garbage-collected values.
+#[derive(Finalize)]
+struct WatcherCell(Watcher);
+
+
+unsafe impl Trace for WatcherCell {
+	gc::unsafe_empty_trace!();
+}
+
+
+#[derive(Trace, Finalize)]
+struct WatchImpl(GcCell<WatcherCell>);
+
+impl NativeFun for WatchImpl {
+	fn name(&self) -> &'static str { "std.fs.watch<impl>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		self.call_iter(context)
+	}
+
+	fn as_iter(&self) -> Option<&dyn NativeIter> { Some(self) }
+}
+
+impl NativeIter for WatchImpl {
+	fn next(&self, context: CallContext) -> Result<Option<Value>, Panic> {
+		self.0.borrow_mut().0.next(&context.pos)
+	}
+}
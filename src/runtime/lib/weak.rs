@@ -0,0 +1,54 @@
+use gc::{Finalize, Trace};
+
+use super::{
+	CallContext,
+	Dict,
+	NativeFun,
+	OrderedMap,
+	RustFun,
+	Panic,
+	Value,
+	Weak,
+};
+
+
+inventory::submit! { RustFun::from(WeakRef) }
+
+
+/// Create a weak reference to a dict or array, which doesn't prevent it from being
+/// collected. Returns a handle with a `get` method, which returns the value or nil if it
+/// has been collected.
+#[derive(Trace, Finalize)]
+struct WeakRef;
+
+impl NativeFun for WeakRef {
+	fn name(&self) -> &'static str { "std.weak" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		let weak = match context.args() {
+			[ value ] => Weak::new(value)
+				.ok_or_else(|| Panic::type_error(value.copy(), "dict or array", context.pos.copy()))?,
+			args => return Err(Panic::invalid_args(args.len() as u32, 1, context.pos)),
+		};
+
+		let mut handle = OrderedMap::new();
+		handle.insert("get".into(), RustFun::from(Get(weak)).into());
+
+		Ok(Dict::new(handle).into())
+	}
+}
+
+
+#[derive(Trace, Finalize)]
+struct Get(Weak);
+
+impl NativeFun for Get {
+	fn name(&self) -> &'static str { "std.weak<get>" }
+
+	fn call(&self, context: CallContext) -> Result<Value, Panic> {
+		match context.args() {
+			[] => Ok(self.0.get()),
+			args => Err(Panic::invalid_args(args.len() as u32, 0, context.pos)),
+		}
+	}
+}
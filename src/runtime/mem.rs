@@ -4,9 +4,9 @@ use gc::{Gc, GcCell, Finalize, Trace};
 
 use crate::{
 	fmt::{self, Display},
-	symbol,
+	symbol::{self, Symbol},
 };
-use super::{program, Value};
+use super::{program, SourcePos, Value};
 
 
 /// The index of a memory slot in the stack, relative to the top.
@@ -44,6 +44,11 @@ enum Slot {
 	Regular(Value),
 	/// A value that is closed over.
 	Closed(Gc<GcCell<Value>>),
+	/// A variable declared without a value, which is nil until assigned.
+	Unassigned {
+		identifier: Symbol,
+		pos: SourcePos,
+	},
 }
 
 
@@ -53,6 +58,7 @@ impl Slot {
 		match self {
 			Slot::Regular(value) => value.copy(),
 			Slot::Closed(value) => value.deref().borrow().copy(),
+			Slot::Unassigned { .. } => Value::Nil,
 		}
 	}
 
@@ -62,6 +68,7 @@ impl Slot {
 		match self {
 			Self::Closed(val) => *val.borrow_mut() = value,
 			Self::Regular(val) => *val = value,
+			Self::Unassigned { .. } => *self = Self::Regular(value),
 		}
 	}
 
@@ -78,6 +85,22 @@ impl Slot {
 				*self = Slot::Closed(value.clone());
 				value
 			}
+			// Closures can't tell whether a value has been assigned.
+			Slot::Unassigned { .. } => {
+				let value = Gc::new(GcCell::new(Value::Nil));
+				*self = Slot::Closed(value.clone());
+				value
+			}
+		}
+	}
+
+
+	/// Mark the slot as declared without a value. Closed values are set to nil instead, as
+	/// they may be shared with closures.
+	fn declare(&mut self, identifier: Symbol, pos: SourcePos) {
+		match self {
+			Self::Closed(val) => *val.borrow_mut() = Value::Nil,
+			_ => *self = Self::Unassigned { identifier, pos },
 		}
 	}
 
@@ -145,6 +168,13 @@ impl Stack {
 	}
 
 
+	/// Fetch the value of a slot.
+	/// The index is counted from the bottom.
+	pub fn fetch_at(&self, index: usize) -> Value {
+		self.slots[index].fetch()
+	}
+
+
 	/// Capture a slot.
 	pub fn capture(&mut self, slot_ix: SlotIx) -> Gc<GcCell<Value>> {
 		let len = self.len();
@@ -161,6 +191,27 @@ impl Stack {
 	}
 
 
+	/// Declare a variable without a value in a slot.
+	/// The offset is counted from the top.
+	pub fn declare(&mut self, slot_ix: SlotIx, identifier: Symbol, pos: SourcePos) {
+		let len = self.len();
+		let offset = slot_ix.0 as usize;
+		self.slots[len - 1 - offset].declare(identifier, pos)
+	}
+
+
+	/// The name and declaration position of the variable in a slot, if it has been declared
+	/// without a value and not assigned since.
+	/// The offset is counted from the top.
+	pub fn unassigned(&self, slot_ix: SlotIx) -> Option<(Symbol, SourcePos)> {
+		let offset = slot_ix.0 as usize;
+		match &self.slots[self.len() - 1 - offset] {
+			Slot::Unassigned { identifier, pos } => Some((*identifier, pos.copy())),
+			_ => None,
+		}
+	}
+
+
 	/// Store a value in a slot.
 	/// The offset is counted from the top.
 	pub fn store(&mut self, slot_ix: SlotIx, value: Value) {
@@ -2,35 +2,48 @@
 #![allow(clippy::mutable_key_type)]
 
 
+pub mod children;
 mod command;
+pub mod debug;
 mod flow;
 mod lib;
 mod mem;
 mod panic;
+pub mod signal;
 mod source;
 pub mod value;
+pub mod watchdog;
 #[cfg(test)]
 mod tests;
 
 use std::collections::HashMap;
 
-use crate::symbol::{self, Symbol};
+use crate::{
+	fmt::{self, FmtString},
+	symbol::{self, Symbol},
+};
 use super::semantic::program;
 use value::{
 	keys,
 	Array,
 	CallContext,
 	Dict,
+	Duration,
 	Error,
 	Float,
 	Function,
 	HushFun,
 	RustFun,
 	NativeFun,
+	NativeIter,
+	OrderedMap,
+	Size,
 	Str,
 	Value,
+	Weak,
 	Type,
 };
+pub use command::fds;
 pub use panic::Panic;
 pub use source::SourcePos;
 use flow::Flow;
@@ -48,6 +61,56 @@ pub struct Runtime {
 	modules: HashMap<Symbol, Value>,
 	/// Command line arguments.
 	args: Value,
+	/// Paths of the scripts being executed, including the import chain.
+	scripts: Vec<Symbol>,
+	/// Functions registered with std.hooks.
+	exec_hooks: command::ExecHooks,
+	/// Output format of errors in imported modules.
+	error_format: fmt::ErrorFormat,
+	/// Whether the script is being run to print completions, see std.complete.
+	completing: bool,
+	/// Test cases registered with std.test.
+	tests: lib::Tests,
+	/// Read timeout for capture blocks, see std.capture_timeout.
+	capture_timeout: Option<command::CaptureTimeout>,
+	/// Active function calls, innermost last, see std.backtrace.
+	frames: Vec<Frame>,
+	/// Options for glob expansion in commands, see std.glob_options.
+	glob_options: command::GlobOptions,
+	/// Status of the last command block in the top level, see std.status.
+	status: Option<i32>,
+	/// Whether the last command block was interrupted, see `check_interrupted`.
+	interrupted: bool,
+	/// Functions registered with std.at_exit.
+	exit_handlers: Vec<Function>,
+	/// Function registered with std.set_panic_hook.
+	panic_hook: Option<Function>,
+	/// Functions registered with std.signal.trap, by signal.
+	signal_traps: HashMap<i32, Function>,
+	/// Whether a signal handler is running, see `check_signals`.
+	handling_signal: bool,
+	/// Whether reading variables declared without a value panics, see std.strict.
+	strict_nil: bool,
+	/// Scheduled collections and their statistics, see std.gc.schedule.
+	collector: lib::Collector,
+	/// Debugger attached with `set_debugger`, if any.
+	debugger: Option<Box<dyn debug::Debugger>>,
+	/// Top levels of the scripts being executed, for debuggers.
+	roots: Vec<debug::Root>,
+}
+
+
+/// An active function call.
+#[derive(Debug)]
+struct Frame {
+	function: Function,
+	/// Where the function was called from.
+	pos: SourcePos,
+	/// Status of the last command block in the function, see std.status.
+	status: Option<i32>,
+	/// Index of the first stack slot of the function, for debuggers. None for builtins, and
+	/// after the slots are dropped for a tail call.
+	base: Option<usize>,
 }
 
 
@@ -71,10 +134,59 @@ impl Runtime {
 			std: lib::new(),
 			modules: HashMap::new(),
 			args: args.into(),
+			scripts: Vec::new(),
+			exec_hooks: command::ExecHooks::default(),
+			error_format: fmt::ErrorFormat::default(),
+			completing: false,
+			tests: lib::Tests::default(),
+			capture_timeout: None,
+			frames: Vec::new(),
+			glob_options: command::GlobOptions::default(),
+			status: None,
+			interrupted: false,
+			exit_handlers: Vec::new(),
+			panic_hook: None,
+			signal_traps: HashMap::new(),
+			handling_signal: false,
+			strict_nil: false,
+			collector: lib::Collector::default(),
+			debugger: None,
+			roots: Vec::new(),
 		}
 	}
 
 
+	/// Set the output format of errors in imported modules.
+	pub fn set_error_format(&mut self, format: fmt::ErrorFormat) {
+		self.error_format = format;
+	}
+
+
+	/// Run the script to print completions for its arguments, see std.complete.
+	pub fn set_completing(&mut self, completing: bool) {
+		self.completing = completing;
+	}
+
+
+	/// Panic when reading variables declared without a value, see std.strict.
+	pub fn set_strict_nil(&mut self, strict_nil: bool) {
+		self.strict_nil = strict_nil;
+	}
+
+
+	/// Attach a debugger, which may pause the execution before each statement.
+	pub fn set_debugger(&mut self, debugger: Box<dyn debug::Debugger>) {
+		self.debugger = Some(debugger);
+	}
+
+
+	/// Run the test cases registered with std.test, printing a report.
+	/// Returns whether all cases passed.
+	pub fn run_tests(&mut self, pos: SourcePos) -> Result<bool, Panic> {
+		lib::run_tests(self, pos).map(|summary| summary.failed == 0)
+	}
+
+
 	/// Get an immutable reference to the symbol interner owned by this runtime.
 	pub fn interner(&self) -> &symbol::Interner {
 		&self.interner
@@ -102,12 +214,40 @@ impl Runtime {
 		// Stdlib.
 		self.stack.store(mem::SlotIx(0), self.std.copy());
 
-		// Execute the program.
-		let value = match self.eval_block(&program.statements)? {
+		if self.scripts.is_empty() {
+			let path = self.interner
+				.resolve(program.source)
+				.expect("failed to resolve symbol")
+				.into();
+
+			lib::set_program_path(&self.std, path);
+		}
+
+		// Execute the program. Each script's top level has its own command status.
+		let status = self.status.take();
+		self.scripts.push(program.source);
+		self.roots.push(
+			debug::Root {
+				path: program.source,
+				depth: self.frames.len(),
+				base: initial_stack_len,
+				slot_names: &program.root_slot_names,
+			}
+		);
+		let result = self.eval_block(&program.statements);
+		self.roots.pop();
+		self.scripts.pop();
+		self.status = status;
+
+		let value = match result? {
 			Flow::Regular(value) => value,
 			flow => panic!("invalid flow in root state: {:#?}", flow)
 		};
 
+		if let Some(statement) = program.statements.0.last() {
+			self.check_interrupted(statement)?;
+		}
+
 		// Drop global variables.
 		self.stack.shrink(slots);
 
@@ -140,14 +280,17 @@ impl Runtime {
 		let last_statement = iter.next_back();
 
 		for statement in iter {
+			self.debug(statement);
+
 			match self.eval_statement(statement)? {
-				Flow::Regular(_) => (),
+				Flow::Regular(_) => self.check_interrupted(statement)?,
 				flow => return Ok(flow),
 			}
 		}
 
 		let flow =
 			if let Some(last_statement) = last_statement {
+				self.debug(last_statement);
 				self.eval_tail_statement(last_statement, tail_call)?
 			} else {
 				Flow::Regular(Value::default())
@@ -157,6 +300,121 @@ impl Runtime {
 	}
 
 
+	/// Let the attached debugger pause before executing a statement.
+	fn debug(&mut self, statement: &'static program::Statement) {
+		let pos = match (&self.debugger, statement.pos()) {
+			(Some(_), Some(pos)) => SourcePos::from(pos),
+			_ => return,
+		};
+
+		if let Some(mut debugger) = self.debugger.take() {
+			if debugger.check(&pos, self.frames.len(), &self.interner) {
+				debugger.pause(debug::frames(self, pos), &self.interner);
+			}
+
+			self.debugger = Some(debugger);
+		}
+	}
+
+
+	/// Command blocks interrupted by SIGINT produce an error value. If the statement is such
+	/// a block, whose value is discarded, the script is interrupted nonetheless.
+	fn check_interrupted(&mut self, statement: &'static program::Statement) -> Result<(), Panic> {
+		match statement {
+			program::Statement::Expr(program::Expr::CommandBlock { pos, .. })
+				if std::mem::take(&mut self.interrupted) => Err(
+					Panic::interrupted(libc::SIGINT, pos.into())
+				),
+
+			_ => Ok(()),
+		}
+	}
+
+
+	/// Panic if the interpreter has been interrupted by a signal, and otherwise call the
+	/// handlers of the pending trapped signals. Handlers are not called recursively: signals
+	/// received while a handler runs are handled afterwards.
+	fn check_signals(&mut self, pos: SourcePos) -> Result<(), Panic> {
+		signal::check(pos.copy())?;
+
+		if self.handling_signal {
+			return Ok(());
+		}
+
+		self.handling_signal = true;
+		let result = self.run_signal_traps(pos);
+		self.handling_signal = false;
+
+		result
+	}
+
+
+	fn run_signal_traps(&mut self, pos: SourcePos) -> Result<(), Panic> {
+		while let Some(signal) = signal::take_trapped() {
+			let handler = match self.signal_traps.get(&signal) {
+				Some(handler) => handler.copy(),
+				None => continue,
+			};
+
+			let args_start = self.arguments.len();
+			self.call(Value::Nil, &handler, args_start, pos.copy())?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Run the functions registered with std.at_exit, the most recent first. Each function
+	/// runs only once, even if it exits the script.
+	pub fn run_exit_handlers(&mut self, pos: SourcePos) -> Result<(), Panic> {
+		while let Some(handler) = self.exit_handlers.pop() {
+			let args_start = self.arguments.len();
+			self.call(Value::Nil, &handler, args_start, pos.copy())?;
+		}
+
+		Ok(())
+	}
+
+
+	/// Call the function registered with std.set_panic_hook, if any, with a dict describing
+	/// the panic: its `kind`, `message`, `path`, `line` and `column`, and the `context` given
+	/// to std.panic. The hook runs only once, even if it panics.
+	pub fn run_panic_hook(&mut self, panic: &Panic) -> Result<(), Panic> {
+		let hook = match self.panic_hook.take() {
+			Some(hook) => hook,
+			None => return Ok(()),
+		};
+
+		let pos = panic.pos();
+
+		let mut dict = OrderedMap::new();
+		dict.insert("kind".into(), panic.kind().into());
+		dict.insert("message".into(), Str::from(panic.message(&self.interner)).into());
+		dict.insert(
+			"path".into(),
+			self.interner
+				.resolve(pos.path)
+				.map(Value::from)
+				.unwrap_or_default(),
+		);
+		dict.insert("line".into(), Value::Int(pos.line as i64));
+		dict.insert("column".into(), Value::Int(pos.column as i64));
+		dict.insert(
+			"context".into(),
+			match panic {
+				Panic::User { context, .. } => context.copy(),
+				_ => Value::default(),
+			}
+		);
+
+		let args_start = self.arguments.len();
+		self.arguments.push(Dict::new(dict).into());
+		self.call(Value::Nil, &hook, args_start, pos.copy())?;
+
+		Ok(())
+	}
+
+
 	/// Execute a literal.
 	/// For trivial types, this basically instatiates a corresponding value.
 	/// For compound types, sub-expressions are evaluated.
@@ -202,7 +460,7 @@ impl Runtime {
 
 			// Dict.
 			program::Literal::Dict(exprs) => {
-				let mut dict = HashMap::new();
+				let mut dict = OrderedMap::new();
 
 				for (symbol, expr) in exprs.iter() {
 					let key: Value = self.interner
@@ -220,7 +478,7 @@ impl Runtime {
 			}
 
 			// Function.
-			program::Literal::Function { params, frame_info, body } => {
+			program::Literal::Function { params, frame_info, body, doc, name } => {
 				let context = frame_info
 					.captures
 					.iter()
@@ -234,7 +492,7 @@ impl Runtime {
 
 				Ok(
 					Flow::Regular(
-						HushFun::new(*params, frame_info, body, context, pos.into()).into()
+						HushFun::new(*params, frame_info, body, context, pos.into(), *doc, *name).into()
 					)
 				)
 			},
@@ -284,7 +542,7 @@ impl Runtime {
 		match expr {
 			// Identifier.
 			program::Expr::Identifier { slot_ix, pos } => {
-				let value = self.stack.fetch(slot_ix.into());
+				let value = self.fetch_variable(slot_ix, pos.into())?;
 				Ok((Flow::Regular(value), pos.into(), Value::default()))
 			},
 
@@ -312,13 +570,30 @@ impl Runtime {
 				Ok((flow, pos, Value::default()))
 			}
 
+			// Interpolation.
+			program::Expr::Interpolation { parts, pos } => {
+				let pos = pos.into();
+				let mut string = Vec::new();
+
+				for part in parts.iter() {
+					match regular_expr!(part, pos).0 {
+						Value::String(ref part) => string.extend_from_slice(part.as_bytes()),
+						value => string.extend_from_slice(value.fmt_string(&self.interner).as_bytes()),
+					}
+				}
+
+				Ok((Flow::Regular(Str::from(string).into()), pos, Value::default()))
+			}
+
 			// If.
-			program::Expr::If { condition, then, otherwise, pos } => {
+			program::Expr::If { condition, condition_text, then, otherwise, pos } => {
 				let pos = pos.into();
 
 				let condition = match self.eval_expr(condition)? {
 					(Flow::Regular(Value::Bool(b)), _, _) => b,
-					(Flow::Regular(value), pos, _) => return Err(Panic::invalid_condition(value, pos)),
+					(Flow::Regular(value), pos, _) => return Err(
+						Panic::invalid_condition(value, *condition_text, pos)
+					),
 					(flow, _, _) => return Ok((flow, pos, Value::default()))
 				};
 
@@ -332,10 +607,14 @@ impl Runtime {
 			}
 
 			// Access.
-			program::Expr::Access { object, field, pos } => {
+			program::Expr::Access { object, field, safe, pos } => {
 				let pos = pos.into();
 
 				let (obj, obj_pos) = regular_expr!(object, pos);
+
+				if *safe && matches!(obj, Value::Nil) {
+					return Ok((Flow::Regular(Value::default()), pos, obj));
+				}
 				let (field, field_pos) = regular_expr!(field, pos);
 
 				let value = match (&obj, field) {
@@ -366,13 +645,13 @@ impl Runtime {
 			}
 
 			// Call.
-			program::Expr::Call { function, args, pos } => {
+			program::Expr::Call { function, function_text, args, pos } => {
 				let pos = pos.into();
 
 				// Eval function.
 				let (function, obj) = match self.eval_expr(function)? {
 					(Flow::Regular(Value::Function(ref fun)), _, obj) => (fun.copy(), obj),
-					(Flow::Regular(value), pos, _) => return Err(Panic::invalid_call(value, pos)),
+					(Flow::Regular(value), pos, _) => return Err(Panic::invalid_call(value, *function_text, pos)),
 					(flow, _, _) => return Ok((flow, pos, Value::default())),
 				};
 
@@ -400,7 +679,17 @@ impl Runtime {
 
 			// CommandBlock.
 			program::Expr::CommandBlock { block, pos } => {
-				let value = self.eval_command_block(block, pos.into())?;
+				let input = match &block.input {
+					Some(input) => match self.eval_expr(&input.source)? {
+						(Flow::Regular(value), pos, _) => Some((value, pos)),
+						(flow, pos, _) => return Ok((flow, pos, Value::default())),
+					},
+
+					None => None,
+				};
+
+				let value = self.eval_command_block(block, input, pos.into())?;
+				self.check_signals(pos.into())?;
 				Ok((Flow::Regular(value), pos.into(), Value::default()))
 			}
 		}
@@ -413,6 +702,48 @@ impl Runtime {
 	}
 
 
+	/// Assign a value to an l-value.
+	fn assign(&mut self, left: &'static program::Lvalue, value: Value) -> Result<Flow, Panic> {
+		match left {
+			program::Lvalue::Identifier { slot_ix, .. } => self.stack.store(slot_ix.into(), value),
+
+			program::Lvalue::Access { object, field, pos } => {
+				let (obj, obj_pos) = match self.eval_expr(object)? {
+					(Flow::Regular(obj), pos, _) => (obj, pos),
+					(flow, _, _) => return Ok(flow),
+				};
+
+				let (field, field_pos) = match self.eval_expr(field)? {
+					(Flow::Regular(field), pos, _) => (field, pos),
+					(flow, _, _) => return Ok(flow),
+				};
+
+				match (obj, field) {
+					// Note that strings are immutable.
+
+					(Value::Dict(ref dict), field) => dict.insert(field, value),
+
+					(Value::Array(ref array), Value::Int(ix)) if ix >= array.len() => return Err(
+						Panic::index_out_of_bounds(Value::Int(ix), field_pos)
+					),
+
+					(Value::Array(ref array), Value::Int(ix)) => array
+						.set(ix, value)
+						.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), pos.into()))?,
+
+					(Value::Array(_), field) => return Err(Panic::type_error(field, "int", field_pos)),
+
+					(Value::Error(_), field) => return Err(Panic::assign_to_readonly_field(field, field_pos)),
+
+					(obj, _) => return Err(Panic::type_error(obj, "array, dict or error", obj_pos)),
+				};
+			}
+		}
+
+		Ok(Flow::Regular(Value::default()))
+	}
+
+
 	/// Execute a statement.
 	fn eval_tail_statement<F>(
 		&mut self,
@@ -423,6 +754,12 @@ impl Runtime {
 		F: FnOnce(&mut Self),
 	{
 		match statement {
+			// Declare.
+			program::Statement::Declare { slot_ix, identifier, pos } => {
+				self.stack.declare(slot_ix.into(), *identifier, pos.into());
+				Ok(Flow::Regular(Value::default()))
+			}
+
 			// Assign.
 			program::Statement::Assign { left, right } => {
 				let value = match self.eval_expr(right)?.0 {
@@ -430,39 +767,10 @@ impl Runtime {
 					flow => return Ok(flow),
 				};
 
-				match left {
-					program::Lvalue::Identifier { slot_ix, .. } => self.stack.store(slot_ix.into(), value),
-
-					program::Lvalue::Access { object, field, pos } => {
-						let (obj, obj_pos) = match self.eval_expr(object)? {
-							(Flow::Regular(obj), pos, _) => (obj, pos),
-							(flow, _, _) => return Ok(flow),
-						};
-
-						let (field, field_pos) = match self.eval_expr(field)? {
-							(Flow::Regular(field), pos, _) => (field, pos),
-							(flow, _, _) => return Ok(flow),
-						};
-
-						match (obj, field) {
-							// Note that strings are immutable.
-
-							(Value::Dict(ref dict), field) => dict.insert(field, value),
-
-							(Value::Array(ref array), Value::Int(ix)) if ix >= array.len() => return Err(
-								Panic::index_out_of_bounds(Value::Int(ix), field_pos)
-							),
-
-							(Value::Array(ref array), Value::Int(ix)) => array
-								.set(ix, value)
-								.map_err(|_| Panic::index_out_of_bounds(Value::Int(ix), pos.into()))?,
-
-							(Value::Array(_), field) => return Err(Panic::type_error(field, "int", field_pos)),
-
-							(Value::Error(_), field) => return Err(Panic::assign_to_readonly_field(field, field_pos)),
-
-							(obj, _) => return Err(Panic::type_error(obj, "array, dict or error", obj_pos)),
-						};
+				for left in left.iter().rev() {
+					match self.assign(left, value.copy())? {
+						Flow::Regular(_) => (),
+						flow => return Ok(flow),
 					}
 				}
 
@@ -481,11 +789,16 @@ impl Runtime {
 			program::Statement::Break => Ok(Flow::Break),
 
 			// While.
-			program::Statement::While { condition, block } => {
+			program::Statement::While { condition, condition_text, block } => {
 				loop {
 					let condition = match self.eval_expr(condition)? {
-						(Flow::Regular(Value::Bool(b)), _, _) => b,
-						(Flow::Regular(value), pos, _) => return Err(Panic::invalid_condition(value, pos)),
+						(Flow::Regular(Value::Bool(b)), pos, _) => {
+							self.check_signals(pos)?;
+							b
+						},
+						(Flow::Regular(value), pos, _) => return Err(
+							Panic::invalid_condition(value, *condition_text, pos)
+						),
 						(flow, _, _) => return Ok(flow)
 					};
 
@@ -493,6 +806,8 @@ impl Runtime {
 						break;
 					}
 
+					self.collector.tick();
+
 					match self.eval_block(block)? {
 						Flow::Regular(_) => (),
 						flow @ Flow::Return(_) => return Ok(flow),
@@ -514,38 +829,14 @@ impl Runtime {
 				};
 
 				loop {
-					// While evaluating arguments, we may need to call other functions, so we must
-					// keep track of when our arguments start.
-					let args_start = self.arguments.len();
-					match self.call(Value::default(), &iter, args_start, pos.copy())? {
-						Value::Dict(ref dict) => {
-							let finished = keys::FINISHED.with(
-								|finished| dict
-									.get(finished)
-									.map_err(|_| Panic::index_out_of_bounds(finished.copy(), pos.copy()))
-							)?;
-
-							match finished {
-								Value::Bool(false) => {
-									let value = keys::VALUE.with(
-										|value| dict
-											.get(value)
-											.map_err(|_| Panic::index_out_of_bounds(value.copy(), pos.copy()))
-									)?;
-
-									self.stack.store(slot_ix.copy(), value);
-								},
-
-								Value::Bool(true) => break,
-
-								other => return Err(Panic::type_error(other, "bool", pos))
-							}
-
-							Value::Nil
-						},
+					self.check_signals(pos.copy())?;
 
-						other => return Err(Panic::type_error(other, "dict", pos)),
-					};
+					match self.iterate(&iter, pos.copy())? {
+						Some(value) => self.stack.store(slot_ix.copy(), value),
+						None => break,
+					}
+
+					self.collector.tick();
 
 					match self.eval_block(block)? {
 						Flow::Regular(_) => (),
@@ -565,7 +856,59 @@ impl Runtime {
 	}
 
 
-	/// Call the given function.
+	/// Fetch the value of a variable. In strict mode, variables which have been declared
+	/// without a value and not assigned since can't be read.
+	fn fetch_variable(&self, slot_ix: &program::mem::SlotIx, pos: SourcePos) -> Result<Value, Panic> {
+		if self.strict_nil {
+			if let Some((variable, declaration)) = self.stack.unassigned(slot_ix.into()) {
+				return Err(Panic::unassigned_variable(variable, declaration, pos));
+			}
+		}
+
+		Ok(self.stack.fetch(slot_ix.into()))
+	}
+
+
+	/// Get the next item of an iterator function, as in for loops. Native iterators are
+	/// advanced directly, while other functions must return an iteration dict.
+	fn iterate(&mut self, iter: &Function, pos: SourcePos) -> Result<Option<Value>, Panic> {
+		if let Function::Rust(fun) = iter {
+			if let Some(native) = fun.as_iter() {
+				let args_start = self.arguments.len();
+				return native.next(CallContext { runtime: self, obj: Value::default(), args_start, pos });
+			}
+		}
+
+		// While evaluating arguments, we may need to call other functions, so we must
+		// keep track of when our arguments start.
+		let args_start = self.arguments.len();
+		let dict = match self.call(Value::default(), iter, args_start, pos.copy())? {
+			Value::Dict(ref dict) => dict.copy(),
+			other => return Err(Panic::type_error(other, "dict", pos)),
+		};
+
+		let finished = keys::FINISHED.with(
+			|finished| dict
+				.get(finished)
+				.map_err(|_| Panic::index_out_of_bounds(finished.copy(), pos.copy()))
+		)?;
+
+		match finished {
+			Value::Bool(true) => Ok(None),
+
+			Value::Bool(false) => keys::VALUE.with(
+				|value| dict
+					.get(value)
+					.map(Some)
+					.map_err(|_| Panic::index_out_of_bounds(value.copy(), pos.copy()))
+			),
+
+			other => Err(Panic::type_error(other, "bool", pos)),
+		}
+	}
+
+
+	/// Call the given function, keeping track of the call in the frames vector.
 	/// The arguments are expected to be on the self.arguments vector.
 	fn call(
 		&mut self,
@@ -574,7 +917,23 @@ impl Runtime {
 		args_start: usize,
 		pos: SourcePos,
 	) -> Result<Value, Panic> {
+		self.frames.push(
+			Frame { function: function.copy(), pos: pos.copy(), status: None, base: None }
+		);
+		let result = self.invoke(obj, function, args_start, pos);
+		self.frames.pop();
+		result
+	}
 
+
+	/// Call the given function without tracking the call, which should be done by `call`.
+	fn invoke(
+		&mut self,
+		obj: Value,
+		function: &Function,
+		args_start: usize,
+		pos: SourcePos,
+	) -> Result<Value, Panic> {
 		let value = match function {
 			Function::Hush(HushFun { params, frame_info, body, context, .. }) => {
 				let args_count = (self.arguments.len() - args_start) as u32;
@@ -587,9 +946,14 @@ impl Runtime {
 				}
 
 				let slots: mem::SlotIx = frame_info.slots.into();
+				let base = self.stack.len();
 				self.stack.extend(slots.copy())
 					.map_err(|_| Panic::stack_overflow(pos))?;
 
+				if let Some(frame) = self.frames.last_mut() {
+					frame.base = Some(base);
+				}
+
 				// Place arguments
 				for (ix, value) in arguments.enumerate() {
 					self.stack.store(mem::SlotIx(ix as u32), value);
@@ -612,6 +976,10 @@ impl Runtime {
 					|runtime| { // Shrink stack before tail calling.
 						runtime.stack.shrink(slots.copy());
 						shrinked = true;
+
+						if let Some(frame) = runtime.frames.last_mut() {
+							frame.base = None;
+						}
 					}
 				);
 
@@ -665,7 +1033,9 @@ impl Runtime {
 		match (op, value) {
 			(Minus, Value::Float(ref f)) => Ok(Flow::Regular((-f).into())),
 			(Minus, Value::Int(i)) => Ok(Flow::Regular((-i).into())),
-			(Minus, value) => Err(Panic::type_error(value, "int or float", operand_pos)),
+			(Minus, Value::Duration(Duration(millis))) => Ok(Flow::Regular(Duration(-millis).into())),
+			(Minus, Value::Size(Size(bytes))) => Ok(Flow::Regular(Size(-bytes).into())),
+			(Minus, value) => Err(Panic::type_error(value, "int, float, duration or size", operand_pos)),
 
 			(Not, Value::Bool(b)) => Ok(Flow::Regular((!b).into())),
 			(Not, value) => Err(Panic::type_error(value, "bool", operand_pos)),
@@ -725,6 +1095,12 @@ impl Runtime {
 				self.ord_op(left, left_pos, op, right, right_pos)?
 			}
 
+			// Short-circuit: the right operand is evaluated only if the left one is nil.
+			Coalesce => match left {
+				Value::Nil => regular_expr!(right).0,
+				left => left,
+			}
+
 			Equals => Value::Bool(left == regular_expr!(right).0),
 			NotEquals => Value::Bool(left != regular_expr!(right).0),
 
@@ -765,7 +1141,86 @@ impl Runtime {
 		right_pos: SourcePos,
 	) -> Result<Value, Panic> {
 		use program::BinaryOp::*;
-		use std::ops::{Add, Sub, Mul, Div, Rem};
+		use std::{convert::TryFrom, ops::{Add, Sub, Mul, Div, Rem}};
+
+		// Byte arithmetic: offsetting a byte by an int yields a byte, and subtracting two
+		// bytes yields their distance as an int.
+		if let Value::Byte(byte) = left {
+			let offset = |offset: Option<i64>| offset
+				.and_then(|offset| (byte as i64).checked_add(offset))
+				.and_then(|value| u8::try_from(value).ok())
+				.map(Value::Byte)
+				.ok_or_else(|| Panic::integer_overflow(pos.copy()));
+
+			return match (op, right) {
+				(Plus, Value::Int(int)) => offset(Some(int)),
+				(Minus, Value::Int(int)) => offset(int.checked_neg()),
+				(Minus, Value::Byte(other)) => Ok(Value::Int(byte as i64 - other as i64)),
+				(Plus, right) => Err(Panic::type_error(right, "int", right_pos)),
+				(Minus, right) => Err(Panic::type_error(right, "int or char", right_pos)),
+				(_, _) => Err(Panic::type_error(Value::Byte(byte), "int or float", left_pos)),
+			};
+		}
+
+		// Unit arithmetic: durations and sizes are added to, subtracted from and divided by
+		// quantities of the same unit, and scaled by ints. Dividing two quantities yields
+		// their ratio as an int.
+		let quantity = |value: &Value| match *value {
+			Value::Duration(Duration(millis)) => Some((Type::Duration, millis)),
+			Value::Size(Size(bytes)) => Some((Type::Size, bytes)),
+			_ => None,
+		};
+
+		let unit = |unit: Type, value: Option<i64>, error: Panic| -> Result<Value, Panic> {
+			match (unit, value) {
+				(Type::Duration, Some(millis)) => Ok(Duration(millis).into()),
+				(_, Some(bytes)) => Ok(Size(bytes).into()),
+				(_, None) => Err(error),
+			}
+		};
+
+		let overflow = || Panic::integer_overflow(pos.copy());
+		let division_by_zero = || Panic::division_by_zero(pos.copy());
+
+		match (&left, quantity(&left), quantity(&right), &right) {
+			(_, Some((left_unit, left)), Some((right_unit, right)), _) if left_unit == right_unit => {
+				match op {
+					Plus => return unit(left_unit, left.checked_add(right), overflow()),
+					Minus => return unit(left_unit, left.checked_sub(right), overflow()),
+					Mod => return unit(left_unit, left.checked_rem(right), division_by_zero()),
+					Div => return left.checked_div(right).map(Value::Int).ok_or_else(division_by_zero),
+					_ => (),
+				}
+			}
+
+			(_, Some((left_unit, left)), None, &Value::Int(right)) => {
+				match op {
+					Times => return unit(left_unit, left.checked_mul(right), overflow()),
+					Div => return unit(left_unit, left.checked_div(right), division_by_zero()),
+					_ => (),
+				}
+			}
+
+			(&Value::Int(left), None, Some((right_unit, right)), _) => {
+				if let Times = op {
+					return unit(right_unit, left.checked_mul(right), overflow());
+				}
+			}
+
+			_ => (),
+		}
+
+		if let Some((left_unit, _)) = quantity(&left) {
+			let expected = match (left_unit, op) {
+				(Type::Duration, Plus | Minus | Mod) => "duration",
+				(Type::Size, Plus | Minus | Mod) => "size",
+				(Type::Duration, Div) => "int or duration",
+				(Type::Size, Div) => "int or size",
+				_ => "int",
+			};
+
+			return Err(Panic::type_error(right, expected, right_pos));
+		}
 
 		macro_rules! arith_operator {
 			($op_float: expr, $op_int: ident, $err_int: expr) => {
@@ -849,19 +1304,27 @@ impl Runtime {
 					| (left @ Value::Float(_), right @ Value::Float(_))
 					| (left @ Value::Byte(_), right @ Value::Byte(_))
 					| (left @ Value::String(_), right @ Value::String(_))
+					| (left @ Value::Duration(_), right @ Value::Duration(_))
+					| (left @ Value::Size(_), right @ Value::Size(_))
 					=> Ok(
 						Value::Bool(
 							order(left.cmp(&right))
 						)
 					),
 
+				// Bytes compare to ints by their numeric value.
+				(Value::Byte(byte), Value::Int(int)) => Ok(Value::Bool(order((byte as i64).cmp(&int)))),
+				(Value::Int(int), Value::Byte(byte)) => Ok(Value::Bool(order(int.cmp(&(byte as i64))))),
+
 				(Value::Int(_), right) => Err(Panic::type_error(right, "int", right_pos)),
 				(Value::Float(_), right) => Err(Panic::type_error(right, "float", right_pos)),
-				(Value::Byte(_), right) => Err(Panic::type_error(right, "char", right_pos)),
+				(Value::Byte(_), right) => Err(Panic::type_error(right, "char or int", right_pos)),
 				(Value::String(_), right) => Err(Panic::type_error(right, "string", right_pos)),
+				(Value::Duration(_), right) => Err(Panic::type_error(right, "duration", right_pos)),
+				(Value::Size(_), right) => Err(Panic::type_error(right, "size", right_pos)),
 
 				// ? + ?
-				(left, _) => Err(Panic::type_error(left, "int, float, byte or string", left_pos)),
+				(left, _) => Err(Panic::type_error(left, "int, float, byte, string, duration or size", left_pos)),
 			}
 		};
 
@@ -6,7 +6,12 @@ use crate::{
 	term::color,
 	symbol::{self, Symbol},
 };
-use super::{Value, SourcePos};
+use super::{
+	signal,
+	watchdog::{self, Limit},
+	Value,
+	SourcePos,
+};
 
 
 /// A panic is an irrecoverable error in Hush.
@@ -25,9 +30,17 @@ pub enum Panic {
 	},
 	/// Attempt to pop from empty collection.
 	EmptyCollection { pos: SourcePos },
+	/// Read of a variable declared without a value, in strict mode.
+	UnassignedVariable {
+		variable: Symbol,
+		declaration: SourcePos,
+		pos: SourcePos,
+	},
 	/// Attempt to call a non-function value.
 	InvalidCall {
 		function: Value,
+		/// Source text of the function expression.
+		expression: Symbol,
 		pos: SourcePos
 	},
 	/// Ammount of supplied arguments in function call is different than expected.
@@ -39,6 +52,8 @@ pub enum Panic {
 	/// Conditional expression is not a boolean.
 	InvalidCondition {
 		value: Value,
+		/// Source text of the condition.
+		expression: Symbol,
 		pos: SourcePos,
 	},
 	/// Unexpected type.
@@ -64,12 +79,18 @@ pub enum Panic {
 		items: u32,
 		pos: SourcePos,
 	},
+	/// A variable in a command argument is not convertible to a string.
+	InvalidCommandVariable {
+		value: Value,
+		variable: Symbol,
+		pos: SourcePos,
+	},
 	/// IO error in the standard library.
 	Io {
 		error: io::Error,
 		pos: SourcePos,
 	},
-	/// Redirection of the given file descriptor is currently unsupported.
+	/// Redirection to a file descriptor which is not open.
 	UnsupportedFileDescriptor {
 		fd: FileDescriptor,
 		pos: SourcePos,
@@ -79,6 +100,11 @@ pub enum Panic {
 		pattern: OsString,
 		pos: SourcePos,
 	},
+	/// Pattern matched no files, see std.glob_options.
+	NoMatches {
+		pattern: OsString,
+		pos: SourcePos,
+	},
 	/// Assertion failed.
 	AssertionFailed { pos: SourcePos },
 	/// Failed to import module.
@@ -86,8 +112,24 @@ pub enum Panic {
 		pos: SourcePos,
 		path: Symbol,
 	},
+	/// Import of a module which is already being imported. The cycle starts and ends with
+	/// the same path.
+	ImportCycle {
+		cycle: Box<[Symbol]>,
+		pos: SourcePos,
+	},
 	/// Attempt to call <command>.join more than once.
 	InvalidJoin { pos: SourcePos },
+	/// The interpreter received a signal.
+	Interrupted {
+		signal: i32,
+		pos: SourcePos,
+	},
+	/// A resource limit was exceeded.
+	LimitExceeded {
+		limit: Limit,
+		pos: SourcePos,
+	},
 	/// std.panic.
 	User {
 		context: Value,
@@ -97,6 +139,39 @@ pub enum Panic {
 
 
 impl Panic {
+	/// The position where the panic occurred.
+	pub fn pos(&self) -> &SourcePos {
+		match self {
+			Self::StackOverflow { pos, .. }
+			| Self::IntegerOverflow { pos, .. }
+			| Self::DivisionByZero { pos, .. }
+			| Self::IndexOutOfBounds { pos, .. }
+			| Self::UnassignedVariable { pos, .. }
+			| Self::EmptyCollection { pos, .. }
+			| Self::InvalidCall { pos, .. }
+			| Self::InvalidArgs { pos, .. }
+			| Self::InvalidCondition { pos, .. }
+			| Self::TypeError { pos, .. }
+			| Self::ValueError { pos, .. }
+			| Self::AssignToReadonlyField { pos, .. }
+			| Self::InvalidCommandArgs { pos, .. }
+			| Self::InvalidCommandVariable { pos, .. }
+			| Self::Io { pos, .. }
+			| Self::UnsupportedFileDescriptor { pos, .. }
+			| Self::InvalidPattern { pos, .. }
+			| Self::NoMatches { pos, .. }
+			| Self::AssertionFailed { pos, .. }
+			| Self::ImportFailed { pos, .. }
+			| Self::ImportCycle { pos, .. }
+			| Self::InvalidJoin { pos, .. }
+			| Self::Interrupted { pos, .. }
+			| Self::LimitExceeded { pos, .. }
+			| Self::User { pos, .. }
+				=> pos,
+		}
+	}
+
+
 	/// Attempt to increase the stack past it's maximum size.
 	pub fn stack_overflow(pos: SourcePos) -> Self {
 		Self::StackOverflow { pos }
@@ -127,6 +202,12 @@ impl Panic {
 	}
 
 
+	/// Read of a variable declared without a value, in strict mode.
+	pub fn unassigned_variable(variable: Symbol, declaration: SourcePos, pos: SourcePos) -> Self {
+		Self::UnassignedVariable { variable, declaration, pos }
+	}
+
+
 	/// Attempt to pop from empty collection.
 	pub fn empty_collection(pos: SourcePos) -> Self {
 		Self::EmptyCollection { pos }
@@ -134,8 +215,8 @@ impl Panic {
 
 
 	/// Attempt to call a non-function value.
-	pub fn invalid_call(function: Value, pos: SourcePos) -> Self {
-		Self::InvalidCall { function, pos }
+	pub fn invalid_call(function: Value, expression: Symbol, pos: SourcePos) -> Self {
+		Self::InvalidCall { function, expression, pos }
 	}
 
 
@@ -146,8 +227,8 @@ impl Panic {
 
 
 	/// Conditional expression is not a boolean.
-	pub fn invalid_condition(value: Value, pos: SourcePos) -> Self {
-		Self::InvalidCondition { value, pos }
+	pub fn invalid_condition(value: Value, expression: Symbol, pos: SourcePos) -> Self {
+		Self::InvalidCondition { value, expression, pos }
 	}
 
 
@@ -183,13 +264,19 @@ impl Panic {
 	}
 
 
+	/// A variable in a command argument is not convertible to a string.
+	pub fn invalid_command_variable(value: Value, variable: Symbol, pos: SourcePos) -> Self {
+		Self::InvalidCommandVariable { value, variable, pos }
+	}
+
+
 	/// IO error.
 	pub fn io(error: io::Error, pos: SourcePos) -> Self {
 		Self::Io { error, pos }
 	}
 
 
-	/// Redirection of the given file descriptor is currently unsupported.
+	/// Redirection to a file descriptor which is not open.
 	pub fn unsupported_fd(fd: FileDescriptor, pos: SourcePos) -> Self {
 		Self::UnsupportedFileDescriptor { fd, pos }
 	}
@@ -200,6 +287,12 @@ impl Panic {
 	}
 
 
+	/// Pattern matched no files, see std.glob_options.
+	pub fn no_matches(pattern: OsString, pos: SourcePos) -> Self {
+		Self::NoMatches { pattern, pos }
+	}
+
+
 	/// Attempt to assign a readonly field value.
 	pub fn assign_to_readonly_field(field: Value, pos: SourcePos) -> Self {
 		Self::AssignToReadonlyField { field, pos }
@@ -210,15 +303,64 @@ impl Panic {
 		Self::ImportFailed { path, pos }
 	}
 
+	/// Import of a module which is already being imported.
+	pub fn import_cycle(cycle: Box<[Symbol]>, pos: SourcePos) -> Self {
+		Self::ImportCycle { cycle, pos }
+	}
+
 	/// Attempt to call <command>.join more than once.
 	pub fn invalid_join(pos: SourcePos) -> Self {
 		Self::InvalidJoin { pos }
 	}
 
+	/// The interpreter received a signal, or was interrupted by the watchdog.
+	pub fn interrupted(signal: i32, pos: SourcePos) -> Self {
+		match watchdog::exceeded() {
+			Some(limit) if signal == signal::LIMIT_EXCEEDED => Self::LimitExceeded { limit, pos },
+			_ => Self::Interrupted { signal, pos },
+		}
+	}
+
 	/// std.panic
 	pub fn user(context: Value, pos: SourcePos) -> Self {
 		Self::User { context, pos }
 	}
+
+	/// The kind of panic, as a snake case name such as `type_error`.
+	pub fn kind(&self) -> &'static str {
+		match self {
+			Self::StackOverflow { .. } => "stack_overflow",
+			Self::IntegerOverflow { .. } => "integer_overflow",
+			Self::DivisionByZero { .. } => "division_by_zero",
+			Self::IndexOutOfBounds { .. } => "index_out_of_bounds",
+			Self::UnassignedVariable { .. } => "unassigned_variable",
+			Self::EmptyCollection { .. } => "empty_collection",
+			Self::InvalidCall { .. } => "invalid_call",
+			Self::InvalidArgs { .. } => "invalid_args",
+			Self::InvalidCondition { .. } => "invalid_condition",
+			Self::TypeError { .. } => "type_error",
+			Self::ValueError { .. } => "value_error",
+			Self::AssignToReadonlyField { .. } => "assign_to_readonly_field",
+			Self::InvalidCommandArgs { .. } => "invalid_command_args",
+			Self::InvalidCommandVariable { .. } => "invalid_command_variable",
+			Self::Io { .. } => "io",
+			Self::UnsupportedFileDescriptor { .. } => "unsupported_file_descriptor",
+			Self::InvalidPattern { .. } => "invalid_pattern",
+			Self::NoMatches { .. } => "no_matches",
+			Self::AssertionFailed { .. } => "assertion_failed",
+			Self::ImportFailed { .. } => "import_failed",
+			Self::ImportCycle { .. } => "import_cycle",
+			Self::InvalidJoin { .. } => "invalid_join",
+			Self::Interrupted { .. } => "interrupted",
+			Self::LimitExceeded { .. } => "limit_exceeded",
+			Self::User { .. } => "user",
+		}
+	}
+
+	/// The panic message, without the position and colors.
+	pub fn message(&self, interner: &symbol::Interner) -> String {
+		color::disabled(|| fmt::Show(Message(self), interner).to_string())
+	}
 }
 
 
@@ -226,138 +368,202 @@ impl<'a> Display<'a> for Panic {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
-		let panic = color::Fg(color::Red, "Panic");
+		write!(
+			f,
+			"{} in {}: {}",
+			color::Fg(color::Red, "Panic"),
+			fmt::Show(self.pos(), context),
+			fmt::Show(Message(self), context)
+		)
+	}
+}
 
-		match self {
-			Self::StackOverflow { pos } =>
-				write!(f, "{} in {}: stack overflow", panic, fmt::Show(pos, context)),
 
-			Self::IntegerOverflow { pos } =>
-				write!(f, "{} in {}: integer overflow", panic, fmt::Show(pos, context)),
+impl<'a> Display<'a> for fmt::Json<&Panic> {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		let panic = self.0;
+
+		let kind = panic.kind();
+
+		let pos = panic.pos();
+
+		fmt::json_error(
+			f,
+			context,
+			Some((pos.path, pos.line, pos.column)),
+			kind,
+			fmt::Show(Message(panic), context)
+		)
+	}
+}
+
+
+/// The panic message, without the position.
+struct Message<'p>(&'p Panic);
+
+
+impl<'a, 'p> Display<'a> for Message<'p> {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self.0 {
+			Panic::StackOverflow { .. } =>
+				write!(f, "stack overflow"),
+
+			Panic::IntegerOverflow { .. } =>
+				write!(f, "integer overflow"),
 
-			Self::DivisionByZero { pos } =>
-				write!(f, "{} in {}: division by zero", panic, fmt::Show(pos, context)),
+			Panic::DivisionByZero { .. } =>
+				write!(f, "division by zero"),
 
-			Self::IndexOutOfBounds { index, pos } =>
+			Panic::IndexOutOfBounds { index, .. } =>
 				write!(
 					f,
-					"{} in {}: index ({}) out of bounds",
-					panic,
-					fmt::Show(pos, context),
+					"index ({}) out of bounds",
 					color::Fg(color::Yellow, fmt::Show(index, context))
 				),
 
-			Self::EmptyCollection { pos } =>
-				write!(f, "{} in {}: collection is empty", panic, fmt::Show(pos, context)),
+			Panic::EmptyCollection { .. } =>
+				write!(f, "collection is empty"),
+
+			Panic::UnassignedVariable { variable, declaration, .. } =>
+				write!(
+					f,
+					"variable ({}) read before being assigned, declared at {}",
+					color::Fg(color::Yellow, fmt::Show(variable, context)),
+					fmt::Show(declaration, context),
+				),
 
-			Self::InvalidCall { function, pos } =>
+			Panic::InvalidCall { function, expression, .. } =>
 				write!(
 					f,
-					"{} in {}: attempt to call ({}), which is not a function",
-					panic,
-					fmt::Show(pos, context),
-					color::Fg(color::Yellow, fmt::Show(function, context))
+					"attempt to call ({}), which is not a function -- evaluated to ({}), of type {}",
+					color::Fg(color::Yellow, fmt::Show(expression, context)),
+					color::Fg(color::Yellow, fmt::Show(function, context)),
+					function.get_type(),
 				),
 
-			Self::InvalidArgs { supplied, expected, pos } =>
+			Panic::InvalidArgs { supplied, expected, .. } =>
 				write!(
 					f,
-					"{} in {}: incorrect amount of function parameters -- supplied {}, expected {}",
-					panic,
-					fmt::Show(pos, context),
+					"incorrect amount of function parameters -- supplied {}, expected {}",
 					supplied,
 					expected
 				),
 
-			Self::InvalidCondition { value, pos } =>
+			Panic::InvalidCondition { value, expression, .. } =>
 				write!(
 					f,
-					"{} in {}: condition ({}) is not a boolean",
-					panic,
-					fmt::Show(pos, context),
-					color::Fg(color::Yellow, fmt::Show(value, context))
+					"condition ({}) is not a boolean -- evaluated to ({}), of type {}",
+					color::Fg(color::Yellow, fmt::Show(expression, context)),
+					color::Fg(color::Yellow, fmt::Show(value, context)),
+					value.get_type(),
 				),
 
-			Self::TypeError { value, expected, pos } =>
+			Panic::TypeError { value, expected, .. } =>
 				write!(
 					f,
-					"{} in {}: value ({}) has unexpected type, expected {}",
-					panic,
-					fmt::Show(pos, context),
+					"value ({}) has unexpected type, expected {}",
 					color::Fg(color::Yellow, fmt::Show(value, context)),
 					expected,
 				),
 
-			Self::ValueError { value, message, pos } =>
+			Panic::ValueError { value, message, .. } =>
 				write!(
 					f,
-					"{} in {}: invalid value ({}), expected {}",
-					panic,
-					fmt::Show(pos, context),
+					"invalid value ({}), expected {}",
 					color::Fg(color::Yellow, fmt::Show(value, context)),
 					message,
 				),
 
-			Self::InvalidCommandArgs { object, items, pos } =>
+			Panic::InvalidCommandArgs { object, items, .. } =>
 				write!(
 					f,
-					"{} in {}: {} expansion resulted in {} items",
-					panic,
-					fmt::Show(pos, context),
+					"{} expansion resulted in {} items",
 					object,
 					items
 				),
 
-			Self::Io { error, pos } =>
-				write!(f, "{} in {}: {}", panic, fmt::Show(pos, context), error),
+			Panic::InvalidCommandVariable { value, variable, .. } =>
+				write!(
+					f,
+					"variable ${} in command argument has unexpected type {} ({}), expected nil, \
+					bool, int, float, byte, string or an array of those -- convert it with \
+					std.to_string, or join an array with std.join",
+					color::Fg(color::Yellow, fmt::Show(variable, context)),
+					value.get_type(),
+					color::Fg(color::Yellow, fmt::Show(value, context)),
+				),
+
+			Panic::Io { error, .. } =>
+				write!(f, "{}", error),
 
-			Self::UnsupportedFileDescriptor { fd, pos } =>
+			Panic::UnsupportedFileDescriptor { fd, .. } =>
 				write!(
 					f,
-					"{} in {}: unsupported file descriptor ({})",
-					panic,
-					fmt::Show(pos, context),
+					"bad file descriptor ({})",
 					color::Fg(color::Yellow, fd)
 				),
 
-			Self::InvalidPattern { pattern, pos } =>
+			Panic::InvalidPattern { pattern, .. } =>
+				write!(
+					f,
+					"pattern ({:?}) has invalid UTF-8",
+					color::Fg(color::Yellow, pattern)
+				),
+
+			Panic::NoMatches { pattern, .. } =>
 				write!(
 					f,
-					"{} in {}: pattern ({:?}) has invalid UTF-8",
-					panic,
-					fmt::Show(pos, context),
+					"pattern ({:?}) matched no files",
 					color::Fg(color::Yellow, pattern)
 				),
 
-			Self::AssignToReadonlyField { field, pos } => write!(
+			Panic::AssignToReadonlyField { field, .. } => write!(
 					f,
-					"{} in {}: attempt to assign field ({}), which is readonly",
-					panic,
-					fmt::Show(pos, context),
+					"attempt to assign field ({}), which is readonly",
 					color::Fg(color::Yellow, fmt::Show(field, context))
 				),
 
-			Self::AssertionFailed { pos } =>
-				write!(f, "{} in {}: assertion failed", panic, fmt::Show(pos, context)),
+			Panic::AssertionFailed { .. } =>
+				write!(f, "assertion failed"),
 
-			Self::ImportFailed { path, pos } =>
+			Panic::ImportFailed { path, .. } =>
 				write!(
 					f,
-					"{} in {}: failed to import module ({})",
-					panic,
-					fmt::Show(pos, context),
+					"failed to import module ({})",
 					color::Fg(color::Yellow, fmt::Show(path, context))
 				),
 
-			Self::InvalidJoin { pos } =>
-				write!(f, "{} in {}: attempt to call join more than once", panic, fmt::Show(pos, context)),
+			Panic::ImportCycle { cycle, .. } => {
+				write!(f, "cyclic import: ")?;
+
+				for (ix, path) in cycle.iter().enumerate() {
+					if ix > 0 {
+						write!(f, " -> ")?;
+					}
+
+					write!(f, "{}", color::Fg(color::Yellow, fmt::Show(path, context)))?;
+				}
+
+				Ok(())
+			}
+
+			Panic::InvalidJoin { .. } =>
+				write!(f, "attempt to call join more than once"),
+
+			Panic::Interrupted { signal, .. } =>
+				write!(f, "interrupted by signal {}", signal),
+
+			Panic::LimitExceeded { limit, .. } =>
+				write!(f, "{} exceeded", limit),
 
-			Self::User { context: value, pos } =>
+			Panic::User { context: value, .. } =>
 				write!(
 					f,
-					"{} in {}: std.panic({})",
-					panic,
-					fmt::Show(pos, context),
+					"std.panic({})",
 					color::Fg(color::Yellow, fmt::Show(value, context))
 				),
 		}
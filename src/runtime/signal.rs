@@ -0,0 +1,529 @@
+//! Signal tracking for the interpreter.
+//!
+//! Once installed, the signal handlers don't terminate the interpreter. Instead, they
+//! record the received signal, which is later checked by the runtime between statements,
+//! and by blocking operations such as sleeps and child process waits.
+//!
+//! Blocking operations wait on a wake pipe, which the signal handlers write to. Besides
+//! the tracked signals, SIGCHLD is handled once waits begin, so that child process waits
+//! block until some child changes state, instead of polling the child.
+//!
+//! Signals may also be trapped by the script, in which case they don't interrupt the
+//! interpreter. Instead, the runtime calls the respective handler between evaluations.
+
+use std::{
+	cell::RefCell,
+	io,
+	process,
+	sync::{atomic::{AtomicBool, AtomicI32, AtomicU8, AtomicU64, Ordering}, Arc, OnceLock},
+	time::{Duration, Instant},
+};
+
+use super::{Panic, SourcePos};
+
+
+/// Signals that are tracked by the interpreter.
+const TRACKED: [libc::c_int; 2] = [libc::SIGINT, libc::SIGTERM];
+
+/// Interval between polls, for waiters that couldn't get a wake pipe.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Maximum number of wake pipes, which is the number of threads that may block in waits
+/// simultaneously. Further waiters fall back to polling.
+const WAKERS: usize = 64;
+
+/// Lowest descriptor for wake pipes. Like in other shells, descriptors below it are left
+/// for scripts, which may redirect from descriptors inherited by the interpreter.
+const WAKE_FD_MIN: libc::c_int = 10;
+
+/// Pseudo signal raised by the watchdog when a resource limit is exceeded. SIGKILL can't
+/// be caught, so it never conflicts with a received signal. When forwarded to a child
+/// process, it kills it.
+pub const LIMIT_EXCEEDED: libc::c_int = libc::SIGKILL;
+
+/// Signals that may be trapped, ignored or restored by scripts, by name.
+const NAMES: [(&str, libc::c_int); 6] = [
+	("SIGHUP", libc::SIGHUP),
+	("SIGINT", libc::SIGINT),
+	("SIGQUIT", libc::SIGQUIT),
+	("SIGTERM", libc::SIGTERM),
+	("SIGUSR1", libc::SIGUSR1),
+	("SIGUSR2", libc::SIGUSR2),
+];
+
+/// Bit set of pending signals.
+static PENDING: AtomicU64 = AtomicU64::new(0);
+/// Bit set of signals trapped by the script, which don't interrupt the interpreter.
+static TRAPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Read ends of the wake pipes, or -1 if not yet created.
+static WAKE_READ: [AtomicI32; WAKERS] = [const { AtomicI32::new(-1) }; WAKERS];
+/// Write ends of the wake pipes, or -1 if not yet created. Pipes are never closed, so the
+/// signal handlers can't write to a reused file descriptor.
+static WAKE_WRITE: [AtomicI32; WAKERS] = [const { AtomicI32::new(-1) }; WAKERS];
+/// Whether each wake pipe is in use by a waiter.
+static WAKE_BUSY: [AtomicBool; WAKERS] = [const { AtomicBool::new(false) }; WAKERS];
+
+
+thread_local! {
+	/// The abandon flag for child process waits in the current thread, see `with_abandon`.
+	static ABANDON: RefCell<Option<Abandon>> = const { RefCell::new(None) };
+}
+
+
+extern "C" fn handler(signal: libc::c_int) {
+	// Only async-signal-safe operations are allowed here.
+	PENDING.fetch_or(1 << signal, Ordering::SeqCst);
+	wake_all();
+}
+
+
+extern "C" fn child_handler(_: libc::c_int) {
+	// Only async-signal-safe operations are allowed here.
+	wake_all();
+}
+
+
+/// Wake all blocked waiters. This is async-signal-safe.
+fn wake_all() {
+	// SAFETY: errno is thread local, and restoring it is required in signal handlers.
+	let errno = unsafe { *errno_location() };
+
+	for fd in WAKE_WRITE.iter() {
+		let fd = fd.load(Ordering::SeqCst);
+		if fd >= 0 {
+			// SAFETY: the pipe is never closed, and it is non-blocking. If the pipe is full,
+			// the waiter is already due to wake up.
+			unsafe { libc::write(fd, b"\0".as_ptr().cast(), 1) };
+		}
+	}
+
+	unsafe { *errno_location() = errno };
+}
+
+
+#[cfg(target_os = "linux")]
+unsafe fn errno_location() -> *mut libc::c_int {
+	libc::__errno_location()
+}
+
+
+#[cfg(not(target_os = "linux"))]
+unsafe fn errno_location() -> *mut libc::c_int {
+	libc::__error()
+}
+
+
+/// How the process reacts to a signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disposition {
+	/// Record the signal, interrupting the interpreter.
+	Interrupt,
+	/// Record the signal, to be handled by the script.
+	Trap,
+	/// Discard the signal. This is inherited by child processes.
+	Ignore,
+	/// The interpreter's default: interrupt for tracked signals, and the system's default
+	/// action otherwise.
+	Default,
+}
+
+
+/// Install the signal handlers for the tracked signals.
+pub fn install() -> io::Result<()> {
+	for &signal in TRACKED.iter() {
+		set_disposition(signal, Disposition::Interrupt)?;
+	}
+
+	Ok(())
+}
+
+
+/// Change how the process reacts to the given signal.
+pub fn set_disposition(signal: libc::c_int, disposition: Disposition) -> io::Result<()> {
+	let disposition = match disposition {
+		Disposition::Default if TRACKED.contains(&signal) => Disposition::Interrupt,
+		other => other,
+	};
+
+	let action = match disposition {
+		Disposition::Interrupt | Disposition::Trap => {
+			handler as extern "C" fn(libc::c_int) as libc::sighandler_t
+		}
+		Disposition::Ignore => libc::SIG_IGN,
+		Disposition::Default => libc::SIG_DFL,
+	};
+
+	set_action(signal, action)?;
+
+	if disposition == Disposition::Trap {
+		TRAPPED.fetch_or(1 << signal, Ordering::SeqCst);
+	} else {
+		TRAPPED.fetch_and(!(1 << signal), Ordering::SeqCst);
+	}
+
+	if disposition == Disposition::Ignore {
+		PENDING.fetch_and(!(1 << signal), Ordering::SeqCst);
+	}
+
+	Ok(())
+}
+
+
+/// Set the action for the given signal.
+fn set_action(signal: libc::c_int, action: libc::sighandler_t) -> io::Result<()> {
+	// SAFETY: the handlers only perform atomic operations and writes to non-blocking pipes,
+	// which are async-signal-safe.
+	let result = unsafe {
+		let mut sigaction: libc::sigaction = std::mem::zeroed();
+		sigaction.sa_sigaction = action;
+		sigaction.sa_flags = libc::SA_RESTART;
+		libc::sigemptyset(&mut sigaction.sa_mask);
+		libc::sigaction(signal, &sigaction, std::ptr::null_mut())
+	};
+
+	if result != 0 {
+		Err(io::Error::last_os_error())
+	} else {
+		Ok(())
+	}
+}
+
+
+/// Get the signal with the given name, such as `SIGINT`.
+pub fn parse(name: &[u8]) -> Option<libc::c_int> {
+	NAMES
+		.iter()
+		.find(|(signal_name, _)| signal_name.as_bytes() == name)
+		.map(|&(_, signal)| signal)
+}
+
+
+/// The names of the signals that may be trapped, for error messages.
+pub fn names() -> impl Iterator<Item = &'static str> {
+	NAMES.iter().map(|&(name, _)| name)
+}
+
+
+/// Mark the given signal as pending, as if it had been received.
+pub fn raise(signal: libc::c_int) {
+	PENDING.fetch_or(1 << signal, Ordering::SeqCst);
+	wake_all();
+}
+
+
+/// Get the lowest pending signal, if any, without clearing it. Trapped signals are not
+/// considered.
+pub fn pending() -> Option<i32> {
+	lowest(interrupting())
+}
+
+
+/// Get and clear the lowest pending signal, if any. Trapped signals are not considered.
+pub fn take() -> Option<i32> {
+	let signal = lowest(interrupting())?;
+	PENDING.fetch_and(!(1 << signal), Ordering::SeqCst);
+	Some(signal)
+}
+
+
+/// Clear the given signal, returning whether it was pending. Trapped signals are not
+/// considered.
+pub fn take_signal(signal: libc::c_int) -> bool {
+	interrupting() & (1 << signal) != 0
+		&& PENDING.fetch_and(!(1 << signal), Ordering::SeqCst) & (1 << signal) != 0
+}
+
+
+/// Get and clear the lowest pending trapped signal, if any.
+pub fn take_trapped() -> Option<i32> {
+	let signal = lowest(PENDING.load(Ordering::SeqCst) & TRAPPED.load(Ordering::SeqCst))?;
+	PENDING.fetch_and(!(1 << signal), Ordering::SeqCst);
+	Some(signal)
+}
+
+
+/// Produce a panic if a signal is pending, clearing it.
+pub fn check(pos: SourcePos) -> Result<(), Panic> {
+	match take() {
+		Some(signal) => Err(Panic::interrupted(signal, pos)),
+		None => Ok(()),
+	}
+}
+
+
+/// The pending signals which are not trapped.
+fn interrupting() -> u64 {
+	PENDING.load(Ordering::SeqCst) & !TRAPPED.load(Ordering::SeqCst)
+}
+
+
+fn lowest(pending: u64) -> Option<i32> {
+	if pending == 0 {
+		None
+	} else {
+		Some(pending.trailing_zeros() as i32)
+	}
+}
+
+
+/// Sleep for the given duration, returning early with the signal if one is received.
+pub fn sleep(duration: Duration) -> Result<(), i32> {
+	let deadline = Instant::now() + duration;
+	let waker = Waker::acquire();
+
+	loop {
+		if let Some(signal) = pending() {
+			return Err(signal);
+		}
+
+		let now = Instant::now();
+		if now >= deadline {
+			return Ok(());
+		}
+
+		waker.wait(Some(deadline - now));
+	}
+}
+
+
+/// A wake pipe, which is written to whenever a signal is received, including SIGCHLD, and
+/// whenever waits are abandoned. Waiters must check their condition after acquiring the
+/// pipe and after every wait, so that no wake up is lost.
+struct Waker(Option<usize>);
+
+
+impl Waker {
+	/// Acquire a free wake pipe, discarding stale wake ups.
+	fn acquire() -> Self {
+		// SIGCHLD is also delivered when a child stops, which process groups must notice. If
+		// the handler can't be installed, waiters must poll.
+		static CHILD_HANDLER: OnceLock<bool> = OnceLock::new();
+		let installed = CHILD_HANDLER.get_or_init(
+			|| set_action(
+				libc::SIGCHLD,
+				child_handler as extern "C" fn(libc::c_int) as libc::sighandler_t
+			).is_ok()
+		);
+
+		if !installed {
+			return Self(None);
+		}
+
+		for slot in 0 .. WAKERS {
+			if WAKE_BUSY[slot].compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+				continue;
+			}
+
+			if WAKE_READ[slot].load(Ordering::SeqCst) < 0 {
+				let mut fds = [-1; 2];
+				// SAFETY: fds has room for both ends of the pipe.
+				let result = unsafe {
+					libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC | libc::O_NONBLOCK)
+				};
+
+				let fds = if result == 0 {
+					fds.map(move_above_min)
+				} else {
+					[-1; 2]
+				};
+
+				if fds.contains(&-1) {
+					for &fd in fds.iter().filter(|&&fd| fd >= 0) {
+						// SAFETY: the descriptor was created above, and is not shared.
+						unsafe { libc::close(fd) };
+					}
+					WAKE_BUSY[slot].store(false, Ordering::SeqCst);
+					return Self(None);
+				}
+
+				WAKE_READ[slot].store(fds[0], Ordering::SeqCst);
+				WAKE_WRITE[slot].store(fds[1], Ordering::SeqCst);
+			}
+
+			let waker = Self(Some(slot));
+			waker.drain();
+			return waker;
+		}
+
+		Self(None)
+	}
+
+
+	/// Block until woken up, or until the timeout elapses. Spurious wake ups may happen.
+	fn wait(&self, timeout: Option<Duration>) {
+		let slot = match self.0 {
+			Some(slot) => slot,
+			None => {
+				std::thread::sleep(timeout.map_or(POLL_INTERVAL, |timeout| timeout.min(POLL_INTERVAL)));
+				return;
+			}
+		};
+
+		let timeout = timeout.map_or(
+			-1,
+			|timeout| timeout
+				.as_nanos()
+				.div_ceil(1_000_000)
+				.min(libc::c_int::MAX as u128) as libc::c_int
+		);
+
+		let mut pollfd = libc::pollfd {
+			fd: WAKE_READ[slot].load(Ordering::SeqCst),
+			events: libc::POLLIN,
+			revents: 0,
+		};
+
+		// SAFETY: pollfd is a single valid entry. Errors, such as EINTR, count as wake ups.
+		unsafe { libc::poll(&mut pollfd, 1, timeout) };
+
+		self.drain();
+	}
+
+
+	/// Discard pending wake ups.
+	fn drain(&self) {
+		if let Some(slot) = self.0 {
+			let fd = WAKE_READ[slot].load(Ordering::SeqCst);
+			let mut buffer = [0u8; 64];
+			// SAFETY: the buffer is valid for its length, and the pipe is non-blocking.
+			while unsafe { libc::read(fd, buffer.as_mut_ptr().cast(), buffer.len()) } > 0 { }
+		}
+	}
+}
+
+
+/// Move a descriptor to a number not lower than `WAKE_FD_MIN`, returning -1 on failure.
+fn move_above_min(fd: libc::c_int) -> libc::c_int {
+	// SAFETY: the descriptor was created by the caller, and is owned by it.
+	unsafe {
+		let moved = libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, WAKE_FD_MIN);
+		libc::close(fd);
+		moved
+	}
+}
+
+
+impl Drop for Waker {
+	fn drop(&mut self) {
+		if let Some(slot) = self.0 {
+			WAKE_BUSY[slot].store(false, Ordering::SeqCst);
+		}
+	}
+}
+
+
+/// A flag to abandon child process waits of another thread.
+#[derive(Debug, Clone, Default)]
+pub struct Abandon(Arc<AtomicU8>);
+
+
+impl Abandon {
+	const NONE: u8 = 0;
+	const KEEP: u8 = 1;
+	const KILL: u8 = 2;
+
+
+	/// Abandon the waits, either killing the children or leaving them running.
+	pub fn set(&self, kill: bool) {
+		self.0.store(if kill { Self::KILL } else { Self::KEEP }, Ordering::SeqCst);
+		wake_all();
+	}
+
+
+	/// Whether the waits have been abandoned.
+	pub fn is_set(&self) -> bool {
+		self.0.load(Ordering::SeqCst) != Self::NONE
+	}
+}
+
+
+/// Run the given function with waits in the current thread subject to the abandon flag.
+pub fn with_abandon<F, R>(abandon: &Abandon, f: F) -> R
+where
+	F: FnOnce() -> R,
+{
+	let previous = ABANDON.with(|flag| flag.replace(Some(abandon.clone())));
+	let result = f();
+	ABANDON.with(|flag| flag.replace(previous));
+	result
+}
+
+
+/// Whether waits in the current thread have been abandoned.
+pub fn abandoned() -> bool {
+	ABANDON.with(
+		|flag| flag
+			.borrow()
+			.as_ref()
+			.is_some_and(Abandon::is_set)
+	)
+}
+
+
+/// Wait for a child process to terminate.
+/// If a signal is received during the wait, it is forwarded to the child, which is then
+/// reaped. This prevents zombie children when the interpreter is interrupted.
+/// If the wait is abandoned, the child is either killed or left running, in which case an
+/// error is returned.
+pub fn wait(child: &mut process::Child) -> io::Result<process::ExitStatus> {
+	let pid = child.id() as libc::pid_t;
+
+	wait_with(
+		child,
+		// SAFETY: kill is safe to call with any arguments. The child has not been reaped
+		// yet, so its pid can't have been reused.
+		|signal| {
+			unsafe { libc::kill(pid, signal) };
+			Ok(())
+		},
+		|| (),
+	)
+}
+
+
+/// Wait for a child process to terminate, like `wait`, delivering signals with the given
+/// function, which may signal other processes as well. The poll function is called
+/// whenever the child is still running, and again whenever any child changes state.
+pub fn wait_with<K, P>(
+	child: &mut process::Child,
+	mut kill: K,
+	mut poll: P,
+) -> io::Result<process::ExitStatus>
+where
+	K: FnMut(libc::c_int) -> io::Result<()>,
+	P: FnMut(),
+{
+	// Acquired before the first check, so that a child exiting right after it wakes us up.
+	let waker = Waker::acquire();
+
+	let abandon = ABANDON.with(|flag| flag.borrow().clone());
+
+	loop {
+		if let Some(status) = child.try_wait()? {
+			return Ok(status);
+		}
+
+		match abandon.as_ref().map(|abandon| abandon.0.load(Ordering::SeqCst)) {
+			Some(Abandon::KILL) => {
+				kill(libc::SIGKILL)?;
+				return child.wait();
+			}
+
+			Some(Abandon::KEEP) => return Err(
+				io::Error::new(io::ErrorKind::TimedOut, "wait abandoned, command left running")
+			),
+
+			_ => (),
+		}
+
+		if let Some(signal) = pending() {
+			kill(signal)?;
+			return child.wait();
+		}
+
+		poll();
+
+		waker.wait(None);
+	}
+}
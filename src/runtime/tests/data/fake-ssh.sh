@@ -0,0 +1,8 @@
+#!/bin/sh
+# Stand-in for the ssh client: skips the options and host, and runs the remote command
+# locally, like sshd would.
+while [ "$1" != "--" ]; do
+	shift
+done
+shift 2
+exec sh -c "$*"
@@ -0,0 +1,34 @@
+/* A native module for the std.import_native tests. */
+
+#include <stdint.h>
+#include <stddef.h>
+#include <string.h>
+
+typedef void (*hush_write)(void *out, const uint8_t *data, size_t len);
+typedef int32_t (*hush_function)(const uint8_t *args, size_t args_len, hush_write write, void *out);
+typedef void (*hush_register)(void *registry, const char *name, hush_function function);
+
+
+/* Return the arguments array as is. */
+static int32_t echo(const uint8_t *args, size_t args_len, hush_write write, void *out) {
+	write(out, args, args_len);
+	return 0;
+}
+
+
+static int32_t fail(const uint8_t *args, size_t args_len, hush_write write, void *out) {
+	const char *message = "plugin failure";
+	write(out, (const uint8_t *) message, strlen(message));
+	return 1;
+}
+
+
+uint32_t hush_plugin_abi_version(void) {
+	return 1;
+}
+
+
+void hush_plugin_init(hush_register register_function, void *registry) {
+	register_function(registry, "echo", echo);
+	register_function(registry, "fail", fail);
+}
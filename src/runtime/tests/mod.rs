@@ -1,7 +1,12 @@
 use std::{
+	cell::RefCell,
+	env,
+	fs::{self, File},
 	io,
 	path::Path,
+	process::Command,
 	os::unix::ffi::OsStrExt,
+	rc::Rc,
 };
 
 use serial_test::serial;
@@ -13,7 +18,7 @@ use crate::{
 	syntax::{self, AnalysisDisplayContext},
 	tests,
 };
-use super::{Runtime, Value, Panic};
+use super::{debug, Runtime, SourcePos, Value, Panic};
 
 
 fn test_dir<P, F>(path: P, mut check: F) -> io::Result<()>
@@ -27,65 +32,73 @@ where
 
 	tests::util::test_dir(
 		path,
-		move |path, file| {
-			let path_symbol = runtime
-				.interner_mut()
-				.get_or_intern(path.as_os_str().as_bytes());
-			let source = syntax::Source::from_reader(path_symbol, file)?;
-			let syntactic_analysis = syntax::Analysis::analyze(
-				&source,
-				runtime.interner_mut()
-			);
-
-			if !syntactic_analysis.errors.is_empty() {
-				panic!(
-					"{}",
-					fmt::Show(
-						syntactic_analysis,
-						AnalysisDisplayContext {
-							max_errors: None,
-							interner: runtime.interner(),
-						}
-					)
-				);
-			}
-
-			let semantic_analysis = semantic::Analyzer::analyze(
-				syntactic_analysis.ast,
-				runtime.interner_mut()
-			);
-			let program = match semantic_analysis {
-				Ok(program) => program,
-				Err(errors) => panic!(
-					"{}",
-					fmt::Show(
-						errors,
-						ErrorsDisplayContext {
-							max_errors: None,
-							interner: runtime.interner(),
-						}
-					)
-				),
-			};
-
-			let program = Box::leak(Box::new(program));
-
-			let result = runtime.eval(program);
-
-			if !check(&result) {
-				match result {
-					Ok(value) => panic!(
-						"File {}: expected panic, got {}",
-						path.display(),
-						fmt::Show(value, runtime.interner())
-					),
-					Err(panic) => panic!("{}", fmt::Show(panic, runtime.interner())),
+		move |path, file| test_file(&mut runtime, path, file, &mut check)
+	)
+}
+
+
+fn test_file<F>(runtime: &mut Runtime, path: &Path, file: File, mut check: F) -> io::Result<()>
+where
+	F: FnMut(&Result<Value, Panic>) -> bool,
+{
+	let path_symbol = runtime
+		.interner_mut()
+		.get_or_intern(path.as_os_str().as_bytes());
+	let source = syntax::Source::from_reader(path_symbol, file)?;
+	let syntactic_analysis = syntax::Analysis::analyze(
+		&source,
+		runtime.interner_mut()
+	);
+
+	if !syntactic_analysis.errors.is_empty() {
+		panic!(
+			"{}",
+			fmt::Show(
+				syntactic_analysis,
+				AnalysisDisplayContext {
+					max_errors: None,
+					interner: runtime.interner(),
+					format: fmt::ErrorFormat::Human,
+				}
+			)
+		);
+	}
+
+	let semantic_analysis = semantic::Analyzer::analyze(
+		syntactic_analysis.ast,
+		runtime.interner_mut()
+	);
+	let program = match semantic_analysis {
+		Ok(program) => program,
+		Err(errors) => panic!(
+			"{}",
+			fmt::Show(
+				errors,
+				ErrorsDisplayContext {
+					max_errors: None,
+					interner: runtime.interner(),
+					format: fmt::ErrorFormat::Human,
 				}
-			}
+			)
+		),
+	};
 
-			Ok(())
+	let program = Box::leak(Box::new(program));
+
+	let result = runtime.eval(program);
+
+	if !check(&result) {
+		match result {
+			Ok(value) => panic!(
+				"File {}: expected panic, got {}",
+				path.display(),
+				fmt::Show(value, runtime.interner())
+			),
+			Err(panic) => panic!("{}", fmt::Show(panic, runtime.interner())),
 		}
-	)
+	}
+
+	Ok(())
 }
 
 
@@ -121,3 +134,232 @@ fn test_asserts() -> io::Result<()> {
 		|result| matches!(result, Err(Panic::AssertionFailed { .. }))
 	)
 }
+
+
+#[test]
+#[serial]
+fn test_interrupt() -> io::Result<()> {
+	// The scripts send SIGINT to the current process.
+	super::signal::install()?;
+
+	test_dir(
+		"src/runtime/tests/data/interrupt",
+		Result::is_ok
+	)
+}
+
+
+#[test]
+#[serial]
+fn test_import_native() -> io::Result<()> {
+	let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+	let dir = env::temp_dir().join(format!("hush-native-{}", std::process::id()));
+	fs::create_dir_all(&dir)?;
+	let library = dir.join("libplugin.so");
+
+	// The plugin is built with the system's C compiler, and the test is skipped without one.
+	let compiler = env::var_os("CC").unwrap_or_else(|| "cc".into());
+	let status = Command::new(&compiler)
+		.args(["-shared", "-fPIC", "-o"])
+		.arg(&library)
+		.arg(root.join("src/runtime/tests/data/plugin.c"))
+		.status();
+
+	let status = match status {
+		Ok(status) => status,
+		Err(error) if error.kind() == io::ErrorKind::NotFound => {
+			eprintln!("skipping test_import_native: {:?} not found", compiler);
+			return fs::remove_dir_all(&dir);
+		}
+		Err(error) => return Err(error),
+	};
+	assert!(status.success(), "failed to build the plugin");
+
+	// The script receives the library path as its argument.
+	let mut runtime = Runtime::new(std::iter::once(library), symbol::Interner::new());
+
+	let path = root.join("src/runtime/tests/data/native/import-native.hsh");
+	let result = test_file(&mut runtime, &path, File::open(&path)?, Result::is_ok);
+
+	fs::remove_dir_all(&dir)?;
+
+	result
+}
+
+
+#[test]
+#[serial]
+fn test_panic_hook() {
+	// The hook checks the description, and then panics itself to show that it ran.
+	let input = r#"
+		std.set_panic_hook(
+			function(panic)
+				std.assert(panic.kind == "user")
+				std.assert(panic.message == "std.panic(\"boom\")")
+				std.assert(panic.context == "boom")
+				std.assert(panic.path == "<test>")
+				std.assert(panic.line == 12)
+				std.panic("reported")
+			end
+		)
+		std.panic("boom")
+	"#;
+
+	let mut runtime = Runtime::new(std::iter::empty::<&str>(), symbol::Interner::new());
+	let path = runtime.interner_mut().get_or_intern("<test>");
+	let source = syntax::Source { path, contents: input.as_bytes().into() };
+
+	let analysis = syntax::Analysis::analyze(&source, runtime.interner_mut());
+	assert!(analysis.errors.is_empty());
+
+	let program = semantic::Analyzer::analyze(analysis.ast, runtime.interner_mut())
+		.unwrap_or_else(|_| panic!("semantic errors"));
+	let program = Box::leak(Box::new(program));
+
+	let panic = match runtime.eval(program) {
+		Err(panic) => panic,
+		Ok(_) => panic!("expected panic"),
+	};
+
+	match runtime.run_panic_hook(&panic) {
+		Err(Panic::User { context: Value::String(ref context), .. }) => {
+			assert_eq!(context.as_bytes(), b"reported")
+		}
+		Err(panic) => panic!("{}", fmt::Show(panic, runtime.interner())),
+		Ok(()) => panic!("the panic hook did not run"),
+	}
+
+	// The hook runs only once.
+	assert!(runtime.run_panic_hook(&panic).is_ok());
+}
+
+
+/// The frames of a pause: the code, the line, and the variables other than `std`.
+type Pause = Vec<(String, Option<u32>, Vec<(String, String)>)>;
+
+
+/// Pauses at the given line, recording the frames.
+#[derive(Debug)]
+struct Recorder {
+	line: u32,
+	pauses: Rc<RefCell<Vec<(usize, Pause)>>>,
+	depth: usize,
+}
+
+
+impl debug::Debugger for Recorder {
+	fn check(&mut self, pos: &SourcePos, depth: usize, _: &symbol::Interner) -> bool {
+		self.depth = depth;
+		pos.line == self.line
+	}
+
+
+	fn pause(&mut self, frames: Vec<debug::Frame>, interner: &symbol::Interner) {
+		let resolve = |symbol| String::from_utf8_lossy(
+			interner
+				.resolve(symbol)
+				.expect("failed to resolve symbol")
+		).into_owned();
+
+		let frames = frames
+			.into_iter()
+			.map(
+				|frame| {
+					let code = match frame.code {
+						debug::Code::Script(_) => "<script>".to_owned(),
+						debug::Code::Function(name) => name.map(resolve).unwrap_or_default(),
+						debug::Code::Builtin(name) => name.to_owned(),
+					};
+
+					let variables = frame.variables
+						.into_iter()
+						.map(
+							|(name, value)| (
+								name.map(resolve).unwrap_or_else(|| "self".to_owned()),
+								fmt::Show(value, interner).to_string(),
+							)
+						)
+						.filter(|(name, _)| name != "std")
+						.collect();
+
+					(code, frame.pos.map(|pos| pos.line), variables)
+				}
+			)
+			.collect();
+
+		self.pauses.borrow_mut().push((self.depth, frames));
+	}
+}
+
+
+#[test]
+#[serial]
+fn test_debugger() {
+	let input = r#"let outer = 1
+		function add(a, b)
+			let sum = a + b
+			sum
+		end
+		let result = add(outer, 2)
+		let nested = std.catch(function () add(outer, 3) end)
+	"#;
+
+	let mut runtime = Runtime::new(std::iter::empty::<&str>(), symbol::Interner::new());
+	let path = runtime.interner_mut().get_or_intern("<test>");
+	let source = syntax::Source { path, contents: input.as_bytes().into() };
+
+	let analysis = syntax::Analysis::analyze(&source, runtime.interner_mut());
+	assert!(analysis.errors.is_empty());
+
+	let program = semantic::Analyzer::analyze(analysis.ast, runtime.interner_mut())
+		.unwrap_or_else(|_| panic!("semantic errors"));
+	let program = Box::leak(Box::new(program));
+
+	let pauses = Rc::new(RefCell::new(Vec::new()));
+	runtime.set_debugger(Box::new(Recorder { line: 4, pauses: pauses.clone(), depth: 0 }));
+
+	if let Err(panic) = runtime.eval(program) {
+		panic!("{}", fmt::Show(panic, runtime.interner()));
+	}
+
+	let pauses = pauses.borrow();
+	assert_eq!(pauses.len(), 2);
+
+	let variables = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+		pairs
+			.iter()
+			.map(|&(name, value)| (name.to_owned(), value.to_owned()))
+			.collect()
+	};
+
+	// Called from the top level.
+	let (depth, frames) = &pauses[0];
+	assert_eq!(*depth, 1);
+	assert_eq!(frames.len(), 2);
+	assert_eq!(
+		frames[0],
+		("add".to_owned(), Some(4), variables(&[("a", "1"), ("b", "2"), ("sum", "3")]))
+	);
+	assert_eq!(frames[1].0, "<script>");
+	assert_eq!(frames[1].1, Some(6));
+	assert_eq!(
+		frames[1].2.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(),
+		["outer", "add", "result", "nested"]
+	);
+	assert_eq!(frames[1].2[2].1, "nil");
+
+	// Tail called from a closure, through a builtin.
+	let (depth, frames) = &pauses[1];
+	assert_eq!(*depth, 3);
+	assert_eq!(frames.len(), 4);
+	assert_eq!(
+		frames[0],
+		("add".to_owned(), Some(4), variables(&[("a", "1"), ("b", "3"), ("sum", "4")]))
+	);
+	// The closure's variables are dropped by the tail call.
+	assert_eq!(frames[1], (String::new(), Some(7), Vec::new()));
+	assert_eq!(frames[2], ("std.catch".to_owned(), None, Vec::new()));
+	assert_eq!(frames[3].0, "<script>");
+	assert_eq!(frames[3].1, Some(7));
+	assert_eq!(frames[3].2[2].1, "3");
+}
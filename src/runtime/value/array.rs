@@ -6,19 +6,19 @@ use std::{
 
 use gc::{Gc, GcCell, GcCellRef, GcCellRefMut, Finalize, Trace};
 
-use super::{EmptyCollection, IndexOutOfBounds, Value};
+use super::{weak, EmptyCollection, IndexOutOfBounds, Value};
 
 
 /// An array in the language.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[derive(Trace, Finalize)]
-pub struct Array(Gc<GcCell<Vec<Value>>>);
+pub struct Array(Gc<GcCell<Items>>);
 
 
 impl Array {
 	/// Crate a new empty array.
 	pub fn new(vec: Vec<Value>) -> Self {
-		Self(Gc::new(GcCell::new(vec)))
+		Self(Gc::new(GcCell::new(Items(vec))))
 	}
 
 
@@ -30,25 +30,31 @@ impl Array {
 
 	/// Borrow the inner Vec.
 	pub fn borrow(&self) -> GcCellRef<Vec<Value>> {
-		self.0.deref().borrow()
+		GcCellRef::map(self.0.deref().borrow(), |items| &items.0)
 	}
 
 
 	/// Borrow the inner Vec mutably.
-	pub fn borrow_mut(&self) -> GcCellRefMut<Vec<Value>> {
-		self.0.deref().borrow_mut()
+	pub fn borrow_mut(&self) -> GcCellRefMut<Items, Vec<Value>> {
+		GcCellRefMut::map(self.0.deref().borrow_mut(), |items| &mut items.0)
+	}
+
+
+	/// The address of the items, which identifies the array for weak references.
+	pub(super) fn address(&self) -> usize {
+		&*self.0.deref().borrow() as *const Items as usize
 	}
 
 
 	/// Push a value into the array.
 	pub fn push(&mut self, value: Value) {
-		self.0.borrow_mut().push(value)
+		self.borrow_mut().push(value)
 	}
 
 
 	/// Pop a value from the back of the array.
 	pub fn pop(&mut self) -> Result<Value, EmptyCollection> {
-		self.0
+		self
 			.borrow_mut()
 			.pop()
 			.ok_or(EmptyCollection)
@@ -113,6 +119,26 @@ impl Array {
 }
 
 
+/// The elements of an array. Collection is reported to weak references.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Trace)]
+pub struct Items(Vec<Value>);
+
+
+impl Finalize for Items {
+	fn finalize(&self) {
+		weak::collected(self as *const Self as usize);
+	}
+}
+
+
+impl std::fmt::Debug for Items {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		self.0.fmt(f)
+	}
+}
+
+
 // GcCell does not implement Eq because `borrow` might panic.
 #[allow(clippy::derived_hash_with_manual_eq)]
 impl Hash for Array {
@@ -1,13 +1,13 @@
 use std::{
 	cmp::Ordering,
-	collections::{HashMap, BTreeMap},
+	collections::BTreeMap,
 	hash::{Hash, Hasher},
 	ops::Deref,
 };
 
 use gc::{Gc, GcCell, GcCellRef, GcCellRefMut, Finalize, Trace};
 
-use super::{IndexOutOfBounds, Value};
+use super::{Function, IndexOutOfBounds, OrderedMap, Str, Value};
 
 
 /// Common dict keys
@@ -21,19 +21,21 @@ pub mod keys {
 		pub static KEY: Value = "key".into();
 		/// VALUE string key.
 		pub static VALUE: Value = "value".into();
+		/// REVEAL string key.
+		pub static REVEAL: Value = "reveal".into();
 	}
 }
 
 
-/// A dict in the language.
+/// A dict in the language. Dicts preserve insertion order when iterated.
 #[derive(Debug, Default, PartialEq, Eq)]
 #[derive(Trace, Finalize)]
-pub struct Dict(Gc<GcCell<HashMap<Value, Value>>>);
+pub struct Dict(Gc<GcCell<OrderedMap>>);
 
 
 impl Dict {
 	/// Crate a new empty dict.
-	pub fn new(dict: HashMap<Value, Value>) -> Self {
+	pub fn new(dict: OrderedMap) -> Self {
 		Self(Gc::new(GcCell::new(dict)))
 	}
 
@@ -44,18 +46,24 @@ impl Dict {
 	}
 
 
-	/// Borrow the hashmap.
-	pub fn borrow(&self) -> GcCellRef<HashMap<Value, Value>> {
+	/// Borrow the map.
+	pub fn borrow(&self) -> GcCellRef<OrderedMap> {
 		self.0.deref().borrow()
 	}
 
 
-	/// Borrow the hashmap mutably.
-	pub fn borrow_mut(&self) -> GcCellRefMut<HashMap<Value, Value>> {
+	/// Borrow the map mutably.
+	pub fn borrow_mut(&self) -> GcCellRefMut<OrderedMap> {
 		self.0.deref().borrow_mut()
 	}
 
 
+	/// The address of the map, which identifies the dict for weak references.
+	pub(super) fn address(&self) -> usize {
+		&*self.borrow() as *const OrderedMap as usize
+	}
+
+
 	/// Insert a value in the dict.
 	pub fn insert(&self, key: Value, value: Value) {
 		self.borrow_mut().insert(key, value);
@@ -90,6 +98,17 @@ impl Dict {
 	pub fn is_empty(&self) -> bool {
 		self.len() == 0
 	}
+
+
+	/// Get the wrapped string, if the dict is a secret, see std.secret.
+	pub fn secret(&self) -> Option<Str> {
+		let reveal = keys::REVEAL.with(|reveal| self.get(reveal)).ok()?;
+
+		match reveal {
+			Value::Function(Function::Rust(ref fun)) => fun.as_secret().map(Str::copy),
+			_ => None,
+		}
+	}
 }
 
 
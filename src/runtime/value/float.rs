@@ -27,6 +27,18 @@ impl Float {
 	pub fn is_nan(&self) -> bool {
 		self.0.is_nan()
 	}
+
+
+	/// Check if the float is neither infinite nor NaN.
+	pub fn is_finite(&self) -> bool {
+		self.0.is_finite()
+	}
+
+
+	/// Check if the float is positive or negative infinity.
+	pub fn is_infinite(&self) -> bool {
+		self.0.is_infinite()
+	}
 }
 
 
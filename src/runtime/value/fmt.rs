@@ -4,7 +4,7 @@ use crate::{
 	fmt::{self, Display},
 	symbol,
 };
-use super::{Array, Dict, Error, Float, Function, HushFun, RustFun, Str, Value};
+use super::{Array, Dict, Duration, Error, Float, Function, HushFun, RustFun, Size, Str, Value};
 
 
 impl std::fmt::Display for RustFun {
@@ -35,9 +35,87 @@ impl<'a> Display<'a> for Function {
 }
 
 
+/// Non-finite values are displayed as `nan`, `inf` and `-inf`.
 impl std::fmt::Display for Float {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-		write!(f, "{:#?}", self.0)
+		if self.is_nan() {
+			f.write_str("nan")
+		} else if self.0 == f64::INFINITY {
+			f.write_str("inf")
+		} else if self.0 == f64::NEG_INFINITY {
+			f.write_str("-inf")
+		} else {
+			write!(f, "{:#?}", self.0)
+		}
+	}
+}
+
+
+/// Durations are displayed such as `200ms`, `5.2s` or `1h30m`, which can be parsed back
+/// by std.units.duration, though durations over a minute are truncated to seconds.
+impl std::fmt::Display for Duration {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		if self.0 < 0 {
+			f.write_str("-")?;
+		}
+
+		let millis = self.0.unsigned_abs();
+
+		if millis < 1000 {
+			return write!(f, "{}ms", millis);
+		}
+
+		if millis < 60_000 {
+			return write!(f, "{}s", Decimal(millis as f64 / 1000.0));
+		}
+
+		let mut seconds = millis / 1000;
+
+		for &(unit, size) in &[("d", 86400), ("h", 3600), ("m", 60), ("s", 1)] {
+			if seconds >= size {
+				write!(f, "{}{}", seconds / size, unit)?;
+				seconds %= size;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+
+/// Sizes are displayed with the largest fitting binary unit, such as `512B`, `1.5K` or
+/// `2G`, which can be parsed back by std.units.size, though rounded to one decimal.
+impl std::fmt::Display for Size {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		if self.0 < 0 {
+			f.write_str("-")?;
+		}
+
+		let bytes = self.0.unsigned_abs() as f64;
+
+		for &(unit, size) in &[("T", 1099511627776.0), ("G", 1073741824.0), ("M", 1048576.0), ("K", 1024.0)] {
+			if bytes >= size {
+				return write!(f, "{}{}", Decimal(bytes / size), unit);
+			}
+		}
+
+		write!(f, "{}B", bytes)
+	}
+}
+
+
+/// A number displayed with at most one decimal, omitted if zero.
+struct Decimal(f64);
+
+impl std::fmt::Display for Decimal {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let rounded = (self.0 * 10.0).round() / 10.0;
+
+		if rounded.fract() == 0.0 {
+			write!(f, "{}", rounded as i64)
+		} else {
+			write!(f, "{:.1}", rounded)
+		}
 	}
 }
 
@@ -68,6 +146,10 @@ impl<'a> Display<'a> for Dict {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		if self.secret().is_some() {
+			return write!(f, "<redacted>");
+		}
+
 		let dict = self.borrow();
 		let mut iter = dict.iter();
 
@@ -132,6 +214,8 @@ impl<'a> Display<'a> for Value {
 			Self::Dict(dict) => write!(f, "{}", fmt::Show(dict, context)),
 			Self::Function(fun) => write!(f, "{}", fmt::Show(fun, context)),
 			Self::Error(error) => write!(f, "{}", fmt::Show(error, context)),
+			Self::Duration(duration) => write!(f, "{}", duration),
+			Self::Size(size) => write!(f, "{}", size),
 		}
 	}
 }
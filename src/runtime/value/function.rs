@@ -6,13 +6,17 @@ use std::{
 
 use gc::{Gc, GcCell, Finalize, Trace};
 
-use crate::symbol;
+use crate::symbol::{self, Symbol};
 use super::{
+	keys,
 	mem,
 	program,
+	Dict,
+	OrderedMap,
 	Panic,
 	Runtime,
 	SourcePos,
+	Str,
 	Value,
 };
 
@@ -72,6 +76,12 @@ pub struct HushFun {
 	#[allow(clippy::type_complexity)]
 	pub context: Gc<Box<[(Gc<GcCell<Value>>, mem::SlotIx)]>>,
 	pub pos: SourcePos,
+	/// The doc comment preceding the function, if any.
+	#[unsafe_ignore_trace]
+	pub doc: Option<Symbol>,
+	/// The name of the variable the function is declared as, if any.
+	#[unsafe_ignore_trace]
+	pub name: Option<Symbol>,
 }
 
 
@@ -82,6 +92,8 @@ impl HushFun {
 		body: &'static program::Block,
 		context: Box<[(Gc<GcCell<Value>>, mem::SlotIx)]>,
 		pos: SourcePos,
+		doc: Option<Symbol>,
+		name: Option<Symbol>,
 	) -> Self {
 		Self {
 			params,
@@ -89,6 +101,8 @@ impl HushFun {
 			body,
 			context: Gc::new(context),
 			pos,
+			doc,
+			name,
 		}
 	}
 
@@ -101,6 +115,8 @@ impl HushFun {
 			body: self.body,
 			context: self.context.clone(),
 			pos: self.pos.copy(),
+			doc: self.doc,
+			name: self.name,
 		}
 	}
 }
@@ -197,6 +213,43 @@ pub trait NativeFun: Trace + Finalize + 'static {
 	fn name(&self) -> &'static str;
 	/// Invoke the function.
 	fn call(&self, context: CallContext) -> Result<Value, Panic>;
+	/// Get the native iterator implementation, if the function is an iterator.
+	fn as_iter(&self) -> Option<&dyn NativeIter> { None }
+	/// Get the wrapped string, if the function reveals a secret, see std.secret.
+	fn as_secret(&self) -> Option<&Str> { None }
+}
+
+
+/// A native iterator. For loops advance such iterators directly, instead of calling them
+/// and inspecting the resulting iteration dict.
+pub trait NativeIter {
+	/// Get the next item, or None if the iterator is finished.
+	fn next(&self, context: CallContext) -> Result<Option<Value>, Panic>;
+
+
+	/// Advance the iterator through the dict protocol, for calls from hush code.
+	fn call_iter(&self, context: CallContext) -> Result<Value, Panic> {
+		let args = context.args();
+		if !args.is_empty() {
+			return Err(Panic::invalid_args(args.len() as u32, 0, context.pos));
+		}
+
+		let next = self.next(context)?;
+
+		let mut iteration = OrderedMap::new();
+
+		keys::FINISHED.with(
+			|finished| iteration.insert(finished.copy(), next.is_none().into())
+		);
+
+		if let Some(next) = next {
+			keys::VALUE.with(
+				|value| iteration.insert(value.copy(), next)
+			);
+		}
+
+		Ok(Dict::new(iteration).into())
+	}
 }
 
 
@@ -222,6 +275,18 @@ impl RustFun {
 	pub fn call(&self, context: CallContext) -> Result<Value, Panic> {
 		self.0.call(context)
 	}
+
+
+	/// Get the native iterator implementation, if the function is an iterator.
+	pub fn as_iter(&self) -> Option<&dyn NativeIter> {
+		self.0.as_iter()
+	}
+
+
+	/// Get the wrapped string, if the function reveals a secret.
+	pub fn as_secret(&self) -> Option<&Str> {
+		self.0.as_secret()
+	}
 }
 
 
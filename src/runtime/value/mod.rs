@@ -7,9 +7,12 @@ mod errors;
 mod float;
 mod fmt;
 mod function;
+mod ordered_map;
 mod string;
+mod units;
+mod weak;
 
-use std::{ffi::OsString, fmt::Display};
+use std::{collections::HashMap, ffi::OsString, fmt::Display};
 
 use gc::{Finalize, Trace};
 
@@ -23,10 +26,13 @@ use super::{
 pub use array::Array;
 pub use dict::{keys, Dict};
 pub use error::Error;
-pub use function::{CallContext, Function, HushFun, RustFun, NativeFun};
+pub use function::{CallContext, Function, HushFun, RustFun, NativeFun, NativeIter};
 pub use float::Float;
+pub use ordered_map::OrderedMap;
 pub use errors::{EmptyCollection, IndexOutOfBounds};
 pub use string::Str;
+pub use units::{Duration, Size};
+pub use weak::Weak;
 
 
 /// The possible types in the language.
@@ -43,6 +49,8 @@ pub enum Type {
 	Dict,
 	Function,
 	Error,
+	Duration,
+	Size,
 }
 
 
@@ -63,6 +71,8 @@ impl Type {
 			b"dict" => Some(Self::Dict),
 			b"function" => Some(Self::Function),
 			b"error" => Some(Self::Error),
+			b"duration" => Some(Self::Duration),
+			b"size" => Some(Self::Size),
 			_ => None,
 		}
 	}
@@ -81,6 +91,8 @@ impl Type {
 			Self::Dict => "dict",
 			Self::Function => "function",
 			Self::Error => "error",
+			Self::Duration => "duration",
+			Self::Size => "size",
 		}
 	}
 }
@@ -115,6 +127,9 @@ pub enum Value {
 	Dict(Dict),
 	Function(Function),
 	Error(Error),
+	/// Durations and sizes are ints tagged with their unit, see std.units.
+	Duration(Duration),
+	Size(Size),
 }
 
 
@@ -131,7 +146,70 @@ impl Value {
 			Self::Array(array) => Self::Array(array.copy()),
 			Self::Dict(dict) => Self::Dict(dict.copy()),
 			Self::Function(fun) => Self::Function(fun.copy()),
-			Self::Error(error) => Self::Error(error.copy())
+			Self::Error(error) => Self::Error(error.copy()),
+			Self::Duration(duration) => Self::Duration(*duration),
+			Self::Size(size) => Self::Size(*size),
+		}
+	}
+
+
+	/// Make a deep copy of the value, copying arrays, dicts and error contexts recursively.
+	/// Shared and cyclic references are preserved in the copy. Strings and functions are
+	/// shared, as they are immutable.
+	pub fn deep_copy(&self) -> Self {
+		self.deep_copy_with(&mut HashMap::new())
+	}
+
+
+	fn deep_copy_with(&self, copies: &mut HashMap<usize, Value>) -> Self {
+		match self {
+			Self::Array(array) => {
+				if let Some(copy) = copies.get(&array.address()) {
+					return copy.copy();
+				}
+
+				let copy = Array::new(Vec::new());
+				copies.insert(array.address(), copy.copy().into());
+
+				let items: Vec<Value> = array.borrow().iter().map(Value::copy).collect();
+				let items = items
+					.iter()
+					.map(|item| item.deep_copy_with(copies))
+					.collect();
+
+				*copy.borrow_mut() = items;
+
+				copy.into()
+			}
+
+			Self::Dict(dict) => {
+				if let Some(copy) = copies.get(&dict.address()) {
+					return copy.copy();
+				}
+
+				let copy = Dict::new(OrderedMap::new());
+				copies.insert(dict.address(), copy.copy().into());
+
+				let entries: Vec<(Value, Value)> = dict
+					.borrow()
+					.iter()
+					.map(|(key, value)| (key.copy(), value.copy()))
+					.collect();
+
+				for (key, value) in entries {
+					let value = value.deep_copy_with(copies);
+					copy.insert(key, value);
+				}
+
+				copy.into()
+			}
+
+			Self::Error(error) => {
+				let context = error.context.borrow().copy();
+				Error::new(error.description.copy(), context.deep_copy_with(copies)).into()
+			}
+
+			other => other.copy(),
 		}
 	}
 
@@ -149,6 +227,8 @@ impl Value {
 			Self::Dict(_) => Type::Dict,
 			Self::Function(_) => Type::Function,
 			Self::Error(_) => Type::Error,
+			Self::Duration(_) => Type::Duration,
+			Self::Size(_) => Type::Size,
 		}
 	}
 }
@@ -182,6 +262,8 @@ from_variant!(Array, Array);
 from_variant!(Dict, Dict);
 from_variant!(Function, Function);
 from_variant!(Error, Error);
+from_variant!(Duration, Duration);
+from_variant!(Size, Size);
 
 
 impl From<()> for Value {
@@ -0,0 +1,183 @@
+#[cfg(test)]
+mod tests;
+
+use std::{collections::HashMap, iter::FromIterator, slice};
+
+use gc::{Finalize, Trace};
+
+use super::{weak, Value};
+
+
+/// A hash map which preserves insertion order when iterated.
+/// Entries are stored in a vector, and indexed by a hash map from keys to positions.
+/// Removed entries are left as tombstones, which are compacted once they outnumber the
+/// live entries, so that removal is amortized constant time.
+#[derive(Debug, Default)]
+#[derive(Trace)]
+pub struct OrderedMap {
+	index: HashMap<Value, usize>,
+	entries: Vec<Option<(Value, Value)>>,
+	/// The number of live entries.
+	len: usize,
+}
+
+
+/// Collection of a dict's map is reported to weak references.
+impl Finalize for OrderedMap {
+	fn finalize(&self) {
+		weak::collected(self as *const Self as usize);
+	}
+}
+
+
+impl OrderedMap {
+	/// Create a new empty map.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+
+	/// Insert an entry, returning the previous value for the key, if any.
+	/// Replacing a value keeps the key in its original position.
+	pub fn insert(&mut self, key: Value, value: Value) -> Option<Value> {
+		if let Some(&ix) = self.index.get(&key) {
+			return Some(std::mem::replace(&mut self.entry_mut(ix).1, value));
+		}
+
+		self.index.insert(key.copy(), self.entries.len());
+		self.entries.push(Some((key, value)));
+		self.len += 1;
+
+		None
+	}
+
+
+	/// Get the value for the given key.
+	pub fn get(&self, key: &Value) -> Option<&Value> {
+		self.index
+			.get(key)
+			.map(|&ix| &self.entry(ix).1)
+	}
+
+
+	/// Check if the map contains the given key.
+	pub fn contains_key(&self, key: &Value) -> bool {
+		self.index.contains_key(key)
+	}
+
+
+	/// Remove the entry for the given key, preserving the order of the remaining entries.
+	pub fn remove(&mut self, key: &Value) -> Option<Value> {
+		let ix = self.index.remove(key)?;
+		let (_, value) = self.entries[ix].take().expect("removed entry in index");
+		self.len -= 1;
+
+		if self.entries.len() - self.len > self.len {
+			self.compact();
+		}
+
+		Some(value)
+	}
+
+
+	/// The number of entries.
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+
+	/// Whether the map is empty.
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+
+	/// Iterate the entries in insertion order.
+	pub fn iter(&self) -> Iter<'_> {
+		Iter { entries: self.entries.iter(), len: self.len }
+	}
+
+
+	fn entry(&self, ix: usize) -> &(Value, Value) {
+		self.entries[ix].as_ref().expect("removed entry in index")
+	}
+
+
+	fn entry_mut(&mut self, ix: usize) -> &mut (Value, Value) {
+		self.entries[ix].as_mut().expect("removed entry in index")
+	}
+
+
+	/// Drop the tombstones, updating the positions in the index.
+	fn compact(&mut self) {
+		self.entries.retain(Option::is_some);
+
+		for (ix, entry) in self.entries.iter().enumerate() {
+			let (key, _) = entry.as_ref().expect("tombstone after compaction");
+			*self.index.get_mut(key).expect("entry missing from index") = ix;
+		}
+	}
+}
+
+
+/// Iterator over the entries of an ordered map, in insertion order.
+pub struct Iter<'a> {
+	entries: slice::Iter<'a, Option<(Value, Value)>>,
+	/// The number of live entries left.
+	len: usize,
+}
+
+
+impl<'a> Iterator for Iter<'a> {
+	type Item = (&'a Value, &'a Value);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.entries.find_map(Option::as_ref)?;
+		self.len -= 1;
+		Some((key, value))
+	}
+
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		(self.len, Some(self.len))
+	}
+}
+
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.entries.rfind(|entry| entry.is_some())?.as_ref()?;
+		self.len -= 1;
+		Some((key, value))
+	}
+}
+
+
+impl<'a> ExactSizeIterator for Iter<'a> { }
+
+
+impl FromIterator<(Value, Value)> for OrderedMap {
+	fn from_iter<I: IntoIterator<Item = (Value, Value)>>(iter: I) -> Self {
+		let mut map = Self::new();
+
+		for (key, value) in iter {
+			map.insert(key, value);
+		}
+
+		map
+	}
+}
+
+
+/// Equality disregards the order of the entries, like in a hash map.
+impl PartialEq for OrderedMap {
+	fn eq(&self, other: &Self) -> bool {
+		self.len() == other.len()
+			&& self
+				.iter()
+				.all(|(key, value)| other.get(key) == Some(value))
+	}
+}
+
+
+impl Eq for OrderedMap { }
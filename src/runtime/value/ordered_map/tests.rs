@@ -0,0 +1,48 @@
+use super::*;
+
+
+fn keys(map: &OrderedMap) -> Vec<i64> {
+	map
+		.iter()
+		.map(
+			|(key, _)| match key {
+				Value::Int(int) => *int,
+				other => panic!("unexpected key {:?}", other),
+			}
+		)
+		.collect()
+}
+
+
+#[test]
+fn test_remove() {
+	let mut map: OrderedMap = (0 .. 10)
+		.map(|int| (Value::Int(int), Value::Int(int * 10)))
+		.collect();
+
+	assert_eq!(map.remove(&Value::Int(3)), Some(Value::Int(30)));
+	assert_eq!(map.remove(&Value::Int(3)), None);
+	assert_eq!(map.len(), 9);
+	assert_eq!(keys(&map), [0, 1, 2, 4, 5, 6, 7, 8, 9]);
+	assert_eq!(map.iter().len(), 9);
+	assert_eq!(map.iter().rev().map(|(_, value)| value.copy()).next(), Some(Value::Int(90)));
+
+	// Removing most entries compacts the tombstones.
+	for int in [0, 2, 4, 6, 8] {
+		map.remove(&Value::Int(int));
+	}
+	assert_eq!(keys(&map), [1, 5, 7, 9]);
+	assert_eq!(map.get(&Value::Int(7)), Some(&Value::Int(70)));
+
+	// New keys go to the end, while replaced ones keep their position.
+	map.insert(Value::Int(0), Value::Nil);
+	map.insert(Value::Int(5), Value::Nil);
+	assert_eq!(keys(&map), [1, 5, 7, 9, 0]);
+	assert_eq!(map.get(&Value::Int(5)), Some(&Value::Nil));
+
+	for int in [1, 5, 7, 9, 0] {
+		map.remove(&Value::Int(int));
+	}
+	assert!(map.is_empty());
+	assert_eq!(keys(&map), [] as [i64; 0]);
+}
@@ -0,0 +1,127 @@
+use std::convert::TryFrom;
+
+use gc::{Finalize, Trace};
+
+
+/// Duration units, in milliseconds.
+const DURATION_UNITS: &[(&str, f64)] = &[
+	("ms", 1.0),
+	("s", 1e3),
+	("m", 60e3),
+	("h", 3600e3),
+	("d", 86400e3),
+	("w", 604800e3),
+];
+
+/// Size units, in bytes. Multiples are binary.
+const SIZE_UNITS: &[(&str, f64)] = &[
+	("", 1.0),
+	("b", 1.0),
+	("k", 1024.0),
+	("kb", 1024.0),
+	("kib", 1024.0),
+	("m", 1048576.0),
+	("mb", 1048576.0),
+	("mib", 1048576.0),
+	("g", 1073741824.0),
+	("gb", 1073741824.0),
+	("gib", 1073741824.0),
+	("t", 1099511627776.0),
+	("tb", 1099511627776.0),
+	("tib", 1099511627776.0),
+];
+
+
+/// A duration, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration(pub i64);
+
+
+impl Finalize for Duration { }
+
+unsafe impl Trace for Duration {
+	gc::unsafe_empty_trace!();
+}
+
+
+impl Duration {
+	/// Parse a duration such as "5s", "200ms" or "1h30m". The units are ms, s, m, h, d and
+	/// w, and quantities may be fractional.
+	pub fn parse(input: &[u8]) -> Option<Self> {
+		parse(input, DURATION_UNITS, false).map(Self)
+	}
+
+
+	/// Convert to a standard duration, if not negative.
+	pub fn to_std(self) -> Option<std::time::Duration> {
+		u64::try_from(self.0)
+			.ok()
+			.map(std::time::Duration::from_millis)
+	}
+}
+
+
+/// A size, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Size(pub i64);
+
+
+impl Finalize for Size { }
+
+unsafe impl Trace for Size {
+	gc::unsafe_empty_trace!();
+}
+
+
+impl Size {
+	/// Parse a size such as "512M", "2G" or "10KiB". The units are K, M, G and T, which are
+	/// binary multiples, optionally followed by B or iB. Quantities without a unit are
+	/// bytes, and may be fractional.
+	pub fn parse(input: &[u8]) -> Option<Self> {
+		parse(input, SIZE_UNITS, true).map(Self)
+	}
+}
+
+
+/// Parse a sequence of quantities followed by units, summing them. Units are case
+/// insensitive. If single, only one quantity is allowed.
+fn parse(input: &[u8], units: &[(&str, f64)], single: bool) -> Option<i64> {
+	let input = std::str::from_utf8(input).ok()?.trim();
+
+	if input.is_empty() {
+		return None;
+	}
+
+	let mut rest = input;
+	let mut total = 0.0;
+
+	while !rest.is_empty() {
+		let number_end = rest
+			.find(|c: char| !(c.is_ascii_digit() || c == '.'))
+			.unwrap_or(rest.len());
+		let quantity: f64 = rest[..number_end].parse().ok()?;
+		rest = rest[number_end..].trim_start();
+
+		let unit_end = rest
+			.find(|c: char| !c.is_ascii_alphabetic())
+			.unwrap_or(rest.len());
+		let unit = rest[..unit_end].to_ascii_lowercase();
+		rest = rest[unit_end..].trim_start();
+
+		let (_, multiplier) = units
+			.iter()
+			.find(|(name, _)| *name == unit)?;
+
+		total += quantity * multiplier;
+
+		if single && !rest.is_empty() {
+			return None;
+		}
+	}
+
+	if total.is_finite() && total <= i64::MAX as f64 {
+		Some(total.round() as i64)
+	} else {
+		None
+	}
+}
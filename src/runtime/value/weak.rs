@@ -0,0 +1,136 @@
+//! Weak references to dicts and arrays.
+//!
+//! The gc crate has no support for weak pointers. Therefore, a weak reference holds an
+//! unrooted pointer which is not traced, and weakly referenced objects report when they
+//! are finalized, which happens before they are freed.
+
+use std::{
+	cell::{Cell, RefCell},
+	collections::HashMap,
+	rc::Rc,
+};
+
+use gc::{Finalize, Trace};
+
+use super::{Array, Dict, Value};
+
+
+thread_local! {
+	/// Liveness of the weakly referenced objects, by address.
+	static TARGETS: RefCell<HashMap<usize, Rc<Cell<bool>>>> = RefCell::new(HashMap::new());
+}
+
+
+/// Report that the object at the given address has been collected.
+pub(super) fn collected(address: usize) {
+	// The registry may have already been destroyed when collecting at thread exit.
+	let _ = TARGETS.try_with(
+		|targets| {
+			let mut targets = targets.borrow_mut();
+
+			if targets.is_empty() {
+				return;
+			}
+
+			if let Some(alive) = targets.remove(&address) {
+				alive.set(false);
+			}
+		}
+	);
+}
+
+
+#[derive(Trace, Finalize)]
+enum Target {
+	Dict(Dict),
+	Array(Array),
+}
+
+
+/// A weak reference, which doesn't prevent its target from being collected.
+#[derive(Finalize)]
+pub struct Weak {
+	target: Target,
+	alive: Rc<Cell<bool>>,
+}
+
+
+impl Weak {
+	/// Create a weak reference to the given value. Only dicts and arrays are supported.
+	pub fn new(value: &Value) -> Option<Self> {
+		let (target, address) = match value {
+			Value::Dict(ref dict) => (Target::Dict(dict.copy()), dict.address()),
+			Value::Array(ref array) => (Target::Array(array.copy()), array.address()),
+			_ => return None,
+		};
+
+		let alive = TARGETS.with(
+			|targets| targets
+				.borrow_mut()
+				.entry(address)
+				.or_insert_with(|| Rc::new(Cell::new(true)))
+				.clone()
+		);
+
+		Some(Self { target, alive })
+	}
+
+
+	/// Get the target, or nil if it has been collected.
+	pub fn get(&self) -> Value {
+		if !self.alive.get() {
+			return Value::default();
+		}
+
+		match &self.target {
+			Target::Dict(dict) => dict.copy().into(),
+			Target::Array(array) => array.copy().into(),
+		}
+	}
+}
+
+
+impl std::fmt::Debug for Weak {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("Weak")
+			.field("alive", &self.alive.get())
+			.finish()
+	}
+}
+
+
+// The target is not traced, so that it may be collected. This is sound with the gc 0.4
+// collector for the following reasons:
+// - Collection marks from rooted boxes only. Once the weak reference is stored in the
+//   heap, its target pointer is unrooted like any other, and as it's never traced, the
+//   target is collected once it's otherwise unreachable.
+// - The collector finalizes every unreachable box before freeing any of them. Thus, the
+//   target reports its collection, clearing `alive`, before its memory is released, even
+//   if the weak reference is collected in the same cycle.
+// - Rooting and unrooting adjust the root count in the target's box, so they must be
+//   skipped once `alive` is cleared, as the pointer is then dangling. The target can't be
+//   collected while the weak reference is rooted, as its root count is then positive.
+// - Dropping a weak reference only touches the target's box if its pointer is rooted,
+//   which is never the case once `alive` is cleared.
+unsafe impl Trace for Weak {
+	unsafe fn trace(&self) {}
+
+
+	unsafe fn root(&self) {
+		if self.alive.get() {
+			self.target.root();
+		}
+	}
+
+
+	unsafe fn unroot(&self) {
+		if self.alive.get() {
+			self.target.unroot();
+		}
+	}
+
+
+	fn finalize_glue(&self) {
+		Finalize::finalize(self);
+	}
+}
@@ -0,0 +1,126 @@
+//! Interpreter-level resource limits.
+//!
+//! The limits are enforced by a watchdog thread, which periodically checks the elapsed
+//! time and the resident memory of the interpreter. When a limit is exceeded, the
+//! watchdog raises a pseudo signal, which interrupts the runtime like a received signal
+//! would, killing the child process being waited, if any. If the runtime doesn't stop
+//! within a grace period, the watchdog terminates the interpreter.
+
+use std::{
+	fs,
+	io,
+	sync::Mutex,
+	thread,
+	time::{Duration, Instant},
+};
+
+use super::signal;
+
+
+/// Interval between checks.
+const CHECK_INTERVAL: Duration = Duration::from_millis(50);
+/// How long the runtime has to stop after being interrupted.
+const GRACE_PERIOD: Duration = Duration::from_secs(2);
+/// Exit status when the interpreter is terminated by the watchdog.
+const EXIT_STATUS: i32 = 127;
+
+/// The limit that was exceeded, if any.
+static EXCEEDED: Mutex<Option<Limit>> = Mutex::new(None);
+
+
+/// Resource limits for the interpreter.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limits {
+	/// Maximum wall-clock runtime.
+	pub max_runtime: Option<Duration>,
+	/// Maximum resident memory, in bytes.
+	pub max_memory: Option<u64>,
+}
+
+
+impl Limits {
+	/// Check if there are no limits.
+	pub fn is_empty(&self) -> bool {
+		self.max_runtime.is_none() && self.max_memory.is_none()
+	}
+}
+
+
+/// A resource limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+	Runtime(Duration),
+	Memory(u64),
+}
+
+
+impl std::fmt::Display for Limit {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Runtime(duration) => write!(f, "runtime limit ({:?})", duration),
+			Self::Memory(bytes) => write!(f, "memory limit ({} bytes)", bytes),
+		}
+	}
+}
+
+
+/// Start the watchdog thread. The signal handlers must have been installed, as the
+/// runtime only checks for interruptions when they are.
+pub fn start(limits: Limits) -> io::Result<()> {
+	if limits.is_empty() {
+		return Ok(());
+	}
+
+	let start = Instant::now();
+
+	thread::Builder::new()
+		.name("watchdog".into())
+		.spawn(
+			move || {
+				let limit = loop {
+					thread::sleep(CHECK_INTERVAL);
+
+					if let Some(max) = limits.max_runtime {
+						if start.elapsed() >= max {
+							break Limit::Runtime(max);
+						}
+					}
+
+					if let Some(max) = limits.max_memory {
+						if resident_memory().is_some_and(|memory| memory >= max) {
+							break Limit::Memory(max);
+						}
+					}
+				};
+
+				*EXCEEDED.lock().expect("watchdog lock poisoned") = Some(limit);
+				signal::raise(signal::LIMIT_EXCEEDED);
+
+				thread::sleep(GRACE_PERIOD);
+
+				eprintln!("Panic: {} exceeded, terminating", limit);
+				std::process::exit(EXIT_STATUS);
+			}
+		)?;
+
+	Ok(())
+}
+
+
+/// The limit that was exceeded, if any.
+pub fn exceeded() -> Option<Limit> {
+	*EXCEEDED.lock().expect("watchdog lock poisoned")
+}
+
+
+/// The resident memory of the interpreter, in bytes.
+/// Only available on systems with procfs.
+fn resident_memory() -> Option<u64> {
+	let statm = fs::read_to_string("/proc/self/statm").ok()?;
+	let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+
+	// SAFETY: sysconf has no memory safety requirements.
+	let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+
+	Some(pages * page_size.max(0) as u64)
+}
@@ -15,6 +15,8 @@ pub struct ErrorsDisplayContext<'a> {
 	pub max_errors: Option<usize>,
 	/// Symbol interner.
 	pub interner: &'a symbol::Interner,
+	/// Output format of the errors.
+	pub format: fmt::ErrorFormat,
 }
 
 
@@ -52,6 +54,14 @@ impl<'a> Display<'a> for ErrorKind {
 			Self::InvalidAssignment => write!(f, "invalid assignment"),
 
 			Self::AsyncBuiltin => write!(f, "use of built-in command in async context"),
+
+			Self::KeywordInCommandBlock(keyword) => write!(
+				f,
+				"{} is not allowed inside command blocks",
+				keyword
+			),
+
+			Self::AmbiguousRedirection => write!(f, "redirection must expand to a single path"),
 		}
 	}
 }
@@ -67,6 +77,39 @@ impl<'a> Display<'a> for Error {
 }
 
 
+impl<'a> Display<'a> for fmt::Json<&Error> {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		let error = self.0;
+
+		let kind = match error.kind {
+			ErrorKind::UndeclaredVariable(_) => "undeclared_variable",
+			ErrorKind::DuplicateVariable(_) => "duplicate_variable",
+			ErrorKind::DuplicateKey(_) => "duplicate_key",
+			ErrorKind::ReturnOutsideFunction => "return_outside_function",
+			ErrorKind::SelfOutsideFunction => "self_outside_function",
+			ErrorKind::TryOutsideFunction => "try_outside_function",
+			ErrorKind::BreakOutsideLoop => "break_outside_loop",
+			ErrorKind::InvalidAssignment => "invalid_assignment",
+			ErrorKind::AsyncBuiltin => "async_builtin",
+			ErrorKind::KeywordInCommandBlock(_) => "keyword_in_command_block",
+			ErrorKind::AmbiguousRedirection => "ambiguous_redirection",
+		};
+
+		let pos = &error.pos;
+
+		fmt::json_error(
+			f,
+			context,
+			Some((pos.path, pos.line, pos.column)),
+			kind,
+			fmt::Show(&error.kind, context)
+		)
+	}
+}
+
+
 /// We need this in order to be able to implement std::error::Error.
 impl std::fmt::Display for Error {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -79,6 +122,14 @@ impl<'a> Display<'a> for Errors {
 	type Context = ErrorsDisplayContext<'a>;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		if context.format == fmt::ErrorFormat::Json {
+			for error in self.0.iter() {
+				writeln!(f, "{}", fmt::Show(fmt::Json(error), context.interner))?;
+			}
+
+			return Ok(());
+		}
+
 		for (ix, error) in self.0.iter().enumerate() {
 			if let Some(max) = context.max_errors {
 				if max <= ix {
@@ -27,6 +27,11 @@ pub enum ErrorKind {
 	/// Built-in command used in async context.
 	/// Async contexts include pipes, redirections and capture or async blocks.
 	AsyncBuiltin,
+	/// Statement keyword used as the program of a command, such as `{ break }`.
+	KeywordInCommandBlock(&'static str),
+	/// Redirection target or source which always expands to multiple or no paths, such
+	/// as `> out-{1..3}`.
+	AmbiguousRedirection,
 }
 
 
@@ -120,6 +125,24 @@ impl Error {
 			pos
 		}
 	}
+
+
+	/// Statement keyword used as the program of a command.
+	pub fn keyword_in_command_block(keyword: &'static str, pos: SourcePos) -> Self {
+		Self {
+			kind: ErrorKind::KeywordInCommandBlock(keyword),
+			pos
+		}
+	}
+
+
+	/// Redirection target or source which always expands to multiple or no paths.
+	pub fn ambiguous_redirection(pos: SourcePos) -> Self {
+		Self {
+			kind: ErrorKind::AmbiguousRedirection,
+			pos
+		}
+	}
 }
 
 
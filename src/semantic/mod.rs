@@ -1,5 +1,6 @@
 mod error;
 mod scope;
+mod warning;
 pub mod program;
 #[cfg(test)]
 mod tests;
@@ -10,8 +11,10 @@ use std::{
 };
 
 use crate::{
+	fmt,
 	symbol::{self, Symbol},
-	syntax::ast::{IllFormed, UnaryOp}
+	syntax::ast::{IllFormed, UnaryOp},
+	term::color,
 };
 use super::syntax::{ast, lexer, SourcePos};
 use program::{
@@ -22,6 +25,7 @@ use program::{
 	Argument,
 	BasicCommand,
 	Block,
+	BlockInput,
 	Command,
 	CommandBlock,
 	Expr,
@@ -33,6 +37,7 @@ use program::{
 	Statement,
 };
 pub use error::{Error, Errors, ErrorsDisplayContext};
+pub use warning::{Warning, WarningKind, Warnings, WarningsDisplayContext};
 
 
 /// Static semantic analyzer.
@@ -40,6 +45,8 @@ pub use error::{Error, Errors, ErrorsDisplayContext};
 pub struct Analyzer<'a> {
 	/// Collected errors.
 	errors: &'a mut Errors,
+	/// Collected warnings.
+	warnings: &'a mut Warnings,
 	/// Scope stack to check declared variables.
 	scope: &'a mut scope::Stack,
 	/// Hashset to check duplicate symbols in dict keys.
@@ -61,28 +68,83 @@ impl<'a> Analyzer<'a> {
 	/// error will be reported for such parts, as those errors were already reported by the
 	/// syntactic analysis.
 	pub fn analyze(ast: ast::Ast, interner: &mut symbol::Interner) -> Result<Program, Errors> {
+		Self::analyze_with_warnings(ast, interner).0
+	}
+
+
+	/// Perform static semantic analysis in the given AST, also producing warnings.
+	/// Warnings are collected even if the analysis fails.
+	pub fn analyze_with_warnings(
+		ast: ast::Ast,
+		interner: &mut symbol::Interner
+	) -> (Result<Program, Errors>, Warnings) {
 		let mut scope = scope::Stack::default();
 		let mut dict_keys = HashSet::default();
 		let mut errors = Errors::default();
+		let mut warnings = Warnings::default();
 
 		let (result, root_frame) = {
-			let mut analyzer = Analyzer::new(interner, &mut scope, &mut dict_keys, &mut errors);
+			let mut analyzer = Analyzer::new(
+				interner,
+				&mut scope,
+				&mut dict_keys,
+				&mut errors,
+				&mut warnings,
+			);
 			let result = analyzer.analyze_block(ast.statements);
 			let root_frame = analyzer.exit_frame();
 			(result, root_frame)
 			// Drop analyzer before proceeding, making sure everything is clean.
 		};
 
-		match result {
+		let result = match result {
 			Some(statements) if errors.0.is_empty() => Ok(
 				Program {
 					source: ast.source,
 					statements,
 					root_slots: root_frame.slots,
+					root_slot_names: root_frame.slot_names,
 				}
 			),
 
 			_ => Err(errors)
+		};
+
+		(result, warnings)
+	}
+
+
+	/// Analyze a command argument outside of any scope, where no variable is declared.
+	pub fn analyze_standalone_argument(
+		argument: ast::Argument,
+		interner: &mut symbol::Interner
+	) -> Result<Argument, Errors> {
+		let mut scope = scope::Stack::default();
+		let mut dict_keys = HashSet::default();
+		let mut errors = Errors::default();
+		let mut warnings = Warnings::default();
+
+		scope.enter_frame();
+
+		let result = {
+			let mut analyzer = Analyzer {
+				errors: &mut errors,
+				warnings: &mut warnings,
+				scope: &mut scope,
+				dict_keys: &mut dict_keys,
+				interner,
+				in_function: false,
+				in_loop: false,
+				dropped: false,
+			};
+			let result = analyzer.analyze_argument(argument);
+			analyzer.exit_frame();
+			result
+		};
+
+		match result {
+			Some(argument) if errors.0.is_empty() => Ok(argument),
+			_ => Err(errors),
 		}
 	}
 
@@ -110,7 +172,10 @@ impl<'a> Analyzer<'a> {
 	fn analyze_statement(&mut self, statement: ast::Statement) -> Option<Statement> {
 		match statement {
 			// Let.
-			ast::Statement::Let { identifier, init, pos } => {
+			// Type annotations are erased here, they are only used by the type checker.
+			ast::Statement::Let { identifier, init, pos, .. } => {
+				self.check_reserved(identifier, pos);
+
 				let slot_ix = self.scope
 					.declare(identifier, pos)
 					.map_err(
@@ -118,25 +183,31 @@ impl<'a> Analyzer<'a> {
 					)
 					.ok();
 
-				let init = self.analyze_expr(init);
+				let init = init.map(|init| self.analyze_expr(init));
 
-				let (slot_ix, right) = slot_ix.zip(init)?;
+				match (slot_ix?, init) {
+					(slot_ix, None) => Some(Statement::Declare { slot_ix, identifier, pos }),
 
-				let left = Lvalue::Identifier { slot_ix, pos };
-
-				Some(Statement::Assign { left, right })
+					(slot_ix, Some(init)) => {
+						let left = Lvalue::Identifier { slot_ix, pos };
+						Some(Statement::Assign { left: Box::new([left]), right: init? })
+					}
+				}
 			}
 
 			// Assign.
 			ast::Statement::Assign { left, right, pos } => {
-				let left = self
-					.analyze_lvalue(left)
-					.map_err(
-						|lvalue| if !lvalue {
-							self.report(Error::invalid_assignment(pos));
-						}
-					)
-					.ok();
+				let left = self.analyze_items(
+					|analyzer, left| analyzer
+						.analyze_lvalue(left)
+						.map_err(
+							|lvalue| if !lvalue {
+								analyzer.report(Error::invalid_assignment(pos));
+							}
+						)
+						.ok(),
+					left.into_vec(), // Use vec's owned iterator.
+				);
 
 				let right = self.analyze_expr(right);
 
@@ -174,6 +245,7 @@ impl<'a> Analyzer<'a> {
 
 			// While.
 			ast::Statement::While { condition, block, .. } => {
+				let condition_text = self.source_text(&condition);
 				let condition = self.analyze_expr(condition);
 				let block = {
 					self.enter_loop().analyze_block(block)
@@ -181,7 +253,7 @@ impl<'a> Analyzer<'a> {
 
 				let (condition, block) = condition.zip(block)?;
 
-				Some(Statement::While { condition, block })
+				Some(Statement::While { condition, condition_text, block })
 			}
 
 			// For.
@@ -194,6 +266,7 @@ impl<'a> Analyzer<'a> {
 						if identifier.is_ill_formed() {
 							None
 						} else {
+							analyzer.check_reserved(identifier, pos);
 							analyzer.scope
 								.declare(identifier, pos)
 								.map_err(
@@ -214,6 +287,7 @@ impl<'a> Analyzer<'a> {
 
 			// Expr.
 			ast::Statement::Expr(expr) => {
+				self.check_discarded_capture(&expr);
 				let expr = self.analyze_expr(expr)?;
 				Some(Statement::Expr(expr))
 			}
@@ -300,8 +374,19 @@ impl<'a> Analyzer<'a> {
 				)
 			}
 
+			// Interpolation.
+			ast::Expr::Interpolation { parts, pos } => {
+				let parts = self.analyze_items(
+					Self::analyze_expr,
+					parts.into_vec(), // Use vec's owned iterator.
+				)?;
+
+				Some(Expr::Interpolation { parts, pos })
+			}
+
 			// If.
 			ast::Expr::If { condition, then, otherwise, pos } => {
+				let condition_text = self.source_text(&condition);
 				let condition = self.analyze_expr(*condition);
 				let then = {
 					self.enter_block().analyze_block(then)
@@ -315,6 +400,7 @@ impl<'a> Analyzer<'a> {
 				Some(
 					Expr::If {
 						condition: Box::new(condition),
+						condition_text,
 						then,
 						otherwise,
 						pos
@@ -323,7 +409,7 @@ impl<'a> Analyzer<'a> {
 			}
 
 			// Access.
-			ast::Expr::Access { object, field, pos } => {
+			ast::Expr::Access { object, field, safe, pos } => {
 				let object = self.analyze_expr(*object);
 				let field = self.analyze_expr(*field);
 
@@ -333,6 +419,7 @@ impl<'a> Analyzer<'a> {
 					Expr::Access {
 						object: Box::new(object),
 						field: Box::new(field),
+						safe,
 						pos
 					}
 				)
@@ -340,6 +427,7 @@ impl<'a> Analyzer<'a> {
 
 			// Call.
 			ast::Expr::Call { function, args, pos } => {
+				let function_text = self.source_text(&function);
 				let function = self.analyze_expr(*function);
 
 				let args = self.analyze_items(
@@ -352,6 +440,7 @@ impl<'a> Analyzer<'a> {
 				Some(
 					Expr::Call {
 						function: Box::new(function),
+						function_text,
 						args,
 						pos,
 					}
@@ -395,8 +484,8 @@ impl<'a> Analyzer<'a> {
 				Ok(Lvalue::Identifier { slot_ix, pos })
 			}
 
-			// Access.
-			ast::Expr::Access { object, field, pos } => {
+			// Access. Safe access is not a valid l-value.
+			ast::Expr::Access { object, field, safe: false, pos } => {
 				let object = self.analyze_expr(*object);
 				let field = self.analyze_expr(*field);
 
@@ -456,7 +545,8 @@ impl<'a> Analyzer<'a> {
 
 			// Dict.
 			ast::Literal::Dict(items) => {
-				self.dict_keys.clear();
+				// Nested dicts must not clobber the keys of the enclosing dict.
+				let outer_keys = std::mem::take(self.dict_keys);
 
 				let items = self.analyze_items(
 					|analyzer, ((symbol, pos), expr)| {
@@ -477,13 +567,15 @@ impl<'a> Analyzer<'a> {
 						Some((symbol, expr))
 					},
 					items.into_vec(), // Use vec's owned iterator.
-				)?;
+				);
 
-				Some(Literal::Dict(items))
+				*self.dict_keys = outer_keys;
+
+				Some(Literal::Dict(items?))
 			}
 
 			// Function.
-			ast::Literal::Function { params, body } => {
+			ast::Literal::Function { params, body, doc, name, .. } => {
 				let mut analyzer = self.enter_frame();
 
 				#[allow(clippy::manual_try_fold)] // We don't want to short circuit here.
@@ -491,10 +583,11 @@ impl<'a> Analyzer<'a> {
 					.iter()
 					.fold(
 						Some(()),
-						|acc, &(symbol ,pos)| {
+						|acc, &((symbol, pos), _)| {
 							let result = if symbol.is_ill_formed() {
 								None
 							} else {
+								analyzer.check_reserved(symbol, pos);
 								analyzer.scope
 									.declare(symbol, pos)
 									.map_err(
@@ -518,7 +611,9 @@ impl<'a> Analyzer<'a> {
 					Literal::Function {
 						params: params.len() as u32,
 						frame_info,
-						body
+						body,
+						doc,
+						name,
 					}
 				)
 			}
@@ -546,13 +641,29 @@ impl<'a> Analyzer<'a> {
 			block.tail.into_vec(), // Use vec's owned iterator.
 		);
 
-		let (head, tail) = head.zip(tail)?;
+		// The input is evaluated in the enclosing scope, before executing the block.
+		let input = match block.input {
+			Some(ast::BlockInput { literal, source }) => self
+				.analyze_expr(*source)
+				.map(
+					|source| Some(
+						BlockInput {
+							literal,
+							source: source.into(),
+						}
+					)
+				),
+			None => Some(None),
+		};
+
+		let ((head, tail), input) = head.zip(tail).zip(input)?;
 
 		Some(
 			CommandBlock {
 				kind: block.kind.into(),
 				head,
 				tail,
+				input,
 			}
 		)
 	}
@@ -561,6 +672,20 @@ impl<'a> Analyzer<'a> {
 	/// Analyze a command.
 	/// None is returned if any error is detected.
 	fn analyze_command(&mut self, command: ast::Command, in_async: bool) -> Option<Command> {
+		// Statements can't be used inside command blocks. Report all of them, so that the
+		// analysis proceeds with the remaining commands.
+		let mut has_keyword = false;
+		for basic_command in std::iter::once(&command.head).chain(command.tail.iter()) {
+			if let Some(keyword) = statement_keyword(&basic_command.program) {
+				self.report(Error::keyword_in_command_block(keyword, basic_command.pos));
+				has_keyword = true;
+			}
+		}
+
+		if has_keyword {
+			return None;
+		}
+
 		match command::Builtin::try_from(&command.head.program) {
 			Ok(_)
 				if in_async // Block is async.
@@ -611,6 +736,8 @@ impl<'a> Analyzer<'a> {
 			return None;
 		};
 
+		self.check_attached_try(&command);
+
 		let program = self.analyze_argument(command.program);
 
 		let env = self.analyze_env(command.env);
@@ -690,11 +817,29 @@ impl<'a> Analyzer<'a> {
 	/// None is returned if any error is detected.
 	fn analyze_arg_expansion(&mut self, expansion: ast::ArgExpansion) -> Option<ArgPart> {
 		match expansion {
-			ast::ArgExpansion::Home => Some(ArgPart::Home),
-			ast::ArgExpansion::Range(from, to) => Some(ArgPart::Range(from, to)),
+			ast::ArgExpansion::Home(user) => Some(ArgPart::Home(user)),
+			ast::ArgExpansion::Range { from, to, step } => {
+				let from = self.analyze_arg_unit(from);
+				let to = self.analyze_arg_unit(to);
+				let step = step.map(|step| self.analyze_arg_unit(step));
+
+				Some(
+					ArgPart::Range {
+						from: from?,
+						to: to?,
+						step: match step {
+							Some(step) => Some(step?),
+							None => None,
+						},
+					}
+				)
+			},
 			ast::ArgExpansion::Collection(items) => {
 				let items = self.analyze_items(
-					Self::analyze_arg_unit,
+					|analyzer, item: Box<[ast::ArgPart]>| analyzer.analyze_items(
+						Self::analyze_arg_part,
+						item.into_vec() // Use vec's owned iterator.
+					),
 					items.into_vec() // Use vec's owned iterator.
 				)?;
 
@@ -723,7 +868,7 @@ impl<'a> Analyzer<'a> {
 						)
 						.ok()?;
 
-					Some(ArgUnit::Dollar { slot_ix, pos })
+					Some(ArgUnit::Dollar { slot_ix, symbol, pos })
 				}
 			}
 		}
@@ -741,26 +886,67 @@ impl<'a> Analyzer<'a> {
 					ast::RedirectionTarget::Fd(fd) => Some(RedirectionTarget::Fd(fd)),
 
 					ast::RedirectionTarget::Overwrite(arg) => self
-						.analyze_argument(arg)
+						.analyze_redirection_argument(arg)
 						.map(RedirectionTarget::Overwrite),
 
 					ast::RedirectionTarget::Append(arg) => self
-						.analyze_argument(arg)
+						.analyze_redirection_argument(arg)
 						.map(RedirectionTarget::Append),
 				}?;
 
 				Some(Redirection::Output { source, target })
 			},
 
-			ast::Redirection::Input { literal, source } => {
-				let source = self.analyze_argument(source)?;
+			ast::Redirection::Input { literal, fd, source } => {
+				let source = self.analyze_redirection_argument(source)?;
 
-				Some(Redirection::Input { literal, source })
+				Some(Redirection::Input { literal, fd, source })
 			}
 		}
 	}
 
 
+	/// Analyze the target or source of a redirection, which must expand to a single path.
+	/// Expansions which never do so are reported, while the remaining cases, such as
+	/// variables and globs, are checked at runtime.
+	/// None is returned if any error is detected.
+	fn analyze_redirection_argument(&mut self, argument: ast::Argument) -> Option<Argument> {
+		let argument = self.analyze_argument(argument)?;
+
+		let ambiguous = argument.parts
+			.iter()
+			.any(
+				|part| match part {
+					ArgPart::Range { from, to, step } => {
+						let int = |unit: &ArgUnit| match unit {
+							ArgUnit::Literal(lit) => std::str::from_utf8(lit).ok()?.parse::<i64>().ok(),
+							ArgUnit::Dollar { .. } => None,
+						};
+
+						let step = match step {
+							Some(step) => int(step),
+							None => Some(1),
+						};
+
+						match (int(from), int(to), step) {
+							(Some(from), Some(to), Some(step)) => from.abs_diff(to) >= step.unsigned_abs().max(1),
+							_ => false,
+						}
+					},
+					ArgPart::Collection(items) => items.len() != 1,
+					_ => false,
+				}
+			);
+
+		if ambiguous {
+			self.report(Error::ambiguous_redirection(argument.pos));
+			None
+		} else {
+			Some(argument)
+		}
+	}
+
+
 	/// Analyze a collection of items.
 	fn analyze_items<T, U, F, I>(&mut self, mut analyze: F, iter: I) -> Option<Box<[U]>>
 	where
@@ -795,7 +981,8 @@ impl<'a> Analyzer<'a> {
 		interner: &'a mut symbol::Interner,
 		scope: &'a mut scope::Stack,
 		dict_keys: &'a mut HashSet<Symbol>,
-		errors: &'a mut Errors
+		errors: &'a mut Errors,
+		warnings: &'a mut Warnings,
 	) -> Self {
 		let std_symbol = interner.get_or_intern("std");
 
@@ -806,6 +993,7 @@ impl<'a> Analyzer<'a> {
 
 		Self {
 			errors,
+			warnings,
 			scope,
 			dict_keys,
 			interner,
@@ -822,6 +1010,7 @@ impl<'a> Analyzer<'a> {
 
 		Analyzer {
 			errors: self.errors,
+			warnings: self.warnings,
 			scope: self.scope,
 			dict_keys: self.dict_keys,
 			interner: self.interner,
@@ -838,6 +1027,7 @@ impl<'a> Analyzer<'a> {
 
 		Analyzer {
 			errors: self.errors,
+			warnings: self.warnings,
 			scope: self.scope,
 			dict_keys: self.dict_keys,
 			interner: self.interner,
@@ -854,6 +1044,7 @@ impl<'a> Analyzer<'a> {
 
 		Analyzer {
 			errors: self.errors,
+			warnings: self.warnings,
 			scope: self.scope,
 			dict_keys: self.dict_keys,
 			interner: self.interner,
@@ -875,6 +1066,117 @@ impl<'a> Analyzer<'a> {
 	fn report(&mut self, error: Error) {
 		self.errors.0.push(error);
 	}
+
+
+	/// Warn if a declared identifier is reserved for a future keyword.
+	fn check_reserved(&mut self, symbol: Symbol, pos: SourcePos) {
+		let reserved = self.interner
+			.resolve(symbol)
+			.is_some_and(lexer::is_reserved);
+
+		if reserved {
+			self.warnings.0.push(Warning::reserved_identifier(symbol, pos));
+		}
+	}
+
+
+	/// Warn if the try operator is attached to an unquoted argument, as in `ls file?`, where
+	/// it may have been intended as a glob.
+	fn check_attached_try(&mut self, command: &ast::BasicCommand) {
+		if !command.try_attached || !command.redirections.is_empty() {
+			return;
+		}
+
+		let last = match command.arguments.last() {
+			Some(argument) => argument,
+			None => return,
+		};
+
+		let unquoted = matches!(
+			last.parts.last(),
+			Some(ast::ArgPart::Unit(ast::ArgUnit::Literal(_)) | ast::ArgPart::Expansion(_))
+		);
+
+		if unquoted {
+			self.warnings.0.push(Warning::attached_try(last.pos));
+		}
+	}
+
+
+	/// Warn if a capture block is used as a statement, as in `${ ls }`, where its output and
+	/// errors are discarded.
+	fn check_discarded_capture(&mut self, expr: &ast::Expr) {
+		if let ast::Expr::CommandBlock { block, pos } = expr {
+			if matches!(block.kind, ast::CommandBlockKind::Capture) {
+				self.warnings.0.push(Warning::discarded_capture(*pos));
+			}
+		}
+	}
+
+
+	/// Render an expression as a single line of source text, to be used in runtime error
+	/// messages. Long expressions are truncated.
+	fn source_text(&mut self, expr: &ast::Expr) -> Symbol {
+		const MAX_LEN: usize = 60;
+
+		let text = color::disabled(
+			|| fmt::Show(expr, ast::fmt::Context::from(&*self.interner).inlined()).to_string()
+		);
+
+		// Binary operations are rendered in parenthesis, which are redundant at the top level.
+		let text = match strip_parens(&text) {
+			Some(inner) => inner.to_owned(),
+			None => text,
+		};
+
+		let text = match text.char_indices().nth(MAX_LEN) {
+			Some((ix, _)) => format!("{}...", &text[..ix]),
+			None => text,
+		};
+
+		self.interner.get_or_intern(text)
+	}
+}
+
+
+/// Strip the outermost parenthesis, if they enclose the whole text.
+fn strip_parens(text: &str) -> Option<&str> {
+	let inner = text.strip_prefix('(')?.strip_suffix(')')?;
+
+	let mut depth = 0usize;
+	for byte in inner.bytes() {
+		match byte {
+			b'(' => depth += 1,
+			b')' => depth = depth.checked_sub(1)?,
+			_ => (),
+		}
+	}
+
+	Some(inner)
+}
+
+
+/// The statement keyword used as a command's program, if any. Quoted programs, such as
+/// `"break"`, are not keywords.
+fn statement_keyword(program: &ast::Argument) -> Option<&'static str> {
+	if program.quoted {
+		return None;
+	}
+
+	match program.parts.as_ref() {
+		[ ast::ArgPart::Unit(ast::ArgUnit::Literal(ref lit)) ] => match lit.as_ref() {
+			b"break" => Some("break"),
+			b"return" => Some("return"),
+			b"let" => Some("let"),
+			b"function" => Some("function"),
+			b"if" => Some("if"),
+			b"for" => Some("for"),
+			b"while" => Some("while"),
+			_ => None,
+		},
+
+		_ => None,
+	}
 }
 
 
@@ -0,0 +1,678 @@
+use std::convert::TryInto;
+
+use crate::{
+	semantic::{Warning, WarningKind, Warnings},
+	symbol::{self, Symbol},
+};
+use super::super::{
+	command::Builtin,
+	mem::{Capture, FrameInfo, SlotIx},
+	ArgPart,
+	ArgUnit,
+	Argument,
+	BasicCommand,
+	BinaryOp,
+	Block,
+	BlockInput,
+	Command,
+	CommandBlock,
+	CommandBlockKind,
+	Expr,
+	Literal,
+	Lvalue,
+	Program,
+	Redirection,
+	RedirectionTarget,
+	SourcePos,
+	Statement,
+	UnaryOp,
+};
+
+
+/// Malformed cache data.
+#[derive(Debug)]
+pub(super) struct Invalid;
+
+
+/// Binary decoder for programs.
+pub(super) struct Decoder<'a> {
+	input: &'a [u8],
+	symbols: Box<[Symbol]>,
+}
+
+
+impl<'a> Decoder<'a> {
+	/// Create a decoder, reading the symbol table and interning all symbols.
+	pub fn new(input: &'a [u8], interner: &mut symbol::Interner) -> Result<Self, Invalid> {
+		let mut decoder = Self { input, symbols: Box::default() };
+
+		let count = decoder.u32()?;
+		let mut symbols = Vec::new();
+		for _ in 0 .. count {
+			let value = decoder.bytes()?;
+			symbols.push(interner.get_or_intern(value));
+		}
+
+		decoder.symbols = symbols.into();
+
+		Ok(decoder)
+	}
+
+
+	/// Check that all input has been consumed.
+	pub fn finish(self) -> Result<(), Invalid> {
+		if self.input.is_empty() {
+			Ok(())
+		} else {
+			Err(Invalid)
+		}
+	}
+
+
+	fn take(&mut self, size: usize) -> Result<&'a [u8], Invalid> {
+		if self.input.len() < size {
+			return Err(Invalid);
+		}
+
+		let (data, rest) = self.input.split_at(size);
+		self.input = rest;
+
+		Ok(data)
+	}
+
+
+	pub fn u8(&mut self) -> Result<u8, Invalid> {
+		Ok(self.take(1)?[0])
+	}
+
+
+	pub fn u32(&mut self) -> Result<u32, Invalid> {
+		let data = self.take(4)?;
+		Ok(u32::from_le_bytes(data.try_into().map_err(|_| Invalid)?))
+	}
+
+
+	pub fn u64(&mut self) -> Result<u64, Invalid> {
+		let data = self.take(8)?;
+		Ok(u64::from_le_bytes(data.try_into().map_err(|_| Invalid)?))
+	}
+
+
+	pub fn bytes(&mut self) -> Result<&'a [u8], Invalid> {
+		let size = self.u32()?;
+		self.take(size as usize)
+	}
+
+
+	pub fn symbol(&mut self) -> Result<Symbol, Invalid> {
+		let ix = self.u32()?;
+		self.symbols
+			.get(ix as usize)
+			.copied()
+			.ok_or(Invalid)
+	}
+
+
+	pub fn decode<T: Decode>(&mut self) -> Result<T, Invalid> {
+		T::decode(self)
+	}
+}
+
+
+/// Types which can be decoded from the cache format.
+pub(super) trait Decode: Sized {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid>;
+}
+
+
+impl Decode for bool {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(false),
+			1 => Ok(true),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for u8 {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		decoder.u8()
+	}
+}
+
+
+impl Decode for u32 {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		decoder.u32()
+	}
+}
+
+
+impl Decode for i32 {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(decoder.u32()? as i32)
+	}
+}
+
+
+impl Decode for i64 {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(decoder.u64()? as i64)
+	}
+}
+
+
+impl Decode for f64 {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(f64::from_bits(decoder.u64()?))
+	}
+}
+
+
+impl Decode for Symbol {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		decoder.symbol()
+	}
+}
+
+
+impl<T: Decode> Decode for Box<T> {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(Box::new(decoder.decode()?))
+	}
+}
+
+
+impl<T: Decode> Decode for Box<[T]> {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		let len = decoder.u32()?;
+		(0 .. len)
+			.map(|_| decoder.decode())
+			.collect()
+	}
+}
+
+
+impl<T: Decode> Decode for Option<T> {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(None),
+			1 => Ok(Some(decoder.decode()?)),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok((decoder.decode()?, decoder.decode()?))
+	}
+}
+
+
+impl Decode for SourcePos {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(
+			Self {
+				line: decoder.u32()?,
+				column: decoder.u32()?,
+				path: decoder.symbol()?,
+			}
+		)
+	}
+}
+
+
+impl Decode for SlotIx {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(Self(decoder.u32()?))
+	}
+}
+
+
+impl Decode for Capture {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(
+			Self {
+				from: decoder.decode()?,
+				to: decoder.decode()?,
+			}
+		)
+	}
+}
+
+
+impl Decode for FrameInfo {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(
+			Self {
+				slots: decoder.decode()?,
+				captures: decoder.decode()?,
+				self_slot: decoder.decode()?,
+				slot_names: decoder.decode()?,
+			}
+		)
+	}
+}
+
+
+impl Decode for ArgUnit {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(Self::Literal(decoder.decode()?)),
+			1 => Ok(
+				Self::Dollar {
+					slot_ix: decoder.decode()?,
+					symbol: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for ArgPart {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(Self::Unit(decoder.decode()?)),
+			1 => Ok(Self::Home(decoder.decode()?)),
+			2 => Ok(
+				Self::Range {
+					from: decoder.decode()?,
+					to: decoder.decode()?,
+					step: decoder.decode()?,
+				}
+			),
+			3 => Ok(Self::Collection(decoder.decode()?)),
+			4 => Ok(Self::Star),
+			5 => Ok(Self::Percent),
+			6 => Ok(Self::CharClass(decoder.decode()?)),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for Argument {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(
+			Self {
+				parts: decoder.decode()?,
+				pos: decoder.decode()?,
+			}
+		)
+	}
+}
+
+
+impl Decode for RedirectionTarget {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(Self::Fd(decoder.decode()?)),
+			1 => Ok(Self::Overwrite(decoder.decode()?)),
+			2 => Ok(Self::Append(decoder.decode()?)),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for Redirection {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(
+				Self::Output {
+					source: decoder.decode()?,
+					target: decoder.decode()?,
+				}
+			),
+			1 => Ok(
+				Self::Input {
+					literal: decoder.decode()?,
+					fd: decoder.decode()?,
+					source: decoder.decode()?,
+				}
+			),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for Builtin {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(Self::Alias),
+			1 => Ok(Self::Cd),
+			2 => Ok(Self::Exec),
+			3 => Ok(Self::Exec0),
+			4 => Ok(Self::Spawn0),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for BasicCommand {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(
+			Self {
+				program: decoder.decode()?,
+				env: decoder.decode()?,
+				arguments: decoder.decode()?,
+				redirections: decoder.decode()?,
+				abort_on_error: decoder.decode()?,
+				pos: decoder.decode()?,
+			}
+		)
+	}
+}
+
+
+impl Decode for Command {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(
+				Self::Builtin {
+					program: decoder.decode()?,
+					arguments: decoder.decode()?,
+					abort_on_error: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			1 => Ok(
+				Self::External {
+					head: decoder.decode()?,
+					tail: decoder.decode()?,
+				}
+			),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for BlockInput {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(
+			Self {
+				literal: decoder.decode()?,
+				source: decoder.decode()?,
+			}
+		)
+	}
+}
+
+
+impl Decode for CommandBlockKind {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(Self::Synchronous),
+			1 => Ok(Self::Asynchronous),
+			2 => Ok(Self::Capture),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for CommandBlock {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(
+			Self {
+				kind: decoder.decode()?,
+				head: decoder.decode()?,
+				tail: decoder.decode()?,
+				input: decoder.decode()?,
+			}
+		)
+	}
+}
+
+
+impl Decode for Block {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(Self(decoder.decode()?))
+	}
+}
+
+
+impl Decode for Literal {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(Self::Nil),
+			1 => Ok(Self::Bool(decoder.decode()?)),
+			2 => Ok(Self::Int(decoder.decode()?)),
+			3 => Ok(Self::Float(decoder.decode()?)),
+			4 => Ok(Self::Byte(decoder.decode()?)),
+			5 => Ok(Self::String(decoder.decode()?)),
+			6 => Ok(Self::Array(decoder.decode()?)),
+			7 => Ok(Self::Dict(decoder.decode()?)),
+			8 => Ok(
+				Self::Function {
+					params: decoder.decode()?,
+					frame_info: decoder.decode()?,
+					body: decoder.decode()?,
+					doc: decoder.decode()?,
+					name: decoder.decode()?,
+				}
+			),
+			9 => Ok(Self::Identifier(decoder.decode()?)),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for UnaryOp {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(Self::Minus),
+			1 => Ok(Self::Not),
+			2 => Ok(Self::Try),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for BinaryOp {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(Self::Plus),
+			1 => Ok(Self::Minus),
+			2 => Ok(Self::Times),
+			3 => Ok(Self::Div),
+			4 => Ok(Self::Mod),
+			5 => Ok(Self::Equals),
+			6 => Ok(Self::NotEquals),
+			7 => Ok(Self::Greater),
+			8 => Ok(Self::GreaterEquals),
+			9 => Ok(Self::Lower),
+			10 => Ok(Self::LowerEquals),
+			11 => Ok(Self::And),
+			12 => Ok(Self::Or),
+			13 => Ok(Self::Concat),
+			14 => Ok(Self::Coalesce),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for Expr {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(
+				Self::Identifier {
+					slot_ix: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			1 => Ok(
+				Self::Literal {
+					literal: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			2 => Ok(
+				Self::UnaryOp {
+					op: decoder.decode()?,
+					operand: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			3 => Ok(
+				Self::BinaryOp {
+					left: decoder.decode()?,
+					op: decoder.decode()?,
+					right: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			4 => Ok(
+				Self::If {
+					condition: decoder.decode()?,
+					condition_text: decoder.decode()?,
+					then: decoder.decode()?,
+					otherwise: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			5 => Ok(
+				Self::Access {
+					object: decoder.decode()?,
+					field: decoder.decode()?,
+					safe: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			6 => Ok(
+				Self::Call {
+					function: decoder.decode()?,
+					function_text: decoder.decode()?,
+					args: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			7 => Ok(
+				Self::CommandBlock {
+					block: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			8 => Ok(
+				Self::Interpolation {
+					parts: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for Lvalue {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(
+				Self::Identifier {
+					slot_ix: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			1 => Ok(
+				Self::Access {
+					object: decoder.decode()?,
+					field: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for Statement {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		match decoder.u8()? {
+			0 => Ok(
+				Self::Assign {
+					left: decoder.decode()?,
+					right: decoder.decode()?,
+				}
+			),
+			1 => Ok(Self::Return { expr: decoder.decode()? }),
+			2 => Ok(Self::Break),
+			3 => Ok(
+				Self::While {
+					condition: decoder.decode()?,
+					condition_text: decoder.decode()?,
+					block: decoder.decode()?,
+				}
+			),
+			4 => Ok(
+				Self::For {
+					slot_ix: decoder.decode()?,
+					expr: decoder.decode()?,
+					block: decoder.decode()?,
+				}
+			),
+			5 => Ok(Self::Expr(decoder.decode()?)),
+			6 => Ok(
+				Self::Declare {
+					slot_ix: decoder.decode()?,
+					identifier: decoder.decode()?,
+					pos: decoder.decode()?,
+				}
+			),
+			_ => Err(Invalid),
+		}
+	}
+}
+
+
+impl Decode for Program {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		Ok(
+			Self {
+				source: decoder.decode()?,
+				statements: decoder.decode()?,
+				root_slots: decoder.decode()?,
+				root_slot_names: decoder.decode()?,
+			}
+		)
+	}
+}
+
+
+impl Decode for Warning {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		let kind = match decoder.u8()? {
+			0 => WarningKind::ReservedIdentifier(decoder.decode()?),
+			1 => WarningKind::AttachedTry,
+			2 => WarningKind::DiscardedCapture,
+			_ => return Err(Invalid),
+		};
+
+		Ok(Self { kind, pos: decoder.decode()? })
+	}
+}
+
+
+impl Decode for Warnings {
+	fn decode(decoder: &mut Decoder) -> Result<Self, Invalid> {
+		let len = decoder.u32()?;
+		(0 .. len)
+			.map(|_| decoder.decode())
+			.collect::<Result<_, _>>()
+			.map(Self)
+	}
+}
@@ -0,0 +1,699 @@
+use std::collections::HashMap;
+
+use crate::{
+	semantic::{Warning, WarningKind, Warnings},
+	symbol::{self, Symbol},
+};
+use super::super::{
+	command::Builtin,
+	mem::{Capture, FrameInfo, SlotIx},
+	ArgPart,
+	ArgUnit,
+	Argument,
+	BasicCommand,
+	BinaryOp,
+	Block,
+	BlockInput,
+	Command,
+	CommandBlock,
+	CommandBlockKind,
+	Expr,
+	Literal,
+	Lvalue,
+	Program,
+	Redirection,
+	RedirectionTarget,
+	SourcePos,
+	Statement,
+	UnaryOp,
+};
+
+
+/// Binary encoder for programs.
+/// Symbols are collected in a table, which is written before the encoded data.
+pub(super) struct Encoder<'a> {
+	interner: &'a symbol::Interner,
+	symbols: HashMap<Symbol, u32>,
+	table: Vec<Symbol>,
+	output: Vec<u8>,
+}
+
+
+impl<'a> Encoder<'a> {
+	pub fn new(interner: &'a symbol::Interner) -> Self {
+		Self {
+			interner,
+			symbols: HashMap::new(),
+			table: Vec::new(),
+			output: Vec::new(),
+		}
+	}
+
+
+	/// Produce the symbol table followed by the encoded data.
+	pub fn finish(self) -> Vec<u8> {
+		let mut table = Encoder::new(self.interner);
+
+		table.u32(self.table.len() as u32);
+		for symbol in self.table {
+			let value = self.interner
+				.resolve(symbol)
+				.expect("failed to resolve symbol");
+			table.bytes(value);
+		}
+
+		let mut output = table.output;
+		output.extend(self.output);
+		output
+	}
+
+
+	pub fn u8(&mut self, value: u8) {
+		self.output.push(value);
+	}
+
+
+	pub fn u32(&mut self, value: u32) {
+		self.output.extend_from_slice(&value.to_le_bytes());
+	}
+
+
+	pub fn u64(&mut self, value: u64) {
+		self.output.extend_from_slice(&value.to_le_bytes());
+	}
+
+
+	pub fn bytes(&mut self, value: &[u8]) {
+		self.u32(value.len() as u32);
+		self.output.extend_from_slice(value);
+	}
+
+
+	pub fn symbol(&mut self, symbol: Symbol) {
+		let table = &mut self.table;
+		let ix = *self.symbols
+			.entry(symbol)
+			.or_insert_with(
+				|| {
+					table.push(symbol);
+					table.len() as u32 - 1
+				}
+			);
+
+		self.u32(ix);
+	}
+
+
+	pub fn encode<T: Encode + ?Sized>(&mut self, value: &T) {
+		value.encode(self)
+	}
+}
+
+
+/// Types which can be encoded in the cache format.
+pub(super) trait Encode {
+	fn encode(&self, encoder: &mut Encoder);
+}
+
+
+impl Encode for bool {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u8(*self as u8)
+	}
+}
+
+
+impl Encode for u8 {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u8(*self)
+	}
+}
+
+
+impl Encode for u32 {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u32(*self)
+	}
+}
+
+
+impl Encode for i32 {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u32(*self as u32)
+	}
+}
+
+
+impl Encode for i64 {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u64(*self as u64)
+	}
+}
+
+
+impl Encode for f64 {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u64(self.to_bits())
+	}
+}
+
+
+impl Encode for Symbol {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.symbol(*self)
+	}
+}
+
+
+impl<T: Encode> Encode for Box<T> {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.encode(self.as_ref())
+	}
+}
+
+
+impl<T: Encode> Encode for Box<[T]> {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u32(self.len() as u32);
+		for item in self.iter() {
+			encoder.encode(item);
+		}
+	}
+}
+
+
+impl<T: Encode> Encode for Option<T> {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			None => encoder.u8(0),
+			Some(value) => {
+				encoder.u8(1);
+				encoder.encode(value);
+			}
+		}
+	}
+}
+
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.encode(&self.0);
+		encoder.encode(&self.1);
+	}
+}
+
+
+impl Encode for SourcePos {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u32(self.line);
+		encoder.u32(self.column);
+		encoder.symbol(self.path);
+	}
+}
+
+
+impl Encode for SlotIx {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u32(self.0)
+	}
+}
+
+
+impl Encode for Capture {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.encode(&self.from);
+		encoder.encode(&self.to);
+	}
+}
+
+
+impl Encode for FrameInfo {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.encode(&self.slots);
+		encoder.encode(&self.captures);
+		encoder.encode(&self.self_slot);
+		encoder.encode(&self.slot_names);
+	}
+}
+
+
+impl Encode for ArgUnit {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Self::Literal(literal) => {
+				encoder.u8(0);
+				encoder.encode(literal);
+			}
+
+			Self::Dollar { slot_ix, symbol, pos } => {
+				encoder.u8(1);
+				encoder.encode(slot_ix);
+				encoder.encode(symbol);
+				encoder.encode(pos);
+			}
+		}
+	}
+}
+
+
+impl Encode for ArgPart {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Self::Unit(unit) => {
+				encoder.u8(0);
+				encoder.encode(unit);
+			}
+
+			Self::Home(user) => {
+				encoder.u8(1);
+				encoder.encode(user);
+			}
+
+			Self::Range { from, to, step } => {
+				encoder.u8(2);
+				encoder.encode(from);
+				encoder.encode(to);
+				encoder.encode(step);
+			}
+
+			Self::Collection(items) => {
+				encoder.u8(3);
+				encoder.encode(items);
+			}
+
+			Self::Star => encoder.u8(4),
+
+			Self::Percent => encoder.u8(5),
+
+			Self::CharClass(class) => {
+				encoder.u8(6);
+				encoder.encode(class);
+			}
+		}
+	}
+}
+
+
+impl Encode for Argument {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.encode(&self.parts);
+		encoder.encode(&self.pos);
+	}
+}
+
+
+impl Encode for RedirectionTarget {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Self::Fd(fd) => {
+				encoder.u8(0);
+				encoder.encode(fd);
+			}
+
+			Self::Overwrite(arg) => {
+				encoder.u8(1);
+				encoder.encode(arg);
+			}
+
+			Self::Append(arg) => {
+				encoder.u8(2);
+				encoder.encode(arg);
+			}
+		}
+	}
+}
+
+
+impl Encode for Redirection {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Self::Output { source, target } => {
+				encoder.u8(0);
+				encoder.encode(source);
+				encoder.encode(target);
+			}
+
+			Self::Input { literal, fd, source } => {
+				encoder.u8(1);
+				encoder.encode(literal);
+				encoder.encode(fd);
+				encoder.encode(source);
+			}
+		}
+	}
+}
+
+
+impl Encode for Builtin {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u8(
+			match self {
+				Self::Alias => 0,
+				Self::Cd => 1,
+				Self::Exec => 2,
+				Self::Exec0 => 3,
+				Self::Spawn0 => 4,
+			}
+		)
+	}
+}
+
+
+impl Encode for BasicCommand {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.encode(&self.program);
+		encoder.encode(&self.env);
+		encoder.encode(&self.arguments);
+		encoder.encode(&self.redirections);
+		encoder.encode(&self.abort_on_error);
+		encoder.encode(&self.pos);
+	}
+}
+
+
+impl Encode for Command {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Self::Builtin { program, arguments, abort_on_error, pos } => {
+				encoder.u8(0);
+				encoder.encode(program);
+				encoder.encode(arguments);
+				encoder.encode(abort_on_error);
+				encoder.encode(pos);
+			}
+
+			Self::External { head, tail } => {
+				encoder.u8(1);
+				encoder.encode(head);
+				encoder.encode(tail);
+			}
+		}
+	}
+}
+
+
+impl Encode for BlockInput {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.encode(&self.literal);
+		encoder.encode(&self.source);
+	}
+}
+
+
+impl Encode for CommandBlockKind {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u8(
+			match self {
+				Self::Synchronous => 0,
+				Self::Asynchronous => 1,
+				Self::Capture => 2,
+			}
+		)
+	}
+}
+
+
+impl Encode for CommandBlock {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.encode(&self.kind);
+		encoder.encode(&self.head);
+		encoder.encode(&self.tail);
+		encoder.encode(&self.input);
+	}
+}
+
+
+impl Encode for Block {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.encode(&self.0)
+	}
+}
+
+
+impl Encode for Literal {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Self::Nil => encoder.u8(0),
+
+			Self::Bool(value) => {
+				encoder.u8(1);
+				encoder.encode(value);
+			}
+
+			Self::Int(value) => {
+				encoder.u8(2);
+				encoder.encode(value);
+			}
+
+			Self::Float(value) => {
+				encoder.u8(3);
+				encoder.encode(value);
+			}
+
+			Self::Byte(value) => {
+				encoder.u8(4);
+				encoder.encode(value);
+			}
+
+			Self::String(value) => {
+				encoder.u8(5);
+				encoder.encode(value);
+			}
+
+			Self::Array(items) => {
+				encoder.u8(6);
+				encoder.encode(items);
+			}
+
+			Self::Dict(items) => {
+				encoder.u8(7);
+				encoder.encode(items);
+			}
+
+			Self::Function { params, frame_info, body, doc, name } => {
+				encoder.u8(8);
+				encoder.encode(params);
+				encoder.encode(frame_info);
+				encoder.encode(body);
+				encoder.encode(doc);
+				encoder.encode(name);
+			}
+
+			Self::Identifier(symbol) => {
+				encoder.u8(9);
+				encoder.encode(symbol);
+			}
+		}
+	}
+}
+
+
+impl Encode for UnaryOp {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u8(
+			match self {
+				Self::Minus => 0,
+				Self::Not => 1,
+				Self::Try => 2,
+			}
+		)
+	}
+}
+
+
+impl Encode for BinaryOp {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u8(
+			match self {
+				Self::Plus => 0,
+				Self::Minus => 1,
+				Self::Times => 2,
+				Self::Div => 3,
+				Self::Mod => 4,
+				Self::Equals => 5,
+				Self::NotEquals => 6,
+				Self::Greater => 7,
+				Self::GreaterEquals => 8,
+				Self::Lower => 9,
+				Self::LowerEquals => 10,
+				Self::And => 11,
+				Self::Or => 12,
+				Self::Concat => 13,
+				Self::Coalesce => 14,
+			}
+		)
+	}
+}
+
+
+impl Encode for Expr {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Self::Identifier { slot_ix, pos } => {
+				encoder.u8(0);
+				encoder.encode(slot_ix);
+				encoder.encode(pos);
+			}
+
+			Self::Literal { literal, pos } => {
+				encoder.u8(1);
+				encoder.encode(literal);
+				encoder.encode(pos);
+			}
+
+			Self::UnaryOp { op, operand, pos } => {
+				encoder.u8(2);
+				encoder.encode(op);
+				encoder.encode(operand);
+				encoder.encode(pos);
+			}
+
+			Self::BinaryOp { left, op, right, pos } => {
+				encoder.u8(3);
+				encoder.encode(left);
+				encoder.encode(op);
+				encoder.encode(right);
+				encoder.encode(pos);
+			}
+
+			Self::If { condition, condition_text, then, otherwise, pos } => {
+				encoder.u8(4);
+				encoder.encode(condition);
+				encoder.encode(condition_text);
+				encoder.encode(then);
+				encoder.encode(otherwise);
+				encoder.encode(pos);
+			}
+
+			Self::Access { object, field, safe, pos } => {
+				encoder.u8(5);
+				encoder.encode(object);
+				encoder.encode(field);
+				encoder.encode(safe);
+				encoder.encode(pos);
+			}
+
+			Self::Call { function, function_text, args, pos } => {
+				encoder.u8(6);
+				encoder.encode(function);
+				encoder.encode(function_text);
+				encoder.encode(args);
+				encoder.encode(pos);
+			}
+
+			Self::CommandBlock { block, pos } => {
+				encoder.u8(7);
+				encoder.encode(block);
+				encoder.encode(pos);
+			}
+
+			Self::Interpolation { parts, pos } => {
+				encoder.u8(8);
+				encoder.encode(parts);
+				encoder.encode(pos);
+			}
+		}
+	}
+}
+
+
+impl Encode for Lvalue {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Self::Identifier { slot_ix, pos } => {
+				encoder.u8(0);
+				encoder.encode(slot_ix);
+				encoder.encode(pos);
+			}
+
+			Self::Access { object, field, pos } => {
+				encoder.u8(1);
+				encoder.encode(object);
+				encoder.encode(field);
+				encoder.encode(pos);
+			}
+		}
+	}
+}
+
+
+impl Encode for Statement {
+	fn encode(&self, encoder: &mut Encoder) {
+		match self {
+			Self::Assign { left, right } => {
+				encoder.u8(0);
+				encoder.encode(left);
+				encoder.encode(right);
+			}
+
+			Self::Return { expr } => {
+				encoder.u8(1);
+				encoder.encode(expr);
+			}
+
+			Self::Break => encoder.u8(2),
+
+			Self::While { condition, condition_text, block } => {
+				encoder.u8(3);
+				encoder.encode(condition);
+				encoder.encode(condition_text);
+				encoder.encode(block);
+			}
+
+			Self::For { slot_ix, expr, block } => {
+				encoder.u8(4);
+				encoder.encode(slot_ix);
+				encoder.encode(expr);
+				encoder.encode(block);
+			}
+
+			Self::Expr(expr) => {
+				encoder.u8(5);
+				encoder.encode(expr);
+			}
+
+			Self::Declare { slot_ix, identifier, pos } => {
+				encoder.u8(6);
+				encoder.encode(slot_ix);
+				encoder.encode(identifier);
+				encoder.encode(pos);
+			}
+		}
+	}
+}
+
+
+impl Encode for Program {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.encode(&self.source);
+		encoder.encode(&self.statements);
+		encoder.encode(&self.root_slots);
+		encoder.encode(&self.root_slot_names);
+	}
+}
+
+
+impl Encode for Warning {
+	fn encode(&self, encoder: &mut Encoder) {
+		match &self.kind {
+			WarningKind::ReservedIdentifier(symbol) => {
+				encoder.u8(0);
+				encoder.encode(symbol);
+			}
+			WarningKind::AttachedTry => encoder.u8(1),
+			WarningKind::DiscardedCapture => encoder.u8(2),
+		}
+
+		encoder.encode(&self.pos);
+	}
+}
+
+
+impl Encode for Warnings {
+	fn encode(&self, encoder: &mut Encoder) {
+		encoder.u32(self.0.len() as u32);
+		for warning in self.0.iter() {
+			encoder.encode(warning);
+		}
+	}
+}
@@ -0,0 +1,159 @@
+mod decode;
+mod encode;
+
+use std::{
+	fs,
+	io::{self, Write},
+	path::PathBuf,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{semantic::Warnings, symbol, syntax::Source};
+use super::Program;
+use decode::{Decoder, Decode};
+use encode::Encoder;
+
+
+/// Magic bytes at the start of every cache file.
+const MAGIC: &[u8] = b"HSHC";
+
+/// Version of the cache format. Must be bumped whenever the program representation
+/// changes.
+const FORMAT_VERSION: u32 = 18;
+
+/// Version of the interpreter. Caches from other versions are considered stale.
+const INTERPRETER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+
+/// Load the cached program for the given source, along with the warnings of its analysis,
+/// if present and fresh. Missing, stale or malformed caches are ignored.
+pub fn load(source: &Source, interner: &mut symbol::Interner) -> Option<(Program, Warnings)> {
+	let data = fs::read(path(source, interner)?).ok()?;
+	decode(source, &data, interner)
+}
+
+
+/// Store the program in the cache for the given source, along with the warnings of its
+/// analysis, so that they are reported on cached runs as well.
+pub fn store(
+	source: &Source,
+	program: &Program,
+	warnings: &Warnings,
+	interner: &symbol::Interner,
+) -> io::Result<()> {
+	let path = path(source, interner)
+		.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no cache directory"))?;
+
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+
+	// Write to a temporary file and rename, so that concurrent runs never observe a
+	// partially written cache.
+	let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+
+	let result = fs::File::create(&temp_path)
+		.and_then(|mut file| file.write_all(&encode(source, program, warnings, interner)))
+		.and_then(|_| fs::rename(&temp_path, &path));
+
+	if result.is_err() {
+		let _ = fs::remove_file(&temp_path);
+	}
+
+	result
+}
+
+
+/// Encode the program of the given source and its warnings, including the header.
+pub(in crate::semantic) fn encode(
+	source: &Source,
+	program: &Program,
+	warnings: &Warnings,
+	interner: &symbol::Interner,
+) -> Vec<u8> {
+	let mut encoder = Encoder::new(interner);
+	encoder.encode(program);
+	encoder.encode(warnings);
+
+	let mut output = header(source, interner);
+	output.extend(encoder.finish());
+	output
+}
+
+
+/// Decode the program of the given source and its warnings, checking the header.
+/// Programs cached for a different source are rejected.
+pub(in crate::semantic) fn decode(
+	source: &Source,
+	data: &[u8],
+	interner: &mut symbol::Interner,
+) -> Option<(Program, Warnings)> {
+	let data = data.strip_prefix(header(source, interner).as_slice())?;
+
+	let mut decoder = Decoder::new(data, interner).ok()?;
+	let program = Program::decode(&mut decoder).ok()?;
+	let warnings = Warnings::decode(&mut decoder).ok()?;
+	decoder.finish().ok()?;
+
+	Some((program, warnings))
+}
+
+
+/// The cache header, identifying the format and interpreter versions, and the source the
+/// program was compiled from.
+fn header(source: &Source, interner: &symbol::Interner) -> Vec<u8> {
+	let mut header = Vec::from(MAGIC);
+	header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+	header.extend_from_slice(&(INTERPRETER_VERSION.len() as u32).to_le_bytes());
+	header.extend_from_slice(INTERPRETER_VERSION.as_bytes());
+	header.extend_from_slice(&digest(source, interner));
+	header
+}
+
+
+/// A SHA-256 digest of the source path and contents, which ensures that a cached program
+/// was compiled from the given source.
+fn digest(source: &Source, interner: &symbol::Interner) -> [u8; 32] {
+	let path = interner
+		.resolve(source.path)
+		.unwrap_or_default();
+
+	let mut hasher = Sha256::new();
+	hasher.update((path.len() as u64).to_le_bytes());
+	hasher.update(path);
+	hasher.update(&source.contents);
+
+	hasher.finalize().into()
+}
+
+
+/// The cache file path for the given source.
+/// The file name is a hash of the source path only, so that each script has a single cache
+/// entry, which is replaced whenever the script changes.
+fn path(source: &Source, interner: &symbol::Interner) -> Option<PathBuf> {
+	let mut path = dir()?;
+
+	let hash = Sha256::digest(interner.resolve(source.path)?);
+
+	path.push(format!("{}.hshc", hex::encode(hash)));
+
+	Some(path)
+}
+
+
+/// The cache directory, respecting `XDG_CACHE_HOME`.
+fn dir() -> Option<PathBuf> {
+	let mut dir = match std::env::var_os("XDG_CACHE_HOME") {
+		Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+		_ => {
+			let mut home = PathBuf::from(std::env::var_os("HOME")?);
+			home.push(".cache");
+			home
+		}
+	};
+
+	dir.push("hush");
+
+	Some(dir)
+}
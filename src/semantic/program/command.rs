@@ -1,7 +1,7 @@
 use std::convert::TryFrom;
 
-use crate::io::FileDescriptor;
-use super::{ast, mem, SourcePos};
+use crate::{io::FileDescriptor, symbol::Symbol};
+use super::{ast, mem, Expr, SourcePos};
 
 
 /// The most basic part of an argument.
@@ -10,6 +10,8 @@ pub enum ArgUnit {
 	Literal(Box<[u8]>),
 	Dollar {
 		slot_ix: mem::SlotIx,
+		/// The variable's name, for error messages.
+		symbol: Symbol,
 		pos: SourcePos,
 	}
 }
@@ -21,9 +23,13 @@ pub enum ArgPart {
 	Unit(ArgUnit),
 
 	// Literal expansions:
-	Home, // ~/
-	Range(i64, i64), // {x..y}
-	Collection(Box<[ArgUnit]>), // {a,b,c}
+	Home(Box<[u8]>), // ~, ~user, ~+, ~-
+	Range { // {x..y}, {x..y..step}
+		from: ArgUnit,
+		to: ArgUnit,
+		step: Option<ArgUnit>,
+	},
+	Collection(Box<[Box<[ArgPart]>]>), // {a,b,c}
 
 	// File expansions:
 	Star, // *
@@ -64,6 +70,8 @@ pub enum Redirection {
 	Input {
 		/// Whether the source is the input or the file path.
 		literal: bool,
+		/// The file descriptor to be redirected, which is stdin by default.
+		fd: FileDescriptor,
 		source: Argument,
 	},
 }
@@ -144,12 +152,23 @@ pub enum Command {
 }
 
 
+/// Block-level input redirection.
+#[derive(Debug)]
+pub struct BlockInput {
+	/// Whether the source is the input itself (<<) or a file path (<).
+	pub literal: bool,
+	pub source: Box<Expr>,
+}
+
+
 /// A command block.
 #[derive(Debug)]
 pub struct CommandBlock {
 	pub kind: CommandBlockKind,
 	pub head: Command,
 	pub tail: Box<[Command]>,
+	/// Standard input for the whole block, if redirected.
+	pub input: Option<BlockInput>,
 }
 
 
@@ -24,6 +24,7 @@ use super::{
 };
 use crate::{
 	fmt::{self, Display, Indentation},
+	io,
 	symbol,
 	term::color
 };
@@ -149,7 +150,7 @@ impl<'a> Display<'a> for Literal {
 				"]".fmt(f)
 			},
 
-			Self::Function { params, frame_info, body } => {
+			Self::Function { params, frame_info, body, .. } => {
 				let step = if context.indentation.is_some() { "\n" } else { " " };
 
 				Keyword::Function.fmt(f)?;
@@ -208,6 +209,7 @@ impl std::fmt::Display for BinaryOp {
 			Self::And => Operator::And.fmt(f),
 			Self::Or => Operator::Or.fmt(f),
 			Self::Concat => Operator::Concat.fmt(f),
+			Self::Coalesce => Operator::Coalesce.fmt(f),
 		}
 	}
 }
@@ -248,6 +250,26 @@ impl<'a> Display<'a> for Expr {
 				")".fmt(f)
 			}
 
+			Self::Interpolation { parts, .. } => {
+				"\"".fmt(f)?;
+
+				for part in parts.iter() {
+					match part {
+						Self::Literal { literal: Literal::String(s), .. } => {
+							color::Bold(String::from_utf8_lossy(s).escape_debug()).fmt(f)?;
+						}
+
+						expr => {
+							"${".fmt(f)?;
+							expr.fmt(f, context.inlined())?;
+							"}".fmt(f)?;
+						}
+					}
+				}
+
+				"\"".fmt(f)
+			}
+
 			Self::If { condition, then, otherwise, .. } => {
 				let step = if context.indentation.is_some() { "\n" } else { " " };
 
@@ -286,16 +308,16 @@ impl<'a> Display<'a> for Expr {
 				Keyword::End.fmt(f)
 			}
 
-			Self::Access { object, field, .. }
+			Self::Access { object, field, safe, .. }
 			if matches!(field.as_ref(), Self::Literal { literal: Literal::Identifier(..), .. }) => {
 				object.fmt(f, context.inlined())?;
-				".".fmt(f)?;
+				(if *safe { "?." } else { "." }).fmt(f)?;
 				field.fmt(f, context.inlined())
 			}
 
-			Self::Access { object, field, .. } => {
+			Self::Access { object, field, safe, .. } => {
 				object.fmt(f, context.inlined())?;
-				"[".fmt(f)?;
+				(if *safe { "?[" } else { "[" }).fmt(f)?;
 				field.fmt(f, context.inlined())?;
 				"]".fmt(f)
 			}
@@ -343,9 +365,18 @@ impl<'a> Display<'a> for Statement {
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
 		match self {
+			Self::Declare { slot_ix, .. } => {
+				Keyword::Let.fmt(f)?;
+				" ".fmt(f)?;
+				slot_ix.fmt(f)
+			}
+
 			Self::Assign { left, right } => {
-				left.fmt(f, context.inlined())?;
-				" = ".fmt(f)?;
+				for left in left.iter() {
+					left.fmt(f, context.inlined())?;
+					" = ".fmt(f)?;
+				}
+
 				right.fmt(f, context)
 			}
 
@@ -357,7 +388,7 @@ impl<'a> Display<'a> for Statement {
 
 			Self::Break => Keyword::Break.fmt(f),
 
-			Self::While { condition, block } => {
+			Self::While { condition, block, .. } => {
 				let step = if context.indentation.is_some() { "\n" } else { " " };
 
 				Keyword::While.fmt(f)?;
@@ -431,12 +462,19 @@ impl std::fmt::Display for ArgPart {
 		match self {
 			Self::Unit(unit) => unit.fmt(f),
 
-			Self::Home => color::Fg(color::Yellow, "~/").fmt(f),
-			Self::Range(start, end) => {
+			Self::Home(user) => color::Fg(
+				color::Yellow,
+				format!("~{}", String::from_utf8_lossy(user))
+			).fmt(f),
+			Self::Range { from, to, step } => {
 				color::Fg(color::Yellow, "{").fmt(f)?;
-				start.fmt(f)?;
+				from.fmt(f)?;
 				color::Fg(color::Yellow, "..").fmt(f)?;
-				end.fmt(f)?;
+				to.fmt(f)?;
+				if let Some(step) = step {
+					color::Fg(color::Yellow, "..").fmt(f)?;
+					step.fmt(f)?;
+				}
 				color::Fg(color::Yellow, "}").fmt(f)
 			},
 			Self::Collection(items) => {
@@ -445,7 +483,12 @@ impl std::fmt::Display for ArgPart {
 				fmt::sep_by(
 					items.iter(),
 					f,
-					|item, f| item.fmt(f),
+					|item, f| {
+						for part in item.iter() {
+							part.fmt(f)?;
+						}
+						Ok(())
+					},
 					color::Fg(color::Yellow, ",")
 				)?;
 
@@ -511,12 +554,18 @@ impl std::fmt::Display for Redirection {
 				target.fmt(f)
 			}
 
-			Self::Input { literal: false, source } => {
+			Self::Input { literal: false, fd, source } => {
+				if *fd != io::stdin_fd() {
+					fd.fmt(f)?;
+				}
 				"<".fmt(f)?;
 				source.fmt(f)
 			}
 
-			Self::Input { literal: true, source } => {
+			Self::Input { literal: true, fd, source } => {
+				if *fd != io::stdin_fd() {
+					fd.fmt(f)?;
+				}
 				"<<".fmt(f)?;
 				source.fmt(f)
 			}
@@ -570,7 +619,7 @@ impl std::fmt::Display for BasicCommand {
 
 		if !self.abort_on_error {
 			" ".fmt(f)?;
-			CommandOperator::Try.fmt(f)?;
+			CommandOperator::Try { attached: false }.fmt(f)?;
 		}
 
 		Ok(())
@@ -591,7 +640,7 @@ impl std::fmt::Display for Command {
 
 				if *abort_on_error {
 					" ".fmt(f)?;
-					CommandOperator::Try.fmt(f)?;
+					CommandOperator::Try { attached: false }.fmt(f)?;
 				}
 			},
 
@@ -643,7 +692,16 @@ impl<'a> Display<'a> for CommandBlock {
 
 		step(f, context)?;
 
-		"}".fmt(f)
+		"}".fmt(f)?;
+
+		if let Some(input) = &self.input {
+			" ".fmt(f)?;
+			CommandOperator::Input { literal: input.literal }.fmt(f)?;
+			" ".fmt(f)?;
+			input.source.fmt(f, context)?;
+		}
+
+		Ok(())
 	}
 }
 
@@ -665,6 +723,7 @@ impl<'a> Display<'a> for Program {
 			slots: self.root_slots,
 			captures: Box::default(),
 			self_slot: None,
+			slot_names: self.root_slot_names.clone(),
 		};
 
 		root_frame.fmt(f, context.indentation)?;
@@ -1,6 +1,6 @@
 mod fmt;
 
-use super::lexer;
+use super::{lexer, Symbol};
 
 
 /// The index of a memory slot in the activation record.
@@ -36,4 +36,7 @@ pub struct FrameInfo {
 	pub captures: Box<[Capture]>,
 	/// Where to insert `self`.
 	pub self_slot: Option<SlotIx>,
+	/// The name of the variable in each slot, for debuggers. Captured variables are named
+	/// with an `@closed` suffix, and the slot of `self` has no name.
+	pub slot_names: Box<[Option<Symbol>]>,
 }
@@ -1,3 +1,4 @@
+pub mod cache;
 pub mod command;
 pub mod fmt;
 pub mod mem;
@@ -12,6 +13,7 @@ pub use command::{
 	ArgUnit,
 	Argument,
 	BasicCommand,
+	BlockInput,
 	Command,
 	CommandBlock,
 	CommandBlockKind,
@@ -49,6 +51,10 @@ pub enum Literal {
 		params: u32,
 		frame_info: mem::FrameInfo,
 		body: Block,
+		/// The doc comment preceding the function, if any.
+		doc: Option<Symbol>,
+		/// The name of the variable the function is declared as, if any.
+		name: Option<Symbol>,
 	},
 	/// For the dot access operator, we want to be able to have identifiers as literal
 	/// strings instead of names for variables. This variant should only be used in such
@@ -106,6 +112,9 @@ pub enum BinaryOp {
 	Or,  // or
 
 	Concat, // ++
+
+	/// Nil-coalescing: the left operand, or the right one if the left is nil.
+	Coalesce, // ??
 }
 
 
@@ -126,6 +135,7 @@ impl From<ast::BinaryOp> for BinaryOp {
 			ast::BinaryOp::And => BinaryOp::And,
 			ast::BinaryOp::Or => BinaryOp::Or,
 			ast::BinaryOp::Concat => BinaryOp::Concat,
+			ast::BinaryOp::Coalesce => BinaryOp::Coalesce,
 		}
 	}
 }
@@ -155,9 +165,17 @@ pub enum Expr {
 		right: Box<Expr>,
 		pos: SourcePos,
 	},
+	/// String literal with interpolations. The parts are converted to strings and
+	/// concatenated.
+	Interpolation {
+		parts: Box<[Expr]>,
+		pos: SourcePos,
+	},
 	/// If-else expression.
 	If {
 		condition: Box<Expr>,
+		/// Source text of the condition, for error messages.
+		condition_text: Symbol,
 		then: Block,
 		otherwise: Block,
 		pos: SourcePos,
@@ -166,11 +184,15 @@ pub enum Expr {
 	Access {
 		object: Box<Expr>,
 		field: Box<Expr>,
+		/// Safe access (?. and ?[]), which yields nil for nil objects.
+		safe: bool,
 		pos: SourcePos,
 	},
 	/// Function call (()) operator.
 	Call {
 		function: Box<Expr>,
+		/// Source text of the function expression, for error messages.
+		function_text: Symbol,
 		args: Box<[Expr]>,
 		pos: SourcePos,
 	},
@@ -181,6 +203,24 @@ pub enum Expr {
 }
 
 
+impl Expr {
+	/// The position of the expression.
+	pub fn pos(&self) -> SourcePos {
+		match self {
+			Self::Identifier { pos, .. }
+			| Self::Literal { pos, .. }
+			| Self::UnaryOp { pos, .. }
+			| Self::BinaryOp { pos, .. }
+			| Self::Interpolation { pos, .. }
+			| Self::If { pos, .. }
+			| Self::Access { pos, .. }
+			| Self::Call { pos, .. }
+			| Self::CommandBlock { pos, .. } => *pos,
+		}
+	}
+}
+
+
 /// L-value expressions.
 #[derive(Debug)]
 pub enum Lvalue {
@@ -199,11 +239,29 @@ pub enum Lvalue {
 }
 
 
+impl Lvalue {
+	/// The position of the l-value.
+	pub fn pos(&self) -> SourcePos {
+		match self {
+			Self::Identifier { pos, .. } | Self::Access { pos, .. } => *pos,
+		}
+	}
+}
+
+
 /// Statements of all kinds in the language.
 #[derive(Debug)]
 pub enum Statement {
+	/// Declaration of a variable without a value. The variable is nil until assigned.
+	Declare {
+		slot_ix: mem::SlotIx,
+		/// The variable's name, for error messages.
+		identifier: Symbol,
+		pos: SourcePos,
+	},
+	/// Assignment of the value to all targets, from right to left.
 	Assign {
-		left: Lvalue,
+		left: Box<[Lvalue]>,
 		right: Expr,
 	},
 	Return {
@@ -213,6 +271,8 @@ pub enum Statement {
 	/// While loop.
 	While {
 		condition: Expr,
+		/// Source text of the condition, for error messages.
+		condition_text: Symbol,
 		block: Block,
 	},
 	/// For loop. Also introduces an identifier.
@@ -225,6 +285,22 @@ pub enum Statement {
 }
 
 
+impl Statement {
+	/// The position of the statement, for debuggers. Breaks have no position.
+	pub fn pos(&self) -> Option<SourcePos> {
+		match self {
+			Self::Declare { pos, .. } => Some(*pos),
+			Self::Assign { left, .. } => left.first().map(Lvalue::pos),
+			Self::Return { expr } => Some(expr.pos()),
+			Self::Break => None,
+			Self::While { condition, .. } => Some(condition.pos()),
+			Self::For { expr, .. } => Some(expr.pos()),
+			Self::Expr(expr) => Some(expr.pos()),
+		}
+	}
+}
+
+
 /// A statically correct (syntactically and semantically) Hush program.
 #[derive(Debug)]
 pub struct Program {
@@ -234,4 +310,6 @@ pub struct Program {
 	pub statements: Block,
 	/// How many slots in the root scope.
 	pub root_slots: mem::SlotIx,
+	/// The name of the variable in each slot of the root scope, for debuggers.
+	pub root_slot_names: Box<[Option<Symbol>]>,
 }
@@ -49,6 +49,8 @@ struct Frame {
 	captures: Vec<Capture>,
 	/// The slot index of `self`.
 	self_slot: Option<SlotIx>,
+	/// The name of the variable in each slot.
+	slot_names: Vec<Option<Symbol>>,
 	/// Stack of scopes in the frame.
 	scopes: Vec<Scope>,
 }
@@ -61,6 +63,7 @@ impl Frame {
 			slots: SlotIx(0),
 			captures: Vec::new(),
 			self_slot: None,
+			slot_names: Vec::new(),
 			scopes: Vec::new(),
 		}
 	}
@@ -87,6 +90,7 @@ impl Frame {
 		let scope = self.scopes.last_mut().expect("attempt to declare in empty stack");
 
 		if scope.declare(symbol, self.slots) {
+			self.slot_names.push(Some(symbol));
 			Ok(self.slots.bump())
 		} else {
 			Err(Error::duplicate_variable(symbol, pos))
@@ -115,6 +119,7 @@ impl Frame {
 
 			Entry::Vacant(entry) => {
 				let slot_ix = self.slots.bump();
+				self.slot_names.push(Some(symbol));
 				entry.insert(slot_ix);
 				self.captures.push(
 					Capture {
@@ -136,6 +141,7 @@ impl Frame {
 
 			None => {
 				let slot_ix = self.slots.bump();
+				self.slot_names.push(None);
 				self.self_slot = Some(slot_ix);
 				slot_ix
 			}
@@ -157,6 +163,7 @@ impl From<Frame> for FrameInfo {
 			slots: frame.slots,
 			captures: std::mem::take(&mut frame.captures).into(),
 			self_slot: frame.self_slot,
+			slot_names: std::mem::take(&mut frame.slot_names).into(),
 		}
 	}
 }
@@ -5,7 +5,7 @@ use std::{
 };
 
 use crate::{fmt, semantic::ErrorsDisplayContext, symbol, syntax::{self, AnalysisDisplayContext}, tests};
-use super::{program, Analyzer, Program, Errors};
+use super::{program, warning::WarningKind, Analyzer, Program, Errors, Warnings, WarningsDisplayContext};
 
 
 fn test_dir<P, F>(path: P, mut check: F) -> io::Result<()>
@@ -30,6 +30,7 @@ where
 						AnalysisDisplayContext {
 							max_errors: None,
 							interner: &interner,
+							format: fmt::ErrorFormat::Human,
 						}
 					)
 				);
@@ -54,6 +55,7 @@ where
 							ErrorsDisplayContext {
 								max_errors: None,
 								interner: &interner,
+								format: fmt::ErrorFormat::Human,
 							}
 						)
 					),
@@ -91,3 +93,163 @@ fn test_negative() -> io::Result<()> {
 		Result::is_err,
 	)
 }
+
+
+#[test]
+fn test_cache_roundtrip() -> io::Result<()> {
+	let mut interner = symbol::Interner::new();
+
+	tests::util::test_dir(
+		"src/semantic/tests/data/positive",
+		move |path, file| {
+			let path_symbol = interner.get_or_intern(path.as_os_str().as_bytes());
+			let source = syntax::Source::from_reader(path_symbol, file)?;
+			let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+			let (result, warnings) = Analyzer::analyze_with_warnings(syntactic_analysis.ast, &mut interner);
+			let program = result.expect("positive test should be valid");
+
+			let show = |program: &Program, interner: &symbol::Interner| fmt::Show(
+				program,
+				program::fmt::Context::from(interner),
+			).to_string();
+
+			let show_warnings = |warnings: &Warnings, interner: &symbol::Interner| fmt::Show(
+				warnings,
+				WarningsDisplayContext { max_warnings: None, interner, format: fmt::ErrorFormat::Human },
+			).to_string();
+
+			let data = program::cache::encode(&source, &program, &warnings, &interner);
+			let (decoded, decoded_warnings) = program::cache::decode(&source, &data, &mut interner)
+				.expect("failed to decode cached program");
+
+			assert_eq!(show(&program, &interner), show(&decoded, &interner));
+			assert_eq!(show_warnings(&warnings, &interner), show_warnings(&decoded_warnings, &interner));
+
+			// Truncated caches must be rejected.
+			assert!(program::cache::decode(&source, &data[.. data.len() - 1], &mut interner).is_none());
+
+			// Programs cached for a different source must be rejected.
+			let edited = syntax::Source {
+				path: source.path,
+				contents: [ &source.contents[..], b"\n" ].concat().into(),
+			};
+			assert!(program::cache::decode(&edited, &data, &mut interner).is_none());
+
+			Ok(())
+		}
+	)
+}
+
+
+#[test]
+fn test_reserved_warnings() -> io::Result<()> {
+	let mut interner = symbol::Interner::new();
+	let path = Path::new("src/semantic/tests/data/positive/reserved.hsh");
+	let path_symbol = interner.get_or_intern(path.as_os_str().as_bytes());
+	let source = syntax::Source::from_reader(path_symbol, std::fs::File::open(path)?)?;
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+
+	let (result, warnings) = Analyzer::analyze_with_warnings(syntactic_analysis.ast, &mut interner);
+
+	assert!(result.is_ok());
+
+	let names: Vec<&[u8]> = warnings.0
+		.iter()
+		.filter_map(
+			|warning| match warning.kind {
+				WarningKind::ReservedIdentifier(symbol) => interner.resolve(symbol),
+				_ => None,
+			}
+		)
+		.collect();
+
+	assert_eq!(names.len(), warnings.0.len(), "unexpected warnings");
+
+	assert_eq!(names, [&b"match"[..], b"defer", b"continue", b"continue"]);
+
+	Ok(())
+}
+
+
+#[test]
+fn test_json_warnings() -> io::Result<()> {
+	let mut interner = symbol::Interner::new();
+	let path = Path::new("src/semantic/tests/data/positive/attached-try.hsh");
+	let path_symbol = interner.get_or_intern(path.as_os_str().as_bytes());
+	let source = syntax::Source::from_reader(path_symbol, std::fs::File::open(path)?)?;
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+
+	let (_, warnings) = Analyzer::analyze_with_warnings(syntactic_analysis.ast, &mut interner);
+
+	let output = fmt::Show(
+		&warnings,
+		WarningsDisplayContext { max_warnings: Some(1), interner: &interner, format: fmt::ErrorFormat::Json },
+	).to_string();
+
+	// All warnings are reported, one JSON object per line.
+	let lines: Vec<serde_json::Value> = output
+		.lines()
+		.map(|line| serde_json::from_str(line).expect("invalid JSON warning"))
+		.collect();
+
+	assert_eq!(lines.len(), 2);
+
+	for (line, expected) in lines.iter().zip([4, 5]) {
+		assert_eq!(line["severity"], "warning");
+		assert_eq!(line["kind"], "attached_try");
+		assert_eq!(line["line"], expected);
+		assert_eq!(line["file"], path.to_str().expect("non UTF-8 path"));
+	}
+
+	Ok(())
+}
+
+
+#[test]
+fn test_attached_try_warnings() -> io::Result<()> {
+	let mut interner = symbol::Interner::new();
+	let path = Path::new("src/semantic/tests/data/positive/attached-try.hsh");
+	let path_symbol = interner.get_or_intern(path.as_os_str().as_bytes());
+	let source = syntax::Source::from_reader(path_symbol, std::fs::File::open(path)?)?;
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+
+	let (result, warnings) = Analyzer::analyze_with_warnings(syntactic_analysis.ast, &mut interner);
+
+	assert!(result.is_ok());
+
+	let lines: Vec<u32> = warnings.0
+		.iter()
+		.filter(|warning| matches!(warning.kind, WarningKind::AttachedTry))
+		.map(|warning| warning.pos.line)
+		.collect();
+
+	assert_eq!(lines, [4, 5]);
+	assert_eq!(lines.len(), warnings.0.len(), "unexpected warnings");
+
+	Ok(())
+}
+
+
+#[test]
+fn test_discarded_capture_warnings() -> io::Result<()> {
+	let mut interner = symbol::Interner::new();
+	let path = Path::new("src/semantic/tests/data/positive/discarded-capture.hsh");
+	let path_symbol = interner.get_or_intern(path.as_os_str().as_bytes());
+	let source = syntax::Source::from_reader(path_symbol, std::fs::File::open(path)?)?;
+	let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+
+	let (result, warnings) = Analyzer::analyze_with_warnings(syntactic_analysis.ast, &mut interner);
+
+	assert!(result.is_ok());
+
+	let lines: Vec<u32> = warnings.0
+		.iter()
+		.filter(|warning| matches!(warning.kind, WarningKind::DiscardedCapture))
+		.map(|warning| warning.pos.line)
+		.collect();
+
+	assert_eq!(lines, [1, 9]);
+	assert_eq!(lines.len(), warnings.0.len(), "unexpected warnings");
+
+	Ok(())
+}
@@ -0,0 +1,114 @@
+use std::fmt::Display as _;
+
+use super::{Warnings, Warning, WarningKind};
+use crate::{
+	fmt::{self, Display},
+	symbol,
+	term::color
+};
+
+
+/// Context for displaying warnings.
+#[derive(Debug, Copy, Clone)]
+pub struct WarningsDisplayContext<'a> {
+	/// Max number of displayed warnings.
+	pub max_warnings: Option<usize>,
+	/// Symbol interner.
+	pub interner: &'a symbol::Interner,
+	/// Output format.
+	pub format: fmt::ErrorFormat,
+}
+
+
+impl<'a> Display<'a> for WarningKind {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self {
+			Self::ReservedIdentifier(symbol) => {
+				"'".fmt(f)?;
+				symbol.fmt(f, context)?;
+				"' is reserved for a future keyword, consider renaming it".fmt(f)
+			}
+
+			Self::AttachedTry => {
+				"'?' after this argument is the try operator, not a glob. Use '%' to match a \
+				single character, or separate the operator with a space".fmt(f)
+			}
+
+			Self::DiscardedCapture => {
+				"the output and errors of this capture block are discarded. Use a '{}' block \
+				instead, or assign the result".fmt(f)
+			}
+		}
+	}
+}
+
+
+impl<'a> Display<'a> for Warning {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		write!(f, "{}: {} - ", color::Fg(color::Yellow, "Warning"), fmt::Show(self.pos, context))?;
+		self.kind.fmt(f, context)
+	}
+}
+
+
+impl<'a> Display<'a> for fmt::Json<&Warning> {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		let warning = self.0;
+
+		let kind = match warning.kind {
+			WarningKind::ReservedIdentifier(_) => "reserved_identifier",
+			WarningKind::AttachedTry => "attached_try",
+			WarningKind::DiscardedCapture => "discarded_capture",
+		};
+
+		let pos = &warning.pos;
+
+		fmt::json_warning(
+			f,
+			context,
+			Some((pos.path, pos.line, pos.column)),
+			kind,
+			fmt::Show(&warning.kind, context)
+		)
+	}
+}
+
+
+impl<'a> Display<'a> for Warnings {
+	type Context = WarningsDisplayContext<'a>;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		if context.format == fmt::ErrorFormat::Json {
+			for warning in self.0.iter() {
+				writeln!(f, "{}", fmt::Show(fmt::Json(warning), context.interner))?;
+			}
+
+			return Ok(());
+		}
+
+		for (ix, warning) in self.0.iter().enumerate() {
+			if let Some(max) = context.max_warnings {
+				if max <= ix {
+					writeln!(
+						f,
+						"{} {}",
+						color::Fg(color::Yellow, self.0.len() - max),
+						color::Fg(color::Yellow, "more supressed warnings"),
+					)?;
+
+					break;
+				}
+			}
+
+			writeln!(f, "{}", fmt::Show(warning, context.interner))?;
+		}
+
+		Ok(())
+	}
+}
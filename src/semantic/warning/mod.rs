@@ -0,0 +1,65 @@
+mod fmt;
+
+use super::{Symbol, SourcePos};
+pub use fmt::WarningsDisplayContext;
+
+
+/// The kind of a semantic warning.
+#[derive(Debug)]
+pub enum WarningKind {
+	/// Variable declared with a name reserved for a future keyword.
+	ReservedIdentifier(Symbol),
+	/// Try operator attached to an unquoted argument, as in `ls file?`, which may be
+	/// mistaken for a glob.
+	AttachedTry,
+	/// Capture block in statement position, as in `${ ls }`, whose output and errors are
+	/// discarded.
+	DiscardedCapture,
+}
+
+
+/// A semantic warning.
+#[derive(Debug)]
+pub struct Warning {
+	pub kind: WarningKind,
+	pub pos: SourcePos,
+}
+
+
+impl Warning {
+	/// Variable declared with a name reserved for a future keyword.
+	pub fn reserved_identifier(symbol: Symbol, pos: SourcePos) -> Self {
+		Self {
+			kind: WarningKind::ReservedIdentifier(symbol),
+			pos
+		}
+	}
+
+	/// Try operator attached to an unquoted argument.
+	pub fn attached_try(pos: SourcePos) -> Self {
+		Self {
+			kind: WarningKind::AttachedTry,
+			pos
+		}
+	}
+
+	/// Capture block whose result is discarded.
+	pub fn discarded_capture(pos: SourcePos) -> Self {
+		Self {
+			kind: WarningKind::DiscardedCapture,
+			pos
+		}
+	}
+}
+
+
+/// A collection of semantic warnings.
+#[derive(Debug, Default)]
+pub struct Warnings(pub Vec<Warning>);
+
+
+impl Warnings {
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+}
@@ -1,5 +1,5 @@
 use crate::{io::FileDescriptor, symbol::Symbol};
-use super::{lexer, IllFormed, SourcePos};
+use super::{lexer, Expr, IllFormed, SourcePos};
 
 
 /// The most basic part of an argument.
@@ -26,9 +26,13 @@ impl From<lexer::ArgUnit> for ArgUnit {
 /// An argument expansion.
 #[derive(Debug)]
 pub enum ArgExpansion {
-	Home, // ~/
-	Range(i64, i64), // {x..y}
-	Collection(Box<[ArgUnit]>), // {a,b,c}
+	Home(Box<[u8]>), // ~, ~user, ~+, ~-
+	Range { // {x..y}, {x..y..step}
+		from: ArgUnit,
+		to: ArgUnit,
+		step: Option<ArgUnit>,
+	},
+	Collection(Box<[Box<[ArgPart]>]>), // {a,b,c}
 
 	Star, // *
 	Percent, // %
@@ -39,13 +43,23 @@ pub enum ArgExpansion {
 impl From<lexer::ArgExpansion> for ArgExpansion {
 	fn from(expansion: lexer::ArgExpansion) -> Self {
 		match expansion {
-			lexer::ArgExpansion::Home => Self::Home,
-			lexer::ArgExpansion::Range(from ,to) => Self::Range(from ,to),
+			lexer::ArgExpansion::Home(user) => Self::Home(user),
+			lexer::ArgExpansion::Range { from, to, step } => Self::Range {
+				from: from.into(),
+				to: to.into(),
+				step: step.map(Into::into),
+			},
 			lexer::ArgExpansion::Collection(items) => Self::Collection(
 				items
 					.into_vec() // Use vec's owned iterator.
 					.into_iter()
-					.map(Into::into)
+					.map(
+						|item| item
+							.into_vec()
+							.into_iter()
+							.flat_map(collection_parts)
+							.collect()
+					)
 					.collect()
 			),
 			lexer::ArgExpansion::Star => Self::Star,
@@ -56,6 +70,22 @@ impl From<lexer::ArgExpansion> for ArgExpansion {
 }
 
 
+/// Convert a part of a collection item. Quotes have no effect inside expansions.
+fn collection_parts(part: lexer::ArgPart) -> Vec<ArgPart> {
+	match part {
+		lexer::ArgPart::Unquoted(unit) => vec![ArgPart::Unit(unit.into())],
+		lexer::ArgPart::SingleQuoted(lit) => vec![ArgPart::Unit(ArgUnit::Literal(lit))],
+		lexer::ArgPart::DoubleQuoted(units) => units
+			.into_vec()
+			.into_iter()
+			.map(|unit| ArgPart::Unit(unit.into()))
+			.collect(),
+		lexer::ArgPart::Expansion(expansion) => vec![ArgPart::Expansion(expansion.into())],
+		lexer::ArgPart::EnvAssign => vec![ArgPart::Unit(ArgUnit::Literal(b"=".as_ref().into()))],
+	}
+}
+
+
 /// The most basic part of an argument.
 #[derive(Debug)]
 pub enum ArgPart {
@@ -68,6 +98,8 @@ pub enum ArgPart {
 #[derive(Debug)]
 pub struct Argument {
 	pub parts: Box<[ArgPart]>,
+	/// Whether any part of the argument is single or double quoted.
+	pub quoted: bool,
 	pub pos: SourcePos,
 }
 
@@ -76,6 +108,7 @@ impl IllFormed for Argument {
 	fn ill_formed() -> Self {
 		Self {
 			parts: Default::default(),
+			quoted: false,
 			pos: SourcePos::ill_formed(),
 		}
 	}
@@ -112,6 +145,8 @@ pub enum Redirection {
 	Input {
 		/// Whether the source is the input or the file path.
 		literal: bool,
+		/// The file descriptor to be redirected, which is stdin by default.
+		fd: FileDescriptor,
 		source: Argument,
 	},
 }
@@ -137,6 +172,8 @@ pub struct BasicCommand {
 	pub arguments: Box<[Argument]>,
 	pub redirections: Box<[Redirection]>,
 	pub abort_on_error: bool,
+	/// Whether the try operator is attached to the last argument, as in `ls file?`.
+	pub try_attached: bool,
 	pub pos: SourcePos,
 }
 
@@ -149,6 +186,7 @@ impl IllFormed for BasicCommand {
 			arguments: Default::default(),
 			redirections: Default::default(),
 			abort_on_error: Default::default(),
+			try_attached: Default::default(),
 			pos: SourcePos::ill_formed(),
 		}
 	}
@@ -181,12 +219,23 @@ impl IllFormed for Command {
 }
 
 
+/// Block-level input redirection, as in `{ cat | wc -l } < "file.txt"`.
+#[derive(Debug)]
+pub struct BlockInput {
+	/// Whether the source is the input itself (<<) or a file path (<).
+	pub literal: bool,
+	pub source: Box<Expr>,
+}
+
+
 /// A command block.
 #[derive(Debug)]
 pub struct CommandBlock {
 	pub kind: CommandBlockKind,
 	pub head: Command,
 	pub tail: Box<[Command]>,
+	/// Standard input for the whole block, if redirected.
+	pub input: Option<BlockInput>,
 }
 
 
@@ -196,6 +245,7 @@ impl IllFormed for CommandBlock {
 			kind: CommandBlockKind::Synchronous,
 			head: Command::ill_formed(),
 			tail: Default::default(),
+			input: None,
 		}
 	}
 
@@ -2,6 +2,7 @@ use std::fmt::Display as _;
 
 use super::{
 	lexer::{self, CommandOperator, Keyword, Operator, TokenKind},
+	Annotation,
 	ArgPart,
 	ArgExpansion,
 	ArgUnit,
@@ -19,10 +20,12 @@ use super::{
 	Redirection,
 	RedirectionTarget,
 	Statement,
+	TypeName,
 	UnaryOp,
 };
 use crate::{
 	fmt::{self, Display, Indentation},
+	io,
 	symbol,
 	syntax::SourcePos,
 	term::color
@@ -50,7 +53,7 @@ impl<'a> Context<'a> {
 
 
 	/// Set to inlined
-	fn inlined(mut self) -> Self {
+	pub fn inlined(mut self) -> Self {
 		self.indentation = None;
 		self
 	}
@@ -89,6 +92,33 @@ impl<'a> Display<'a> for Block {
 }
 
 
+impl<'a> Display<'a> for TypeName {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self {
+			Self::Nil => color::Fg(color::Blue, "nil").fmt(f),
+			Self::Function => Keyword::Function.fmt(f),
+			Self::Identifier(identifier) => identifier.fmt(f, context),
+		}
+	}
+}
+
+
+impl<'a> Display<'a> for Option<Annotation> {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		if let Some(annotation) = self {
+			": ".fmt(f)?;
+			annotation.type_name.fmt(f, context)?;
+		}
+
+		Ok(())
+	}
+}
+
+
 impl<'a> Display<'a> for Literal {
 	type Context = Context<'a>;
 
@@ -156,21 +186,25 @@ impl<'a> Display<'a> for Literal {
 				"]".fmt(f)
 			},
 
-			Self::Function { params, body } => {
+			Self::Function { params, return_annotation, body, .. } => {
 				Keyword::Function.fmt(f)?;
 				"(".fmt(f)?;
 
 				fmt::sep_by(
 					params.iter(),
 					f,
-					|(ident, _), f| ident.fmt(f, context.interner),
+					|((ident, _), annotation), f| {
+						ident.fmt(f, context.interner)?;
+						annotation.fmt(f, context.interner)
+					},
 					", "
 				)?;
 
+				")".fmt(f)?;
+				return_annotation.fmt(f, context.interner)?;
+
 				if context.indentation.is_some() {
-					")\n".fmt(f)?;
-				} else {
-					")".fmt(f)?;
+					"\n".fmt(f)?;
 				}
 
 				body.fmt(f, context.indent())?;
@@ -214,6 +248,7 @@ impl std::fmt::Display for BinaryOp {
 			Self::And => Operator::And.fmt(f),
 			Self::Or => Operator::Or.fmt(f),
 			Self::Concat => Operator::Concat.fmt(f),
+			Self::Coalesce => Operator::Coalesce.fmt(f),
 		}
 	}
 }
@@ -258,6 +293,26 @@ impl<'a> Display<'a> for Expr {
 				")".fmt(f)
 			}
 
+			Self::Interpolation { parts, .. } => {
+				"\"".fmt(f)?;
+
+				for part in parts.iter() {
+					match part {
+						Self::Literal { literal: Literal::String(s), .. } => {
+							color::Bold(String::from_utf8_lossy(s).escape_debug()).fmt(f)?;
+						}
+
+						expr => {
+							"${".fmt(f)?;
+							expr.fmt(f, context.inlined())?;
+							"}".fmt(f)?;
+						}
+					}
+				}
+
+				"\"".fmt(f)
+			}
+
 			Self::If { condition, then, otherwise, .. } => {
 				let step = if context.indentation.is_some() { "\n" } else { " " };
 
@@ -296,16 +351,16 @@ impl<'a> Display<'a> for Expr {
 				Keyword::End.fmt(f)
 			}
 
-			Self::Access { object, field, .. }
+			Self::Access { object, field, safe, .. }
 			if matches!(field.as_ref(), Self::Literal { literal: Literal::Identifier(..), .. }) => {
 				object.fmt(f, context.inlined())?;
-				".".fmt(f)?;
+				(if *safe { "?." } else { "." }).fmt(f)?;
 				field.fmt(f, context.inlined())
 			}
 
-			Self::Access { object, field, .. } => {
+			Self::Access { object, field, safe, .. } => {
 				object.fmt(f, context.inlined())?;
-				"[".fmt(f)?;
+				(if *safe { "?[" } else { "[" }).fmt(f)?;
 				field.fmt(f, context.inlined())?;
 				"]".fmt(f)
 			}
@@ -337,17 +392,26 @@ impl<'a> Display<'a> for Statement {
 		match self {
 			Self::IllFormed => ILL_FORMED.fmt(f),
 
-			Self::Let { identifier, init, .. } => {
+			Self::Let { identifier, annotation, init, .. } => {
 				Keyword::Let.fmt(f)?;
 				" ".fmt(f)?;
 				identifier.fmt(f, context.interner)?;
-				" = ".fmt(f)?;
-				init.fmt(f, context)
+				annotation.fmt(f, context.interner)?;
+
+				if let Some(init) = init {
+					" = ".fmt(f)?;
+					init.fmt(f, context)?;
+				}
+
+				Ok(())
 			}
 
 			Self::Assign { left, right, .. } => {
-				left.fmt(f, context.inlined())?;
-				" = ".fmt(f)?;
+				for left in left.iter() {
+					left.fmt(f, context.inlined())?;
+					" = ".fmt(f)?;
+				}
+
 				right.fmt(f, context)
 			}
 
@@ -435,12 +499,19 @@ impl<'a> Display<'a> for ArgExpansion {
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
 		match self {
-			Self::Home => color::Fg(color::Yellow, "~/").fmt(f),
-			Self::Range(start, end) => {
+			Self::Home(user) => color::Fg(
+				color::Yellow,
+				format!("~{}", String::from_utf8_lossy(user))
+			).fmt(f),
+			Self::Range { from, to, step } => {
 				color::Fg(color::Yellow, "{").fmt(f)?;
-				start.fmt(f)?;
+				from.fmt(f, context)?;
 				color::Fg(color::Yellow, "..").fmt(f)?;
-				end.fmt(f)?;
+				to.fmt(f, context)?;
+				if let Some(step) = step {
+					color::Fg(color::Yellow, "..").fmt(f)?;
+					step.fmt(f, context)?;
+				}
 				color::Fg(color::Yellow, "}").fmt(f)
 			},
 			Self::Collection(items) => {
@@ -449,7 +520,12 @@ impl<'a> Display<'a> for ArgExpansion {
 				fmt::sep_by(
 					items.iter(),
 					f,
-					|item, f| item.fmt(f, context),
+					|item, f| {
+						for part in item.iter() {
+							part.fmt(f, context)?;
+						}
+						Ok(())
+					},
 					color::Fg(color::Yellow, ",")
 				)?;
 
@@ -539,12 +615,18 @@ impl<'a> Display<'a> for Redirection {
 				target.fmt(f, context)
 			}
 
-			Self::Input { literal: false, source } => {
+			Self::Input { literal: false, fd, source } => {
+				if *fd != io::stdin_fd() {
+					fd.fmt(f)?;
+				}
 				"<".fmt(f)?;
 				source.fmt(f, context)
 			}
 
-			Self::Input { literal: true, source } => {
+			Self::Input { literal: true, fd, source } => {
+				if *fd != io::stdin_fd() {
+					fd.fmt(f)?;
+				}
 				"<<".fmt(f)?;
 				source.fmt(f, context)
 			}
@@ -578,7 +660,7 @@ impl<'a> Display<'a> for BasicCommand {
 
 		if !self.abort_on_error {
 			" ".fmt(f)?;
-			CommandOperator::Try.fmt(f)?;
+			CommandOperator::Try { attached: false }.fmt(f)?;
 		}
 
 		Ok(())
@@ -635,7 +717,16 @@ impl<'a> Display<'a> for CommandBlock {
 
 		step(f, context)?;
 
-		"}".fmt(f)
+		"}".fmt(f)?;
+
+		if let Some(input) = &self.input {
+			" ".fmt(f)?;
+			CommandOperator::Input { literal: input.literal }.fmt(f)?;
+			" ".fmt(f)?;
+			input.source.fmt(f, context.inlined())?;
+		}
+
+		Ok(())
 	}
 }
 
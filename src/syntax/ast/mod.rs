@@ -9,6 +9,7 @@ pub use command::{
 	ArgUnit,
 	Argument,
 	BasicCommand,
+	BlockInput,
 	Command,
 	CommandBlock,
 	CommandBlockKind,
@@ -64,6 +65,13 @@ impl IllFormed for SourcePos {
 }
 
 
+impl<T> IllFormed for Option<T> {
+	fn ill_formed() -> Self {
+		None
+	}
+}
+
+
 impl IllFormed for Symbol {
 	fn ill_formed() -> Self {
 		Symbol::default()
@@ -115,6 +123,29 @@ impl IllFormed for Block {
 }
 
 
+/// The name of a type in an annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeName {
+	Nil,
+	Function,
+	/// Other types are named by identifiers, such as `int` or `string`.
+	Identifier(Symbol),
+}
+
+
+/// A type annotation, as in `let x: int` or `function f(a: string): int`.
+/// Annotations are only used by the optional type checker, and are erased at runtime.
+#[derive(Debug, Clone, Copy)]
+pub struct Annotation {
+	pub type_name: TypeName,
+	pub pos: SourcePos,
+}
+
+
+/// A function parameter: its identifier, and its annotation, if any.
+pub type Param = ((Symbol, SourcePos), Option<Annotation>);
+
+
 /// Literals of all types in the language.
 /// Note that there are no literals for the error type.
 #[derive(Debug)]
@@ -128,9 +159,15 @@ pub enum Literal {
 	Array(Box<[Expr]>),
 	Dict(Box<[((Symbol, SourcePos), Expr)]>),
 	Function {
-		/// A list of parameters (identifiers).
-		params: Box<[(Symbol, SourcePos)]>,
+		/// A list of parameters (identifiers), optionally annotated.
+		params: Box<[Param]>,
+		/// The annotated return type, if any.
+		return_annotation: Option<Annotation>,
 		body: Block,
+		/// The doc comment preceding the function, if any.
+		doc: Option<Symbol>,
+		/// The name of the variable the function is declared as, if any.
+		name: Option<Symbol>,
 	},
 	/// For the dot access operator, we want to be able to have identifiers as literal
 	/// strings instead of names for variables. This variant should only be used in such
@@ -212,6 +249,9 @@ pub enum BinaryOp {
 	Or,  // or
 
 	Concat, // ++
+
+	/// Nil-coalescing: the left operand, or the right one if the left is nil.
+	Coalesce, // ??
 }
 
 
@@ -233,6 +273,7 @@ impl From<lexer::Operator> for BinaryOp {
 			lexer::Operator::And => BinaryOp::And,
 			lexer::Operator::Or => BinaryOp::Or,
 			lexer::Operator::Concat => BinaryOp::Concat,
+			lexer::Operator::Coalesce => BinaryOp::Coalesce,
 			_ => panic!("invalid operator"),
 		}
 	}
@@ -267,6 +308,12 @@ pub enum Expr {
 		right: Box<Expr>,
 		pos: SourcePos,
 	},
+	/// String literal with interpolations, as in `"a $b"`. The parts are string literals
+	/// and interpolated expressions, which are converted to strings and concatenated.
+	Interpolation {
+		parts: Box<[Expr]>,
+		pos: SourcePos,
+	},
 	/// If-else expression.
 	If {
 		condition: Box<Expr>,
@@ -278,6 +325,8 @@ pub enum Expr {
 	Access {
 		object: Box<Expr>,
 		field: Box<Expr>,
+		/// Safe access (?. and ?[]), which yields nil for nil objects.
+		safe: bool,
 		pos: SourcePos,
 	},
 	/// Function call (()) operator.
@@ -293,6 +342,27 @@ pub enum Expr {
 }
 
 
+impl Expr {
+	/// The position of the expression.
+	/// Ill-formed expressions have no position, so the default one is returned.
+	pub fn pos(&self) -> SourcePos {
+		match self {
+			Self::IllFormed => SourcePos::default(),
+			Self::Self_ { pos }
+			| Self::Identifier { pos, .. }
+			| Self::Literal { pos, .. }
+			| Self::UnaryOp { pos, .. }
+			| Self::BinaryOp { pos, .. }
+			| Self::Interpolation { pos, .. }
+			| Self::If { pos, .. }
+			| Self::Access { pos, .. }
+			| Self::Call { pos, .. }
+			| Self::CommandBlock { pos, .. } => *pos,
+		}
+	}
+}
+
+
 impl IllFormed for Expr {
 	fn ill_formed() -> Self {
 		Self::IllFormed
@@ -312,11 +382,14 @@ pub enum Statement {
 	/// Introduces an identifier.
 	Let {
 		identifier: Symbol,
-		init: Expr,
+		annotation: Option<Annotation>,
+		/// None if the variable is declared without a value.
+		init: Option<Expr>,
 		pos: SourcePos,
 	},
+	/// Assignment of the value to all targets, from right to left, as in `a = b = 0`.
 	Assign {
-		left: Expr,
+		left: Box<[Expr]>,
 		right: Expr,
 		pos: SourcePos,
 	},
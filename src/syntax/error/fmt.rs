@@ -12,7 +12,33 @@ impl<'a> Display<'a> for Error {
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
 		match self {
 			Self::Lexer(error) => error.fmt(f, context),
-			Self::Parser(error) => error.fmt(f, context),
+			Self::Parser { error, resumed } => {
+				error.fmt(f, context)?;
+
+				match resumed {
+					// Only show the synchronization point if some tokens were skipped.
+					Some(pos) if Some(*pos) != error.pos() => write!(
+						f,
+						"\n  {}: parsing resumed at {}",
+						color::Fg(color::Blue, "Note"),
+						fmt::Show(pos, context)
+					),
+
+					_ => Ok(()),
+				}
+			}
+		}
+	}
+}
+
+
+impl<'a> Display<'a> for fmt::Json<&Error> {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self.0 {
+			Error::Lexer(error) => fmt::Json(error).fmt(f, context),
+			Error::Parser { error, .. } => fmt::Json(error).fmt(f, context),
 		}
 	}
 }
@@ -30,6 +56,14 @@ impl<'a> Display<'a> for Errors {
 	type Context = AnalysisDisplayContext<'a>;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		if context.format == fmt::ErrorFormat::Json {
+			for error in self.0.iter() {
+				writeln!(f, "{}", fmt::Show(fmt::Json(error), context.interner))?;
+			}
+
+			return Ok(());
+		}
+
 		for (ix, error) in self.0.iter().enumerate() {
 			if let Some(max) = context.max_errors {
 				if max <= ix {
@@ -1,13 +1,17 @@
 mod fmt;
 
-use super::{lexer, parser, AnalysisDisplayContext};
+use super::{lexer, parser, AnalysisDisplayContext, SourcePos};
 
 
 /// Syntax error.
 #[derive(Debug)]
 pub enum Error {
 	Lexer(lexer::Error),
-	Parser(parser::Error),
+	Parser {
+		error: parser::Error,
+		/// Where parsing resumed after the error, or None if the end of file was reached.
+		resumed: Option<SourcePos>,
+	},
 }
 
 
@@ -1,6 +1,6 @@
 use super::{ast, Analysis};
 use crate::{
-	fmt::Display,
+	fmt::{Display, ErrorFormat},
 	symbol,
 };
 
@@ -12,6 +12,8 @@ pub struct AnalysisDisplayContext<'a> {
 	pub max_errors: Option<usize>,
 	/// Symbol interner.
 	pub interner: &'a symbol::Interner,
+	/// Output format of the errors.
+	pub format: ErrorFormat,
 }
 
 
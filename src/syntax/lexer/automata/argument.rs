@@ -305,7 +305,7 @@ where
 			}
 
 			// EOF when no braces.
-			(_, None) => produce!(true),
+			(_, None) => produce!(false),
 		}
 	}
 }
@@ -630,6 +630,14 @@ impl ExpansionContext for Argument {
 		Transition::step(self)
 	}
 
+	fn resume(mut self, expansion: crate::syntax::lexer::ArgExpansion) -> Transition {
+		self.parts.push(
+			ArgPart::Expansion(expansion)
+		);
+
+		Transition::resume(self)
+	}
+
 	fn rollback(self, checkpoint: Checkpoint) -> Transition {
 		// If expansion parsing fails, handle it like a word.
 		Transition::rollback(checkpoint, Word::from(self))
@@ -656,6 +664,20 @@ impl ExpansionContext for Word<Argument> {
 		Transition::step(argument_state)
 	}
 
+	fn resume(self, expansion: crate::syntax::lexer::ArgExpansion) -> Transition {
+		let mut argument_state = self.context;
+
+		argument_state.parts.push(ArgPart::Unquoted(ArgUnit::Literal(
+			self.value.into_boxed_slice(),
+		)));
+
+		argument_state.parts.push(
+			ArgPart::Expansion(expansion)
+		);
+
+		Transition::resume(argument_state)
+	}
+
 	fn rollback(mut self, checkpoint: Checkpoint) -> Transition {
 		self.allow_expansion_start = false;
 		// If expansion parsing fails, handle it like a word.
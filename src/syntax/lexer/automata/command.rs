@@ -1,6 +1,8 @@
 use super::{
+	argument::WordContext,
 	symbol::CommandSymbolChar,
 	Argument,
+	CommandOperator,
 	CommandSymbol,
 	Comment,
 	Cursor,
@@ -33,12 +35,15 @@ impl Command {
 				Token { kind: TokenKind::CloseCommand, pos: cursor.pos() },
 			),
 
+			// Try operator.
+			Some(b'?') => Self::try_operator(cursor),
+
 			// Argument or operator.
 			Some(c) => match CommandSymbolChar::from_first(c) {
 				// Argument.
 				CommandSymbolChar::None => Transition::resume(Argument::at(cursor)),
 
-				// Semicolon, pipe or try.
+				// Semicolon or pipe.
 				CommandSymbolChar::Single(token) => {
 					Transition::produce(self, Token { kind: token, pos: cursor.pos() })
 				}
@@ -53,6 +58,39 @@ impl Command {
 			None => Transition::error(Root, Error::unexpected_eof(cursor.pos())),
 		}
 	}
+
+
+	/// The question mark is always the try operator, and never a glob, which is `%` in Hush.
+	/// Inside an argument, as in `file?.txt`, it would split the argument, so that's reported
+	/// as an error.
+	fn try_operator(cursor: &Cursor) -> Transition {
+		let offset = cursor.offset();
+		let input = cursor.slice();
+
+		// Closing braces in the command state belong to argument expansions.
+		let is_argument = |c: u8| Argument::is_word(c) || c == b'}';
+
+		let attached = offset
+			.checked_sub(1)
+			.and_then(|ix| input.get(ix))
+			.is_some_and(|&c| is_argument(c));
+
+		let followed = input
+			.get(offset + 1)
+			.is_some_and(|&c| Argument::is_word(c) || matches!(c, b'\'' | b'"' | b'$'));
+
+		if attached && followed {
+			return Transition::error(Self, Error::try_in_argument(cursor.pos()));
+		}
+
+		Transition::produce(
+			Self,
+			Token {
+				kind: TokenKind::CmdOperator(CommandOperator::Try { attached }),
+				pos: cursor.pos(),
+			},
+		)
+	}
 }
 
 
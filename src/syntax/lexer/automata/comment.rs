@@ -1,4 +1,4 @@
-use super::{Command, Cursor, Root, State, Transition};
+use super::{Command, Cursor, Root, SourcePos, State, SymbolInterner, Token, TokenKind, Transition};
 
 /// The state for lexing comments.
 /// This state is generic in the sense that it returns to the previous state once the
@@ -43,3 +43,111 @@ impl From<Comment<Command>> for State {
 		Self::CommandComment(state)
 	}
 }
+
+
+/// The state for lexing doc comments, which start with `##` at the top level.
+/// Consecutive doc comment lines are merged in a single token. A single `#` is a regular
+/// comment.
+#[derive(Debug)]
+pub(super) struct DocComment {
+	text: Vec<u8>,
+	stage: DocStage,
+	pos: SourcePos,
+}
+
+
+#[derive(Debug)]
+enum DocStage {
+	/// After the first `#`, which may start a regular comment.
+	First,
+	/// After the `##`, where a single space is skipped.
+	Start,
+	/// In the comment's text.
+	Line,
+	/// Between lines, where a following doc comment continues this one.
+	Between,
+	/// After the first `#` of a following line.
+	Next,
+}
+
+
+impl DocComment {
+	pub fn at(cursor: &Cursor) -> Self {
+		Self {
+			text: Vec::new(),
+			stage: DocStage::First,
+			pos: cursor.pos(),
+		}
+	}
+
+
+	pub fn visit(mut self, cursor: &Cursor, interner: &mut SymbolInterner) -> Transition {
+		match (&self.stage, cursor.peek()) {
+			(DocStage::First, Some(b'#')) => {
+				self.stage = DocStage::Start;
+				Transition::step(self)
+			}
+
+			(DocStage::First, _) => Transition::resume(Comment::from(Root)),
+
+			(DocStage::Start, Some(b' ')) => {
+				self.stage = DocStage::Line;
+				Transition::step(self)
+			}
+
+			(DocStage::Start, _) => {
+				self.stage = DocStage::Line;
+				Transition::resume(self)
+			}
+
+			(DocStage::Line, Some(b'\n')) => {
+				self.stage = DocStage::Between;
+				Transition::step(self)
+			}
+
+			(DocStage::Line, Some(c)) => {
+				self.text.push(c);
+				Transition::step(self)
+			}
+
+			(DocStage::Between, Some(c)) if c.is_ascii_whitespace() => Transition::step(self),
+
+			(DocStage::Between, Some(b'#')) => {
+				self.stage = DocStage::Next;
+				Transition::step(self)
+			}
+
+			(DocStage::Next, Some(b'#')) => {
+				self.text.push(b'\n');
+				self.stage = DocStage::Start;
+				Transition::step(self)
+			}
+
+			// The following line is a regular comment, which must still be consumed.
+			(DocStage::Next, _) => {
+				let token = self.token(interner);
+				Transition::resume_produce(Comment::from(Root), token)
+			}
+
+			(DocStage::Line, None) | (DocStage::Between, _) => {
+				let token = self.token(interner);
+				Transition::resume_produce(Root, token)
+			}
+		}
+	}
+
+
+	fn token(self, interner: &mut SymbolInterner) -> Token {
+		Token {
+			kind: TokenKind::DocComment(interner.get_or_intern(self.text)),
+			pos: self.pos,
+		}
+	}
+}
+
+
+impl From<DocComment> for State {
+	fn from(state: DocComment) -> State {
+		Self::DocComment(state)
+	}
+}
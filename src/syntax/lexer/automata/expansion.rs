@@ -1,10 +1,16 @@
 use super::{
 	argument,
+	word::{self, IsWord},
 	Argument,
 	ArgExpansion,
+	ArgPart,
+	ArgUnit,
 	Cursor,
 	Checkpoint,
+	SourcePos,
 	State,
+	SymbolInterner,
+	TokenKind,
 	Transition,
 };
 
@@ -17,6 +23,8 @@ use super::{
 pub(super) trait ExpansionContext {
 	/// The transition to make when a expansion has been produced.
 	fn produce(self, expansion: ArgExpansion) -> Transition;
+	/// Non-consuming variant of produce.
+	fn resume(self, expansion: ArgExpansion) -> Transition;
 	/// The transition to make when no expansion could be parsed.
 	/// Yield and rollback to the given checkpoint.
 	fn rollback(self, checkpoint: Checkpoint) -> Transition;
@@ -31,8 +39,12 @@ pub(super) struct Expansion<C> {
 	start: Checkpoint,
 	/// Whether to allow recognition of the home expansion.
 	allow_home: bool,
-	/// Whether the tilde has been consumed for the home expansion.
-	tilde_consumed: bool,
+	/// The user name, once the tilde has been consumed for the home expansion.
+	user: Option<Vec<u8>>,
+	/// The contents of the braces, once the opening brace has been consumed.
+	braces: Option<Braces>,
+	/// The contents of the char class, once the opening bracket has been consumed.
+	class: Option<Vec<u8>>,
 	/// The argument context.
 	context: C,
 }
@@ -47,13 +59,15 @@ where
 		Self {
 			start: cursor.checkpoint(),
 			allow_home,
-			tilde_consumed: false,
+			user: None,
+			braces: None,
+			class: None,
 			context,
 		}
 	}
 
 
-	pub fn visit(mut self, cursor: &Cursor) -> Transition {
+	pub fn visit(mut self, cursor: &Cursor, interner: &mut SymbolInterner) -> Transition {
 		// Note that we must only allow home expansion in the beggining of the input.
 		let allow_home = self.allow_home;
 		self.allow_home = false;
@@ -61,17 +75,78 @@ where
 		match cursor.peek() {
 			// Home expansion start.
 			Some(b'~') if allow_home => {
-				self.tilde_consumed = true;
+				self.user = Some(Vec::new());
 				Transition::step(self)
 			}
 
-			// Home expansion end.
-			Some(b'/') if self.tilde_consumed => {
-				self.context.produce(ArgExpansion::Home)
+			// Home expansion end, either before a slash or at the end of the argument. The
+			// slash is left for the remainder of the argument.
+			Some(b'/') if self.user.is_some() => {
+				let user = self.user.take().unwrap_or_default();
+				self.context.resume(ArgExpansion::Home(user.into_boxed_slice()))
 			}
 
-			// Home expansion missing tilde.
-			Some(_) if self.tilde_consumed => self.context.rollback(self.start),
+			None if self.user.is_some() => {
+				let user = self.user.take().unwrap_or_default();
+				self.context.resume(ArgExpansion::Home(user.into_boxed_slice()))
+			}
+
+			// Home expansion user name, or `+` and `-` for PWD and OLDPWD.
+			Some(c) if is_user_name(c) && self.user.is_some() => {
+				if let Some(user) = &mut self.user {
+					user.push(c);
+				}
+				Transition::step(self)
+			}
+
+			Some(c) if self.user.is_some() && is_argument_end::<C>(c) => {
+				let user = self.user.take().unwrap_or_default();
+				self.context.resume(ArgExpansion::Home(user.into_boxed_slice()))
+			}
+
+			// Home expansion followed by something else, such as a quote or dollar.
+			Some(_) if self.user.is_some() => self.context.rollback(self.start),
+
+			// Range or collection end.
+			Some(b'}') if self.braces.as_ref().is_some_and(|braces| braces.depth == 0) => {
+				let braces = self.braces.take().expect("missing braces");
+				let mut parser = BracesParser { pos: braces.pos, interner };
+
+				match parser.expansion(&braces.contents, 0) {
+					Some(expansion) => self.context.produce(expansion),
+					None => self.context.rollback(self.start),
+				}
+			}
+
+			// Range or collection contents, including nested braces and dollars.
+			Some(c) if self.braces.is_some() && (C::is_expansion_word(c) || b"${}".contains(&c)) && c != b'[' => {
+				if let Some(braces) = &mut self.braces {
+					match c {
+						b'{' => braces.depth += 1,
+						b'}' => braces.depth -= 1,
+						_ => (),
+					}
+					braces.contents.push(c);
+				}
+				Transition::step(self)
+			}
+
+			// Char class end.
+			Some(b']') if self.class.as_ref().is_some_and(|class| !class.is_empty()) => {
+				let class = self.class.take().unwrap_or_default();
+				self.context.produce(ArgExpansion::CharClass(class.into_boxed_slice()))
+			}
+
+			// Char class contents.
+			Some(c) if self.class.is_some() && C::is_expansion_word(c) && c != b'[' => {
+				if let Some(class) = &mut self.class {
+					class.push(c);
+				}
+				Transition::step(self)
+			}
+
+			// Unterminated range, collection or char class.
+			_ if self.braces.is_some() || self.class.is_some() => self.context.rollback(self.start),
 
 			// Star.
 			Some(b'*') => {
@@ -83,12 +158,18 @@ where
 				self.context.produce(ArgExpansion::Percent)
 			}
 
+			// Char class start.
 			Some(b'[') => {
-				todo!() // char class.
+				self.class = Some(Vec::new());
+				Transition::step(self)
 			}
 
+			// Range or collection start.
 			Some(b'{') => {
-				todo!() // range, collection
+				self.braces = Some(
+					Braces { contents: Vec::new(), depth: 0, pos: cursor.pos() }
+				);
+				Transition::step(self)
 			}
 
 			// Failed to parse expansion.
@@ -112,6 +193,233 @@ impl From<Expansion<argument::Word<Argument>>> for State {
 }
 
 
+/// The contents of a range or collection expansion.
+#[derive(Debug)]
+struct Braces {
+	contents: Vec<u8>,
+	/// The amount of unclosed nested braces.
+	depth: usize,
+	/// The position of the opening brace.
+	pos: SourcePos,
+}
+
+
+/// Parser for the contents of braces, which are only known to be an expansion once the
+/// closing brace has been consumed.
+struct BracesParser<'a> {
+	/// The position of the opening brace.
+	pos: SourcePos,
+	interner: &'a mut SymbolInterner,
+}
+
+
+impl BracesParser<'_> {
+	/// Parse the contents of braces, starting at the given offset, as a range, `x..y` or
+	/// `x..y..step`, or a collection, `a,b,c`. Braces which contain neither are not an
+	/// expansion, like in other shells.
+	fn expansion(&mut self, braces: &[u8], offset: usize) -> Option<ArgExpansion> {
+		let items = split_top_level(braces, b",");
+
+		if items.len() > 1 {
+			let items = items
+				.into_iter()
+				.map(|(ix, item)| self.parts(item, offset + ix).map(Vec::into_boxed_slice))
+				.collect::<Option<_>>()?;
+
+			return Some(ArgExpansion::Collection(items));
+		}
+
+		let bounds = split_top_level(braces, b"..")
+			.into_iter()
+			.map(|(ix, bound)| self.bound(bound, offset + ix))
+			.collect::<Option<Vec<_>>>()?;
+
+		let mut bounds = bounds.into_iter();
+
+		match (bounds.next(), bounds.next(), bounds.next(), bounds.next()) {
+			(Some(from), Some(to), step, None) => Some(ArgExpansion::Range { from, to, step }),
+			_ => None,
+		}
+	}
+
+
+	/// Parse a range bound or step, which must be either an integer or a single dollar.
+	fn bound(&mut self, bound: &[u8], offset: usize) -> Option<ArgUnit> {
+		if bound.starts_with(b"$") {
+			match self.dollar(bound, offset)? {
+				(unit, len) if len == bound.len() => Some(unit),
+				_ => None,
+			}
+		} else {
+			std::str::from_utf8(bound).ok()?.parse::<i64>().ok()?;
+			Some(ArgUnit::Literal(bound.into()))
+		}
+	}
+
+
+	/// Parse an item of a collection. Nested braces which are not expansions are literals.
+	fn parts(&mut self, item: &[u8], offset: usize) -> Option<Vec<ArgPart>> {
+		let mut parts = Vec::new();
+		let mut literal = Vec::new();
+
+		let push_literal = |literal: &mut Vec<u8>, parts: &mut Vec<ArgPart>| {
+			if !literal.is_empty() {
+				let literal = std::mem::take(literal).into_boxed_slice();
+				parts.push(ArgPart::Unquoted(ArgUnit::Literal(literal)));
+			}
+		};
+
+		let mut ix = 0;
+		while ix < item.len() {
+			match item[ix] {
+				b'$' => {
+					let (unit, len) = self.dollar(&item[ix ..], offset + ix)?;
+					push_literal(&mut literal, &mut parts);
+					parts.push(ArgPart::Unquoted(unit));
+					ix += len;
+				}
+
+				b'{' => {
+					let len = closing_brace(&item[ix ..])?;
+					let inner = &item[ix + 1 .. ix + len];
+
+					match self.expansion(inner, offset + ix + 1) {
+						Some(expansion) => {
+							push_literal(&mut literal, &mut parts);
+							parts.push(ArgPart::Expansion(expansion));
+						}
+
+						None => {
+							literal.push(b'{');
+							for part in self.parts(inner, offset + ix + 1)? {
+								match part {
+									ArgPart::Unquoted(ArgUnit::Literal(lit)) => literal.extend(lit.iter()),
+									part => {
+										push_literal(&mut literal, &mut parts);
+										parts.push(part);
+									}
+								}
+							}
+							literal.push(b'}');
+						}
+					}
+
+					ix += len + 1;
+				}
+
+				c => {
+					literal.push(c);
+					ix += 1;
+				}
+			}
+		}
+
+		// An empty item is an empty literal.
+		if parts.is_empty() || !literal.is_empty() {
+			parts.push(ArgPart::Unquoted(ArgUnit::Literal(literal.into_boxed_slice())));
+		}
+
+		Some(parts)
+	}
+
+
+	/// Parse a dollar, `$name` or `${name}`, returning it along with its length.
+	fn dollar(&mut self, input: &[u8], offset: usize) -> Option<(ArgUnit, usize)> {
+		let (identifier, len) = match input.get(1) {
+			Some(b'{') => {
+				let len = input.iter().position(|&c| c == b'}')?;
+				(&input[2 .. len], len + 1)
+			}
+
+			_ => {
+				let len = input[1 ..]
+					.iter()
+					.position(|c| !c.is_word())
+					.map_or(input.len(), |len| len + 1);
+				(&input[1 .. len], len)
+			}
+		};
+
+		if !identifier.first()?.is_word_start() || !identifier.iter().all(IsWord::is_word) {
+			return None;
+		}
+
+		match word::to_token(identifier, self.interner) {
+			TokenKind::Identifier(symbol) => {
+				let pos = SourcePos {
+					column: self.pos.column + 1 + offset as u32,
+					..self.pos
+				};
+
+				Some((ArgUnit::Dollar { symbol, pos }, len))
+			}
+
+			_ => None,
+		}
+	}
+}
+
+
+/// Split the input by the given separator, ignoring separators inside nested braces.
+/// Returns the offset of each item along with the item.
+fn split_top_level<'a>(input: &'a [u8], separator: &[u8]) -> Vec<(usize, &'a [u8])> {
+	let mut items = Vec::new();
+	let mut depth = 0usize;
+	let mut start = 0;
+	let mut ix = 0;
+
+	while ix < input.len() {
+		match input[ix] {
+			b'{' => depth += 1,
+			b'}' => depth = depth.saturating_sub(1),
+			_ if depth == 0 && input[ix ..].starts_with(separator) => {
+				items.push((start, &input[start .. ix]));
+				ix += separator.len();
+				start = ix;
+				continue;
+			}
+			_ => (),
+		}
+
+		ix += 1;
+	}
+
+	items.push((start, &input[start ..]));
+
+	items
+}
+
+
+/// The offset of the brace which closes the one at the start of the input.
+fn closing_brace(input: &[u8]) -> Option<usize> {
+	let mut depth = 0usize;
+
+	for (ix, &c) in input.iter().enumerate() {
+		match c {
+			b'{' => depth += 1,
+			b'}' if depth == 1 => return Some(ix),
+			b'}' => depth -= 1,
+			_ => (),
+		}
+	}
+
+	None
+}
+
+
+
+/// Whether a character ends the argument in which an expansion is being lexed.
+fn is_argument_end<C: ExpansionContext>(c: u8) -> bool {
+	!C::is_expansion_word(c) && !b"$'\"=".contains(&c)
+}
+
+
+/// Whether a character may be part of a user name in the home expansion.
+fn is_user_name(c: u8) -> bool {
+	c.is_ascii_alphanumeric() || b"_.-+".contains(&c)
+}
+
+
 /// Whether a character is an expansion starter.
 pub fn is_start(c: u8) -> bool {
 	b"{[~*%".contains(&c)
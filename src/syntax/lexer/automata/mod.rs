@@ -12,13 +12,14 @@ use self::{
 	argument::{Argument, DoubleQuoted, SingleQuoted},
 	expansion::Expansion,
 	command::Command,
-	comment::Comment,
+	comment::{Comment, DocComment},
 	number::NumberLiteral,
 	root::Root,
 	string::{ByteLiteral, StringLiteral},
 	symbol::{CommandSymbol, Symbol},
 	word::Word,
 };
+pub use word::{is_reserved, Words};
 use super::{
 	ArgPart,
 	ArgExpansion,
@@ -141,6 +142,7 @@ enum State {
 	// Top level lexer states:
 	Root(Root),
 	Comment(Comment<Root>),
+	DocComment(DocComment),
 	NumberLiteral(NumberLiteral),
 	ByteLiteral(ByteLiteral),
 	StringLiteral(StringLiteral),
@@ -176,17 +178,18 @@ impl State {
 		match self {
 			Self::Root(state) => state.visit(cursor),
 			Self::Comment(state) => state.visit(cursor),
+			Self::DocComment(state) => state.visit(cursor, interner),
 			Self::NumberLiteral(state) => state.visit(cursor),
 			Self::ByteLiteral(state) => state.visit(cursor),
-			Self::StringLiteral(state) => state.visit(cursor),
+			Self::StringLiteral(state) => state.visit(cursor, interner),
 			Self::Word(state) => state.visit(cursor, interner),
 			Self::Symbol(state) => state.visit(cursor),
 
 			Self::Command(state) => state.visit(cursor),
 			Self::CommandComment(state) => state.visit(cursor),
 			Self::Argument(state) => state.visit(cursor),
-			Self::Expansion(state) => state.visit(cursor),
-			Self::ExpansionWord(state) => state.visit(cursor),
+			Self::Expansion(state) => state.visit(cursor, interner),
+			Self::ExpansionWord(state) => state.visit(cursor, interner),
 			Self::SingleQuoted(state) => state.visit(cursor),
 			Self::DoubleQuoted(state) => state.visit(cursor),
 			Self::UnquotedWord(state) => state.visit(cursor),
@@ -213,6 +216,18 @@ impl<'a, 'b> Automata<'a, 'b> {
 	pub fn new(cursor: Cursor<'a>, interner: &'b mut SymbolInterner) -> Self {
 		Self { state: State::default(), cursor, interner }
 	}
+
+
+	/// An automata for a single command argument, starting in the argument state.
+	pub fn argument(cursor: Cursor<'a>, interner: &'b mut SymbolInterner) -> Self {
+		Self { state: Argument::at(&cursor).into(), cursor, interner }
+	}
+
+
+	/// The current position in the input.
+	pub fn cursor(&self) -> &Cursor<'a> {
+		&self.cursor
+	}
 }
 
 
@@ -228,8 +243,9 @@ impl<'a, 'b> Iterator for Automata<'a, 'b> {
 
 			self.state = transition.state;
 
-			// Check EOF *before* stepping.
-			let eof = self.cursor.is_eof();
+			// Check EOF *before* stepping. States which resume or rollback at EOF may still
+			// produce output, such as an argument ending at the end of the input.
+			let eof = self.cursor.is_eof() && matches!(transition.step, Step::Forward);
 
 			transition.step.apply(&mut self.cursor);
 
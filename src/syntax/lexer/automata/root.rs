@@ -3,8 +3,8 @@ use super::{
 	word::IsWord,
 	ByteLiteral,
 	Command,
-	Comment,
 	Cursor,
+	DocComment,
 	Error,
 	NumberLiteral,
 	State,
@@ -28,8 +28,8 @@ impl Root {
 			// Whitespace.
 			Some(c) if c.is_ascii_whitespace() => Transition::step(self),
 
-			// Comments.
-			Some(b'#') => Transition::step(Comment::from(self)),
+			// Comments and doc comments.
+			Some(b'#') => Transition::step(DocComment::at(cursor)),
 
 			// String literals.
 			Some(b'"') => Transition::step(StringLiteral::at(cursor)),
@@ -1,4 +1,22 @@
-use super::{Cursor, Error, Literal, Root, SourcePos, State, Token, TokenKind, Transition};
+use std::collections::VecDeque;
+
+use super::{
+	word::{self, IsWord},
+	Automata,
+	Cursor,
+	Error,
+	ErrorKind,
+	Keyword,
+	Literal,
+	Output,
+	Root,
+	SourcePos,
+	State,
+	SymbolInterner,
+	Token,
+	TokenKind,
+	Transition,
+};
 
 
 /// The state for lexing byte literals.
@@ -85,12 +103,25 @@ impl From<ByteLiteral> for State {
 
 
 /// The state for lexing string literals.
+/// Strings may interpolate variables (`$name`) and expressions (`${expr}`), in which case
+/// the literal is lexed as interpolation quotes enclosing its parts: literal parts as string
+/// literals, and interpolated expressions enclosed in parenthesis.
 #[derive(Debug)]
 pub(super) struct StringLiteral {
-	/// The parsed bytes, if any.
+	/// The parsed bytes since the last interpolation, if any.
 	value: Vec<u8>,
 	/// The position of the current escape sequence, if any.
 	escaping: Option<(usize, SourcePos)>,
+	/// The position of the current dollar, if any.
+	dollar: Option<SourcePos>,
+	/// The offset and position of the current interpolated variable name, if any.
+	name: Option<(usize, SourcePos)>,
+	/// Whether the literal has interpolations.
+	interpolated: bool,
+	/// Tokens of the interpolation which are yet to be produced.
+	pending: VecDeque<Output>,
+	/// Whether the closing quote has been consumed.
+	closed: bool,
 	/// The position of the literal.
 	pos: SourcePos,
 }
@@ -101,16 +132,104 @@ impl StringLiteral {
 		Self {
 			value: Vec::with_capacity(8), // We expect most literals to not be empty.
 			escaping: None,
+			dollar: None,
+			name: None,
+			interpolated: false,
+			pending: VecDeque::new(),
+			closed: false,
 			pos: cursor.pos(),
 		}
 	}
 
 
-	pub fn visit(mut self, cursor: &Cursor) -> Transition {
+	pub fn visit(mut self, cursor: &Cursor, interner: &mut SymbolInterner) -> Transition {
+		if let Some(output) = self.pending.pop_front() {
+			return match output {
+				Ok(token) => Transition::resume_produce(self, token),
+				Err(error) => Transition::resume_error(self, error),
+			};
+		}
+
+		if self.closed {
+			return Transition::resume(Root);
+		}
+
 		match (&self, cursor.peek()) {
 			// EOF while scanning a literal is always an error.
 			(_, None) => Transition::error(Root, Error::unexpected_eof(cursor.pos())),
 
+			// Interpolated variable name.
+			(&Self { name: Some(_), .. }, Some(c)) if c.is_word() => Transition::step(self),
+
+			(&Self { name: Some((offset, pos)), .. }, Some(_)) => {
+				self.name = None;
+
+				let word = &cursor.slice()[offset .. cursor.offset()];
+
+				match word::to_token(word, interner) {
+					kind @ (TokenKind::Identifier(_) | TokenKind::Keyword(Keyword::Self_)) => {
+						self.interpolate(vec![Ok(Token { kind, pos })], pos);
+					}
+
+					// Other words can't be variables, and are kept as is.
+					_ => {
+						self.value.push(b'$');
+						self.value.extend_from_slice(word);
+					}
+				}
+
+				Transition::resume(self)
+			}
+
+			// Interpolated expression.
+			(&Self { dollar: Some(pos), .. }, Some(b'{')) => {
+				self.dollar = None;
+
+				let mut expression = cursor.clone();
+				expression.step();
+
+				// The expression is lexed as in the top level, which has no use for closing
+				// braces. Therefore, the first unexpected closing brace ends the expression.
+				let mut automata = Automata::new(expression, interner);
+				let mut tokens = Vec::new();
+
+				loop {
+					match automata.next() {
+						Some(Err(Error { error: ErrorKind::Unexpected(b'}'), pos: close_pos })) => {
+							if tokens.is_empty() {
+								tokens.push(Err(Error::unexpected(b'}', close_pos)));
+							}
+							break;
+						}
+
+						Some(output) => tokens.push(output),
+
+						// The literal will report the unexpected EOF.
+						None => break,
+					}
+				}
+
+				let checkpoint = automata.cursor.checkpoint();
+
+				self.interpolate(tokens, pos);
+
+				// Skip the expression, which has already been lexed.
+				Transition::rollback(checkpoint, self)
+			}
+
+			(&Self { dollar: Some(_), .. }, Some(c)) if c.is_word_start() => {
+				self.dollar = None;
+				self.name = Some((cursor.offset(), cursor.pos()));
+				Transition::step(self)
+			}
+
+			// A dollar which is not followed by a name or braces is kept as is.
+			(&Self { dollar: Some(_), .. }, Some(_)) => {
+				self.dollar = None;
+				self.value.push(b'$');
+				Transition::resume(self)
+			}
+
 			// Escaped character.
 			(&Self { escaping: Some((offset, pos)), .. }, Some(value)) => {
 				self.escaping = None;
@@ -130,6 +249,20 @@ impl StringLiteral {
 				Transition::step(self)
 			}
 
+			// Begin of interpolation.
+			(_, Some(b'$')) => {
+				self.dollar = Some(cursor.pos());
+				Transition::step(self)
+			}
+
+			// Closing quote of an interpolated literal.
+			(&Self { interpolated: true, .. }, Some(b'\"')) => {
+				self.push_value();
+				self.push(TokenKind::CloseInterpolation, cursor.pos());
+				self.closed = true;
+				Transition::step(self)
+			}
+
 			// Closing quote.
 			(_, Some(b'\"')) => Transition::produce(
 				Root,
@@ -146,6 +279,37 @@ impl StringLiteral {
 			}
 		}
 	}
+
+
+	/// Add the tokens for an interpolated expression to the interpolation.
+	fn interpolate(&mut self, tokens: Vec<Output>, pos: SourcePos) {
+		if !self.interpolated {
+			self.interpolated = true;
+			self.push(TokenKind::OpenInterpolation, self.pos);
+		}
+
+		self.push_value();
+
+		self.push(TokenKind::OpenParens, pos);
+		self.pending.extend(tokens);
+		self.push(TokenKind::CloseParens, pos);
+	}
+
+
+	/// Add the bytes scanned since the last interpolation to the interpolation.
+	fn push_value(&mut self) {
+		if self.value.is_empty() {
+			return;
+		}
+
+		let value = std::mem::take(&mut self.value);
+		self.push(TokenKind::Literal(Literal::String(value.into_boxed_slice())), self.pos);
+	}
+
+
+	fn push(&mut self, kind: TokenKind, pos: SourcePos) {
+		self.pending.push_back(Ok(Token { kind, pos }));
+	}
 }
 
 
@@ -165,6 +329,7 @@ fn validate_escape(sequence: u8) -> Option<u8> {
 		b't' => Some(b'\t'),
 		b'0' => Some(b'\0'),
 		b'\\' => Some(b'\\'),
+		b'$' => Some(b'$'),
 		_ => None,
 	}
 }
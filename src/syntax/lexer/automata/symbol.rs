@@ -39,6 +39,11 @@ impl Symbol {
 			(b'>', _) => skip_produce(operator(Operator::Greater)),
 
 			(b'<', Some(b'=')) => Transition::produce(Root, operator(Operator::LowerEquals)),
+			// Used for block-level literal input redirection.
+			(b'<', Some(b'<')) => Transition::produce(
+				Root,
+				token(TokenKind::CmdOperator(CommandOperator::Input { literal: true }))
+			),
 			(b'<', _) => skip_produce(operator(Operator::Lower)),
 
 			(b'+', Some(b'+')) => Transition::produce(Root, operator(Operator::Concat)),
@@ -59,6 +64,11 @@ impl Symbol {
 			(b'&', Some(b'{')) => Transition::produce(Command, token(TokenKind::AsyncCommand)),
 			(b'&', _) => unexpected(self.first),
 
+			(b'?', Some(b'?')) => Transition::produce(Root, operator(Operator::Coalesce)),
+			(b'?', Some(b'.')) => Transition::produce(Root, operator(Operator::SafeDot)),
+			(b'?', Some(b'[')) => Transition::produce(Root, token(TokenKind::OpenSafeBracket)),
+			(b'?', _) => skip_produce(operator(Operator::Try)),
+
 			// We must have covered all possibilites for the first character. The peeked
 			// character is wildcarded, which will cover everthing including EOF (None).
 			_ => unreachable!("invalid first character in symbol state"),
@@ -149,7 +159,6 @@ impl SymbolChar {
 			b'/' => operator(Operator::Div),
 			b'%' => operator(Operator::Mod),
 			b'.' => operator(Operator::Dot),
-			b'?' => operator(Operator::Try),
 			b':' => token(TokenKind::Colon),
 			b',' => token(TokenKind::Comma),
 			b'(' => token(TokenKind::OpenParens),
@@ -167,6 +176,7 @@ impl SymbolChar {
 			b'@' => double(first),
 			b'$' => double(first),
 			b'&' => double(first),
+			b'?' => double(first),
 
 			// Not a symbol character:
 			_ => SymbolChar::None,
@@ -189,12 +199,11 @@ pub enum CommandSymbolChar {
 impl CommandSymbolChar {
 	pub fn from_first(first: u8) -> Self {
 		let token = Self::Single;
-		let operator = |op| token(TokenKind::CmdOperator(op));
 		let double = |c| Self::Double { first: c };
 
 		match first {
-			// Single character.
-			b'?' => operator(CommandOperator::Try),
+			// Single character. The try operator is handled by the command state, as it
+			// depends on the surrounding characters.
 			b'|' => token(TokenKind::Pipe),
 			b';' => token(TokenKind::Semicolon),
 
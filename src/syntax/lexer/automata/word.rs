@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use super::{
 	Cursor,
 	Keyword,
@@ -11,6 +13,7 @@ use super::{
 	TokenKind,
 	Transition,
 };
+use crate::symbol::Symbol;
 
 
 /// The state for lexing identifiers, keywords and word operators.
@@ -90,6 +93,64 @@ pub fn to_token(word: &[u8], interner: &mut SymbolInterner) -> TokenKind {
 }
 
 
+/// The words which are not lexed as identifiers: keywords, and word literals and operators.
+const WORDS: &[&[u8]] = &[
+	b"let", b"if", b"then", b"else", b"elseif", b"end", b"for", b"in", b"do", b"while",
+	b"function", b"return", b"break", b"self",
+	b"nil", b"true", b"false",
+	b"not", b"and", b"or",
+];
+
+
+/// Soft keywords: words reserved for future use. These are still lexed as identifiers,
+/// but declaring variables with such names produces a warning, so that they can be
+/// promoted to keywords without silently breaking scripts.
+const RESERVED: &[&[u8]] = &[
+	b"match", b"defer", b"continue",
+];
+
+
+/// Whether the given word is reserved for a future keyword.
+pub fn is_reserved(word: &[u8]) -> bool {
+	RESERVED.contains(&word)
+}
+
+
+/// Symbols for the words which are not lexed as identifiers. Such words may still be used
+/// as names where unambiguous, like in dict keys and field accesses.
+#[derive(Debug)]
+pub struct Words(HashMap<Box<[u8]>, Symbol>);
+
+
+impl Words {
+	pub fn new(interner: &mut SymbolInterner) -> Self {
+		Self(
+			WORDS
+				.iter()
+				.map(|&word| (word.into(), interner.get_or_intern(word)))
+				.collect()
+		)
+	}
+
+
+	/// Get the symbol for the word of the given token, if it is lexed from a word.
+	pub fn symbol(&self, token: &TokenKind) -> Option<Symbol> {
+		let word = match token {
+			TokenKind::Keyword(keyword) => keyword.word(),
+			TokenKind::Literal(Literal::Nil) => "nil",
+			TokenKind::Literal(Literal::True) => "true",
+			TokenKind::Literal(Literal::False) => "false",
+			TokenKind::Operator(Operator::Not) => "not",
+			TokenKind::Operator(Operator::And) => "and",
+			TokenKind::Operator(Operator::Or) => "or",
+			_ => return None,
+		};
+
+		self.0.get(word.as_bytes()).copied()
+	}
+}
+
+
 /// Helper trait for checking if a character is a valid word constituent.
 pub trait IsWord {
 	fn is_word_start(&self) -> bool;
@@ -1,7 +1,13 @@
 use super::{Source, SourcePos};
 
 
+/// The UTF-8 byte order mark, which is skipped if present at the start of the source.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+
 /// A cursor for the source code.
+/// CRLF line endings are presented as a single LF, so that the automata need not care
+/// about them.
 #[derive(Debug, Clone)]
 pub struct Cursor<'a> {
 	input: &'a [u8],
@@ -27,7 +33,11 @@ impl<'a> Cursor<'a> {
 
 
 	pub fn peek(&self) -> Option<u8> {
-		self.input.get(self.offset).copied()
+		if self.is_crlf() {
+			Some(b'\n')
+		} else {
+			self.input.get(self.offset).copied()
+		}
 	}
 
 
@@ -41,6 +51,10 @@ impl<'a> Cursor<'a> {
 			return;
 		}
 
+		if self.is_crlf() {
+			self.offset += 1;
+		}
+
 		if self.input[self.offset] == b'\n' {
 			self.pos.line += 1;
 			self.pos.column = 0;
@@ -52,6 +66,12 @@ impl<'a> Cursor<'a> {
 	}
 
 
+	/// Whether the cursor is at a CRLF line ending.
+	fn is_crlf(&self) -> bool {
+		self.input[self.offset ..].starts_with(b"\r\n")
+	}
+
+
 	/// Save a checkpoint in the current position.
 	pub fn checkpoint(&self) -> Checkpoint {
 		Checkpoint {
@@ -71,9 +91,11 @@ impl<'a> Cursor<'a> {
 
 impl<'a> From<&'a Source> for Cursor<'a> {
 	fn from(source: &'a Source) -> Self {
+		let offset = if source.contents.starts_with(BOM) { BOM.len() } else { 0 };
+
 		Self {
 			input: &source.contents,
-			offset: 0,
+			offset,
 			pos: SourcePos { line: 1, column: 0, path: source.path }
 		}
 	}
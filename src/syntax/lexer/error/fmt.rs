@@ -29,6 +29,11 @@ impl std::fmt::Display for ErrorKind {
 			Self::InvalidIdentifier(ident) => {
 				write!(f, "invalid identifier '{}'", String::from_utf8_lossy(ident))?;
 			}
+
+			Self::TryInArgument => {
+				"'?' is the try operator, which must end the command. Use '%' to match a single \
+				character, or '\\?' for a literal question mark".fmt(f)?;
+			}
 		};
 
 		Ok(())
@@ -45,6 +50,29 @@ impl<'a> Display<'a> for Error {
 }
 
 
+impl<'a> Display<'a> for fmt::Json<&Error> {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		let error = self.0;
+
+		let kind = match error.error {
+			ErrorKind::UnexpectedEof => "unexpected_eof",
+			ErrorKind::Unexpected(_) => "unexpected_character",
+			ErrorKind::EmptyByteLiteral => "empty_char_literal",
+			ErrorKind::InvalidEscapeSequence(_) => "invalid_escape_sequence",
+			ErrorKind::InvalidNumber(_) => "invalid_number",
+			ErrorKind::InvalidIdentifier(_) => "invalid_identifier",
+			ErrorKind::TryInArgument => "try_in_argument",
+		};
+
+		let pos = &error.pos;
+
+		fmt::json_error(f, context, Some((pos.path, pos.line, pos.column)), kind, &error.error)
+	}
+}
+
+
 impl std::fmt::Display for Error {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		write!(f, "line {}, column {} - {}.", self.pos.line, self.pos.column, self.error)
@@ -18,6 +18,8 @@ pub enum ErrorKind {
 	InvalidNumber(Box<[u8]>),
 	/// Invalid identifier, only possible in dollar braces (${}).
 	InvalidIdentifier(Box<[u8]>),
+	/// Try operator inside a command argument, such as `file?.txt`.
+	TryInArgument,
 }
 
 
@@ -65,4 +67,8 @@ impl Error {
 			pos,
 		}
 	}
+
+	pub fn try_in_argument(pos: SourcePos) -> Self {
+		Self { error: ErrorKind::TryInArgument, pos }
+	}
 }
@@ -7,6 +7,7 @@ mod token;
 
 use crate::symbol;
 use automata::Automata;
+pub use automata::{is_reserved, Words};
 use super::{Source, SourcePos};
 pub use cursor::{Cursor, Checkpoint};
 pub use error::{Error, ErrorKind};
@@ -42,3 +43,26 @@ impl<'a, 'b> Iterator for Lexer<'a, 'b> {
 		self.0.next()
 	}
 }
+
+
+/// Lex a single command argument, as in a command block. Anything past the argument, such
+/// as whitespace or command operators, is an error.
+pub fn argument(cursor: Cursor, interner: &mut symbol::Interner) -> Result<Box<[ArgPart]>, Error> {
+	let mut automata = Automata::argument(cursor, interner);
+
+	match automata.next() {
+		Some(Ok(Token { kind: TokenKind::Argument(parts), .. })) => {
+			let cursor = automata.cursor();
+
+			match cursor.peek() {
+				None => Ok(parts),
+				Some(value) => Err(Error::unexpected(value, cursor.pos())),
+			}
+		}
+
+		Some(Err(error)) => Err(error),
+
+		// The argument state always produces an argument or an error.
+		_ => unreachable!("argument state produced no argument"),
+	}
+}
@@ -255,6 +255,108 @@ fn test_string_literals() {
 }
 
 
+#[test]
+fn test_string_interpolation() {
+	let input = r#"
+		"a $b ${c + 1}!" "$5 \$d $"
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::OpenInterpolation),
+			token!(TokenKind::Literal(Literal::String(lit1))),
+			token!(TokenKind::OpenParens),
+			token!(TokenKind::Identifier(b)),
+			token!(TokenKind::CloseParens),
+			token!(TokenKind::Literal(Literal::String(lit2))),
+			token!(TokenKind::OpenParens),
+			token!(TokenKind::Identifier(c)),
+			token!(TokenKind::Operator(Operator::Plus)),
+			token!(TokenKind::Literal(Literal::Int(1))),
+			token!(TokenKind::CloseParens),
+			token!(TokenKind::Literal(Literal::String(lit3))),
+			token!(TokenKind::CloseInterpolation),
+			token!(TokenKind::Literal(Literal::String(lit4))),
+		]
+			=> {
+				assert_eq!(lit1.as_ref(), b"a ");
+				assert_symbol!(interner, b, "b");
+				assert_eq!(lit2.as_ref(), b" ");
+				assert_symbol!(interner, c, "c");
+				assert_eq!(lit3.as_ref(), b"!");
+				assert_eq!(lit4.as_ref(), b"$5 $d $");
+			}
+	);
+}
+
+
+#[test]
+fn test_crlf_and_bom() {
+	let input = "\u{FEFF}let var = \"crlf\r\nline\"\r\nvar # comment\r\n";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			Ok(Token { kind: TokenKind::Keyword(Keyword::Let), pos: let_pos }),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Literal(Literal::String(lit))),
+			Ok(Token { kind: TokenKind::Identifier(_), pos: var_pos }),
+		]
+			=> {
+				assert_eq!((let_pos.line, let_pos.column), (1, 0));
+				assert_eq!(lit.as_ref(), b"crlf\nline");
+				assert_eq!((var_pos.line, var_pos.column), (3, 0));
+			}
+	);
+}
+
+
+#[test]
+fn test_doc_comments() {
+	let input = "# comment\n## first\n\t##second\n# comment\nlet\n##last";
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			Ok(Token { kind: TokenKind::DocComment(doc), pos }),
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::DocComment(last)),
+		]
+			=> {
+				assert_eq!((pos.line, pos.column), (2, 0));
+				assert_eq!(interner.resolve(*doc), Some(&b"first\nsecond"[..]));
+				assert_eq!(interner.resolve(*last), Some(&b"last"[..]));
+			}
+	);
+}
+
+
 #[test]
 fn test_number_literals() {
 	let input = r#"
@@ -333,7 +435,7 @@ fn test_command_block() {
 			token!(TokenKind::Argument(args4)),
 			token!(TokenKind::CmdOperator(CommandOperator::Output { append: true })),
 			token!(TokenKind::Argument(args5)),
-			token!(TokenKind::CmdOperator(CommandOperator::Try)),
+			token!(TokenKind::CmdOperator(CommandOperator::Try { attached: true })),
 			token!(TokenKind::Semicolon),
 			token!(TokenKind::Argument(dollar1)),
 			token!(TokenKind::Argument(dollar2)),
@@ -386,6 +488,88 @@ fn test_command_block() {
 }
 
 
+#[test]
+fn test_try_operator() {
+	let input = r#"
+		{
+			ls file ?;
+			ls file?;
+			ls file?.txt;
+			ls file%.txt
+		}
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::CmdOperator(CommandOperator::Try { attached: false })),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::CmdOperator(CommandOperator::Try { attached: true })),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Argument(_)),
+			error!(ErrorKind::TryInArgument),
+			..,
+		]
+	);
+}
+
+
+#[test]
+fn test_coalesce_operator() {
+	let input = r#"
+		let port = std.env("PORT") ?? "8080"
+		let value = result? ?? nil
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Dot)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::OpenParens),
+			token!(TokenKind::Literal(Literal::String(_))),
+			token!(TokenKind::CloseParens),
+			token!(TokenKind::Operator(Operator::Coalesce)),
+			token!(TokenKind::Literal(Literal::String(port))),
+			token!(TokenKind::Keyword(Keyword::Let)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Assign)),
+			token!(TokenKind::Identifier(_)),
+			token!(TokenKind::Operator(Operator::Try)),
+			token!(TokenKind::Operator(Operator::Coalesce)),
+			token!(TokenKind::Literal(Literal::Nil)),
+		]
+			=> assert_eq!(port.as_ref(), b"8080")
+	);
+}
+
+
 #[test]
 fn test_expansions() {
 	let input = r#"
@@ -427,7 +611,7 @@ fn test_expansions() {
 			token!(TokenKind::Semicolon),
 			token!(TokenKind::Argument(args6)),
 			token!(TokenKind::Argument(args7)),
-			token!(TokenKind::CmdOperator(CommandOperator::Try)),
+			token!(TokenKind::CmdOperator(CommandOperator::Try { attached: true })),
 			token!(TokenKind::Semicolon),
 			token!(TokenKind::Argument(args8)),
 			token!(TokenKind::Argument(args9)),
@@ -436,7 +620,10 @@ fn test_expansions() {
 		]
 			=> {
 				assert_eq!(args0.as_ref(), &[unquoted(literal("ls"))]);
-				assert_eq!(args1.as_ref(), &[expansion(ArgExpansion::Home)]);
+				assert_eq!(
+					args1.as_ref(),
+					&[expansion(ArgExpansion::Home(Box::default())), unquoted(literal("/"))]
+				);
 
 				assert_eq!(args2.as_ref(), &[unquoted(literal("ls"))]);
 				assert_eq!(args3.as_ref(), &[expansion(ArgExpansion::Star), unquoted(literal("~/"))]);
@@ -456,7 +643,8 @@ fn test_expansions() {
 				assert_eq!(
 					args7.as_ref(),
 					&[
-						expansion(ArgExpansion::Home),
+						expansion(ArgExpansion::Home(Box::default())),
+						unquoted(literal("/")),
 						expansion(ArgExpansion::Star),
 						expansion(ArgExpansion::Percent),
 						unquoted(literal("file")),
@@ -470,3 +658,305 @@ fn test_expansions() {
 			}
 	);
 }
+
+
+#[test]
+fn test_home_expansions() {
+	let input = r#"
+		{
+			ls ~user/a ~+/ ~-/ ~user ~ ~+ ~-;
+			ls ~user"/a" ~$x a~ ~user\ x;
+		}
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	let unquoted = ArgPart::Unquoted;
+	let home = |user: &str| ArgPart::Expansion(ArgExpansion::Home(user.as_bytes().into()));
+
+	let literal = |lit: &str| ArgUnit::Literal(lit.as_bytes().into());
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Argument(args0)),
+			token!(TokenKind::Argument(args1)),
+			token!(TokenKind::Argument(args2)),
+			token!(TokenKind::Argument(args3)),
+			token!(TokenKind::Argument(args4)),
+			token!(TokenKind::Argument(args5)),
+			token!(TokenKind::Argument(args6)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Argument(args7)),
+			token!(TokenKind::Argument(args8)),
+			token!(TokenKind::Argument(args9)),
+			token!(TokenKind::Argument(args10)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::CloseCommand),
+		]
+			=> {
+				assert_eq!(args0.as_ref(), &[home("user"), unquoted(literal("/a"))]);
+				assert_eq!(args1.as_ref(), &[home("+"), unquoted(literal("/"))]);
+				assert_eq!(args2.as_ref(), &[home("-"), unquoted(literal("/"))]);
+				assert_eq!(args3.as_ref(), &[home("user")]);
+				assert_eq!(args4.as_ref(), &[home("")]);
+				assert_eq!(args5.as_ref(), &[home("+")]);
+				assert_eq!(args6.as_ref(), &[home("-")]);
+
+				// Only unquoted home expansions are recognized.
+				assert_matches!(args7.as_ref(), [ArgPart::Unquoted(_), ArgPart::DoubleQuoted(_)]);
+				assert_matches!(args8.as_ref(), [ArgPart::Unquoted(_), ArgPart::Unquoted(ArgUnit::Dollar { .. })]);
+				assert_eq!(args9.as_ref(), &[unquoted(literal("a~"))]);
+				assert_eq!(args10.as_ref(), &[unquoted(literal("~user x"))]);
+			}
+	);
+}
+
+
+#[test]
+fn test_brace_expansions() {
+	let input = r#"
+		{
+			ls a{1..3} {x,,y}z [a-c]d {a,b [;
+		}
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	let unquoted = ArgPart::Unquoted;
+	let expansion = ArgPart::Expansion;
+
+	let literal = |lit: &str| ArgUnit::Literal(lit.as_bytes().into());
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Argument(args0)),
+			token!(TokenKind::Argument(args1)),
+			token!(TokenKind::Argument(args2)),
+			token!(TokenKind::Argument(args3)),
+			token!(TokenKind::Argument(args4)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::CloseCommand),
+		]
+			=> {
+				assert_eq!(
+					args0.as_ref(),
+					&[
+						unquoted(literal("a")),
+						expansion(ArgExpansion::Range { from: literal("1"), to: literal("3"), step: None })
+					]
+				);
+				assert_eq!(
+					args1.as_ref(),
+					&[
+						expansion(
+							ArgExpansion::Collection(
+								vec![
+									vec![unquoted(literal("x"))].into(),
+									vec![unquoted(literal(""))].into(),
+									vec![unquoted(literal("y"))].into(),
+								].into()
+							)
+						),
+						unquoted(literal("z")),
+					]
+				);
+				assert_eq!(
+					args2.as_ref(),
+					&[expansion(ArgExpansion::CharClass(b"a-c".to_vec().into())), unquoted(literal("d"))]
+				);
+				// Unterminated expansions are literals.
+				assert_eq!(args3.as_ref(), &[unquoted(literal("{a,b"))]);
+				assert_eq!(args4.as_ref(), &[unquoted(literal("["))]);
+			}
+	);
+}
+
+
+#[test]
+fn test_nested_brace_expansions() {
+	let input = r#"
+		{
+			echo {a,{b,c}d} {1..$n} {0..10..2} {x,${y}z,{w}} {1..$n$m}
+		}
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	let unquoted = ArgPart::Unquoted;
+	let expansion = ArgPart::Expansion;
+
+	let literal = |lit: &str| ArgUnit::Literal(lit.as_bytes().into());
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Argument(args0)),
+			token!(TokenKind::Argument(args1)),
+			token!(TokenKind::Argument(args2)),
+			token!(TokenKind::Argument(args3)),
+			token!(TokenKind::Argument(args4)),
+			..
+		]
+			=> {
+				assert_eq!(
+					args0.as_ref(),
+					&[
+						expansion(
+							ArgExpansion::Collection(
+								vec![
+									vec![unquoted(literal("a"))].into(),
+									vec![
+										expansion(
+											ArgExpansion::Collection(
+												vec![
+													vec![unquoted(literal("b"))].into(),
+													vec![unquoted(literal("c"))].into(),
+												].into()
+											)
+										),
+										unquoted(literal("d")),
+									].into(),
+								].into()
+							)
+						)
+					]
+				);
+
+				assert_matches!(
+					args1.as_ref(),
+					[
+						ArgPart::Expansion(
+							ArgExpansion::Range {
+								from: ArgUnit::Literal(from),
+								to: ArgUnit::Dollar { pos: SourcePos { line: 3, column: 23, .. }, .. },
+								step: None,
+							}
+						)
+					] => assert_eq!(from.as_ref(), b"1")
+				);
+
+				assert_eq!(
+					args2.as_ref(),
+					&[
+						expansion(
+							ArgExpansion::Range {
+								from: literal("0"),
+								to: literal("10"),
+								step: Some(literal("2")),
+							}
+						)
+					]
+				);
+
+				assert_matches!(
+					args3.as_ref(),
+					[ ArgPart::Expansion(ArgExpansion::Collection(items)) ]
+						=> assert_matches!(
+							items.as_ref(),
+							[ x, y, w ] => {
+								assert_eq!(x.as_ref(), &[unquoted(literal("x"))]);
+								assert_matches!(
+									y.as_ref(),
+									[ ArgPart::Unquoted(ArgUnit::Dollar { .. }), z ] => assert_eq!(z, &unquoted(literal("z")))
+								);
+								// Braces which are not expansions are literals.
+								assert_eq!(w.as_ref(), &[unquoted(literal("{w}"))]);
+							}
+						)
+				);
+
+				// Range bounds must be a single dollar.
+				assert_matches!(args4.first(), Some(ArgPart::Unquoted(ArgUnit::Literal(_))));
+			}
+	);
+}
+
+
+#[test]
+fn test_multiline_command_block() {
+	let input = r#"
+		{
+			echo a | # comment
+				# comment
+				cat >
+					file;
+			# comment
+		}
+	"#;
+
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+	let source = Source { path, contents: input.as_bytes().into() };
+	let cursor = Cursor::from(&source);
+	let lexer = Lexer::new(cursor, &mut interner);
+
+	let tokens: Vec<Result<Token, Error>> = lexer.collect();
+
+	assert_matches!(
+		&tokens[..],
+		[
+			token!(TokenKind::Command),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Pipe),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::CmdOperator(CommandOperator::Output { append: false })),
+			token!(TokenKind::Argument(_)),
+			token!(TokenKind::Semicolon),
+			token!(TokenKind::CloseCommand),
+		]
+	);
+}
+
+
+#[test]
+fn test_standalone_argument() {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+
+	let mut lex = |input: &str| {
+		let source = Source { path, contents: input.as_bytes().into() };
+		argument(Cursor::from(&source), &mut interner)
+	};
+
+	let literal = |lit: &str| ArgPart::Unquoted(ArgUnit::Literal(lit.as_bytes().into()));
+
+	// The argument may end at the end of the input in any state.
+	assert_matches!(lex("abc").as_deref(), Ok([ lit ]) if *lit == literal("abc"));
+	assert_matches!(lex("a{1..2}").as_deref(), Ok([ _, ArgPart::Expansion(ArgExpansion::Range { .. }) ]));
+	assert_matches!(lex("a{1..2").as_deref(), Ok([ lit ]) if *lit == literal("a{1..2"));
+	assert_matches!(lex("$x").as_deref(), Ok([ ArgPart::Unquoted(ArgUnit::Dollar { .. }) ]));
+	assert_matches!(lex("").as_deref(), Ok([]));
+
+	// Anything past the argument is an error.
+	assert_matches!(lex("a b"), error!(ErrorKind::Unexpected(b' ')));
+	assert_matches!(lex("a}"), error!(ErrorKind::Unexpected(b'}')));
+	assert_matches!(lex("'a"), error!(ErrorKind::UnexpectedEof));
+}
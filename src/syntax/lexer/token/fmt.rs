@@ -20,27 +20,7 @@ use crate::{
 
 impl std::fmt::Display for Keyword {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-		color
-			::Fg(
-				color::Blue,
-				match self {
-					Self::Let => "let",
-					Self::If => "if",
-					Self::Then => "then",
-					Self::Else => "else",
-					Self::ElseIf => "elseif",
-					Self::End => "end",
-					Self::For => "for",
-					Self::In => "in",
-					Self::Do => "do",
-					Self::While => "while",
-					Self::Function => "function",
-					Self::Return => "return",
-					Self::Break => "break",
-					Self::Self_ => "self",
-				}
-			)
-			.fmt(f)
+		color::Fg(color::Blue, self.word()).fmt(f)
 	}
 }
 
@@ -82,7 +62,9 @@ impl std::fmt::Display for Operator {
 			Self::And => color::Fg(color::Blue, "and").fmt(f),
 			Self::Or => color::Fg(color::Blue, "or").fmt(f),
 			Self::Concat => color::Fg(color::Yellow, "++").fmt(f),
+			Self::Coalesce => color::Fg(color::Yellow, "??").fmt(f),
 			Self::Dot => color::Fg(color::Yellow, ".").fmt(f),
+			Self::SafeDot => color::Fg(color::Yellow, "?.").fmt(f),
 			Self::Assign => "=".fmt(f),
 			Self::Try => color::Fg(color::Yellow, "?").fmt(f),
 		}
@@ -111,12 +93,19 @@ impl<'a> Display<'a> for ArgExpansion {
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
 		match self {
-			Self::Home => color::Fg(color::Yellow, "~/").fmt(f),
-			Self::Range(start, end) => {
+			Self::Home(user) => color::Fg(
+				color::Yellow,
+				format!("~{}", String::from_utf8_lossy(user))
+			).fmt(f),
+			Self::Range { from, to, step } => {
 				color::Fg(color::Yellow, "{").fmt(f)?;
-				start.fmt(f)?;
+				from.fmt(f, context)?;
 				color::Fg(color::Yellow, "..").fmt(f)?;
-				end.fmt(f)?;
+				to.fmt(f, context)?;
+				if let Some(step) = step {
+					color::Fg(color::Yellow, "..").fmt(f)?;
+					step.fmt(f, context)?;
+				}
 				color::Fg(color::Yellow, "}").fmt(f)
 			},
 			Self::Collection(items) => {
@@ -125,7 +114,12 @@ impl<'a> Display<'a> for ArgExpansion {
 				fmt::sep_by(
 					items.iter(),
 					f,
-					|item, f| item.fmt(f, context),
+					|item, f| {
+						for part in item.iter() {
+							part.fmt(f, context)?;
+						}
+						Ok(())
+					},
 					color::Fg(color::Yellow, ",")
 				)?;
 
@@ -184,7 +178,7 @@ impl std::fmt::Display for CommandOperator {
 					Self::Output { append: false } => ">",
 					Self::Input { literal: true } => "<<",
 					Self::Input { literal: false } => "<",
-					Self::Try => "?",
+					Self::Try { .. } => "?",
 				}
 			)
 			.fmt(f)
@@ -205,8 +199,10 @@ impl<'a> Display<'a> for TokenKind {
 			Self::Comma => ",".fmt(f),
 			Self::OpenParens => "(".fmt(f),
 			Self::CloseParens => ")".fmt(f),
+			Self::OpenInterpolation | Self::CloseInterpolation => "\"".fmt(f),
 			Self::OpenBracket => "[".fmt(f),
 			Self::OpenDict => "@[".fmt(f),
+			Self::OpenSafeBracket => "?[".fmt(f),
 			Self::CloseBracket => "]".fmt(f),
 			Self::Command => "{".fmt(f),
 			Self::CaptureCommand => "${".fmt(f),
@@ -221,6 +217,10 @@ impl<'a> Display<'a> for TokenKind {
 			Self::CmdOperator(op) => op.fmt(f),
 			Self::Semicolon => ";".fmt(f),
 			Self::Pipe => color::Fg(color::Yellow, "|").fmt(f),
+			Self::DocComment(doc) => {
+				"## ".fmt(f)?;
+				doc.fmt(f, context)
+			}
 		}
 	}
 }
@@ -24,6 +24,29 @@ pub enum Keyword {
 }
 
 
+impl Keyword {
+	/// The word for the keyword, as in the source code.
+	pub fn word(&self) -> &'static str {
+		match self {
+			Self::Let => "let",
+			Self::If => "if",
+			Self::Then => "then",
+			Self::Else => "else",
+			Self::ElseIf => "elseif",
+			Self::End => "end",
+			Self::For => "for",
+			Self::In => "in",
+			Self::Do => "do",
+			Self::While => "while",
+			Self::Function => "function",
+			Self::Return => "return",
+			Self::Break => "break",
+			Self::Self_ => "self",
+		}
+	}
+}
+
+
 /// Literals for non-composite types.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
@@ -58,8 +81,10 @@ pub enum Operator {
 	And, // and
 	Or,  // or
 
-	Concat, // ++
-	Dot,    // .
+	Concat,   // ++
+	Coalesce, // ??
+	Dot,     // .
+	SafeDot, // ?.
 
 	Assign, // =
 
@@ -126,9 +151,15 @@ impl ArgUnit {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ArgExpansion {
-	Home, // ~/
-	Range(i64, i64), // {x..y}
-	Collection(Box<[ArgUnit]>), // {a,b,c}
+	Home(Box<[u8]>), // ~, ~user, ~+, ~-
+	Range { // {x..y}, {x..y..step}
+		/// Bounds and steps are either integer literals or dollars.
+		from: ArgUnit,
+		to: ArgUnit,
+		step: Option<ArgUnit>,
+	},
+	/// Items consist of unquoted units and nested expansions.
+	Collection(Box<[Box<[ArgPart]>]>), // {a,b,c}
 
 	Star, // *
 	Percent, // %
@@ -162,7 +193,8 @@ impl ArgPart {
 pub enum CommandOperator {
 	Output { append: bool }, // >, >>
 	Input { literal: bool }, // <, <<
-	Try,                     // ?
+	/// Whether the operator is attached to the preceding argument, as in `ls file?`.
+	Try { attached: bool },  // ?
 }
 
 
@@ -191,9 +223,15 @@ pub enum TokenKind {
 	OpenParens,  // (
 	CloseParens, // )
 
-	OpenBracket,  // [
-	OpenDict,     // @[
-	CloseBracket, // ]
+	// Quotes of string literals with interpolations. Literal parts are produced as string
+	// literals, and interpolated expressions are enclosed in parenthesis.
+	OpenInterpolation,  // "
+	CloseInterpolation, // "
+
+	OpenBracket,     // [
+	OpenDict,        // @[
+	OpenSafeBracket, // ?[
+	CloseBracket,    // ]
 
 	// Command block tokens
 	Command,        // {
@@ -208,6 +246,9 @@ pub enum TokenKind {
 	// commands, instead of being attributed to a single command.
 	Semicolon, // ;
 	Pipe,      // |
+
+	/// Doc comment lines (##), merged and without the leading hashes.
+	DocComment(Symbol),
 }
 
 
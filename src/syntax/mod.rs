@@ -31,6 +31,7 @@ pub struct Analysis {
 impl Analysis {
 	/// Perform syntax analysis in the given source.
 	pub fn analyze(source: &Source, interner: &mut symbol::Interner) -> Self {
+		let words = lexer::Words::new(interner);
 		let cursor = lexer::Cursor::from(source);
 		let lexer = Lexer::new(cursor, interner);
 
@@ -47,8 +48,19 @@ impl Analysis {
 			}
 		});
 
-		let parser = Parser::new(tokens, |error| {
-			errors.borrow_mut().push(Error::Parser(error))
+		let parser = Parser::new(tokens, words, |error, resumed| {
+			let mut errors = errors.borrow_mut();
+
+			// An unterminated literal has already been reported by the lexer, and the parser
+			// would report the premature end of file once for each enclosing construct.
+			let eof_reported = matches!(
+				errors.last(),
+				Some(Error::Lexer(lexer::Error { error: lexer::ErrorKind::UnexpectedEof, .. }))
+			);
+
+			if !(eof_reported && matches!(error, parser::Error::UnexpectedEof)) {
+				errors.push(Error::Parser { error, resumed })
+			}
 		});
 
 		let statements = parser.parse();
@@ -68,3 +80,17 @@ impl Analysis {
 		self.errors.is_empty()
 	}
 }
+
+
+/// Parse a single command argument, as in a command block.
+pub fn parse_argument(
+	source: &Source,
+	interner: &mut symbol::Interner
+) -> Result<ast::Argument, lexer::Error> {
+	let cursor = lexer::Cursor::from(source);
+	let pos = cursor.pos();
+
+	let parts = lexer::argument(cursor, interner)?;
+
+	Ok(parser::build_arg(parts.into_vec(), pos))
+}
@@ -54,7 +54,7 @@ where
 		self.expect(TokenKind::CloseCommand)
 			.with_sync(sync::Strategy::token(TokenKind::CloseCommand))?;
 
-		Ok(ast::CommandBlock { kind, head, tail })
+		Ok(ast::CommandBlock { kind, head, tail, input: None })
 	}
 
 
@@ -117,7 +117,7 @@ where
 			}
 		}
 
-		let (redirections, abort_on_error) = self.parse_operators()
+		let (redirections, abort_on_error, try_attached) = self.parse_operators()
 			.with_sync(sync::Strategy::basic_command_terminator())?;
 
 
@@ -128,6 +128,7 @@ where
 				arguments: arguments.into(),
 				redirections,
 				abort_on_error,
+				try_attached,
 				pos,
 			}
 		)
@@ -142,7 +143,7 @@ where
 		})?;
 
 		Ok(
-			Self::build_arg(
+			build_arg(
 				arg_parts.into_vec(), // Use vec's owned iterator.
 				pos
 			)
@@ -156,7 +157,7 @@ where
 			if matches!(&parts[..], [ ArgPart::Unquoted(_), ArgPart::EnvAssign, .. ]) => {
 				let mut parts = parts.into_vec(); // Use vec's owned iterator.
 
-				let value = Self::build_arg(
+				let value = build_arg(
 					parts.drain(2..),
 					pos
 				);
@@ -174,18 +175,18 @@ where
 	}
 
 	/// Parse command operators.
-	/// Returns a pair of (redirections, abort_on_error).
-	fn parse_operators(&mut self) -> Result<(Box<[ast::Redirection]>, bool), Error> {
+	/// Returns a triple of (redirections, abort_on_error, try_attached).
+	fn parse_operators(&mut self) -> Result<(Box<[ast::Redirection]>, bool, bool), Error> {
 		let mut redirections = Vec::new();
 
 		loop {
 			match &self.token {
 				Some(Token { kind: token, .. }) if token.is_basic_command_terminator() => break,
 
-				Some(Token { kind: TokenKind::CmdOperator(Operator::Try), .. }) => {
+				&Some(Token { kind: TokenKind::CmdOperator(Operator::Try { attached }), .. }) => {
 					self.step();
 
-					return Ok((redirections.into(), false));
+					return Ok((redirections.into(), false, attached));
 				}
 
 				Some(_) => {
@@ -199,12 +200,14 @@ where
 			}
 		}
 
-		Ok((redirections.into(), true))
+		Ok((redirections.into(), true, false))
 	}
 
 
-	/// Parse a single redirection operation.
+	/// Parse a single redirection operation, with an optional prefixed file descriptor.
 	fn parse_redirection(&mut self) -> sync::Result<ast::Redirection, Error> {
+		let fd = self.parse_file_descriptor();
+
 		match &self.token {
 			// Input redirection.
 			&Some(Token { kind: TokenKind::CmdOperator(Operator::Input { literal }), .. }) => {
@@ -214,15 +217,17 @@ where
 					.with_sync(sync::Strategy::keep())?;
 
 				Ok(
-					ast::Redirection::Input { literal, source }
+					ast::Redirection::Input {
+						literal,
+						fd: fd.unwrap_or_else(io::stdin_fd),
+						source,
+					}
 				)
 			}
 
-			// Expect a output redirection, with an optional prefixed file descriptor.
+			// Expect a output redirection.
 			Some(_) => {
-				let source_fd = self
-					.parse_file_descriptor()
-					.unwrap_or_else(io::stdout_fd);
+				let source_fd = fd.unwrap_or_else(io::stdout_fd);
 
 				let redirection = self.parse_output_redirection(source_fd)?;
 
@@ -248,7 +253,7 @@ where
 						.with_sync(sync::Strategy::keep())?;
 
 					ast::RedirectionTarget::Append(target)
-				} else if let Some(fd) = self.parse_file_descriptor() { // > fd
+				} else if let Some(fd) = self.parse_target_file_descriptor() { // > fd, >&fd
 					ast::RedirectionTarget::Fd(fd)
 				} else { // > file
 					let target = self.parse_argument()
@@ -273,10 +278,24 @@ where
 
 	/// Parse a optional file descriptor from a argument.
 	fn parse_file_descriptor(&mut self) -> Option<FileDescriptor> {
+		self.parse_file_descriptor_with(b"")
+	}
+
+
+	/// Parse a optional redirection target file descriptor from a argument. Like in other
+	/// shells, the descriptor may be prefixed by an ampersand, as in `2>&1`.
+	fn parse_target_file_descriptor(&mut self) -> Option<FileDescriptor> {
+		self.parse_file_descriptor_with(b"&")
+	}
+
+
+	/// Parse a optional file descriptor from a argument, with an optional prefix.
+	fn parse_file_descriptor_with(&mut self, prefix: &[u8]) -> Option<FileDescriptor> {
 		match &self.token {
 			Some(Token { kind: TokenKind::Argument(parts), .. }) => {
 				match parts.as_ref() {
 					[ArgPart::Unquoted(ArgUnit::Literal(ref lit))] => {
+						let lit = lit.strip_prefix(prefix).unwrap_or(lit);
 						let lit = std::str::from_utf8(lit).ok()?;
 						let number: u8 = lit.parse().ok()?;
 
@@ -293,86 +312,98 @@ where
 		}
 	}
 
-	fn build_arg<J>(arg_parts: J, pos: SourcePos) -> ast::Argument
-	where
-		J: IntoIterator<Item = ArgPart>,
-	{
-		let mut parts = Vec::<ast::ArgPart>::new();
-		let mut literal = Vec::<u8>::new();
-
-		let join_owned_literal = |literal: &mut Vec<u8>, lit: Box<[u8]>| {
-			if literal.is_empty() {
-				*literal = lit.into(); // Reuse allocation.
-			} else {
-				literal.extend(lit.iter())
-			}
-		};
+	fn build_arg_unit(unit: ArgUnit) -> ast::ArgUnit {
+		match unit {
+			ArgUnit::Dollar { symbol, pos } => ast::ArgUnit::Dollar { symbol, pos },
+			ArgUnit::Literal(lit) => ast::ArgUnit::Literal(lit),
+		}
+	}
+}
 
-		let push_literal = |literal: &mut Vec<u8>, parts: &mut Vec<ast::ArgPart>| {
-			if !literal.is_empty() {
-				let literal = std::mem::take(literal).into();
-				parts.push(
-					ast::ArgPart::Unit(ast::ArgUnit::Literal(literal))
-				);
-			}
-		};
 
-		let push_part = |literal: &mut Vec<u8>, parts: &mut Vec<ast::ArgPart>, part| {
-			push_literal(literal, parts);
-			parts.push(part);
-		};
+/// Build an argument from the parts produced by the lexer. Adjacent literals are joined,
+/// and quoted literals never expand.
+pub fn build_arg<J>(arg_parts: J, pos: SourcePos) -> ast::Argument
+where
+	J: IntoIterator<Item = ArgPart>,
+{
+	let mut parts = Vec::<ast::ArgPart>::new();
+	let mut literal = Vec::<u8>::new();
+	let mut quoted = false;
+
+	let join_owned_literal = |literal: &mut Vec<u8>, lit: Box<[u8]>| {
+		if literal.is_empty() {
+			*literal = lit.into(); // Reuse allocation.
+		} else {
+			literal.extend(lit.iter())
+		}
+	};
 
-		let push_dollar = |literal: &mut Vec<u8>, parts: &mut Vec<ast::ArgPart>, symbol, pos| {
-			push_part(
-				literal,
-				parts,
-				ast::ArgPart::Unit(ast::ArgUnit::Dollar { symbol, pos })
+	let push_literal = |literal: &mut Vec<u8>, parts: &mut Vec<ast::ArgPart>| {
+		if !literal.is_empty() {
+			let literal = std::mem::take(literal).into();
+			parts.push(
+				ast::ArgPart::Unit(ast::ArgUnit::Literal(literal))
 			);
-		};
+		}
+	};
+
+	let push_part = |literal: &mut Vec<u8>, parts: &mut Vec<ast::ArgPart>, part| {
+		push_literal(literal, parts);
+		parts.push(part);
+	};
+
+	let push_dollar = |literal: &mut Vec<u8>, parts: &mut Vec<ast::ArgPart>, symbol, pos| {
+		push_part(
+			literal,
+			parts,
+			ast::ArgPart::Unit(ast::ArgUnit::Dollar { symbol, pos })
+		);
+	};
+
+	for part in arg_parts {
+		match part {
+			ArgPart::SingleQuoted(lit) => {
+				quoted = true;
+				join_owned_literal(&mut literal, lit)
+			}
 
-		for part in arg_parts {
-			match part {
-				ArgPart::SingleQuoted(lit) => join_owned_literal(&mut literal, lit),
+			ArgPart::DoubleQuoted(units) => {
+				quoted = true;
 
-				ArgPart::DoubleQuoted(units) => for unit in units.into_vec() {
+				for unit in units.into_vec() {
 					match unit {
 						ArgUnit::Dollar { symbol, pos } => push_dollar(&mut literal, &mut parts, symbol, pos),
 						// Literals in double quotes don't expand to patterns.
 						ArgUnit::Literal(lit) => join_owned_literal(&mut literal, lit),
 					}
 				}
+			}
 
-				ArgPart::Unquoted(unit) => {
-					match unit {
-						ArgUnit::Dollar { symbol, pos } => push_dollar(&mut literal, &mut parts, symbol, pos),
-						ArgUnit::Literal(lit) => join_owned_literal(&mut literal, lit),
-					}
+			ArgPart::Unquoted(unit) => {
+				match unit {
+					ArgUnit::Dollar { symbol, pos } => push_dollar(&mut literal, &mut parts, symbol, pos),
+					ArgUnit::Literal(lit) => join_owned_literal(&mut literal, lit),
 				}
-
-				ArgPart::Expansion(expansion) => push_part(
-					&mut literal,
-					&mut parts,
-					ast::ArgPart::Expansion(expansion.into())
-				),
-
-				// Env assign past the first command should be treated as a literal.
-				ArgPart::EnvAssign => literal.extend(b"="),
 			}
-		}
 
-		// Push the trailing literal, if any.
-		push_literal(&mut literal, &mut parts);
+			ArgPart::Expansion(expansion) => push_part(
+				&mut literal,
+				&mut parts,
+				ast::ArgPart::Expansion(expansion.into())
+			),
 
-		ast::Argument {
-			parts: parts.into(),
-			pos
+			// Env assign past the first command should be treated as a literal.
+			ArgPart::EnvAssign => literal.extend(b"="),
 		}
 	}
 
-	fn build_arg_unit(unit: ArgUnit) -> ast::ArgUnit {
-		match unit {
-			ArgUnit::Dollar { symbol, pos } => ast::ArgUnit::Dollar { symbol, pos },
-			ArgUnit::Literal(lit) => ast::ArgUnit::Literal(lit),
-		}
+	// Push the trailing literal, if any.
+	push_literal(&mut literal, &mut parts);
+
+	ast::Argument {
+		parts: parts.into(),
+		quoted,
+		pos
 	}
 }
@@ -28,21 +28,56 @@ impl<'a> Display<'a> for Error {
 	type Context = &'a symbol::Interner;
 
 	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
-		match self {
-			Self::InvalidEnvAssign => "internal error: invalid env-assign".fmt(f),
+		if let Some(pos) = self.pos() {
+			write!(f, "{} - ", fmt::Show(pos, context))?;
+		}
+
+		Message(self).fmt(f, context)
+	}
+}
+
 
-			Self::UnexpectedEof => "unexpected end of file".fmt(f),
+impl<'a> Display<'a> for fmt::Json<&Error> {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		let error = self.0;
 
-			Self::Unexpected { token: Token { kind, pos }, expected } => {
-				write!(f, "{} - unexpected '", fmt::Show(pos, context))?;
+		let kind = match error {
+			Error::InvalidEnvAssign => "invalid_env_assign",
+			Error::UnexpectedEof => "unexpected_eof",
+			Error::Unexpected { .. } => "unexpected_token",
+			Error::EmptyCommandBlock { .. } => "empty_command_block",
+		};
+
+		let pos = error.pos().map(|pos| (pos.path, pos.line, pos.column));
+
+		fmt::json_error(f, context, pos, kind, fmt::Show(Message(error), context))
+	}
+}
+
+
+/// The error message, without the position.
+struct Message<'e>(&'e Error);
+
+
+impl<'a, 'e> Display<'a> for Message<'e> {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self.0 {
+			Error::InvalidEnvAssign => "internal error: invalid env-assign".fmt(f),
+
+			Error::UnexpectedEof => "unexpected end of file".fmt(f),
+
+			Error::Unexpected { token: Token { kind, .. }, expected } => {
+				"unexpected '".fmt(f)?;
 				kind.fmt(f, context)?;
 				"', expected ".fmt(f)?;
 				expected.fmt(f, context)
 			},
 
-			Self::EmptyCommandBlock { pos } => {
-				write!(f, "{} - empty command block", fmt::Show(pos, context))
-			}
+			Error::EmptyCommandBlock { .. } => "empty command block".fmt(f),
 		}
 	}
 }
@@ -49,6 +49,16 @@ impl Error {
 	pub fn empty_command_block(pos: SourcePos) -> Self {
 		Self::EmptyCommandBlock { pos }
 	}
+
+
+	/// The position of the error, if any.
+	pub fn pos(&self) -> Option<SourcePos> {
+		match self {
+			Self::Unexpected { token, .. } => Some(token.pos),
+			Self::EmptyCommandBlock { pos } => Some(*pos),
+			Self::UnexpectedEof | Self::InvalidEnvAssign => None,
+		}
+	}
 }
 
 
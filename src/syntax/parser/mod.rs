@@ -11,29 +11,34 @@ use super::{
 		ArgPart,
 		ArgUnit,
 		Keyword,
+		Literal,
 		Token,
 		TokenKind,
 		Operator,
-		CommandOperator
+		CommandOperator,
+		Words,
 	}
 };
 use sync::{ResultExt, WithSync, Synchronizable};
+pub use command::build_arg;
 pub use error::Error;
 
 
 /// The parser may report multiple errors before finishing. Instead of allocating those in
 /// an vector, we delegate such handling to the caller.
 pub trait ErrorReporter {
-	fn report(&mut self, error: Error);
+	/// Report an error, along with the position where parsing resumed after synchronizing.
+	/// The position is None if synchronization reached the end of file.
+	fn report(&mut self, error: Error, resumed: Option<SourcePos>);
 }
 
 
 impl<F> ErrorReporter for F
 where
-	F: FnMut(Error),
+	F: FnMut(Error, Option<SourcePos>),
 {
-	fn report(&mut self, error: Error) {
-		self(error)
+	fn report(&mut self, error: Error, resumed: Option<SourcePos>) {
+		self(error, resumed)
 	}
 }
 
@@ -48,7 +53,15 @@ where
 	// because we must be able to move from `token`, but Peekable only returns a reference.
 	cursor: Peekable<I>,
 	token: Option<Token>,
+	/// The doc comment preceding the current token, if any.
+	doc: Option<ast::Symbol>,
+	/// Symbols for keywords and other words, which may be used as names.
+	words: Words,
 	error_reporter: E,
+	/// Whether no token has been consumed since the last synchronization.
+	/// Errors produced in such state are most likely a consequence of the previous error,
+	/// and therefore are not reported.
+	recovering: bool,
 }
 
 
@@ -58,10 +71,19 @@ where
 	E: ErrorReporter,
 {
 	/// Create a new parser for the given input.
-	pub fn new(mut cursor: I, error_reporter: E) -> Self {
-		let token = cursor.next();
+	pub fn new(cursor: I, words: Words, error_reporter: E) -> Self {
+		let mut parser = Self {
+			cursor: cursor.peekable(),
+			token: None,
+			doc: None,
+			words,
+			error_reporter,
+			recovering: false,
+		};
+
+		parser.step();
 
-		Self { cursor: cursor.peekable(), token, error_reporter }
+		parser
 	}
 
 
@@ -72,8 +94,17 @@ where
 
 
 	/// Step the cursor, placing the next token on self.token.
+	/// Doc comments are not placed on self.token, but are kept for the following token.
 	fn step(&mut self) {
 		self.token = self.cursor.next();
+		self.doc = None;
+
+		while let Some(Token { kind: TokenKind::DocComment(doc), .. }) = self.token {
+			self.doc = Some(doc);
+			self.token = self.cursor.next();
+		}
+
+		self.recovering = false;
 	}
 
 
@@ -173,7 +204,7 @@ where
 	E: ErrorReporter,
 {
 	fn synchronize(&mut self, error: Error, mut strategy: sync::Strategy) {
-		self.error_reporter.report(error);
+		let secondary = self.recovering;
 
 		while let Some(Token { kind: token, .. }) = &self.token {
 			if strategy.synchronized(token) {
@@ -182,6 +213,13 @@ where
 				self.step();
 			}
 		}
+
+		self.recovering = true;
+
+		if !secondary {
+			let resumed = self.token.as_ref().map(|token| token.pos);
+			self.error_reporter.report(error, resumed);
+		}
 	}
 }
 
@@ -200,8 +238,12 @@ where
 				// If the token is a block terminator, the parse_block won't parse anything.
 				// We must then prevent an infinite loop here.
 				Some(token) if token.kind.is_block_terminator() => {
+					// The token has already been taken, so we must step the cursor to resume
+					// parsing after it.
+					self.step();
+
 					Err(Error::unexpected_msg(token, "statement"))
-						.with_sync(sync::Strategy::skip_one())
+						.with_sync(sync::Strategy::keep())
 						.synchronize(&mut self)
 				}
 
@@ -258,30 +300,51 @@ where
 		match self.token.take() {
 			// Let.
 			Some(Token { kind: TokenKind::Keyword(Keyword::Let), .. }) => {
+				let doc = self.doc.take();
 				self.step();
 
 				let (identifier, pos) = self
 					.parse_identifier()
 					.synchronize(self);
 
+				let annotation = self.parse_annotation()
+					.synchronize(self);
+
 				let init =
 					if matches!(self.token, Some(Token { kind: TokenKind::Operator(Operator::Assign), .. })) {
 						self.step();
 						// Don't synchronize here because this expression is the last part of the statement.
-						self.parse_expression()?
-					} else {
-						ast::Expr::Literal {
-							literal: ast::Literal::default(),
-							pos,
+						let mut init = self.parse_expression()?;
+
+						// Doc comments before the let statement document the function.
+						if let ast::Expr::Literal { literal: ast::Literal::Function { doc: init_doc, name, .. }, .. } = &mut init {
+							if init_doc.is_none() {
+								*init_doc = doc;
+							}
+
+							if name.is_none() {
+								*name = Some(identifier);
+							}
 						}
+
+						Some(init)
+					} else {
+						None
 					};
 
-				Ok(ast::Statement::Let { identifier, init, pos })
+				// Many bindings are parsed as consecutive let statements: the comma is replaced by
+				// a let keyword, so that the next statement parses the following binding.
+				if let Some(Token { kind: TokenKind::Comma, pos }) = self.token {
+					self.token = Some(Token { kind: TokenKind::Keyword(Keyword::Let), pos });
+				}
+
+				Ok(ast::Statement::Let { identifier, annotation, init, pos })
 			}
 
 			// Let function.
 			Some(Token { kind: TokenKind::Keyword(Keyword::Function), pos })
 				if matches!(self.peek(), Some(Token { kind: TokenKind::Identifier(_), .. })) => {
+					let doc = self.doc.take();
 					self.step();
 
 					// This should not fail because we have just peeked an identifier.
@@ -289,12 +352,13 @@ where
 						.parse_identifier()
 						.expect("there should be an identifier");
 
-					let (params, body) = self.parse_function()?;
+					let literal = self.parse_function(doc, Some(identifier))?;
 
 					Ok(
 						ast::Statement::Let {
 							identifier,
-							init: ast::Expr::Literal { literal: ast::Literal::Function { params, body }, pos },
+							annotation: None,
+							init: Some(ast::Expr::Literal { literal, pos }),
 							pos: id_pos,
 						}
 					)
@@ -382,13 +446,22 @@ where
 				};
 
 				if let Some(pos) = pos {
+					let mut left = vec![expr];
+
 					self.step();
 
 					// Don't synchronize here because this expression is the last part of the statement.
-					let right = self.parse_expression()?;
+					let mut right = self.parse_expression()?;
+
+					// Chained assignment.
+					while let Some(Token { kind: TokenKind::Operator(Operator::Assign), .. }) = self.token {
+						self.step();
+						left.push(right);
+						right = self.parse_expression()?;
+					}
 
 					Ok(
-						ast::Statement::Assign { left: expr, right, pos }
+						ast::Statement::Assign { left: left.into(), right, pos }
 					)
 				} else {
 					Ok(ast::Statement::Expr(expr))
@@ -413,7 +486,8 @@ where
 		let parse_factor     = binop!(Self::parse_prefix, Operator::is_factor);
 		let parse_term       = binop!(parse_factor,     Operator::is_term);
 		let parse_concat     = binop!(parse_term,       |&op| op == Operator::Concat);
-		let parse_comparison = binop!(parse_concat,     Operator::is_comparison);
+		let parse_coalesce   = binop!(parse_concat,     |&op| op == Operator::Coalesce);
+		let parse_comparison = binop!(parse_coalesce,   Operator::is_comparison);
 		let parse_equality   = binop!(parse_comparison, Operator::is_equality);
 		let parse_and        = binop!(parse_equality,   |&op| op == Operator::And);
 		let parse_or         = binop!(parse_and,        |&op| op == Operator::Or);
@@ -509,7 +583,7 @@ where
 				},
 
 				// Subscript operator.
-				Some(Token { kind: TokenKind::OpenBracket, pos }) => {
+				Some(Token { kind: kind @ (TokenKind::OpenBracket | TokenKind::OpenSafeBracket), pos }) => {
 					self.step();
 
 					let field = self.parse_expression()
@@ -521,17 +595,18 @@ where
 					expr = ast::Expr::Access {
 						object: expr.into(),
 						field: field.into(),
+						safe: kind == TokenKind::OpenSafeBracket,
 						pos,
 					}
 				},
 
 				// Dot access operator.
-				Some(Token { kind: TokenKind::Operator(Operator::Dot), pos }) => {
+				Some(Token { kind: TokenKind::Operator(op @ (Operator::Dot | Operator::SafeDot)), pos }) => {
 					self.step();
 
 					// Here, the identifier is a literal, and not a variable name. Hence, `var.id`
 					// is equivalent to `var["id"]`, and not from `var[id]`.
-					let (identifier, id_pos) = self.parse_identifier()?;
+					let (identifier, id_pos) = self.parse_name()?;
 
 					let field = ast::Expr::Literal {
 						literal: ast::Literal::Identifier(identifier),
@@ -541,6 +616,7 @@ where
 					expr = ast::Expr::Access {
 						object: expr.into(),
 						field: field.into(),
+						safe: op == Operator::SafeDot,
 						pos,
 					}
 				},
@@ -591,6 +667,23 @@ where
 				Ok(ast::Expr::Literal { literal: literal.into(), pos })
 			}
 
+			// String literal with interpolations.
+			Some(Token { kind: TokenKind::OpenInterpolation, pos }) => {
+				self.step();
+
+				let mut parts = Vec::new();
+
+				// The lexer only produces string literals and parenthesized expressions as parts.
+				while let Some(Token { kind: TokenKind::Literal(_) | TokenKind::OpenParens, .. }) = &self.token {
+					parts.push(self.parse_primary().synchronize(self));
+				}
+
+				self.expect(TokenKind::CloseInterpolation)
+					.with_sync(sync::Strategy::token(TokenKind::CloseInterpolation))?;
+
+				Ok(ast::Expr::Interpolation { parts: parts.into(), pos })
+			}
+
 			// Array literal.
 			Some(Token { kind: TokenKind::OpenBracket, pos }) => {
 				self.step();
@@ -615,7 +708,7 @@ where
 
 				let items = self.comma_sep(
 					|parser| {
-						let key = parser.parse_identifier()
+						let key = parser.parse_name()
 							.with_sync(sync::Strategy::skip_one())
 							.synchronize(parser);
 
@@ -638,11 +731,12 @@ where
 
 			// Function literal.
 			Some(Token { kind: TokenKind::Keyword(Keyword::Function), pos }) => {
+				let doc = self.doc.take();
 				self.step();
 
-				let (params, body) = self.parse_function()?;
+				let literal = self.parse_function(doc, None)?;
 
-				Ok(ast::Expr::Literal { literal: ast::Literal::Function { params, body }, pos })
+				Ok(ast::Expr::Literal { literal, pos })
 			}
 
 			// Command blocks.
@@ -650,9 +744,11 @@ where
 				let pos = token.pos;
 				self.token = Some(token);
 
-				let block = self.parse_command_block()
+				let mut block = self.parse_command_block()
 					.synchronize(self);
 
+				block.input = self.parse_block_input()?;
+
 				Ok(
 					ast::Expr::CommandBlock {
 						block,
@@ -704,6 +800,31 @@ where
 	}
 
 
+	/// Parse an optional block-level input redirection, which may follow a command block.
+	/// The source is parsed as a postfix expression, and therefore compound expressions
+	/// must be enclosed in parenthesis.
+	fn parse_block_input(&mut self) -> sync::Result<Option<ast::BlockInput>, Error> {
+		let literal = match &self.token {
+			Some(Token { kind: TokenKind::Operator(Operator::Lower), .. }) => false,
+			Some(Token { kind: TokenKind::CmdOperator(CommandOperator::Input { literal: true }), .. }) => true,
+			_ => return Ok(None),
+		};
+
+		self.step();
+
+		let source = self.parse_postfix()?;
+
+		Ok(
+			Some(
+				ast::BlockInput {
+					literal,
+					source: source.into(),
+				}
+			)
+		)
+	}
+
+
 	/// Parse a identifier.
 	fn parse_identifier(&mut self) -> sync::Result<(ast::Symbol, SourcePos), Error> {
 		self
@@ -717,12 +838,71 @@ where
 	}
 
 
+	/// Parse a name, as in dict keys and field accesses. Besides identifiers, names may be
+	/// keywords, and word literals and operators, like `end` or `nil`.
+	fn parse_name(&mut self) -> sync::Result<(ast::Symbol, SourcePos), Error> {
+		let word = self.token
+			.as_ref()
+			.and_then(|token| self.words.symbol(&token.kind));
+
+		self
+			.eat(
+				|token| match (token, word) {
+					(Token { kind: TokenKind::Identifier(symbol), pos }, _) => Ok((symbol, pos)),
+					(Token { pos, .. }, Some(symbol)) => Ok((symbol, pos)),
+					(token, None) => Err((Error::unexpected_msg(token.clone(), "identifier"), token)),
+				}
+			)
+			.with_sync(sync::Strategy::keep())
+	}
+
+
+	/// Parse an optional type annotation, as in `: int`.
+	/// Returns None if the current token is not a colon.
+	fn parse_annotation(&mut self) -> sync::Result<Option<ast::Annotation>, Error> {
+		match &self.token {
+			Some(Token { kind: TokenKind::Colon, .. }) => self.step(),
+			_ => return Ok(None),
+		}
+
+		self
+			.eat(
+				|token| match token {
+					Token { kind: TokenKind::Identifier(symbol), pos } => Ok(
+						ast::Annotation { type_name: ast::TypeName::Identifier(symbol), pos }
+					),
+
+					Token { kind: TokenKind::Literal(Literal::Nil), pos } => Ok(
+						ast::Annotation { type_name: ast::TypeName::Nil, pos }
+					),
+
+					Token { kind: TokenKind::Keyword(Keyword::Function), pos } => Ok(
+						ast::Annotation { type_name: ast::TypeName::Function, pos }
+					),
+
+					token => Err((Error::unexpected_msg(token.clone(), "type name"), token)),
+				}
+			)
+			.map(Some)
+			.with_sync(sync::Strategy::keep())
+	}
+
+
+	/// Parse a function parameter, optionally annotated.
+	fn parse_param(&mut self) -> sync::Result<ast::Param, Error> {
+		let identifier = self.parse_identifier()?;
+		let annotation = self.parse_annotation()?;
+
+		Ok((identifier, annotation))
+	}
+
+
 	/// Parse a function literal after the function keyword.
-	/// Returns a pair of parameters and body.
-	#[allow(clippy::type_complexity)]
 	fn parse_function(
-		&mut self
-	) -> sync::Result<(Box<[(ast::Symbol, SourcePos)]>, ast::Block), Error> {
+		&mut self,
+		doc: Option<ast::Symbol>,
+		name: Option<ast::Symbol>,
+	) -> sync::Result<ast::Literal, Error> {
 		let result = self.expect(TokenKind::OpenParens)
 			.with_sync(sync::Strategy::keep());
 
@@ -731,7 +911,7 @@ where
 		result.synchronize(self);
 
 		let params = self.comma_sep(
-			Self::parse_identifier,
+			Self::parse_param,
 			|token| *token == TokenKind::CloseParens,
 		);
 
@@ -745,12 +925,15 @@ where
 			)
 			.synchronize(self);
 
+		let return_annotation = self.parse_annotation()
+			.synchronize(self);
+
 		let body = self.parse_block();
 
 		self.expect(TokenKind::Keyword(Keyword::End))
 			.with_sync(sync::Strategy::keyword(Keyword::End))?;
 
-		Ok((params, body))
+		Ok(ast::Literal::Function { params, return_annotation, body, doc, name })
 	}
 
 
@@ -777,6 +960,8 @@ where
 
 				let (condition, then, otherwise) = self.parse_condblock()?;
 
+				// The nested if must be the only statement, as an expression statement, so that
+				// its value becomes the value of the else block.
 				let stmt = ast::Statement::Expr(ast::Expr::If { condition, then, otherwise, pos, });
 
 				ast::Block::Block(Box::new([stmt]))
@@ -28,6 +28,7 @@ where
 					AnalysisDisplayContext {
 						max_errors: None,
 						interner: &interner,
+						format: fmt::ErrorFormat::Human,
 					}
 				));
 			}
@@ -63,3 +64,96 @@ fn test_negative() -> io::Result<()> {
 		|analysis| !analysis.errors.is_empty(),
 	)
 }
+
+
+#[test]
+fn test_negative_json() -> io::Result<()> {
+	let mut interner = symbol::Interner::new();
+
+	tests::util::test_dir(
+		"src/syntax/tests/data/negative",
+		move |path, file| {
+			let path_symbol = interner.get_or_intern(path.as_os_str().as_bytes());
+			let source = Source::from_reader(path_symbol, file)?;
+			let analysis = Analysis::analyze(&source, &mut interner);
+
+			let output = fmt::Show(
+				&analysis.errors,
+				AnalysisDisplayContext {
+					max_errors: None,
+					interner: &interner,
+					format: fmt::ErrorFormat::Json,
+				}
+			).to_string();
+
+			assert_eq!(output.lines().count(), analysis.errors.0.len());
+
+			for line in output.lines() {
+				let object: serde_json::Value = serde_json::from_str(line)
+					.expect("invalid json error");
+
+				for field in &["file", "line", "column", "kind", "message"] {
+					assert!(object.get(field).is_some(), "missing field {} in {}", field, line);
+				}
+			}
+
+			Ok(())
+		}
+	)
+}
+
+
+#[test]
+fn test_unterminated_literal() {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+
+	let inputs = [
+		r#"let x = "abc"#,
+		r#"let x = "a ${x"#,
+		r#"if true then let x = "a $x"#,
+	];
+
+	// The unexpected end of file is reported once, by the lexer.
+	for input in inputs {
+		let source = Source { path, contents: input.as_bytes().into() };
+		let analysis = Analysis::analyze(&source, &mut interner);
+		assert_eq!(analysis.errors.0.len(), 1, "{}", input);
+	}
+}
+
+
+#[test]
+fn test_synchronization() {
+	let mut interner = symbol::Interner::new();
+	let path = interner.get_or_intern("<test>");
+
+	let analyze = |input: &str, interner: &mut symbol::Interner| {
+		let source = Source { path, contents: input.as_bytes().into() };
+		Analysis::analyze(&source, interner)
+	};
+
+	// The error notes where parsing resumed, and the next statement is still analyzed.
+	let analysis = analyze("std.print(1 2 3)\nlet z = = 1\n", &mut interner);
+	assert_eq!(analysis.errors.0.len(), 2);
+	match &analysis.errors.0[0] {
+		super::Error::Parser { resumed: Some(pos), .. } => {
+			assert_eq!((pos.line, pos.column), (2, 0));
+		}
+		error => panic!("unexpected error: {:?}", error),
+	}
+
+	let output = fmt::Show(
+		&analysis.errors,
+		AnalysisDisplayContext {
+			max_errors: None,
+			interner: &interner,
+			format: fmt::ErrorFormat::Human,
+		}
+	).to_string();
+	assert!(output.contains("parsing resumed at"), "{}", output);
+
+	// The unclosed parenthesis is not reported again after synchronizing.
+	let analysis = analyze("let x = (1 +\nlet y = 3\n", &mut interner);
+	assert_eq!(analysis.errors.0.len(), 1);
+}
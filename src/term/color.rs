@@ -1,21 +1,100 @@
 use std::{
+	cell::Cell,
 	io,
 	fmt::{self, Debug, Display},
+	sync::atomic::{AtomicU8, Ordering},
 };
 
 pub use termion::color::{Blue, Green, Red, Yellow};
 
 
+/// When to color the interpreter's output.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Mode {
+	/// Color if both stdout and stderr are terminals, and NO_COLOR is not set.
+	#[default]
+	Auto,
+	Always,
+	Never,
+}
+
+
+impl Mode {
+	/// Whether colors are enabled for this mode.
+	fn enabled(self) -> bool {
+		match self {
+			Self::Auto => !no_color() && termion::is_tty(&io::stdout()) && termion::is_tty(&io::stderr()),
+			Self::Always => true,
+			Self::Never => false,
+		}
+	}
+
+
+	fn load() -> Self {
+		match MODE.load(Ordering::Relaxed) {
+			1 => Self::Always,
+			2 => Self::Never,
+			_ => Self::Auto,
+		}
+	}
+}
+
+
+/// The global color mode, used to initialize each thread.
+static MODE: AtomicU8 = AtomicU8::new(0);
+
+
 thread_local! {
-	static IS_TTY: bool = termion::is_tty(&io::stdout())
-		&& termion::is_tty(&io::stderr());
+	static IS_TTY: Cell<bool> = Cell::new(Mode::load().enabled());
+}
+
+
+/// Set the color mode for all threads.
+pub fn set_mode(mode: Mode) {
+	let value = match mode {
+		Mode::Auto => 0,
+		Mode::Always => 1,
+		Mode::Never => 2,
+	};
+
+	MODE.store(value, Ordering::Relaxed);
+	IS_TTY.with(|is_tty| is_tty.set(mode.enabled()));
+}
+
+
+/// Whether script output to stdout should be colored, see std.term.color. Unlike the
+/// interpreter's output, this only depends on stdout being a terminal.
+pub fn stdout_enabled() -> bool {
+	match Mode::load() {
+		Mode::Auto => !no_color() && termion::is_tty(&io::stdout()),
+		Mode::Always => true,
+		Mode::Never => false,
+	}
+}
+
+
+/// Whether the NO_COLOR environment variable is set to a non-empty value.
+fn no_color() -> bool {
+	std::env::var_os("NO_COLOR").is_some_and(|var| !var.is_empty())
+}
+
+
+/// Run the given function with colors disabled in the current thread.
+pub fn disabled<F, R>(f: F) -> R
+where
+	F: FnOnce() -> R,
+{
+	let is_tty = IS_TTY.with(|is_tty| is_tty.replace(false));
+	let result = f();
+	IS_TTY.with(|cell| cell.set(is_tty));
+	result
 }
 
 
 macro_rules! tty_fmt {
 	($f: expr, $open: expr, $value: expr, $close: expr) => {
 		IS_TTY.with(
-			|&is_tty| if is_tty {
+			|is_tty| if is_tty.get() {
 				write!($f, "{}", $open)?;
 				$value.fmt($f)?;
 				write!($f, "{}", $close)
@@ -0,0 +1,163 @@
+//! Reading of single key presses from the controlling terminal.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+	cell::RefCell,
+	collections::VecDeque,
+	io::{self, Read},
+	os::unix::io::AsRawFd,
+	time::{Duration, Instant},
+};
+
+use termion::{
+	event::{self, Event, Key},
+	raw::IntoRawMode,
+};
+
+
+/// Maximum interval between checks for interruptions.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+
+thread_local! {
+	/// Bytes read from the terminal but not yet parsed, such as when many keys are pressed
+	/// at once or text is pasted.
+	static PENDING: RefCell<VecDeque<u8>> = const { RefCell::new(VecDeque::new()) };
+}
+
+
+/// Read a key press from the controlling terminal, which is used even if the standard
+/// streams are redirected. The terminal is in raw mode only while reading, and is restored
+/// when returning, even on errors. Returns None if the timeout expires, if the input is
+/// closed, or if `interrupted` returns true, which is checked periodically.
+pub fn read(timeout: Option<Duration>, interrupted: &dyn Fn() -> bool) -> io::Result<Option<Key>> {
+	if let Some(key) = parse_pending() {
+		return Ok(Some(key));
+	}
+
+	let mut tty = termion::get_tty()?;
+	let _raw = tty.try_clone()?.into_raw_mode()?;
+
+	let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+	loop {
+		if interrupted() {
+			return Ok(None);
+		}
+
+		let interval = match deadline {
+			Some(deadline) => {
+				let now = Instant::now();
+				if now >= deadline {
+					return Ok(None);
+				}
+				POLL_INTERVAL.min(deadline - now)
+			}
+
+			None => POLL_INTERVAL,
+		};
+
+		if !poll(&tty, interval)? {
+			continue;
+		}
+
+		let mut buffer = [0; 64];
+		let len = tty.read(&mut buffer)?;
+
+		if len == 0 {
+			return Ok(None);
+		}
+
+		PENDING.with(|pending| pending.borrow_mut().extend(&buffer[.. len]));
+
+		if let Some(key) = parse_pending() {
+			return Ok(Some(key));
+		}
+	}
+}
+
+
+/// The name of a key: the character itself for printable characters, or names such as
+/// `up`, `enter`, `f1`, `ctrl-c` and `alt-x` otherwise.
+pub fn name(key: Key) -> String {
+	let named = match key {
+		Key::Backspace => "backspace",
+		Key::Left => "left",
+		Key::Right => "right",
+		Key::Up => "up",
+		Key::Down => "down",
+		Key::Home => "home",
+		Key::End => "end",
+		Key::PageUp => "page_up",
+		Key::PageDown => "page_down",
+		Key::BackTab => "backtab",
+		Key::Delete => "delete",
+		Key::Insert => "insert",
+		Key::Esc => "esc",
+		Key::Null => "null",
+		Key::Char('\n') => "enter",
+		Key::Char('\t') => "tab",
+
+		Key::F(n) => return format!("f{}", n),
+		Key::Char(c) => return c.to_string(),
+		Key::Alt(c) => return format!("alt-{}", c),
+		Key::Ctrl(c) => return format!("ctrl-{}", c),
+
+		_ => "unknown",
+	};
+
+	named.to_owned()
+}
+
+
+/// Parse the next key from the given bytes, consuming them. Bytes of events which are not
+/// keys, such as mouse events, are skipped. A lone escape byte is the escape key.
+fn parse(bytes: &mut VecDeque<u8>) -> Option<Key> {
+	while let Some(first) = bytes.pop_front() {
+		if first == 0x1b && bytes.is_empty() {
+			return Some(Key::Esc);
+		}
+
+		let mut rest = std::iter::from_fn(|| bytes.pop_front().map(Ok));
+
+		if let Ok(Event::Key(key)) = event::parse_event(first, &mut rest) {
+			return Some(key);
+		}
+	}
+
+	None
+}
+
+
+fn parse_pending() -> Option<Key> {
+	PENDING.with(|pending| parse(&mut pending.borrow_mut()))
+}
+
+
+/// Wait until the file is readable, returning whether it is.
+fn poll<F: AsRawFd>(file: &F, timeout: Duration) -> io::Result<bool> {
+	let mut fd = libc::pollfd {
+		fd: file.as_raw_fd(),
+		events: libc::POLLIN,
+		revents: 0,
+	};
+
+	// Safety: the pollfd is valid for the duration of the call.
+	let result = unsafe { libc::poll(&mut fd, 1, timeout.as_millis() as libc::c_int) };
+
+	match result {
+		-1 => {
+			let error = io::Error::last_os_error();
+			if error.kind() == io::ErrorKind::Interrupted {
+				Ok(false)
+			} else {
+				Err(error)
+			}
+		}
+
+		0 => Ok(false),
+		_ => Ok(true),
+	}
+}
@@ -0,0 +1,23 @@
+use super::*;
+
+
+fn parse_all(bytes: &[u8]) -> Vec<String> {
+	let mut bytes: VecDeque<u8> = bytes.iter().copied().collect();
+	std::iter::from_fn(|| parse(&mut bytes)).map(name).collect()
+}
+
+
+#[test]
+fn test_parse() {
+	assert_eq!(parse_all(b"a"), ["a"]);
+	assert_eq!(parse_all(b"\x1b[A\x1b[B\x1b[C\x1b[D"), ["up", "down", "right", "left"]);
+	assert_eq!(parse_all(b"\r\t\x7f"), ["enter", "tab", "backspace"]);
+	assert_eq!(parse_all(b"\x03\x04"), ["ctrl-c", "ctrl-d"]);
+	assert_eq!(parse_all(b"\x1bx"), ["alt-x"]);
+	assert_eq!(parse_all(b"\x1b"), ["esc"]);
+	assert_eq!(parse_all(b"\x1bOP\x1b[5~"), ["f1", "page_up"]);
+	assert_eq!(parse_all("é".as_bytes()), ["é"]);
+
+	// Many keys read at once, as when pasting.
+	assert_eq!(parse_all(b"hi "), ["h", "i", " "]);
+}
@@ -0,0 +1,193 @@
+//! Interactive selection menus, rendered on the controlling terminal.
+
+#[cfg(test)]
+mod tests;
+
+use std::io::{self, Write};
+
+use termion::{
+	clear,
+	cursor,
+	event::Key,
+	input::TermRead,
+	raw::IntoRawMode,
+	style,
+};
+
+
+/// Lines used by the title and the help line.
+const EXTRA_LINES: usize = 2;
+
+
+/// The result of a menu interaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+	/// Indices of the selected options, in order.
+	Selected(Vec<usize>),
+	Cancelled,
+}
+
+
+/// A menu over a list of options, either selecting a single option or many.
+#[derive(Debug)]
+pub struct Menu<'a> {
+	title: &'a str,
+	options: &'a [String],
+	multi: bool,
+	cursor: usize,
+	/// The first visible option, when the options don't fit the terminal.
+	offset: usize,
+	selected: Vec<bool>,
+}
+
+
+impl<'a> Menu<'a> {
+	/// Create a menu. The options must not be empty.
+	pub fn new(title: &'a str, options: &'a [String], multi: bool) -> Self {
+		debug_assert!(!options.is_empty());
+
+		Self {
+			title,
+			options,
+			multi,
+			cursor: 0,
+			offset: 0,
+			selected: vec![false; options.len()],
+		}
+	}
+
+
+	/// Handle a key press, returning the outcome if the interaction is over.
+	pub fn handle(&mut self, key: Key) -> Option<Outcome> {
+		let last = self.options.len() - 1;
+
+		match key {
+			Key::Up | Key::Char('k') | Key::Ctrl('p') => {
+				self.cursor = if self.cursor == 0 { last } else { self.cursor - 1 };
+			}
+
+			Key::Down | Key::Char('j') | Key::Ctrl('n') => {
+				self.cursor = if self.cursor == last { 0 } else { self.cursor + 1 };
+			}
+
+			Key::Home | Key::Char('g') => self.cursor = 0,
+			Key::End | Key::Char('G') => self.cursor = last,
+
+			// Jump to the numbered option.
+			Key::Char(digit @ '1' ..= '9') => {
+				let ix = digit as usize - '1' as usize;
+				if ix <= last {
+					self.cursor = ix;
+				}
+			}
+
+			Key::Char(' ') if self.multi => {
+				self.selected[self.cursor] = !self.selected[self.cursor];
+			}
+
+			Key::Char('a') if self.multi => {
+				let all = self.selected.iter().all(|&selected| selected);
+				self.selected.iter_mut().for_each(|selected| *selected = !all);
+			}
+
+			Key::Char('\n') => return Some(
+				Outcome::Selected(
+					if self.multi {
+						(0 ..= last).filter(|&ix| self.selected[ix]).collect()
+					} else {
+						vec![self.cursor]
+					}
+				)
+			),
+
+			Key::Esc | Key::Char('q') | Key::Ctrl('c') | Key::Ctrl('d') => return Some(Outcome::Cancelled),
+
+			_ => (),
+		}
+
+		None
+	}
+
+
+	/// Render the menu, showing at most the given amount of lines. Lines are terminated with
+	/// CRLF, as the terminal is in raw mode. Returns the amount of lines written.
+	pub fn render<W: Write>(&mut self, out: &mut W, max_lines: usize) -> io::Result<usize> {
+		let visible = max_lines
+			.saturating_sub(EXTRA_LINES)
+			.clamp(1, self.options.len());
+
+		// Scroll to keep the cursor visible.
+		if self.cursor < self.offset {
+			self.offset = self.cursor;
+		} else if self.cursor >= self.offset + visible {
+			self.offset = self.cursor + 1 - visible;
+		}
+
+		write!(out, "{}{}{}\r\n", style::Bold, self.title, style::Reset)?;
+
+		for ix in self.offset .. self.offset + visible {
+			let pointer = if ix == self.cursor { ">" } else { " " };
+			let number = ix + 1;
+
+			if self.multi {
+				let mark = if self.selected[ix] { "x" } else { " " };
+				write!(out, "{} [{}] {}) {}\r\n", pointer, mark, number, self.options[ix])?;
+			} else {
+				write!(out, "{} {}) {}\r\n", pointer, number, self.options[ix])?;
+			}
+		}
+
+		let help = if self.multi {
+			"up/down: move, space: toggle, a: all, enter: confirm, q: cancel"
+		} else {
+			"up/down: move, enter: select, q: cancel"
+		};
+
+		write!(out, "{}{}{}\r\n", style::Faint, help, style::Reset)?;
+
+		Ok(visible + EXTRA_LINES)
+	}
+}
+
+
+/// Run a menu on the controlling terminal, which is used even if the standard streams
+/// are redirected. The menu is erased once the interaction is over.
+pub fn run(title: &str, options: &[String], multi: bool) -> io::Result<Outcome> {
+	let tty = termion::get_tty()?;
+	let input = tty.try_clone()?;
+	let mut out = tty.into_raw_mode()?;
+
+	let max_lines = termion::terminal_size()
+		.map(|(_, rows)| rows as usize)
+		.unwrap_or(24)
+		.saturating_sub(1);
+
+	let mut menu = Menu::new(title, options, multi);
+
+	write!(out, "{}", cursor::Hide)?;
+	let mut lines = menu.render(&mut out, max_lines)?;
+	out.flush()?;
+
+	let mut keys = input.keys();
+
+	let outcome = loop {
+		let outcome = match keys.next() {
+			Some(key) => menu.handle(key?),
+			None => Some(Outcome::Cancelled),
+		};
+
+		write!(out, "{}\r{}", cursor::Up(lines as u16), clear::AfterCursor)?;
+
+		if let Some(outcome) = outcome {
+			break outcome;
+		}
+
+		lines = menu.render(&mut out, max_lines)?;
+		out.flush()?;
+	};
+
+	write!(out, "{}", cursor::Show)?;
+	out.flush()?;
+
+	Ok(outcome)
+}
@@ -0,0 +1,66 @@
+use termion::event::Key;
+
+use super::*;
+
+
+fn options() -> Vec<String> {
+	vec!["red".into(), "green".into(), "blue".into()]
+}
+
+
+#[test]
+fn test_select() {
+	let options = options();
+	let mut menu = Menu::new("color", &options, false);
+
+	assert_eq!(menu.handle(Key::Down), None);
+	assert_eq!(menu.handle(Key::Down), None);
+	assert_eq!(menu.handle(Key::Down), None); // Wraps around.
+	assert_eq!(menu.handle(Key::Up), None);
+	assert_eq!(menu.handle(Key::Char('\n')), Some(Outcome::Selected(vec![2])));
+
+	let mut menu = Menu::new("color", &options, false);
+	assert_eq!(menu.handle(Key::Char('2')), None);
+	assert_eq!(menu.handle(Key::Char('9')), None); // Out of range.
+	assert_eq!(menu.handle(Key::Char(' ')), None); // Only toggles in multi select.
+	assert_eq!(menu.handle(Key::Char('\n')), Some(Outcome::Selected(vec![1])));
+
+	let mut menu = Menu::new("color", &options, false);
+	assert_eq!(menu.handle(Key::Esc), Some(Outcome::Cancelled));
+}
+
+
+#[test]
+fn test_multi_select() {
+	let options = options();
+	let mut menu = Menu::new("colors", &options, true);
+
+	assert_eq!(menu.handle(Key::Char(' ')), None);
+	assert_eq!(menu.handle(Key::End), None);
+	assert_eq!(menu.handle(Key::Char(' ')), None);
+	assert_eq!(menu.handle(Key::Char('\n')), Some(Outcome::Selected(vec![0, 2])));
+
+	let mut menu = Menu::new("colors", &options, true);
+	assert_eq!(menu.handle(Key::Char('a')), None);
+	assert_eq!(menu.handle(Key::Char('\n')), Some(Outcome::Selected(vec![0, 1, 2])));
+
+	let mut menu = Menu::new("colors", &options, true);
+	assert_eq!(menu.handle(Key::Char('\n')), Some(Outcome::Selected(vec![])));
+}
+
+
+#[test]
+fn test_render_scrolls() {
+	let options = options();
+	let mut menu = Menu::new("color", &options, false);
+
+	menu.handle(Key::End);
+
+	let mut out = Vec::new();
+	let lines = menu.render(&mut out, 3).expect("render failed");
+	let out = String::from_utf8(out).expect("invalid utf-8");
+
+	assert_eq!(lines, 3);
+	assert!(out.contains("> 3) blue"));
+	assert!(!out.contains("red"));
+}
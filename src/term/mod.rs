@@ -1 +1,3 @@
 pub mod color;
+pub mod keys;
+pub mod menu;
@@ -0,0 +1,478 @@
+mod warning;
+#[cfg(test)]
+mod tests;
+
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+	runtime::value::Type,
+	symbol::{self, Symbol},
+	syntax::{ast, SourcePos},
+};
+pub use warning::{Warning, Warnings, WarningsDisplayContext};
+
+
+/// The signature of a function declared with annotations.
+#[derive(Debug)]
+struct Signature {
+	params: Box<[Option<Type>]>,
+	ret: Option<Type>,
+}
+
+
+/// What is known about a variable.
+#[derive(Debug, Default, Clone)]
+struct Binding {
+	/// The annotated type, if any.
+	annotation: Option<Type>,
+	/// The signature, if the variable was bound to a function literal.
+	signature: Option<Rc<Signature>>,
+}
+
+
+/// Best-effort static type checker for annotated programs.
+/// Unannotated variables have unknown types, and unknown types are compatible with
+/// everything. The nil value is accepted for any annotation. The checker never rejects a
+/// program, it only produces warnings.
+#[derive(Debug)]
+pub struct Checker<'a> {
+	interner: &'a symbol::Interner,
+	/// Stack of lexical scopes.
+	scopes: Vec<HashMap<Symbol, Binding>>,
+	/// The return annotation of the enclosing function, if any.
+	return_type: Option<Type>,
+	warnings: Warnings,
+}
+
+
+impl<'a> Checker<'a> {
+	/// Check the given AST, producing warnings for type mismatches.
+	/// Ill-formed parts of the AST are ignored.
+	pub fn check(ast: &ast::Ast, interner: &'a symbol::Interner) -> Warnings {
+		let mut checker = Self {
+			interner,
+			scopes: vec![HashMap::new()],
+			return_type: None,
+			warnings: Warnings::default(),
+		};
+
+		checker.check_block(&ast.statements);
+
+		checker.warnings
+	}
+
+
+	/// Check a block, returning the type of its value, if known.
+	fn check_block(&mut self, block: &ast::Block) -> Option<Type> {
+		self.scopes.push(HashMap::new());
+
+		let mut block_type = Some(Type::Nil);
+
+		if let ast::Block::Block(statements) = block {
+			for statement in statements.iter() {
+				block_type = self.check_statement(statement);
+			}
+		}
+
+		self.scopes.pop();
+
+		block_type
+	}
+
+
+	/// Check a statement, returning the type of its value, if known.
+	fn check_statement(&mut self, statement: &ast::Statement) -> Option<Type> {
+		match statement {
+			ast::Statement::Let { identifier, annotation, init, .. } => {
+				let annotation = annotation.and_then(|annotation| self.resolve(annotation));
+
+				let signature = match init {
+					Some(ast::Expr::Literal { literal: literal @ ast::Literal::Function { .. }, .. }) =>
+						self.signature(literal),
+					_ => None,
+				};
+
+				// Declare before checking the initializer, allowing recursive functions.
+				self.declare(*identifier, Binding { annotation, signature });
+
+				if let Some(init) = init {
+					let init_type = self.check_expr(init);
+					self.expect(annotation, init_type, init.pos());
+				}
+
+				Some(Type::Nil)
+			}
+
+			ast::Statement::Assign { left, right, .. } => {
+				let right_type = self.check_expr(right);
+
+				for left in left.iter().rev() {
+					match left {
+						ast::Expr::Identifier { identifier, pos } => {
+							let annotation = self.lookup(*identifier).and_then(
+								|binding| {
+									// The variable may no longer hold the declared function.
+									binding.signature = None;
+									binding.annotation
+								}
+							);
+
+							self.expect(annotation, right_type, *pos);
+						}
+
+						other => { self.check_expr(other); }
+					}
+				}
+
+				Some(Type::Nil)
+			}
+
+			ast::Statement::Return { expr, pos } => {
+				let expr_type = self.check_expr(expr);
+				self.expect(self.return_type, expr_type, *pos);
+				None
+			}
+
+			ast::Statement::Break { .. } => None,
+
+			ast::Statement::While { condition, block, .. } => {
+				let condition_type = self.check_expr(condition);
+				self.expect(Some(Type::Bool), condition_type, condition.pos());
+				self.check_block(block);
+				Some(Type::Nil)
+			}
+
+			ast::Statement::For { identifier, expr, block, .. } => {
+				let expr_type = self.check_expr(expr);
+				self.expect(Some(Type::Function), expr_type, expr.pos());
+
+				self.scopes.push(HashMap::new());
+				self.declare(*identifier, Binding::default());
+				self.check_block(block);
+				self.scopes.pop();
+
+				Some(Type::Nil)
+			}
+
+			ast::Statement::Expr(expr) => self.check_expr(expr),
+
+			ast::Statement::IllFormed => None,
+		}
+	}
+
+
+	/// Check an expression, returning its type, if known.
+	fn check_expr(&mut self, expr: &ast::Expr) -> Option<Type> {
+		match expr {
+			ast::Expr::Self_ { .. } => None,
+
+			ast::Expr::Identifier { identifier, .. } => self
+				.lookup(*identifier)
+				.and_then(|binding| binding.annotation),
+
+			ast::Expr::Literal { literal, .. } => self.check_literal(literal),
+
+			ast::Expr::UnaryOp { op, operand, .. } => {
+				let operand_type = self.check_expr(operand);
+
+				match op {
+					ast::UnaryOp::Minus => self.expect_number(operand_type, operand.pos()),
+
+					ast::UnaryOp::Not => {
+						self.expect(Some(Type::Bool), operand_type, operand.pos());
+						Some(Type::Bool)
+					}
+
+					ast::UnaryOp::Try => None,
+				}
+			}
+
+			ast::Expr::Interpolation { parts, .. } => {
+				for part in parts.iter() {
+					self.check_expr(part);
+				}
+
+				Some(Type::String)
+			}
+
+			ast::Expr::BinaryOp { left, op, right, .. } => {
+				use ast::BinaryOp::*;
+
+				let left_type = self.check_expr(left);
+				let right_type = self.check_expr(right);
+
+				match op {
+					// Byte arithmetic: byte + int and byte - int yield a byte, and byte - byte
+					// yields an int.
+					Plus | Minus if left_type == Some(Type::Byte) => {
+						match (op, right_type) {
+							(Minus, Some(Type::Byte)) => Some(Type::Int),
+							(_, None) => None,
+							_ => {
+								self.expect(Some(Type::Int), right_type, right.pos());
+								Some(Type::Byte)
+							}
+						}
+					}
+
+					Plus | Minus | Times | Div | Mod => {
+						let left_type = self.expect_number(left_type, left.pos());
+						self.expect(left_type, right_type, right.pos());
+						left_type
+					}
+
+					Greater | GreaterEquals | Lower | LowerEquals => {
+						match (left_type, right_type) {
+							// Bytes compare to ints.
+							(Some(Type::Byte), Some(Type::Int)) | (Some(Type::Int), Some(Type::Byte)) => (),
+							_ => self.expect(left_type, right_type, right.pos()),
+						}
+						Some(Type::Bool)
+					}
+
+					Equals | NotEquals => Some(Type::Bool),
+
+					And | Or => {
+						self.expect(Some(Type::Bool), left_type, left.pos());
+						self.expect(Some(Type::Bool), right_type, right.pos());
+						Some(Type::Bool)
+					}
+
+					Concat => {
+						self.expect(Some(Type::String), left_type, left.pos());
+						self.expect(Some(Type::String), right_type, right.pos());
+						Some(Type::String)
+					}
+
+					// The left operand may be nil only if its type is unknown or nil.
+					Coalesce => match left_type {
+						Some(Type::Nil) => right_type,
+						left_type => left_type,
+					}
+				}
+			}
+
+			ast::Expr::If { condition, then, otherwise, .. } => {
+				let condition_type = self.check_expr(condition);
+				self.expect(Some(Type::Bool), condition_type, condition.pos());
+
+				let then_type = self.check_block(then);
+				let otherwise_type = self.check_block(otherwise);
+
+				if then_type == otherwise_type {
+					then_type
+				} else {
+					None
+				}
+			}
+
+			ast::Expr::Access { object, field, .. } => {
+				self.check_expr(object);
+				self.check_expr(field);
+				None
+			}
+
+			ast::Expr::Call { function, args, .. } => {
+				let signature = match function.as_ref() {
+					ast::Expr::Identifier { identifier, .. } => self
+						.lookup(*identifier)
+						.and_then(|binding| binding.signature.clone()),
+
+					other => {
+						self.check_expr(other);
+						None
+					}
+				};
+
+				let arg_types: Vec<_> = args
+					.iter()
+					.map(|arg| (self.check_expr(arg), arg.pos()))
+					.collect();
+
+				let signature = signature?;
+
+				for (param, (arg_type, pos)) in signature.params.iter().zip(arg_types) {
+					self.expect(*param, arg_type, pos);
+				}
+
+				signature.ret
+			}
+
+			ast::Expr::CommandBlock { block, .. } => {
+				if let Some(input) = &block.input {
+					let input_type = self.check_expr(&input.source);
+					self.expect(Some(Type::String), input_type, input.source.pos());
+				}
+
+				match block.kind {
+					ast::CommandBlockKind::Asynchronous => Some(Type::Dict),
+					_ => None, // Nil, dict or error.
+				}
+			}
+
+			ast::Expr::IllFormed => None,
+		}
+	}
+
+
+	/// Check a literal, returning its type.
+	fn check_literal(&mut self, literal: &ast::Literal) -> Option<Type> {
+		match literal {
+			ast::Literal::Nil => Some(Type::Nil),
+			ast::Literal::Bool(_) => Some(Type::Bool),
+			ast::Literal::Int(_) => Some(Type::Int),
+			ast::Literal::Float(_) => Some(Type::Float),
+			ast::Literal::Byte(_) => Some(Type::Byte),
+			ast::Literal::String(_) => Some(Type::String),
+			ast::Literal::Identifier(_) => Some(Type::String),
+
+			ast::Literal::Array(items) => {
+				for item in items.iter() {
+					self.check_expr(item);
+				}
+
+				Some(Type::Array)
+			}
+
+			ast::Literal::Dict(items) => {
+				for (_, value) in items.iter() {
+					self.check_expr(value);
+				}
+
+				Some(Type::Dict)
+			}
+
+			ast::Literal::Function { params, return_annotation, body, .. } => {
+				let return_type = return_annotation.and_then(|annotation| self.resolve(annotation));
+
+				let mut scope = HashMap::new();
+				for ((symbol, _), annotation) in params.iter() {
+					let annotation = annotation.and_then(|annotation| self.resolve(annotation));
+					scope.insert(*symbol, Binding { annotation, signature: None });
+				}
+
+				self.scopes.push(scope);
+				let enclosing_return_type = std::mem::replace(&mut self.return_type, return_type);
+
+				let body_type = self.check_block(body);
+
+				// The value of the last statement is implicitly returned.
+				if let ast::Block::Block(statements) = body {
+					if let Some(ast::Statement::Expr(expr)) = statements.last() {
+						self.expect(return_type, body_type, expr.pos());
+					}
+				}
+
+				self.return_type = enclosing_return_type;
+				self.scopes.pop();
+
+				Some(Type::Function)
+			}
+		}
+	}
+
+
+	/// Build the signature of a function literal.
+	/// Unannotated functions have no useful signature.
+	fn signature(&mut self, literal: &ast::Literal) -> Option<Rc<Signature>> {
+		match literal {
+			ast::Literal::Function { params, return_annotation, .. } => {
+				let annotated = return_annotation.is_some()
+					|| params.iter().any(|(_, annotation)| annotation.is_some());
+
+				if !annotated {
+					return None;
+				}
+
+				// Unknown type names are reported when checking the literal.
+				let resolve = |annotation: &Option<ast::Annotation>| annotation
+					.and_then(|annotation| self.resolve_type_name(annotation.type_name));
+
+				Some(
+					Rc::new(
+						Signature {
+							params: params.iter().map(|(_, annotation)| resolve(annotation)).collect(),
+							ret: resolve(return_annotation),
+						}
+					)
+				)
+			}
+
+			_ => None,
+		}
+	}
+
+
+	/// Resolve the type of an annotation, reporting unknown type names.
+	fn resolve(&mut self, annotation: ast::Annotation) -> Option<Type> {
+		let resolved = self.resolve_type_name(annotation.type_name);
+
+		if resolved.is_none() {
+			if let ast::TypeName::Identifier(symbol) = annotation.type_name {
+				self.report(Warning::unknown_type(symbol, annotation.pos));
+			}
+		}
+
+		resolved
+	}
+
+
+	fn resolve_type_name(&self, type_name: ast::TypeName) -> Option<Type> {
+		match type_name {
+			ast::TypeName::Nil => Some(Type::Nil),
+			ast::TypeName::Function => Some(Type::Function),
+			ast::TypeName::Identifier(symbol) => self.interner
+				.resolve(symbol)
+				.and_then(Type::parse),
+		}
+	}
+
+
+	/// Check that the found type is compatible with the expected one.
+	/// Unknown types and nil are compatible with everything.
+	fn expect(&mut self, expected: Option<Type>, found: Option<Type>, pos: SourcePos) {
+		match (expected, found) {
+			(Some(expected), Some(found)) if found != expected && found != Type::Nil => {
+				self.report(Warning::type_mismatch(expected.display(), found, pos))
+			}
+
+			_ => (),
+		}
+	}
+
+
+	/// Check that the found type is a number, returning it if known.
+	fn expect_number(&mut self, found: Option<Type>, pos: SourcePos) -> Option<Type> {
+		match found {
+			Some(Type::Int) | Some(Type::Float) => found,
+
+			Some(found) => {
+				self.report(Warning::type_mismatch("int or float", found, pos));
+				None
+			}
+
+			None => None,
+		}
+	}
+
+
+	fn declare(&mut self, symbol: Symbol, binding: Binding) {
+		self.scopes
+			.last_mut()
+			.expect("attempt to declare in empty scope stack")
+			.insert(symbol, binding);
+	}
+
+
+	fn lookup(&mut self, symbol: Symbol) -> Option<&mut Binding> {
+		self.scopes
+			.iter_mut()
+			.rev()
+			.find_map(|scope| scope.get_mut(&symbol))
+	}
+
+
+	fn report(&mut self, warning: Warning) {
+		self.warnings.0.push(warning);
+	}
+}
@@ -0,0 +1,85 @@
+use std::{
+	io,
+	path::Path,
+	os::unix::ffi::OsStrExt,
+};
+
+use crate::{fmt, symbol, syntax::{self, AnalysisDisplayContext}, tests};
+use super::{Checker, Warnings, WarningsDisplayContext};
+
+
+fn test_dir<P, F>(path: P, mut check: F) -> io::Result<()>
+where
+	P: AsRef<Path>,
+	F: FnMut(&Warnings) -> bool,
+{
+	let mut interner = symbol::Interner::new();
+
+	tests::util::test_dir(
+		path,
+		move |path, file| {
+			let path_symbol = interner.get_or_intern(path.as_os_str().as_bytes());
+			let source = syntax::Source::from_reader(path_symbol, file)?;
+			let syntactic_analysis = syntax::Analysis::analyze(&source, &mut interner);
+
+			if !syntactic_analysis.errors.is_empty() {
+				panic!(
+					"{}",
+					fmt::Show(
+						syntactic_analysis,
+						AnalysisDisplayContext {
+							max_errors: None,
+							interner: &interner,
+							format: fmt::ErrorFormat::Human,
+						}
+					)
+				);
+			}
+
+			let warnings = Checker::check(&syntactic_analysis.ast, &interner);
+
+			if !check(&warnings) {
+				panic!(
+					"{}",
+					fmt::Show(
+						warnings,
+						WarningsDisplayContext {
+							max_warnings: None,
+							interner: &interner,
+						}
+					)
+				);
+			}
+
+			Ok(())
+		}
+	)
+}
+
+
+#[test]
+fn test_examples() -> io::Result<()> {
+	// Unannotated programs must never produce warnings.
+	test_dir(
+		"examples/hush",
+		Warnings::is_empty,
+	)
+}
+
+
+#[test]
+fn test_positive() -> io::Result<()> {
+	test_dir(
+		"src/typecheck/tests/data/positive",
+		Warnings::is_empty,
+	)
+}
+
+
+#[test]
+fn test_negative() -> io::Result<()> {
+	test_dir(
+		"src/typecheck/tests/data/negative",
+		|warnings| !warnings.is_empty(),
+	)
+}
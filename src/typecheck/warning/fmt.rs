@@ -0,0 +1,76 @@
+use std::fmt::Display as _;
+
+use super::{Warnings, Warning, WarningKind};
+use crate::{
+	fmt::{self, Display},
+	symbol,
+	term::color
+};
+
+
+/// Context for displaying warnings.
+#[derive(Debug, Copy, Clone)]
+pub struct WarningsDisplayContext<'a> {
+	/// Max number of displayed warnings.
+	pub max_warnings: Option<usize>,
+	/// Symbol interner.
+	pub interner: &'a symbol::Interner,
+}
+
+
+impl<'a> Display<'a> for WarningKind {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		match self {
+			Self::UnknownType(symbol) => {
+				"unknown type '".fmt(f)?;
+				symbol.fmt(f, context)?;
+				"'".fmt(f)
+			}
+
+			Self::TypeMismatch { expected, found } => write!(
+				f,
+				"type mismatch, expected {}, found {}",
+				expected,
+				found.display()
+			),
+		}
+	}
+}
+
+
+impl<'a> Display<'a> for Warning {
+	type Context = &'a symbol::Interner;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		write!(f, "{}: {} - ", color::Fg(color::Yellow, "Warning"), fmt::Show(self.pos, context))?;
+		self.kind.fmt(f, context)
+	}
+}
+
+
+impl<'a> Display<'a> for Warnings {
+	type Context = WarningsDisplayContext<'a>;
+
+	fn fmt(&self, f: &mut std::fmt::Formatter, context: Self::Context) -> std::fmt::Result {
+		for (ix, warning) in self.0.iter().enumerate() {
+			if let Some(max) = context.max_warnings {
+				if max <= ix {
+					writeln!(
+						f,
+						"{} {}",
+						color::Fg(color::Yellow, self.0.len() - max),
+						color::Fg(color::Yellow, "more supressed type warnings"),
+					)?;
+
+					break;
+				}
+			}
+
+			writeln!(f, "{}", fmt::Show(warning, context.interner))?;
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,57 @@
+mod fmt;
+
+use crate::{runtime::value::Type, symbol::Symbol, syntax::SourcePos};
+pub use fmt::WarningsDisplayContext;
+
+
+/// The kind of a type checker warning.
+#[derive(Debug)]
+pub enum WarningKind {
+	/// Annotation with an unknown type name.
+	UnknownType(Symbol),
+	/// Value of an unexpected type.
+	TypeMismatch {
+		expected: &'static str,
+		found: Type,
+	},
+}
+
+
+/// A type checker warning.
+#[derive(Debug)]
+pub struct Warning {
+	pub kind: WarningKind,
+	pub pos: SourcePos,
+}
+
+
+impl Warning {
+	/// Annotation with an unknown type name.
+	pub fn unknown_type(symbol: Symbol, pos: SourcePos) -> Self {
+		Self {
+			kind: WarningKind::UnknownType(symbol),
+			pos
+		}
+	}
+
+
+	/// Value of an unexpected type.
+	pub fn type_mismatch(expected: &'static str, found: Type, pos: SourcePos) -> Self {
+		Self {
+			kind: WarningKind::TypeMismatch { expected, found },
+			pos
+		}
+	}
+}
+
+
+/// A collection of type checker warnings.
+#[derive(Debug, Default)]
+pub struct Warnings(pub Vec<Warning>);
+
+
+impl Warnings {
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+}